@@ -1,7 +1,7 @@
 use rand::Rng;
 use std::{path::Path, sync::Arc};
 
-use rustray::core::{camera, object, render, scene, scene_file, world};
+use rustray::core::{camera, object, render, resolution, scene, scene_file, world};
 use rustray::geometry::{instance::GeometryInstance, primitives::sphere, transform};
 use rustray::materials::{dielectric, instance::MaterialInstance, lambertian, metallic};
 use rustray::math::vec;
@@ -17,7 +17,7 @@ fn main() {
 
     let nx = 800;
     let ar = 16.0 / 9.0;
-    let ny = (nx as f32 / ar) as u32;
+    let resolution = resolution::Resolution::from_aspect(nx, ar).expect("valid resolution");
     let ns = 1000;
     let max_depth = 50;
 
@@ -85,12 +85,12 @@ fn main() {
                     let motion = 0.5 * rng.random::<f32>();
                     geometry_instance
                         .transforms
-                        .push(transform::Transform::Move {
-                            start: vec::Vec3::new(0.0, 0.0, 0.0),
-                            end: vec::Vec3::new(0.0, motion, 0.0),
-                            time_start: 0.0,
-                            time_end: 1.0,
-                        });
+                        .push(transform::Transform::Move(transform::Motion::between(
+                            vec::Vec3::new(0.0, 0.0, 0.0),
+                            vec::Vec3::new(0.0, motion, 0.0),
+                            0.0,
+                            1.0,
+                        )));
                 }
                 geometry_instance
                     .transforms
@@ -172,13 +172,7 @@ fn main() {
     scene.add_object(Box::new(skybox));
     scene.build_bvh(&mut rng);
 
-    let render = render::Render {
-        width: nx,
-        samples: ns,
-        depth: max_depth,
-        camera,
-        scene,
-    };
+    let render = render::Render::new(resolution, ns, max_depth, camera, scene);
 
     match scene_file::save_render(&render, &Path::new("scenes/bouncing_spheres.toml")) {
         Ok(_) => println!("Scene saved to scenes/bouncing_spheres.toml"),
@@ -192,8 +186,8 @@ fn main() {
 
     println!(
         "Rendering a {}x{} image with {} samples per pixel and max depth {}",
-        render.width,
-        render.width as f32 * render.camera.aspect_ratio,
+        render.image_width(),
+        render.image_height(),
         render.samples,
         render.depth
     );
@@ -201,14 +195,14 @@ fn main() {
     let data = if is_concurrent {
         raytrace_concurrent(&render)
     } else {
-        raytrace(&mut rng, &render)
+        raytrace(&render)
     };
 
     match image::save_buffer(
         &Path::new("samples/bouncing_spheres.png"),
         data.as_slice(),
-        nx,
-        ny,
+        render.image_width(),
+        render.image_height(),
         image::ColorType::Rgb8,
     ) {
         Ok(_) => println!("Image saved to output.png"),
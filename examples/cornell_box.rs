@@ -1,6 +1,6 @@
 use std::{f32::consts::PI, path::Path, sync::Arc};
 
-use rustray::core::{camera, object, render, scene, scene_file};
+use rustray::core::{camera, object, render, resolution, scene, scene_file};
 use rustray::geometry::{
     instance::GeometryInstance,
     primitives::{cube, quad},
@@ -30,7 +30,7 @@ fn main() {
 
     let nx = 600;
     let ar = 1.0;
-    let ny = (nx as f32 / ar) as u32;
+    let resolution = resolution::Resolution::from_aspect(nx, ar).expect("valid resolution");
     let ns = 1000;
     let max_depth = 10;
 
@@ -159,13 +159,7 @@ fn main() {
 
     scene.build_bvh(&mut rng);
 
-    let render = render::Render {
-        width: nx,
-        samples: ns,
-        depth: max_depth,
-        camera,
-        scene,
-    };
+    let render = render::Render::new(resolution, ns, max_depth, camera, scene);
 
     match scene_file::save_render(&render, &Path::new("scenes/cornell_box.toml")) {
         Ok(_) => println!("Scene saved to scenes/cornell_box.toml"),
@@ -179,8 +173,8 @@ fn main() {
 
     println!(
         "Rendering a {}x{} image with {} samples per pixel and max depth {}",
-        render.width,
-        render.width as f32 * render.camera.aspect_ratio,
+        render.image_width(),
+        render.image_height(),
         render.samples,
         render.depth
     );
@@ -188,14 +182,14 @@ fn main() {
     let data = if is_concurrent {
         raytrace_concurrent(&render)
     } else {
-        raytrace(&mut rng, &render)
+        raytrace(&render)
     };
 
     match image::save_buffer(
         &Path::new("samples/cornell_box.png"),
         data.as_slice(),
-        nx,
-        ny,
+        render.image_width(),
+        render.image_height(),
         image::ColorType::Rgb8,
     ) {
         Ok(_) => println!("Image saved to samples/cornell_box.png"),
@@ -1,7 +1,7 @@
 use rand::Rng;
 use std::{f32::consts::PI, path::Path, sync::Arc};
 
-use rustray::core::{camera, object, render, scene, scene_file, volume};
+use rustray::core::{camera, object, render, resolution, scene, scene_file, volume};
 use rustray::geometry::{
     instance::GeometryInstance,
     primitives::{cube, quad, sphere},
@@ -33,7 +33,7 @@ fn main() {
 
     let nx = 800;
     let ar = 1.0;
-    let ny = (nx as f32 / ar) as u32;
+    let resolution = resolution::Resolution::from_aspect(nx, ar).expect("valid resolution");
     let ns = 1000;
     let max_depth = 40;
 
@@ -108,12 +108,14 @@ fn main() {
     // Moving sphere
     let moving_sphere_geom = Arc::new(sphere::Sphere::new(&vec::Vec3::new(0.0, 0.0, 0.0), 50.0));
     let mut moving_instance = GeometryInstance::new(moving_sphere_geom.clone());
-    moving_instance.transforms.push(transform::Transform::Move {
-        start: vec::Vec3::new(0.0, 0.0, 0.0),
-        end: vec::Vec3::new(30.0, 0.0, 0.0),
-        time_start: 0.0,
-        time_end: 1.0,
-    });
+    moving_instance
+        .transforms
+        .push(transform::Transform::Move(transform::Motion::between(
+            vec::Vec3::new(0.0, 0.0, 0.0),
+            vec::Vec3::new(30.0, 0.0, 0.0),
+            0.0,
+            1.0,
+        )));
     moving_instance
         .transforms
         .push(transform::Transform::Translate(vec::Vec3::new(
@@ -252,13 +254,7 @@ fn main() {
 
     scene.build_bvh(&mut rng);
 
-    let render = render::Render {
-        width: nx,
-        samples: ns,
-        depth: max_depth,
-        camera,
-        scene,
-    };
+    let render = render::Render::new(resolution, ns, max_depth, camera, scene);
 
     match scene_file::save_render(&render, &Path::new("scenes/next_week_scene.toml")) {
         Ok(_) => println!("Scene saved to scenes/next_week_scene.toml"),
@@ -272,8 +268,8 @@ fn main() {
 
     println!(
         "Rendering a {}x{} image with {} samples per pixel and max depth {}",
-        render.width,
-        render.width as f32 * render.camera.aspect_ratio,
+        render.image_width(),
+        render.image_height(),
         render.samples,
         render.depth
     );
@@ -281,14 +277,14 @@ fn main() {
     let data = if is_concurrent {
         raytrace_concurrent(&render)
     } else {
-        raytrace(&mut rng, &render)
+        raytrace(&render)
     };
 
     match image::save_buffer(
         &Path::new("samples/next_week_scene.png"),
         data.as_slice(),
-        nx,
-        ny,
+        render.image_width(),
+        render.image_height(),
         image::ColorType::Rgb8,
     ) {
         Ok(_) => println!("Image saved to output.png"),
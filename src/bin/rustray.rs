@@ -4,31 +4,145 @@ extern crate rand;
 
 use std::{
     env::{self},
+    io::Write,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
 };
 
-use rustray::core::scene;
+use rand::SeedableRng;
+
+use rustray::core::{
+    aov, bundle, color, disk_film, events, furnace, fuzz, inspect, interrupt, overlay, package,
+    path_export, preprocess, preset, progressive, render, scene_diff, scene_file, shutter,
+    temporal,
+};
+use rustray::math::vec;
 use rustray::{raytrace, raytrace_concurrent};
 
+/// Frames per second `--frame` and `package` assume without `--frame-rate`.
+const DEFAULT_FRAME_RATE: f64 = 24.0;
+
+/// Shutter angle of frames rendered with `--frame`: open for half of each frame.
+const SHUTTER_ANGLE: f64 = 180.0;
+
+/// Set by `--json-progress`, which keeps stdout for JSON events.
+static JSON_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Prints a human-readable status line: to stdout, or to stderr while stdout carries
+/// `--json-progress` events.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if JSON_PROGRESS.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
 fn main() {
     let mut rng = rand::rng();
 
-    let mut args = env::args();
+    let mut args = env::args().peekable();
     let program_name = args.next().unwrap_or_else(|| String::from("rustray"));
+    if args.peek().map(String::as_str) == Some("diff") {
+        args.next();
+        diff(&program_name, args.collect());
+        return;
+    }
+    if args.peek().map(String::as_str) == Some("inspect") {
+        args.next();
+        inspect_scene(&program_name, args.collect());
+        return;
+    }
+    if args.peek().map(String::as_str) == Some("fuzz") {
+        args.next();
+        fuzz(&program_name, args.collect());
+        return;
+    }
+    if args.peek().map(String::as_str) == Some("furnace") {
+        args.next();
+        furnace(&program_name, args.collect());
+        return;
+    }
+    if args.peek().map(String::as_str) == Some("package") {
+        args.next();
+        package(&program_name, args.collect());
+        return;
+    }
+    if args.peek().map(String::as_str) == Some("sequence") {
+        args.next();
+        sequence(&program_name, args.collect());
+        return;
+    }
     let mut scene_path: Option<PathBuf> = None;
     let mut is_concurrent = false;
+    let mut is_progressive = false;
+    let mut resume = false;
+    let mut use_disk_film = false;
+    let mut inspect = false;
     let mut samples_override: Option<u32> = None;
+    let mut dither = false;
+    let mut stats_overlay = false;
+    let mut sky_only = false;
+    let mut stats_sampling: Option<u32> = None;
+    let mut export_depth = false;
+    let mut export_gbuffer = false;
+    let mut export_motion = false;
+    let mut export_light_groups = false;
+    let mut export_paths: Option<u32> = None;
+    let mut preset_override: Option<preset::Preset> = None;
+    let mut scale: Option<f32> = None;
+    let mut snapshot_every: Option<std::time::Duration> = None;
+    let mut frame: Option<u32> = None;
+    let mut frame_rate: f64 = DEFAULT_FRAME_RATE;
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--concurrent" => {
                 is_concurrent = true;
             }
+            "--progressive" => {
+                is_progressive = true;
+            }
+            "--resume" => {
+                resume = true;
+            }
+            "--disk-film" => {
+                use_disk_film = true;
+            }
+            "--inspect" => {
+                inspect = true;
+            }
+            "--dither" => {
+                dither = true;
+            }
+            "--stats" => {
+                stats_overlay = true;
+            }
+            "--depth" => {
+                export_depth = true;
+            }
+            "--gbuffer" => {
+                export_gbuffer = true;
+            }
+            "--sky-only" => {
+                sky_only = true;
+            }
+            "--motion" => {
+                export_motion = true;
+            }
+            "--light-groups" => {
+                export_light_groups = true;
+            }
+            "--json-progress" => {
+                JSON_PROGRESS.store(true, Ordering::Relaxed);
+            }
             "--spp" => {
                 let value = args.next().unwrap_or_default();
                 if value.is_empty() {
                     eprintln!(
-                        "Missing value for --spp. Usage: {} [scene-file] [--concurrent] [--spp <samples>]",
+                        "Missing value for --spp. Usage: {} [scene-file] [--concurrent] [--progressive] [--resume] [--disk-film] [--inspect] [--dither] [--stats] [--depth] [--gbuffer] [--motion] [--light-groups] [--json-progress] [--frame <n>] [--frame-rate <fps>] [--paths <stride>] [--preset <name>] [--scale <fraction>] [--sky-only] [--snapshot-every <minutes>] [--spp <samples>] [--stats-sampling <every>]",
                         program_name
                     );
                     std::process::exit(1);
@@ -41,6 +155,71 @@ fn main() {
                     }
                 }
             }
+            "--preset" => {
+                let value = args.next().unwrap_or_default();
+                match value.parse::<preset::Preset>() {
+                    Ok(preset) => preset_override = Some(preset),
+                    Err(err) => {
+                        eprintln!("Invalid value for --preset: {}", err);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            _ if arg.starts_with("--preset=") => {
+                let value = arg.trim_start_matches("--preset=");
+                match value.parse::<preset::Preset>() {
+                    Ok(preset) => preset_override = Some(preset),
+                    Err(err) => {
+                        eprintln!("Invalid value for --preset: {}", err);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--paths" => {
+                let value = args.next().unwrap_or_default();
+                export_paths = Some(parse_stride("--paths", &value));
+            }
+            _ if arg.starts_with("--paths=") => {
+                export_paths = Some(parse_stride("--paths", arg.trim_start_matches("--paths=")));
+            }
+            "--frame" => {
+                let value = args.next().unwrap_or_default();
+                frame = Some(parse_frame(&value));
+            }
+            _ if arg.starts_with("--frame=") => {
+                frame = Some(parse_frame(arg.trim_start_matches("--frame=")));
+            }
+            "--frame-rate" => {
+                let value = args.next().unwrap_or_default();
+                frame_rate = parse_frame_rate(&value);
+            }
+            _ if arg.starts_with("--frame-rate=") => {
+                frame_rate = parse_frame_rate(arg.trim_start_matches("--frame-rate="));
+            }
+            "--stats-sampling" => {
+                let value = args.next().unwrap_or_default();
+                stats_sampling = Some(parse_stride("--stats-sampling", &value));
+            }
+            _ if arg.starts_with("--stats-sampling=") => {
+                stats_sampling = Some(parse_stride(
+                    "--stats-sampling",
+                    arg.trim_start_matches("--stats-sampling="),
+                ));
+            }
+            "--scale" => {
+                let value = args.next().unwrap_or_default();
+                scale = Some(parse_scale(&value));
+            }
+            _ if arg.starts_with("--scale=") => {
+                scale = Some(parse_scale(arg.trim_start_matches("--scale=")));
+            }
+            "--snapshot-every" => {
+                let value = args.next().unwrap_or_default();
+                snapshot_every = Some(parse_minutes(&value));
+            }
+            _ if arg.starts_with("--snapshot-every=") => {
+                snapshot_every = Some(parse_minutes(arg.trim_start_matches("--snapshot-every=")));
+            }
             _ if arg.starts_with("--spp=") => {
                 let value = arg.trim_start_matches("--spp=");
                 match value.parse::<u32>() {
@@ -53,7 +232,7 @@ fn main() {
             }
             _ if arg.starts_with("--") => {
                 eprintln!(
-                    "Unknown option: {}. Usage: {} [scene-file] [--concurrent] [--spp <samples>]",
+                    "Unknown option: {}. Usage: {} [scene-file] [--concurrent] [--progressive] [--resume] [--disk-film] [--inspect] [--dither] [--stats] [--depth] [--gbuffer] [--motion] [--light-groups] [--json-progress] [--frame <n>] [--frame-rate <fps>] [--paths <stride>] [--preset <name>] [--scale <fraction>] [--sky-only] [--snapshot-every <minutes>] [--spp <samples>] [--stats-sampling <every>]",
                     arg, program_name
                 );
                 std::process::exit(1);
@@ -61,7 +240,7 @@ fn main() {
             _ => {
                 if scene_path.is_some() {
                     eprintln!(
-                        "Unexpected extra argument: {}. Usage: {} [scene-file] [--concurrent] [--spp <samples>]",
+                        "Unexpected extra argument: {}. Usage: {} [scene-file] [--concurrent] [--progressive] [--resume] [--disk-film] [--inspect] [--dither] [--stats] [--depth] [--gbuffer] [--motion] [--light-groups] [--json-progress] [--frame <n>] [--frame-rate <fps>] [--paths <stride>] [--preset <name>] [--scale <fraction>] [--sky-only] [--snapshot-every <minutes>] [--spp <samples>] [--stats-sampling <every>]",
                         arg, program_name
                     );
                     std::process::exit(1);
@@ -75,15 +254,32 @@ fn main() {
 
     if !scene_path.is_file() {
         eprintln!(
-            "Scene file not found: {}. Usage: {} [scene-file] [--concurrent] [--spp <samples>]",
+            "Scene file not found: {}. Usage: {} [scene-file] [--concurrent] [--progressive] [--resume] [--disk-film] [--inspect] [--dither] [--stats] [--depth] [--gbuffer] [--motion] [--light-groups] [--json-progress] [--frame <n>] [--frame-rate <fps>] [--paths <stride>] [--preset <name>] [--scale <fraction>] [--sky-only] [--snapshot-every <minutes>] [--spp <samples>] [--stats-sampling <every>]",
             scene_path.display(),
             program_name
         );
         std::process::exit(1);
     }
 
-    let mut render = match scene::load_from_file(&mut rng, scene_path.as_path()) {
-        Ok(result) => result,
+    if let Some(frame) = frame {
+        // Before the scene is built, so `Move` keyframes are timed against this frame.
+        preprocess::on_scene_file(move |file| {
+            file.shutter = shutter::Shutter::frame(frame as f64, frame_rate, SHUTTER_ANGLE);
+            Ok(())
+        });
+    }
+
+    let mut render = match scene_file::load_render_timed(&mut rng, scene_path.as_path()) {
+        Ok((render, timings)) => {
+            status!(
+                "Load time: {:.2?} (parse {:.2?}, assets {:.2?}, build {:.2?})",
+                timings.total(),
+                timings.parse,
+                timings.assets,
+                timings.build
+            );
+            render
+        }
         Err(err) => {
             eprintln!(
                 "Failed to load scene from {}: {}",
@@ -94,45 +290,887 @@ fn main() {
         }
     };
 
+    if let Some(preset) = preset_override {
+        render.apply_preset(preset);
+    }
     if let Some(samples) = samples_override {
         render.samples = samples;
     }
+    if dither {
+        render.dither = true;
+    }
+    if stats_overlay {
+        render.stats_overlay = true;
+    }
+    if sky_only {
+        render.scene.sky_only = true;
+    }
+    if let Some(every) = stats_sampling {
+        render.scene.set_stats_sampling(every);
+    }
+    if let Some(scale) = scale {
+        render.scale = scale;
+    }
+    if JSON_PROGRESS.load(Ordering::Relaxed) {
+        render.events = Some(events::json_lines());
+    }
+    if resume && !is_progressive {
+        eprintln!("--resume continues a --progressive render; pass both");
+        std::process::exit(1);
+    }
+    if snapshot_every.is_some() && !is_progressive {
+        eprintln!("--snapshot-every saves --progressive passes; pass both");
+        std::process::exit(1);
+    }
+    render.interrupt = Some(interrupt::on_ctrl_c());
 
-    let data = if is_concurrent {
+    let stem = scene_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let filename = match frame {
+        Some(frame) => package::frame_name(stem, frame),
+        None => stem.to_string(),
+    };
+    let filename = filename.as_str();
+    // Outputs all go here; farm machines start from an empty directory.
+    if let Err(err) = std::fs::create_dir_all("samples") {
+        eprintln!("Failed to create samples/: {}", err);
+        std::process::exit(1);
+    }
+
+    render.emit(events::RenderEvent::Started {
+        width: render.image_width(),
+        height: render.image_height(),
+        samples: render.samples,
+        depth: render.depth,
+    });
+    let render_start = std::time::Instant::now();
+    let rays_before = render.scene.rays_traced();
+    let mut samples_per_pixel = render.samples as f32;
+    let data = if use_disk_film {
+        status!(
+            "Rendering a {}x{} image with {} samples per pixel and max depth {} to disk",
+            render.image_width(),
+            render.image_height(),
+            render.samples,
+            render.depth
+        );
+        save_disk_film(&render, filename);
+        None
+    } else if is_progressive {
+        status!(
+            "Progressively rendering a {}x{} image with up to {} samples per pixel and max depth {}",
+            render.image_width(),
+            render.image_height(),
+            render.samples,
+            render.depth
+        );
+        let checkpoint_path = format!("samples/{}.checkpoint", filename);
+        let mut progressive = progressive::ProgressiveRender::new(&render);
+        if resume {
+            match progressive.resume(Path::new(&checkpoint_path)) {
+                Ok(()) => status!(
+                    "Resuming from {} after {} passes",
+                    checkpoint_path,
+                    progressive.passes()
+                ),
+                Err(e) => {
+                    eprintln!("Failed to resume from {}: {}", checkpoint_path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        let mut pausing = inspect;
+        let mut last_snapshot = std::time::Instant::now();
+        while progressive.step() {
+            if snapshot_every.is_some_and(|interval| last_snapshot.elapsed() >= interval) {
+                save_snapshot(&render, &progressive, filename, render_start, rays_before);
+                last_snapshot = std::time::Instant::now();
+            }
+            if !pausing {
+                continue;
+            }
+            let prompt = format!("pass {}", progressive.passes());
+            match inspect_prompt(&render, &prompt, |x, y| progressive.inspect(x, y)) {
+                InspectCommand::Next => {}
+                InspectCommand::Continue => pausing = false,
+                InspectCommand::Quit => break,
+            }
+        }
+        status!("Stopped after {} passes", progressive.passes());
+        if render.interrupted() {
+            match progressive.save_checkpoint(Path::new(&checkpoint_path)) {
+                Ok(_) => status!(
+                    "Checkpoint saved to {}; pass --resume to continue",
+                    checkpoint_path
+                ),
+                Err(e) => eprintln!("Failed to save checkpoint: {}", e),
+            }
+        } else if resume {
+            // The checkpoint has been rendered to completion.
+            let _ = std::fs::remove_file(&checkpoint_path);
+        }
+        samples_per_pixel = progressive.samples_per_pixel();
+        let mut data = render.encode(&progressive.film());
+        render.stamp_stats(
+            &mut data,
+            &overlay::RenderStats {
+                samples: samples_per_pixel,
+                elapsed: render_start.elapsed(),
+                rays: render.scene.rays_traced() - rays_before,
+            },
+        );
+        Some(data)
+    } else if is_concurrent {
         let cpus = num_cpus::get();
-        println!(
+        status!(
             "Rendering a {}x{} image with {} samples per pixel and max depth {} using {} threads",
-            render.width,
-            render.width as f32 / render.camera.aspect_ratio,
+            render.image_width(),
+            render.image_height(),
             render.samples,
             render.depth,
             cpus
         );
-        raytrace_concurrent(&render)
+        Some(raytrace_concurrent(&render))
     } else {
-        println!(
+        status!(
             "Rendering a {}x{} image with {} samples per pixel and max depth {}",
-            render.width,
-            render.width as f32 / render.camera.aspect_ratio,
+            render.image_width(),
+            render.image_height(),
             render.samples,
             render.depth
         );
-        raytrace(&mut rng, &render)
+        Some(raytrace(&render))
+    };
+    let stats = overlay::RenderStats {
+        samples: samples_per_pixel,
+        elapsed: render_start.elapsed(),
+        rays: render.scene.rays_traced() - rays_before,
+    };
+
+    if let Some(data) = data {
+        match color::save_png(
+            Path::new(&format!("samples/{}.png", filename)),
+            data.as_slice(),
+            render.image_width(),
+            render.image_height(),
+            render.color_space,
+        ) {
+            Ok(_) => status!("Image saved to samples/{}.png", filename),
+            Err(e) => eprintln!("Failed to save image: {}", e),
+        }
+    }
+    if render.interrupted() {
+        status!("Interrupted; the saved image is partial");
+        if !is_progressive {
+            status!("No checkpoint was written; render with --progressive to be able to --resume");
+        }
+        render.emit(events::RenderEvent::finished(&stats, true));
+        std::process::exit(130);
+    }
+
+    if export_depth {
+        save_depth(&render, filename);
+    }
+    if export_gbuffer {
+        save_gbuffer(&render, filename);
+    }
+    if export_motion {
+        let path = format!("samples/{}_motion.exr", filename);
+        match aov::render_motion_vectors(&render).save_exr(Path::new(&path)) {
+            Ok(_) => status!("Motion vectors saved to {}", path),
+            Err(e) => eprintln!("Failed to save motion vectors: {}", e),
+        }
+    }
+    if export_light_groups {
+        save_light_groups(&render, filename);
+    }
+    if let Some(stride) = export_paths {
+        save_paths(&render, filename, stride);
+    }
+    render.emit(events::RenderEvent::finished(&stats, false));
+    if inspect && !is_progressive {
+        inspect_prompt(&render, "done", |x, y| {
+            Some(inspect::trace_pixel(&render, x, y))
+        });
+    }
+}
+
+/// What to do after an inspect prompt.
+enum InspectCommand {
+    /// Render the next pass and prompt again.
+    Next,
+    /// Render the remaining passes without prompting.
+    Continue,
+    /// Stop rendering and keep what's done.
+    Quit,
+}
+
+/// Pauses for pixel queries: `<x> <y> [sample]` re-traces a pixel (in image coordinates,
+/// from the top-left) and prints its paths as a tree, until an empty line (`Next`),
+/// `c` (`Continue`), or `q`/end of input (`Quit`).
+fn inspect_prompt<F>(render: &render::Render, prompt: &str, trace: F) -> InspectCommand
+where
+    F: Fn(u32, u32) -> Option<inspect::PixelTrace>,
+{
+    let (width, height) = (render.image_width(), render.image_height());
+    let stdin = std::io::stdin();
+    loop {
+        print!("{}> ", prompt);
+        let _ = std::io::stdout().flush();
+        let mut line = String::new();
+        if stdin.read_line(&mut line).unwrap_or(0) == 0 {
+            return InspectCommand::Quit;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => return InspectCommand::Next,
+            ["c" | "continue"] => return InspectCommand::Continue,
+            ["q" | "quit"] => return InspectCommand::Quit,
+            [x, y, rest @ ..] if rest.len() <= 1 => {
+                let (Ok(x), Ok(y)) = (x.parse::<u32>(), y.parse::<u32>()) else {
+                    println!("Pixel coordinates must be whole numbers");
+                    continue;
+                };
+                let sample = match rest.first().map(|sample| sample.parse::<usize>()) {
+                    Some(Ok(sample)) => Some(sample),
+                    Some(Err(_)) => {
+                        println!("The sample index must be a whole number");
+                        continue;
+                    }
+                    None => None,
+                };
+                if x >= width || y >= height {
+                    println!("({}, {}) is outside the {}x{} image", x, y, width, height);
+                    continue;
+                }
+                // The film counts rows from the bottom.
+                let Some(mut pixel) = trace(x, height - 1 - y) else {
+                    continue;
+                };
+                if let Some(sample) = sample {
+                    pixel.samples.retain(|(index, _)| *index == sample);
+                }
+                print!("{}", pixel);
+            }
+            _ => println!(
+                "Commands: <x> <y> [sample] to trace a pixel, empty line for the next pass, c to continue, q to quit"
+            ),
+        }
+    }
+}
+
+/// Renders in 64-pixel tiles into the disk-backed film `samples/<name>.film`, then
+/// assembles it into `samples/<name>.exr` without holding the image in memory.
+fn save_disk_film(render: &render::Render, filename: &str) {
+    let film_path = format!("samples/{}.film", filename);
+    let film = match disk_film::DiskFilm::create(
+        Path::new(&film_path),
+        render.image_width(),
+        render.image_height(),
+        render.film_precision,
+    ) {
+        Ok(film) => film,
+        Err(e) => {
+            eprintln!("Failed to create disk film {}: {}", film_path, e);
+            return;
+        }
+    };
+    rustray::raytrace_to_disk(render, &film, 64);
+    if let Err(e) = film.flush() {
+        eprintln!("Failed to write disk film {}: {}", film_path, e);
+        return;
+    }
+
+    let exr_path = format!("samples/{}.exr", filename);
+    match film.save_exr(Path::new(&exr_path), render.color_space) {
+        Ok(_) => status!("Image saved to {}", exr_path),
+        Err(e) => eprintln!("Failed to save image: {}", e),
+    }
+}
+
+/// Writes the depth AOV in world units to `samples/<name>_depth.exr`, plus a grayscale
+/// preview mapped through the scene's `depth_range` to `samples/<name>_depth.png`.
+fn save_depth(render: &render::Render, filename: &str) {
+    let depth = aov::render_depth(render);
+
+    let exr_path = format!("samples/{}_depth.exr", filename);
+    match depth.save_exr(Path::new(&exr_path)) {
+        Ok(_) => status!("Depth saved to {}", exr_path),
+        Err(e) => eprintln!("Failed to save depth: {}", e),
+    }
+
+    let png_path = format!("samples/{}_depth.png", filename);
+    match aov::visualize_depth(&depth, render.depth_range).save(&png_path) {
+        Ok(_) => status!("Depth preview saved to {}", png_path),
+        Err(e) => eprintln!("Failed to save depth preview: {}", e),
+    }
+}
+
+/// Writes the first-hit world positions and normals to `samples/<name>_position.exr` and
+/// `samples/<name>_normal.exr`.
+fn save_gbuffer(render: &render::Render, filename: &str) {
+    for (pass, buffer) in [
+        ("position", aov::render_position(render)),
+        ("normal", aov::render_normal(render)),
+    ] {
+        let path = format!("samples/{}_{}.exr", filename, pass);
+        match buffer.save_exr(Path::new(&path)) {
+            Ok(_) => status!("G-buffer {} saved to {}", pass, path),
+            Err(e) => eprintln!("Failed to save G-buffer {}: {}", pass, e),
+        }
+    }
+}
+
+/// Records the paths of every `stride`-th pixel and writes them as polylines to
+/// `samples/<name>_paths.obj` and `samples/<name>_paths.ply`.
+fn save_paths(render: &render::Render, filename: &str, stride: u32) {
+    let paths = path_export::PathSet::record(render, stride);
+    for extension in ["obj", "ply"] {
+        let path = format!("samples/{}_paths.{}", filename, extension);
+        match paths.save(Path::new(&path)) {
+            Ok(_) => status!("{} paths saved to {}", paths.paths.len(), path),
+            Err(e) => eprintln!("Failed to save paths: {}", e),
+        }
+    }
+}
+
+fn parse_stride(flag: &str, value: &str) -> u32 {
+    match value.parse::<u32>() {
+        Ok(stride) if stride > 0 => stride,
+        Ok(_) => {
+            eprintln!("Invalid value for {} (0): must be positive", flag);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("Invalid value for {} ({}): {}", flag, value, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_frame(value: &str) -> u32 {
+    match value.parse::<u32>() {
+        Ok(frame) => frame,
+        Err(err) => {
+            eprintln!("Invalid value for --frame ({}): {}", value, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_frame_rate(value: &str) -> f64 {
+    match value.parse::<f64>() {
+        Ok(frame_rate) if frame_rate > 0.0 && frame_rate.is_finite() => frame_rate,
+        Ok(frame_rate) => {
+            eprintln!(
+                "Invalid value for --frame-rate ({}): must be positive",
+                frame_rate
+            );
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("Invalid value for --frame-rate ({}): {}", value, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_scale(value: &str) -> f32 {
+    match value.parse::<f32>() {
+        Ok(scale) if scale > 0.0 && scale.is_finite() => scale,
+        Ok(scale) => {
+            eprintln!("Invalid value for --scale ({}): must be positive", scale);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("Invalid value for --scale ({}): {}", value, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses `--snapshot-every`: a positive number of minutes, fractions allowed.
+fn parse_minutes(value: &str) -> std::time::Duration {
+    match value.parse::<f64>() {
+        Ok(minutes) if minutes > 0.0 && minutes.is_finite() => {
+            std::time::Duration::from_secs_f64(minutes * 60.0)
+        }
+        Ok(minutes) => {
+            eprintln!(
+                "Invalid value for --snapshot-every ({}): must be positive",
+                minutes
+            );
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("Invalid value for --snapshot-every ({}): {}", value, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Writes the image a progressive render has accumulated so far to
+/// `samples/<name>_pass<passes>.png`, leaving earlier snapshots in place.
+fn save_snapshot(
+    render: &render::Render,
+    progressive: &progressive::ProgressiveRender,
+    filename: &str,
+    render_start: std::time::Instant,
+    rays_before: u64,
+) {
+    let mut data = render.encode(&progressive.film());
+    render.stamp_stats(
+        &mut data,
+        &overlay::RenderStats {
+            samples: progressive.samples_per_pixel(),
+            elapsed: render_start.elapsed(),
+            rays: render.scene.rays_traced() - rays_before,
+        },
+    );
+    let path = format!("samples/{}_pass{:04}.png", filename, progressive.passes());
+    match color::save_png(
+        Path::new(&path),
+        data.as_slice(),
+        render.image_width(),
+        render.image_height(),
+        render.color_space,
+    ) {
+        Ok(_) => status!(
+            "Snapshot after {} passes saved to {}",
+            progressive.passes(),
+            path
+        ),
+        Err(e) => eprintln!("Failed to save snapshot: {}", e),
+    }
+}
+
+/// Renders each light group separately and writes its linear film to
+/// `samples/<name>_light_<group>.exr`.
+fn save_light_groups(render: &render::Render, filename: &str) {
+    for (group, film) in rustray::raytrace_light_groups(render) {
+        let group: String = group
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = format!("samples/{}_light_{}.exr", filename, group);
+        match film
+            .to_rgb32f()
+            .save_with_format(&path, image::ImageFormat::OpenExr)
+        {
+            Ok(_) => status!("Light group saved to {}", path),
+            Err(e) => eprintln!("Failed to save light group: {}", e),
+        }
+    }
+}
+
+/// `rustray diff a.toml b.toml`: prints structural differences between two scenes and
+/// exits with status 1 if there are any, like `diff`.
+fn diff(program_name: &str, paths: Vec<String>) {
+    let [before, after] = paths.as_slice() else {
+        eprintln!("Usage: {} diff <scene-a> <scene-b>", program_name);
+        std::process::exit(2);
+    };
+
+    let read = |path: &String| match scene_file::read_scene_file(Path::new(path)) {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("Failed to load scene from {}: {}", path, err);
+            std::process::exit(2);
+        }
+    };
+    let scene_diff = scene_diff::diff(&read(before), &read(after));
+    if scene_diff.is_empty() {
+        println!("No differences.");
+    } else {
+        print!("{}", scene_diff);
+        std::process::exit(1);
+    }
+}
+
+/// `rustray inspect <scene-file>`: loads a scene and prints what it's made of and how
+/// good its BVH is, so acceleration-structure regressions show up without a render.
+fn inspect_scene(program_name: &str, paths: Vec<String>) {
+    let [path] = paths.as_slice() else {
+        eprintln!("Usage: {} inspect <scene-file>", program_name);
+        std::process::exit(2);
+    };
+
+    let mut rng = rand::rng();
+    let (render, timings) = match scene_file::load_render_timed(&mut rng, Path::new(path)) {
+        Ok(result) => result,
+        Err(err) => {
+            eprintln!("Failed to load scene from {}: {}", path, err);
+            std::process::exit(2);
+        }
+    };
+    let scene = &render.scene;
+    println!(
+        "{} objects ({} unbounded), {} lights",
+        scene.handles().count(),
+        scene.unbounded.len(),
+        scene.lights.len()
+    );
+    println!(
+        "Loaded in {:.3} s (build {:.3} s)",
+        timings.total().as_secs_f64(),
+        timings.build.as_secs_f64()
+    );
+    match scene.bvh_stats() {
+        Some(stats) => {
+            println!("BVH:");
+            for line in stats.to_string().lines() {
+                println!("  {}", line);
+            }
+        }
+        None => println!("BVH: none"),
+    }
+}
+
+/// `rustray fuzz [--iterations N] [--seed S]`: renders randomly generated scenes and
+/// saves any that panic or produce non-finite pixels to `fuzz/failure_<seed>.toml`.
+/// Each scene is generated from its own seed, so `--seed <seed> --iterations 1`
+/// reproduces a failure.
+fn fuzz(program_name: &str, args: Vec<String>) {
+    let usage = || {
+        eprintln!(
+            "Usage: {} fuzz [--iterations <count>] [--seed <seed>]",
+            program_name
+        );
+        std::process::exit(2);
+    };
+    let mut iterations: u64 = 100;
+    let mut seed: u64 = rand::random();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_default();
+        match (arg.as_str(), value.parse::<u64>()) {
+            ("--iterations", Ok(value)) => iterations = value,
+            ("--seed", Ok(value)) => seed = value,
+            _ => usage(),
+        }
+    }
+
+    let mut failures = 0;
+    for scene_seed in seed..seed.saturating_add(iterations) {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(scene_seed);
+        let scene_file = fuzz::generate_scene(&mut rng);
+        let content = match toml::to_string(&scene_file) {
+            Ok(content) => content,
+            Err(err) => {
+                failures += 1;
+                eprintln!("seed {}: scene failed to serialize: {}", scene_seed, err);
+                continue;
+            }
+        };
+
+        // Saved before rendering and removed if the scene passes: release builds abort on
+        // panic, so a crashing scene is only kept if it's already on disk.
+        let path = format!("fuzz/failure_{}.toml", scene_seed);
+        let saved = std::fs::create_dir_all("fuzz").and_then(|_| std::fs::write(&path, content));
+        match fuzz::check_scene(scene_file) {
+            Ok(()) => {
+                if saved.is_ok() {
+                    let _ = std::fs::remove_file(&path);
+                }
+            }
+            Err(failure) => {
+                failures += 1;
+                match saved {
+                    Ok(_) => eprintln!("seed {}: {} (saved {})", scene_seed, failure, path),
+                    Err(err) => {
+                        eprintln!("seed {}: {} (failed to save: {})", scene_seed, failure, err)
+                    }
+                }
+            }
+        }
+    }
+
+    println!(
+        "Fuzzed {} scenes from seed {}: {} failure(s)",
+        iterations, seed, failures
+    );
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// `rustray furnace [scene-file] [--samples N] [--tolerance T]`: measures the albedo of
+/// the built-in materials, or of every material in a scene file, in a white furnace and
+/// exits with status 1 if any reflects more or less than it should.
+fn furnace(program_name: &str, args: Vec<String>) {
+    let usage = || {
+        eprintln!(
+            "Usage: {} furnace [scene-file] [--samples <count>] [--tolerance <albedo>]",
+            program_name
+        );
+        std::process::exit(2);
+    };
+    let mut settings = furnace::FurnaceSettings::default();
+    let mut tolerance: f32 = 0.01;
+    let mut scene_path: Option<String> = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--samples" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(samples) => settings.samples = samples,
+                None => usage(),
+            },
+            "--tolerance" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => tolerance = value,
+                None => usage(),
+            },
+            _ if scene_path.is_none() && !arg.starts_with("--") => scene_path = Some(arg),
+            _ => usage(),
+        }
+    }
+
+    let materials = match &scene_path {
+        None => furnace::reference_materials(),
+        Some(path) => {
+            let scene = match scene_file::read_scene_file(Path::new(path)) {
+                Ok(scene) => scene,
+                Err(err) => {
+                    eprintln!("Failed to load scene from {}: {}", path, err);
+                    std::process::exit(2);
+                }
+            };
+            let mut materials = Vec::new();
+            for entry in &scene.materials {
+                match entry.material.to_scatterable() {
+                    Ok(material) => materials.push((format!("material {}", entry.id), material)),
+                    Err(err) => {
+                        eprintln!("Failed to build material {}: {}", entry.id, err);
+                        std::process::exit(2);
+                    }
+                }
+            }
+            materials
+        }
+    };
+
+    let format = |color: vec::Vec3| format!("{:.4} {:.4} {:.4}", color.x, color.y, color.z);
+    let mut failures = 0;
+    for (name, material) in materials {
+        let report = furnace::test(material, &settings);
+        let verdict = match report.expected {
+            None => String::from("no expected albedo"),
+            Some(expected) if report.passed(tolerance) => {
+                format!("ok (expected {})", format(expected))
+            }
+            Some(expected) => {
+                failures += 1;
+                format!("FAIL (expected {})", format(expected))
+            }
+        };
+        println!(
+            "{}: measured {} ± {:.4}, {}",
+            name,
+            format(report.measured),
+            report.standard_error,
+            verdict
+        );
+    }
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// `rustray package <scene-file> --frames <first>-<last> [--frame-rate <fps>]
+/// [--chunk-size <frames>] [--output <dir>] [-- <render options>]`: bundles the scene
+/// and writes a job manifest splitting the frames into chunks for a render farm.
+fn package(program_name: &str, args: Vec<String>) {
+    let usage = || {
+        eprintln!(
+            "Usage: {} package <scene-file> --frames <first>-<last> [--frame-rate <fps>] [--chunk-size <frames>] [--output <dir>] [-- <render options>]",
+            program_name
+        );
+        std::process::exit(2);
+    };
+    let mut scene_path: Option<PathBuf> = None;
+    let mut frames: Option<std::ops::RangeInclusive<u32>> = None;
+    let mut frame_rate = DEFAULT_FRAME_RATE;
+    let mut chunk_size: u32 = 1;
+    let mut output: Option<PathBuf> = None;
+    let mut render_args = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => match parse_frames(&args.next().unwrap_or_default()) {
+                Some(range) => frames = Some(range),
+                None => usage(),
+            },
+            "--frame-rate" => frame_rate = parse_frame_rate(&args.next().unwrap_or_default()),
+            "--chunk-size" => {
+                chunk_size = parse_stride("--chunk-size", &args.next().unwrap_or_default())
+            }
+            "--output" => match args.next() {
+                Some(dir) => output = Some(PathBuf::from(dir)),
+                None => usage(),
+            },
+            "--" => render_args.extend(args.by_ref()),
+            _ if scene_path.is_none() && !arg.starts_with("--") => {
+                scene_path = Some(PathBuf::from(arg))
+            }
+            _ => usage(),
+        }
+    }
+    let (Some(scene_path), Some(frames)) = (scene_path, frames) else {
+        usage();
+        return;
+    };
+
+    let stem = scene_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("scene");
+    let output = output.unwrap_or_else(|| Path::new("package").join(stem));
+    let bundle_name = format!("{}.{}", stem, bundle::EXTENSION);
+    if let Err(err) = std::fs::create_dir_all(&output) {
+        eprintln!("Failed to create {}: {}", output.display(), err);
+        std::process::exit(1);
+    }
+    let assets = match package::write_bundle(&scene_path, &output.join(&bundle_name)) {
+        Ok(assets) => assets,
+        Err(err) => {
+            eprintln!("Failed to bundle {}: {}", scene_path.display(), err);
+            std::process::exit(1);
+        }
     };
 
-    let filename = scene_path
+    let settings = package::JobSettings::new(frames, frame_rate)
+        .with_chunk_size(chunk_size)
+        .with_render_args(render_args);
+    let manifest = package::Manifest::new(&bundle_name, &settings);
+    let manifest_path = output.join("manifest.json");
+    if let Err(err) = std::fs::write(&manifest_path, manifest.to_json()) {
+        eprintln!("Failed to write {}: {}", manifest_path.display(), err);
+        std::process::exit(1);
+    }
+    println!(
+        "Packaged {} with {} asset(s): {} frames in {} chunk(s), manifest {}",
+        bundle_name,
+        assets.len(),
+        manifest.frames.len(),
+        manifest.chunks.len(),
+        manifest_path.display()
+    );
+}
+
+/// `<first>-<last>` (or a single frame) as an inclusive range.
+fn parse_frames(value: &str) -> Option<std::ops::RangeInclusive<u32>> {
+    let (first, last) = value.split_once('-').unwrap_or((value, value));
+    match (first.parse::<u32>(), last.parse::<u32>()) {
+        (Ok(first), Ok(last)) if first <= last => Some(first..=last),
+        _ => None,
+    }
+}
+
+/// `rustray sequence <scene-file> --frames <first>-<last> [--frame-rate <fps>]
+/// [--spp <samples>] [--temporal]`: renders an animation's frames in order to
+/// `samples/<scene>_<frame>.png`. With `--temporal`, every frame gets its own noise and
+/// is blended with the earlier ones reprojected into it ([`temporal::TemporalReuse`]), so
+/// still parts of the shot converge across frames while moving ones start over.
+fn sequence(program_name: &str, args: Vec<String>) {
+    let usage = || {
+        eprintln!(
+            "Usage: {} sequence <scene-file> --frames <first>-<last> [--frame-rate <fps>] [--spp <samples>] [--temporal]",
+            program_name
+        );
+        std::process::exit(2);
+    };
+    let mut scene_path: Option<PathBuf> = None;
+    let mut frames: Option<std::ops::RangeInclusive<u32>> = None;
+    let mut frame_rate = DEFAULT_FRAME_RATE;
+    let mut samples: Option<u32> = None;
+    let mut temporal_reuse: Option<temporal::TemporalReuse> = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => match parse_frames(&args.next().unwrap_or_default()) {
+                Some(range) => frames = Some(range),
+                None => usage(),
+            },
+            "--frame-rate" => frame_rate = parse_frame_rate(&args.next().unwrap_or_default()),
+            "--spp" => samples = Some(parse_stride("--spp", &args.next().unwrap_or_default())),
+            "--temporal" => temporal_reuse = Some(temporal::TemporalReuse::new()),
+            _ if scene_path.is_none() && !arg.starts_with("--") => {
+                scene_path = Some(PathBuf::from(arg))
+            }
+            _ => usage(),
+        }
+    }
+    let (Some(scene_path), Some(frames)) = (scene_path, frames) else {
+        usage();
+        return;
+    };
+    let stem = scene_path
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("output");
+    if let Err(err) = std::fs::create_dir_all("samples") {
+        eprintln!("Failed to create samples/: {}", err);
+        std::process::exit(1);
+    }
 
-    match image::save_buffer(
-        &Path::new(&format!("samples/{}.png", filename)),
-        data.as_slice(),
-        render.width,
-        (render.width as f32 / render.camera.aspect_ratio) as u32,
-        image::ColorType::Rgb8,
-    ) {
-        Ok(_) => println!("Image saved to samples/{}.png", filename),
-        Err(e) => eprintln!("Failed to save image: {}", e),
+    let mut rng = rand::rng();
+    for frame in frames {
+        // Each frame is built from the file again, so `Move` keyframes are timed
+        // against its shutter.
+        let render = scene_file::read_scene_file(&scene_path).and_then(|mut file| {
+            file.shutter = shutter::Shutter::frame(frame as f64, frame_rate, SHUTTER_ANGLE);
+            if temporal_reuse.is_some() {
+                // Frames sampled alike would repeat their noise, which blending can't
+                // average away.
+                file.frame_noise = shutter::FrameNoise::PerFrame;
+            }
+            file.into_render(&mut rng)
+        });
+        let mut render = match render {
+            Ok(render) => render,
+            Err(err) => {
+                eprintln!(
+                    "Failed to load scene from {}: {}",
+                    scene_path.display(),
+                    err
+                );
+                std::process::exit(1);
+            }
+        };
+        if let Some(samples) = samples {
+            render.samples = samples;
+        }
+
+        let start = std::time::Instant::now();
+        let mut film = rustray::raytrace_film(&render);
+        if let Some(temporal_reuse) = temporal_reuse.as_mut() {
+            film = temporal_reuse.accumulate(&render, film);
+        }
+        let filename = package::frame_name(stem, frame);
+        match color::save_png(
+            Path::new(&format!("samples/{}.png", filename)),
+            &render.encode(&film),
+            render.image_width(),
+            render.image_height(),
+            render.color_space,
+        ) {
+            Ok(_) => status!(
+                "Frame {} rendered in {:.2?} and saved to samples/{}.png",
+                frame,
+                start.elapsed(),
+                filename
+            ),
+            Err(e) => {
+                eprintln!("Failed to save frame {}: {}", frame, e);
+                std::process::exit(1);
+            }
+        }
     }
 }
@@ -2,8 +2,14 @@ use std::env;
 use std::path::{Path, PathBuf};
 use std::time;
 
-use rustray::core::scene;
+use rand::Rng;
+
+use rustray::core::{render, scene_file};
+use rustray::integrators;
+use rustray::math::rng;
+use rustray::samplers::traversal;
 use rustray::stats::charts;
+use rustray::traits::renderable::Renderable;
 use rustray::{raytrace, raytrace_concurrent};
 
 // const SAMPLES: &[u32] = &[10, 50, 100, 200, 500, 1000, 2000, 5000, 10000];
@@ -17,26 +23,74 @@ fn format_duration(dur: time::Duration) -> String {
     format!("{}.{:03} seconds", secs, millis)
 }
 
+/// Number of primary-ray passes over the image used by the BVH traversal benchmark.
+const BVH_BENCH_PASSES: u32 = 4;
+
+/// Times closest-hit queries for jittered camera rays to isolate BVH traversal cost.
+fn bench_bvh(render: &render::Render) {
+    let mut rng = rng::hashed_rng(render.seed);
+    let width = render.image_width();
+    let height = render.image_height();
+    let rays: Vec<_> = (0..BVH_BENCH_PASSES)
+        .flat_map(|_| (0..height).flat_map(move |y| (0..width).map(move |x| (x, y))))
+        .map(|(x, y)| {
+            let u = (x as f32 + rng.random::<f32>()) / width as f32;
+            let v = (y as f32 + rng.random::<f32>()) / height as f32;
+            render.camera.get_ray(&mut rng, u, v)
+        })
+        .collect();
+
+    let start = time::Instant::now();
+    let hits = rays
+        .iter()
+        .filter(|ray| render.scene.hit(ray, 0.001, f32::MAX).is_some())
+        .count();
+    let elapsed = start.elapsed();
+
+    println!("\n=== BVH Traversal Benchmark ===");
+    if let Some(stats) = render.scene.bvh_stats() {
+        println!("{}", stats);
+    }
+    println!(
+        "{} primary rays ({} hits) in {} ({:.2} Mrays/s)",
+        rays.len(),
+        hits,
+        format_duration(elapsed),
+        rays.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON) / 1.0e6
+    );
+}
+
 fn main() {
     let mut rng = rand::rng();
     let mut args = env::args();
     let program_name = args.next().unwrap_or_else(|| String::from("rustray"));
-    let scene_path = args
-        .next()
-        .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("scenes/bouncing_spheres.toml"));
-    let is_concurrent = args.next().map(|s| s == "--concurrent").unwrap_or(false);
+    let mut scene_path: Option<PathBuf> = None;
+    let mut is_concurrent = false;
+    let mut bvh_only = false;
+    let mut pixel_order = traversal::PixelOrder::Scanline;
+    let mut integrator = integrators::Integrator::Megakernel;
+    for arg in args {
+        match arg.as_str() {
+            "--concurrent" => is_concurrent = true,
+            "--bvh" => bvh_only = true,
+            "--morton" => pixel_order = traversal::PixelOrder::Morton,
+            "--wavefront" => integrator = integrators::Integrator::Wavefront,
+            "--sppm" => integrator = integrators::Integrator::Sppm,
+            _ => scene_path = Some(PathBuf::from(arg)),
+        }
+    }
+    let scene_path = scene_path.unwrap_or_else(|| PathBuf::from("scenes/bouncing_spheres.toml"));
 
     if !scene_path.is_file() {
         eprintln!(
-            "Scene file not found: {}. Usage: {} <scene-file>",
+            "Scene file not found: {}. Usage: {} <scene-file> [--concurrent] [--bvh] [--morton] [--wavefront] [--sppm]",
             scene_path.display(),
             program_name
         );
         std::process::exit(1);
     }
 
-    let mut render = match scene::load_from_file(&mut rng, scene_path.as_path()) {
+    let (mut render, load_timings) = match scene_file::load_render_timed(&mut rng, &scene_path) {
         Ok(result) => result,
         Err(err) => {
             eprintln!(
@@ -48,6 +102,20 @@ fn main() {
         }
     };
 
+    render.pixel_order = pixel_order;
+    render.integrator = integrator;
+
+    println!("=== Load Profile ===");
+    println!("Parse: {}", format_duration(load_timings.parse));
+    println!("Assets: {}", format_duration(load_timings.assets));
+    println!("Build: {}", format_duration(load_timings.build));
+    println!("Total: {}", format_duration(load_timings.total()));
+
+    bench_bvh(&render);
+    if bvh_only {
+        return;
+    }
+
     let mut wall_times = Vec::new();
 
     for &ns in SAMPLES.iter() {
@@ -59,8 +127,8 @@ fn main() {
             let cpus = num_cpus::get();
             println!(
                 "Rendering a {}x{} image with {} samples per pixel and max depth {} using {} threads",
-                render.width,
-                render.width as f32 * render.camera.aspect_ratio,
+                render.image_width(),
+                render.image_height(),
                 render.samples,
                 render.depth,
                 cpus
@@ -70,12 +138,12 @@ fn main() {
         } else {
             println!(
                 "Rendering a {}x{} image with {} samples per pixel and max depth {}",
-                render.width,
-                render.width as f32 * render.camera.aspect_ratio,
+                render.image_width(),
+                render.image_height(),
                 render.samples,
                 render.depth
             );
-            raytrace(&mut rng, &render)
+            raytrace(&render)
         };
 
         wall_times.push(render_start.elapsed());
@@ -103,8 +171,8 @@ fn main() {
         match image::save_buffer(
             &Path::new(&format!("samples/{}.png", filename)),
             data.as_slice(),
-            render.width,
-            (render.width as f32 / render.camera.aspect_ratio) as u32,
+            render.image_width(),
+            render.image_height(),
             image::ColorType::Rgb8,
         ) {
             Ok(_) => println!("Image saved."),
@@ -126,6 +194,8 @@ fn main() {
     }
 
     println!("\n=== Render Profile Summary ===");
+    println!("Pixel order: {:?}", render.pixel_order);
+    println!("Integrator: {:?}", render.integrator);
     for (i, &ns) in SAMPLES.iter().enumerate() {
         println!(
             "{} samples: Render Wall Time: {}",
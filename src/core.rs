@@ -1,12 +1,38 @@
 //! Core math and scene structures.
 pub mod acceleration;
+pub mod aov;
+pub mod assets;
+pub mod bake;
 pub mod bbox;
+pub mod bundle;
 pub mod bvh;
 pub mod camera;
+pub mod color;
+pub mod disk_film;
+pub mod events;
+pub mod film;
+pub mod furnace;
+pub mod fuzz;
+pub mod inspect;
+pub mod interrupt;
+pub mod introspect;
 pub mod object;
+pub mod overlay;
+pub mod package;
+pub mod path;
+pub mod path_export;
+pub mod photometry;
+pub mod plugins;
+pub mod preprocess;
+pub mod preset;
+pub mod progressive;
 pub mod ray;
 pub mod render;
+pub mod resolution;
 pub mod scene;
+pub mod scene_diff;
 pub mod scene_file;
+pub mod shutter;
+pub mod temporal;
 pub mod volume;
 pub mod world;
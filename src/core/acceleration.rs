@@ -1,5 +1,5 @@
 use crate::core::render;
-use crate::{ChunkBounds, assemble_chunks, image_height, raytrace_chunk};
+use crate::{ChunkBounds, assemble_chunks, raytrace_chunk};
 
 pub struct Threaded {
     num_threads: usize,
@@ -13,7 +13,7 @@ impl Threaded {
 
     pub fn render(&self, render: &render::Render) -> Vec<u8> {
         // split the render into horizontal strips for each thread
-        let height = image_height(render);
+        let height = render.image_height();
         let threads = self.num_threads.max(1);
         let strip_height = (height + threads as u32 - 1) / threads as u32;
 
@@ -30,15 +30,12 @@ impl Threaded {
 
                 let bounds = ChunkBounds {
                     x_start: 0,
-                    x_end: render.width,
+                    x_end: render.image_width(),
                     y_start,
                     y_end,
                 };
 
-                handles.push(scope.spawn(move || {
-                    let mut thread_rng = rand::rng();
-                    raytrace_chunk(&mut thread_rng, render, bounds)
-                }));
+                handles.push(scope.spawn(move || raytrace_chunk(render, bounds)));
             }
 
             for handle in handles {
@@ -46,6 +43,6 @@ impl Threaded {
             }
         });
 
-        assemble_chunks(&chunks, render.width, height)
+        assemble_chunks(&chunks, render)
     }
 }
@@ -0,0 +1,259 @@
+//! Auxiliary output buffers (AOVs) rendered alongside the beauty image for compositing.
+//!
+//! AOVs are traced with one ray through each pixel center from the lens center, so they
+//! are noise-free and don't blend values across edges; they line up pixel-for-pixel with
+//! the film of the same [`render::Render`].
+use std::path::Path;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{camera, object, ray, render, volume};
+use crate::geometry::instance;
+use crate::math::vec;
+use crate::traits::hittable;
+
+/// Value stored for pixels whose primary ray escapes the scene.
+pub const MISS_DEPTH: f32 = f32::INFINITY;
+
+/// Per-pixel float channels, stored like [`crate::core::film::Film`] with `(0, 0)` at
+/// the bottom-left corner.
+pub struct AovBuffer {
+    pub width: u32,
+    pub height: u32,
+    pub channels: usize,
+    data: Vec<f32>,
+}
+
+impl AovBuffer {
+    /// Creates a zeroed buffer with `channels` values per pixel.
+    pub fn new(width: u32, height: u32, channels: usize) -> Self {
+        AovBuffer {
+            width,
+            height,
+            channels,
+            data: vec![0.0; width as usize * height as usize * channels],
+        }
+    }
+
+    /// Returns the channels of the pixel at `(x, y)`.
+    pub fn pixel(&self, x: u32, y: u32) -> &[f32] {
+        let offset = (y as usize * self.width as usize + x as usize) * self.channels;
+        &self.data[offset..offset + self.channels]
+    }
+
+    /// Converts to a top-down float image. Single-channel buffers fill all three colors;
+    /// missing channels are zero and extra ones are dropped.
+    pub fn to_rgb32f(&self) -> image::Rgb32FImage {
+        image::Rgb32FImage::from_fn(self.width, self.height, |x, y| {
+            let pixel = self.pixel(x, self.height - 1 - y);
+            let channel = |index: usize| pixel.get(index).copied().unwrap_or(0.0);
+            match pixel {
+                [value] => image::Rgb([*value; 3]),
+                _ => image::Rgb([channel(0), channel(1), channel(2)]),
+            }
+        })
+    }
+
+    /// Writes the unmodified values to an OpenEXR file.
+    pub fn save_exr(&self, path: &Path) -> image::ImageResult<()> {
+        self.to_rgb32f()
+            .save_with_format(path, image::ImageFormat::OpenExr)
+    }
+}
+
+/// Depths mapped to the ends of the 8-bit depth visualization.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DepthRange {
+    /// Depth drawn white; anything closer is clamped.
+    pub near: f32,
+    /// Depth drawn black; anything farther, including misses, is clamped.
+    pub far: f32,
+}
+
+impl DepthRange {
+    /// Fits the range to the finite depths in `depth`; `None` if every pixel missed.
+    pub fn fit(depth: &AovBuffer) -> Option<DepthRange> {
+        depth
+            .data
+            .iter()
+            .copied()
+            .filter(|depth| depth.is_finite())
+            .fold(None, |range: Option<DepthRange>, depth| {
+                Some(match range {
+                    Some(range) => DepthRange {
+                        near: range.near.min(depth),
+                        far: range.far.max(depth),
+                    },
+                    None => DepthRange {
+                        near: depth,
+                        far: depth,
+                    },
+                })
+            })
+    }
+
+    /// Maps a depth to `[0, 1]`, 1 at `near` and 0 at `far`.
+    pub fn normalize(&self, depth: f32) -> f32 {
+        let span = self.far - self.near;
+        if span <= 0.0 {
+            return if depth <= self.near { 1.0 } else { 0.0 };
+        }
+        (1.0 - (depth - self.near) / span).clamp(0.0, 1.0)
+    }
+}
+
+/// Renders planar depth: the distance from the camera to each pixel's primary hit,
+/// measured along the viewing direction in world units ([`MISS_DEPTH`] where nothing is
+/// hit).
+///
+/// This is the z-depth compositing packages expect for depth-of-field and fog, not the
+/// distance along the ray, so flat walls facing the camera have constant depth.
+pub fn render_depth(render: &render::Render) -> AovBuffer {
+    let forward = -render.camera.w;
+    trace_primary(render, 1, |ray, record, depth| {
+        depth[0] = record.map_or(MISS_DEPTH, |record| {
+            record.hit.t * ray.direction.dot(&forward)
+        });
+    })
+}
+
+/// Renders the world-space position of each pixel's primary hit as RGB = XYZ; pixels
+/// where nothing is hit are zero (check [`render_normal`] to tell them from the origin).
+pub fn render_position(render: &render::Render) -> AovBuffer {
+    trace_primary(render, 3, |_, record, position| {
+        if let Some(record) = record {
+            let point = record.hit.point;
+            position.copy_from_slice(&[point.x, point.y, point.z]);
+        }
+    })
+}
+
+/// Renders the unit world-space surface normal at each pixel's primary hit as RGB = XYZ,
+/// flipped to face the camera so the visible side is always lit by relighting. Pixels
+/// where nothing is hit are the zero vector.
+pub fn render_normal(render: &render::Render) -> AovBuffer {
+    trace_primary(render, 3, |ray, record, normal| {
+        if let Some(record) = record {
+            let mut facing = record.hit.normal.normalize();
+            if facing.dot(&ray.direction) > 0.0 {
+                facing = -facing;
+            }
+            normal.copy_from_slice(&[facing.x, facing.y, facing.z]);
+        }
+    })
+}
+
+/// Renders screen-space motion vectors over the shutter interval: how far, in pixels,
+/// the surface seen at each pixel center when the shutter opens (time 0) has moved by
+/// the time it closes (time 1). Channels are `(dx, dy)` with `y` pointing up the image;
+/// pixels that hit nothing, or whose surface leaves the view, are zero.
+///
+/// Motion comes from `Move` transforms; the camera is fixed during the shutter.
+pub fn render_motion_vectors(render: &render::Render) -> AovBuffer {
+    let camera = &render.camera;
+    let (width, height) = (render.image_width() as f32, render.image_height() as f32);
+    trace_primary(render, 2, |_, record, motion| {
+        let Some(record) = record else {
+            return;
+        };
+        let start = record.hit.point;
+        let end = transport(record, &start, 0.0, 1.0);
+        if let (Some((u0, v0)), Some((u1, v1))) = (camera.project(&start), camera.project(&end)) {
+            motion.copy_from_slice(&[(u1 - u0) * width, (v1 - v0) * height]);
+        }
+    })
+}
+
+/// Renders motion vectors between animation frames: how far, in pixels, the surface
+/// seen at each pixel center moved on screen since the previous frame was shot with
+/// `previous_camera`. Layout and zeroes match [`render_motion_vectors`], so a temporal
+/// filter finds a pixel's previous position at its own position minus its vector.
+///
+/// Geometry is assumed to hold still between frames; only the camera's motion counts.
+pub fn render_frame_motion_vectors(
+    render: &render::Render,
+    previous_camera: &camera::Camera,
+) -> AovBuffer {
+    let camera = &render.camera;
+    let (width, height) = (render.image_width() as f32, render.image_height() as f32);
+    trace_primary(render, 2, |_, record, motion| {
+        let Some(record) = record else {
+            return;
+        };
+        let point = record.hit.point;
+        if let (Some((u0, v0)), Some((u1, v1))) =
+            (previous_camera.project(&point), camera.project(&point))
+        {
+            motion.copy_from_slice(&[(u1 - u0) * width, (v1 - v0) * height]);
+        }
+    })
+}
+
+/// Moves a point on the hit renderable's surface from time `from` to `to`, following its
+/// transforms. Renderables without transformed geometry stay put.
+fn transport(
+    record: &hittable::HitRecord<'_>,
+    point: &vec::Point3,
+    from: f64,
+    to: f64,
+) -> vec::Point3 {
+    let renderable = record.renderable.as_any();
+    let instance = if let Some(render_object) = renderable.downcast_ref::<object::RenderObject>() {
+        Some(&render_object.geometry_instance)
+    } else if let Some(render_volume) = renderable.downcast_ref::<volume::RenderVolume>() {
+        render_volume
+            .boundary
+            .as_any()
+            .downcast_ref::<instance::GeometryInstance>()
+    } else {
+        None
+    };
+    instance.map_or(*point, |instance| instance.transport(point, from, to))
+}
+
+/// Traces the pixel-center ray of every pixel in parallel and lets `shade` fill that
+/// pixel's channels from the primary hit, or `None` if the ray escaped.
+fn trace_primary<F>(render: &render::Render, channels: usize, shade: F) -> AovBuffer
+where
+    F: Fn(&ray::Ray, Option<&hittable::HitRecord<'_>>, &mut [f32]) + Sync,
+{
+    let (width, height) = (render.image_width(), render.image_height());
+    let mut buffer = AovBuffer::new(width, height, channels);
+
+    buffer
+        .data
+        .par_chunks_mut(width as usize * channels)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.chunks_mut(channels).enumerate() {
+                let u = (x as f32 + 0.5) / width as f32;
+                let v = (y as f32 + 0.5) / height as f32;
+                let ray = render.camera.center_ray(u, v);
+                let record = render
+                    .camera
+                    .clip(&ray, &render.ray_ranges)
+                    .camera
+                    .hit(&render.scene, &ray)
+                    // The `World` background reports its hit at `f32::MAX`.
+                    .filter(|record| record.hit.t < f32::MAX);
+                shade(&ray, record.as_ref(), pixel);
+            }
+        });
+    buffer
+}
+
+/// Draws a depth buffer as a top-down grayscale image, white at `range.near` and black
+/// at `range.far`. Without a range, the visible depths are fitted.
+pub fn visualize_depth(depth: &AovBuffer, range: Option<DepthRange>) -> image::GrayImage {
+    let range = range
+        .or_else(|| DepthRange::fit(depth))
+        .unwrap_or(DepthRange {
+            near: 0.0,
+            far: 1.0,
+        });
+    image::GrayImage::from_fn(depth.width, depth.height, |x, y| {
+        let value = range.normalize(depth.pixel(x, depth.height - 1 - y)[0]);
+        image::Luma([(value * 255.0).round() as u8])
+    })
+}
@@ -0,0 +1,112 @@
+//! Resolution of asset references (textures, meshes) named in scene files.
+//!
+//! A reference is either a local path or an `http://`/`https://` URL. URLs are
+//! downloaded once into a local cache directory and served from there afterwards,
+//! so a scene can be shared as a single TOML file that pulls its own assets.
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Environment variable that overrides the download cache directory.
+pub const CACHE_DIR_ENV: &str = "RUSTRAY_CACHE_DIR";
+
+thread_local! {
+    static BASE_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with relative local references resolved against `dir` instead of the
+/// working directory (used while loading a scene bundle).
+pub fn with_base_dir<T>(dir: &Path, f: impl FnOnce() -> T) -> T {
+    let previous = BASE_DIR.with(|base| base.replace(Some(dir.to_path_buf())));
+    let result = f();
+    BASE_DIR.with(|base| *base.borrow_mut() = previous);
+    result
+}
+
+/// Directory relative references resolve against on this thread, if set by
+/// [`with_base_dir`]; for handing the setting on to worker threads.
+pub fn base_dir() -> Option<PathBuf> {
+    BASE_DIR.with(|base| base.borrow().clone())
+}
+
+/// Returns whether `reference` names a remote asset.
+pub fn is_remote(reference: &str) -> bool {
+    reference.starts_with("http://") || reference.starts_with("https://")
+}
+
+/// Directory downloaded assets are cached in: `$RUSTRAY_CACHE_DIR`, or
+/// `rustray-cache` under the system temp directory.
+pub fn cache_dir() -> PathBuf {
+    std::env::var_os(CACHE_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("rustray-cache"))
+}
+
+/// Resolves an asset reference to a local file, downloading remote assets into
+/// the cache on first use.
+pub fn resolve(reference: &str) -> io::Result<PathBuf> {
+    if !is_remote(reference) {
+        let path = PathBuf::from(reference);
+        return Ok(match base_dir() {
+            Some(base) if path.is_relative() => base.join(path),
+            _ => path,
+        });
+    }
+
+    let cached = cache_dir().join(cache_file_name(reference));
+    if cached.is_file() {
+        return Ok(cached);
+    }
+    download(reference, &cached)?;
+    Ok(cached)
+}
+
+fn download(url: &str, destination: &Path) -> io::Result<()> {
+    let response = ureq::get(url)
+        .call()
+        .map_err(|err| io::Error::other(format!("failed to fetch asset: {}", err)))?;
+
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write to a sibling file first so an interrupted download never looks cached. Scenes
+    // load their assets in parallel, so each download gets its own.
+    static DOWNLOADS: AtomicU64 = AtomicU64::new(0);
+    let partial = destination.with_extension(format!(
+        "partial-{}-{}",
+        std::process::id(),
+        DOWNLOADS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = fs::File::create(&partial)?;
+    io::copy(&mut response.into_reader(), &mut file)?;
+    fs::rename(&partial, destination)
+}
+
+/// Names the cache entry after a stable hash of the URL, keeping the extension so
+/// format detection by file name still works.
+fn cache_file_name(url: &str) -> String {
+    let hash = content_hash(url.as_bytes());
+
+    let file_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .unwrap_or_default();
+    match Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+    {
+        Some(ext) => format!("{:016x}.{}", hash, ext),
+        None => format!("{:016x}", hash),
+    }
+}
+
+/// FNV-1a hash used to name cache entries; stable across builds, unlike
+/// `std::hash::DefaultHasher`.
+pub(crate) fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
@@ -0,0 +1,259 @@
+//! Textures baked from an object's surface, for masks in procedural materials.
+//!
+//! [`ambient_occlusion`] and [`curvature`] record a property of one object's surface as
+//! a grayscale image over its `(u, v)` coordinates, laid out the way
+//! [`crate::textures::uv::UvTexture`] reads images, so a bake can drive where dirt
+//! gathers in crevices or paint wears off edges. A bake draws random points on the
+//! surface ([`hittable::Hittable::sample_surface`]), evaluates the property there, and
+//! averages the points landing in each texel; texels no point landed in take their
+//! neighbors' values, so filtering at UV seams doesn't pull in black.
+use rayon::prelude::*;
+
+use crate::core::{object, ray, scene};
+use crate::math::{onb, pdf, rng, vec};
+use crate::traits::hittable::{self, Hittable};
+
+/// How far off the surface points are probed and rays start, relative to the size of
+/// the coordinates there.
+const PROBE_OFFSET: f32 = 1e-3;
+
+/// Spacing of the neighbors curvature is measured against, as a fraction of the
+/// object's bounding-box diagonal.
+const CURVATURE_PROBE: f32 = 0.01;
+
+/// Neighbors each curvature estimate compares normals with.
+const CURVATURE_NEIGHBORS: u32 = 4;
+
+/// Surface points drawn by each parallel task.
+const SAMPLES_PER_TASK: u64 = 4096;
+
+/// Ray time bakes are taken at, when the shutter opens.
+const BAKE_TIME: f64 = 0.0;
+
+/// Resolution and quality of a bake.
+#[derive(Clone, Copy, Debug)]
+pub struct BakeSettings {
+    pub width: u32,
+    pub height: u32,
+    /// Surface points drawn per texel, on average.
+    pub samples_per_texel: u32,
+    /// Occlusion rays traced from each point.
+    pub rays: u32,
+    /// How far away an occluder still darkens a point, as a fraction of the object's
+    /// bounding-box diagonal.
+    pub occlusion_distance: f32,
+    /// Radius of curvature baked fully white (convex) or black (concave), as a fraction
+    /// of the object's bounding-box diagonal; flatter surfaces fade to mid-gray.
+    pub curvature_radius: f32,
+    /// Seed for the random points and rays; the same seed reproduces the same bake.
+    pub seed: u64,
+}
+
+impl BakeSettings {
+    /// A `width` by `height` bake of 4 points per texel and 32 occlusion rays per point,
+    /// counting occluders within a quarter of the object's size and saturating
+    /// curvature at a twentieth of it.
+    pub fn new(width: u32, height: u32) -> Self {
+        BakeSettings {
+            width,
+            height,
+            samples_per_texel: 4,
+            rays: 32,
+            occlusion_distance: 0.25,
+            curvature_radius: 0.05,
+            seed: 0,
+        }
+    }
+}
+
+/// Bakes how open the sky is above each point of `object`: white where nothing in
+/// `scene` (including the object itself) blocks the cosine-weighted hemisphere within
+/// `settings.occlusion_distance`, darker in crevices and contact areas.
+///
+/// `None` if the object is unbounded or can't draw points on its surface.
+pub fn ambient_occlusion(
+    scene: &scene::Scene,
+    object: &object::RenderObject,
+    settings: &BakeSettings,
+) -> Option<image::GrayImage> {
+    let rays = settings.rays.max(1);
+    bake(object, settings, |rng, point, normal, diagonal| {
+        let distance = settings.occlusion_distance * diagonal;
+        let hemisphere = pdf::cosine::CosinePDF::new(normal);
+        let origin = *point + *normal * offset(point);
+        let open = (0..rays)
+            .filter(|_| {
+                let direction = pdf::PDF::generate(&hemisphere, rng);
+                let ray = ray::Ray::new(&origin, &direction, Some(BAKE_TIME));
+                !scene.occluded(&ray, 0.0, distance)
+            })
+            .count();
+        Some(open as f32 / rays as f32)
+    })
+}
+
+/// Bakes the mean curvature of `object`'s surface: mid-gray where it's flat, brighter
+/// on convex bumps and edges, darker in concave creases, saturating at
+/// `settings.curvature_radius`. Only the object's own surface is measured.
+///
+/// `None` if the object is unbounded or can't draw points on its surface.
+pub fn curvature(
+    object: &object::RenderObject,
+    settings: &BakeSettings,
+) -> Option<image::GrayImage> {
+    let geometry = &object.geometry_instance;
+    bake(object, settings, |rng, point, normal, diagonal| {
+        let spacing = CURVATURE_PROBE * diagonal;
+        let frame = onb::ONB::build_from_w(normal);
+        let start_angle = rand::Rng::random::<f32>(rng) * std::f32::consts::TAU;
+        let mut total = 0.0;
+        let mut found = 0;
+        for index in 0..CURVATURE_NEIGHBORS {
+            // Look straight down at the surface from a little way along the tangent
+            // plane, and compare the normal there with this one.
+            let angle =
+                start_angle + index as f32 * std::f32::consts::TAU / CURVATURE_NEIGHBORS as f32;
+            let tangent = frame.u * angle.cos() + frame.v * angle.sin();
+            let above = *point + tangent * spacing + *normal * spacing;
+            let probe = ray::Ray::new(&above, &-*normal, Some(BAKE_TIME));
+            let Some(neighbor) = geometry.hit(&probe, 0.0, 3.0 * spacing) else {
+                continue;
+            };
+            let neighbor_normal = facing(&neighbor, normal);
+            let step = neighbor.point - *point;
+            let length_squared = step.squared_length();
+            if length_squared <= f32::EPSILON {
+                continue;
+            }
+            // Normals of a sphere of radius R turn by |step| / R over a step, so this
+            // is 1 / R: positive where the surface bends away from its normal.
+            total += (neighbor_normal - *normal).dot(&step) / length_squared;
+            found += 1;
+        }
+        (found > 0).then(|| {
+            let bend = total / found as f32 * settings.curvature_radius * diagonal;
+            0.5 + 0.5 * bend.clamp(-1.0, 1.0)
+        })
+    })
+}
+
+/// Averages `evaluate(rng, point, normal, diagonal)`, a value in `[0, 1]`, over random
+/// points of `object`'s surface into the texels their `(u, v)` fall in.
+fn bake(
+    object: &object::RenderObject,
+    settings: &BakeSettings,
+    evaluate: impl Fn(&mut rng::SampleRng, &vec::Point3, &vec::Vec3, f32) -> Option<f32> + Sync,
+) -> Option<image::GrayImage> {
+    let geometry = &object.geometry_instance;
+    let bounds = geometry.bounding_box();
+    if bounds.is_unbounded() {
+        return None;
+    }
+    let diagonal = vec::Vec3::new(bounds.x.length(), bounds.y.length(), bounds.z.length()).length();
+    let (width, height) = (settings.width.max(1), settings.height.max(1));
+    let texels = width as usize * height as usize;
+    let total = texels as u64 * settings.samples_per_texel.max(1) as u64;
+
+    let samples: Vec<Vec<(usize, f32)>> = (0..total.div_ceil(SAMPLES_PER_TASK))
+        .into_par_iter()
+        .map(|task| {
+            let mut rng = rng::hashed_rng(settings.seed ^ task.rotate_left(32));
+            let count = SAMPLES_PER_TASK.min(total - task * SAMPLES_PER_TASK);
+            (0..count)
+                .filter_map(|_| {
+                    let sample = geometry.sample_surface(&mut rng, BAKE_TIME)?;
+                    let point = sample.point;
+                    let probe_offset = offset(&point);
+                    let probe = ray::Ray::new(
+                        &(point + sample.normal * probe_offset),
+                        &-sample.normal,
+                        Some(BAKE_TIME),
+                    );
+                    let hit = geometry.hit(&probe, 0.0, 2.0 * probe_offset)?;
+                    let normal = facing(&hit, &sample.normal);
+                    let value = evaluate(&mut rng, &hit.point, &normal, diagonal)?;
+                    Some((texel(&hit, width, height), value))
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut sums = vec![0.0_f32; texels];
+    let mut counts = vec![0_u32; texels];
+    for (index, value) in samples.into_iter().flatten() {
+        sums[index] += value;
+        counts[index] += 1;
+    }
+    let mut values: Vec<Option<f32>> = sums
+        .iter()
+        .zip(&counts)
+        .map(|(sum, count)| (*count > 0).then(|| sum / *count as f32))
+        .collect();
+    if values.iter().all(Option::is_none) {
+        return None;
+    }
+    dilate(&mut values, width as usize, height as usize);
+
+    let pixels = values
+        .into_iter()
+        .map(|value| (value.unwrap_or(0.0).clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+    image::GrayImage::from_raw(width, height, pixels)
+}
+
+/// Fills empty texels with the average of their filled neighbors, ring by ring, until
+/// every texel has a value.
+fn dilate(values: &mut [Option<f32>], width: usize, height: usize) {
+    loop {
+        let mut filled = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if values[y * width + x].is_some() {
+                    continue;
+                }
+                let neighbors: Vec<f32> = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .iter()
+                    .filter_map(|(dx, dy)| {
+                        let nx = x.checked_add_signed(*dx).filter(|nx| *nx < width)?;
+                        let ny = y.checked_add_signed(*dy).filter(|ny| *ny < height)?;
+                        values[ny * width + nx]
+                    })
+                    .collect();
+                if !neighbors.is_empty() {
+                    let mean = neighbors.iter().sum::<f32>() / neighbors.len() as f32;
+                    filled.push((y * width + x, mean));
+                }
+            }
+        }
+        if filled.is_empty() {
+            return;
+        }
+        for (index, value) in filled {
+            values[index] = Some(value);
+        }
+    }
+}
+
+/// Texel index of a hit's `(u, v)`, wrapping `u` and clamping `v` as
+/// [`crate::textures::uv::UvTexture`] does, with `v = 1` in the top row.
+fn texel(hit: &hittable::Hit, width: u32, height: u32) -> usize {
+    let u = hit.u - hit.u.floor();
+    let v = hit.v.clamp(0.0, 1.0);
+    let x = ((u * width as f32) as u32).min(width - 1);
+    let y = (((1.0 - v) * height as f32) as u32).min(height - 1);
+    y as usize * width as usize + x as usize
+}
+
+/// The hit's shading normal, turned to the same side as `toward`.
+fn facing(hit: &hittable::Hit, toward: &vec::Vec3) -> vec::Vec3 {
+    let normal = vec::unit_vector(&hit.shading_normal.unwrap_or(hit.normal));
+    if normal.dot(toward) < 0.0 {
+        -normal
+    } else {
+        normal
+    }
+}
+
+fn offset(point: &vec::Point3) -> f32 {
+    PROBE_OFFSET * (1.0 + point.x.abs().max(point.y.abs()).max(point.z.abs()))
+}
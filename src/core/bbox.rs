@@ -34,6 +34,21 @@ impl BBox {
         }
     }
 
+    /// The region both boxes cover, if they overlap.
+    pub fn intersection(&self, other: &BBox) -> Option<BBox> {
+        Some(BBox {
+            x: self.x.overlap(&other.x)?,
+            y: self.y.overlap(&other.y)?,
+            z: self.z.overlap(&other.z)?,
+        })
+    }
+
+    /// Total area of the box's six faces.
+    pub fn surface_area(&self) -> f32 {
+        let (x, y, z) = (self.x.length(), self.y.length(), self.z.length());
+        2.0 * (x * y + y * z + z * x)
+    }
+
     pub fn axis(&self, axis: usize) -> &interval::Interval {
         match axis {
             0 => &self.x,
@@ -43,6 +58,14 @@ impl BBox {
         }
     }
 
+    /// Whether any axis spans an infinite extent (e.g. the skybox), which makes the box
+    /// useless for culling.
+    pub fn is_unbounded(&self) -> bool {
+        [self.x, self.y, self.z]
+            .iter()
+            .any(|axis| axis.length() == f32::INFINITY)
+    }
+
     pub fn longest_axis(&self) -> usize {
         let x_length = self.x.length();
         let y_length = self.y.length();
@@ -70,6 +93,12 @@ impl BBox {
     }
 
     pub fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> bool {
+        self.clip(ray, t_min, t_max).is_some()
+    }
+
+    /// Narrows `[t_min, t_max]` to the part of the ray inside the box, or `None` if the
+    /// ray misses it in that range.
+    pub fn clip(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<(f32, f32)> {
         let inv_dir = vec::Vec3::new(
             1.0 / ray.direction.x,
             1.0 / ray.direction.y,
@@ -106,6 +135,6 @@ impl BBox {
         t_min = t0.max(t_min);
         t_max = t1.min(t_max);
 
-        t_max > t_min
+        (t_max > t_min).then_some((t_min, t_max))
     }
 }
@@ -0,0 +1,84 @@
+//! `.rustray` scene bundles: a zip (or tar) archive holding `scene.toml` plus the
+//! textures and meshes it references, so a complete scene travels as one file.
+//!
+//! Bundles are unpacked into the asset cache (see [`assets::cache_dir`]) keyed by
+//! their contents, and relative asset paths in the scene resolve inside the bundle.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::core::assets;
+use crate::core::scene_file::{self, SceneFileError};
+
+/// File extension that marks a scene bundle.
+pub const EXTENSION: &str = "rustray";
+
+/// Name of the scene file at the root of a bundle.
+pub const SCENE_FILE: &str = "scene.toml";
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Returns whether `path` names a scene bundle.
+pub fn is_bundle(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some(EXTENSION)
+}
+
+/// Reads the scene file packed in a bundle.
+pub fn read_scene_file(path: &Path) -> Result<scene_file::SceneFile, SceneFileError> {
+    read_scene_file_timed(path).map(|(file, _)| file)
+}
+
+/// Like [`read_scene_file`], counting the unpack as part of parsing.
+pub fn read_scene_file_timed(
+    path: &Path,
+) -> Result<(scene_file::SceneFile, scene_file::LoadTimings), SceneFileError> {
+    let unpack_start = Instant::now();
+    let dir = unpack(path)?;
+    let unpacking = unpack_start.elapsed();
+    let scene_path = dir.join(SCENE_FILE);
+    if !scene_path.is_file() {
+        return Err(SceneFileError::Bundle(format!(
+            "{} has no {} at its root",
+            path.display(),
+            SCENE_FILE
+        )));
+    }
+    let (file, mut timings) =
+        assets::with_base_dir(&dir, || scene_file::read_scene_file_timed(&scene_path))?;
+    timings.parse += unpacking;
+    Ok((file, timings))
+}
+
+/// Unpacks a bundle into the cache, reusing an earlier unpack of identical contents.
+pub fn unpack(path: &Path) -> Result<PathBuf, SceneFileError> {
+    let bytes = fs::read(path)?;
+    let dir = assets::cache_dir()
+        .join("bundles")
+        .join(format!("{:016x}", assets::content_hash(&bytes)));
+    if dir.join(SCENE_FILE).is_file() {
+        return Ok(dir);
+    }
+
+    // Unpack next to the final directory and rename, so a failed unpack never
+    // looks complete.
+    let partial = dir.with_extension("partial");
+    if partial.exists() {
+        fs::remove_dir_all(&partial)?;
+    }
+    fs::create_dir_all(&partial)?;
+    if bytes.starts_with(ZIP_MAGIC) {
+        zip::ZipArchive::new(io::Cursor::new(bytes))
+            .and_then(|mut archive| archive.extract(&partial))
+            .map_err(|err| SceneFileError::Bundle(format!("{}: {}", path.display(), err)))?;
+    } else {
+        tar::Archive::new(io::Cursor::new(bytes))
+            .unpack(&partial)
+            .map_err(|err| SceneFileError::Bundle(format!("{}: {}", path.display(), err)))?;
+    }
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::rename(&partial, &dir)?;
+    Ok(dir)
+}
@@ -1,7 +1,13 @@
 //! Bounding Volume Hierarchy for accelerating renderable hit tests.
+use std::fmt;
+
 use crate::core::{bbox, ray};
+use crate::math::vec;
 use crate::traits::{hittable, renderable};
 
+/// Cost of visiting a branch in [`BvhStats::sah_cost`], relative to testing one object.
+const SAH_TRAVERSAL_COST: f32 = 0.125;
+
 /// Internal BVH node representation.
 pub enum BvhNode {
     Leaf {
@@ -10,6 +16,8 @@ pub enum BvhNode {
     },
     Branch {
         bounding_box: bbox::BBox,
+        /// Axis the children were split along; `left` holds the lower half.
+        axis: usize,
         left: Box<BvhNode>,
         right: Box<BvhNode>,
     },
@@ -53,6 +61,7 @@ impl BvhNode {
 
         BvhNode::Branch {
             bounding_box,
+            axis,
             left,
             right,
         }
@@ -69,6 +78,7 @@ impl BvhNode {
             BvhNode::Leaf { index, .. } => objects[*index].hit(ray, t_min, t_max),
             BvhNode::Branch {
                 bounding_box,
+                axis,
                 left,
                 right,
             } => {
@@ -76,16 +86,24 @@ impl BvhNode {
                     return None;
                 }
 
+                // Visit the child nearer along the split axis first so its hit distance
+                // can cull the far child.
+                let (near, far) = if ray.direction[*axis] < 0.0 {
+                    (right, left)
+                } else {
+                    (left, right)
+                };
+
                 let mut closest = t_max;
                 let mut hit_record: Option<hittable::HitRecord> = None;
 
-                if let Some(left_hit) = left.hit(objects, ray, t_min, closest) {
-                    closest = left_hit.hit.t;
-                    hit_record = Some(left_hit);
+                if let Some(near_hit) = near.hit(objects, ray, t_min, closest) {
+                    closest = near_hit.hit.t;
+                    hit_record = Some(near_hit);
                 }
 
-                if let Some(right_hit) = right.hit(objects, ray, t_min, closest) {
-                    hit_record = Some(right_hit);
+                if let Some(far_hit) = far.hit(objects, ray, t_min, closest) {
+                    hit_record = Some(far_hit);
                 }
 
                 hit_record
@@ -93,6 +111,35 @@ impl BvhNode {
         }
     }
 
+    fn hit_any(
+        &self,
+        objects: &[Box<dyn renderable::Renderable + Send + Sync>],
+        ray: &ray::Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> bool {
+        match self {
+            BvhNode::Leaf { index, .. } => objects[*index].occludes(ray, t_min, t_max),
+            BvhNode::Branch {
+                bounding_box,
+                axis,
+                left,
+                right,
+            } => {
+                if !bounding_box.hit(ray, t_min, t_max) {
+                    return false;
+                }
+
+                let (near, far) = if ray.direction[*axis] < 0.0 {
+                    (right, left)
+                } else {
+                    (left, right)
+                };
+                near.hit_any(objects, ray, t_min, t_max) || far.hit_any(objects, ray, t_min, t_max)
+            }
+        }
+    }
+
     fn bounding_box(&self) -> &bbox::BBox {
         match self {
             BvhNode::Leaf { bounding_box, .. } => bounding_box,
@@ -117,6 +164,85 @@ impl BvhNode {
     }
 }
 
+/// Quality metrics of a built [`Bvh`], for spotting acceleration-structure regressions
+/// without timing a render.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BvhStats {
+    /// Branches and leaves.
+    pub nodes: usize,
+    pub leaves: usize,
+    /// Objects referenced by the leaves.
+    pub objects: usize,
+    /// Edges from the root to the deepest leaf.
+    pub max_depth: u32,
+    /// Objects per leaf.
+    pub average_leaf_size: f32,
+    /// Expected cost of tracing a ray through the tree under the surface area heuristic:
+    /// each node's chance of being visited, its surface area over the root's, times the
+    /// cost of visiting it, in units of one object test. Lower is better; a flat list
+    /// costs the object count.
+    pub sah_cost: f32,
+    /// Average over branches of how much their children's boxes overlap, as the surface
+    /// area of the overlap over the branch's; 0 when siblings never overlap, so a ray
+    /// entering one needn't enter the other.
+    pub overlap: f32,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} nodes ({} leaves over {} objects), max depth {}",
+            self.nodes, self.leaves, self.objects, self.max_depth
+        )?;
+        writeln!(f, "SAH cost: {:.3}", self.sah_cost)?;
+        writeln!(f, "Average leaf size: {:.2}", self.average_leaf_size)?;
+        write!(f, "Overlap factor: {:.3}", self.overlap)
+    }
+}
+
+/// Running totals behind [`BvhStats`].
+#[derive(Default)]
+struct StatsTally {
+    stats: BvhStats,
+    branches: usize,
+    overlap_sum: f32,
+    weighted_cost: f32,
+}
+
+impl StatsTally {
+    fn visit(&mut self, node: &BvhNode, depth: u32) {
+        self.stats.nodes += 1;
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        match node {
+            BvhNode::Leaf { bounding_box, .. } => {
+                self.stats.leaves += 1;
+                self.stats.objects += 1;
+                self.weighted_cost += bounding_box.surface_area();
+            }
+            BvhNode::Branch {
+                bounding_box,
+                left,
+                right,
+                ..
+            } => {
+                self.branches += 1;
+                let area = bounding_box.surface_area();
+                self.weighted_cost += SAH_TRAVERSAL_COST * area;
+                if area > 0.0 {
+                    let shared = left
+                        .bounding_box()
+                        .intersection(right.bounding_box())
+                        .map_or(0.0, |overlap| overlap.surface_area());
+                    self.overlap_sum += shared / area;
+                }
+                self.visit(left, depth + 1);
+                self.visit(right, depth + 1);
+            }
+        }
+    }
+}
+
 /// BVH root wrapper that implements the `Renderable` trait.
 pub struct Bvh {
     pub root: BvhNode,
@@ -128,6 +254,15 @@ impl Bvh {
         objects: &[Box<dyn renderable::Renderable + Send + Sync>],
     ) -> Self {
         let indices = (0..objects.len()).collect::<Vec<_>>();
+        Bvh::from_indices(rng, objects, indices)
+    }
+
+    /// Builds a BVH over a subset of `objects`; leaves keep indices into the full slice.
+    pub fn from_indices(
+        rng: &mut rand::rngs::ThreadRng,
+        objects: &[Box<dyn renderable::Renderable + Send + Sync>],
+        indices: Vec<usize>,
+    ) -> Self {
         Bvh {
             root: BvhNode::new(rng, objects, indices),
         }
@@ -137,6 +272,19 @@ impl Bvh {
         self.root.bounding_box()
     }
 
+    /// Measures the tree's quality; see [`BvhStats`].
+    pub fn stats(&self) -> BvhStats {
+        let mut tally = StatsTally::default();
+        tally.visit(&self.root, 0);
+        let mut stats = tally.stats;
+        stats.average_leaf_size = stats.objects as f32 / stats.leaves as f32;
+        stats.sah_cost = tally.weighted_cost / self.bounding_box().surface_area();
+        if tally.branches > 0 {
+            stats.overlap = tally.overlap_sum / tally.branches as f32;
+        }
+        stats
+    }
+
     pub fn hit<'a>(
         &'a self,
         objects: &'a [Box<dyn renderable::Renderable + Send + Sync>],
@@ -146,4 +294,251 @@ impl Bvh {
     ) -> Option<hittable::HitRecord<'a>> {
         self.root.hit(objects, ray, t_min, t_max)
     }
+
+    /// Finds the closest hit for each ray in a packet.
+    ///
+    /// The packet walks the tree together with a single node stack: each node filters the
+    /// rays still active against its box, and children are visited nearest-first according
+    /// to the packet's majority direction along the split axis.
+    pub fn hit_batch<'a>(
+        &'a self,
+        objects: &'a [Box<dyn renderable::Renderable + Send + Sync>],
+        rays: &[ray::Ray],
+        t_min: f32,
+        t_max: f32,
+    ) -> Vec<Option<hittable::HitRecord<'a>>> {
+        let mut closest = vec![t_max; rays.len()];
+        let mut records: Vec<Option<hittable::HitRecord<'a>>> =
+            (0..rays.len()).map(|_| None).collect();
+        let mut stack: Vec<(&BvhNode, Vec<usize>)> = vec![(&self.root, (0..rays.len()).collect())];
+
+        while let Some((node, active)) = stack.pop() {
+            match node {
+                // Leaves defer to the object's own test, like the single-ray traversal;
+                // some leaf boxes are looser than the geometry they wrap.
+                BvhNode::Leaf { index, .. } => {
+                    for i in active {
+                        if let Some(record) = objects[*index].hit(&rays[i], t_min, closest[i]) {
+                            closest[i] = record.hit.t;
+                            records[i] = Some(record);
+                        }
+                    }
+                }
+                BvhNode::Branch {
+                    bounding_box,
+                    axis,
+                    left,
+                    right,
+                } => {
+                    let active: Vec<usize> = active
+                        .into_iter()
+                        .filter(|&i| bounding_box.hit(&rays[i], t_min, closest[i]))
+                        .collect();
+                    if active.is_empty() {
+                        continue;
+                    }
+
+                    let negative = active
+                        .iter()
+                        .filter(|&&i| rays[i].direction[*axis] < 0.0)
+                        .count();
+                    let (near, far) = if negative * 2 > active.len() {
+                        (right, left)
+                    } else {
+                        (left, right)
+                    };
+                    stack.push((far, active.clone()));
+                    stack.push((near, active));
+                }
+            }
+        }
+
+        records
+    }
+
+    /// Returns whether any object intersects the ray within `[t_min, t_max]`, stopping at the
+    /// first intersection found instead of searching for the closest one.
+    pub fn hit_any(
+        &self,
+        objects: &[Box<dyn renderable::Renderable + Send + Sync>],
+        ray: &ray::Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> bool {
+        self.root.hit_any(objects, ray, t_min, t_max)
+    }
+}
+
+/// Something with bounds, so a [`FlatBvh`] can be built over a slice of them.
+pub trait Bounded {
+    fn bounding_box(&self) -> bbox::BBox;
+}
+
+/// An item paired with bounds computed up front, for items that can't produce their own.
+impl<T> Bounded for (bbox::BBox, T) {
+    fn bounding_box(&self) -> bbox::BBox {
+        self.0
+    }
+}
+
+/// A node of a [`FlatBvh`]. Children of a branch are stored right after it (left) and at
+/// `second` (right); a leaf covers `count` items from `first`.
+#[derive(Clone, Copy)]
+struct FlatNode {
+    bounding_box: bbox::BBox,
+    first: u32,
+    count: u32,
+    second: u32,
+}
+
+/// A BVH over the items of one primitive (a mesh's triangles, a fractal's copies), kept
+/// as a flat array of nodes. Building it reorders the items so every leaf covers a
+/// contiguous run of them; the primitive keeps the items and the tree indexes into them.
+#[derive(Clone, Default)]
+pub struct FlatBvh {
+    nodes: Vec<FlatNode>,
+}
+
+impl FlatBvh {
+    /// Builds the tree over `items`, splitting nodes at the median centroid until at most
+    /// `leaf_size` items remain.
+    pub fn build<T: Bounded>(items: &mut [T], leaf_size: usize) -> Self {
+        let mut nodes = Vec::with_capacity(items.len().div_ceil(leaf_size.max(1)) * 2);
+        if !items.is_empty() {
+            let count = items.len();
+            build_flat(items, leaf_size.max(1), 0, count, &mut nodes);
+        }
+        FlatBvh { nodes }
+    }
+
+    /// Bounds of every item, or an empty box if there are none.
+    pub fn bounding_box(&self) -> bbox::BBox {
+        self.nodes
+            .first()
+            .map_or_else(bbox::BBox::default, |node| node.bounding_box)
+    }
+
+    /// Walks the leaves whose boxes `ray` hits before the closest hit so far, calling
+    /// `hit_item` with an item's index and that distance; it returns the item's `t` when
+    /// the item is hit nearer, which then bounds the rest of the walk.
+    pub fn hit(
+        &self,
+        ray: &ray::Ray,
+        t_min: f32,
+        t_max: f32,
+        mut hit_item: impl FnMut(usize, f32) -> Option<f32>,
+    ) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut closest_t = t_max;
+        let mut stack = vec![0_usize];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounding_box.hit(ray, t_min, closest_t) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.second as usize);
+                stack.push(index + 1);
+                continue;
+            }
+            let first = node.first as usize;
+            for item in first..first + node.count as usize {
+                if let Some(t) = hit_item(item, closest_t) {
+                    closest_t = t;
+                }
+            }
+        }
+    }
+}
+
+/// Builds the subtree over `items[first..first + count]`, reordering them so every leaf
+/// covers a contiguous run, and returns the index of its root.
+fn build_flat<T: Bounded>(
+    items: &mut [T],
+    leaf_size: usize,
+    first: usize,
+    count: usize,
+    nodes: &mut Vec<FlatNode>,
+) -> usize {
+    let run = &mut items[first..first + count];
+    let bounding_box = run
+        .iter()
+        .skip(1)
+        .fold(run[0].bounding_box(), |bounding_box, item| {
+            bounding_box.union(&item.bounding_box())
+        });
+    let index = nodes.len();
+    nodes.push(FlatNode {
+        bounding_box,
+        first: first as u32,
+        count: count as u32,
+        second: 0,
+    });
+    if count <= leaf_size {
+        return index;
+    }
+
+    // Split at the median centroid along the axis the centroids spread furthest.
+    let centroid = |item: &T| -> vec::Point3 {
+        let bounds = item.bounding_box();
+        vec::Point3::new(
+            (bounds.x.min + bounds.x.max) * 0.5,
+            (bounds.y.min + bounds.y.max) * 0.5,
+            (bounds.z.min + bounds.z.max) * 0.5,
+        )
+    };
+    let axis = run
+        .iter()
+        .map(|item| bbox::BBox::bounding(centroid(item), centroid(item)))
+        .reduce(|bounds, next| bounds.union(&next))
+        .map_or(0, |bounds| bounds.longest_axis());
+    let half = count / 2;
+    run.select_nth_unstable_by(half, |a, b| centroid(a)[axis].total_cmp(&centroid(b)[axis]));
+
+    nodes[index].count = 0;
+    build_flat(items, leaf_size, first, half, nodes);
+    let second = build_flat(items, leaf_size, first + half, count - half, nodes);
+    nodes[index].second = second as u32;
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn flat_bvh_finds_the_closest_item() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut point = || vec::Point3::new(rng.random(), rng.random(), rng.random()) * 10.0;
+        let boxes: Vec<bbox::BBox> = (0..200)
+            .map(|_| {
+                let corner = point();
+                bbox::BBox::bounding(corner, corner + vec::Vec3::new(0.5, 0.5, 0.5))
+            })
+            .collect();
+        let mut entries: Vec<(bbox::BBox, usize)> = boxes.iter().copied().zip(0..).collect();
+        let tree = FlatBvh::build(&mut entries, 4);
+
+        for _ in 0..100 {
+            let ray = ray::Ray::new(&point(), &(point() - point()), None);
+            let expected = boxes
+                .iter()
+                .enumerate()
+                .filter_map(|(index, bounds)| Some((bounds.clip(&ray, 0.0, f32::MAX)?.0, index)))
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, index)| index);
+            let mut closest = None;
+            tree.hit(&ray, 0.0, f32::MAX, |item, closest_t| {
+                let (bounds, index) = entries[item];
+                let (t, _) = bounds.clip(&ray, 0.0, closest_t)?;
+                closest = Some(index);
+                Some(t)
+            });
+            assert_eq!(closest, expected);
+        }
+    }
 }
@@ -1,10 +1,16 @@
 //! Pinhole camera with configurable lens blur and field of view.
+//!
+//! A thin lens may also disperse light: with `dispersion` set, red, green, and blue
+//! focus at slightly different distances, so out-of-focus highlights pick up colored
+//! fringes (longitudinal chromatic aberration). It's a three-channel approximation: each
+//! camera sample traces one channel through that channel's focus and counts three times
+//! toward it, which averages out to the full color.
 
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::core::ray;
-use crate::math::vec;
+use crate::core::{bbox, ray};
+use crate::math::{rng, vec};
 
 /// Parameters used to build a [`Camera`].
 #[derive(Debug, Clone, Copy)]
@@ -42,6 +48,17 @@ pub struct Camera {
     pub aperture: f32,
     pub vertical_fov: f32,
     pub aspect_ratio: f32,
+    /// Depth in front of the camera, along its view direction, nearer than which
+    /// geometry is cut away, e.g. to see into a room from outside its wall.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub near_clip: Option<f32>,
+    /// Depth beyond which geometry is hidden and the sky shows instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub far_clip: Option<f32>,
+    /// Fraction of the focus distance by which red focuses farther and blue nearer than
+    /// green, e.g. 0.02; only visible with an `aperture`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispersion: Option<f32>,
 }
 
 impl Camera {
@@ -88,11 +105,62 @@ impl Camera {
             lower_left_corner,
             horizontal,
             vertical,
+            near_clip: None,
+            far_clip: None,
+            dispersion: None,
         };
 
         camera
     }
 
+    /// Clips away geometry nearer than `near` or farther than `far` from the camera.
+    pub fn with_clipping(mut self, near: Option<f32>, far: Option<f32>) -> Self {
+        self.near_clip = near;
+        self.far_clip = far;
+        self
+    }
+
+    /// Makes the lens focus red `dispersion` times the focus distance farther than green,
+    /// and blue as much nearer.
+    pub fn with_dispersion(mut self, dispersion: f32) -> Self {
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    /// `ranges` with the camera range narrowed to the part of `ray`, one of this
+    /// camera's rays, between the near and far clip planes. The planes face the view
+    /// direction, so a cut is a straight section across the whole frame.
+    pub fn clip(&self, ray: &ray::Ray, ranges: &ray::RayRanges) -> ray::RayRanges {
+        let mut ranges = *ranges;
+        // Depth gained per unit of `t` along the ray.
+        let depth_rate = -ray.direction.dot(&self.w);
+        if depth_rate <= 0.0 {
+            return ranges;
+        }
+        if let Some(near) = self.near_clip {
+            ranges.camera.t_min = ranges.camera.t_min.max(near / depth_rate);
+        }
+        if let Some(far) = self.far_clip {
+            ranges.camera.t_max = ranges.camera.t_max.min(far / depth_rate);
+        }
+        ranges
+    }
+
+    /// Returns a copy of this camera with the frame widened or narrowed to `aspect_ratio`,
+    /// keeping its position, direction, and vertical field of view.
+    pub fn with_aspect_ratio(&self, aspect_ratio: f32) -> Camera {
+        let horizontal = self.u * (self.vertical.length() * aspect_ratio);
+        Camera {
+            aspect_ratio,
+            horizontal,
+            lower_left_corner: self.origin
+                - (horizontal / 2.0)
+                - (self.vertical / 2.0)
+                - self.w * self.focal_length,
+            ..self.clone()
+        }
+    }
+
     /// Re-aims the camera at a new target while preserving viewport size.
     pub fn look_at(&mut self, val: &vec::Vec3) {
         let w = (self.origin - *val).normalize();
@@ -108,8 +176,86 @@ impl Camera {
             self.origin - (self.horizontal / 2.0) - (self.vertical / 2.0) - w * self.focal_length;
     }
 
+    /// Returns a pinhole copy of this camera that keeps its viewing direction and field of
+    /// view but is re-aimed at the center of `bbox` and pulled back until the box's
+    /// bounding sphere fits inside the frame.
+    pub fn framing(&self, bbox: &bbox::BBox) -> Camera {
+        let min = vec::Vec3::new(bbox.x.min, bbox.y.min, bbox.z.min);
+        let max = vec::Vec3::new(bbox.x.max, bbox.y.max, bbox.z.max);
+        let center = (min + max) / 2.0;
+        let radius = ((max - min).length() / 2.0).max(1.0e-3);
+
+        let half_vertical = self.vertical_fov.to_radians() / 2.0;
+        let half_horizontal = (self.aspect_ratio * half_vertical.tan()).atan();
+        let distance = radius / half_vertical.min(half_horizontal).sin();
+
+        Camera::with_config(CameraConfig {
+            origin: center + self.w * distance,
+            look_at: center,
+            up: self.up,
+            aspect_ratio: self.aspect_ratio,
+            viewport_height: 2.0,
+            focal_length: distance,
+            aperture: 0.0,
+            vertical_fov: self.vertical_fov,
+        })
+    }
+
+    /// Ray from the lens center through normalized viewport coordinates (`u`, `v`),
+    /// without depth-of-field jitter or a random time.
+    pub fn center_ray(&self, u: f32, v: f32) -> ray::Ray {
+        let direction =
+            self.lower_left_corner + u * self.horizontal + v * self.vertical - self.origin;
+        ray::Ray::new(&self.origin, &direction, None)
+    }
+
+    /// Maps a world-space point to normalized viewport coordinates (`u`, `v`), the
+    /// inverse of [`Camera::center_ray`]. Returns `None` for points behind the camera.
+    pub fn project(&self, point: &vec::Vec3) -> Option<(f32, f32)> {
+        let direction = *point - self.origin;
+        let facing = direction.dot(&self.w);
+        if facing >= 0.0 {
+            return None;
+        }
+        let plane_distance = (self.lower_left_corner - self.origin).dot(&self.w);
+        let on_plane = self.origin + direction * (plane_distance / facing) - self.lower_left_corner;
+        Some((
+            on_plane.dot(&self.horizontal) / self.horizontal.squared_length(),
+            on_plane.dot(&self.vertical) / self.vertical.squared_length(),
+        ))
+    }
+
     /// Generates a ray through normalized viewport coordinates (`u`, `v`).
-    pub fn get_ray(&self, rng: &mut rand::rngs::ThreadRng, u: f32, v: f32) -> ray::Ray {
+    pub fn get_ray(&self, rng: &mut rng::SampleRng, u: f32, v: f32) -> ray::Ray {
+        self.focused_ray(rng, u, v, 1.0)
+    }
+
+    /// Generates a ray like [`Camera::get_ray`] together with the weight of its radiance
+    /// in the pixel. A dispersive lens traces a random channel through its own focus,
+    /// weighted three in that channel and zero in the others; otherwise the ray is
+    /// [`Camera::get_ray`]'s, weighted white.
+    pub fn get_weighted_ray(
+        &self,
+        rng: &mut rng::SampleRng,
+        u: f32,
+        v: f32,
+    ) -> (ray::Ray, vec::Vec3) {
+        let Some(dispersion) = self.dispersion else {
+            return (self.get_ray(rng, u, v), vec::Vec3::new(1.0, 1.0, 1.0));
+        };
+        let channel = rng.random_range(0..3);
+        let mut weight = [0.0; 3];
+        weight[channel] = 3.0;
+        let focus = 1.0 + dispersion * [1.0, 0.0, -1.0][channel];
+        (
+            self.focused_ray(rng, u, v, focus),
+            vec::Vec3::new(weight[0], weight[1], weight[2]),
+        )
+    }
+
+    /// A ray through (`u`, `v`) from a random point on the lens, converging `focus`
+    /// times as far away as the focus plane.
+    fn focused_ray(&self, rng: &mut rng::SampleRng, u: f32, v: f32, focus: f32) -> ray::Ray {
         let lens_radius = self.aperture / 2.0;
         let rd = lens_radius * vec::random_in_unit_disk(rng);
         let offset = self.u * rd.x + self.v * rd.y;
@@ -117,8 +263,9 @@ impl Camera {
 
         ray::Ray {
             origin: self.origin + offset,
-            direction: self.lower_left_corner + u * self.horizontal + v * self.vertical
-                - self.origin
+            direction: (self.lower_left_corner + u * self.horizontal + v * self.vertical
+                - self.origin)
+                * focus
                 - offset,
             time: ray_time,
         }
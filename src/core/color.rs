@@ -0,0 +1,160 @@
+//! Output color spaces.
+//!
+//! The renderer works in linear RGB with Rec. 709 (sRGB) primaries: texture colors,
+//! albedos, and emission are all read that way. A [`ColorSpace`] decides what happens on
+//! the way out, converting film colors to its primaries and applying its transfer curve
+//! before quantizing, and describing the result in the metadata of the files written
+//! (`cICP` and `cHRM` chunks in PNGs, the chromaticities attribute in OpenEXR), so
+//! wide-gamut and HDR displays show saturated colors that sRGB clips.
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::math::vec;
+
+/// CIE xy of the D65 white point shared by every supported space.
+const D65: (f32, f32) = (0.3127, 0.3290);
+
+/// Color space of encoded output, `color_space = "rec2020"` in scene files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorSpace {
+    /// Rec. 709 primaries with the renderer's historical gamma 2 (square root) curve. No
+    /// color metadata is written, so existing scenes render exactly as before.
+    #[default]
+    Srgb,
+    /// DCI-P3 primaries with a D65 white and the sRGB curve, as on Apple and most HDR
+    /// laptop and phone displays.
+    DisplayP3,
+    /// ITU-R BT.2020 primaries and transfer curve, the gamut of HDR video and UHD TVs.
+    Rec2020,
+}
+
+impl ColorSpace {
+    pub fn is_default(&self) -> bool {
+        *self == ColorSpace::default()
+    }
+
+    /// Converts a linear Rec. 709 color to this space's primaries, still linear.
+    pub fn from_rec709(&self, color: vec::Vec3) -> vec::Vec3 {
+        let rows = match self {
+            ColorSpace::Srgb => return color,
+            ColorSpace::DisplayP3 => [
+                [0.822_462, 0.177_538, 0.0],
+                [0.033_194, 0.966_806, 0.0],
+                [0.017_083, 0.072_397, 0.910_520],
+            ],
+            ColorSpace::Rec2020 => [
+                [0.627_404, 0.329_283, 0.043_313],
+                [0.069_097, 0.919_540, 0.011_362],
+                [0.016_392, 0.088_013, 0.895_595],
+            ],
+        };
+        let row = |r: [f32; 3]| r[0] * color.x + r[1] * color.y + r[2] * color.z;
+        vec::Vec3::new(row(rows[0]), row(rows[1]), row(rows[2]))
+    }
+
+    /// Converts a linear Rec. 709 color to this space and applies its transfer curve,
+    /// giving channels in `[0, 1]` ready to quantize.
+    pub fn encode(&self, color: vec::Vec3) -> vec::Vec3 {
+        let color = self.from_rec709(color);
+        let transfer = |value: f32| {
+            let value = value.clamp(0.0, 1.0);
+            match self {
+                ColorSpace::Srgb => value.sqrt(),
+                ColorSpace::DisplayP3 => {
+                    if value <= 0.003_130_8 {
+                        value * 12.92
+                    } else {
+                        1.055 * value.powf(1.0 / 2.4) - 0.055
+                    }
+                }
+                ColorSpace::Rec2020 => {
+                    if value < 0.018_054 {
+                        value * 4.5
+                    } else {
+                        1.099_297 * value.powf(0.45) - 0.099_297
+                    }
+                }
+            }
+        };
+        vec::Vec3::new(transfer(color.x), transfer(color.y), transfer(color.z))
+    }
+
+    /// CIE xy chromaticities of the red, green, and blue primaries and the white point.
+    pub fn chromaticities(&self) -> [(f32, f32); 4] {
+        match self {
+            ColorSpace::Srgb => [(0.64, 0.33), (0.30, 0.60), (0.15, 0.06), D65],
+            ColorSpace::DisplayP3 => [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060), D65],
+            ColorSpace::Rec2020 => [(0.708, 0.292), (0.170, 0.797), (0.131, 0.046), D65],
+        }
+    }
+
+    /// ITU-T H.273 code points for colour primaries, transfer characteristics, matrix
+    /// coefficients, and full range, as stored in a PNG `cICP` chunk. `None` for
+    /// [`ColorSpace::Srgb`], whose gamma 2 curve has no code point.
+    pub fn cicp(&self) -> Option<[u8; 4]> {
+        match self {
+            ColorSpace::Srgb => None,
+            ColorSpace::DisplayP3 => Some([12, 13, 0, 1]),
+            ColorSpace::Rec2020 => Some([9, 14, 0, 1]),
+        }
+    }
+
+    /// The chromaticities in the form OpenEXR headers store them.
+    pub fn exr_chromaticities(&self) -> exr::meta::attribute::Chromaticities {
+        let [red, green, blue, white] = self.chromaticities().map(|(x, y)| exr::math::Vec2(x, y));
+        exr::meta::attribute::Chromaticities {
+            red,
+            green,
+            blue,
+            white,
+        }
+    }
+}
+
+/// Luminance of a linear Rec. 709 color, the `Y` of CIE XYZ: how bright it looks rather
+/// than how much energy it carries.
+pub fn luminance(color: vec::Vec3) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+/// Writes 8-bit RGB `data` (as encoded by [`crate::core::render::Render::encode`]) to a
+/// PNG at `path`, tagged with `color_space`'s primaries and transfer curve.
+pub fn save_png(
+    path: &Path,
+    data: &[u8],
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+) -> image::ImageResult<()> {
+    let Some(cicp) = color_space.cicp() else {
+        return image::save_buffer(path, data, width, height, image::ColorType::Rgb8);
+    };
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let [red, green, blue, white] = color_space
+        .chromaticities()
+        .map(|(x, y)| (png::ScaledFloat::new(x), png::ScaledFloat::new(y)));
+    // For readers that predate cICP; those that understand it ignore cHRM.
+    encoder.set_source_chromaticities(png::SourceChromaticities {
+        white,
+        red,
+        green,
+        blue,
+    });
+    let to_image_error = |e: png::EncodingError| {
+        image::ImageError::Encoding(image::error::EncodingError::new(
+            image::ImageFormat::Png.into(),
+            e,
+        ))
+    };
+    let mut writer = encoder.write_header().map_err(to_image_error)?;
+    writer
+        .write_chunk(png::chunk::cICP, &cicp)
+        .map_err(to_image_error)?;
+    writer.write_image_data(data).map_err(to_image_error)?;
+    writer.finish().map_err(to_image_error)
+}
@@ -0,0 +1,212 @@
+//! Disk-backed film for renders too large to hold in memory.
+//!
+//! At wall-art resolutions the pixels alone can outgrow the machine's memory. A
+//! [`DiskFilm`] keeps them in a file mapped into memory instead, so the operating system
+//! pages rows in and out as needed. [`crate::raytrace_to_disk`] streams each tile into it
+//! as soon as the tile is finished, and [`DiskFilm::save_exr`] assembles the final image
+//! straight from the file.
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use memmap2::MmapMut;
+
+use crate::core::{color, film};
+use crate::math::vec;
+
+/// Linear RGB pixels stored in a file as raw little-endian floats, row by row.
+///
+/// Pixel `(0, 0)` is the bottom-left corner, like [`film::Film`]. Unlike a film, tiles
+/// are written whole, replacing what was there, rather than accumulated; the file keeps
+/// its contents after the film is dropped, so a render can be assembled later with
+/// [`DiskFilm::open`].
+pub struct DiskFilm {
+    pub width: u32,
+    pub height: u32,
+    precision: film::FilmPrecision,
+    path: PathBuf,
+    map: Mutex<MmapMut>,
+}
+
+impl DiskFilm {
+    /// Creates (or truncates) a black film backed by the file at `path`, storing each
+    /// channel at `precision`.
+    pub fn create(
+        path: &Path,
+        width: u32,
+        height: u32,
+        precision: film::FilmPrecision,
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(file_size(width, height, precision))?;
+        DiskFilm::map(file, path, width, height, precision)
+    }
+
+    /// Reopens a film file written earlier with the same size and precision.
+    pub fn open(
+        path: &Path,
+        width: u32,
+        height: u32,
+        precision: film::FilmPrecision,
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let expected = file_size(width, height, precision);
+        let actual = file.metadata()?.len();
+        if actual != expected {
+            return Err(io::Error::other(format!(
+                "{} holds {} bytes, but a {}x{} film needs {}",
+                path.display(),
+                actual,
+                width,
+                height,
+                expected
+            )));
+        }
+        DiskFilm::map(file, path, width, height, precision)
+    }
+
+    fn map(
+        file: File,
+        path: &Path,
+        width: u32,
+        height: u32,
+        precision: film::FilmPrecision,
+    ) -> io::Result<Self> {
+        // Safety: the film owns the mapping; nothing else is expected to resize or write
+        // the file while it's open.
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        Ok(DiskFilm {
+            width,
+            height,
+            precision,
+            path: path.to_path_buf(),
+            map: Mutex::new(map),
+        })
+    }
+
+    pub fn precision(&self) -> film::FilmPrecision {
+        self.precision
+    }
+
+    /// The backing file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stores a finished tile: `pixels` are row-major from `(x_start, y_start)`,
+    /// `tile_width` to a row.
+    pub fn write_tile(&self, x_start: u32, y_start: u32, tile_width: u32, pixels: &[vec::Vec3]) {
+        let pixel_size = pixel_size(self.precision);
+        let mut row_bytes = Vec::with_capacity(tile_width as usize * pixel_size);
+        let mut map = self.map.lock().expect("disk film lock poisoned");
+        for (row, colors) in pixels.chunks(tile_width.max(1) as usize).enumerate() {
+            row_bytes.clear();
+            for color in colors {
+                encode(color, self.precision, &mut row_bytes);
+            }
+            let offset = self.offset(x_start, y_start + row as u32);
+            map[offset..offset + row_bytes.len()].copy_from_slice(&row_bytes);
+        }
+    }
+
+    /// Returns the color stored at `(x, y)`.
+    pub fn pixel(&self, x: u32, y: u32) -> vec::Vec3 {
+        let map = self.map.lock().expect("disk film lock poisoned");
+        decode(&map[self.offset(x, y)..], self.precision)
+    }
+
+    /// Writes pending changes through to the backing file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.map.lock().expect("disk film lock poisoned").flush()
+    }
+
+    /// Writes the film to an OpenEXR file with channels of its precision, reading the
+    /// pixels from the backing file as they're encoded. Colors stay linear but are
+    /// converted to `color_space`'s primaries, which the file's chromaticities record.
+    pub fn save_exr(&self, path: &Path, color_space: color::ColorSpace) -> io::Result<()> {
+        use exr::prelude::*;
+
+        let map = self.map.lock().expect("disk film lock poisoned");
+        let bytes: &[u8] = &map;
+        let (width, height) = (self.width as usize, self.height as usize);
+        // EXR rows run top-down.
+        let color = |Vec2(x, y): Vec2<usize>| {
+            color_space.from_rec709(decode(
+                &bytes[self.offset(x as u32, (height - 1 - y) as u32)..],
+                self.precision,
+            ))
+        };
+        let chromaticities = Some(color_space.exr_chromaticities());
+        let result = match self.precision {
+            film::FilmPrecision::Full => {
+                let channels = SpecificChannels::rgb(|position| {
+                    let color = color(position);
+                    (color.x, color.y, color.z)
+                });
+                let mut image = Image::from_channels((width, height), channels);
+                image.attributes.chromaticities = chromaticities;
+                image.write().to_file(path)
+            }
+            film::FilmPrecision::Half => {
+                let channels = SpecificChannels::rgb(|position| {
+                    let color = color(position);
+                    (
+                        half::f16::from_f32(color.x),
+                        half::f16::from_f32(color.y),
+                        half::f16::from_f32(color.z),
+                    )
+                });
+                let mut image = Image::from_channels((width, height), channels);
+                image.attributes.chromaticities = chromaticities;
+                image.write().to_file(path)
+            }
+        };
+        result.map_err(io::Error::other)
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * pixel_size(self.precision)
+    }
+}
+
+fn pixel_size(precision: film::FilmPrecision) -> usize {
+    match precision {
+        film::FilmPrecision::Full => 3 * size_of::<f32>(),
+        film::FilmPrecision::Half => 3 * size_of::<half::f16>(),
+    }
+}
+
+fn file_size(width: u32, height: u32, precision: film::FilmPrecision) -> u64 {
+    width as u64 * height as u64 * pixel_size(precision) as u64
+}
+
+fn encode(color: &vec::Vec3, precision: film::FilmPrecision, bytes: &mut Vec<u8>) {
+    for value in [color.x, color.y, color.z] {
+        match precision {
+            film::FilmPrecision::Full => bytes.extend_from_slice(&value.to_le_bytes()),
+            film::FilmPrecision::Half => {
+                bytes.extend_from_slice(&half::f16::from_f32(value).to_le_bytes())
+            }
+        }
+    }
+}
+
+fn decode(bytes: &[u8], precision: film::FilmPrecision) -> vec::Vec3 {
+    let channel = |index: usize| match precision {
+        film::FilmPrecision::Full => {
+            let start = index * 4;
+            f32::from_le_bytes(bytes[start..start + 4].try_into().unwrap())
+        }
+        film::FilmPrecision::Half => {
+            let start = index * 2;
+            half::f16::from_le_bytes(bytes[start..start + 2].try_into().unwrap()).to_f32()
+        }
+    };
+    vec::Vec3::new(channel(0), channel(1), channel(2))
+}
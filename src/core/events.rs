@@ -0,0 +1,87 @@
+//! Machine-readable render progress.
+//!
+//! A render with an [`EventSink`] in [`crate::core::render::Render::events`] reports each
+//! finished tile and progressive pass to it as a [`RenderEvent`], from whichever worker
+//! thread finished it. The `rustray` binary's `--json-progress` flag installs
+//! [`json_lines`], which writes every event to stdout as one line of JSON, e.g.
+//! `{"event":"tile_done","x_start":0,"x_end":64,"y_start":0,"y_end":64,"samples":16}`, so
+//! GUIs and CI wrappers can follow a render without parsing its human-readable output.
+use std::io::Write;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::core::overlay;
+
+/// Receives render events; called concurrently from worker threads.
+pub type EventSink = Arc<dyn Fn(&RenderEvent) + Send + Sync>;
+
+/// Something that happened during a render, tagged by `event` in its JSON form.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RenderEvent {
+    /// Rendering is about to begin, after the scene has loaded.
+    Started {
+        width: u32,
+        height: u32,
+        /// Samples per pixel asked for; progressive renders may stop tiles sooner.
+        samples: u32,
+        depth: u32,
+    },
+    /// A tile (or row chunk) of the image has been rendered. Tiles of one pass never
+    /// overlap, so their areas add up to the progress through it.
+    TileDone {
+        x_start: u32,
+        x_end: u32,
+        y_start: u32,
+        y_end: u32,
+        /// Samples per pixel the tile has now.
+        samples: u32,
+    },
+    /// A progressive pass has finished.
+    PassDone {
+        /// Passes run so far, counting from 1.
+        pass: u32,
+        /// Samples per pixel averaged over the image.
+        samples_per_pixel: f32,
+        /// Tiles that still want samples.
+        tiles_remaining: usize,
+    },
+    /// The image and any extra outputs are saved; the stats cover the main render.
+    Finished {
+        /// Whether the render was stopped early and the image is partial.
+        interrupted: bool,
+        samples_per_pixel: f32,
+        elapsed_seconds: f64,
+        rays: u64,
+        rays_per_second: f64,
+    },
+}
+
+impl RenderEvent {
+    /// The `finished` event for a render that cost `stats`.
+    pub fn finished(stats: &overlay::RenderStats, interrupted: bool) -> Self {
+        RenderEvent::Finished {
+            interrupted,
+            samples_per_pixel: stats.samples,
+            elapsed_seconds: stats.elapsed.as_secs_f64(),
+            rays: stats.rays,
+            rays_per_second: stats.rays_per_second(),
+        }
+    }
+}
+
+/// A sink writing each event to stdout as a line of JSON, flushed immediately so readers
+/// see it as it happens.
+pub fn json_lines() -> EventSink {
+    Arc::new(|event| {
+        let Ok(mut line) = serde_json::to_string(event) else {
+            return;
+        };
+        line.push('\n');
+        let mut stdout = std::io::stdout().lock();
+        // A closed pipe shouldn't stop the render.
+        let _ = stdout.write_all(line.as_bytes());
+        let _ = stdout.flush();
+    })
+}
@@ -0,0 +1,229 @@
+//! HDR film that accumulates linear radiance before 8-bit output.
+use std::sync::atomic::{AtomicU16, AtomicU32, Ordering};
+
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::core::color;
+use crate::math::vec;
+
+/// Precision a [`Film`] stores its pixels at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilmPrecision {
+    /// 32-bit floats, 12 bytes per pixel.
+    #[default]
+    Full,
+    /// 16-bit half floats, 6 bytes per pixel: half the memory for very large images
+    /// (a 16K panorama needs about 800 MB instead of 1.6 GB), with about three
+    /// significant digits per channel.
+    Half,
+}
+
+impl FilmPrecision {
+    pub fn is_full(&self) -> bool {
+        *self == FilmPrecision::Full
+    }
+}
+
+enum Pixels {
+    Full(Vec<[AtomicU32; 3]>),
+    Half(Vec<[AtomicU16; 3]>),
+}
+
+/// Linear RGB accumulation buffer for a rendered image.
+///
+/// Pixel `(0, 0)` is the bottom-left corner, matching the camera's `(u, v)`
+/// convention. Splatting takes `&self` and adds atomically, so one film can be
+/// shared across worker threads by techniques that write to arbitrary pixels
+/// (light tracing, bidirectional connections to the camera).
+///
+/// Half-precision films round every splat, so renderers accumulate each tile's samples
+/// in 32-bit buffers and splat each finished pixel once; techniques that splat many
+/// small contributions into one pixel should use a full-precision film.
+pub struct Film {
+    pub width: u32,
+    pub height: u32,
+    pixels: Pixels,
+}
+
+impl Film {
+    /// Creates a black full-precision film of the given size.
+    pub fn new(width: u32, height: u32) -> Self {
+        Film::with_precision(width, height, FilmPrecision::Full)
+    }
+
+    /// Creates a black film of the given size that stores pixels at `precision`.
+    pub fn with_precision(width: u32, height: u32, precision: FilmPrecision) -> Self {
+        let count = width as usize * height as usize;
+        let pixels = match precision {
+            FilmPrecision::Full => Pixels::Full(
+                (0..count)
+                    .map(|_| [AtomicU32::new(0), AtomicU32::new(0), AtomicU32::new(0)])
+                    .collect(),
+            ),
+            FilmPrecision::Half => Pixels::Half(
+                (0..count)
+                    .map(|_| [AtomicU16::new(0), AtomicU16::new(0), AtomicU16::new(0)])
+                    .collect(),
+            ),
+        };
+        Film {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn precision(&self) -> FilmPrecision {
+        match self.pixels {
+            Pixels::Full(_) => FilmPrecision::Full,
+            Pixels::Half(_) => FilmPrecision::Half,
+        }
+    }
+
+    /// Adds `color` to the pixel at `(x, y)`; out-of-range pixels are ignored.
+    pub fn splat(&self, x: u32, y: u32, color: vec::Vec3) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index = (y * self.width + x) as usize;
+        let values = [color.x, color.y, color.z];
+        match &self.pixels {
+            Pixels::Full(pixels) => {
+                for (channel, value) in pixels[index].iter().zip(values) {
+                    if value != 0.0 {
+                        atomic_add(channel, value);
+                    }
+                }
+            }
+            Pixels::Half(pixels) => {
+                for (channel, value) in pixels[index].iter().zip(values) {
+                    if value != 0.0 {
+                        atomic_add_half(channel, value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds `color` to the pixel containing the film coordinates `(u, v)` in `[0, 1)`.
+    pub fn splat_uv(&self, u: f32, v: f32, color: vec::Vec3) {
+        if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+            return;
+        }
+        let x = (u * self.width as f32) as u32;
+        let y = (v * self.height as f32) as u32;
+        self.splat(x, y, color);
+    }
+
+    /// Returns the accumulated linear color of the pixel at `(x, y)`.
+    pub fn pixel(&self, x: u32, y: u32) -> vec::Vec3 {
+        let index = (y * self.width + x) as usize;
+        let [r, g, b] = match &self.pixels {
+            Pixels::Full(pixels) => pixels[index]
+                .each_ref()
+                .map(|c| f32::from_bits(c.load(Ordering::Relaxed))),
+            Pixels::Half(pixels) => pixels[index]
+                .each_ref()
+                .map(|c| half::f16::from_bits(c.load(Ordering::Relaxed)).to_f32()),
+        };
+        vec::Vec3::new(r, g, b)
+    }
+
+    /// Copies the linear film to a top-down float image, e.g. for saving as OpenEXR.
+    pub fn to_rgb32f(&self) -> image::Rgb32FImage {
+        image::Rgb32FImage::from_fn(self.width, self.height, |x, y| {
+            let col = self.pixel(x, self.height - 1 - y);
+            image::Rgb([col.x, col.y, col.z])
+        })
+    }
+
+    /// Gamma-corrects and quantizes the film to a top-down RGB8 buffer.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.to_rgb8_scaled(1.0, color::ColorSpace::default())
+    }
+
+    /// Like [`Film::to_rgb8`], but multiplies every pixel by `scale` first (an exposure)
+    /// and encodes it in `color_space`.
+    pub fn to_rgb8_scaled(&self, scale: f32, color_space: color::ColorSpace) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.width as usize * self.height as usize * 3);
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let col = color_space.encode(self.pixel(x, y) * scale);
+                data.push((col.x * 255.99) as u8);
+                data.push((col.y * 255.99) as u8);
+                data.push((col.z * 255.99) as u8);
+            }
+        }
+        data
+    }
+
+    /// Like [`Film::to_rgb8_scaled`], but diffuses each pixel's quantization error onto its
+    /// not-yet-quantized neighbours (serpentine Floyd–Steinberg) so smooth gradients
+    /// such as skies don't band.
+    ///
+    /// The split between the horizontal and vertical diffusion weights is jittered per
+    /// pixel from `seed`, which pushes the error toward blue noise instead of the regular
+    /// worm patterns plain error diffusion leaves in flat regions, while keeping the
+    /// output reproducible.
+    pub fn to_rgb8_dithered(
+        &self,
+        seed: u64,
+        scale: f32,
+        color_space: color::ColorSpace,
+    ) -> Vec<u8> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let width = self.width as usize;
+        let mut data = vec![0_u8; width * self.height as usize * 3];
+        // Padded by one pixel on each side so the kernel never needs bounds checks.
+        let mut current = vec![vec::Vec3::default(); width + 2];
+        let mut below = vec![vec::Vec3::default(); width + 2];
+
+        for (row, y) in (0..self.height).rev().enumerate() {
+            let forward = row % 2 == 0;
+            for step in 0..width {
+                let x = if forward { step } else { width - 1 - step };
+                let value: vec::Vec3 =
+                    color_space.encode(self.pixel(x as u32, y) * scale) * 255.0 + current[x + 1];
+                // Clamp before measuring the error so blown-out pixels don't smear into
+                // their neighbours.
+                let value = vec::Vec3::new(
+                    value.x.clamp(0.0, 255.0),
+                    value.y.clamp(0.0, 255.0),
+                    value.z.clamp(0.0, 255.0),
+                );
+                let quantized = vec::Vec3::new(value.x.round(), value.y.round(), value.z.round());
+                let offset = (row * width + x) * 3;
+                data[offset] = quantized.x as u8;
+                data[offset + 1] = quantized.y as u8;
+                data[offset + 2] = quantized.z as u8;
+
+                let error = value - quantized;
+                let jitter: f32 = rng.random_range(-1.0..1.0);
+                let (ahead, behind) = if forward { (x + 2, x) } else { (x, x + 2) };
+                current[ahead] = current[ahead] + error * ((7.0 + jitter) / 16.0);
+                below[behind] = below[behind] + error * (3.0 / 16.0);
+                below[x + 1] = below[x + 1] + error * ((5.0 - jitter) / 16.0);
+                below[ahead] = below[ahead] + error * (1.0 / 16.0);
+            }
+            std::mem::swap(&mut current, &mut below);
+            below.fill(vec::Vec3::default());
+        }
+        data
+    }
+}
+
+fn atomic_add(channel: &AtomicU32, value: f32) {
+    // Infallible: the closure always returns `Some`.
+    let _ = channel.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+        Some((f32::from_bits(bits) + value).to_bits())
+    });
+}
+
+fn atomic_add_half(channel: &AtomicU16, value: f32) {
+    // Infallible: the closure always returns `Some`.
+    let _ = channel.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+        Some(half::f16::from_f32(half::f16::from_bits(bits).to_f32() + value).to_bits())
+    });
+}
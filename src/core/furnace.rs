@@ -0,0 +1,210 @@
+//! White-furnace test of a material's energy balance.
+//!
+//! A sphere made of the material sits inside an environment of uniform radiance 1, and
+//! paths are traced at it from every direction. Whatever comes back is light the surface
+//! reflected or transmitted, so the average over all paths is the material's albedo: a
+//! Lambertian surface should return its color, a mirror its tint, clear glass exactly 1.
+//! More than expected means the material creates energy, less that it loses some, which
+//! makes this a quick check for mistakes in a new BRDF's sampling or weights.
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::core::{object, path, ray, scene, world};
+use crate::geometry::instance::GeometryInstance;
+use crate::geometry::primitives::sphere;
+use crate::materials::instance::MaterialInstance;
+use crate::materials::{dielectric, lambertian, metallic, velvet};
+use crate::math::{rng, vec};
+use crate::textures::color;
+use crate::traits::scatterable::Scatterable;
+
+/// How far from the sphere's center paths start, in radii.
+const START_DISTANCE: f32 = 4.0;
+
+/// Paths traced by each parallel task.
+const PATHS_PER_TASK: u64 = 4096;
+
+/// Ray time the test is taken at.
+const FURNACE_TIME: f64 = 0.0;
+
+/// How many paths to trace and how far to follow them.
+#[derive(Clone, Copy, Debug)]
+pub struct FurnaceSettings {
+    pub samples: u64,
+    /// Bounce limit per path; set well above a render's so glass isn't cut short.
+    pub depth: u32,
+    /// Seed for the path directions; the same seed reproduces the same measurement.
+    pub seed: u64,
+}
+
+impl Default for FurnaceSettings {
+    /// 65536 paths of up to 64 bounces.
+    fn default() -> Self {
+        FurnaceSettings {
+            samples: 1 << 16,
+            depth: 64,
+            seed: 0,
+        }
+    }
+}
+
+/// The albedo a furnace test measured, alongside what the material should reflect.
+#[derive(Clone, Copy, Debug)]
+pub struct FurnaceReport {
+    pub measured: vec::Vec3,
+    /// The material's albedo, when it's known (see [`expected_albedo`]).
+    pub expected: Option<vec::Vec3>,
+    /// Standard error of the mean of `measured`, averaged over the channels.
+    pub standard_error: f32,
+}
+
+impl FurnaceReport {
+    /// `measured - expected` per channel: positive where the material gains energy,
+    /// negative where it loses some. `None` without an expected albedo.
+    pub fn error(&self) -> Option<vec::Vec3> {
+        self.expected.map(|expected| self.measured - expected)
+    }
+
+    /// Whether every channel is within `tolerance` of the expected albedo, allowing four
+    /// standard errors on top for sampling noise. Passes if nothing is expected.
+    pub fn passed(&self, tolerance: f32) -> bool {
+        let Some(error) = self.error() else {
+            return true;
+        };
+        let allowed = tolerance + 4.0 * self.standard_error;
+        (0..3).all(|channel| error[channel].abs() <= allowed)
+    }
+}
+
+/// Measures `material`'s albedo in a white furnace, expecting [`expected_albedo`].
+pub fn test(
+    material: Arc<dyn Scatterable + Send + Sync>,
+    settings: &FurnaceSettings,
+) -> FurnaceReport {
+    let expected = expected_albedo(material.as_ref());
+    let mut scene = scene::Scene::new();
+    let sphere = sphere::Sphere::new(&vec::Vec3::new(0.0, 0.0, 0.0), 1.0);
+    scene.add_object(Box::new(object::RenderObject {
+        geometry_instance: GeometryInstance::new(Arc::new(sphere)),
+        material_instance: MaterialInstance::new(material),
+    }));
+    let white = vec::Vec3::new(1.0, 1.0, 1.0);
+    scene.add_object(Box::new(world::World::new(&white, &white)));
+    scene.build_bvh(&mut rand::rng());
+
+    let samples = settings.samples.max(1);
+    let (sum, sum_squared) = (0..samples.div_ceil(PATHS_PER_TASK))
+        .into_par_iter()
+        .map(|task| {
+            let mut rng = rng::hashed_rng(settings.seed ^ task.rotate_left(32));
+            let count = PATHS_PER_TASK.min(samples - task * PATHS_PER_TASK);
+            let mut sum = vec::Vec3::new(0.0, 0.0, 0.0);
+            let mut sum_squared = 0.0_f64;
+            for _ in 0..count {
+                let ray = furnace_ray(&mut rng);
+                let radiance = crate::trace_ray(
+                    &mut rng,
+                    &scene,
+                    &ray,
+                    &mut path::PathState::new(settings.depth),
+                );
+                sum = sum + radiance;
+                let mean = (radiance.x + radiance.y + radiance.z) as f64 / 3.0;
+                sum_squared += mean * mean;
+            }
+            (sum, sum_squared)
+        })
+        .reduce(
+            || (vec::Vec3::new(0.0, 0.0, 0.0), 0.0),
+            |(a, a_squared), (b, b_squared)| (a + b, a_squared + b_squared),
+        );
+
+    let measured = sum / samples as f32;
+    let mean = (measured.x + measured.y + measured.z) as f64 / 3.0;
+    let variance = (sum_squared / samples as f64 - mean * mean).max(0.0);
+    FurnaceReport {
+        measured,
+        expected,
+        standard_error: (variance / samples as f64).sqrt() as f32,
+    }
+}
+
+/// A ray at a uniformly random point of the unit sphere's silhouette, seen from a
+/// uniformly random direction, so every patch of surface is lit from every side in
+/// proportion to its projected area.
+fn furnace_ray(rng: &mut rng::SampleRng) -> ray::Ray {
+    let direction = vec::unit_vector(&vec::random_in_unit_sphere(rng));
+    let frame = crate::math::onb::ONB::build_from_w(&direction);
+    let disk = vec::random_in_unit_disk(rng);
+    let target = frame.u * disk.x + frame.v * disk.y;
+    let origin = target - direction * START_DISTANCE;
+    ray::Ray::new(&origin, &direction, Some(FURNACE_TIME))
+}
+
+/// What an energy-conserving `material` should reflect in a white furnace: the color of
+/// a solid-colored Lambertian surface, the tint of a metal, the color of velvet with a
+/// sheen of the same color, and all of the light for clear glass. `None` for textured or
+/// emissive materials and anything else without a single albedo.
+pub fn expected_albedo(material: &dyn Scatterable) -> Option<vec::Vec3> {
+    let material = material.as_any();
+    if let Some(lambert) = material.downcast_ref::<lambertian::Lambertian>() {
+        return lambert
+            .texture
+            .as_any()
+            .downcast_ref::<color::ColorTexture>()
+            .map(|texture| texture.albedo);
+    }
+    if let Some(metal) = material.downcast_ref::<metallic::Metallic>() {
+        return metal.texture.is_none().then_some(metal.albedo);
+    }
+    if let Some(cloth) = material.downcast_ref::<velvet::Velvet>() {
+        // The sheen reflects a view-dependent share and the base the rest, so only
+        // matching colors give a single albedo.
+        return (0..3)
+            .all(|channel| cloth.sheen[channel] == cloth.color[channel])
+            .then_some(cloth.color);
+    }
+    if let Some(glass) = material.downcast_ref::<dielectric::Dielectric>() {
+        // Tinted or absorbing glass keeps a path-dependent share of the light.
+        return (!glass.is_tinted() && glass.absorption.is_none())
+            .then(|| vec::Vec3::new(1.0, 1.0, 1.0));
+    }
+    None
+}
+
+/// The built-in materials in a few representative configurations, each with a name to
+/// report it by.
+pub fn reference_materials() -> Vec<(String, Arc<dyn Scatterable + Send + Sync>)> {
+    let gray = vec::Vec3::new(0.5, 0.5, 0.5);
+    let gold = vec::Vec3::new(1.0, 0.78, 0.34);
+    let mut materials: Vec<(String, Arc<dyn Scatterable + Send + Sync>)> = vec![(
+        String::from("Lambertian 0.5"),
+        Arc::new(lambertian::Lambertian::new(Box::new(
+            color::ColorTexture::new(gray),
+        ))),
+    )];
+    for roughness in [0.0, 0.5, 1.0] {
+        materials.push((
+            format!("Metallic roughness {}", roughness),
+            Arc::new(metallic::Metallic::new(&gold, roughness)),
+        ));
+    }
+    for roughness in [0.25, 0.5, 1.0] {
+        materials.push((
+            format!("Metallic GGX roughness {}", roughness),
+            Arc::new(
+                metallic::Metallic::new(&gold, roughness).with_model(metallic::MetalModel::Ggx),
+            ),
+        ));
+    }
+    materials.push((
+        String::from("Velvet 0.5"),
+        Arc::new(velvet::Velvet::new(&gray).with_sheen(&gray, 0.5)),
+    ));
+    materials.push((
+        String::from("Dielectric 1.5"),
+        Arc::new(dielectric::Dielectric::new(1.5)),
+    ));
+    materials
+}
@@ -0,0 +1,671 @@
+//! Random scene generation for robustness testing.
+//!
+//! [`generate_scene`] builds valid scene files that lean on edge cases: degenerate
+//! and mirrored transforms, tiny and huge coordinates, degenerate quads and cubes,
+//! and overlapping volumes. [`check_scene`] renders one at low resolution and
+//! reports panics or non-finite pixels.
+use std::panic;
+
+use rand::Rng;
+
+use crate::core::scene_file::{
+    GeometryEntry, GeometryTemplate, GodRayInstance, MaterialEntry, MaterialTemplate,
+    NormalMapTemplate, ObjectInstance, ScatterInstance, SceneFile, SceneFileError, SectionSettings,
+    TextureTemplate, VolumeInstance,
+};
+use crate::core::{camera, film, ray, shutter, world};
+use crate::geometry::primitives::{cube, curves, disk, heightfield, quad, sdf, sphere};
+use crate::geometry::{cutout, generators, god_rays, scatter, section, transform};
+use crate::materials::{
+    anisotropic, blinn_phong, conductor, dielectric, metallic, plastic, principled, toon, velvet,
+};
+use crate::math::{mat, rng, vec};
+use crate::textures::{checker, color};
+
+/// Coordinate magnitudes a generated scene is built at.
+const SCALES: [f32; 5] = [1.0e-3, 0.1, 1.0, 1.0e3, 1.0e5];
+
+/// Why a generated scene failed its check.
+#[derive(Debug)]
+pub enum FuzzFailure {
+    /// The scene file did not convert into a render.
+    Load(SceneFileError),
+    /// Loading or rendering panicked.
+    Panic(String),
+    /// A pixel came out NaN or infinite.
+    NonFinite { x: u32, y: u32, value: vec::Vec3 },
+}
+
+impl std::fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FuzzFailure::Load(err) => write!(f, "scene failed to load: {}", err),
+            FuzzFailure::Panic(message) => write!(f, "panicked: {}", message),
+            FuzzFailure::NonFinite { x, y, value } => {
+                write!(f, "non-finite pixel at ({}, {}): {:?}", x, y, value)
+            }
+        }
+    }
+}
+
+/// Generates a random scene file rendered at a tiny resolution and sample count.
+pub fn generate_scene<R: Rng>(rng: &mut R) -> SceneFile {
+    let scale = SCALES[rng.random_range(0..SCALES.len())];
+
+    let mut materials: Vec<MaterialEntry> = (0..rng.random_range(1..6))
+        .map(|id| MaterialEntry {
+            id,
+            max_bounces: rng.random_bool(0.2).then(|| rng.random_range(0..8)),
+            normal_map: rng.random_bool(0.1).then(|| NormalMapTemplate {
+                texture: random_texture(rng),
+                strength: rng.random_range(0.0..2.0),
+            }),
+            material: random_material(rng),
+        })
+        .collect();
+    let mut geometries: Vec<GeometryEntry> = (0..rng.random_range(1..8))
+        .map(|id| GeometryEntry {
+            id,
+            geometry: random_geometry(rng, scale),
+        })
+        .collect();
+
+    let mut objects: Vec<ObjectInstance> = (0..rng.random_range(1..12))
+        .map(|_| ObjectInstance {
+            geometry: rng.random_range(0..geometries.len()),
+            material: rng.random_range(0..materials.len()),
+            transforms: (0..rng.random_range(0..3))
+                .map(|_| random_transform(rng, scale))
+                .collect(),
+            albedo: rng.random_bool(0.2).then(|| random_color(rng)),
+            slot_materials: Vec::new(),
+            light_group: None,
+            cutout: rng.random_bool(0.1).then(|| random_cutout(rng, scale)),
+            motion_blur: rng.random_bool(0.8),
+            smooth_shading: rng.random_bool(0.8),
+        })
+        .collect();
+
+    // Occasionally strew small copies of a geometry over one of the objects.
+    let scatters = if rng.random_bool(0.15) {
+        let smallest = 10.0_f32.powf(rng.random_range(-2.0..0.0));
+        vec![ScatterInstance {
+            geometry: rng.random_range(0..geometries.len()),
+            material: rng.random_range(0..materials.len()),
+            surface: rng.random_range(0..objects.len()),
+            density: rng.random_bool(0.3).then(|| random_texture(rng)),
+            settings: scatter::ScatterSettings {
+                count: rng.random_range(0..20),
+                seed: random_seed(rng),
+                scale: [smallest, smallest * rng.random_range(1.0..3.0)],
+                spin: rng.random_range(0.0..360.0),
+                tilt: rng.random_range(0.0..90.0),
+                align: rng.random_bool(0.7),
+            },
+        }]
+    } else {
+        Vec::new()
+    };
+
+    let god_rays = if rng.random_bool(0.15) {
+        vec![GodRayInstance {
+            light: rng.random_range(0..objects.len()),
+            phase_function: rng.random_range(0..materials.len()),
+            density: 10.0_f32.powf(rng.random_range(-3.0..1.0)) / scale,
+            settings: god_rays::BeamSettings {
+                length: random_extent(rng, scale),
+                angle: rng.random_range(0.0..90.0),
+                direction: Some(random_point(rng, 1.0)),
+                segments: rng.random_range(0..40),
+            },
+        }]
+    } else {
+        Vec::new()
+    };
+
+    if rng.random_bool(0.3) {
+        let mut sky = world::World::new(&random_color(rng), &random_color(rng));
+        if rng.random_bool(0.3) {
+            sky = sky.with_haze(world::Haze {
+                color: random_color(rng),
+                height: rng.random_range(0.0..0.5),
+            });
+        }
+        if rng.random_bool(0.3) {
+            sky = sky.with_stars(world::Stars {
+                brightness: rng.random_range(0.0..20.0),
+                density: rng.random(),
+                cells: rng.random_range(0..1024),
+                seed: random_seed(rng),
+            });
+        }
+        if rng.random_bool(0.3) {
+            sky = sky.with_sun(world::Sun {
+                direction: random_color(rng) * 2.0 - vec::Vec3::new(1.0, 1.0, 1.0),
+                color: random_color(rng) * rng.random_range(0.0..100.0),
+                angular_radius: rng.random_range(0.0..10.0),
+                glow: rng.random(),
+                glow_width: rng.random_range(0.0..30.0),
+            });
+        }
+        let id = geometries.len();
+        geometries.push(GeometryEntry {
+            id,
+            geometry: GeometryTemplate::World(sky),
+        });
+        materials.push(MaterialEntry {
+            id: materials.len(),
+            max_bounces: None,
+            normal_map: None,
+            material: MaterialTemplate::World(sky),
+        });
+        objects.push(ObjectInstance {
+            geometry: id,
+            material: materials.len() - 1,
+            transforms: Vec::new(),
+            albedo: None,
+            slot_materials: Vec::new(),
+            light_group: None,
+            cutout: None,
+            motion_blur: true,
+            smooth_shading: true,
+        });
+    }
+
+    // Volumes share one phase function and frequently overlap each other and objects.
+    let volumes = if rng.random_bool(0.4) {
+        let phase_function = materials.len();
+        materials.push(MaterialEntry {
+            id: phase_function,
+            max_bounces: None,
+            normal_map: None,
+            material: MaterialTemplate::Isotropic {
+                texture: TextureTemplate::Color(color::ColorTexture::new(random_color(rng))),
+            },
+        });
+        (0..rng.random_range(1..4))
+            .map(|_| VolumeInstance {
+                boundary_geometry: rng.random_range(0..geometries.len()),
+                phase_function,
+                density: 10.0_f32.powf(rng.random_range(-3.0..2.0)) / scale,
+                boundary_transforms: (0..rng.random_range(0..2))
+                    .map(|_| random_transform(rng, scale))
+                    .collect(),
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // Occasionally slice the scene open, sometimes with planes facing each other.
+    let section = rng.random_bool(0.15).then(|| SectionSettings {
+        planes: (0..rng.random_range(1..3))
+            .map(|_| section::SectionPlane {
+                point: random_point(rng, scale),
+                normal: random_point(rng, 1.0),
+            })
+            .collect(),
+        cap: rng
+            .random_bool(0.5)
+            .then(|| rng.random_range(0..materials.len())),
+    });
+
+    SceneFile {
+        width: 32,
+        height: None,
+        samples: Some(4),
+        depth: Some(8),
+        dither: false,
+        seed: 0,
+        preset: None,
+        presets: Default::default(),
+        depth_range: None,
+        film_precision: film::FilmPrecision::default(),
+        rng: rng::RngAlgorithm::default(),
+        // Occasionally a shifted or vanishingly short shutter.
+        shutter: if rng.random_bool(0.2) {
+            let open = rng.random_range(-1.0..1.0);
+            shutter::Shutter::new(
+                open,
+                open + rng.random_range(0.0..2.0) * rng.random::<f64>(),
+            )
+        } else {
+            shutter::Shutter::default()
+        },
+        frame_noise: shutter::FrameNoise::default(),
+        exposure: None,
+        color_space: Default::default(),
+        stats_overlay: false,
+        sky_only: rng.random_bool(0.1),
+        rays: ray::RayRanges::default(),
+        section,
+        camera: random_camera(rng, scale),
+        geometries,
+        materials,
+        objects,
+        volumes,
+        scatters,
+        god_rays,
+    }
+}
+
+/// Builds and renders a scene file, failing on load errors, panics, or NaN/infinite
+/// pixels. Panics are only caught where they unwind; under `panic = "abort"` (the
+/// release profile) they end the process, so callers should save the scene first.
+pub fn check_scene(scene_file: SceneFile) -> Result<(), FuzzFailure> {
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let mut rng = rand::rng();
+        let render = scene_file.into_render(&mut rng)?;
+        Ok(crate::raytrace_film(&render))
+    }));
+
+    let film = match result {
+        Ok(Ok(film)) => film,
+        Ok(Err(err)) => return Err(FuzzFailure::Load(err)),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            return Err(FuzzFailure::Panic(message));
+        }
+    };
+
+    for y in 0..film.height {
+        for x in 0..film.width {
+            let value = film.pixel(x, y);
+            if !(value.x.is_finite() && value.y.is_finite() && value.z.is_finite()) {
+                return Err(FuzzFailure::NonFinite { x, y, value });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A seed that fits a TOML integer, which is signed.
+fn random_seed<R: Rng>(rng: &mut R) -> u64 {
+    rng.random_range(0..=i64::MAX as u64)
+}
+
+fn random_color<R: Rng>(rng: &mut R) -> vec::Vec3 {
+    vec::Vec3::new(rng.random(), rng.random(), rng.random())
+}
+
+fn random_point<R: Rng>(rng: &mut R, scale: f32) -> vec::Vec3 {
+    vec::Vec3::new(
+        rng.random_range(-1.0..1.0),
+        rng.random_range(-1.0..1.0),
+        rng.random_range(-1.0..1.0),
+    ) * scale
+}
+
+/// A length spanning several orders of magnitude around `scale`, occasionally zero.
+fn random_extent<R: Rng>(rng: &mut R, scale: f32) -> f32 {
+    if rng.random_bool(0.05) {
+        0.0
+    } else {
+        10.0_f32.powf(rng.random_range(-4.0..1.0)) * scale
+    }
+}
+
+fn random_texture<R: Rng>(rng: &mut R) -> TextureTemplate {
+    if rng.random_bool(0.7) {
+        TextureTemplate::Color(color::ColorTexture::new(random_color(rng)))
+    } else {
+        TextureTemplate::Checker(checker::CheckerTexture::new(
+            color::ColorTexture::new(random_color(rng)),
+            color::ColorTexture::new(random_color(rng)),
+            10.0_f32.powf(rng.random_range(-3.0..3.0)),
+        ))
+    }
+}
+
+fn random_material<R: Rng>(rng: &mut R) -> MaterialTemplate {
+    match rng.random_range(0..14) {
+        0 => MaterialTemplate::Lambertian {
+            texture: random_texture(rng),
+        },
+        1 => MaterialTemplate::Metallic {
+            metal: metallic::Metallic::new(&random_color(rng), rng.random_range(0.0..1.5))
+                .with_model(if rng.random_bool(0.5) {
+                    metallic::MetalModel::Ggx
+                } else {
+                    metallic::MetalModel::Fuzz
+                }),
+            texture: rng.random_bool(0.3).then(|| random_texture(rng)),
+        },
+        2 => {
+            let mut glass = dielectric::Dielectric::new(rng.random_range(1.0..2.5));
+            if rng.random_bool(0.5) {
+                glass = glass.with_tint(random_color(rng));
+            }
+            if rng.random_bool(0.3) {
+                glass = glass.with_absorption(random_color(rng) * rng.random_range(0.0..10.0));
+            }
+            MaterialTemplate::Dielectric {
+                glass,
+                texture: rng.random_bool(0.2).then(|| random_texture(rng)),
+            }
+        }
+        3 => MaterialTemplate::Principled(
+            principled::Principled::new(&random_color(rng))
+                .with_metallic(rng.random())
+                .with_roughness(rng.random())
+                .with_specular(rng.random())
+                .with_sheen(rng.random_range(0.0..2.0))
+                .with_clearcoat(rng.random(), rng.random())
+                .with_transmission(rng.random(), rng.random_range(1.0..2.5)),
+        ),
+        4 => {
+            let metal =
+                anisotropic::Anisotropic::new(&random_color(rng), rng.random(), rng.random());
+            MaterialTemplate::Anisotropic(if rng.random_bool(0.5) {
+                metal.with_tangent(random_point(rng, 1.0))
+            } else {
+                metal
+            })
+        }
+        5 => {
+            let metal = match rng.random_range(0..5) {
+                0 => conductor::Metal::Gold,
+                1 => conductor::Metal::Copper,
+                2 => conductor::Metal::Silver,
+                3 => conductor::Metal::Aluminum,
+                _ => conductor::Metal::Custom {
+                    eta: random_color(rng) * 3.0,
+                    k: random_color(rng) * 10.0,
+                },
+            };
+            MaterialTemplate::Conductor(conductor::Conductor::new(metal, rng.random()))
+        }
+        6 => MaterialTemplate::TwoSided {
+            front: Box::new(random_material(rng)),
+            back: Box::new(random_material(rng)),
+        },
+        7 => MaterialTemplate::Opacity {
+            material: Box::new(random_material(rng)),
+            opacity: rng.random(),
+            mask: rng.random_bool(0.5).then(|| random_texture(rng)),
+        },
+        8 => MaterialTemplate::Layered {
+            coat: Box::new(random_material(rng)),
+            base: Box::new(random_material(rng)),
+            ior: rng.random_range(1.0..2.5),
+            tint: rng.random_bool(0.5).then(|| random_color(rng)),
+        },
+        9 => MaterialTemplate::BlinnPhong(
+            blinn_phong::BlinnPhong::new(&random_color(rng))
+                .with_specular(&random_color(rng), rng.random_range(0.0..10000.0)),
+        ),
+        10 => MaterialTemplate::Velvet(
+            velvet::Velvet::new(&random_color(rng)).with_sheen(&random_color(rng), rng.random()),
+        ),
+        11 => {
+            let mut toon = toon::Toon::new(&random_color(rng))
+                .with_light_direction(&random_point(rng, 1.0))
+                .with_bands(rng.random_range(0..8), rng.random());
+            if rng.random_bool(0.5) {
+                toon = toon.with_outline(rng.random(), &random_color(rng));
+            }
+            MaterialTemplate::Toon(toon)
+        }
+        12 => MaterialTemplate::Plastic(
+            plastic::Plastic::new(&random_color(rng))
+                .with_ior(rng.random_range(1.0..3.0))
+                .with_roughness(rng.random()),
+        ),
+        _ => MaterialTemplate::DiffuseLight {
+            texture: TextureTemplate::Color(color::ColorTexture::new(
+                random_color(rng) * rng.random_range(0.0..20.0),
+            )),
+            power: None,
+            intensity: if rng.random_bool(0.2) {
+                rng.random_range(0.0..10.0)
+            } else {
+                1.0
+            },
+            single_sided: rng.random_bool(0.2),
+            camera_visible: !rng.random_bool(0.2),
+        },
+    }
+}
+
+fn random_geometry<R: Rng>(rng: &mut R, scale: f32) -> GeometryTemplate {
+    match rng.random_range(0..8) {
+        0 => {
+            let radius = random_extent(rng, scale);
+            // Negative radii are legal (inward-facing spheres).
+            let radius = if rng.random_bool(0.1) {
+                -radius
+            } else {
+                radius
+            };
+            GeometryTemplate::Sphere(sphere::Sphere::new(&random_point(rng, scale), radius))
+        }
+        1 => {
+            let u_extent = random_extent(rng, scale);
+            let u = random_point(rng, u_extent);
+            // Occasionally collapse the quad onto a line.
+            let v = if rng.random_bool(0.1) {
+                u * rng.random_range(-2.0..2.0)
+            } else {
+                let v_extent = random_extent(rng, scale);
+                random_point(rng, v_extent)
+            };
+            GeometryTemplate::Quad(
+                quad::Quad::new(random_point(rng, scale), u, v)
+                    .with_single_sided(rng.random_bool(0.2)),
+            )
+        }
+        2 => {
+            let outer_radius = random_extent(rng, scale);
+            // Occasionally make the ring empty or vanishingly thin.
+            let inner_radius = if rng.random_bool(0.1) {
+                outer_radius * rng.random_range(0.99..1.5)
+            } else if rng.random_bool(0.3) {
+                outer_radius * rng.random_range(0.0..1.0)
+            } else {
+                0.0
+            };
+            GeometryTemplate::Disk(disk::Disk::annulus(
+                random_point(rng, scale),
+                random_point(rng, 1.0),
+                inner_radius,
+                outer_radius,
+            ))
+        }
+        3 => {
+            let half_extents = vec::Vec3::new(
+                random_extent(rng, scale),
+                random_extent(rng, scale),
+                random_extent(rng, scale),
+            );
+            // Occasionally round past the half extents.
+            let radius = random_extent(rng, scale) * rng.random_range(0.0..1.2);
+            GeometryTemplate::Sdf(sdf::SdfGeometry::new(
+                random_point(rng, scale),
+                sdf::SdfShape::RoundedBox {
+                    half_extents,
+                    radius,
+                },
+            ))
+        }
+        4 => {
+            let columns = rng.random_range(2..6);
+            let rows = rng.random_range(2..6);
+            // Heights outside `[0, 1]` are clamped.
+            let heights = (0..columns * rows)
+                .map(|_| rng.random_range(-0.5..1.5))
+                .collect();
+            let size = random_point(rng, scale);
+            match heightfield::Heightfield::new(
+                random_point(rng, scale),
+                size,
+                heightfield::HeightSource::Samples { columns, heights },
+            ) {
+                Ok(heightfield) => GeometryTemplate::Heightfield(heightfield),
+                // Zero-width terrain is rejected up front.
+                Err(_) => GeometryTemplate::Sphere(sphere::Sphere::new(&size, 1.0)),
+            }
+        }
+        5 => {
+            let strands = (0..rng.random_range(0..4))
+                .map(|_| curves::Strand {
+                    points: [
+                        random_point(rng, scale),
+                        random_point(rng, scale),
+                        random_point(rng, scale),
+                        random_point(rng, scale),
+                    ],
+                    // Occasionally taper to nothing.
+                    width: [
+                        random_extent(rng, scale) * 0.1,
+                        random_extent(rng, scale) * rng.random_range(0.0..0.1),
+                    ],
+                })
+                .collect();
+            GeometryTemplate::Curves(curves::Curves::new(
+                curves::CurveSource::Strands { strands },
+                rng.random_range(1..6),
+            ))
+        }
+        6 => {
+            let kind = [
+                generators::FractalKind::Menger,
+                generators::FractalKind::Sierpinski,
+                generators::FractalKind::Sphereflake,
+            ][rng.random_range(0..3)];
+            // Shallow depths keep fuzzed scenes quick to build.
+            let center = random_point(rng, scale);
+            match generators::Fractal::new(
+                kind,
+                rng.random_range(0..3),
+                center,
+                random_extent(rng, scale),
+            ) {
+                Ok(fractal) => GeometryTemplate::Fractal(fractal),
+                Err(_) => GeometryTemplate::Sphere(sphere::Sphere::new(&center, 1.0)),
+            }
+        }
+        _ => {
+            let min = random_point(rng, scale);
+            let mut size = vec::Vec3::new(
+                random_extent(rng, scale),
+                random_extent(rng, scale),
+                random_extent(rng, scale),
+            );
+            // Occasionally flatten the cube along one axis.
+            if rng.random_bool(0.1) {
+                size[rng.random_range(0..3)] = 0.0;
+            }
+            GeometryTemplate::Cube(cube::Cube::new(min, min + size))
+        }
+    }
+}
+
+fn random_cutout<R: Rng>(rng: &mut R, scale: f32) -> cutout::CutoutPattern {
+    if rng.random_bool(0.5) {
+        cutout::CutoutPattern::Checker {
+            scale: random_extent(rng, scale),
+        }
+    } else {
+        let spacing = random_extent(rng, scale);
+        // Occasionally wide enough to remove the whole surface.
+        cutout::CutoutPattern::Holes {
+            spacing,
+            radius: spacing * rng.random_range(0.0..1.0),
+        }
+    }
+}
+
+fn random_transform<R: Rng>(rng: &mut R, scale: f32) -> transform::Transform {
+    match rng.random_range(0..5) {
+        0 => {
+            let axis = random_point(rng, 1.0).normalize();
+            transform::Transform::Rotate(mat::Mat3::rotation(
+                &axis,
+                rng.random_range(-360.0..360.0),
+            ))
+        }
+        1 => transform::Transform::Translate(random_point(rng, scale)),
+        2 => transform::Transform::RotateAround {
+            pivot: random_point(rng, scale),
+            rotation: mat::Mat3::from_euler(&random_point(rng, 360.0)),
+        },
+        3 => {
+            // Includes mirroring (negative) and near-degenerate (tiny) factors.
+            let mut factor = || {
+                let magnitude = 10.0_f32.powf(rng.random_range(-4.0..3.0));
+                if rng.random_bool(0.2) {
+                    -magnitude
+                } else {
+                    magnitude
+                }
+            };
+            transform::Transform::Scale(vec::Vec3::new(factor(), factor(), factor()))
+        }
+        _ => {
+            // Keyframes all at one time are an edge case of their own.
+            let frozen = rng.random_bool(0.1).then(|| rng.random_range(0.0..1.0));
+            let keyframes = (0..rng.random_range(2..=4))
+                .map(|_| transform::Keyframe {
+                    time: frozen.unwrap_or_else(|| rng.random_range(0.0..1.0)),
+                    offset: random_point(rng, scale),
+                })
+                .collect();
+            let interpolation = if rng.random_bool(0.5) {
+                transform::Interpolation::Cubic
+            } else {
+                transform::Interpolation::Linear
+            };
+            transform::Transform::Move(transform::Motion::new(keyframes, interpolation))
+        }
+    }
+}
+
+fn random_camera<R: Rng>(rng: &mut R, scale: f32) -> camera::Camera {
+    let look_at = random_point(rng, scale * 0.1);
+    let mut origin = random_point(rng, scale * 3.0);
+    // Keep the view direction away from the up vector so the camera basis is valid.
+    if (origin - look_at).normalize().y.abs() > 0.99 {
+        origin.x += scale;
+    }
+    let camera = camera::Camera::with_config(camera::CameraConfig {
+        origin,
+        look_at,
+        up: vec::Vec3::new(0.0, 1.0, 0.0),
+        aspect_ratio: rng.random_range(0.5..2.5),
+        viewport_height: 2.0,
+        focal_length: (origin - look_at).length(),
+        aperture: if rng.random_bool(0.3) {
+            rng.random_range(0.0..0.1) * scale
+        } else {
+            0.0
+        },
+        vertical_fov: rng.random_range(10.0..120.0),
+    });
+    if rng.random_bool(0.2) {
+        camera.with_dispersion(rng.random_range(-0.1..0.1))
+    } else {
+        camera
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn generated_scenes_serialize() {
+        for seed in 0..200 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let scene_file = generate_scene(&mut rng);
+            if let Err(err) = toml::to_string(&scene_file) {
+                panic!("scene for seed {} failed to serialize: {}", seed, err);
+            }
+        }
+    }
+}
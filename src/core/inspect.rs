@@ -0,0 +1,181 @@
+//! Re-traces single pixels with every path vertex logged, for diagnosing fireflies and
+//! black pixels.
+//!
+//! A pixel is re-traced from the same per-pixel generator the render used, so the logged
+//! paths are exactly the samples that produced its color. [`PixelTrace`] prints them as a
+//! tree: one branch per sample, one node per bounce with the object hit, the material's
+//! scattering event, and its pdf values.
+use std::fmt;
+
+use crate::core::{introspect, path, render, scene};
+use crate::math::vec;
+use crate::samplers::monte_carlo::MonteCarloSampler;
+
+/// Logged samples of one pixel.
+pub struct PixelTrace {
+    /// Pixel coordinates, with `(0, 0)` at the bottom-left like [`crate::core::film::Film`].
+    pub x: u32,
+    pub y: u32,
+    /// Each sample's index within the pixel and its path, in tracing order.
+    pub samples: Vec<(usize, path::TracedPath)>,
+    /// Display label of each renderable, by index.
+    labels: Vec<String>,
+}
+
+impl PixelTrace {
+    pub(crate) fn new(
+        scene: &scene::Scene,
+        x: u32,
+        y: u32,
+        samples: Vec<(usize, path::TracedPath)>,
+    ) -> Self {
+        let labels = introspect::objects(scene)
+            .into_iter()
+            .map(|info| match info.kind {
+                introspect::ObjectKind::Object | introspect::ObjectKind::Volume => format!(
+                    "{} / {}",
+                    info.geometry.unwrap_or("?"),
+                    info.material.unwrap_or("?")
+                ),
+                kind => format!("{:?}", kind),
+            })
+            .collect();
+        PixelTrace {
+            x,
+            y,
+            samples,
+            labels,
+        }
+    }
+
+    /// Average radiance of the logged samples.
+    pub fn mean(&self) -> vec::Vec3 {
+        if self.samples.is_empty() {
+            return vec::Vec3::default();
+        }
+        let sum = self
+            .samples
+            .iter()
+            .fold(vec::Vec3::default(), |sum, (_, path)| sum + path.radiance);
+        sum / self.samples.len() as f32
+    }
+}
+
+/// Re-traces pixel `(x, y)` of a [`crate::raytrace_film`] render (or the single-threaded
+/// and concurrent CLI renders), logging every path. Wavefront renders draw their samples
+/// differently and aren't reproduced.
+pub fn trace_pixel(render: &render::Render, x: u32, y: u32) -> PixelTrace {
+    let sampler = MonteCarloSampler::new(
+        render.samples,
+        render.depth,
+        &render.camera,
+        &render.scene,
+        crate::trace_ray,
+    )
+    .with_ray_ranges(render.ray_ranges);
+    let mut rng = render.rng.pixel_rng(render.frame_seed(), x, y);
+    let paths =
+        sampler.sample_pixel_paths(&mut rng, x, y, render.image_width(), render.image_height());
+    PixelTrace::new(&render.scene, x, y, paths.into_iter().enumerate().collect())
+}
+
+impl fmt::Display for PixelTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "pixel ({}, {}): {} samples, mean {}",
+            self.x,
+            self.y,
+            self.samples.len(),
+            color(self.mean())
+        )?;
+        for (position, (index, path)) in self.samples.iter().enumerate() {
+            let last_sample = position + 1 == self.samples.len();
+            let (branch, indent) = if last_sample {
+                ("└─", "   ")
+            } else {
+                ("├─", "│  ")
+            };
+            writeln!(
+                f,
+                "{} sample {}: radiance {}",
+                branch,
+                index,
+                color(path.radiance)
+            )?;
+
+            for vertex in path.vertices.iter() {
+                let label = match vertex.object {
+                    Some(object) => format!(
+                        "#{} {}",
+                        object,
+                        self.labels.get(object).map_or("?", String::as_str)
+                    ),
+                    None => "unknown object".to_string(),
+                };
+                writeln!(
+                    f,
+                    "{}├─ {} at t {:.4}, point {}, normal {}",
+                    indent,
+                    label,
+                    vertex.t,
+                    point(vertex.point),
+                    point(vertex.normal)
+                )?;
+                writeln!(
+                    f,
+                    "{}│     throughput {}, emitted {}",
+                    indent,
+                    color(vertex.throughput),
+                    color(vertex.emitted)
+                )?;
+                writeln!(f, "{}│     {}", indent, event(&vertex.event))?;
+            }
+
+            let end = match path.end() {
+                path::PathEnd::Escaped => "escaped",
+                path::PathEnd::Light => "ended on a light",
+                path::PathEnd::Absorbed => "absorbed",
+                path::PathEnd::DepthLimit => "stopped at the depth limit",
+                path::PathEnd::ZeroPdf => "dropped on a zero pdf",
+            };
+            writeln!(f, "{}└─ {}", indent, end)?;
+        }
+        Ok(())
+    }
+}
+
+fn event(event: &path::VertexEvent) -> String {
+    match event {
+        path::VertexEvent::Specular { attenuation } => {
+            format!("specular, attenuation {}", color(*attenuation))
+        }
+        path::VertexEvent::Diffuse {
+            attenuation,
+            scatter_pdf,
+            sample_pdf,
+            light_sampling,
+        } => format!(
+            "diffuse, attenuation {}, scatter pdf {:.4}, sample pdf {:.4}{}",
+            color(*attenuation),
+            scatter_pdf,
+            sample_pdf,
+            if *light_sampling {
+                " (mixed with lights)"
+            } else {
+                ""
+            }
+        ),
+        path::VertexEvent::Absorbed => "no scattering".to_string(),
+        path::VertexEvent::DepthLimit => "out of bounces".to_string(),
+        path::VertexEvent::ZeroPdf => "sampled a direction with zero pdf".to_string(),
+    }
+}
+
+fn color(color: vec::Vec3) -> String {
+    format!("({:.4}, {:.4}, {:.4})", color.x, color.y, color.z)
+}
+
+fn point(point: vec::Point3) -> String {
+    format!("({:.3}, {:.3}, {:.3})", point.x, point.y, point.z)
+}
@@ -0,0 +1,42 @@
+//! Ctrl-C handling for long renders.
+//!
+//! [`on_ctrl_c`] turns SIGINT into a flag for [`crate::core::render::Render::interrupt`]:
+//! the first Ctrl-C asks renderers to stop sampling and return what they have, so the
+//! caller can save a partial image instead of losing hours of work. A second Ctrl-C
+//! exits at once, for when saving itself hangs.
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, OnceLock};
+
+/// Exit status after a second Ctrl-C, the shell convention for death by SIGINT.
+#[cfg(unix)]
+const FORCED_EXIT_STATUS: i32 = 130;
+
+static FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Installs a SIGINT handler and returns the flag it sets. Every call returns the same
+/// flag. Outside Unix no handler is installed and the flag is never set.
+pub fn on_ctrl_c() -> Arc<AtomicBool> {
+    let flag = FLAG
+        .get_or_init(|| Arc::new(AtomicBool::new(false)))
+        .clone();
+    #[cfg(unix)]
+    // SAFETY: the handler only touches an atomic and calls the async-signal-safe `_exit`.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            handle_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+    flag
+}
+
+#[cfg(unix)]
+extern "C" fn handle_sigint(_signal: libc::c_int) {
+    let already_interrupted = FLAG
+        .get()
+        .is_some_and(|flag| flag.swap(true, std::sync::atomic::Ordering::SeqCst));
+    if already_interrupted {
+        // SAFETY: `_exit` is async-signal-safe.
+        unsafe { libc::_exit(FORCED_EXIT_STATUS) };
+    }
+}
@@ -0,0 +1,136 @@
+//! Read-only views of a built scene for editors and other external tools.
+//!
+//! [`objects`] lists every renderable with its bounds, transforms, and material names for
+//! outliner panels and selection gizmos; [`bvh_nodes`] flattens the acceleration structure
+//! so its boxes can be drawn as an overlay. Nothing here changes the scene.
+use crate::core::{bbox, bvh, object, scene, volume, world};
+use crate::geometry::transform;
+
+/// What kind of renderable an [`ObjectInfo`] describes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectKind {
+    /// A geometry/material pair ([`object::RenderObject`]).
+    Object,
+    /// A participating medium ([`volume::RenderVolume`]).
+    Volume,
+    /// The sky background ([`world::World`]).
+    World,
+    /// A renderable type this module doesn't know how to look inside.
+    Other,
+}
+
+/// Description of one renderable in a scene.
+pub struct ObjectInfo<'a> {
+    /// Index into the scene's renderables; BVH leaves refer to objects by this index.
+    pub index: usize,
+    pub kind: ObjectKind,
+    /// World-space bounds, including transforms and motion.
+    pub bounding_box: bbox::BBox,
+    /// Geometry type name (e.g. `Sphere`), or the boundary's for volumes.
+    pub geometry: Option<&'static str>,
+    /// Transforms applied to the geometry, outermost last.
+    pub transforms: &'a [transform::Transform],
+    /// Material type name (e.g. `Lambertian`), or the phase function's for volumes.
+    pub material: Option<&'static str>,
+    /// Material type names of per-slot overrides, in slot order.
+    pub slot_materials: Vec<&'static str>,
+    /// Whether the object is tested through the BVH rather than after traversal.
+    pub in_bvh: bool,
+}
+
+/// One BVH node, listed in depth-first order by [`bvh_nodes`].
+#[derive(Clone, Copy, Debug)]
+pub struct BvhNodeInfo {
+    pub bounding_box: bbox::BBox,
+    /// Distance from the root, which is at depth 0.
+    pub depth: u32,
+    /// For leaves, the index of the renderable they hold.
+    pub object: Option<usize>,
+}
+
+/// Describes every renderable in the scene, in index order.
+pub fn objects(scene: &scene::Scene) -> Vec<ObjectInfo<'_>> {
+    scene
+        .renderables
+        .objects
+        .iter()
+        .enumerate()
+        .map(|(index, renderable)| {
+            let any = renderable.as_any();
+            let mut info = ObjectInfo {
+                index,
+                kind: ObjectKind::Other,
+                bounding_box: renderable.bounding_box(),
+                geometry: None,
+                transforms: &[],
+                material: None,
+                slot_materials: Vec::new(),
+                in_bvh: scene.bvh.is_some() && !scene.unbounded.contains(&index),
+            };
+
+            if let Some(render_object) = any.downcast_ref::<object::RenderObject>() {
+                let geometry = &render_object.geometry_instance;
+                let material = &render_object.material_instance;
+                info.kind = ObjectKind::Object;
+                info.geometry = Some(geometry.ref_obj.geometry_name());
+                info.transforms = &geometry.transforms;
+                info.material = Some(material.ref_mat.material_name());
+                info.slot_materials = material
+                    .slots
+                    .iter()
+                    .map(|slot| slot.material_name())
+                    .collect();
+            } else if let Some(render_volume) = any.downcast_ref::<volume::RenderVolume>() {
+                info.kind = ObjectKind::Volume;
+                info.geometry = Some(render_volume.boundary.geometry_name());
+                info.material = Some(render_volume.phase_function.material_name());
+            } else if any.is::<world::World>() {
+                info.kind = ObjectKind::World;
+            }
+            info
+        })
+        .collect()
+}
+
+/// Flattens the scene's BVH depth-first, parents before children. Empty if the scene has
+/// no BVH (e.g. it hasn't been built yet).
+pub fn bvh_nodes(scene: &scene::Scene) -> Vec<BvhNodeInfo> {
+    let mut nodes = Vec::new();
+    if let Some(bvh) = &scene.bvh {
+        collect_nodes(&bvh.root, 0, &mut nodes);
+    }
+    nodes
+}
+
+fn collect_nodes(node: &bvh::BvhNode, depth: u32, nodes: &mut Vec<BvhNodeInfo>) {
+    match node {
+        bvh::BvhNode::Leaf {
+            bounding_box,
+            index,
+        } => nodes.push(BvhNodeInfo {
+            bounding_box: *bounding_box,
+            depth,
+            object: Some(*index),
+        }),
+        bvh::BvhNode::Branch {
+            bounding_box,
+            left,
+            right,
+            ..
+        } => {
+            nodes.push(BvhNodeInfo {
+                bounding_box: *bounding_box,
+                depth,
+                object: None,
+            });
+            collect_nodes(left, depth + 1, nodes);
+            collect_nodes(right, depth + 1, nodes);
+        }
+    }
+}
+
+/// Strips the module path and generic arguments from a [`std::any::type_name`].
+pub(crate) fn short_type_name(full: &'static str) -> &'static str {
+    let base = full.split('<').next().unwrap_or(full);
+    base.rsplit("::").next().unwrap_or(base)
+}
@@ -1,9 +1,10 @@
 use std::sync::Arc;
 
-use crate::core::{bbox, ray};
+use crate::core::{bbox, path, ray};
 use crate::geometry::instance::GeometryInstance;
+use crate::materials::diffuse_light;
 use crate::materials::instance::MaterialInstance;
-use crate::math::{interval, vec};
+use crate::math::{interval, rng, vec};
 use crate::traits::hittable::Hittable;
 use crate::traits::renderable::Renderable;
 use crate::traits::scatterable::Scatterable;
@@ -34,16 +35,24 @@ impl RenderObject {
         let geometry_instance = GeometryInstance {
             ref_obj: hittable,
             transforms: Vec::new(),
+            motion_blur: true,
+            smooth_shading: true,
         };
-        let material_instance = MaterialInstance {
-            ref_mat: scatterable,
-            albedo: None,
-        };
+        let material_instance = MaterialInstance::new(scatterable);
         RenderObject {
             geometry_instance,
             material_instance,
         }
     }
+
+    /// Whether the object's material emits light, making it a light-sampling target.
+    pub fn is_emissive(&self) -> bool {
+        self.material_instance
+            .ref_mat
+            .as_any()
+            .downcast_ref::<diffuse_light::DiffuseLight>()
+            .is_some()
+    }
 }
 
 impl Renderable for RenderObject {
@@ -63,6 +72,10 @@ impl Renderable for RenderObject {
         Some(hit_record)
     }
 
+    fn occludes(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> bool {
+        self.geometry_instance.hit(ray, t_min, t_max).is_some()
+    }
+
     fn bounding_box(&self) -> bbox::BBox {
         self.geometry_instance.bounding_box()
     }
@@ -72,22 +85,49 @@ impl Renderable for RenderObject {
         origin: &vec::Point3,
         time: f64,
     ) -> Box<dyn crate::math::pdf::PDF + Send + Sync + '_> {
-        self.geometry_instance.get_pdf(origin, time)
+        let emission_map = self
+            .material_instance
+            .ref_mat
+            .as_any()
+            .downcast_ref::<diffuse_light::DiffuseLight>()
+            .and_then(|light| light.emission_map());
+        match emission_map {
+            Some(emission) => self
+                .geometry_instance
+                .get_emission_pdf(origin, time, emission),
+            None => self.geometry_instance.get_pdf(origin, time),
+        }
     }
 
     fn scatter(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut rng::SampleRng,
         hit_record: &hittable::HitRecord<'_>,
-        depth: u32,
+        state: &mut path::PathState,
     ) -> Option<scatterable::ScatterRecord> {
-        self.material_instance.scatter(rng, hit_record, depth)
+        self.material_instance.scatter(rng, hit_record, state)
     }
 
     fn emit(&self, hit_record: &hittable::HitRecord<'_>) -> vec::Vec3 {
         self.material_instance.emit(hit_record)
     }
 
+    fn max_bounces(&self, hit_record: &hittable::HitRecord<'_>) -> Option<u32> {
+        self.material_instance
+            .material_for(hit_record)
+            .max_bounces()
+    }
+
+    fn camera_visible(&self, hit_record: &hittable::HitRecord<'_>) -> bool {
+        self.material_instance
+            .material_for(hit_record)
+            .camera_visible(hit_record)
+    }
+
+    fn light_group(&self) -> Option<usize> {
+        self.material_instance.light_group
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
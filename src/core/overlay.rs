@@ -0,0 +1,218 @@
+//! Render statistics burned into preview images.
+//!
+//! Draft and preview renders can carry their own sample count, render time, and ray
+//! throughput in the top-left corner, so contact sheets and progress snapshots say how
+//! they were made. Text is drawn with a small built-in bitmap font, white on a darkened
+//! box, scaled up with the image so it stays legible on large frames.
+use std::time::Duration;
+
+/// Glyph width in font pixels.
+const GLYPH_WIDTH: u32 = 5;
+/// Glyph height in font pixels.
+const GLYPH_HEIGHT: u32 = 7;
+/// Space between glyphs and around the text, in font pixels.
+const SPACING: u32 = 1;
+/// Image height that one font pixel per image pixel is sized for; taller images draw
+/// each font pixel as a larger square.
+const REFERENCE_HEIGHT: u32 = 240;
+/// Factor the box behind the text divides the image's brightness by.
+const BACKING_DARKENING: u8 = 4;
+
+/// What a render cost, as stamped by [`stamp`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    /// Samples per pixel, averaged over the image for adaptive renders.
+    pub samples: f32,
+    /// Wall time spent rendering.
+    pub elapsed: Duration,
+    /// Rays traced against the scene (see [`crate::core::scene::Scene::rays_traced`]).
+    pub rays: u64,
+}
+
+impl RenderStats {
+    /// Rays traced per second of wall time; zero if no time has passed.
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.rays as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// The lines [`stamp`] draws, e.g. `64 spp`, `12.3 s`, `4.5M rays/s`.
+    pub fn lines(&self) -> [String; 3] {
+        [
+            format!("{:.0} spp", self.samples),
+            format_elapsed(self.elapsed),
+            format!("{} rays/s", format_rate(self.rays_per_second())),
+        ]
+    }
+}
+
+/// Draws `stats` into the top-left corner of `data`, a top-down RGB8 image of
+/// `width` by `height` pixels. Text that doesn't fit is clipped.
+pub fn stamp(data: &mut [u8], width: u32, height: u32, stats: &RenderStats) {
+    let scale = (height / REFERENCE_HEIGHT).max(1);
+    let lines = stats.lines();
+    let columns = lines
+        .iter()
+        .map(|line| line.chars().count() as u32)
+        .max()
+        .unwrap_or(0);
+    let box_width = SPACING + columns * (GLYPH_WIDTH + SPACING);
+    let box_height = SPACING + lines.len() as u32 * (GLYPH_HEIGHT + SPACING);
+
+    let mut canvas = Canvas {
+        data,
+        width,
+        height,
+        scale,
+    };
+    for y in 0..box_height {
+        for x in 0..box_width {
+            canvas.darken(x, y);
+        }
+    }
+    for (row, line) in lines.iter().enumerate() {
+        let top = SPACING + row as u32 * (GLYPH_HEIGHT + SPACING);
+        for (column, character) in line.chars().enumerate() {
+            let left = SPACING + column as u32 * (GLYPH_WIDTH + SPACING);
+            for (y, bits) in glyph(character).iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                        canvas.light(left + x, top + y as u32);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An RGB8 image addressed in font pixels.
+struct Canvas<'a> {
+    data: &'a mut [u8],
+    width: u32,
+    height: u32,
+    scale: u32,
+}
+
+impl Canvas<'_> {
+    fn darken(&mut self, x: u32, y: u32) {
+        self.fill(x, y, |channel| channel / BACKING_DARKENING);
+    }
+
+    fn light(&mut self, x: u32, y: u32) {
+        self.fill(x, y, |_| u8::MAX);
+    }
+
+    fn fill(&mut self, x: u32, y: u32, shade: impl Fn(u8) -> u8) {
+        let (x_start, y_start) = (x * self.scale, y * self.scale);
+        for py in y_start..(y_start + self.scale).min(self.height) {
+            for px in x_start..(x_start + self.scale).min(self.width) {
+                let index = 3 * (py as usize * self.width as usize + px as usize);
+                if let Some(pixel) = self.data.get_mut(index..index + 3) {
+                    pixel
+                        .iter_mut()
+                        .for_each(|channel| *channel = shade(*channel));
+                }
+            }
+        }
+    }
+}
+
+/// `12.3 s` under a minute, then `4m 05s` and `2h 10m`.
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    if seconds < 60 {
+        format!("{:.1} s", elapsed.as_secs_f64())
+    } else if seconds < 3600 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h {:02}m", seconds / 3600, (seconds % 3600) / 60)
+    }
+}
+
+/// A count with a `k`, `M`, or `G` suffix and one decimal.
+fn format_rate(rate: f64) -> String {
+    [(1e9, "G"), (1e6, "M"), (1e3, "k")]
+        .iter()
+        .find(|(unit, _)| rate >= *unit)
+        .map_or(format!("{:.0}", rate), |(unit, suffix)| {
+            format!("{:.1}{}", rate / unit, suffix)
+        })
+}
+
+/// Rows of a glyph from the top, leftmost pixel in the highest of the low five bits.
+/// Characters the font doesn't have are blank.
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT as usize] {
+    match character {
+        '0' => [
+            0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+        ],
+        '1' => [
+            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        '2' => [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+        '3' => [
+            0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+        ],
+        '4' => [
+            0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+        ],
+        '5' => [
+            0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+        ],
+        '6' => [
+            0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+        ],
+        '7' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+        ],
+        '8' => [
+            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+        ],
+        '9' => [
+            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+        ],
+        '.' => [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100,
+        ],
+        '/' => [
+            0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000,
+        ],
+        'a' => [
+            0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111,
+        ],
+        'h' => [
+            0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001,
+        ],
+        'k' => [
+            0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010,
+        ],
+        'm' => [
+            0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001,
+        ],
+        'p' => [
+            0b00000, 0b00000, 0b10110, 0b11001, 0b11110, 0b10000, 0b10000,
+        ],
+        'r' => [
+            0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000,
+        ],
+        's' => [
+            0b00000, 0b00000, 0b01110, 0b10000, 0b01110, 0b00001, 0b11110,
+        ],
+        'y' => [
+            0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110,
+        ],
+        'G' => [
+            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+        ],
+        'M' => [
+            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+        ],
+        _ => [0; GLYPH_HEIGHT as usize],
+    }
+}
@@ -0,0 +1,244 @@
+//! Job manifests for rendering an animation across machines.
+//!
+//! [`write_bundle`] packs a scene and the local files it references into a `.rustray`
+//! bundle (see [`bundle`]), so every machine renders from one self-contained file, and
+//! [`Manifest`] lists what to do with it: the frames, split into chunks of consecutive
+//! frames that can each go to a different machine, the command that renders each frame,
+//! and the image each command leaves behind. Manifests serialize to JSON for render farm
+//! managers such as Deadline, or for a few lines of script over `ssh`.
+//!
+//! Commands are relative to the directory holding the manifest and the bundle, and
+//! render one frame each with `rustray <bundle> --frame <n> --frame-rate <fps>`.
+use std::fs;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::core::scene_file::{self, SceneFileError};
+use crate::core::{assets, bundle};
+
+/// Directory in a bundle that assets from outside the scene's tree are packed into.
+const OUTSIDE_ASSETS: &str = "assets";
+
+/// Version of the manifest layout, bumped when fields change meaning.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Name of frame `frame`'s outputs for a scene named `stem`, e.g. `shot_0012`.
+pub fn frame_name(stem: &str, frame: u32) -> String {
+    format!("{}_{:04}", stem, frame)
+}
+
+/// Writes the scene at `scene_path` and the local files it references to a bundle at
+/// `bundle_path`, returning the asset paths packed alongside `scene.toml`.
+///
+/// Assets are found by their `path` entries. Relative paths inside the working
+/// directory keep their place in the bundle; others are packed under `assets/` and the
+/// scene is rewritten to match. Remote assets stay URLs, fetched by each machine. A
+/// bundle is copied as it is.
+pub fn write_bundle(scene_path: &Path, bundle_path: &Path) -> Result<Vec<String>, SceneFileError> {
+    if bundle::is_bundle(scene_path) {
+        bundle::read_scene_file(scene_path)?;
+        fs::copy(scene_path, bundle_path)?;
+        return Ok(Vec::new());
+    }
+
+    let text = fs::read_to_string(scene_path)?;
+    // Fails early, and the same way a render would, on scenes that won't load.
+    scene_file::read_scene_file(scene_path)?;
+    let mut scene: toml::Value = toml::from_str(&text)?;
+    let mut references = Vec::new();
+    collect_references(&mut scene, &mut references);
+
+    let mut assets: Vec<(String, PathBuf)> = Vec::new();
+    let mut rewritten = false;
+    for reference in references {
+        let original = reference.as_str().unwrap_or_default().to_string();
+        let source = assets::resolve(&original)?;
+        let inside = Path::new(&original).is_relative()
+            && !Path::new(&original)
+                .components()
+                .any(|component| matches!(component, std::path::Component::ParentDir));
+        let packed = if inside {
+            original.replace('\\', "/")
+        } else {
+            let name = source
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("asset");
+            format!(
+                "{}/{:016x}-{}",
+                OUTSIDE_ASSETS,
+                assets::content_hash(original.as_bytes()),
+                name
+            )
+        };
+        if packed != original {
+            *reference = toml::Value::String(packed.clone());
+            rewritten = true;
+        }
+        if !assets.iter().any(|(name, _)| *name == packed) {
+            assets.push((packed, source));
+        }
+    }
+    let scene_text = if rewritten {
+        toml::to_string(&scene)?
+    } else {
+        text
+    };
+
+    let bundle_error = |err: zip::result::ZipError| {
+        SceneFileError::Bundle(format!("{}: {}", bundle_path.display(), err))
+    };
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut archive = zip::ZipWriter::new(fs::File::create(bundle_path)?);
+    archive
+        .start_file(bundle::SCENE_FILE, options)
+        .map_err(bundle_error)?;
+    archive.write_all(scene_text.as_bytes())?;
+    for (name, source) in &assets {
+        let bytes = fs::read(source).map_err(|err| {
+            SceneFileError::Bundle(format!("asset {}: {}", source.display(), err))
+        })?;
+        archive.start_file(name, options).map_err(bundle_error)?;
+        archive.write_all(&bytes)?;
+    }
+    archive.finish().map_err(bundle_error)?;
+    Ok(assets.into_iter().map(|(name, _)| name).collect())
+}
+
+/// Every local asset reference in `value`: strings under `path` keys.
+fn collect_references<'a>(value: &'a mut toml::Value, references: &mut Vec<&'a mut toml::Value>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, entry) in table.iter_mut() {
+                let local = entry
+                    .as_str()
+                    .is_some_and(|reference| !assets::is_remote(reference));
+                if key == "path" && local {
+                    references.push(entry);
+                } else {
+                    collect_references(entry, references);
+                }
+            }
+        }
+        toml::Value::Array(entries) => {
+            for entry in entries.iter_mut() {
+                collect_references(entry, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Frames to render and how to split them.
+#[derive(Clone, Debug)]
+pub struct JobSettings {
+    pub frames: RangeInclusive<u32>,
+    pub frame_rate: f64,
+    /// Consecutive frames per chunk, each chunk a unit of work for one machine.
+    pub chunk_size: u32,
+    /// Extra options passed to every render command, e.g. `--preset final`.
+    pub render_args: Vec<String>,
+}
+
+impl JobSettings {
+    /// `frames` at `frame_rate` frames per second, one frame per chunk.
+    pub fn new(frames: RangeInclusive<u32>, frame_rate: f64) -> Self {
+        JobSettings {
+            frames,
+            frame_rate,
+            chunk_size: 1,
+            render_args: Vec::new(),
+        }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: u32) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    pub fn with_render_args(mut self, render_args: Vec<String>) -> Self {
+        self.render_args = render_args;
+        self
+    }
+}
+
+/// Consecutive frames rendered together on one machine.
+#[derive(Clone, Debug, Serialize)]
+pub struct Chunk {
+    pub index: usize,
+    pub frames: Vec<u32>,
+    /// One command line per frame, program first.
+    pub commands: Vec<Vec<String>>,
+    /// Images the commands write, one per frame, relative to the manifest.
+    pub outputs: Vec<String>,
+}
+
+/// Everything a farm needs to render a bundled animation.
+#[derive(Clone, Debug, Serialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// The scene bundle, relative to the manifest.
+    pub scene: String,
+    pub frame_rate: f64,
+    pub frames: Vec<u32>,
+    pub chunks: Vec<Chunk>,
+}
+
+impl Manifest {
+    /// The job rendering the bundle named `scene` as `settings` describes.
+    pub fn new(scene: &str, settings: &JobSettings) -> Self {
+        let stem = Path::new(scene)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("output");
+        // The disk film writes an EXR where other renders save a PNG.
+        let extension = if settings.render_args.iter().any(|arg| arg == "--disk-film") {
+            "exr"
+        } else {
+            "png"
+        };
+        let frames: Vec<u32> = settings.frames.clone().collect();
+        let chunks = frames
+            .chunks(settings.chunk_size.max(1) as usize)
+            .enumerate()
+            .map(|(index, chunk)| Chunk {
+                index,
+                frames: chunk.to_vec(),
+                commands: chunk
+                    .iter()
+                    .map(|frame| {
+                        let mut command = vec![
+                            String::from("rustray"),
+                            scene.to_string(),
+                            String::from("--frame"),
+                            frame.to_string(),
+                            String::from("--frame-rate"),
+                            settings.frame_rate.to_string(),
+                        ];
+                        command.extend(settings.render_args.iter().cloned());
+                        command
+                    })
+                    .collect(),
+                outputs: chunk
+                    .iter()
+                    .map(|&frame| format!("samples/{}.{}", frame_name(stem, frame), extension))
+                    .collect(),
+            })
+            .collect();
+        Manifest {
+            version: MANIFEST_VERSION,
+            scene: scene.to_string(),
+            frame_rate: settings.frame_rate,
+            frames,
+            chunks,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("manifest serializes")
+    }
+}
@@ -0,0 +1,226 @@
+//! Per-path state threaded through the integrator and material scattering.
+use crate::core::ray;
+use crate::math::vec;
+
+/// Kind of the most recent scattering event along a path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BounceKind {
+    /// No scattering yet; the path is still the primary camera ray.
+    #[default]
+    Camera,
+    /// Scattered by sampling a PDF (diffuse surfaces, phase functions).
+    Diffuse,
+    /// Scattered along an explicit ray (mirrors, glass).
+    Specular,
+}
+
+/// What happened to a path at a vertex recorded by [`PathState::with_log`].
+#[derive(Clone, Copy, Debug)]
+pub enum VertexEvent {
+    /// Scattered along an explicit ray (mirrors, glass); the PDF is a delta.
+    Specular { attenuation: vec::Vec3 },
+    /// Scattered in a direction drawn from a PDF. `scatter_pdf` is the material's density
+    /// for that direction and `sample_pdf` the density it was actually drawn with, which
+    /// differ when `light_sampling` mixed in the scene's lights.
+    Diffuse {
+        attenuation: vec::Vec3,
+        scatter_pdf: f32,
+        sample_pdf: f32,
+        light_sampling: bool,
+    },
+    /// The material didn't scatter (emitters, absorbing media).
+    Absorbed,
+    /// The bounce budget ran out before scattering.
+    DepthLimit,
+    /// The sampled direction had zero density, so the path was dropped.
+    ZeroPdf,
+}
+
+/// One vertex of a logged path.
+#[derive(Clone, Copy, Debug)]
+pub struct PathVertex {
+    /// Index of the hit renderable in [`crate::core::scene::Scene::renderables`].
+    pub object: Option<usize>,
+    pub t: f32,
+    pub point: vec::Point3,
+    pub normal: vec::Vec3,
+    /// Radiance emitted at the hit, before weighting by `throughput`.
+    pub emitted: vec::Vec3,
+    /// Path throughput arriving at the vertex.
+    pub throughput: vec::Vec3,
+    pub event: VertexEvent,
+    /// Direction of the continuation ray, if the path went on.
+    pub scattered: Option<vec::Vec3>,
+}
+
+/// A camera path traced with its vertices logged.
+#[derive(Clone, Debug)]
+pub struct TracedPath {
+    /// The camera ray the path started from.
+    pub ray: ray::Ray,
+    /// Radiance the path carried back to the camera.
+    pub radiance: vec::Vec3,
+    pub vertices: Vec<PathVertex>,
+}
+
+/// Why a logged path stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathEnd {
+    /// Left the scene, or reached the sky.
+    Escaped,
+    /// Stopped on an emitter.
+    Light,
+    /// Stopped on a surface that neither scattered nor emitted.
+    Absorbed,
+    /// Ran out of bounces.
+    DepthLimit,
+    /// Sampled a direction with zero density.
+    ZeroPdf,
+}
+
+impl TracedPath {
+    /// Why the path stopped, judged from its last vertex.
+    pub fn end(&self) -> PathEnd {
+        let Some(last) = self.vertices.last() else {
+            return PathEnd::Escaped;
+        };
+        // The sky reports its hit at `f32::MAX`.
+        if last.scattered.is_some() || last.t == f32::MAX {
+            return PathEnd::Escaped;
+        }
+        match last.event {
+            VertexEvent::DepthLimit => PathEnd::DepthLimit,
+            VertexEvent::ZeroPdf => PathEnd::ZeroPdf,
+            _ if last.emitted.squared_length() > 0.0 => PathEnd::Light,
+            _ => PathEnd::Absorbed,
+        }
+    }
+}
+
+/// Mutable context carried along a single camera path.
+///
+/// New integrator features should add fields here instead of widening the
+/// `trace_ray`/`scatter` signatures.
+#[derive(Clone, Debug)]
+pub struct PathState {
+    /// Remaining bounce budget; materials stop scattering once it reaches zero.
+    pub depth: u32,
+    /// Number of scattering events so far.
+    pub bounces: u32,
+    /// Refractive indices of the media the path is inside, innermost last.
+    pub eta_stack: Vec<f32>,
+    /// Kind of the last scattering event.
+    pub last_bounce: BounceKind,
+    /// Radiance gathered per light group, indexed like
+    /// [`crate::core::scene::Scene::light_groups`] with one extra slot at the end for
+    /// emitters without a group. Empty unless the render splits emission by group.
+    pub light_groups: Vec<vec::Vec3>,
+    /// Every vertex of the path in order, when logging was requested with
+    /// [`PathState::with_log`]. Logging never draws random numbers, so a logged path is
+    /// the same path the render traced.
+    pub log: Option<Vec<PathVertex>>,
+    /// Hit distances accepted by each kind of ray the path traces.
+    pub ray_ranges: ray::RayRanges,
+}
+
+impl PathState {
+    /// Creates the state for a new camera path with the given bounce budget.
+    pub fn new(max_depth: u32) -> Self {
+        PathState {
+            depth: max_depth,
+            bounces: 0,
+            eta_stack: Vec::new(),
+            last_bounce: BounceKind::Camera,
+            light_groups: Vec::new(),
+            log: None,
+            ray_ranges: ray::RayRanges::default(),
+        }
+    }
+
+    /// Traces the path with the hit distances in `ray_ranges` instead of the defaults.
+    pub fn with_ray_ranges(mut self, ray_ranges: ray::RayRanges) -> Self {
+        self.ray_ranges = ray_ranges;
+        self
+    }
+
+    /// Hit distances for the path's next ray: the camera's until the first bounce, then
+    /// the indirect ones.
+    pub fn ray_range(&self) -> ray::RayRange {
+        match self.last_bounce {
+            BounceKind::Camera => self.ray_ranges.camera,
+            BounceKind::Diffuse | BounceKind::Specular => self.ray_ranges.indirect,
+        }
+    }
+
+    /// Splits the radiance this path gathers into `count` light groups plus the
+    /// ungrouped slot.
+    pub fn with_light_groups(mut self, count: usize) -> Self {
+        self.light_groups = vec![vec::Vec3::default(); count + 1];
+        self
+    }
+
+    /// Records every vertex of the path into [`PathState::log`].
+    pub fn with_log(mut self) -> Self {
+        self.log = Some(Vec::new());
+        self
+    }
+
+    /// Refractive index of the medium the path is currently travelling through.
+    pub fn current_eta(&self) -> f32 {
+        self.eta_stack.last().copied().unwrap_or(1.0)
+    }
+
+    /// Refractive index of the medium around the current one, which the path enters
+    /// when it leaves through the current medium's surface.
+    pub fn enclosing_eta(&self) -> f32 {
+        match self.eta_stack.len() {
+            0 | 1 => 1.0,
+            len => self.eta_stack[len - 2],
+        }
+    }
+
+    /// Ratio of refractive indices across a surface of a medium with index `eta`, from
+    /// the side the path is on to the other: entering it through a front face from the
+    /// current medium, or leaving it through a back face into the enclosing one.
+    pub fn refraction_ratio(&self, eta: f32, front_face: bool) -> f32 {
+        if front_face {
+            self.current_eta() / eta
+        } else {
+            eta / self.enclosing_eta()
+        }
+    }
+
+    /// Records a scattering event, consuming one unit of the bounce budget.
+    pub fn record_bounce(&mut self, kind: BounceKind) {
+        self.depth = self.depth.saturating_sub(1);
+        self.bounces += 1;
+        self.last_bounce = kind;
+    }
+
+    /// Whether the path must stop at a material allowing `max_bounces` (see
+    /// [`crate::traits::scatterable::Scatterable::max_bounces`]): either the shared
+    /// budget is spent or the path has already bounced as often as the material allows.
+    pub fn out_of_bounces(&self, max_bounces: Option<u32>) -> bool {
+        self.depth == 0 || max_bounces.is_some_and(|max| self.bounces >= max)
+    }
+
+    /// Credits radiance that reached the camera to the light group of its emitter, if
+    /// the path is tracking groups. Unknown groups count as ungrouped.
+    pub fn record_emission(&mut self, light_group: Option<usize>, radiance: vec::Vec3) {
+        let Some(ungrouped) = self.light_groups.len().checked_sub(1) else {
+            return;
+        };
+        let slot = light_group
+            .filter(|&group| group < ungrouped)
+            .unwrap_or(ungrouped);
+        self.light_groups[slot] = self.light_groups[slot] + radiance;
+    }
+
+    /// Appends the vertex built by `vertex` to the log; does nothing, without building
+    /// it, unless the path is being logged.
+    pub fn log_vertex(&mut self, vertex: impl FnOnce() -> PathVertex) {
+        if let Some(log) = self.log.as_mut() {
+            log.push(vertex());
+        }
+    }
+}
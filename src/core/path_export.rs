@@ -0,0 +1,216 @@
+//! Records a subset of traced paths and exports them as polylines for viewing light
+//! transport in a 3D viewer.
+//!
+//! Each path becomes one polyline from the camera through its bounce points. Paths that
+//! escape, including those that reach the sky, end in a segment along their last
+//! direction. OBJ files group the polylines by [`path::PathEnd`] so viewers can toggle
+//! them; PLY files store them as edges with per-vertex colors by the same reason.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::core::{path, render};
+use crate::math::vec;
+use crate::samplers::monte_carlo::MonteCarloSampler;
+
+const ENDS: [path::PathEnd; 5] = [
+    path::PathEnd::Escaped,
+    path::PathEnd::Light,
+    path::PathEnd::Absorbed,
+    path::PathEnd::DepthLimit,
+    path::PathEnd::ZeroPdf,
+];
+
+/// Paths recorded from a render, ready to export.
+pub struct PathSet {
+    pub paths: Vec<path::TracedPath>,
+    /// Length of the segment drawn along the last direction of escaping paths.
+    pub escape_length: f32,
+}
+
+impl PathSet {
+    /// Traces every sample of every `stride`-th pixel along both axes, exactly as the
+    /// render draws them (see [`crate::core::inspect::trace_pixel`]). Escaping paths are
+    /// drawn out to the size of the scene's content.
+    pub fn record(render: &render::Render, stride: u32) -> Self {
+        let stride = stride.max(1) as usize;
+        let (width, height) = (render.image_width(), render.image_height());
+        let sampler = MonteCarloSampler::new(
+            render.samples,
+            render.depth,
+            &render.camera,
+            &render.scene,
+            crate::trace_ray,
+        )
+        .with_ray_ranges(render.ray_ranges);
+
+        let rows: Vec<u32> = (0..height).step_by(stride).collect();
+        let paths = rows
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                let sampler = &sampler;
+                (0..width).step_by(stride).flat_map(move |x| {
+                    let mut rng = render.rng.pixel_rng(render.frame_seed(), x, y);
+                    sampler.sample_pixel_paths(&mut rng, x, y, width, height)
+                })
+            })
+            .collect();
+
+        let escape_length = render
+            .scene
+            .content_bounds()
+            .map(|bounds| {
+                let extent =
+                    vec::Vec3::new(bounds.x.length(), bounds.y.length(), bounds.z.length());
+                extent.length()
+            })
+            .filter(|length| length.is_finite() && *length > 0.0)
+            .unwrap_or(1.0);
+
+        PathSet {
+            paths,
+            escape_length,
+        }
+    }
+
+    /// Writes the paths as a Wavefront OBJ polyline set, one `g` group per
+    /// [`path::PathEnd`].
+    pub fn write_obj<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let polylines = self.polylines();
+        writeln!(writer, "# {} paths", polylines.len())?;
+        for (_, points) in polylines.iter() {
+            for point in points {
+                writeln!(writer, "v {} {} {}", point.x, point.y, point.z)?;
+            }
+        }
+
+        for end in ENDS {
+            let mut lines = polylines
+                .iter()
+                .scan(1_usize, |next, (path_end, points)| {
+                    let first = *next;
+                    *next += points.len();
+                    Some((*path_end, first, points.len()))
+                })
+                .filter(|(path_end, _, _)| *path_end == end)
+                .peekable();
+            if lines.peek().is_none() {
+                continue;
+            }
+            writeln!(writer, "g {}", end_name(end))?;
+            for (_, first, count) in lines {
+                write!(writer, "l")?;
+                for index in first..first + count {
+                    write!(writer, " {}", index)?;
+                }
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the paths as an ASCII PLY file of vertices and edges, colored by
+    /// [`path::PathEnd`]: gray escaped, yellow light, blue absorbed, red depth limit,
+    /// magenta zero pdf.
+    pub fn write_ply<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let polylines = self.polylines();
+        let vertex_count: usize = polylines.iter().map(|(_, points)| points.len()).sum();
+        let edge_count: usize = polylines
+            .iter()
+            .map(|(_, points)| points.len().saturating_sub(1))
+            .sum();
+
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format ascii 1.0")?;
+        writeln!(writer, "comment {} paths", polylines.len())?;
+        writeln!(writer, "element vertex {}", vertex_count)?;
+        writeln!(writer, "property float x")?;
+        writeln!(writer, "property float y")?;
+        writeln!(writer, "property float z")?;
+        writeln!(writer, "property uchar red")?;
+        writeln!(writer, "property uchar green")?;
+        writeln!(writer, "property uchar blue")?;
+        writeln!(writer, "element edge {}", edge_count)?;
+        writeln!(writer, "property int vertex1")?;
+        writeln!(writer, "property int vertex2")?;
+        writeln!(writer, "end_header")?;
+
+        for (end, points) in polylines.iter() {
+            let [r, g, b] = end_color(*end);
+            for point in points {
+                writeln!(
+                    writer,
+                    "{} {} {} {} {} {}",
+                    point.x, point.y, point.z, r, g, b
+                )?;
+            }
+        }
+        let mut first = 0;
+        for (_, points) in polylines.iter() {
+            for index in first + 1..first + points.len() {
+                writeln!(writer, "{} {}", index - 1, index)?;
+            }
+            first += points.len();
+        }
+        Ok(())
+    }
+
+    /// Writes the paths to `path`, as PLY for a `.ply` extension and OBJ otherwise.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ply") => self.write_ply(&mut writer)?,
+            _ => self.write_obj(&mut writer)?,
+        }
+        writer.flush()
+    }
+
+    /// The points of each path's polyline with its end reason.
+    fn polylines(&self) -> Vec<(path::PathEnd, Vec<vec::Point3>)> {
+        self.paths
+            .iter()
+            .map(|path| {
+                let mut points = vec![path.ray.origin];
+                let mut direction = path.ray.direction;
+                for vertex in path.vertices.iter() {
+                    // The sky's hit is at infinity; the escape segment stands in for it.
+                    if vertex.t == f32::MAX {
+                        break;
+                    }
+                    points.push(vertex.point);
+                    if let Some(scattered) = vertex.scattered {
+                        direction = scattered;
+                    }
+                }
+                let end = path.end();
+                if end == path::PathEnd::Escaped {
+                    let last = points[points.len() - 1];
+                    points.push(last + direction.normalize() * self.escape_length);
+                }
+                (end, points)
+            })
+            .collect()
+    }
+}
+
+fn end_name(end: path::PathEnd) -> &'static str {
+    match end {
+        path::PathEnd::Escaped => "escaped",
+        path::PathEnd::Light => "light",
+        path::PathEnd::Absorbed => "absorbed",
+        path::PathEnd::DepthLimit => "depth_limit",
+        path::PathEnd::ZeroPdf => "zero_pdf",
+    }
+}
+
+fn end_color(end: path::PathEnd) -> [u8; 3] {
+    match end {
+        path::PathEnd::Escaped => [160, 160, 160],
+        path::PathEnd::Light => [255, 220, 0],
+        path::PathEnd::Absorbed => [60, 120, 255],
+        path::PathEnd::DepthLimit => [255, 60, 60],
+        path::PathEnd::ZeroPdf => [255, 0, 255],
+    }
+}
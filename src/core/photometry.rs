@@ -0,0 +1,112 @@
+//! Real-world light units.
+//!
+//! Scenes without photometric settings are unitless: emission is whatever the textures
+//! say, and a radiance of 1 is displayed as white. For physically plausible scenes, lay
+//! them out in meters and read every radiance as luminance in nits (candela per square
+//! meter). Lights can then be given their output from a spec sheet ([`LightPower`],
+//! fitted to the emitting surface by
+//! [`crate::materials::diffuse_light::DiffuseLight::with_power`]), and the film holds
+//! absolute luminance that a camera exposure ([`exposure_scale`]) brings into display
+//! range, as a real camera would.
+use serde::{Deserialize, Serialize};
+
+use crate::core::{color, ray};
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+
+/// Lumens per watt of light at 555 nm, where the eye is most sensitive; the most any
+/// light can give per watt it radiates.
+pub const MAX_LUMINOUS_EFFICACY: f32 = 683.0;
+
+/// Luminance that just saturates an ISO 100 sensor at EV 0, from the saturation-based
+/// speed of ISO 12232 (78 / (100 * 0.65)).
+const SATURATION_LUMINANCE: f32 = 1.2;
+
+/// Surface points drawn to measure an emitter's flux.
+const FLUX_SAMPLES: u64 = 1024;
+
+/// How far off the surface emission is probed, relative to the size of the coordinates
+/// there.
+const PROBE_OFFSET: f32 = 1e-3;
+
+/// Ray time emitters are measured at, the middle of the shutter interval.
+const MEASURE_TIME: f64 = 0.5;
+
+/// Total light output of an emitter, as `{ lumens = 800 }` or
+/// `{ watts = 60, efficacy = 15 }` in scene files.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LightPower {
+    /// Luminous flux, as printed on bulb packaging and fixture spec sheets.
+    Lumens { lumens: f32 },
+    /// Power in watts, turned into lumens at `efficacy` lumens per watt. The default
+    /// efficacy, [`MAX_LUMINOUS_EFFICACY`], reads `watts` as radiated light; for a lamp's
+    /// electrical rating give its efficacy instead (about 15 for incandescent bulbs, 100
+    /// for LEDs).
+    Watts {
+        watts: f32,
+        #[serde(default = "default_efficacy")]
+        efficacy: f32,
+    },
+}
+
+impl LightPower {
+    /// The output in lumens.
+    pub fn lumens(&self) -> f32 {
+        match self {
+            LightPower::Lumens { lumens } => *lumens,
+            LightPower::Watts { watts, efficacy } => watts * efficacy,
+        }
+    }
+}
+
+fn default_efficacy() -> f32 {
+    MAX_LUMINOUS_EFFICACY
+}
+
+/// Factor that scales film luminance (in nits) for display at `ev100`, the exposure
+/// value at ISO 100: a luminance of `1.2 * 2^ev100` nits comes out white. Bright
+/// daylight is around EV 15, an overcast day 12, a lit interior 5 to 7.
+pub fn exposure_scale(ev100: f32) -> f32 {
+    1.0 / (SATURATION_LUMINANCE * 2.0_f32.powf(ev100))
+}
+
+/// Luminous flux leaving `geometry` when its surface emits the radiance `emission`
+/// returns for a hit, in lumens if that radiance is in nits and the geometry is in
+/// meters.
+///
+/// Each side of the surface counts if light leaving straight out of it gets away, so a
+/// quad emits from both faces while a sphere or closed mesh only counts its outside.
+/// `None` if the geometry can't draw points on its surface.
+pub fn emitted_flux(
+    geometry: &dyn hittable::Hittable,
+    emission: impl Fn(&hittable::Hit) -> vec::Vec3,
+) -> Option<f32> {
+    // A fixed generator keeps the measurement, and so the light, the same every load.
+    let mut rng = rng::hashed_rng(0);
+    let mut sampled = false;
+    let mut total = 0.0;
+    for _ in 0..FLUX_SAMPLES {
+        let Some(sample) = geometry.sample_surface(&mut rng, MEASURE_TIME) else {
+            continue;
+        };
+        sampled = true;
+        let point = sample.point;
+        let offset = PROBE_OFFSET * (1.0 + point.x.abs().max(point.y.abs()).max(point.z.abs()));
+        for side in [1.0_f32, -1.0] {
+            let normal = sample.normal * side;
+            let above = point + normal * offset;
+            let outward = ray::Ray::new(&above, &normal, Some(MEASURE_TIME));
+            if geometry.hit(&outward, 0.0, f32::MAX).is_some() {
+                continue;
+            }
+            let probe = ray::Ray::new(&above, &-normal, Some(MEASURE_TIME));
+            let Some(hit) = geometry.hit(&probe, 0.0, 2.0 * offset) else {
+                continue;
+            };
+            // A Lambertian emitter of radiance L sends out pi * L per unit area.
+            total += std::f32::consts::PI * color::luminance(emission(&hit)) / sample.pdf;
+        }
+    }
+    sampled.then(|| total / FLUX_SAMPLES as f32)
+}
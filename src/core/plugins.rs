@@ -0,0 +1,174 @@
+//! Materials and textures defined outside this crate.
+//!
+//! Scene files name their materials and textures by tag (`sampleable = "Lambertian"`,
+//! `texturable = "Checker"`). A downstream crate with its own [`Scatterable`] or
+//! [`Texturable`] type can give it a tag of its own with [`register_material`] or
+//! [`register_texture`], and scene files can then use it like a built-in one:
+//!
+//! ```toml
+//! [[materials]]
+//! id = 3
+//! sampleable = "Suede"
+//!
+//! [materials.data]
+//! color = [0.4, 0.05, 0.1]
+//! ```
+//!
+//! The entry's `data` is deserialized into the registered type, which is then used as the
+//! material as is. Saving a scene (e.g. with
+//! [`crate::core::scene_file::SceneFile::from_render`]) writes registered types back out
+//! under their tags. Registered tags take precedence over built-in ones. Register plugins
+//! at startup, before loading scenes; a scene file naming an unregistered tag fails to
+//! load just as one naming an unknown built-in would.
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::core::scene_file::SceneFileError;
+use crate::traits::scatterable::Scatterable;
+use crate::traits::texturable::Texturable;
+
+/// A registered type: its tag, how to build it from `data`, and how to save it back as
+/// `data` (`None` if a value isn't of the type).
+struct Plugin<B, S> {
+    tag: String,
+    build: B,
+    save: S,
+}
+
+type MaterialPlugin = Plugin<
+    fn(Option<toml::Value>) -> Result<Arc<dyn Scatterable + Send + Sync>, SceneFileError>,
+    fn(&dyn Scatterable) -> Option<Result<Option<toml::Value>, SceneFileError>>,
+>;
+
+type TexturePlugin = Plugin<
+    fn(Option<toml::Value>) -> Result<Box<dyn Texturable + Send + Sync>, SceneFileError>,
+    fn(&dyn Texturable) -> Option<Result<Option<toml::Value>, SceneFileError>>,
+>;
+
+struct Plugins {
+    materials: Vec<MaterialPlugin>,
+    textures: Vec<TexturePlugin>,
+}
+
+static PLUGINS: RwLock<Plugins> = RwLock::new(Plugins {
+    materials: Vec::new(),
+    textures: Vec::new(),
+});
+
+/// Registers `T` as the material scene files name `tag`, replacing any type registered
+/// under it before.
+pub fn register_material<T>(tag: &str)
+where
+    T: Scatterable + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    let mut plugins = PLUGINS.write().expect("plugin registry lock");
+    plugins.materials.retain(|plugin| plugin.tag != tag);
+    plugins.materials.push(Plugin {
+        tag: tag.to_string(),
+        build: |data| Ok(Arc::new(from_data::<T>(data)?)),
+        save: |material| material.as_any().downcast_ref::<T>().map(to_data),
+    });
+}
+
+/// Registers `T` as the texture scene files name `tag`, replacing any type registered
+/// under it before.
+pub fn register_texture<T>(tag: &str)
+where
+    T: Texturable + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    let mut plugins = PLUGINS.write().expect("plugin registry lock");
+    plugins.textures.retain(|plugin| plugin.tag != tag);
+    plugins.textures.push(Plugin {
+        tag: tag.to_string(),
+        build: |data| Ok(Box::new(from_data::<T>(data)?)),
+        save: |texture| texture.as_any().downcast_ref::<T>().map(to_data),
+    });
+}
+
+/// Unregisters every plugin.
+pub fn clear() {
+    let mut plugins = PLUGINS.write().expect("plugin registry lock");
+    plugins.materials.clear();
+    plugins.textures.clear();
+}
+
+/// Whether a material is registered under `tag`.
+pub(crate) fn has_material(tag: &str) -> bool {
+    let plugins = PLUGINS.read().expect("plugin registry lock");
+    plugins.materials.iter().any(|plugin| plugin.tag == tag)
+}
+
+/// Whether a texture is registered under `tag`.
+pub(crate) fn has_texture(tag: &str) -> bool {
+    let plugins = PLUGINS.read().expect("plugin registry lock");
+    plugins.textures.iter().any(|plugin| plugin.tag == tag)
+}
+
+/// Builds the material registered under `tag` from its scene-file `data`.
+pub(crate) fn build_material(
+    tag: &str,
+    data: Option<toml::Value>,
+) -> Result<Arc<dyn Scatterable + Send + Sync>, SceneFileError> {
+    let build = PLUGINS
+        .read()
+        .expect("plugin registry lock")
+        .materials
+        .iter()
+        .find(|plugin| plugin.tag == tag)
+        .map(|plugin| plugin.build)
+        .ok_or_else(|| SceneFileError::UnsupportedMaterial(tag.to_string()))?;
+    build(data)
+}
+
+/// Builds the texture registered under `tag` from its scene-file `data`.
+pub(crate) fn build_texture(
+    tag: &str,
+    data: Option<toml::Value>,
+) -> Result<Box<dyn Texturable + Send + Sync>, SceneFileError> {
+    let build = PLUGINS
+        .read()
+        .expect("plugin registry lock")
+        .textures
+        .iter()
+        .find(|plugin| plugin.tag == tag)
+        .map(|plugin| plugin.build)
+        .ok_or_else(|| SceneFileError::UnsupportedTexture(tag.to_string()))?;
+    build(data)
+}
+
+/// The tag and scene-file `data` of `material`, if its type is registered.
+pub(crate) fn save_material(
+    material: &dyn Scatterable,
+) -> Option<Result<(String, Option<toml::Value>), SceneFileError>> {
+    let plugins = PLUGINS.read().expect("plugin registry lock");
+    plugins.materials.iter().find_map(|plugin| {
+        (plugin.save)(material).map(|data| data.map(|data| (plugin.tag.clone(), data)))
+    })
+}
+
+/// The tag and scene-file `data` of `texture`, if its type is registered.
+pub(crate) fn save_texture(
+    texture: &dyn Texturable,
+) -> Option<Result<(String, Option<toml::Value>), SceneFileError>> {
+    let plugins = PLUGINS.read().expect("plugin registry lock");
+    plugins.textures.iter().find_map(|plugin| {
+        (plugin.save)(texture).map(|data| data.map(|data| (plugin.tag.clone(), data)))
+    })
+}
+
+/// Deserializes a `T` from an entry's `data`, or from an empty table if it has none.
+fn from_data<T: DeserializeOwned>(data: Option<toml::Value>) -> Result<T, SceneFileError> {
+    let data = data.unwrap_or_else(|| toml::Value::Table(toml::Table::new()));
+    Ok(data.try_into()?)
+}
+
+/// Serializes `value` as an entry's `data`, leaving it out if it's an empty table.
+fn to_data<T: Serialize>(value: &T) -> Result<Option<toml::Value>, SceneFileError> {
+    let data = toml::Value::try_from(value)?;
+    Ok(match &data {
+        toml::Value::Table(table) if table.is_empty() => None,
+        _ => Some(data),
+    })
+}
@@ -0,0 +1,76 @@
+//! Hooks that customize scenes as they load.
+//!
+//! Pipelines often need every scene adjusted the same way: a ground plane or turntable
+//! added, materials swapped for a clay render, lights tagged for compositing. Rather than
+//! forking the loader, register a hook once at startup and every scene built by
+//! [`crate::core::scene_file::SceneFile::into_render`] (and so by `load_render`, bundles,
+//! and thumbnails) passes through it:
+//!
+//! - [`on_scene_file`] hooks see the parsed [`SceneFile`] before anything is built, so
+//!   they can add, remove, or edit geometry, material, and object entries as data.
+//! - [`on_scene`] hooks see the assembled [`scene::Scene`] just before its BVH is built,
+//!   so they can add objects and lights built in code, including ones scene files can't
+//!   describe.
+//!
+//! Hooks run in the order they were registered, on whichever thread loads the scene; the
+//! first error aborts the load.
+use std::sync::{Arc, RwLock};
+
+use crate::core::scene;
+use crate::core::scene_file::{SceneFile, SceneFileError};
+
+/// Edits a scene file before it's built.
+pub type SceneFileHook = Arc<dyn Fn(&mut SceneFile) -> Result<(), SceneFileError> + Send + Sync>;
+
+/// Edits a built scene before its BVH is built.
+pub type SceneHook = Arc<dyn Fn(&mut scene::Scene) -> Result<(), SceneFileError> + Send + Sync>;
+
+struct Hooks {
+    scene_files: Vec<SceneFileHook>,
+    scenes: Vec<SceneHook>,
+}
+
+static HOOKS: RwLock<Hooks> = RwLock::new(Hooks {
+    scene_files: Vec::new(),
+    scenes: Vec::new(),
+});
+
+/// Registers `hook` to run on every scene file as it's built.
+pub fn on_scene_file(
+    hook: impl Fn(&mut SceneFile) -> Result<(), SceneFileError> + Send + Sync + 'static,
+) {
+    let mut hooks = HOOKS.write().expect("preprocess hooks lock");
+    hooks.scene_files.push(Arc::new(hook));
+}
+
+/// Registers `hook` to run on every scene before its BVH is built.
+pub fn on_scene(
+    hook: impl Fn(&mut scene::Scene) -> Result<(), SceneFileError> + Send + Sync + 'static,
+) {
+    let mut hooks = HOOKS.write().expect("preprocess hooks lock");
+    hooks.scenes.push(Arc::new(hook));
+}
+
+/// Unregisters every hook.
+pub fn clear() {
+    let mut hooks = HOOKS.write().expect("preprocess hooks lock");
+    hooks.scene_files.clear();
+    hooks.scenes.clear();
+}
+
+/// Runs the registered scene-file hooks on `file`.
+pub(crate) fn run_scene_file_hooks(file: &mut SceneFile) -> Result<(), SceneFileError> {
+    // Cloned out of the lock so hooks may register more hooks.
+    let hooks = HOOKS
+        .read()
+        .expect("preprocess hooks lock")
+        .scene_files
+        .clone();
+    hooks.iter().try_for_each(|hook| hook(file))
+}
+
+/// Runs the registered scene hooks on `scene`.
+pub(crate) fn run_scene_hooks(scene: &mut scene::Scene) -> Result<(), SceneFileError> {
+    let hooks = HOOKS.read().expect("preprocess hooks lock").scenes.clone();
+    hooks.iter().try_for_each(|hook| hook(scene))
+}
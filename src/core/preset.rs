@@ -0,0 +1,91 @@
+//! Named quality presets for render settings.
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// A named quality level that maps to sampling settings.
+///
+/// Presets cover the settings the renderer currently exposes (samples per pixel
+/// and bounce depth); scene files can override individual values per preset.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// Fast, noisy renders for blocking out a scene.
+    Draft,
+    /// Reasonably clean renders for iterating on look.
+    #[default]
+    Preview,
+    /// Converged output.
+    Final,
+}
+
+/// Settings a preset resolves to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PresetSettings {
+    pub samples: u32,
+    pub depth: u32,
+}
+
+/// Per-preset overrides from a scene file; unset fields keep the preset's value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresetOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub samples: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Draft, Preset::Preview, Preset::Final];
+
+    /// Returns the built-in settings for this preset.
+    pub fn settings(&self) -> PresetSettings {
+        match self {
+            Preset::Draft => PresetSettings {
+                samples: 16,
+                depth: 8,
+            },
+            Preset::Preview => PresetSettings {
+                samples: 100,
+                depth: 25,
+            },
+            Preset::Final => PresetSettings {
+                samples: 10000,
+                depth: 50,
+            },
+        }
+    }
+
+    /// Returns this preset's settings with a scene file's overrides applied.
+    pub fn resolve(&self, overrides: Option<&PresetOverride>) -> PresetSettings {
+        let settings = self.settings();
+        let Some(overrides) = overrides else {
+            return settings;
+        };
+        PresetSettings {
+            samples: overrides.samples.unwrap_or(settings.samples),
+            depth: overrides.depth.unwrap_or(settings.depth),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Draft => "draft",
+            Preset::Preview => "preview",
+            Preset::Final => "final",
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Preset::ALL
+            .into_iter()
+            .find(|preset| preset.name() == s)
+            .ok_or_else(|| format!("unknown preset '{}' (expected draft, preview, or final)", s))
+    }
+}
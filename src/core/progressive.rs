@@ -0,0 +1,483 @@
+//! Progressive, adaptive rendering by tiles.
+//!
+//! The image is split into square tiles that are refined over repeated passes. Every
+//! tile gets a first batch of samples; after that each pass ranks the unfinished tiles
+//! with a [`TilePriority`] strategy and spends its samples on the top of the list, so by
+//! default the noisiest regions (glass, caustics) are refined first. A tile is finished
+//! once its estimated error drops below the error threshold or it reaches
+//! `render.samples` samples per pixel.
+//!
+//! A render stopped early (see [`render::Render::interrupt`]) can save its tiles'
+//! running sums to a checkpoint file and be resumed from it later; each tile carries on
+//! with the samples it would have drawn next. Tiles are always path traced a camera path
+//! at a time, whatever `render.integrator` says, so checkpoints are only for such renders;
+//! interrupted wavefront and SPPM renders keep the passes they finished but can't resume.
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::core::{color, events, film, inspect, render};
+use crate::math::vec;
+use crate::samplers::monte_carlo::MonteCarloSampler;
+use crate::samplers::sampleable::Sampleable;
+
+/// First bytes of a checkpoint file written by [`ProgressiveRender::save_checkpoint`].
+const CHECKPOINT_MAGIC: &[u8; 4] = b"RRPC";
+
+/// What a [`TilePriority`] strategy gets to know about a tile.
+#[derive(Clone, Copy, Debug)]
+pub struct TileStats {
+    /// Pixel bounds of the tile, `[x_start, x_end) x [y_start, y_end)`.
+    pub x_start: u32,
+    pub x_end: u32,
+    pub y_start: u32,
+    pub y_end: u32,
+    /// Samples per pixel taken so far.
+    pub samples: u32,
+    /// Estimated relative standard error of the tile's pixels (RMS over the tile); zero
+    /// before the tile has two samples.
+    pub error: f32,
+}
+
+/// Ranks unfinished tiles; passes sample the highest-priority tiles first.
+pub trait TilePriority: Send + Sync {
+    fn priority(&self, tile: &TileStats) -> f32;
+}
+
+/// Noisiest tiles first: the priority is the tile's estimated error. The default.
+pub struct RemainingError;
+
+impl TilePriority for RemainingError {
+    fn priority(&self, tile: &TileStats) -> f32 {
+        tile.error
+    }
+}
+
+/// Least-sampled tiles first, ignoring noise; spreads samples evenly like a
+/// non-adaptive render.
+pub struct EvenSamples;
+
+impl TilePriority for EvenSamples {
+    fn priority(&self, tile: &TileStats) -> f32 {
+        -(tile.samples as f32)
+    }
+}
+
+/// Running per-pixel sums for one tile.
+struct Tile {
+    stats: TileStats,
+    /// Sum of the radiance samples of each pixel, row-major.
+    sum: Vec<vec::Vec3>,
+    /// Sum of the squared luminance of each pixel's samples, for the variance estimate.
+    sum_sq: Vec<f32>,
+}
+
+impl Tile {
+    fn finished(&self, max_samples: u32, error_threshold: f32) -> bool {
+        self.stats.samples >= max_samples
+            || (self.stats.samples >= 2 && self.stats.error < error_threshold)
+    }
+
+    /// Takes `samples` more samples for every pixel and refreshes the error estimate.
+    fn refine(&mut self, render: &render::Render, samples: u32) {
+        let (width, height) = (render.image_width(), render.image_height());
+        // One sample per call; the progressive schedule replaces stratification.
+        let sampler = MonteCarloSampler::new(
+            1,
+            render.depth,
+            &render.camera,
+            &render.scene,
+            crate::trace_ray,
+        )
+        .with_ray_ranges(render.ray_ranges);
+        let stats = self.stats;
+        let tile_width = stats.x_end - stats.x_start;
+        for y in stats.y_start..stats.y_end {
+            for x in stats.x_start..stats.x_end {
+                let index = ((y - stats.y_start) * tile_width + (x - stats.x_start)) as usize;
+                let mut rng = render
+                    .rng
+                    .pixel_rng_at(render.frame_seed(), x, y, stats.samples);
+                for _ in 0..samples {
+                    let sample = sampler.sample_pixel(&mut rng, x, y, width, height);
+                    self.sum[index] = self.sum[index] + sample;
+                    self.sum_sq[index] += color::luminance(sample) * color::luminance(sample);
+                }
+            }
+        }
+        self.stats.samples += samples;
+        self.stats.error = self.error();
+    }
+
+    fn error(&self) -> f32 {
+        let n = self.stats.samples as f32;
+        if self.stats.samples < 2 || self.sum.is_empty() {
+            return 0.0;
+        }
+        let squared: f32 = self
+            .sum
+            .iter()
+            .zip(self.sum_sq.iter())
+            .map(|(sum, sum_sq)| {
+                let mean = color::luminance(*sum) / n;
+                let variance = ((sum_sq / n - mean * mean) * n / (n - 1.0)).max(0.0);
+                // Relative to the pixel's brightness, floored so dark pixels don't dominate.
+                variance / n / (mean.max(0.0) + 0.01).powi(2)
+            })
+            .sum();
+        (squared / self.sum.len() as f32).sqrt()
+    }
+}
+
+/// A render refined tile by tile over repeated passes; see the module docs.
+///
+/// Call [`ProgressiveRender::step`] until it returns `false` (or just
+/// [`ProgressiveRender::run`]), taking [`ProgressiveRender::film`] whenever a preview is
+/// wanted. Each tile's samples are seeded from [`render::Render::frame_seed`] and the
+/// tile's sample count, so the same settings always produce the same image.
+pub struct ProgressiveRender<'a> {
+    render: &'a render::Render,
+    tiles: Vec<Tile>,
+    priority: Box<dyn TilePriority>,
+    /// Samples per pixel a tile gets each time it is scheduled.
+    pub samples_per_pass: u32,
+    /// Estimated relative error below which a tile stops receiving samples.
+    pub error_threshold: f32,
+    /// Fraction of the unfinished tiles, highest priority first, sampled by each pass
+    /// after the first.
+    pub pass_fraction: f32,
+    passes: u32,
+}
+
+impl<'a> ProgressiveRender<'a> {
+    /// Prepares a progressive render in 32-pixel tiles, 4 samples per pass, stopping tiles
+    /// at 1% estimated error.
+    pub fn new(render: &'a render::Render) -> Self {
+        ProgressiveRender {
+            render,
+            tiles: Vec::new(),
+            priority: Box::new(RemainingError),
+            samples_per_pass: 4,
+            error_threshold: 0.01,
+            pass_fraction: 0.5,
+            passes: 0,
+        }
+        .with_tile_size(32)
+    }
+
+    /// Re-tiles the image; any samples taken so far are discarded.
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        let tile_size = tile_size.max(1);
+        let (width, height) = (self.render.image_width(), self.render.image_height());
+        self.tiles = (0..height)
+            .step_by(tile_size as usize)
+            .flat_map(|y_start| {
+                (0..width).step_by(tile_size as usize).map(move |x_start| {
+                    let x_end = (x_start + tile_size).min(width);
+                    let y_end = (y_start + tile_size).min(height);
+                    let pixels = ((x_end - x_start) * (y_end - y_start)) as usize;
+                    Tile {
+                        stats: TileStats {
+                            x_start,
+                            x_end,
+                            y_start,
+                            y_end,
+                            samples: 0,
+                            error: 0.0,
+                        },
+                        sum: vec![vec::Vec3::default(); pixels],
+                        sum_sq: vec![0.0; pixels],
+                    }
+                })
+            })
+            .collect();
+        self.passes = 0;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Box<dyn TilePriority>) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_samples_per_pass(mut self, samples_per_pass: u32) -> Self {
+        self.samples_per_pass = samples_per_pass.max(1);
+        self
+    }
+
+    pub fn with_error_threshold(mut self, error_threshold: f32) -> Self {
+        self.error_threshold = error_threshold;
+        self
+    }
+
+    pub fn with_pass_fraction(mut self, pass_fraction: f32) -> Self {
+        self.pass_fraction = pass_fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Runs one pass. The first pass samples every tile; later ones sample the
+    /// highest-priority `pass_fraction` of the unfinished tiles. Returns `false`, without
+    /// sampling, once every tile is finished or the render has been interrupted; tiles
+    /// still waiting their turn when it is interrupted are skipped.
+    pub fn step(&mut self) -> bool {
+        if self.render.interrupted() {
+            return false;
+        }
+        let max_samples = self.render.samples.max(1);
+        let error_threshold = self.error_threshold;
+        let mut pending: Vec<usize> = (0..self.tiles.len())
+            .filter(|&index| !self.tiles[index].finished(max_samples, error_threshold))
+            .collect();
+        if pending.is_empty() {
+            return false;
+        }
+
+        if self.passes > 0 {
+            let priorities: Vec<f32> = self
+                .tiles
+                .iter()
+                .map(|tile| self.priority.priority(&tile.stats))
+                .collect();
+            // Stable, so equal priorities keep image order.
+            pending.sort_by(|a, b| priorities[*b].total_cmp(&priorities[*a]));
+            let count = (pending.len() as f32 * self.pass_fraction).ceil() as usize;
+            pending.truncate(count.max(1));
+        }
+
+        let render = self.render;
+        let samples_per_pass = self.samples_per_pass;
+        let mut selected = vec![false; self.tiles.len()];
+        for index in pending {
+            selected[index] = true;
+        }
+        let mut scheduled: Vec<&mut Tile> = self
+            .tiles
+            .iter_mut()
+            .zip(selected)
+            .filter_map(|(tile, selected)| selected.then_some(tile))
+            .collect();
+        scheduled.par_iter_mut().for_each(|tile| {
+            if render.interrupted() {
+                return;
+            }
+            let samples = samples_per_pass.min(max_samples - tile.stats.samples);
+            tile.refine(render, samples);
+            render.emit(events::RenderEvent::TileDone {
+                x_start: tile.stats.x_start,
+                x_end: tile.stats.x_end,
+                y_start: tile.stats.y_start,
+                y_end: tile.stats.y_end,
+                samples: tile.stats.samples,
+            });
+        });
+
+        self.passes += 1;
+        if render.events.is_some() {
+            render.emit(events::RenderEvent::PassDone {
+                pass: self.passes,
+                samples_per_pixel: self.samples_per_pixel(),
+                tiles_remaining: self
+                    .tiles
+                    .iter()
+                    .filter(|tile| !tile.finished(max_samples, error_threshold))
+                    .count(),
+            });
+        }
+        true
+    }
+
+    /// Runs passes until every tile is finished and returns the final film.
+    pub fn run(&mut self) -> film::Film {
+        while self.step() {}
+        self.film()
+    }
+
+    /// Current estimate of the image: each pixel's mean over the samples taken so far.
+    pub fn film(&self) -> film::Film {
+        let render = self.render;
+        let film = film::Film::with_precision(
+            render.image_width(),
+            render.image_height(),
+            render.film_precision,
+        );
+        for tile in self.tiles.iter() {
+            let stats = &tile.stats;
+            if stats.samples == 0 {
+                continue;
+            }
+            let scale = 1.0 / stats.samples as f32;
+            let tile_width = stats.x_end - stats.x_start;
+            for (index, sum) in tile.sum.iter().enumerate() {
+                let x = stats.x_start + index as u32 % tile_width;
+                let y = stats.y_start + index as u32 / tile_width;
+                film.splat(x, y, *sum * scale);
+            }
+        }
+        film
+    }
+
+    /// Re-traces pixel `(x, y)` with every path logged (see [`inspect`]), reproducing the
+    /// samples it has received so far pass by pass. `None` if the pixel is off the image.
+    pub fn inspect(&self, x: u32, y: u32) -> Option<inspect::PixelTrace> {
+        let tile = self.tiles.iter().find(|tile| {
+            let stats = &tile.stats;
+            (stats.x_start..stats.x_end).contains(&x) && (stats.y_start..stats.y_end).contains(&y)
+        })?;
+        let render = self.render;
+        let sampler = MonteCarloSampler::new(
+            1,
+            render.depth,
+            &render.camera,
+            &render.scene,
+            crate::trace_ray,
+        )
+        .with_ray_ranges(render.ray_ranges);
+        let (width, height) = (render.image_width(), render.image_height());
+
+        // Every pass but a tile's last took `samples_per_pass` samples from a generator
+        // seeded with the sample count at the start of the pass.
+        let mut samples = Vec::with_capacity(tile.stats.samples as usize);
+        for first_sample in (0..tile.stats.samples).step_by(self.samples_per_pass as usize) {
+            let count = self.samples_per_pass.min(tile.stats.samples - first_sample);
+            let mut rng = render
+                .rng
+                .pixel_rng_at(render.frame_seed(), x, y, first_sample);
+            for index in first_sample..first_sample + count {
+                let path = sampler.sample_pixel_paths(&mut rng, x, y, width, height);
+                samples.extend(path.into_iter().map(|path| (index as usize, path)));
+            }
+        }
+        Some(inspect::PixelTrace::new(&render.scene, x, y, samples))
+    }
+
+    /// Writes the samples taken so far to a checkpoint file at `path`, from which
+    /// [`ProgressiveRender::resume`] can carry on later.
+    pub fn save_checkpoint(&self, path: &Path) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(CHECKPOINT_MAGIC)?;
+        let (width, height) = (self.render.image_width(), self.render.image_height());
+        for value in [width, height, self.tiles.len() as u32, self.passes] {
+            file.write_all(&value.to_le_bytes())?;
+        }
+        for tile in &self.tiles {
+            let stats = &tile.stats;
+            for value in [
+                stats.x_start,
+                stats.x_end,
+                stats.y_start,
+                stats.y_end,
+                stats.samples,
+            ] {
+                file.write_all(&value.to_le_bytes())?;
+            }
+            file.write_all(&stats.error.to_le_bytes())?;
+            for (sum, sum_sq) in tile.sum.iter().zip(&tile.sum_sq) {
+                for value in [sum.x, sum.y, sum.z, *sum_sq] {
+                    file.write_all(&value.to_le_bytes())?;
+                }
+            }
+        }
+        file.flush()
+    }
+
+    /// Restores the samples saved by [`ProgressiveRender::save_checkpoint`], so the next
+    /// pass continues where that render stopped. The checkpoint must come from a render
+    /// of the same size and tiling; that it's of the same scene and settings is up to the
+    /// caller. Fails, leaving this render as it was, if the file doesn't match.
+    pub fn resume(&mut self, path: &Path) -> io::Result<()> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut magic = [0_u8; 4];
+        file.read_exact(&mut magic)?;
+        if magic != *CHECKPOINT_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a progressive render checkpoint",
+            ));
+        }
+        let mut read_u32 = || -> io::Result<u32> {
+            let mut bytes = [0_u8; 4];
+            file.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        };
+        let (width, height, tile_count, passes) =
+            (read_u32()?, read_u32()?, read_u32()?, read_u32()?);
+        if (width, height, tile_count as usize)
+            != (
+                self.render.image_width(),
+                self.render.image_height(),
+                self.tiles.len(),
+            )
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "checkpoint is of a {}x{} image in {} tiles, not {}x{} in {}",
+                    width,
+                    height,
+                    tile_count,
+                    self.render.image_width(),
+                    self.render.image_height(),
+                    self.tiles.len()
+                ),
+            ));
+        }
+
+        let mut tiles = Vec::with_capacity(self.tiles.len());
+        for tile in &self.tiles {
+            let bounds = [read_u32()?, read_u32()?, read_u32()?, read_u32()?];
+            let stats = &tile.stats;
+            if bounds != [stats.x_start, stats.x_end, stats.y_start, stats.y_end] {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "checkpoint tiles don't match this render's",
+                ));
+            }
+            let samples = read_u32()?;
+            let error = f32::from_bits(read_u32()?);
+            let mut sum = Vec::with_capacity(tile.sum.len());
+            let mut sum_sq = Vec::with_capacity(tile.sum_sq.len());
+            for _ in 0..tile.sum.len() {
+                let [x, y, z, squared] = [(); 4].map(|_| read_u32().map(f32::from_bits));
+                sum.push(vec::Vec3::new(x?, y?, z?));
+                sum_sq.push(squared?);
+            }
+            tiles.push(Tile {
+                stats: TileStats {
+                    samples,
+                    error,
+                    ..*stats
+                },
+                sum,
+                sum_sq,
+            });
+        }
+        self.tiles = tiles;
+        self.passes = passes;
+        Ok(())
+    }
+
+    /// Number of passes run so far.
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    /// Samples per pixel taken so far, averaged over the image.
+    pub fn samples_per_pixel(&self) -> f32 {
+        let (samples, pixels) = self.tiles().fold((0.0, 0.0), |(samples, pixels), tile| {
+            let area = ((tile.x_end - tile.x_start) * (tile.y_end - tile.y_start)) as f64;
+            (samples + tile.samples as f64 * area, pixels + area)
+        });
+        if pixels > 0.0 {
+            (samples / pixels) as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Current statistics of every tile, in image order.
+    pub fn tiles(&self) -> impl Iterator<Item = &TileStats> {
+        self.tiles.iter().map(|tile| &tile.stats)
+    }
+}
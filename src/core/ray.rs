@@ -1,7 +1,10 @@
 //! Ray representation used for casting through the scene.
 use serde::{Deserialize, Serialize};
 
+use crate::core::scene;
 use crate::math::vec;
+use crate::traits::hittable;
+use crate::traits::renderable::Renderable;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 /// A half-infinite line defined by an origin and direction, with time parameter.
@@ -26,3 +29,96 @@ impl Ray {
         self.origin + self.direction * t
     }
 }
+
+/// The span of ray parameter `t` within which a ray accepts hits.
+///
+/// Like the `t` of a hit, these are multiples of the ray's direction: scattered rays
+/// mostly have unit directions, so for them this is a world distance, while camera rays
+/// cross the focus plane at `t = 1`. `t_min` keeps a ray leaving a surface from hitting
+/// that same surface again through rounding error ("acne"); too small and large scenes
+/// speckle, too large and rays skip through thin gaps and small scenes leak light.
+/// `t_max` clips hits beyond it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RayRange {
+    #[serde(default = "default_t_min")]
+    pub t_min: f32,
+    #[serde(default = "default_t_max")]
+    pub t_max: f32,
+}
+
+fn default_t_min() -> f32 {
+    0.001
+}
+
+fn default_t_max() -> f32 {
+    f32::MAX
+}
+
+impl Default for RayRange {
+    fn default() -> Self {
+        RayRange {
+            t_min: default_t_min(),
+            t_max: default_t_max(),
+        }
+    }
+}
+
+impl RayRange {
+    pub fn new(t_min: f32, t_max: f32) -> Self {
+        RayRange { t_min, t_max }
+    }
+
+    /// Closest hit of `ray` in `scene` within the range. A ray whose hits are all
+    /// clipped by `t_max` sees the sky, as if nothing were there.
+    pub fn hit<'a>(&self, scene: &'a scene::Scene, ray: &Ray) -> Option<hittable::HitRecord<'a>> {
+        let hit = scene.hit(ray, self.t_min, self.t_max);
+        if hit.is_some() || self.t_max == f32::MAX {
+            return hit;
+        }
+        // Unbounded backgrounds only answer queries that reach infinity.
+        scene.hit(ray, f32::MAX, f32::MAX)
+    }
+
+    /// Closest hits of a packet of rays sharing the range, like [`RayRange::hit`] for
+    /// each.
+    pub fn hit_batch<'a>(
+        &self,
+        scene: &'a scene::Scene,
+        rays: &[Ray],
+    ) -> Vec<Option<hittable::HitRecord<'a>>> {
+        let mut hits = scene.hit_batch(rays, self.t_min, self.t_max);
+        if self.t_max < f32::MAX {
+            for (ray, hit) in rays.iter().zip(hits.iter_mut()) {
+                if hit.is_none() {
+                    *hit = scene.hit(ray, f32::MAX, f32::MAX);
+                }
+            }
+        }
+        hits
+    }
+}
+
+/// The [`RayRange`] of each kind of ray a render traces, e.g.
+/// `rays = { indirect = { t_min = 0.05 } }` to raise the bounce offset where a city-sized
+/// scene shows acne. These are epsilons for self-intersection and light leaks; to cut
+/// geometry away near or far from the camera, use [`Camera::near_clip`] and
+/// [`Camera::far_clip`], which are depths in world units.
+///
+/// [`Camera::near_clip`]: crate::core::camera::Camera::near_clip
+/// [`Camera::far_clip`]: crate::core::camera::Camera::far_clip
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RayRanges {
+    /// Rays from the camera, which leave the lens rather than a surface, so the default
+    /// rarely needs changing.
+    #[serde(default)]
+    pub camera: RayRange,
+    /// Rays scattered off surfaces and through media, and photons.
+    #[serde(default)]
+    pub indirect: RayRange,
+}
+
+impl RayRanges {
+    pub fn is_default(&self) -> bool {
+        *self == RayRanges::default()
+    }
+}
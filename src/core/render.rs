@@ -1,9 +1,181 @@
-use crate::core::{camera, scene};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::core::{
+    aov, camera, color, events, film, overlay, photometry, preset, ray, resolution, scene, shutter,
+};
+use crate::integrators;
+use crate::math::rng;
+use crate::samplers::traversal;
 
 pub struct Render {
-    pub width: u32,
+    /// Output size in pixels at full resolution. Set it with
+    /// [`Render::set_resolution`] to keep the camera's aspect ratio in step.
+    pub resolution: resolution::Resolution,
     pub samples: u32,
     pub depth: u32,
     pub camera: camera::Camera,
     pub scene: scene::Scene,
+    /// Order in which pixels are visited within each render chunk.
+    pub pixel_order: traversal::PixelOrder,
+    /// How camera paths are scheduled through the scene.
+    pub integrator: integrators::Integrator,
+    /// Diffuse quantization error when converting the film to 8-bit output.
+    pub dither: bool,
+    /// Seed for per-pixel sampling and deterministic post-processing such as dithering;
+    /// the same seed and settings always reproduce the same image.
+    pub seed: u64,
+    /// Quality preset the sampling settings came from, if any.
+    pub preset: Option<preset::Preset>,
+    /// Scene-specific overrides applied when a preset is selected.
+    pub preset_overrides: BTreeMap<preset::Preset, preset::PresetOverride>,
+    /// Fraction of `resolution` to render at, for quick previews. Framing and aspect
+    /// ratio are unchanged; only the pixel grid gets coarser.
+    pub scale: f32,
+    /// Depths drawn white and black by [`aov::visualize_depth`]; fitted to the visible
+    /// depths when unset. The depth AOV itself is always in world units.
+    pub depth_range: Option<aov::DepthRange>,
+    /// Precision of the films this render produces; half precision halves their memory
+    /// for very large images.
+    pub film_precision: film::FilmPrecision,
+    /// Generator algorithm for per-pixel sampling.
+    pub rng: rng::RngAlgorithm,
+    /// Scene time the shutter is open over; maps ray time to seconds.
+    pub shutter: shutter::Shutter,
+    /// Whether an animation's frames share sampling noise or each get their own; see
+    /// [`Render::frame_seed`].
+    pub frame_noise: shutter::FrameNoise,
+    /// Camera exposure in EV at ISO 100, applied when encoding 8-bit output; films stay
+    /// in scene luminance (see [`photometry`]). `None` encodes radiance as is.
+    pub exposure: Option<f32>,
+    /// Burn samples per pixel, render time, and rays per second into the corner of
+    /// 8-bit output ([`overlay`]). Final-preset renders are never stamped.
+    pub stats_overlay: bool,
+    /// Hit distances accepted by camera and indirect rays.
+    pub ray_ranges: ray::RayRanges,
+    /// Primaries and transfer curve of encoded output, and of saved EXRs' primaries.
+    pub color_space: color::ColorSpace,
+    /// Receives progress events as tiles and passes finish (see [`events`]).
+    pub events: Option<events::EventSink>,
+    /// Once set, renderers stop taking samples and return what they have so far, leaving
+    /// unrendered pixels black (see [`crate::core::interrupt`]).
+    pub interrupt: Option<Arc<AtomicBool>>,
+}
+
+impl Render {
+    /// Bundles a scene and camera with output settings; other options take their defaults.
+    /// The camera's aspect ratio is changed to `resolution`'s if it doesn't fit it.
+    pub fn new(
+        resolution: resolution::Resolution,
+        samples: u32,
+        depth: u32,
+        camera: camera::Camera,
+        scene: scene::Scene,
+    ) -> Self {
+        Render {
+            resolution,
+            samples,
+            depth,
+            camera: fit_camera(camera, &resolution),
+            scene,
+            pixel_order: traversal::PixelOrder::default(),
+            integrator: integrators::Integrator::default(),
+            dither: false,
+            seed: 0,
+            preset: None,
+            preset_overrides: BTreeMap::new(),
+            scale: 1.0,
+            depth_range: None,
+            film_precision: film::FilmPrecision::default(),
+            rng: rng::RngAlgorithm::default(),
+            shutter: shutter::Shutter::default(),
+            frame_noise: shutter::FrameNoise::default(),
+            exposure: None,
+            stats_overlay: false,
+            ray_ranges: ray::RayRanges::default(),
+            color_space: color::ColorSpace::default(),
+            events: None,
+            interrupt: None,
+        }
+    }
+
+    /// Seed the pixels of this frame are sampled and dithered from: `seed`, mixed with
+    /// the shutter for [`shutter::FrameNoise::PerFrame`] noise.
+    pub fn frame_seed(&self) -> u64 {
+        self.frame_noise.seed(self.seed, &self.shutter)
+    }
+
+    /// Quantizes a rendered film to RGB8 output at the render's exposure in its color
+    /// space, dithering if enabled.
+    pub fn encode(&self, film: &film::Film) -> Vec<u8> {
+        let scale = self.exposure.map_or(1.0, photometry::exposure_scale);
+        if self.dither {
+            film.to_rgb8_dithered(self.frame_seed(), scale, self.color_space)
+        } else {
+            film.to_rgb8_scaled(scale, self.color_space)
+        }
+    }
+
+    /// Reports `event` to the render's event sink, if it has one.
+    pub fn emit(&self, event: events::RenderEvent) {
+        if let Some(events) = &self.events {
+            events(&event);
+        }
+    }
+
+    /// Whether the render has been asked to stop early.
+    pub fn interrupted(&self) -> bool {
+        self.interrupt
+            .as_ref()
+            .is_some_and(|interrupt| interrupt.load(Ordering::Relaxed))
+    }
+
+    /// Stamps `stats` onto `data`, this render's encoded output, if `stats_overlay` is
+    /// set and this isn't a final-preset render.
+    pub fn stamp_stats(&self, data: &mut [u8], stats: &overlay::RenderStats) {
+        if self.stats_overlay && self.preset != Some(preset::Preset::Final) {
+            overlay::stamp(data, self.image_width(), self.image_height(), stats);
+        }
+    }
+
+    /// Changes the output size, widening or narrowing the camera's frame to match.
+    pub fn set_resolution(&mut self, resolution: resolution::Resolution) {
+        self.resolution = resolution;
+        self.camera = fit_camera(self.camera.clone(), &resolution);
+    }
+
+    /// Changes the output width, with the height following the camera's aspect ratio.
+    pub fn set_width(&mut self, width: u32) -> Result<(), resolution::ResolutionError> {
+        self.resolution = resolution::Resolution::from_aspect(width, self.camera.aspect_ratio)?;
+        Ok(())
+    }
+
+    /// Width of the rendered image in pixels, after applying `scale`.
+    pub fn image_width(&self) -> u32 {
+        self.resolution.scaled(self.scale).width()
+    }
+
+    /// Height of the rendered image in pixels, after applying `scale`.
+    pub fn image_height(&self) -> u32 {
+        self.resolution.scaled(self.scale).height()
+    }
+
+    /// Switches to a quality preset, replacing `samples` and `depth` with its settings
+    /// (including this scene's overrides for it).
+    pub fn apply_preset(&mut self, preset: preset::Preset) {
+        let settings = preset.resolve(self.preset_overrides.get(&preset));
+        self.samples = settings.samples;
+        self.depth = settings.depth;
+        self.preset = Some(preset);
+    }
+}
+
+/// `camera`, reframed to `resolution`'s aspect ratio unless it already fits.
+fn fit_camera(camera: camera::Camera, resolution: &resolution::Resolution) -> camera::Camera {
+    if resolution.matches_aspect(camera.aspect_ratio) {
+        camera
+    } else {
+        camera.with_aspect_ratio(resolution.aspect_ratio())
+    }
 }
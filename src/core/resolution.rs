@@ -0,0 +1,101 @@
+//! Output image dimensions.
+//!
+//! A [`Resolution`] is the one place a render's pixel grid is decided: every image,
+//! film, and tile scheduler sizes itself from [`crate::core::render::Render::image_width`]
+//! and [`crate::core::render::Render::image_height`], which scale it, and the camera's
+//! aspect ratio is kept in step with it so pixels stay square.
+
+/// Largest width or height accepted, well past a 16K panorama.
+pub const MAX_EDGE: u32 = 1 << 16;
+
+/// Why a resolution was rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResolutionError {
+    /// A width or height of zero.
+    Empty { width: u32, height: u32 },
+    /// A width or height over [`MAX_EDGE`].
+    TooLarge { width: u32, height: u32 },
+    /// An aspect ratio that isn't a positive, finite number.
+    InvalidAspect(f32),
+}
+
+impl std::fmt::Display for ResolutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolutionError::Empty { width, height } => {
+                write!(f, "resolution {}x{} has no pixels", width, height)
+            }
+            ResolutionError::TooLarge { width, height } => write!(
+                f,
+                "resolution {}x{} is over the {} pixel limit per edge",
+                width, height, MAX_EDGE
+            ),
+            ResolutionError::InvalidAspect(aspect) => {
+                write!(f, "invalid aspect ratio {}", aspect)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResolutionError {}
+
+/// Width and height of an image in pixels, both in `1..=MAX_EDGE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    width: u32,
+    height: u32,
+}
+
+impl Resolution {
+    pub fn new(width: u32, height: u32) -> Result<Self, ResolutionError> {
+        if width == 0 || height == 0 {
+            return Err(ResolutionError::Empty { width, height });
+        }
+        if width > MAX_EDGE || height > MAX_EDGE {
+            return Err(ResolutionError::TooLarge { width, height });
+        }
+        Ok(Resolution { width, height })
+    }
+
+    /// `width` pixels across, with the height that gives `aspect_ratio` (width over
+    /// height), rounded down.
+    pub fn from_aspect(width: u32, aspect_ratio: f32) -> Result<Self, ResolutionError> {
+        if !aspect_ratio.is_finite() || aspect_ratio <= 0.0 {
+            return Err(ResolutionError::InvalidAspect(aspect_ratio));
+        }
+        let height = ((width as f32 / aspect_ratio) as u32).max(1);
+        Resolution::new(width, height)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Width over height.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /// Whether a camera of `aspect_ratio` fits this grid: the height
+    /// [`Resolution::from_aspect`] would give it is this one's, so its pixels are square
+    /// to within a pixel's rounding.
+    pub fn matches_aspect(&self, aspect_ratio: f32) -> bool {
+        Resolution::from_aspect(self.width, aspect_ratio) == Ok(*self)
+    }
+
+    /// The grid at `scale` times the size along each edge, at least one pixel.
+    pub fn scaled(&self, scale: f32) -> Resolution {
+        if scale == 1.0 {
+            return *self;
+        }
+        let scale = |edge: u32| ((edge as f32 * scale).round() as u32).clamp(1, MAX_EDGE);
+        Resolution {
+            width: scale(self.width),
+            height: scale(self.height),
+        }
+    }
+}
@@ -1,16 +1,106 @@
 //! Scene container that stores renderable objects and routes ray intersections.
+use std::any::Any;
+use std::cell::Cell;
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::core::{bvh, object, ray, render};
-use crate::math::{pdf, vec};
+use crate::core::{bbox, bvh, object, path, ray, render};
+use crate::geometry::instance::GeometryInstance;
+use crate::materials::instance::MaterialInstance;
+use crate::math::{pdf, rng, vec};
 use crate::traits::{hittable, renderable, scatterable};
 
+/// Refers to one renderable in a [`Scene`] by its position in `renderables`.
+///
+/// Handles stay valid as long as objects aren't added or removed, so tools can hold on to
+/// them across renders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObjectHandle(pub usize);
+
 /// Collection of renderable objects making up the world.
+///
+/// # Units
+///
+/// Scenes are unitless unless they opt into real-world photometry: then distances are
+/// in meters and radiance is luminance in nits (cd/m²), lights given a
+/// [`crate::core::photometry::LightPower`] emit that many lumens, and
+/// [`render::Render::exposure`] sets the camera's EV for display. Emission given
+/// directly by a texture is taken to be in nits too, so both kinds of light can be
+/// mixed in one scene.
 pub struct Scene {
     pub renderables: object::Renderables,
     pub lights: Vec<Box<dyn renderable::Renderable + Send + Sync>>,
 
     pub bvh: Option<bvh::Bvh>,
+    /// Indices of renderables with unbounded boxes (e.g. the skybox); kept out of the BVH so
+    /// they don't inflate its bounds, and tested after BVH traversal.
+    pub unbounded: Vec<usize>,
+    /// Names of the light groups objects can credit their emission to (see
+    /// [`crate::raytrace_light_groups`]).
+    pub light_groups: Vec<String>,
+    /// Light the scene from the sky alone: emissive objects give off nothing and aren't
+    /// sampled as lights, for neutral look-dev passes without editing their materials.
+    pub sky_only: bool,
+    /// Rays traced against the scene so far, for throughput statistics.
+    rays_traced: RayCounter,
+}
+
+/// Stripes of [`RayCounter`]; threads beyond this many share.
+const RAY_COUNTER_STRIPES: usize = 64;
+
+/// A count of rays many threads add to at once. Each worker thread adds to its own
+/// cache line, so counting doesn't make them contend the way one shared atomic would.
+///
+/// With a sampling rate of `n` above 1, each thread records only every `n`th query it
+/// counts, as `n` times its rays, so the total is an estimate whose error shrinks as the
+/// render goes on, and the queries in between cost a thread-local decrement.
+struct RayCounter {
+    stripes: Vec<Stripe>,
+    every: u64,
+}
+
+thread_local! {
+    /// Queries this thread has left to skip before a sampled [`RayCounter`] records one.
+    static QUERIES_TO_SKIP: Cell<u64> = const { Cell::new(0) };
+}
+
+#[repr(align(128))]
+#[derive(Default)]
+struct Stripe(AtomicU64);
+
+impl Default for RayCounter {
+    fn default() -> Self {
+        RayCounter {
+            stripes: (0..RAY_COUNTER_STRIPES)
+                .map(|_| Stripe::default())
+                .collect(),
+            every: 1,
+        }
+    }
+}
+
+impl RayCounter {
+    fn add(&self, rays: u64) {
+        if self.every > 1 {
+            let skip = QUERIES_TO_SKIP.get();
+            if skip > 0 {
+                QUERIES_TO_SKIP.set(skip - 1);
+                return;
+            }
+            QUERIES_TO_SKIP.set(self.every - 1);
+        }
+        let rays = rays * self.every;
+        let stripe = rayon::current_thread_index().unwrap_or(0) % self.stripes.len();
+        self.stripes[stripe].0.fetch_add(rays, Ordering::Relaxed);
+    }
+
+    fn total(&self) -> u64 {
+        self.stripes
+            .iter()
+            .map(|stripe| stripe.0.load(Ordering::Relaxed))
+            .sum()
+    }
 }
 
 impl Scene {
@@ -20,9 +110,38 @@ impl Scene {
             renderables: object::Renderables::new(),
             lights: Vec::new(),
             bvh: None,
+            unbounded: Vec::new(),
+            light_groups: Vec::new(),
+            sky_only: false,
+            rays_traced: RayCounter::default(),
         }
     }
 
+    /// Number of rays traced against the scene (closest-hit and shadow queries) since it
+    /// was built. Take the difference between two readings to count a render's rays.
+    pub fn rays_traced(&self) -> u64 {
+        self.rays_traced.total()
+    }
+
+    /// Quality metrics of the scene's BVH, or `None` if it hasn't been built.
+    pub fn bvh_stats(&self) -> Option<bvh::BvhStats> {
+        self.bvh.as_ref().map(bvh::Bvh::stats)
+    }
+
+    /// Counts only every `every`th ray query on each thread toward
+    /// [`Scene::rays_traced`], scaled up by `every`, so throughput statistics can stay on
+    /// in long renders for less overhead; 1 (the default) counts every query exactly.
+    /// Readings taken at different rates can't be subtracted from one another.
+    pub fn set_stats_sampling(&mut self, every: u32) {
+        self.rays_traced.every = u64::from(every.max(1));
+    }
+
+    /// How many ray queries each thread counts one of (see
+    /// [`Scene::set_stats_sampling`]).
+    pub fn stats_sampling(&self) -> u32 {
+        self.rays_traced.every as u32
+    }
+
     /// Adds a renderable object to the scene.
     pub fn add_object(&mut self, object: Box<dyn renderable::Renderable + Send + Sync>) {
         self.renderables.add(object);
@@ -32,13 +151,222 @@ impl Scene {
         self.lights.push(light);
     }
 
+    /// Returns the index of the light group called `name`, adding it if it's new.
+    pub fn add_light_group(&mut self, name: &str) -> usize {
+        match self.light_groups.iter().position(|group| group == name) {
+            Some(index) => index,
+            None => {
+                self.light_groups.push(name.to_string());
+                self.light_groups.len() - 1
+            }
+        }
+    }
+
+    /// Handles of every renderable, in order.
+    pub fn handles(&self) -> impl Iterator<Item = ObjectHandle> + use<> {
+        (0..self.renderables.len()).map(ObjectHandle)
+    }
+
+    /// The geometry/material object behind `handle`, or `None` if it names a volume, the
+    /// sky, or nothing.
+    pub fn object(&self, handle: ObjectHandle) -> Option<&object::RenderObject> {
+        self.renderables
+            .objects
+            .get(handle.0)?
+            .as_any()
+            .downcast_ref::<object::RenderObject>()
+    }
+
+    /// Replaces the material of the object behind `handle` in place and returns the old
+    /// one, or `None` (changing nothing) if the handle doesn't name a
+    /// [`object::RenderObject`].
+    ///
+    /// Geometry is untouched, so the BVH stays valid and the next render picks up the new
+    /// material immediately, e.g. between passes of a material editor's live preview.
+    /// The light list follows along when the object starts or stops emitting.
+    pub fn set_material(
+        &mut self,
+        handle: ObjectHandle,
+        material: Arc<dyn scatterable::Scatterable + Send + Sync>,
+    ) -> Option<Arc<dyn scatterable::Scatterable + Send + Sync>> {
+        let renderable: &mut dyn Any = self.renderables.objects.get_mut(handle.0)?.as_mut();
+        let render_object = renderable.downcast_mut::<object::RenderObject>()?;
+
+        let was_emissive = render_object.is_emissive();
+        let previous = std::mem::replace(&mut render_object.material_instance.ref_mat, material);
+        let is_emissive = render_object.is_emissive();
+
+        let geometry = &render_object.geometry_instance;
+        if was_emissive && !is_emissive {
+            // Drop the light built from this object (same shared geometry and material).
+            let position = self.lights.iter().position(|light| {
+                light
+                    .as_any()
+                    .downcast_ref::<object::RenderObject>()
+                    .is_some_and(|light| {
+                        Arc::ptr_eq(&light.geometry_instance.ref_obj, &geometry.ref_obj)
+                            && Arc::ptr_eq(&light.material_instance.ref_mat, &previous)
+                    })
+            });
+            if let Some(position) = position {
+                self.lights.remove(position);
+            }
+        } else if is_emissive && !was_emissive {
+            let light = object::RenderObject {
+                geometry_instance: GeometryInstance {
+                    ref_obj: geometry.ref_obj.clone(),
+                    transforms: geometry.transforms.clone(),
+                    motion_blur: geometry.motion_blur,
+                    smooth_shading: geometry.smooth_shading,
+                },
+                material_instance: MaterialInstance::new(
+                    render_object.material_instance.ref_mat.clone(),
+                ),
+            };
+            self.lights.push(Box::new(light));
+        }
+        Some(previous)
+    }
+
+    /// Sets or clears the albedo tint of the object behind `handle`; returns `false` if
+    /// the handle doesn't name a [`object::RenderObject`].
+    pub fn set_albedo(&mut self, handle: ObjectHandle, albedo: Option<vec::Vec3>) -> bool {
+        let Some(renderable) = self.renderables.objects.get_mut(handle.0) else {
+            return false;
+        };
+        let renderable: &mut dyn Any = renderable.as_mut();
+        match renderable.downcast_mut::<object::RenderObject>() {
+            Some(render_object) => {
+                render_object.material_instance.albedo = albedo;
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn build_bvh(&mut self, rng: &mut rand::rngs::ThreadRng) {
+        self.unbounded.clear();
         if self.renderables.objects.is_empty() {
             self.bvh = None;
             return;
         }
         self.renderables.rebuild_bbox();
-        self.bvh = Some(bvh::Bvh::new(rng, &self.renderables.objects));
+
+        let (unbounded, bounded): (Vec<usize>, Vec<usize>) = (0..self.renderables.len())
+            .partition(|&idx| self.renderables.objects[idx].bounding_box().is_unbounded());
+        self.unbounded = unbounded;
+        self.bvh = if bounded.is_empty() {
+            None
+        } else {
+            Some(bvh::Bvh::from_indices(
+                rng,
+                &self.renderables.objects,
+                bounded,
+            ))
+        };
+    }
+
+    /// Bounds of the scene's subject matter, for framing a camera on it.
+    ///
+    /// Unbounded renderables (the skybox) are skipped, and so are backdrops such as ground
+    /// planes or enclosing fog whose box is more than ten times the median object's size.
+    /// Returns `None` if the scene has nothing bounded.
+    pub fn content_bounds(&self) -> Option<bbox::BBox> {
+        let boxes: Vec<(bbox::BBox, f32)> = self
+            .renderables
+            .objects
+            .iter()
+            .map(|object| object.bounding_box())
+            .filter(|bbox| !bbox.is_unbounded())
+            .map(|bbox| {
+                let diagonal = vec::Vec3::new(bbox.x.length(), bbox.y.length(), bbox.z.length());
+                (bbox, diagonal.length())
+            })
+            .collect();
+
+        let mut sizes: Vec<f32> = boxes.iter().map(|(_, size)| *size).collect();
+        sizes.sort_by(f32::total_cmp);
+        let median = *sizes.get(sizes.len() / 2)?;
+
+        boxes
+            .iter()
+            .filter(|(_, size)| *size <= median * 10.0)
+            .map(|(bbox, _)| *bbox)
+            .reduce(|acc, bbox| acc.union(&bbox))
+    }
+
+    /// Finds the closest hit for every ray in a packet, sharing BVH traversal across rays.
+    ///
+    /// Equivalent to calling [`renderable::Renderable::hit`] per ray, but intended as the
+    /// building block for integrators that trace coherent batches of rays together.
+    pub fn hit_batch(
+        &self,
+        rays: &[ray::Ray],
+        t_min: f32,
+        t_max: f32,
+    ) -> Vec<Option<hittable::HitRecord<'_>>> {
+        let Some(bvh) = &self.bvh else {
+            // Each ray is counted by `hit`.
+            return rays
+                .iter()
+                .map(|ray| renderable::Renderable::hit(self, ray, t_min, t_max))
+                .collect();
+        };
+
+        self.rays_traced.add(rays.len() as u64);
+        let mut records = bvh.hit_batch(&self.renderables.objects, rays, t_min, t_max);
+        for (ray, record) in rays.iter().zip(records.iter_mut()) {
+            let mut closest_so_far = record.as_ref().map_or(t_max, |r| r.hit.t);
+            for &idx in self.unbounded.iter() {
+                if let Some(temp_record) =
+                    self.renderables.objects[idx].hit(ray, t_min, closest_so_far)
+                {
+                    closest_so_far = temp_record.hit.t;
+                    *record = Some(temp_record);
+                }
+            }
+        }
+        records
+    }
+
+    /// Returns whether anything blocks the ray between `t_min` and `t_max`.
+    ///
+    /// Cheaper than [`renderable::Renderable::hit`] for shadow rays: traversal stops at the
+    /// first intersection and no hit record is built.
+    pub fn occluded(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> bool {
+        self.rays_traced.add(1);
+        if self.bvh.is_none() && self.unbounded.is_empty() {
+            return self
+                .renderables
+                .objects
+                .iter()
+                .any(|object| object.occludes(ray, t_min, t_max));
+        }
+
+        if let Some(bvh) = &self.bvh
+            && bvh.hit_any(&self.renderables.objects, ray, t_min, t_max)
+        {
+            return true;
+        }
+        self.unbounded
+            .iter()
+            .any(|&idx| self.renderables.objects[idx].occludes(ray, t_min, t_max))
+    }
+
+    /// Radiance emitted at `hit_record`, which is only the sky's when
+    /// [`Scene::sky_only`] is set.
+    pub(crate) fn emitted(&self, hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        if self.sky_only && !self.is_sky(hit_record.renderable) {
+            return vec::Vec3::new(0.0, 0.0, 0.0);
+        }
+        hit_record.renderable.emit(hit_record)
+    }
+
+    /// Whether `renderable` is one of the scene's unbounded backgrounds.
+    fn is_sky(&self, renderable: &dyn renderable::Renderable) -> bool {
+        self.unbounded
+            .iter()
+            .any(|&index| std::ptr::addr_eq(self.renderables.objects[index].as_ref(), renderable))
     }
 
     pub(crate) fn light_pdf<'a, 'b>(
@@ -49,7 +377,7 @@ impl Scene {
     where
         'a: 'b,
     {
-        if self.lights.is_empty() {
+        if self.lights.is_empty() || self.sky_only {
             return None;
         }
 
@@ -70,13 +398,30 @@ impl Scene {
 impl renderable::Renderable for Scene {
     /// Finds the closest intersection among scene objects.
     fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::HitRecord<'_>> {
-        if let Some(bvh) = &self.bvh {
-            return bvh.hit(&self.renderables.objects, ray, t_min, t_max);
-        }
-
+        self.rays_traced.add(1);
         let mut closest_so_far = t_max;
         let mut hit_record: Option<hittable::HitRecord> = None;
 
+        if self.bvh.is_some() || !self.unbounded.is_empty() {
+            if let Some(bvh) = &self.bvh {
+                hit_record = bvh.hit(&self.renderables.objects, ray, t_min, t_max);
+                if let Some(record) = &hit_record {
+                    closest_so_far = record.hit.t;
+                }
+            }
+
+            for &idx in self.unbounded.iter() {
+                if let Some(temp_record) =
+                    self.renderables.objects[idx].hit(ray, t_min, closest_so_far)
+                {
+                    closest_so_far = temp_record.hit.t;
+                    hit_record = Some(temp_record);
+                }
+            }
+
+            return hit_record;
+        }
+
         if !self.renderables.bbox.hit(ray, t_min, t_max) {
             return None;
         }
@@ -108,11 +453,11 @@ impl renderable::Renderable for Scene {
     /// Delegates scattering to the material bound to the hit object.
     fn scatter(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut rng::SampleRng,
         hit_record: &hittable::HitRecord<'_>,
-        depth: u32,
+        state: &mut path::PathState,
     ) -> Option<scatterable::ScatterRecord> {
-        let result = hit_record.renderable.scatter(rng, hit_record, depth);
+        let result = hit_record.renderable.scatter(rng, hit_record, state);
         result
     }
 
@@ -0,0 +1,601 @@
+//! Structural comparison of two scene files.
+//!
+//! Raw TOML diffs of scenes are hard to review: ids shift, textures embed large
+//! arrays, and one material tweak shows up far from the objects using it. This
+//! compares two [`SceneFile`]s as data instead, reporting changed settings,
+//! geometries and materials (matched by id, with per-parameter changes), scatters
+//! (matched by position, likewise), and objects/volumes added or removed.
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::core::scene_file::{ObjectInstance, ScatterInstance, SceneFile, VolumeInstance};
+
+/// Arrays longer than this are summarized instead of printed in full.
+const MAX_PRINTED_ARRAY: usize = 8;
+
+/// One leaf value that differs between the two scenes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    /// Dotted path to the value, e.g. `data.albedo` or `camera.origin`.
+    pub path: String,
+    /// Printed old value, or `None` if the field was added.
+    pub old: Option<String>,
+    /// Printed new value, or `None` if the field was removed.
+    pub new: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// A render setting or the camera changed.
+    Setting(FieldChange),
+    GeometryAdded {
+        id: usize,
+        kind: String,
+    },
+    GeometryRemoved {
+        id: usize,
+        kind: String,
+    },
+    GeometryChanged {
+        id: usize,
+        kind: String,
+        fields: Vec<FieldChange>,
+    },
+    MaterialAdded {
+        id: usize,
+        kind: String,
+    },
+    MaterialRemoved {
+        id: usize,
+        kind: String,
+    },
+    MaterialChanged {
+        id: usize,
+        kind: String,
+        fields: Vec<FieldChange>,
+    },
+    /// An object present only in the second scene, described by its references.
+    ObjectAdded(String),
+    /// An object present only in the first scene, described by its references.
+    ObjectRemoved(String),
+    VolumeAdded(String),
+    VolumeRemoved(String),
+    /// A scatter only in the second scene, by its index in `scatters`.
+    ScatterAdded {
+        index: usize,
+        scatter: String,
+    },
+    ScatterRemoved {
+        index: usize,
+        scatter: String,
+    },
+    ScatterChanged {
+        index: usize,
+        fields: Vec<FieldChange>,
+    },
+}
+
+/// Differences between two scene files, in a stable order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SceneDiff {
+    pub changes: Vec<Change>,
+}
+
+impl SceneDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Lists compared entry by entry rather than as settings.
+const ENTRY_LISTS: [&str; 5] = ["geometries", "materials", "objects", "volumes", "scatters"];
+
+/// Every render setting and the camera: the scene with its entry lists taken out, so
+/// settings added to [`SceneFile`] are compared without being listed here.
+fn settings(scene: &SceneFile) -> toml::Value {
+    let mut value = to_value(scene);
+    if let toml::Value::Table(table) = &mut value {
+        for key in ENTRY_LISTS {
+            table.remove(key);
+        }
+    }
+    value
+}
+
+/// Compares scene `a` (before) against scene `b` (after).
+pub fn diff(a: &SceneFile, b: &SceneFile) -> SceneDiff {
+    let mut changes = Vec::new();
+
+    let mut fields = Vec::new();
+    diff_values("", Some(&settings(a)), Some(&settings(b)), &mut fields);
+    changes.extend(fields.into_iter().map(Change::Setting));
+
+    let geometries = |scene: &SceneFile| -> Vec<(usize, toml::Value)> {
+        scene
+            .geometries
+            .iter()
+            .map(|entry| (entry.id, to_value(&entry.geometry)))
+            .collect()
+    };
+    for (id, before, after) in pair_by_id(geometries(a), geometries(b)) {
+        match (before, after) {
+            (Some(before), None) => changes.push(Change::GeometryRemoved {
+                id,
+                kind: kind(&before, "hittable"),
+            }),
+            (None, Some(after)) => changes.push(Change::GeometryAdded {
+                id,
+                kind: kind(&after, "hittable"),
+            }),
+            (Some(before), Some(after)) => {
+                let mut fields = Vec::new();
+                diff_values("", Some(&before), Some(&after), &mut fields);
+                if !fields.is_empty() {
+                    changes.push(Change::GeometryChanged {
+                        id,
+                        kind: kind(&after, "hittable"),
+                        fields,
+                    });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    let materials = |scene: &SceneFile| -> Vec<(usize, toml::Value)> {
+        scene
+            .materials
+            .iter()
+            .map(|entry| (entry.id, to_value(entry)))
+            .collect()
+    };
+    for (id, before, after) in pair_by_id(materials(a), materials(b)) {
+        match (before, after) {
+            (Some(before), None) => changes.push(Change::MaterialRemoved {
+                id,
+                kind: kind(&before, "sampleable"),
+            }),
+            (None, Some(after)) => changes.push(Change::MaterialAdded {
+                id,
+                kind: kind(&after, "sampleable"),
+            }),
+            (Some(before), Some(after)) => {
+                let mut fields = Vec::new();
+                diff_values("", Some(&before), Some(&after), &mut fields);
+                if !fields.is_empty() {
+                    changes.push(Change::MaterialChanged {
+                        id,
+                        kind: kind(&after, "sampleable"),
+                        fields,
+                    });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    let (removed, added) = multiset_difference(&a.objects, &b.objects);
+    changes.extend(
+        removed
+            .into_iter()
+            .map(|object| Change::ObjectRemoved(describe_object(a, object))),
+    );
+    changes.extend(
+        added
+            .into_iter()
+            .map(|object| Change::ObjectAdded(describe_object(b, object))),
+    );
+
+    let (removed, added) = multiset_difference(&a.volumes, &b.volumes);
+    changes.extend(
+        removed
+            .into_iter()
+            .map(|volume| Change::VolumeRemoved(describe_volume(a, volume))),
+    );
+    changes.extend(
+        added
+            .into_iter()
+            .map(|volume| Change::VolumeAdded(describe_volume(b, volume))),
+    );
+
+    // Scatters have no ids; the same position in the list is the same scatter.
+    let scatters = |scene: &SceneFile| -> Vec<(usize, toml::Value)> {
+        scene.scatters.iter().map(to_value).enumerate().collect()
+    };
+    for (index, before, after) in pair_by_id(scatters(a), scatters(b)) {
+        match (before, after) {
+            (Some(_), None) => changes.push(Change::ScatterRemoved {
+                index,
+                scatter: describe_scatter(a, &a.scatters[index]),
+            }),
+            (None, Some(_)) => changes.push(Change::ScatterAdded {
+                index,
+                scatter: describe_scatter(b, &b.scatters[index]),
+            }),
+            (Some(before), Some(after)) => {
+                let mut fields = Vec::new();
+                diff_values("", Some(&before), Some(&after), &mut fields);
+                if !fields.is_empty() {
+                    changes.push(Change::ScatterChanged { index, fields });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    SceneDiff { changes }
+}
+
+fn to_value<T: Serialize>(value: &T) -> toml::Value {
+    toml::Value::try_from(value).expect("scene data serializes to TOML")
+}
+
+/// Reads an enum tag such as `hittable = "Sphere"` from a serialized template.
+fn kind(value: &toml::Value, tag: &str) -> String {
+    value
+        .get(tag)
+        .and_then(|kind| kind.as_str())
+        .unwrap_or("?")
+        .to_string()
+}
+
+/// Lines up entries from both scenes by id, in id order.
+fn pair_by_id(
+    a: Vec<(usize, toml::Value)>,
+    b: Vec<(usize, toml::Value)>,
+) -> Vec<(usize, Option<toml::Value>, Option<toml::Value>)> {
+    let ids: BTreeSet<usize> = a.iter().chain(b.iter()).map(|(id, _)| *id).collect();
+    let find = |entries: &[(usize, toml::Value)], id: usize| {
+        entries
+            .iter()
+            .find(|(entry_id, _)| *entry_id == id)
+            .map(|(_, value)| value.clone())
+    };
+    ids.into_iter()
+        .map(|id| (id, find(&a, id), find(&b, id)))
+        .collect()
+}
+
+/// Entries only in `a` and only in `b`, counting duplicates.
+fn multiset_difference<'a, T: Serialize>(a: &'a [T], b: &'a [T]) -> (Vec<&'a T>, Vec<&'a T>) {
+    let a_values: Vec<toml::Value> = a.iter().map(to_value).collect();
+    let mut unmatched: Vec<Option<toml::Value>> =
+        b.iter().map(|item| Some(to_value(item))).collect();
+
+    let mut removed = Vec::new();
+    for (item, value) in a.iter().zip(a_values) {
+        match unmatched
+            .iter_mut()
+            .find(|candidate| candidate.as_ref() == Some(&value))
+        {
+            Some(candidate) => *candidate = None,
+            None => removed.push(item),
+        }
+    }
+    let added = b
+        .iter()
+        .zip(unmatched)
+        .filter_map(|(item, value)| value.map(|_| item))
+        .collect();
+    (removed, added)
+}
+
+fn describe_geometry(scene: &SceneFile, id: usize) -> String {
+    match scene.geometries.iter().find(|entry| entry.id == id) {
+        Some(entry) => format!(
+            "geometry {} ({})",
+            id,
+            kind(&to_value(&entry.geometry), "hittable")
+        ),
+        None => format!("geometry {} (missing)", id),
+    }
+}
+
+fn describe_material(scene: &SceneFile, id: usize) -> String {
+    match scene.materials.iter().find(|entry| entry.id == id) {
+        Some(entry) => format!(
+            "material {} ({})",
+            id,
+            kind(&to_value(&entry.material), "sampleable")
+        ),
+        None => format!("material {} (missing)", id),
+    }
+}
+
+fn describe_object(scene: &SceneFile, object: &ObjectInstance) -> String {
+    let mut description = format!(
+        "{} with {}",
+        describe_geometry(scene, object.geometry),
+        describe_material(scene, object.material)
+    );
+    if !object.transforms.is_empty() {
+        description.push_str(&format!(", {} transform(s)", object.transforms.len()));
+    }
+    description
+}
+
+fn describe_volume(scene: &SceneFile, volume: &VolumeInstance) -> String {
+    format!(
+        "{} bounded by {}, density {}",
+        describe_material(scene, volume.phase_function),
+        describe_geometry(scene, volume.boundary_geometry),
+        volume.density
+    )
+}
+
+fn describe_scatter(scene: &SceneFile, scatter: &ScatterInstance) -> String {
+    format!(
+        "{} copies of {} with {} over object {}",
+        scatter.settings.count,
+        describe_geometry(scene, scatter.geometry),
+        describe_material(scene, scatter.material),
+        scatter.surface
+    )
+}
+
+/// Appends a [`FieldChange`] for every leaf that differs between `a` and `b`.
+fn diff_values(
+    path: &str,
+    a: Option<&toml::Value>,
+    b: Option<&toml::Value>,
+    out: &mut Vec<FieldChange>,
+) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match (a, b) {
+        (Some(toml::Value::Table(a)), Some(toml::Value::Table(b))) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                diff_values(&join(key), a.get(key), b.get(key), out);
+            }
+        }
+        (Some(toml::Value::Array(a)), Some(toml::Value::Array(b)))
+            if a.len() == b.len() && a.iter().all(|value| value.is_table()) =>
+        {
+            for (index, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                diff_values(&join(&index.to_string()), Some(a), Some(b), out);
+            }
+        }
+        _ if a != b => out.push(FieldChange {
+            path: path.to_string(),
+            old: a.map(summarize),
+            new: b.map(summarize),
+        }),
+        _ => {}
+    }
+}
+
+fn summarize(value: &toml::Value) -> String {
+    match value {
+        toml::Value::Array(values) if values.len() > MAX_PRINTED_ARRAY => {
+            format!("[{} values]", values.len())
+        }
+        toml::Value::Array(values) => format!(
+            "[{}]",
+            values.iter().map(summarize).collect::<Vec<_>>().join(", ")
+        ),
+        // Scene values are stored as `f32`; print them at that precision.
+        toml::Value::Float(value) => (*value as f32).to_string(),
+        _ => value.to_string(),
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "{}: {} -> {}", self.path, old, new),
+            (Some(old), None) => write!(f, "{}: removed (was {})", self.path, old),
+            (None, Some(new)) => write!(f, "{}: added ({})", self.path, new),
+            (None, None) => write!(f, "{}", self.path),
+        }
+    }
+}
+
+impl fmt::Display for SceneDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            match change {
+                Change::Setting(field) => writeln!(f, "~ setting {}", field)?,
+                Change::GeometryAdded { id, kind } => writeln!(f, "+ geometry {} ({})", id, kind)?,
+                Change::GeometryRemoved { id, kind } => {
+                    writeln!(f, "- geometry {} ({})", id, kind)?
+                }
+                Change::GeometryChanged { id, kind, fields } => {
+                    writeln!(f, "~ geometry {} ({})", id, kind)?;
+                    for field in fields {
+                        writeln!(f, "    {}", field)?;
+                    }
+                }
+                Change::MaterialAdded { id, kind } => writeln!(f, "+ material {} ({})", id, kind)?,
+                Change::MaterialRemoved { id, kind } => {
+                    writeln!(f, "- material {} ({})", id, kind)?
+                }
+                Change::MaterialChanged { id, kind, fields } => {
+                    writeln!(f, "~ material {} ({})", id, kind)?;
+                    for field in fields {
+                        writeln!(f, "    {}", field)?;
+                    }
+                }
+                Change::ObjectAdded(object) => writeln!(f, "+ object: {}", object)?,
+                Change::ObjectRemoved(object) => writeln!(f, "- object: {}", object)?,
+                Change::VolumeAdded(volume) => writeln!(f, "+ volume: {}", volume)?,
+                Change::VolumeRemoved(volume) => writeln!(f, "- volume: {}", volume)?,
+                Change::ScatterAdded { index, scatter } => {
+                    writeln!(f, "+ scatter {}: {}", index, scatter)?
+                }
+                Change::ScatterRemoved { index, scatter } => {
+                    writeln!(f, "- scatter {}: {}", index, scatter)?
+                }
+                Change::ScatterChanged { index, fields } => {
+                    writeln!(f, "~ scatter {}", index)?;
+                    for field in fields {
+                        writeln!(f, "    {}", field)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::core::scene_file::{self, GodRayInstance, SectionSettings};
+    use crate::core::{aov, color, film, ray, shutter};
+    use crate::geometry::{god_rays, scatter, section};
+    use crate::math::{rng, vec};
+
+    fn cornell_box() -> SceneFile {
+        scene_file::read_scene_file(Path::new("scenes/cornell_box.toml")).unwrap()
+    }
+
+    fn changed_settings(change: impl Fn(&mut SceneFile)) -> Vec<String> {
+        let before = cornell_box();
+        let mut after = cornell_box();
+        change(&mut after);
+        diff(&before, &after)
+            .changes
+            .into_iter()
+            .map(|change| match change {
+                Change::Setting(field) => field.path,
+                other => panic!("expected a setting change, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_scenes_have_no_differences() {
+        assert!(diff(&cornell_box(), &cornell_box()).is_empty());
+    }
+
+    #[test]
+    fn scatters_are_compared_by_position() {
+        let scatter = ScatterInstance {
+            geometry: 0,
+            material: 0,
+            surface: 0,
+            density: None,
+            settings: scatter::ScatterSettings::new(10),
+        };
+        let before = cornell_box();
+        let mut after = cornell_box();
+        after.scatters.push(scatter.clone());
+        assert_eq!(
+            diff(&before, &after).changes,
+            vec![Change::ScatterAdded {
+                index: 0,
+                scatter: String::from(
+                    "10 copies of geometry 0 (Quad) with material 0 (Lambertian) over object 0"
+                ),
+            }]
+        );
+
+        let mut changed = cornell_box();
+        changed.scatters.push(ScatterInstance {
+            settings: scatter::ScatterSettings {
+                seed: 3,
+                ..scatter.settings.clone()
+            },
+            ..scatter
+        });
+        let changes = diff(&after, &changed).changes;
+        assert!(matches!(
+            changes.as_slice(),
+            [Change::ScatterChanged { index: 0, fields }] if fields[0].path == "seed"
+        ));
+    }
+
+    #[test]
+    fn every_setting_is_compared() {
+        type Edit = Box<dyn Fn(&mut SceneFile)>;
+        let cases: Vec<(&str, Edit)> = vec![
+            ("sky_only", Box::new(|scene| scene.sky_only = true)),
+            (
+                "stats_overlay",
+                Box::new(|scene| scene.stats_overlay = true),
+            ),
+            ("exposure", Box::new(|scene| scene.exposure = Some(12.0))),
+            (
+                "film_precision",
+                Box::new(|scene| scene.film_precision = film::FilmPrecision::Half),
+            ),
+            (
+                "rng",
+                Box::new(|scene| scene.rng = rng::RngAlgorithm::Pcg32),
+            ),
+            (
+                "color_space",
+                Box::new(|scene| scene.color_space = color::ColorSpace::DisplayP3),
+            ),
+            (
+                "frame_noise",
+                Box::new(|scene| scene.frame_noise = shutter::FrameNoise::PerFrame),
+            ),
+            (
+                "shutter",
+                Box::new(|scene| scene.shutter = shutter::Shutter::frame(2.0, 24.0, 180.0)),
+            ),
+            (
+                "rays",
+                Box::new(|scene| scene.rays.camera = ray::RayRange::new(0.5, 1000.0)),
+            ),
+            (
+                "depth_range",
+                Box::new(|scene| {
+                    scene.depth_range = Some(aov::DepthRange {
+                        near: 1.0,
+                        far: 20.0,
+                    })
+                }),
+            ),
+            (
+                "section",
+                Box::new(|scene| {
+                    scene.section = Some(SectionSettings {
+                        planes: vec![section::SectionPlane {
+                            point: vec::Vec3::new(0.0, 100.0, 0.0),
+                            normal: vec::Vec3::new(0.0, 1.0, 0.0),
+                        }],
+                        cap: None,
+                    })
+                }),
+            ),
+            (
+                "god_rays",
+                Box::new(|scene| {
+                    scene.god_rays.push(GodRayInstance {
+                        light: 0,
+                        phase_function: 0,
+                        density: 0.01,
+                        settings: god_rays::BeamSettings {
+                            length: 100.0,
+                            angle: 30.0,
+                            direction: None,
+                            segments: 8,
+                        },
+                    })
+                }),
+            ),
+        ];
+        for (setting, change) in cases {
+            let paths = changed_settings(change);
+            assert!(
+                !paths.is_empty() && paths.iter().all(|path| path.starts_with(setting)),
+                "{} changes reported as {:?}",
+                setting,
+                paths
+            );
+        }
+    }
+}
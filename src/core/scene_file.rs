@@ -1,32 +1,126 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
+use std::time::{Duration, Instant};
 
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::core::{camera, object, render, scene, volume, world};
+use crate::core::color::ColorSpace;
+use crate::core::{
+    aov, assets, bundle, camera, film, object, photometry, plugins, preprocess, preset, ray,
+    render, resolution, scene, shutter, volume, world,
+};
 use crate::geometry::{
+    cutout, generators, god_rays,
     instance::GeometryInstance,
-    primitives::{cube, quad, sphere},
-    transform,
+    primitives::{cube, curves, disk, heightfield, mesh, quad, sdf, sphere},
+    scatter, section, transform,
 };
 use crate::materials::{
-    dielectric, diffuse_light, instance::MaterialInstance, lambertian, metallic,
+    anisotropic, blinn_phong, bounce_limit, conductor, dielectric, diffuse_light,
+    instance::MaterialInstance, lambertian, layered, measured, metallic, normal_map, opacity,
+    plastic, principled, toon, two_sided, velvet,
 };
-use crate::math::vec;
-use crate::textures::{checker, color, noise, uv};
+use crate::math::{rng, vec};
+use crate::textures::{checker, color, noise, uv, vertex_color};
 use crate::traits::{hittable, scatterable, texturable};
 
 #[derive(Serialize, Deserialize)]
 pub struct SceneFile {
     pub width: u32,
-    pub samples: u32,
-    pub depth: u32,
+    /// Output height; when omitted it follows the camera's aspect ratio, otherwise the
+    /// camera is reframed to `width / height`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Explicit samples per pixel; falls back to the preset's value when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub samples: Option<u32>,
+    /// Explicit bounce depth; falls back to the preset's value when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+    /// Error-diffusion dither the 8-bit output.
+    #[serde(default)]
+    pub dither: bool,
+    /// Seed for per-pixel sampling (see [`render::Render::seed`]). TOML integers are
+    /// signed, so seeds above `i64::MAX` are saved as the negative integer with the same
+    /// bits.
+    #[serde(
+        default,
+        serialize_with = "serialize_seed",
+        deserialize_with = "deserialize_seed"
+    )]
+    pub seed: u64,
+    /// Quality preset used for settings the file doesn't spell out (`preview` if omitted).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<preset::Preset>,
+    /// Per-preset overrides, e.g. `[presets.final] samples = 2000`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub presets: BTreeMap<preset::Preset, preset::PresetOverride>,
+    /// Near/far depths for the depth AOV visualization, e.g.
+    /// `depth_range = { near = 1.0, far = 20.0 }`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth_range: Option<aov::DepthRange>,
+    /// Film storage precision, `"full"` (the default) or `"half"` for very large images.
+    #[serde(default, skip_serializing_if = "film::FilmPrecision::is_full")]
+    pub film_precision: film::FilmPrecision,
+    /// Sampling generator, `"xoshiro"` (the default) or `"pcg32"`.
+    #[serde(default, skip_serializing_if = "rng::RngAlgorithm::is_default")]
+    pub rng: rng::RngAlgorithm,
+    /// Scene time the shutter is open over, as `{ open = 0.0, close = 0.02 }` in seconds
+    /// or `{ frame = 12, frame_rate = 24, angle = 180 }`. `Move` keyframes are in the
+    /// same seconds.
+    #[serde(default, skip_serializing_if = "shutter::Shutter::is_default")]
+    pub shutter: shutter::Shutter,
+    /// Sampling noise across animation frames: `"fixed"` (the default) in place on
+    /// screen, or `"per-frame"` to change with the shutter.
+    #[serde(default, skip_serializing_if = "shutter::FrameNoise::is_default")]
+    pub frame_noise: shutter::FrameNoise,
+    /// Camera exposure in EV at ISO 100, for scenes lit in real units (see
+    /// [`photometry`]); without it radiance is displayed as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exposure: Option<f32>,
+    /// Color space of the output, `"srgb"` (the default), `"display-p3"`, or `"rec2020"`
+    /// (see [`crate::core::color`]).
+    #[serde(default, skip_serializing_if = "ColorSpace::is_default")]
+    pub color_space: ColorSpace,
+    /// Stamp render statistics onto draft and preview output (see
+    /// [`crate::core::overlay`]).
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub stats_overlay: bool,
+    /// Light the scene from the sky alone, ignoring emissive materials (see
+    /// [`scene::Scene::sky_only`]).
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub sky_only: bool,
+    /// Hit distances for camera and indirect rays (see [`ray::RayRanges`]);
+    /// each defaults to `{ t_min = 0.001 }` with no far limit.
+    #[serde(default, skip_serializing_if = "ray::RayRanges::is_default")]
+    pub rays: ray::RayRanges,
+    /// Planes cutting away part of every object for a cutaway view (see [`section`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<SectionSettings>,
     pub camera: camera::Camera,
     pub geometries: Vec<GeometryEntry>,
     pub materials: Vec<MaterialEntry>,
     pub objects: Vec<ObjectInstance>,
     #[serde(default)]
     pub volumes: Vec<VolumeInstance>,
+    /// Copies of geometries strewn over objects' surfaces, added as objects at load.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scatters: Vec<ScatterInstance>,
+    /// Hazy cones in front of lights, added as volumes at load.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub god_rays: Vec<GodRayInstance>,
+}
+
+/// Scene-wide section planes, e.g.
+/// `section = { planes = [{ point = [0, 2, 0], normal = [0, 1, 0] }], cap = 4 }`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectionSettings {
+    pub planes: Vec<section::SectionPlane>,
+    /// Material id for the faces closing solids where the planes cut them; each object's
+    /// own material if omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cap: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -39,10 +133,30 @@ pub struct GeometryEntry {
 #[derive(Serialize, Deserialize)]
 pub struct MaterialEntry {
     pub id: usize,
+    /// Most bounces a path may have made and still scatter off the material, within the
+    /// render's `depth` (see [`bounce_limit`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bounces: Option<u32>,
+    /// Tangent-space normal map bending the material's shading normals (see
+    /// [`normal_map`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normal_map: Option<NormalMapTemplate>,
     #[serde(flatten)]
     pub material: MaterialTemplate,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NormalMapTemplate {
+    pub texture: TextureTemplate,
+    /// How far the map tilts normals; 1 is as authored.
+    #[serde(default = "default_normal_map_strength")]
+    pub strength: f32,
+}
+
+fn default_normal_map_strength() -> f32 {
+    1.0
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ObjectInstance {
     pub geometry: usize,
@@ -50,6 +164,82 @@ pub struct ObjectInstance {
     #[serde(default)]
     pub transforms: Vec<transform::Transform>,
     pub albedo: Option<vec::Vec3>,
+    /// Material ids bound to the geometry's material slots (e.g. one per cube face).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slot_materials: Vec<usize>,
+    /// Light group the object's emission is credited to when rendering light groups.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub light_group: Option<String>,
+    /// Pattern cut out of the geometry, with rays passing through the holes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cutout: Option<cutout::CutoutPattern>,
+    /// Whether `Move` transforms blur over the shutter; `false` freezes the object at the
+    /// shutter midpoint.
+    #[serde(default = "default_motion_blur", skip_serializing_if = "is_true")]
+    pub motion_blur: bool,
+    /// Whether the object shades with its geometry's vertex normals, when it has any;
+    /// `false` shows a mesh's flat facets.
+    #[serde(default = "default_smooth_shading", skip_serializing_if = "is_true")]
+    pub smooth_shading: bool,
+}
+
+fn default_motion_blur() -> bool {
+    true
+}
+
+fn default_smooth_shading() -> bool {
+    true
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_coat_ior() -> f32 {
+    1.5
+}
+
+fn default_camera_visible() -> bool {
+    true
+}
+
+/// Writes `seed` as the signed integer with the same bits, which TOML can hold.
+fn serialize_seed<S: serde::Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    (*seed as i64).serialize(serializer)
+}
+
+/// Reads a seed saved by [`serialize_seed`], or written out as an unsigned integer.
+fn deserialize_seed<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seed {
+        Signed(i64),
+        Unsigned(u64),
+    }
+
+    Ok(match Seed::deserialize(deserializer)? {
+        Seed::Signed(seed) => seed as u64,
+        Seed::Unsigned(seed) => seed,
+    })
+}
+
+fn is_one(value: &f32) -> bool {
+    *value == 1.0
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -61,33 +251,213 @@ pub struct VolumeInstance {
     pub boundary_transforms: Vec<transform::Transform>,
 }
 
+/// Copies of a geometry scattered over an object's surface (see [`scatter`]), e.g.
+/// `{ geometry = 4, material = 2, surface = 0, count = 200, scale = [0.5, 1.5] }`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScatterInstance {
+    pub geometry: usize,
+    pub material: usize,
+    /// Index into `objects` of the object whose surface the copies are scattered over.
+    pub surface: usize,
+    /// Texture whose brightness at a spot, from 0 to 1, is the chance a copy lands there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub density: Option<TextureTemplate>,
+    #[serde(flatten)]
+    pub settings: scatter::ScatterSettings,
+}
+
+/// A volume filling the beam in front of a light (see [`god_rays`]), e.g.
+/// `{ light = 5, phase_function = 6, density = 0.002, length = 500, angle = 25 }`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GodRayInstance {
+    /// Index into `objects` of the light the beam comes from.
+    pub light: usize,
+    pub phase_function: usize,
+    pub density: f32,
+    #[serde(flatten)]
+    pub settings: god_rays::BeamSettings,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(tag = "hittable", content = "data")]
 pub enum GeometryTemplate {
     Sphere(sphere::Sphere),
     Quad(quad::Quad),
     Cube(cube::Cube),
+    Disk(disk::Disk),
+    Sdf(sdf::SdfGeometry),
+    Heightfield(heightfield::Heightfield),
+    Curves(curves::Curves),
+    Mesh(mesh::Mesh),
+    Fractal(generators::Fractal),
     World(world::World),
 }
 
+// `remote = "Self"` derives inherent (de)serializers for the built-in tags; the trait
+// impls below route registered plugin tags around them.
 #[derive(Clone, Serialize, Deserialize)]
-#[serde(tag = "sampleable", content = "data")]
+#[serde(remote = "Self", tag = "sampleable", content = "data")]
 pub enum MaterialTemplate {
-    Lambertian { texture: TextureTemplate },
-    Metallic(metallic::Metallic),
-    Dielectric(dielectric::Dielectric),
-    DiffuseLight { texture: TextureTemplate },
-    Isotropic { texture: TextureTemplate },
+    Lambertian {
+        texture: TextureTemplate,
+    },
+    /// A metal, its albedo multiplied by `texture` if there is one.
+    Metallic {
+        #[serde(flatten)]
+        metal: metallic::Metallic,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        texture: Option<TextureTemplate>,
+    },
+    /// Glass, its tint multiplied by `texture` if there is one.
+    Dielectric {
+        #[serde(flatten)]
+        glass: dielectric::Dielectric,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        texture: Option<TextureTemplate>,
+    },
+    Principled(principled::Principled),
+    Anisotropic(anisotropic::Anisotropic),
+    Conductor(conductor::Conductor),
+    BlinnPhong(blinn_phong::BlinnPhong),
+    Velvet(velvet::Velvet),
+    Toon(toon::Toon),
+    Plastic(plastic::Plastic),
+    /// A MERL BRDF table, loaded from `path`.
+    Measured(measured::Measured),
+    /// `power` (e.g. `{ lumens = 800 }`) fits the emission of every object using the
+    /// light to that total output; otherwise the texture times `intensity` is the
+    /// emitted radiance.
+    DiffuseLight {
+        texture: TextureTemplate,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        power: Option<photometry::LightPower>,
+        #[serde(default = "default_intensity", skip_serializing_if = "is_one")]
+        intensity: f32,
+        /// Emit only from the side the surface normal points to.
+        #[serde(default, skip_serializing_if = "is_false")]
+        single_sided: bool,
+        /// Whether camera rays see the light, or pass through it.
+        #[serde(default = "default_camera_visible", skip_serializing_if = "is_true")]
+        camera_visible: bool,
+    },
+    Isotropic {
+        texture: TextureTemplate,
+    },
     World(world::World),
+    /// `front` on the side the surface normal points to, `back` on the other.
+    TwoSided {
+        front: Box<MaterialTemplate>,
+        back: Box<MaterialTemplate>,
+    },
+    /// `material` where the surface is there, `opacity` times the `mask` texture's
+    /// average channel; rays pass straight through elsewhere.
+    Opacity {
+        material: Box<MaterialTemplate>,
+        #[serde(default = "default_opacity", skip_serializing_if = "is_one")]
+        opacity: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mask: Option<TextureTemplate>,
+    },
+    /// `coat` over `base`, split by the Fresnel reflectance of a film of refractive
+    /// index `ior`; light reaching the base crosses the film's `tint` in and out.
+    Layered {
+        coat: Box<MaterialTemplate>,
+        base: Box<MaterialTemplate>,
+        #[serde(default = "default_coat_ior")]
+        ior: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tint: Option<vec::Vec3>,
+    },
+    /// A material registered under the tag `sampleable` with
+    /// [`plugins::register_material`].
+    #[serde(skip)]
+    Plugin {
+        sampleable: String,
+        data: Option<toml::Value>,
+    },
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-#[serde(tag = "texturable", content = "data")]
+#[serde(remote = "Self", tag = "texturable", content = "data")]
 pub enum TextureTemplate {
     Color(color::ColorTexture),
     Checker(checker::CheckerTexture),
     Noise(noise::NoiseTexture),
     Uv(uv::UvTexture),
+    VertexColor(vertex_color::VertexColorTexture),
+    /// A texture registered under the tag `texturable` with [`plugins::register_texture`].
+    #[serde(skip)]
+    Plugin {
+        texturable: String,
+        data: Option<toml::Value>,
+    },
+}
+
+impl Serialize for MaterialTemplate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MaterialTemplate::Plugin { sampleable, data } => {
+                serialize_plugin(serializer, "sampleable", sampleable, data)
+            }
+            _ => MaterialTemplate::serialize(self, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MaterialTemplate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = toml::Value::deserialize(deserializer)?;
+        match value.get("sampleable").and_then(toml::Value::as_str) {
+            Some(tag) if plugins::has_material(tag) => Ok(MaterialTemplate::Plugin {
+                sampleable: tag.to_string(),
+                data: value.get("data").cloned(),
+            }),
+            _ => MaterialTemplate::deserialize(value)
+                .map_err(|err| serde::de::Error::custom(err.message())),
+        }
+    }
+}
+
+impl Serialize for TextureTemplate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TextureTemplate::Plugin { texturable, data } => {
+                serialize_plugin(serializer, "texturable", texturable, data)
+            }
+            _ => TextureTemplate::serialize(self, serializer),
+        }
+    }
+}
+
+/// Writes a plugin entry the way built-in entries are written, its tag under `tag_key`
+/// and its `data` beside it.
+fn serialize_plugin<S: serde::Serializer>(
+    serializer: S,
+    tag_key: &'static str,
+    tag: &str,
+    data: &Option<toml::Value>,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeMap;
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry(tag_key, tag)?;
+    if let Some(data) = data {
+        map.serialize_entry("data", data)?;
+    }
+    map.end()
+}
+
+impl<'de> Deserialize<'de> for TextureTemplate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = toml::Value::deserialize(deserializer)?;
+        match value.get("texturable").and_then(toml::Value::as_str) {
+            Some(tag) if plugins::has_texture(tag) => Ok(TextureTemplate::Plugin {
+                texturable: tag.to_string(),
+                data: value.get("data").cloned(),
+            }),
+            _ => TextureTemplate::deserialize(value)
+                .map_err(|err| serde::de::Error::custom(err.message())),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -101,6 +471,16 @@ pub enum SceneFileError {
     UnsupportedTexture(String),
     MissingGeometry(usize),
     MissingMaterial(usize),
+    MissingObject(usize),
+    Bundle(String),
+    /// A texture image named by `path` couldn't be read or decoded.
+    TextureLoad {
+        path: String,
+        error: std::io::Error,
+    },
+    Resolution(resolution::ResolutionError),
+    /// A [`preprocess`] hook rejected the scene.
+    Preprocess(String),
 }
 
 impl std::fmt::Display for SceneFileError {
@@ -123,6 +503,13 @@ impl std::fmt::Display for SceneFileError {
             }
             SceneFileError::MissingGeometry(id) => write!(f, "missing geometry id {}", id),
             SceneFileError::MissingMaterial(id) => write!(f, "missing material id {}", id),
+            SceneFileError::MissingObject(index) => write!(f, "missing object {}", index),
+            SceneFileError::Bundle(reason) => write!(f, "invalid scene bundle: {}", reason),
+            SceneFileError::TextureLoad { error, .. } => write!(f, "{}", error),
+            SceneFileError::Resolution(err) => write!(f, "{}", err),
+            SceneFileError::Preprocess(reason) => {
+                write!(f, "scene preprocessing failed: {}", reason)
+            }
         }
     }
 }
@@ -141,6 +528,12 @@ impl From<toml::de::Error> for SceneFileError {
     }
 }
 
+impl From<resolution::ResolutionError> for SceneFileError {
+    fn from(value: resolution::ResolutionError) -> Self {
+        SceneFileError::Resolution(value)
+    }
+}
+
 impl From<toml::ser::Error> for SceneFileError {
     fn from(value: toml::ser::Error) -> Self {
         SceneFileError::TomlSer(value)
@@ -152,20 +545,80 @@ impl SceneFile {
         let mut builder = RegistryBuilder::default();
         let mut objects: Vec<ObjectInstance> = Vec::new();
         let mut volumes: Vec<VolumeInstance> = Vec::new();
+        let mut sections: Vec<Option<SectionSettings>> = Vec::new();
 
         for renderable in render.scene.renderables.objects.iter() {
             if let Some(render_object) = renderable.as_any().downcast_ref::<object::RenderObject>()
             {
-                let geometry_id =
-                    builder.register_geometry(&render_object.geometry_instance.ref_obj)?;
+                let mut instance = &render_object.geometry_instance;
+                if let Some(wrapper) = instance.ref_obj.as_any().downcast_ref::<section::Section>()
+                {
+                    let inner = wrapper
+                        .inner()
+                        .as_any()
+                        .downcast_ref::<GeometryInstance>()
+                        .ok_or_else(|| {
+                            SceneFileError::UnsupportedGeometry(
+                                "section of a geometry without its own instance".to_string(),
+                            )
+                        })?;
+                    let cap = render_object
+                        .material_instance
+                        .cap
+                        .as_ref()
+                        .map(|cap| builder.register_material(cap))
+                        .transpose()?;
+                    sections.push(Some(SectionSettings {
+                        planes: wrapper.planes().to_vec(),
+                        cap,
+                    }));
+                    instance = inner;
+                } else if instance
+                    .ref_obj
+                    .as_any()
+                    .downcast_ref::<world::World>()
+                    .is_none()
+                {
+                    sections.push(None);
+                }
+                let mut geometry = &instance.ref_obj;
+                let mut cutout = None;
+                if let Some(wrapper) = geometry.as_any().downcast_ref::<cutout::Cutout>() {
+                    let Some(pattern) = wrapper.pattern() else {
+                        return Err(SceneFileError::UnsupportedGeometry(
+                            "custom hit filter".to_string(),
+                        ));
+                    };
+                    cutout = Some(pattern.clone());
+                    geometry = wrapper.inner();
+                }
+                let geometry_id = builder.register_geometry(geometry)?;
                 let material_id =
                     builder.register_material(&render_object.material_instance.ref_mat)?;
+                let slot_materials = render_object
+                    .material_instance
+                    .slots
+                    .iter()
+                    .map(|slot| builder.register_material(slot))
+                    .collect::<Result<_, _>>()?;
 
                 objects.push(ObjectInstance {
                     geometry: geometry_id,
                     material: material_id,
-                    transforms: render_object.geometry_instance.transforms.clone(),
+                    transforms: instance
+                        .transforms
+                        .iter()
+                        .map(|transform| render.shutter.to_seconds(transform))
+                        .collect(),
                     albedo: render_object.material_instance.albedo,
+                    slot_materials,
+                    light_group: render_object
+                        .material_instance
+                        .light_group
+                        .and_then(|group| render.scene.light_groups.get(group).cloned()),
+                    cutout,
+                    motion_blur: instance.motion_blur,
+                    smooth_shading: instance.smooth_shading,
                 });
                 continue;
             }
@@ -189,7 +642,11 @@ impl SceneFile {
                     boundary_geometry: geometry_id,
                     phase_function: phase_function_id,
                     density: render_volume.density,
-                    boundary_transforms: boundary.transforms.clone(),
+                    boundary_transforms: boundary
+                        .transforms
+                        .iter()
+                        .map(|transform| render.shutter.to_seconds(transform))
+                        .collect(),
                 });
                 continue;
             }
@@ -198,24 +655,154 @@ impl SceneFile {
                 "unknown renderable type".to_string(),
             ));
         }
+        // Scene files only hold one set of planes, shared by every object but the sky.
+        let section = sections.first().cloned().flatten();
+        if sections.iter().any(|other| *other != section) {
+            return Err(SceneFileError::UnsupportedGeometry(
+                "section planes that differ between objects".to_string(),
+            ));
+        }
 
         Ok(SceneFile {
-            width: render.width,
-            samples: render.samples,
-            depth: render.depth,
+            width: render.resolution.width(),
+            height: Some(render.resolution.height()),
+            samples: Some(render.samples),
+            depth: Some(render.depth),
+            dither: render.dither,
+            seed: render.seed,
+            preset: render.preset,
+            presets: render.preset_overrides.clone(),
+            depth_range: render.depth_range,
+            film_precision: render.film_precision,
+            rng: render.rng,
+            shutter: render.shutter,
+            frame_noise: render.frame_noise,
+            exposure: render.exposure,
+            color_space: render.color_space,
+            stats_overlay: render.stats_overlay,
+            sky_only: render.scene.sky_only,
+            rays: render.ray_ranges,
+            section,
             camera: render.camera.clone(),
             geometries: builder.geometries,
             materials: builder.materials,
             objects,
             volumes,
+            scatters: Vec::new(),
+            god_rays: Vec::new(),
+        })
+    }
+
+    /// The volume `god_ray` expands into, its boundary added to `geometries`.
+    fn god_ray_volume(
+        &self,
+        god_ray: &GodRayInstance,
+        geometries: &mut Vec<std::sync::Arc<dyn hittable::Hittable + Send + Sync>>,
+    ) -> Result<VolumeInstance, SceneFileError> {
+        let light = self
+            .objects
+            .get(god_ray.light)
+            .ok_or(SceneFileError::MissingObject(god_ray.light))?;
+        let Some(geometry) = geometries.get(light.geometry) else {
+            return Err(SceneFileError::MissingGeometry(light.geometry));
+        };
+        let light_instance = GeometryInstance {
+            ref_obj: geometry.clone(),
+            transforms: light
+                .transforms
+                .iter()
+                .map(|transform| self.shutter.to_ray_time(transform))
+                .collect(),
+            motion_blur: light.motion_blur,
+            smooth_shading: light.smooth_shading,
+        };
+        let beam = god_rays::beam(&light_instance, &god_ray.settings).ok_or_else(|| {
+            SceneFileError::UnsupportedGeometry(format!(
+                "god rays need a `direction` for light object {}",
+                god_ray.light
+            ))
+        })?;
+        geometries.push(std::sync::Arc::new(beam));
+        Ok(VolumeInstance {
+            boundary_geometry: geometries.len() - 1,
+            phase_function: god_ray.phase_function,
+            density: god_ray.density,
+            boundary_transforms: Vec::new(),
         })
     }
 
+    /// The objects `scatter` expands into, scattered over its surface object built from
+    /// `geometries`.
+    fn scattered_objects(
+        &self,
+        scatter: &ScatterInstance,
+        geometries: &[std::sync::Arc<dyn hittable::Hittable + Send + Sync>],
+    ) -> Result<Vec<ObjectInstance>, SceneFileError> {
+        let surface = self
+            .objects
+            .get(scatter.surface)
+            .ok_or(SceneFileError::MissingObject(scatter.surface))?;
+        let Some(geometry) = geometries.get(surface.geometry) else {
+            return Err(SceneFileError::MissingGeometry(surface.geometry));
+        };
+        let geometry = match surface.cutout.clone() {
+            Some(pattern) => std::sync::Arc::new(cutout::Cutout::new(geometry.clone(), pattern))
+                as std::sync::Arc<dyn hittable::Hittable + Send + Sync>,
+            None => geometry.clone(),
+        };
+        let surface_instance = GeometryInstance {
+            ref_obj: geometry,
+            transforms: surface
+                .transforms
+                .iter()
+                .map(|transform| self.shutter.to_ray_time(transform))
+                .collect(),
+            motion_blur: surface.motion_blur,
+            smooth_shading: surface.smooth_shading,
+        };
+        let density = scatter
+            .density
+            .as_ref()
+            .map(|texture| texture.to_texturable())
+            .transpose()?;
+        let placements = scatter::placements(
+            &surface_instance,
+            &scatter.settings,
+            density
+                .as_deref()
+                .map(|density| density as &dyn texturable::Texturable),
+        )
+        .ok_or_else(|| {
+            SceneFileError::UnsupportedGeometry(format!(
+                "scatter surface object {} is unbounded",
+                scatter.surface
+            ))
+        })?;
+
+        Ok(placements
+            .into_iter()
+            .map(|transforms| ObjectInstance {
+                geometry: scatter.geometry,
+                material: scatter.material,
+                transforms,
+                albedo: None,
+                slot_materials: Vec::new(),
+                light_group: None,
+                cutout: None,
+                motion_blur: true,
+                smooth_shading: true,
+            })
+            .collect())
+    }
+
+    /// Builds the scene and its render settings, running the registered [`preprocess`]
+    /// hooks on the file first and on the scene before its BVH is built.
     pub fn into_render(
-        self,
+        mut self,
         rng: &mut rand::rngs::ThreadRng,
     ) -> Result<render::Render, SceneFileError> {
-        let geometries: Vec<_> = self
+        preprocess::run_scene_file_hooks(&mut self)?;
+        let mut geometries: Vec<_> = self
             .geometries
             .iter()
             .map(|entry| entry.geometry.to_hittable())
@@ -223,50 +810,141 @@ impl SceneFile {
         let materials: Vec<_> = self
             .materials
             .iter()
-            .map(|entry| entry.material.to_scatterable())
+            .map(|entry| entry.to_scatterable())
             .collect::<Result<_, _>>()?;
+        let cap = match self.section.as_ref().and_then(|section| section.cap) {
+            Some(id) => Some(
+                materials
+                    .get(id)
+                    .cloned()
+                    .ok_or(SceneFileError::MissingMaterial(id))?,
+            ),
+            None => None,
+        };
+
+        let scattered = self
+            .scatters
+            .iter()
+            .map(|scatter| self.scattered_objects(scatter, &geometries))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.objects.extend(scattered.into_iter().flatten());
+        let beams = self
+            .god_rays
+            .iter()
+            .map(|god_ray| self.god_ray_volume(god_ray, &mut geometries))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.volumes.extend(beams);
 
         let mut scene = scene::Scene::new();
         for object in self.objects.into_iter() {
             let Some(geometry) = geometries.get(object.geometry) else {
                 return Err(SceneFileError::MissingGeometry(object.geometry));
             };
+            let geometry = match object.cutout {
+                Some(pattern) => {
+                    std::sync::Arc::new(cutout::Cutout::new(geometry.clone(), pattern))
+                        as std::sync::Arc<dyn hittable::Hittable + Send + Sync>
+                }
+                None => geometry.clone(),
+            };
             let Some(material) = materials.get(object.material) else {
                 return Err(SceneFileError::MissingMaterial(object.material));
             };
 
             let albedo = object.albedo;
-            let transforms = object.transforms;
-            let geometry_instance = GeometryInstance {
+            let transforms: Vec<transform::Transform> = object
+                .transforms
+                .iter()
+                .map(|transform| self.shutter.to_ray_time(transform))
+                .collect();
+            let motion_blur = object.motion_blur;
+            let smooth_shading = object.smooth_shading;
+            let slots = object
+                .slot_materials
+                .iter()
+                .map(|id| {
+                    materials
+                        .get(*id)
+                        .cloned()
+                        .ok_or(SceneFileError::MissingMaterial(*id))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut geometry_instance = GeometryInstance {
                 ref_obj: geometry.clone(),
-                transforms: transforms.clone(),
+                transforms,
+                motion_blur,
+                smooth_shading,
             };
+            // Planes are in world space, so they cut the object after its transforms.
+            let sectioned = self
+                .section
+                .as_ref()
+                .filter(|_| geometry.as_any().downcast_ref::<world::World>().is_none());
+            if let Some(settings) = sectioned {
+                geometry_instance = GeometryInstance {
+                    ref_obj: std::sync::Arc::new(section::Section::new(
+                        std::sync::Arc::new(geometry_instance),
+                        settings.planes.clone(),
+                    )),
+                    transforms: Vec::new(),
+                    motion_blur,
+                    smooth_shading,
+                };
+            }
+            // A light's output depends on how much surface it covers, so every object
+            // fitted to a power gets its own copy.
+            let material = match self.materials.get(object.material).map(|entry| &entry.material) {
+                Some(
+                    light @ MaterialTemplate::DiffuseLight {
+                        power: Some(power), ..
+                    },
+                ) => std::sync::Arc::new(
+                    light
+                        .to_diffuse_light()?
+                        .with_power(*power, &geometry_instance)
+                        .ok_or_else(|| {
+                            SceneFileError::UnsupportedGeometry(format!(
+                                "light `power` on geometry {} with no surface to sample or a black texture",
+                                object.geometry
+                            ))
+                        })?,
+                )
+                    as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
+                _ => material.clone(),
+            };
+            let light_group = object
+                .light_group
+                .as_deref()
+                .map(|name| scene.add_light_group(name));
             let material_instance = MaterialInstance {
                 ref_mat: material.clone(),
                 albedo,
+                slots: slots.clone(),
+                light_group,
+                cap: cap.clone(),
             };
 
+            let light_geometry = GeometryInstance {
+                ref_obj: geometry_instance.ref_obj.clone(),
+                transforms: geometry_instance.transforms.clone(),
+                motion_blur,
+                smooth_shading,
+            };
             let render_object = object::RenderObject {
                 geometry_instance,
                 material_instance,
             };
-            let is_emissive = render_object
-                .material_instance
-                .ref_mat
-                .as_any()
-                .downcast_ref::<diffuse_light::DiffuseLight>()
-                .is_some();
+            let is_emissive = render_object.is_emissive();
 
             scene.add_object(Box::new(render_object));
 
             if is_emissive {
-                let light_geometry = GeometryInstance {
-                    ref_obj: geometry.clone(),
-                    transforms,
-                };
                 let light_material = MaterialInstance {
                     ref_mat: material.clone(),
                     albedo,
+                    slots,
+                    light_group,
+                    cap: cap.clone(),
                 };
                 scene.add_light(Box::new(object::RenderObject {
                     geometry_instance: light_geometry,
@@ -278,13 +956,29 @@ impl SceneFile {
             let Some(geometry) = geometries.get(volume.boundary_geometry) else {
                 return Err(SceneFileError::MissingGeometry(volume.boundary_geometry));
             };
+            if geometry
+                .as_any()
+                .downcast_ref::<mesh::Mesh>()
+                .is_some_and(|mesh| !mesh.is_closed())
+            {
+                return Err(SceneFileError::UnsupportedGeometry(format!(
+                    "volume boundary mesh {} is not closed",
+                    volume.boundary_geometry
+                )));
+            }
             let Some(phase_function) = materials.get(volume.phase_function) else {
                 return Err(SceneFileError::MissingMaterial(volume.phase_function));
             };
 
             let boundary = GeometryInstance {
                 ref_obj: geometry.clone(),
-                transforms: volume.boundary_transforms,
+                transforms: volume
+                    .boundary_transforms
+                    .iter()
+                    .map(|transform| self.shutter.to_ray_time(transform))
+                    .collect(),
+                motion_blur: true,
+                smooth_shading: true,
             };
 
             scene.add_object(Box::new(volume::RenderVolume::new(
@@ -293,15 +987,54 @@ impl SceneFile {
                 phase_function.clone(),
             )));
         }
+        preprocess::run_scene_hooks(&mut scene)?;
         scene.build_bvh(rng);
 
-        Ok(render::Render {
-            width: self.width,
-            samples: self.samples,
-            depth: self.depth,
-            camera: self.camera,
+        let preset = self.preset.unwrap_or_default();
+        let settings = preset.resolve(self.presets.get(&preset));
+        let resolution = match self.height {
+            Some(height) => resolution::Resolution::new(self.width, height)?,
+            None => resolution::Resolution::from_aspect(self.width, self.camera.aspect_ratio)?,
+        };
+        let mut render = render::Render::new(
+            resolution,
+            self.samples.unwrap_or(settings.samples),
+            self.depth.unwrap_or(settings.depth),
+            self.camera,
             scene,
-        })
+        );
+        render.preset = self.preset;
+        render.preset_overrides = self.presets;
+        render.dither = self.dither;
+        render.seed = self.seed;
+        render.depth_range = self.depth_range;
+        render.film_precision = self.film_precision;
+        render.rng = self.rng;
+        render.shutter = self.shutter;
+        render.frame_noise = self.frame_noise;
+        render.exposure = self.exposure;
+        render.color_space = self.color_space;
+        render.stats_overlay = self.stats_overlay;
+        render.scene.sky_only = self.sky_only;
+        render.ray_ranges = self.rays;
+        Ok(render)
+    }
+}
+
+/// Time spent in each phase of loading a scene, before the first ray is traced.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LoadTimings {
+    /// Reading and parsing the TOML (and unpacking bundles).
+    pub parse: Duration,
+    /// Decoding textures and loading and processing meshes, in parallel across entries.
+    pub assets: Duration,
+    /// Assembling objects and lights and building the scene's BVH.
+    pub build: Duration,
+}
+
+impl LoadTimings {
+    pub fn total(&self) -> Duration {
+        self.parse + self.assets + self.build
     }
 }
 
@@ -309,9 +1042,112 @@ pub fn load_render(
     rng: &mut rand::rngs::ThreadRng,
     path: &Path,
 ) -> Result<render::Render, SceneFileError> {
+    load_render_timed(rng, path).map(|(render, _)| render)
+}
+
+/// Like [`load_render`], also reporting how long each load phase took.
+pub fn load_render_timed(
+    rng: &mut rand::rngs::ThreadRng,
+    path: &Path,
+) -> Result<(render::Render, LoadTimings), SceneFileError> {
+    let (file, mut timings) = read_scene_file_timed(path)?;
+    let build_start = Instant::now();
+    let render = file.into_render(rng)?;
+    timings.build = build_start.elapsed();
+    Ok((render, timings))
+}
+
+/// Reads a scene file (or `.rustray` bundle) without building the scene.
+pub fn read_scene_file(path: &Path) -> Result<SceneFile, SceneFileError> {
+    read_scene_file_timed(path).map(|(file, _)| file)
+}
+
+/// Like [`read_scene_file`], also reporting how long parsing and loading assets took.
+pub fn read_scene_file_timed(path: &Path) -> Result<(SceneFile, LoadTimings), SceneFileError> {
+    if bundle::is_bundle(path) {
+        return bundle::read_scene_file_timed(path);
+    }
+    let parse_start = Instant::now();
     let content = std::fs::read_to_string(path)?;
-    let scene_file: SceneFile = toml::from_str(&content)?;
-    scene_file.into_render(rng)
+    let mut table: toml::Table = toml::from_str(&content)?;
+
+    // Geometry and material entries decode images and load and process meshes as they
+    // deserialize, so they're set aside and deserialized in parallel once the rest of
+    // the file has checked out.
+    let geometries = table.remove("geometries");
+    let materials = table.remove("materials");
+    for (key, entries) in [("geometries", &geometries), ("materials", &materials)] {
+        if entries.is_some() {
+            table.insert(key.to_string(), toml::Value::Array(Vec::new()));
+        }
+    }
+    uv::take_load_error();
+    let mut file: SceneFile = toml::Value::Table(table)
+        .try_into()
+        .map_err(deserialize_error)?;
+    let parse = parse_start.elapsed();
+
+    let assets_start = Instant::now();
+    // Taken here: either half of the join may run on another thread.
+    let base_dir = assets::base_dir();
+    let (geometries, materials) = rayon::join(
+        || deserialize_entries(geometries, "geometries", &base_dir),
+        || deserialize_entries(materials, "materials", &base_dir),
+    );
+    file.geometries = geometries?;
+    file.materials = materials?;
+    let timings = LoadTimings {
+        parse,
+        assets: assets_start.elapsed(),
+        build: Duration::ZERO,
+    };
+    Ok((file, timings))
+}
+
+/// Deserializes the entries of the array `key` in parallel, resolving relative asset
+/// references against `base_dir` if set.
+fn deserialize_entries<T: serde::de::DeserializeOwned + Send>(
+    entries: Option<toml::Value>,
+    key: &str,
+    base_dir: &Option<std::path::PathBuf>,
+) -> Result<Vec<T>, SceneFileError> {
+    let Some(entries) = entries else {
+        return Ok(Vec::new());
+    };
+    let toml::Value::Array(entries) = entries else {
+        return Err(SceneFileError::TomlDe(serde::de::Error::custom(format!(
+            "`{}` must be an array",
+            key
+        ))));
+    };
+    entries
+        .into_par_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let deserialize = || {
+                uv::take_load_error();
+                entry.try_into().map_err(|err| {
+                    deserialize_error(serde::de::Error::custom(format!(
+                        "{}[{}]: {}",
+                        key, index, err
+                    )))
+                })
+            };
+            match base_dir {
+                Some(dir) => assets::with_base_dir(dir, deserialize),
+                None => deserialize(),
+            }
+        })
+        .collect()
+}
+
+/// `err` from deserializing part of a scene on this thread, or the texture load failure
+/// behind it.
+fn deserialize_error(err: toml::de::Error) -> SceneFileError {
+    match uv::take_load_error() {
+        Some((path, error)) => SceneFileError::TextureLoad { path, error },
+        None => SceneFileError::TomlDe(err),
+    }
 }
 
 pub fn save_render(render: &render::Render, path: &Path) -> Result<(), SceneFileError> {
@@ -359,6 +1195,16 @@ impl RegistryBuilder {
 
         let entry = MaterialEntry {
             id: self.materials.len(),
+            max_bounces: material.max_bounces(),
+            normal_map: material
+                .normal_map()
+                .map(|normal_map| -> Result<_, SceneFileError> {
+                    Ok(NormalMapTemplate {
+                        texture: TextureTemplate::from_texturable(normal_map.texture())?,
+                        strength: normal_map.strength(),
+                    })
+                })
+                .transpose()?,
             material: MaterialTemplate::from_scatterable(material)?,
         };
         self.material_ids.insert(key, entry.id);
@@ -380,6 +1226,29 @@ impl GeometryTemplate {
         if let Some(cube) = hittable.as_any().downcast_ref::<cube::Cube>() {
             return Ok(GeometryTemplate::Cube(cube.clone()));
         }
+        if let Some(disk) = hittable.as_any().downcast_ref::<disk::Disk>() {
+            return Ok(GeometryTemplate::Disk(disk.clone()));
+        }
+        if let Some(sdf) = hittable.as_any().downcast_ref::<sdf::SdfGeometry>() {
+            if sdf.shape().is_none() {
+                return Err(SceneFileError::UnsupportedGeometry(
+                    "custom distance field".to_string(),
+                ));
+            }
+            return Ok(GeometryTemplate::Sdf(sdf.clone()));
+        }
+        if let Some(heightfield) = hittable.as_any().downcast_ref::<heightfield::Heightfield>() {
+            return Ok(GeometryTemplate::Heightfield(heightfield.clone()));
+        }
+        if let Some(curves) = hittable.as_any().downcast_ref::<curves::Curves>() {
+            return Ok(GeometryTemplate::Curves(curves.clone()));
+        }
+        if let Some(mesh) = hittable.as_any().downcast_ref::<mesh::Mesh>() {
+            return Ok(GeometryTemplate::Mesh(mesh.clone()));
+        }
+        if let Some(fractal) = hittable.as_any().downcast_ref::<generators::Fractal>() {
+            return Ok(GeometryTemplate::Fractal(fractal.clone()));
+        }
         if let Some(world) = hittable.as_any().downcast_ref::<world::World>() {
             return Ok(GeometryTemplate::World(*world));
         }
@@ -397,6 +1266,18 @@ impl GeometryTemplate {
                 as std::sync::Arc<dyn hittable::Hittable + Send + Sync>,
             GeometryTemplate::Cube(cube) => std::sync::Arc::new(cube.clone())
                 as std::sync::Arc<dyn hittable::Hittable + Send + Sync>,
+            GeometryTemplate::Disk(disk) => std::sync::Arc::new(disk.clone())
+                as std::sync::Arc<dyn hittable::Hittable + Send + Sync>,
+            GeometryTemplate::Sdf(sdf) => std::sync::Arc::new(sdf.clone())
+                as std::sync::Arc<dyn hittable::Hittable + Send + Sync>,
+            GeometryTemplate::Heightfield(heightfield) => std::sync::Arc::new(heightfield.clone())
+                as std::sync::Arc<dyn hittable::Hittable + Send + Sync>,
+            GeometryTemplate::Curves(curves) => std::sync::Arc::new(curves.clone())
+                as std::sync::Arc<dyn hittable::Hittable + Send + Sync>,
+            GeometryTemplate::Mesh(mesh) => std::sync::Arc::new(mesh.clone())
+                as std::sync::Arc<dyn hittable::Hittable + Send + Sync>,
+            GeometryTemplate::Fractal(fractal) => std::sync::Arc::new(fractal.clone())
+                as std::sync::Arc<dyn hittable::Hittable + Send + Sync>,
             GeometryTemplate::World(world) => {
                 std::sync::Arc::new(*world) as std::sync::Arc<dyn hittable::Hittable + Send + Sync>
             }
@@ -404,6 +1285,28 @@ impl GeometryTemplate {
     }
 }
 
+impl MaterialEntry {
+    /// Builds the entry's material, with its normal map and limited to its `max_bounces`
+    /// if it has them.
+    pub fn to_scatterable(
+        &self,
+    ) -> Result<std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>, SceneFileError> {
+        let mut material = self.material.to_scatterable()?;
+        if let Some(mapped) = &self.normal_map {
+            material = std::sync::Arc::new(
+                normal_map::NormalMap::new(material, mapped.texture.to_texturable()?)
+                    .with_strength(mapped.strength),
+            );
+        }
+        Ok(match self.max_bounces {
+            Some(max_bounces) => {
+                std::sync::Arc::new(bounce_limit::BounceLimit::new(material, max_bounces))
+            }
+            None => material,
+        })
+    }
+}
+
 impl MaterialTemplate {
     fn from_scatterable(
         material: &std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
@@ -419,10 +1322,48 @@ impl MaterialTemplate {
             });
         }
         if let Some(metal) = material.as_any().downcast_ref::<metallic::Metallic>() {
-            return Ok(MaterialTemplate::Metallic(metal.clone()));
+            return Ok(MaterialTemplate::Metallic {
+                metal: metal.clone(),
+                texture: metal
+                    .texture
+                    .as_ref()
+                    .map(|texture| TextureTemplate::from_texturable(texture.as_ref()))
+                    .transpose()?,
+            });
         }
         if let Some(dielectric) = material.as_any().downcast_ref::<dielectric::Dielectric>() {
-            return Ok(MaterialTemplate::Dielectric(dielectric.clone()));
+            return Ok(MaterialTemplate::Dielectric {
+                glass: dielectric.clone(),
+                texture: dielectric
+                    .texture
+                    .as_ref()
+                    .map(|texture| TextureTemplate::from_texturable(texture.as_ref()))
+                    .transpose()?,
+            });
+        }
+        if let Some(principled) = material.as_any().downcast_ref::<principled::Principled>() {
+            return Ok(MaterialTemplate::Principled(principled.clone()));
+        }
+        if let Some(anisotropic) = material.as_any().downcast_ref::<anisotropic::Anisotropic>() {
+            return Ok(MaterialTemplate::Anisotropic(anisotropic.clone()));
+        }
+        if let Some(conductor) = material.as_any().downcast_ref::<conductor::Conductor>() {
+            return Ok(MaterialTemplate::Conductor(conductor.clone()));
+        }
+        if let Some(blinn_phong) = material.as_any().downcast_ref::<blinn_phong::BlinnPhong>() {
+            return Ok(MaterialTemplate::BlinnPhong(blinn_phong.clone()));
+        }
+        if let Some(velvet) = material.as_any().downcast_ref::<velvet::Velvet>() {
+            return Ok(MaterialTemplate::Velvet(velvet.clone()));
+        }
+        if let Some(toon) = material.as_any().downcast_ref::<toon::Toon>() {
+            return Ok(MaterialTemplate::Toon(toon.clone()));
+        }
+        if let Some(plastic) = material.as_any().downcast_ref::<plastic::Plastic>() {
+            return Ok(MaterialTemplate::Plastic(plastic.clone()));
+        }
+        if let Some(measured) = material.as_any().downcast_ref::<measured::Measured>() {
+            return Ok(MaterialTemplate::Measured(measured.clone()));
         }
         if let Some(diffuse_light) = material
             .as_any()
@@ -430,18 +1371,51 @@ impl MaterialTemplate {
         {
             return Ok(MaterialTemplate::DiffuseLight {
                 texture: TextureTemplate::from_texturable(diffuse_light.texture.as_ref())?,
+                power: diffuse_light.power(),
+                intensity: diffuse_light.intensity(),
+                single_sided: diffuse_light.single_sided(),
+                camera_visible: diffuse_light.camera_visible(),
             });
         }
         if let Some(world) = material.as_any().downcast_ref::<world::World>() {
             return Ok(MaterialTemplate::World(*world));
         }
+        if let Some(two_sided) = material.as_any().downcast_ref::<two_sided::TwoSided>() {
+            return Ok(MaterialTemplate::TwoSided {
+                front: Box::new(MaterialTemplate::from_scatterable(two_sided.front())?),
+                back: Box::new(MaterialTemplate::from_scatterable(two_sided.back())?),
+            });
+        }
+        if let Some(opacity) = material.as_any().downcast_ref::<opacity::Opacity>() {
+            return Ok(MaterialTemplate::Opacity {
+                material: Box::new(MaterialTemplate::from_scatterable(opacity.material())?),
+                opacity: opacity.opacity(),
+                mask: opacity
+                    .mask()
+                    .map(TextureTemplate::from_texturable)
+                    .transpose()?,
+            });
+        }
+        if let Some(layered) = material.as_any().downcast_ref::<layered::Layered>() {
+            return Ok(MaterialTemplate::Layered {
+                coat: Box::new(MaterialTemplate::from_scatterable(layered.coat())?),
+                base: Box::new(MaterialTemplate::from_scatterable(layered.base())?),
+                ior: layered.ior(),
+                tint: layered.tint(),
+            });
+        }
+        if let Some(saved) = plugins::save_material(material.as_ref()) {
+            let (sampleable, data) = saved?;
+            return Ok(MaterialTemplate::Plugin { sampleable, data });
+        }
 
         Err(SceneFileError::UnsupportedMaterial(
             "unknown material".to_string(),
         ))
     }
 
-    fn to_scatterable(
+    /// Builds the material this template describes.
+    pub fn to_scatterable(
         &self,
     ) -> Result<std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>, SceneFileError> {
         let material: std::sync::Arc<dyn scatterable::Scatterable + Send + Sync> = match self {
@@ -451,19 +1425,93 @@ impl MaterialTemplate {
             MaterialTemplate::Isotropic { texture } => {
                 std::sync::Arc::new(volume::Isotropic::new(texture.to_texturable()?))
             }
-            MaterialTemplate::Metallic(metal) => std::sync::Arc::new(metal.clone())
+            MaterialTemplate::Metallic { metal, texture } => {
+                let mut metal = metal.clone();
+                if let Some(texture) = texture {
+                    metal = metal.with_texture(texture.to_texturable()?.into());
+                }
+                std::sync::Arc::new(metal)
+            }
+            MaterialTemplate::Dielectric { glass, texture } => {
+                let mut glass = glass.clone();
+                if let Some(texture) = texture {
+                    glass = glass.with_texture(texture.to_texturable()?.into());
+                }
+                std::sync::Arc::new(glass)
+            }
+            MaterialTemplate::Principled(principled) => std::sync::Arc::new(principled.clone())
                 as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
-            MaterialTemplate::Dielectric(dielectric) => std::sync::Arc::new(dielectric.clone())
+            MaterialTemplate::Anisotropic(anisotropic) => std::sync::Arc::new(anisotropic.clone())
                 as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
-            MaterialTemplate::DiffuseLight { texture } => {
-                std::sync::Arc::new(diffuse_light::DiffuseLight::new(texture.to_texturable()?))
-            }
+            MaterialTemplate::Conductor(conductor) => std::sync::Arc::new(conductor.clone())
+                as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
+            MaterialTemplate::BlinnPhong(blinn_phong) => std::sync::Arc::new(blinn_phong.clone())
+                as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
+            MaterialTemplate::Velvet(velvet) => std::sync::Arc::new(velvet.clone())
+                as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
+            MaterialTemplate::Toon(toon) => std::sync::Arc::new(toon.clone())
+                as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
+            MaterialTemplate::Plastic(plastic) => std::sync::Arc::new(plastic.clone())
+                as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
+            MaterialTemplate::Measured(measured) => std::sync::Arc::new(measured.clone())
+                as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
+            MaterialTemplate::DiffuseLight { .. } => std::sync::Arc::new(self.to_diffuse_light()?),
             MaterialTemplate::World(world) => std::sync::Arc::new(*world)
                 as std::sync::Arc<dyn scatterable::Scatterable + Send + Sync>,
+            MaterialTemplate::TwoSided { front, back } => std::sync::Arc::new(
+                two_sided::TwoSided::new(front.to_scatterable()?, back.to_scatterable()?),
+            ),
+            MaterialTemplate::Opacity {
+                material,
+                opacity,
+                mask,
+            } => {
+                let mut wrapper = opacity::Opacity::new(material.to_scatterable()?, *opacity);
+                if let Some(mask) = mask {
+                    wrapper = wrapper.with_mask(mask.to_texturable()?);
+                }
+                std::sync::Arc::new(wrapper)
+            }
+            MaterialTemplate::Layered {
+                coat,
+                base,
+                ior,
+                tint,
+            } => {
+                let mut layered =
+                    layered::Layered::new(coat.to_scatterable()?, base.to_scatterable()?, *ior);
+                if let Some(tint) = tint {
+                    layered = layered.with_tint(*tint);
+                }
+                std::sync::Arc::new(layered)
+            }
+            MaterialTemplate::Plugin { sampleable, data } => {
+                plugins::build_material(sampleable, data.clone())?
+            }
         };
 
         Ok(material)
     }
+
+    /// Builds a `DiffuseLight` template's light, without fitting it to its power.
+    fn to_diffuse_light(&self) -> Result<diffuse_light::DiffuseLight, SceneFileError> {
+        let MaterialTemplate::DiffuseLight {
+            texture,
+            intensity,
+            single_sided,
+            camera_visible,
+            ..
+        } = self
+        else {
+            return Err(SceneFileError::UnsupportedMaterial(
+                "expected a DiffuseLight".to_string(),
+            ));
+        };
+        Ok(diffuse_light::DiffuseLight::new(texture.to_texturable()?)
+            .with_intensity(*intensity)
+            .with_single_sided(*single_sided)
+            .with_camera_visible(*camera_visible))
+    }
 }
 
 impl TextureTemplate {
@@ -480,6 +1528,16 @@ impl TextureTemplate {
         if let Some(uv) = texture.as_any().downcast_ref::<uv::UvTexture>() {
             return Ok(TextureTemplate::Uv(uv.clone()));
         }
+        if let Some(vertex_color) = texture
+            .as_any()
+            .downcast_ref::<vertex_color::VertexColorTexture>()
+        {
+            return Ok(TextureTemplate::VertexColor(vertex_color.clone()));
+        }
+        if let Some(saved) = plugins::save_texture(texture) {
+            let (texturable, data) = saved?;
+            return Ok(TextureTemplate::Plugin { texturable, data });
+        }
 
         Err(SceneFileError::UnsupportedTexture(
             "unknown texture".to_string(),
@@ -494,6 +1552,10 @@ impl TextureTemplate {
             TextureTemplate::Checker(checker) => Box::new(checker.clone()),
             TextureTemplate::Noise(noise) => Box::new(noise.clone()),
             TextureTemplate::Uv(uv) => Box::new(uv.clone()),
+            TextureTemplate::VertexColor(vertex_color) => Box::new(vertex_color.clone()),
+            TextureTemplate::Plugin { texturable, data } => {
+                plugins::build_texture(texturable, data.clone())?
+            }
         };
 
         Ok(texture)
@@ -504,3 +1566,244 @@ fn arc_key<T: ?Sized>(arc: &std::sync::Arc<T>) -> usize {
     let ptr = std::sync::Arc::as_ptr(arc);
     ptr as *const () as usize
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cornell_box() -> SceneFile {
+        read_scene_file(Path::new("scenes/cornell_box.toml")).unwrap()
+    }
+
+    fn to_value(scene: &SceneFile) -> toml::Value {
+        toml::Value::try_from(scene).unwrap()
+    }
+
+    /// Checks that `edit` changes what the Cornell box saves as and that the change
+    /// survives a save and reload.
+    fn assert_round_trips(field: &str, edit: impl Fn(&mut SceneFile)) {
+        let original = to_value(&cornell_box());
+        let mut scene = cornell_box();
+        edit(&mut scene);
+        let saved = to_value(&scene);
+        assert_ne!(saved, original, "{} isn't saved", field);
+
+        let text = toml::to_string(&scene)
+            .unwrap_or_else(|err| panic!("{} fails to serialize: {}", field, err));
+        let loaded: SceneFile = toml::from_str(&text)
+            .unwrap_or_else(|err| panic!("{} fails to deserialize: {}", field, err));
+        assert_eq!(to_value(&loaded), saved, "{} changes on reload", field);
+    }
+
+    #[test]
+    fn slot_materials_survive_a_round_trip() {
+        assert_round_trips("slot_materials", |scene| {
+            scene.objects[0].slot_materials = vec![0, 1];
+        });
+    }
+
+    #[test]
+    fn dither_and_seed_survive_a_round_trip() {
+        assert_round_trips("dither", |scene| scene.dither = true);
+        assert_round_trips("seed", |scene| scene.seed = i64::MAX as u64);
+        assert_round_trips("seed", |scene| scene.seed = u64::MAX);
+        let mut scene = cornell_box();
+        scene.seed = u64::MAX;
+        assert_eq!(to_value(&scene)["seed"], toml::Value::Integer(-1));
+    }
+
+    #[test]
+    fn presets_survive_a_round_trip() {
+        assert_round_trips("samples", |scene| scene.samples = Some(8));
+        assert_round_trips("depth", |scene| scene.depth = Some(5));
+        assert_round_trips("preset", |scene| scene.preset = Some(preset::Preset::Final));
+        assert_round_trips("presets", |scene| {
+            scene.presets.insert(
+                preset::Preset::Final,
+                preset::PresetOverride {
+                    samples: Some(2000),
+                    depth: None,
+                },
+            );
+        });
+    }
+
+    #[test]
+    fn depth_range_survives_a_round_trip() {
+        assert_round_trips("depth_range", |scene| {
+            scene.depth_range = Some(aov::DepthRange {
+                near: 1.0,
+                far: 20.0,
+            })
+        });
+    }
+
+    #[test]
+    fn light_group_survives_a_round_trip() {
+        assert_round_trips("light_group", |scene| {
+            scene.objects[0].light_group = Some(String::from("key"));
+        });
+    }
+
+    #[test]
+    fn film_precision_survives_a_round_trip() {
+        assert_round_trips("film_precision", |scene| {
+            scene.film_precision = film::FilmPrecision::Half
+        });
+    }
+
+    #[test]
+    fn rng_survives_a_round_trip() {
+        assert_round_trips("rng", |scene| scene.rng = rng::RngAlgorithm::Pcg32);
+    }
+
+    #[test]
+    fn cutout_survives_a_round_trip() {
+        assert_round_trips("cutout", |scene| {
+            scene.objects[0].cutout = Some(cutout::CutoutPattern::Holes {
+                spacing: 10.0,
+                radius: 2.0,
+            });
+        });
+    }
+
+    #[test]
+    fn motion_blur_survives_a_round_trip() {
+        assert_round_trips("motion_blur", |scene| scene.objects[0].motion_blur = false);
+    }
+
+    #[test]
+    fn shutter_survives_a_round_trip() {
+        assert_round_trips("shutter", |scene| {
+            scene.shutter = shutter::Shutter::frame(2.0, 24.0, 180.0)
+        });
+    }
+
+    #[test]
+    fn smooth_shading_survives_a_round_trip() {
+        assert_round_trips("smooth_shading", |scene| {
+            scene.objects[0].smooth_shading = false;
+        });
+    }
+
+    #[test]
+    fn exposure_survives_a_round_trip() {
+        assert_round_trips("exposure", |scene| scene.exposure = Some(12.0));
+    }
+
+    #[test]
+    fn stats_overlay_survives_a_round_trip() {
+        assert_round_trips("stats_overlay", |scene| scene.stats_overlay = true);
+    }
+
+    #[test]
+    fn rays_survive_a_round_trip() {
+        assert_round_trips("rays", |scene| {
+            scene.rays.camera = ray::RayRange::new(0.5, 1000.0);
+            scene.rays.indirect = ray::RayRange::new(0.01, 500.0);
+        });
+    }
+
+    #[test]
+    fn camera_clips_survive_a_round_trip() {
+        assert_round_trips("camera clips", |scene| {
+            scene.camera.near_clip = Some(1.0);
+            scene.camera.far_clip = Some(500.0);
+        });
+    }
+
+    #[test]
+    fn section_survives_a_round_trip() {
+        assert_round_trips("section", |scene| {
+            scene.section = Some(SectionSettings {
+                planes: vec![section::SectionPlane {
+                    point: vec::Vec3::new(0.0, 100.0, 0.0),
+                    normal: vec::Vec3::new(0.0, 1.0, 0.0),
+                }],
+                cap: Some(4),
+            })
+        });
+    }
+
+    #[test]
+    fn height_survives_a_round_trip() {
+        assert_round_trips("height", |scene| scene.height = Some(300));
+    }
+
+    #[test]
+    fn max_bounces_survive_a_round_trip() {
+        assert_round_trips("max_bounces", |scene| {
+            scene.materials[0].max_bounces = Some(4)
+        });
+    }
+
+    #[test]
+    fn color_space_survives_a_round_trip() {
+        assert_round_trips("color_space", |scene| {
+            scene.color_space = ColorSpace::Rec2020
+        });
+    }
+
+    #[test]
+    fn normal_map_survives_a_round_trip() {
+        assert_round_trips("normal_map", |scene| {
+            scene.materials[0].normal_map = Some(NormalMapTemplate {
+                texture: TextureTemplate::Color(color::ColorTexture::new(vec::Vec3::new(
+                    0.5, 0.5, 1.0,
+                ))),
+                strength: 0.5,
+            });
+        });
+    }
+
+    #[test]
+    fn scatters_survive_a_round_trip() {
+        assert_round_trips("scatters", |scene| {
+            let mut settings = scatter::ScatterSettings::new(10);
+            settings.seed = i64::MAX as u64;
+            scene.scatters.push(ScatterInstance {
+                geometry: 0,
+                material: 0,
+                surface: 0,
+                density: Some(TextureTemplate::Color(color::ColorTexture::new(
+                    vec::Vec3::new(0.5, 0.5, 0.5),
+                ))),
+                settings,
+            })
+        });
+    }
+
+    #[test]
+    fn sky_only_survives_a_round_trip() {
+        assert_round_trips("sky_only", |scene| scene.sky_only = true);
+    }
+
+    #[test]
+    fn dispersion_survives_a_round_trip() {
+        assert_round_trips("dispersion", |scene| scene.camera.dispersion = Some(0.02));
+    }
+
+    #[test]
+    fn god_rays_survive_a_round_trip() {
+        assert_round_trips("god_rays", |scene| {
+            scene.god_rays.push(GodRayInstance {
+                light: 0,
+                phase_function: 0,
+                density: 0.01,
+                settings: god_rays::BeamSettings {
+                    length: 100.0,
+                    angle: 30.0,
+                    direction: Some(vec::Vec3::new(0.0, -1.0, 0.0)),
+                    segments: 8,
+                },
+            })
+        });
+    }
+
+    #[test]
+    fn frame_noise_survives_a_round_trip() {
+        assert_round_trips("frame_noise", |scene| {
+            scene.frame_noise = shutter::FrameNoise::PerFrame
+        });
+    }
+}
@@ -0,0 +1,153 @@
+//! How ray time relates to time in the scene.
+//!
+//! Every ray carries a time in `[0, 1)`: how far through the shutter interval it was
+//! cast, 0 as the shutter opens and 1 as it closes. Geometry is intersected in those
+//! units, so [`crate::geometry::transform::Transform::Move`] keyframes in a built scene
+//! are shutter fractions too. A [`Shutter`] pins the interval to scene time in seconds,
+//! so motion can be authored in real units (speeds from a physics simulation, keyframes
+//! from an animation at a given frame rate) and converted with [`Shutter::ray_time`].
+//! Scene files give `Move` keyframes in seconds and are converted on load.
+//!
+//! Frames of an animation differ only in their shutter, so it also tells frames apart
+//! for [`FrameNoise::PerFrame`] sampling.
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::transform;
+use crate::math::rng;
+
+/// Shortest shutter interval; a closed shutter is treated as open this long, which
+/// freezes motion at the moment it opens.
+const MIN_DURATION: f64 = 1e-9;
+
+/// When the camera's shutter is open, in scene seconds.
+///
+/// The default is open from 0 to 1 second, which makes seconds and ray time the same.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Shutter {
+    pub open: f64,
+    pub close: f64,
+}
+
+impl Default for Shutter {
+    fn default() -> Self {
+        Shutter {
+            open: 0.0,
+            close: 1.0,
+        }
+    }
+}
+
+impl Shutter {
+    pub fn new(open: f64, close: f64) -> Self {
+        Shutter { open, close }
+    }
+
+    /// The shutter of frame `frame` of an animation at `frame_rate` frames per second,
+    /// open for `angle` degrees of each frame's 360 (a film camera's 180-degree shutter
+    /// is open for half the frame).
+    pub fn frame(frame: f64, frame_rate: f64, angle: f64) -> Self {
+        let open = frame / frame_rate;
+        Shutter {
+            open,
+            close: open + angle / 360.0 / frame_rate,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Shutter::default()
+    }
+
+    /// How long the shutter stays open, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.close - self.open
+    }
+
+    /// Scene time of a ray cast at `ray_time`.
+    pub fn seconds(&self, ray_time: f64) -> f64 {
+        self.open + ray_time * self.duration().max(MIN_DURATION)
+    }
+
+    /// Ray time at `seconds` of scene time; outside `[0, 1)` before the shutter opens or
+    /// after it closes.
+    pub fn ray_time(&self, seconds: f64) -> f64 {
+        (seconds - self.open) / self.duration().max(MIN_DURATION)
+    }
+
+    /// Converts a transform's keyframes from scene seconds to ray time.
+    pub fn to_ray_time(&self, transform: &transform::Transform) -> transform::Transform {
+        transform.retimed(|seconds| self.ray_time(seconds))
+    }
+
+    /// Converts a transform's keyframes from ray time to scene seconds.
+    pub fn to_seconds(&self, transform: &transform::Transform) -> transform::Transform {
+        transform.retimed(|ray_time| self.seconds(ray_time))
+    }
+}
+
+impl<'de> Deserialize<'de> for Shutter {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum ShutterData {
+            Interval {
+                open: f64,
+                close: f64,
+            },
+            Frame {
+                frame: f64,
+                frame_rate: f64,
+                #[serde(default = "default_angle")]
+                angle: f64,
+            },
+        }
+
+        match ShutterData::deserialize(deserializer)? {
+            ShutterData::Interval { open, close } => Ok(Shutter::new(open, close)),
+            ShutterData::Frame {
+                frame,
+                frame_rate,
+                angle,
+            } => {
+                if frame_rate <= 0.0 {
+                    return Err(serde::de::Error::custom("frame_rate must be positive"));
+                }
+                Ok(Shutter::frame(frame, frame_rate, angle))
+            }
+        }
+    }
+}
+
+fn default_angle() -> f64 {
+    180.0
+}
+
+/// Whether sampling noise follows the pixel or the frame across an animation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FrameNoise {
+    /// Every frame draws the same samples for a pixel, so noise stays put on screen
+    /// while the picture moves, as temporal denoisers expect. The default.
+    #[default]
+    Fixed,
+    /// Each frame's samples are seeded from its shutter as well, so noise changes from
+    /// frame to frame and averages out across them, as frame blending and
+    /// [`crate::core::temporal::TemporalReuse`] need.
+    PerFrame,
+}
+
+impl FrameNoise {
+    pub fn is_default(&self) -> bool {
+        *self == FrameNoise::default()
+    }
+
+    /// Sampling seed for the frame `shutter` opens on, from the render's `seed`.
+    pub fn seed(&self, seed: u64, shutter: &Shutter) -> u64 {
+        match self {
+            FrameNoise::Fixed => seed,
+            FrameNoise::PerFrame => seed ^ rng::mix(shutter.open.to_bits()),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Frame-to-frame sample reuse for animation sequences.
+use crate::core::{camera, film, render};
+use crate::math::vec;
+
+/// Distance recorded for pixels whose primary ray escapes the scene.
+const MISS_DISTANCE: f32 = f32::INFINITY;
+
+/// What the previous frame left behind for reprojection.
+struct History {
+    camera: camera::Camera,
+    width: u32,
+    height: u32,
+    color: Vec<vec::Vec3>,
+    /// Distance from the camera to each pixel's primary hit.
+    distance: Vec<f32>,
+    /// Number of frames accumulated into each pixel.
+    frames: Vec<u32>,
+}
+
+/// Accumulates successive animation frames by reprojecting the previous frame's film.
+///
+/// Each pixel's primary hit is projected into the previous frame's camera; if the
+/// previous frame saw the same surface there (its primary hit distance agrees within
+/// `distance_tolerance`), the new frame is blended into that pixel's running average.
+/// Otherwise the pixel was disoccluded and starts over from the new frame alone.
+/// Mostly static shots converge as if rendered with several times the samples, while
+/// moving content falls back to single-frame noise instead of ghosting.
+///
+/// Render each frame with [`crate::raytrace_film`], pass it through
+/// [`TemporalReuse::accumulate`], and encode the result with [`render::Render::encode`];
+/// `rustray sequence --temporal` does this for every frame of a scene.
+/// Give each frame its own noise, with [`crate::core::shutter::FrameNoise::PerFrame`] or
+/// a `seed` per frame; frames sampled alike repeat the same noise, so averaging them
+/// gains nothing.
+pub struct TemporalReuse {
+    /// Caps how many frames a pixel averages over; older frames then decay exponentially.
+    pub max_frames: u32,
+    /// Relative difference in primary hit distance treated as the same surface.
+    pub distance_tolerance: f32,
+    history: Option<History>,
+}
+
+impl TemporalReuse {
+    pub fn new() -> Self {
+        TemporalReuse {
+            max_frames: 16,
+            distance_tolerance: 0.02,
+            history: None,
+        }
+    }
+
+    pub fn with_max_frames(mut self, max_frames: u32) -> Self {
+        self.max_frames = max_frames.max(1);
+        self
+    }
+
+    pub fn with_distance_tolerance(mut self, distance_tolerance: f32) -> Self {
+        self.distance_tolerance = distance_tolerance;
+        self
+    }
+
+    /// Drops the accumulated history, e.g. at a cut.
+    pub fn reset(&mut self) {
+        self.history = None;
+    }
+
+    /// Blends a newly rendered frame with the reprojected history and returns the
+    /// accumulated film. `render` must be the state the frame was rendered with.
+    pub fn accumulate(&mut self, render: &render::Render, frame: film::Film) -> film::Film {
+        let (width, height) = (frame.width, frame.height);
+        let pixel_count = width as usize * height as usize;
+        let mut color = Vec::with_capacity(pixel_count);
+        let mut distance = Vec::with_capacity(pixel_count);
+        let mut frames = Vec::with_capacity(pixel_count);
+
+        let history = self
+            .history
+            .take()
+            .filter(|history| history.width == width && history.height == height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let u = (x as f32 + 0.5) / width as f32;
+                let v = (y as f32 + 0.5) / height as f32;
+                let ray = render.camera.center_ray(u, v);
+                let direction = ray.direction.normalize();
+                let hit_distance = render
+                    .camera
+                    .clip(&ray, &render.ray_ranges)
+                    .camera
+                    .hit(&render.scene, &ray)
+                    // The `World` background reports its hit at `f32::MAX`.
+                    .filter(|record| record.hit.t < f32::MAX)
+                    .map_or(MISS_DISTANCE, |record| {
+                        record.hit.t * ray.direction.length()
+                    });
+
+                let current = frame.pixel(x, y);
+                let previous = history.as_ref().and_then(|history| {
+                    self.reproject(history, &render.camera.origin, &direction, hit_distance)
+                });
+
+                let (pixel_color, pixel_frames) = match previous {
+                    Some((previous_color, previous_frames)) => {
+                        let pixel_frames = (previous_frames + 1).min(self.max_frames);
+                        let weight = 1.0 / pixel_frames as f32;
+                        (
+                            previous_color * (1.0 - weight) + current * weight,
+                            pixel_frames,
+                        )
+                    }
+                    None => (current, 1),
+                };
+                color.push(pixel_color);
+                distance.push(hit_distance);
+                frames.push(pixel_frames);
+            }
+        }
+
+        let output = film::Film::with_precision(width, height, render.film_precision);
+        for y in 0..height {
+            for x in 0..width {
+                output.splat(x, y, color[(y * width + x) as usize]);
+            }
+        }
+
+        self.history = Some(History {
+            camera: render.camera.clone(),
+            width,
+            height,
+            color,
+            distance,
+            frames,
+        });
+        output
+    }
+
+    /// Looks up the previous frame's color and frame count for a surface point seen
+    /// from `origin` along `direction`, or `None` if it was not visible there.
+    fn reproject(
+        &self,
+        history: &History,
+        origin: &vec::Vec3,
+        direction: &vec::Vec3,
+        hit_distance: f32,
+    ) -> Option<(vec::Vec3, u32)> {
+        // Misses reproject by direction alone, using a point far along the ray.
+        let point_distance = if hit_distance.is_finite() {
+            hit_distance
+        } else {
+            1.0e6
+        };
+        let point = *origin + *direction * point_distance;
+
+        let (u, v) = history.camera.project(&point)?;
+        if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+            return None;
+        }
+        let x = (u * history.width as f32) as u32;
+        let y = (v * history.height as f32) as u32;
+        let index = (y * history.width + x) as usize;
+
+        let previous_distance = history.distance[index];
+        let visible = if hit_distance.is_finite() {
+            let expected = (point - history.camera.origin).length();
+            (previous_distance - expected).abs() <= self.distance_tolerance * expected
+        } else {
+            !previous_distance.is_finite()
+        };
+        visible.then(|| (history.color[index], history.frames[index]))
+    }
+}
+
+impl Default for TemporalReuse {
+    fn default() -> Self {
+        TemporalReuse::new()
+    }
+}
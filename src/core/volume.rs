@@ -1,8 +1,8 @@
 use rand::Rng;
 use std::sync::Arc;
 
-use crate::core::{bbox, ray};
-use crate::math::{pdf, vec};
+use crate::core::{bbox, path, ray};
+use crate::math::{pdf, rng, vec};
 use crate::traits::{hittable, renderable, scatterable, texturable};
 
 pub struct Isotropic {
@@ -22,11 +22,11 @@ impl Isotropic {
 impl scatterable::Scatterable for Isotropic {
     fn scatter(
         &self,
-        _rng: &mut rand::rngs::ThreadRng,
+        _rng: &mut rng::SampleRng,
         hit_record: &hittable::HitRecord,
-        depth: u32,
+        state: &mut path::PathState,
     ) -> Option<scatterable::ScatterRecord> {
-        if depth == 0 {
+        if state.depth == 0 {
             return None;
         }
 
@@ -53,6 +53,13 @@ pub struct RenderVolume {
     pub phase_function: Arc<dyn scatterable::Scatterable + Send + Sync>,
 }
 
+/// Step past each boundary crossing before looking for the next, so a ray through an
+/// edge shared by two triangles isn't counted as crossing twice.
+const CROSSING_STEP: f32 = 0.0001;
+
+/// Most boundary crossings followed along one ray.
+const MAX_CROSSINGS: usize = 64;
+
 impl RenderVolume {
     pub fn new(
         boundary: Box<dyn hittable::Hittable + Send + Sync>,
@@ -65,33 +72,56 @@ impl RenderVolume {
             phase_function,
         }
     }
+
+    /// The stretches of `ray` inside the boundary, as `(enter, exit)` parameters in
+    /// order along the whole line. Crossings alternate between entering and leaving
+    /// from far behind the origin, which holds for any closed surface (spheres, boxes,
+    /// closed meshes however concave) whichever way its normals face; a final entry
+    /// with no exit, through a hole in an open surface, is dropped.
+    fn inside_spans(&self, ray: &ray::Ray) -> Vec<(f32, f32)> {
+        let mut spans = Vec::new();
+        let mut entered = None;
+        let mut t = f32::MIN;
+        for _ in 0..MAX_CROSSINGS {
+            let Some(crossing) = self.boundary.hit(ray, t, f32::MAX) else {
+                break;
+            };
+            match entered.take() {
+                None => entered = Some(crossing.t),
+                Some(enter) => spans.push((enter, crossing.t)),
+            }
+            t = crossing.t + CROSSING_STEP;
+        }
+        spans
+    }
 }
 
 impl renderable::Renderable for RenderVolume {
     fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::HitRecord<'_>> {
-        // hit function to handle volumes
-        let mut rec1 = self.boundary.hit(ray, f32::MIN, f32::MAX)?;
-        let mut rec2 = self.boundary.hit(ray, rec1.t + 0.0001, f32::MAX)?;
-        if rec1.t < t_min {
-            rec1.t = t_min;
-        }
-        if rec2.t > t_max {
-            rec2.t = t_max;
-        }
-        if rec1.t >= rec2.t {
+        let spans = self.inside_spans(ray);
+        if spans.is_empty() {
             return None;
         }
-        if rec1.t < 0.0 {
-            rec1.t = 0.0;
-        }
 
-        let distance_inside_boundary = (rec2.t - rec1.t) * ray.direction.length();
-        let hit_distance = -(1.0 / self.density) * rand::rng().random::<f32>().ln();
-        if hit_distance > distance_inside_boundary {
-            return None;
-        }
+        // `hit` takes no generator, so the free-flight sample is drawn from one seeded by
+        // the ray itself, which keeps seeded renders reproducible. Free flight is
+        // memoryless, so one distance is spent across the spans in order.
+        let mut rng = rng::hashed_rng(ray_bits(ray));
+        let mut hit_distance = -(1.0 / self.density) * rng.random::<f32>().ln();
+        let length = ray.direction.length();
+        let t = spans.into_iter().find_map(|(enter, exit)| {
+            let (enter, exit) = (enter.max(t_min), exit.min(t_max));
+            if enter >= exit {
+                return None;
+            }
+            let distance_inside_boundary = (exit - enter) * length;
+            if hit_distance > distance_inside_boundary {
+                hit_distance -= distance_inside_boundary;
+                return None;
+            }
+            Some(enter + hit_distance / length)
+        })?;
 
-        let t = rec1.t + hit_distance / ray.direction.length();
         let point = ray.point_at(t);
         let normal = vec::Vec3::new(1.0, 0.0, 0.0); // arbitrary
         let hit_record = hittable::HitRecord {
@@ -102,6 +132,10 @@ impl renderable::Renderable for RenderVolume {
                 ray: ray.clone(),
                 u: 0.0,
                 v: 0.0,
+                material_slot: 0,
+                vertex_color: None,
+                shading_normal: None,
+                tangent: None,
             },
             pdf: Box::new(pdf::phase::ConstantPhaseFunction {}),
             renderable: self,
@@ -120,18 +154,38 @@ impl renderable::Renderable for RenderVolume {
 
     fn scatter(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut rng::SampleRng,
         hit_record: &hittable::HitRecord,
-        depth: u32,
+        state: &mut path::PathState,
     ) -> Option<scatterable::ScatterRecord> {
-        self.phase_function.scatter(rng, hit_record, depth)
+        self.phase_function.scatter(rng, hit_record, state)
     }
 
     fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3 {
         self.phase_function.emit(hit_record)
     }
 
+    fn max_bounces(&self, _hit_record: &hittable::HitRecord) -> Option<u32> {
+        self.phase_function.max_bounces()
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
 }
+
+/// Folds a ray's origin, direction, and time into 64 bits.
+fn ray_bits(ray: &ray::Ray) -> u64 {
+    [
+        ray.origin.x,
+        ray.origin.y,
+        ray.origin.z,
+        ray.direction.x,
+        ray.direction.y,
+        ray.direction.z,
+    ]
+    .iter()
+    .fold(ray.time.to_bits(), |bits, value| {
+        bits.rotate_left(9) ^ value.to_bits() as u64
+    })
+}
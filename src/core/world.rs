@@ -1,15 +1,99 @@
 //! Procedural sky gradient that acts as both geometry and material.
+//!
+//! The gradient can be dressed up without an HDRI: [`Haze`] washes the sky out toward a
+//! color near the horizon, [`Stars`] scatters points of light over it for night skies,
+//! and a [`Sun`] adds a disk with a glow around it. Each is looked up from the direction
+//! alone, so the sky is as cheap to evaluate as the plain gradient.
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::core::{bbox, ray};
-use crate::math::{pdf, vec};
+use crate::core::{bbox, path, ray};
+use crate::math::{pdf, rng, vec};
 use crate::traits::{hittable, renderable, scatterable};
 
+/// Radius of a star in units of its cell; centers stay this far from the cell's edges
+/// so each star is found from its own cell alone.
+const STAR_RADIUS: f32 = 0.25;
+
 #[derive(Clone, Copy, Serialize, Deserialize)]
 /// Background gradient defined by top and bottom colors.
 pub struct World {
     pub top_color: vec::Vec3,
     pub bottom_color: vec::Vec3,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub haze: Option<Haze>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stars: Option<Stars>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sun: Option<Sun>,
+}
+
+/// A band of `color` along the horizon, fading out over `height`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Haze {
+    pub color: vec::Vec3,
+    /// Height above and below the horizon, as the `y` of a unit direction, over which the
+    /// haze thins to 1/e; 0.1 by default.
+    #[serde(default = "default_haze_height")]
+    pub height: f32,
+}
+
+fn default_haze_height() -> f32 {
+    0.1
+}
+
+/// Randomly placed stars. The sky is divided into `cells` by `cells` squares on each face
+/// of a cube around the camera, and each square holds a star with probability `density`.
+/// Stars range from white-hot blue to cool orange, and most are much dimmer than
+/// `brightness`. Haze hides them near the horizon.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Stars {
+    /// Radiance of the brightest stars.
+    pub brightness: f32,
+    #[serde(default = "default_star_density")]
+    pub density: f32,
+    #[serde(default = "default_star_cells")]
+    pub cells: u32,
+    /// Seed for the star positions; the same seed gives the same sky. Scene files hold
+    /// seeds up to `i64::MAX`.
+    #[serde(default)]
+    pub seed: u64,
+}
+
+fn default_star_density() -> f32 {
+    0.05
+}
+
+fn default_star_cells() -> u32 {
+    256
+}
+
+/// A sun disk of radiance `color` in `direction`, with an optional glow around it.
+///
+/// The disk is only found by rays that happen to hit it, so a small, bright sun lights a
+/// scene noisily; a wider `glow` spreads its light over more of the sky.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Sun {
+    /// Direction toward the sun; need not be unit length.
+    pub direction: vec::Vec3,
+    pub color: vec::Vec3,
+    /// Angular radius of the disk in degrees; 0.27, the real sun's, by default.
+    #[serde(default = "default_sun_radius")]
+    pub angular_radius: f32,
+    /// Radiance of the glow at the disk's edge, as a multiple of `color`.
+    #[serde(default)]
+    pub glow: f32,
+    /// Angle in degrees past the disk over which the glow fades to 1/e; 5 by default.
+    #[serde(default = "default_glow_width")]
+    pub glow_width: f32,
+}
+
+fn default_sun_radius() -> f32 {
+    0.27
+}
+
+fn default_glow_width() -> f32 {
+    5.0
 }
 
 impl World {
@@ -18,8 +102,103 @@ impl World {
         World {
             top_color: *top_color,
             bottom_color: *bottom_color,
+            haze: None,
+            stars: None,
+            sun: None,
         }
     }
+
+    pub fn with_haze(mut self, haze: Haze) -> Self {
+        self.haze = Some(haze);
+        self
+    }
+
+    pub fn with_stars(mut self, stars: Stars) -> Self {
+        self.stars = Some(stars);
+        self
+    }
+
+    pub fn with_sun(mut self, sun: Sun) -> Self {
+        self.sun = Some(sun);
+        self
+    }
+
+    /// Radiance of the sky seen looking along `direction`.
+    pub fn radiance(&self, direction: &vec::Vec3) -> vec::Vec3 {
+        let direction = vec::unit_vector(direction);
+        let t = 0.5 * (direction.y + 1.0);
+        let mut radiance = self.bottom_color * (1.0 - t) + self.top_color * t;
+
+        let clear = match self.haze {
+            Some(haze) => {
+                let amount = (-direction.y.abs() / haze.height.max(f32::EPSILON)).exp();
+                radiance = radiance * (1.0 - amount) + haze.color * amount;
+                1.0 - amount
+            }
+            None => 1.0,
+        };
+        if let Some(stars) = self.stars {
+            radiance = radiance + stars.radiance(&direction) * clear;
+        }
+        if let Some(sun) = self.sun {
+            radiance = radiance + sun.radiance(&direction);
+        }
+        radiance
+    }
+}
+
+impl Stars {
+    /// Radiance of the star, if any, covering unit `direction`.
+    fn radiance(&self, direction: &vec::Vec3) -> vec::Vec3 {
+        let black = vec::Vec3::new(0.0, 0.0, 0.0);
+        // Project onto the cube face the direction points through.
+        let axis = (0..3)
+            .max_by(|&a, &b| direction[a].abs().total_cmp(&direction[b].abs()))
+            .unwrap_or(0);
+        let major = direction[axis];
+        let face = 2 * axis as u64 + (major < 0.0) as u64;
+        let (s, t) = (
+            direction[(axis + 1) % 3] / major.abs(),
+            direction[(axis + 2) % 3] / major.abs(),
+        );
+        let cells = self.cells.max(1) as f32;
+        let (s, t) = ((s + 1.0) * 0.5 * cells, (t + 1.0) * 0.5 * cells);
+        let (column, row) = (s.floor().min(cells - 1.0), t.floor().min(cells - 1.0));
+
+        let mut rng = rng::hashed_rng(
+            self.seed.rotate_left(48) ^ (face << 40) ^ ((column as u64) << 20) ^ row as u64,
+        );
+        if rng.random::<f32>() >= self.density {
+            return black;
+        }
+        let margin = 1.0 - 2.0 * STAR_RADIUS;
+        let center_s = column + STAR_RADIUS + margin * rng.random::<f32>();
+        let center_t = row + STAR_RADIUS + margin * rng.random::<f32>();
+        let distance_squared = (s - center_s).powi(2) + (t - center_t).powi(2);
+        if distance_squared >= STAR_RADIUS * STAR_RADIUS {
+            return black;
+        }
+
+        let magnitude = rng.random::<f32>().powi(4);
+        let warmth = rng.random::<f32>();
+        let color = vec::Vec3::new(0.7, 0.8, 1.0) * (1.0 - warmth)
+            + vec::Vec3::new(1.0, 0.75, 0.5) * warmth;
+        let falloff = 1.0 - distance_squared / (STAR_RADIUS * STAR_RADIUS);
+        color * (self.brightness * magnitude * falloff)
+    }
+}
+
+impl Sun {
+    /// Radiance of the disk or its glow along unit `direction`.
+    fn radiance(&self, direction: &vec::Vec3) -> vec::Vec3 {
+        let toward = vec::unit_vector(&self.direction);
+        let angle = direction.dot(&toward).clamp(-1.0, 1.0).acos().to_degrees();
+        if angle <= self.angular_radius {
+            return self.color;
+        }
+        let past_edge = (angle - self.angular_radius) / self.glow_width.max(f32::EPSILON);
+        self.color * (self.glow * (-past_edge).exp())
+    }
 }
 
 impl hittable::Hittable for World {
@@ -40,6 +219,10 @@ impl hittable::Hittable for World {
             normal,
             u: 0.0,
             v: 0.0,
+            material_slot: 0,
+            vertex_color: None,
+            shading_normal: None,
+            tangent: None,
         })
     }
 
@@ -61,20 +244,18 @@ impl hittable::Hittable for World {
 }
 
 impl scatterable::Scatterable for World {
-    /// Emits a vertical gradient based on the ray direction.
+    /// Emits the sky seen along the ray ([`World::radiance`]).
     fn scatter(
         &self,
-        _rng: &mut rand::rngs::ThreadRng,
+        _rng: &mut rng::SampleRng,
         _hit_record: &hittable::HitRecord<'_>,
-        _depth: u32,
+        _state: &mut path::PathState,
     ) -> Option<scatterable::ScatterRecord> {
         None
     }
 
     fn emit(&self, hit_record: &hittable::HitRecord<'_>) -> vec::Vec3 {
-        let unit_direction = vec::unit_vector(&hit_record.hit.ray.direction);
-        let t = 0.5 * (unit_direction.y + 1.0);
-        self.bottom_color * (1.0 - t) + self.top_color * t
+        self.radiance(&hit_record.hit.ray.direction)
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -100,6 +281,11 @@ impl renderable::Renderable for World {
         Some(hit_record)
     }
 
+    /// The skybox is a background fill, never an occluder.
+    fn occludes(&self, _ray: &ray::Ray, _t_min: f32, _t_max: f32) -> bool {
+        false
+    }
+
     fn bounding_box(&self) -> bbox::BBox {
         // Skybox is infinite; return a large bounding box.
         bbox::BBox::bounding(
@@ -114,11 +300,11 @@ impl renderable::Renderable for World {
 
     fn scatter(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut rng::SampleRng,
         hit_record: &hittable::HitRecord<'_>,
-        depth: u32,
+        state: &mut path::PathState,
     ) -> Option<scatterable::ScatterRecord> {
-        (self as &dyn scatterable::Scatterable).scatter(rng, hit_record, depth)
+        (self as &dyn scatterable::Scatterable).scatter(rng, hit_record, state)
     }
 
     fn emit(&self, hit_record: &hittable::HitRecord<'_>) -> vec::Vec3 {
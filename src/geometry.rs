@@ -1,3 +1,14 @@
+pub mod cutout;
+pub mod displacement;
+pub mod generators;
+pub mod god_rays;
 pub mod instance;
+pub mod obj;
+pub mod ply;
 pub mod primitives;
+pub mod scatter;
+pub mod section;
+pub mod stl;
+pub mod subdivision;
+pub mod testing;
 pub mod transform;
@@ -0,0 +1,149 @@
+//! Procedural hit rejection, in the spirit of a GPU ray tracer's any-hit shader.
+//!
+//! A [`Cutout`] wraps another geometry and asks a [`HitFilter`] about every hit before
+//! it's shaded. Rejected hits are skipped and the ray carries on to the next surface
+//! behind them, so patterns of holes can be cut into existing primitives (a checker
+//! floor with every other square missing, a perforated sheet) without a new primitive
+//! type for each effect.
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{bbox, ray};
+use crate::math::{pdf, rng, vec};
+use crate::traits::hittable;
+
+/// Most hits a ray may have rejected on one cutout before it counts as a miss, so a
+/// filter that rejects everything can't keep a ray searching forever.
+const MAX_REJECTIONS: usize = 256;
+
+/// Decides whether a hit on the wrapped geometry counts.
+///
+/// Hits are in the wrapped geometry's own space, before object transforms, so patterns
+/// move with the object. Any `Fn(&Hit) -> bool` closure is a filter.
+pub trait HitFilter: Send + Sync {
+    /// `true` to keep the hit, `false` to let the ray pass through.
+    fn accept(&self, hit: &hittable::Hit) -> bool;
+}
+
+impl<F> HitFilter for F
+where
+    F: Fn(&hittable::Hit) -> bool + Send + Sync,
+{
+    fn accept(&self, hit: &hittable::Hit) -> bool {
+        self(hit)
+    }
+}
+
+/// Built-in cutout patterns, evaluated on the hit position.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "pattern")]
+pub enum CutoutPattern {
+    /// A 3D checkerboard of `scale`-unit cells, laid out like
+    /// [`crate::textures::checker::CheckerTexture`], with every other cell cut away.
+    Checker { scale: f32 },
+    /// Spherical holes of `radius` around every point of a cubic lattice `spacing` units
+    /// apart; surfaces through lattice points get round holes.
+    Holes { spacing: f32, radius: f32 },
+}
+
+impl HitFilter for CutoutPattern {
+    fn accept(&self, hit: &hittable::Hit) -> bool {
+        let point = hit.point;
+        match self {
+            CutoutPattern::Checker { scale } => {
+                let cell = |value: f32| (value / scale).floor() as i64;
+                (cell(point.x) + cell(point.y) + cell(point.z)).rem_euclid(2) == 0
+            }
+            CutoutPattern::Holes { spacing, radius } => {
+                let offset = |value: f32| value - (value / spacing).round() * spacing;
+                let offset = vec::Vec3::new(offset(point.x), offset(point.y), offset(point.z));
+                offset.squared_length() >= radius * radius
+            }
+        }
+    }
+}
+
+/// A geometry whose hits are filtered before shading; see the module docs.
+///
+/// Light sampling is delegated to the wrapped geometry unchanged: directions it picks
+/// toward a hole simply miss, which stays unbiased but wastes those samples.
+#[derive(Clone)]
+pub struct Cutout {
+    inner: Arc<dyn hittable::Hittable + Send + Sync>,
+    /// The built-in pattern, if the filter is one; custom filters can't be saved to scene
+    /// files.
+    pattern: Option<CutoutPattern>,
+    filter: Arc<dyn HitFilter>,
+}
+
+impl Cutout {
+    /// Cuts a built-in pattern out of `inner`.
+    pub fn new(inner: Arc<dyn hittable::Hittable + Send + Sync>, pattern: CutoutPattern) -> Self {
+        let mut cutout = Cutout::custom(inner, Arc::new(pattern.clone()));
+        cutout.pattern = Some(pattern);
+        cutout
+    }
+
+    /// Filters hits on `inner` with user-provided code.
+    pub fn custom(
+        inner: Arc<dyn hittable::Hittable + Send + Sync>,
+        filter: Arc<dyn HitFilter>,
+    ) -> Self {
+        Cutout {
+            inner,
+            pattern: None,
+            filter,
+        }
+    }
+
+    /// The wrapped geometry.
+    pub fn inner(&self) -> &Arc<dyn hittable::Hittable + Send + Sync> {
+        &self.inner
+    }
+
+    /// The built-in pattern, or `None` for a custom filter.
+    pub fn pattern(&self) -> Option<&CutoutPattern> {
+        self.pattern.as_ref()
+    }
+}
+
+impl hittable::Hittable for Cutout {
+    fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
+        let mut start = t_min;
+        for _ in 0..MAX_REJECTIONS {
+            let hit = self.inner.hit(ray, start, t_max)?;
+            if self.filter.accept(&hit) {
+                return Some(hit);
+            }
+            // Resume just past the rejected hit; stop if the geometry reports it again.
+            if hit.t <= start {
+                return None;
+            }
+            start = hit.t;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> bbox::BBox {
+        self.inner.bounding_box()
+    }
+
+    fn get_pdf(&self, origin: &vec::Point3, time: f64) -> Box<dyn pdf::PDF + Send + Sync + '_> {
+        self.inner.get_pdf(origin, time)
+    }
+
+    /// Samples the whole wrapped surface, holes included; photons that start in a hole
+    /// are emitted anyway, so emissive cutouts are brighter in photon-mapped renders.
+    fn sample_surface(
+        &self,
+        rng: &mut rng::SampleRng,
+        time: f64,
+    ) -> Option<hittable::SurfaceSample> {
+        self.inner.sample_surface(rng, time)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
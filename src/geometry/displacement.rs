@@ -0,0 +1,169 @@
+//! Displacement of mesh vertices along their normals.
+//!
+//! A height in `[0, 1]` is looked up for every vertex, from a grayscale image laid over
+//! the mesh from above or from a 3D fractal noise function, and the vertex moves
+//! `amount * height` along its normal. Applied at load time to a finely subdivided
+//! plane this makes terrain; applied to a model it embosses patterns into its surface.
+//! Only vertices move, so the detail a displacement can show is limited by how densely
+//! the mesh is tessellated.
+use serde::{Deserialize, Serialize};
+
+use crate::core::assets;
+use crate::math::{noise, vec};
+
+/// Where displacement heights come from. Every source gives heights in `[0, 1]`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source")]
+pub enum DisplacementSource {
+    /// A grayscale image (color images are converted to luma) projected straight down
+    /// onto the mesh, spanning its bounds in X and Z with the top row along the lowest
+    /// Z, like a [`crate::geometry::primitives::heightfield::Heightfield`] heightmap.
+    /// `path` may be a local file or an `http(s)://` URL fetched through
+    /// [`assets::resolve`].
+    Image { path: String },
+    /// Fractal value noise over 3D space, the sum of `octaves` layers each at twice the
+    /// frequency of the last. The same `seed` always gives the same surface.
+    Noise {
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+fn default_octaves() -> u32 {
+    4
+}
+
+/// How far and with what pattern to displace a mesh, e.g.
+/// `{ source = "Noise", amount = 0.2, scale = 3 }`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Displacement {
+    #[serde(flatten)]
+    pub source: DisplacementSource,
+    /// Distance a vertex of height 1 moves along its normal; negative values push
+    /// vertices inward, engraving instead of embossing.
+    pub amount: f32,
+    /// Repeats of the image across the mesh, or noise features per unit of distance.
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+/// Moves every vertex along its normal by its height times `displacement.amount`,
+/// returning the new positions and, if `normals` isn't empty, unit normals recomputed
+/// from the displaced surface. Vertices without a normal are taken to have the
+/// area-weighted average of the normals of the triangles around them.
+pub fn displace(
+    vertices: &[vec::Point3],
+    triangles: &[[u32; 3]],
+    normals: &[vec::Vec3],
+    displacement: &Displacement,
+) -> std::io::Result<(Vec<vec::Point3>, Vec<vec::Vec3>)> {
+    let directions = if normals.is_empty() {
+        vertex_normals(vertices, triangles)
+    } else {
+        normals.to_vec()
+    };
+    let heights = heights(vertices, displacement)?;
+    let displaced: Vec<vec::Point3> = vertices
+        .iter()
+        .zip(&directions)
+        .zip(&heights)
+        .map(|((vertex, normal), height)| *vertex + *normal * (displacement.amount * height))
+        .collect();
+    let normals = if normals.is_empty() {
+        Vec::new()
+    } else {
+        vertex_normals(&displaced, triangles)
+    };
+    Ok((displaced, normals))
+}
+
+/// Height of every vertex under `displacement`'s source.
+fn heights(vertices: &[vec::Point3], displacement: &Displacement) -> std::io::Result<Vec<f32>> {
+    let scale = displacement.scale;
+    match &displacement.source {
+        DisplacementSource::Image { path } => {
+            let resolved = assets::resolve(path)?;
+            let image = image::open(&resolved)
+                .map_err(|err| {
+                    std::io::Error::other(format!(
+                        "failed to open displacement map {}: {}",
+                        path, err
+                    ))
+                })?
+                .to_luma16();
+            let (width, height) = image.dimensions();
+            let samples: Vec<f32> = image
+                .into_raw()
+                .into_iter()
+                .map(|value| value as f32 / u16::MAX as f32)
+                .collect();
+            let sample = |x: i64, y: i64| {
+                let x = x.rem_euclid(width as i64) as usize;
+                let y = y.rem_euclid(height as i64) as usize;
+                samples[y * width as usize + x]
+            };
+
+            // The XZ bounds of the mesh, which the image spans.
+            let (mut min_x, mut max_x) = (f32::MAX, f32::MIN);
+            let (mut min_z, mut max_z) = (f32::MAX, f32::MIN);
+            for vertex in vertices {
+                (min_x, max_x) = (min_x.min(vertex.x), max_x.max(vertex.x));
+                (min_z, max_z) = (min_z.min(vertex.z), max_z.max(vertex.z));
+            }
+            let (width_x, width_z) = (
+                (max_x - min_x).max(f32::EPSILON),
+                (max_z - min_z).max(f32::EPSILON),
+            );
+            Ok(vertices
+                .iter()
+                .map(|vertex| {
+                    // Pixel centers sit at half-pixel offsets; interpolate between the
+                    // four around the vertex, wrapping so tiled images repeat seamlessly.
+                    let u = (vertex.x - min_x) / width_x * scale;
+                    let v = (vertex.z - min_z) / width_z * scale;
+                    let x = u * width as f32 - 0.5;
+                    let y = v * height as f32 - 0.5;
+                    let (i, j) = (x.floor() as i64, y.floor() as i64);
+                    let (fx, fy) = (x - x.floor(), y - y.floor());
+                    let near = sample(i, j) * (1.0 - fx) + sample(i + 1, j) * fx;
+                    let far = sample(i, j + 1) * (1.0 - fx) + sample(i + 1, j + 1) * fx;
+                    near * (1.0 - fy) + far * fy
+                })
+                .collect())
+        }
+        DisplacementSource::Noise { octaves, seed } => Ok(vertices
+            .iter()
+            .map(|vertex| noise::fractal(*vertex * scale, *octaves, *seed))
+            .collect()),
+    }
+}
+
+/// Unit vertex normals averaged from the triangles around each vertex, weighted by
+/// their area; zero for vertices no triangle uses.
+fn vertex_normals(vertices: &[vec::Point3], triangles: &[[u32; 3]]) -> Vec<vec::Vec3> {
+    let mut normals = vec![vec::Vec3::default(); vertices.len()];
+    for triangle in triangles {
+        let [a, b, c] = triangle.map(|index| vertices[index as usize]);
+        // Twice the triangle's area in length, so larger faces count for more.
+        let normal = (b - a).cross(&(c - a));
+        for index in triangle {
+            normals[*index as usize] = normals[*index as usize] + normal;
+        }
+    }
+    normals
+        .into_iter()
+        .map(|normal| {
+            if normal.squared_length() > 0.0 {
+                normal.normalize()
+            } else {
+                normal
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,417 @@
+//! Fractal geometry built programmatically from a recursion depth.
+//!
+//! A [`Fractal`] is many copies of one small base shape (a cube, tetrahedron, or
+//! sphere), each moved and uniformly scaled into place by the generator. The copies
+//! share the base shape rather than each holding its own geometry, so a depth-4 Menger
+//! sponge costs one cube and 160,000 placements; like [`super::primitives::curves`], the
+//! fractal keeps its own BVH over the copies and appears to the scene as one object.
+use std::sync::Arc;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{bbox, bvh, ray};
+use crate::geometry::primitives::{cube, mesh, sphere};
+use crate::math::{onb, pdf, rng, vec};
+use crate::traits::hittable;
+use crate::traits::hittable::Hittable;
+
+/// Most copies kept in one leaf of the fractal BVH.
+const LEAF_COPIES: usize = 4;
+
+/// Child sphere radius of a sphereflake, relative to its parent.
+const SPHEREFLAKE_RATIO: f32 = 1.0 / 3.0;
+
+/// Which fractal to generate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FractalKind {
+    /// Menger sponge: a cube split into 27 and the center and face centers removed,
+    /// leaving 20 cubes per level.
+    Menger,
+    /// Sierpinski pyramid: a regular tetrahedron replaced by four at its corners, each
+    /// half its size.
+    Sierpinski,
+    /// Sphereflake: a sphere with nine spheres a third its size resting on it, six
+    /// around its equator and three above, repeated on each child away from its parent.
+    Sphereflake,
+}
+
+impl FractalKind {
+    /// Deepest recursion allowed; each level multiplies the copies by 20 (Menger), 4
+    /// (Sierpinski), or 9 (Sphereflake).
+    pub fn max_depth(&self) -> u32 {
+        match self {
+            FractalKind::Menger => 5,
+            FractalKind::Sierpinski => 8,
+            FractalKind::Sphereflake => 6,
+        }
+    }
+
+    /// The shape every copy is scaled from, fitting `[-0.5, 0.5]` on each axis.
+    fn base(&self) -> Arc<dyn Hittable + Send + Sync> {
+        match self {
+            FractalKind::Menger => Arc::new(cube::Cube::new(
+                vec::Vec3::new(-0.5, -0.5, -0.5),
+                vec::Vec3::new(0.5, 0.5, 0.5),
+            )),
+            FractalKind::Sierpinski => Arc::new(mesh::Mesh::new(
+                TETRAHEDRON.map(tetrahedron_corner).to_vec(),
+                vec![[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]],
+            )),
+            FractalKind::Sphereflake => {
+                Arc::new(sphere::Sphere::new(&vec::Vec3::new(0.0, 0.0, 0.0), 0.5))
+            }
+        }
+    }
+
+    /// Surface area of the base shape.
+    fn base_area(&self) -> f32 {
+        match self {
+            FractalKind::Menger => 6.0,
+            // Four equilateral triangles with edges of sqrt(2).
+            FractalKind::Sierpinski => 2.0 * 3.0_f32.sqrt(),
+            FractalKind::Sphereflake => std::f32::consts::PI,
+        }
+    }
+}
+
+/// Corners of the base tetrahedron, alternate corners of the unit cube.
+const TETRAHEDRON: [[f32; 3]; 4] = [
+    [0.5, 0.5, 0.5],
+    [0.5, -0.5, -0.5],
+    [-0.5, 0.5, -0.5],
+    [-0.5, -0.5, 0.5],
+];
+
+fn tetrahedron_corner([x, y, z]: [f32; 3]) -> vec::Point3 {
+    vec::Point3::new(x, y, z)
+}
+
+/// One copy of the base shape: moved to `center` and scaled by `scale`.
+#[derive(Clone, Copy)]
+struct Placement {
+    center: vec::Point3,
+    scale: f32,
+}
+
+impl Placement {
+    fn bounding_box(&self, base: &bbox::BBox) -> bbox::BBox {
+        let corner = |pick: fn(&crate::math::interval::Interval) -> f32| {
+            self.center + vec::Vec3::new(pick(&base.x), pick(&base.y), pick(&base.z)) * self.scale
+        };
+        bbox::BBox::bounding(corner(|axis| axis.min), corner(|axis| axis.max))
+    }
+}
+
+pub struct FractalPDF<'a> {
+    fractal: &'a Fractal,
+    origin: vec::Point3,
+    time: f64,
+}
+
+impl pdf::PDF for FractalPDF<'_> {
+    fn value(&self, direction: vec::Vec3) -> f32 {
+        let ray = ray::Ray::new(&self.origin, &direction, Some(self.time));
+        let Some(hit) = self.fractal.hit(&ray, 0.001, f32::MAX) else {
+            return 0.0;
+        };
+        let area = self.fractal.area();
+        let direction_len_sq = direction.squared_length();
+        if direction_len_sq <= f32::EPSILON || area <= 0.0 {
+            return 0.0;
+        }
+        let distance_squared = hit.t * hit.t * direction_len_sq;
+        let cosine = (direction.dot(&hit.normal) / direction_len_sq.sqrt()).abs();
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        distance_squared / (cosine * area)
+    }
+
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
+        match self.fractal.sample_surface(rng, self.time) {
+            Some(sample) => sample.point - self.origin,
+            None => vec::Vec3::new(1.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// A Menger sponge, Sierpinski pyramid, or sphereflake, recursed `depth` times.
+///
+/// `center` and `size` place the whole fractal: `size` is the edge of the sponge's
+/// cube, of the cube the pyramid's corners sit on, or the diameter of the sphereflake's
+/// central sphere. Depth 0 is the base shape alone. Hits report the base shape's `(u,
+/// v)` and material slot on the copy that was hit, so a Menger sponge's faces can take
+/// slot materials like a [`cube::Cube`]'s.
+///
+/// Copies that touch share faces inside the solid; light sampling counts those too, so
+/// an emissive sponge or pyramid is sampled a little less efficiently than its outside
+/// alone would be.
+#[derive(Clone)]
+pub struct Fractal {
+    kind: FractalKind,
+    depth: u32,
+    center: vec::Point3,
+    size: f32,
+    base: Arc<dyn Hittable + Send + Sync>,
+    copies: Vec<Placement>,
+    /// Running totals of the copies' squared scales, for picking a copy by area.
+    cumulative_areas: Vec<f32>,
+    bvh: bvh::FlatBvh,
+}
+
+impl Fractal {
+    /// Generates the fractal, failing if `depth` is past [`FractalKind::max_depth`] or
+    /// `size` isn't positive.
+    pub fn new(
+        kind: FractalKind,
+        depth: u32,
+        center: vec::Point3,
+        size: f32,
+    ) -> Result<Self, String> {
+        if depth > kind.max_depth() {
+            return Err(format!(
+                "{:?} fractals go at most {} levels deep, got {}",
+                kind,
+                kind.max_depth(),
+                depth
+            ));
+        }
+        if size.is_nan() || size <= 0.0 {
+            return Err(format!("a fractal needs a positive size, got {}", size));
+        }
+
+        let mut copies = Vec::new();
+        let root = Placement {
+            center,
+            scale: size,
+        };
+        match kind {
+            FractalKind::Menger => menger(root, depth, &mut copies),
+            FractalKind::Sierpinski => sierpinski(root, depth, &mut copies),
+            FractalKind::Sphereflake => {
+                copies.push(root);
+                sphereflake(root, vec::Vec3::new(0.0, 1.0, 0.0), depth, &mut copies);
+            }
+        }
+
+        let base = kind.base();
+        let base_box = base.bounding_box();
+        let mut entries: Vec<(bbox::BBox, Placement)> = copies
+            .into_iter()
+            .map(|copy| (copy.bounding_box(&base_box), copy))
+            .collect();
+        let bvh = bvh::FlatBvh::build(&mut entries, LEAF_COPIES);
+        let copies: Vec<Placement> = entries.into_iter().map(|(_, copy)| copy).collect();
+        let cumulative_areas = copies
+            .iter()
+            .scan(0.0, |total, copy| {
+                *total += copy.scale * copy.scale;
+                Some(*total)
+            })
+            .collect();
+        Ok(Fractal {
+            kind,
+            depth,
+            center,
+            size,
+            base,
+            copies,
+            cumulative_areas,
+            bvh,
+        })
+    }
+
+    pub fn kind(&self) -> FractalKind {
+        self.kind
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Number of copies of the base shape.
+    pub fn copy_count(&self) -> usize {
+        self.copies.len()
+    }
+
+    /// Total surface area of the copies, counting faces hidden where they touch.
+    pub fn area(&self) -> f32 {
+        self.kind.base_area() * self.cumulative_areas.last().copied().unwrap_or(0.0)
+    }
+}
+
+/// Adds the cubes of a Menger sponge of `depth` levels filling `cube`.
+fn menger(cube: Placement, depth: u32, copies: &mut Vec<Placement>) {
+    if depth == 0 {
+        copies.push(cube);
+        return;
+    }
+    let scale = cube.scale / 3.0;
+    for x in -1_i32..=1 {
+        for y in -1_i32..=1 {
+            for z in -1_i32..=1 {
+                // Dropping every cube on two or more middle planes leaves 20 of the 27.
+                if (x == 0) as u32 + (y == 0) as u32 + (z == 0) as u32 >= 2 {
+                    continue;
+                }
+                let offset = vec::Vec3::new(x as f32, y as f32, z as f32) * scale;
+                menger(
+                    Placement {
+                        center: cube.center + offset,
+                        scale,
+                    },
+                    depth - 1,
+                    copies,
+                );
+            }
+        }
+    }
+}
+
+/// Adds the tetrahedra of a Sierpinski pyramid of `depth` levels filling `tetrahedron`.
+fn sierpinski(tetrahedron: Placement, depth: u32, copies: &mut Vec<Placement>) {
+    if depth == 0 {
+        copies.push(tetrahedron);
+        return;
+    }
+    // Each child shares one corner with its parent and reaches halfway to the others.
+    let scale = tetrahedron.scale * 0.5;
+    for corner in TETRAHEDRON.map(tetrahedron_corner) {
+        sierpinski(
+            Placement {
+                center: tetrahedron.center + corner * scale,
+                scale,
+            },
+            depth - 1,
+            copies,
+        );
+    }
+}
+
+/// Adds `depth` levels of child spheres around `parent`, arranged about `up`, the
+/// direction away from the parent's own parent.
+fn sphereflake(parent: Placement, up: vec::Vec3, depth: u32, copies: &mut Vec<Placement>) {
+    if depth == 0 {
+        return;
+    }
+    let frame = onb::ONB::build_from_w(&up);
+    let scale = parent.scale * SPHEREFLAKE_RATIO;
+    // Six children around the equator, then three between them raised 60 degrees.
+    let directions = (0..6)
+        .map(|index| (index as f32 * 60.0, 0.0_f32))
+        .chain((0..3).map(|index| (30.0 + index as f32 * 120.0, 60.0_f32)))
+        .map(|(azimuth, elevation): (f32, f32)| {
+            let (azimuth, elevation) = (azimuth.to_radians(), elevation.to_radians());
+            (frame.u * azimuth.cos() + frame.v * azimuth.sin()) * elevation.cos()
+                + frame.w * elevation.sin()
+        });
+    for direction in directions {
+        let child = Placement {
+            center: parent.center + direction * (0.5 * (parent.scale + scale)),
+            scale,
+        };
+        copies.push(child);
+        sphereflake(child, direction, depth - 1, copies);
+    }
+}
+
+impl Serialize for Fractal {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        FractalData {
+            kind: self.kind,
+            depth: self.depth,
+            center: self.center,
+            size: self.size,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fractal {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let data = FractalData::deserialize(deserializer)?;
+        Fractal::new(data.kind, data.depth, data.center, data.size)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FractalData {
+    kind: FractalKind,
+    depth: u32,
+    center: vec::Point3,
+    size: f32,
+}
+
+impl hittable::Hittable for Fractal {
+    fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
+        let mut closest: Option<(hittable::Hit, usize)> = None;
+        self.bvh.hit(ray, t_min, t_max, |copy_index, closest_t| {
+            let copy = &self.copies[copy_index];
+            // Scaling the direction along with the origin keeps `t` the same in the
+            // copy's space as in the fractal's.
+            let local = ray::Ray::new(
+                &((ray.origin - copy.center) / copy.scale),
+                &(ray.direction / copy.scale),
+                Some(ray.time),
+            );
+            let hit = self.base.hit(&local, t_min, closest_t)?;
+            let t = hit.t;
+            closest = Some((hit, copy_index));
+            Some(t)
+        });
+
+        let (hit, copy_index) = closest?;
+        let copy = &self.copies[copy_index];
+        Some(hittable::Hit {
+            ray: *ray,
+            point: copy.center + hit.point * copy.scale,
+            ..hit
+        })
+    }
+
+    fn bounding_box(&self) -> bbox::BBox {
+        self.bvh.bounding_box()
+    }
+
+    fn get_pdf(&self, origin: &vec::Point3, time: f64) -> Box<dyn pdf::PDF + Send + Sync + '_> {
+        Box::new(FractalPDF {
+            fractal: self,
+            origin: *origin,
+            time,
+        })
+    }
+
+    fn sample_surface(
+        &self,
+        rng: &mut rng::SampleRng,
+        time: f64,
+    ) -> Option<hittable::SurfaceSample> {
+        // Copies are picked in proportion to their area, then a point on the picked
+        // copy is uniform, so points are uniform over the whole surface.
+        let total = *self.cumulative_areas.last()?;
+        let pick = rng.random::<f32>() * total;
+        let copy_index = self
+            .cumulative_areas
+            .partition_point(|area| *area <= pick)
+            .min(self.copies.len() - 1);
+        let copy = &self.copies[copy_index];
+        let sample = self.base.sample_surface(rng, time)?;
+        Some(hittable::SurfaceSample {
+            point: copy.center + sample.point * copy.scale,
+            normal: sample.normal,
+            pdf: sample.pdf / total,
+        })
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,130 @@
+//! Beams of light made visible by haze in front of a light: god rays.
+//!
+//! Light only shows as shafts where it crosses a participating medium, and filling a
+//! whole scene with fog is slow and washes it out. [`beam`] instead builds a closed cone
+//! just enclosing the light and reaching out from it: as wide as the light where it
+//! starts, flaring by `angle` on every side over `length`, along the light's surface
+//! normal or a given `direction`. Filled with a thin volume, it catches the light right
+//! where the beam is, and the shadows of anything in its way streak through it.
+//!
+//! Scene files describe beams in `[[god_rays]]` entries, which become ordinary volumes
+//! when the scene loads.
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::primitives::mesh;
+use crate::math::{onb, rng, vec};
+use crate::traits::hittable;
+
+/// How far the cone starts in front of the light's center, as a fraction of its length,
+/// so its cap doesn't coincide with a flat light.
+const START_OFFSET: f32 = 1e-3;
+
+/// Ray time the light is measured at: where it is when the shutter opens.
+const LIGHT_TIME: f64 = 0.0;
+
+/// Shape of a beam; every field but `length` may be omitted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BeamSettings {
+    /// Distance the beam reaches from the light.
+    pub length: f32,
+    /// Degrees the beam's sides lean out from its axis, from 0 (a straight shaft) to
+    /// below 90.
+    #[serde(default = "default_angle")]
+    pub angle: f32,
+    /// Direction the beam points; the light's surface normal if omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<vec::Vec3>,
+    /// Sides of the polygon approximating the cone's round cross-section.
+    #[serde(default = "default_segments")]
+    pub segments: u32,
+}
+
+fn default_angle() -> f32 {
+    20.0
+}
+
+fn default_segments() -> u32 {
+    32
+}
+
+impl BeamSettings {
+    /// A beam reaching `length` from the light along its normal, flaring by 20 degrees.
+    pub fn new(length: f32) -> Self {
+        BeamSettings {
+            length,
+            angle: default_angle(),
+            direction: None,
+            segments: default_segments(),
+        }
+    }
+}
+
+/// A closed mesh, in world space, enclosing the beam `settings` describes from `light`.
+/// `None` if `light` is unbounded, or has no surface to take a normal from and no
+/// `direction` was given.
+pub fn beam(light: &dyn hittable::Hittable, settings: &BeamSettings) -> Option<mesh::Mesh> {
+    let bbox = light.bounding_box();
+    if bbox.is_unbounded() {
+        return None;
+    }
+    let direction = match settings.direction {
+        Some(direction) => direction,
+        None => {
+            let mut rng = rng::hashed_rng(0);
+            light.sample_surface(&mut rng, LIGHT_TIME)?.normal
+        }
+    };
+    if direction.squared_length() <= 0.0 {
+        return None;
+    }
+    let frame = onb::ONB::build_from_w(&direction);
+    let center = vec::Vec3::new(
+        (bbox.x.min + bbox.x.max) / 2.0,
+        (bbox.y.min + bbox.y.max) / 2.0,
+        (bbox.z.min + bbox.z.max) / 2.0,
+    );
+
+    // Wide enough at the start to take in every corner of the light's box.
+    let start_radius = (0..8)
+        .map(|corner| {
+            let point = vec::Vec3::new(
+                [bbox.x.min, bbox.x.max][corner & 1],
+                [bbox.y.min, bbox.y.max][(corner >> 1) & 1],
+                [bbox.z.min, bbox.z.max][corner >> 2],
+            );
+            let offset = point - center;
+            (offset - frame.w * offset.dot(&frame.w)).length()
+        })
+        .fold(0.0, f32::max);
+    let spread = settings.angle.clamp(0.0, 89.0).to_radians().tan();
+    let end_radius = start_radius + settings.length * spread;
+    let start = center + frame.w * (settings.length * START_OFFSET);
+    let end = center + frame.w * settings.length;
+
+    let segments = settings.segments.max(3);
+    let ring = |middle: vec::Point3, radius: f32| {
+        (0..segments).map(move |segment| {
+            let phi = 2.0 * std::f32::consts::PI * segment as f32 / segments as f32;
+            middle + (frame.u * phi.cos() + frame.v * phi.sin()) * radius
+        })
+    };
+    let mut vertices: Vec<vec::Point3> = ring(start, start_radius)
+        .chain(ring(end, end_radius))
+        .collect();
+    vertices.push(start);
+    vertices.push(end);
+
+    // Wound so face normals point out of the cone.
+    let (start_middle, end_middle) = (2 * segments, 2 * segments + 1);
+    let mut triangles = Vec::with_capacity(4 * segments as usize);
+    for segment in 0..segments {
+        let next = (segment + 1) % segments;
+        let (a, b) = (segment, next);
+        let (c, d) = (segments + segment, segments + next);
+        triangles.push([a, b, d]);
+        triangles.push([a, d, c]);
+        triangles.push([start_middle, b, a]);
+        triangles.push([end_middle, c, d]);
+    }
+    Some(mesh::Mesh::new(vertices, triangles))
+}
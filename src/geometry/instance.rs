@@ -1,13 +1,25 @@
 use std::sync::Arc;
 
 use crate::core::{bbox, ray};
+use crate::geometry::primitives::quad;
 use crate::geometry::transform;
-use crate::math::{pdf, vec};
+use crate::math::{distribution, pdf, rng, vec};
 use crate::traits::hittable;
 
+/// Ray time at the middle of the shutter interval.
+const SHUTTER_MIDPOINT: f64 = 0.5;
+
 pub struct GeometryInstance {
     pub ref_obj: Arc<dyn hittable::Hittable + Send + Sync>,
     pub transforms: Vec<transform::Transform>,
+    /// Whether moving transforms blur over the shutter. When `false` they're evaluated at
+    /// the shutter midpoint for every ray, freezing the object mid-motion while the rest
+    /// of the scene blurs.
+    pub motion_blur: bool,
+    /// Whether hits shade with the geometry's interpolated vertex normals when it has
+    /// them ([`hittable::Hit::shading_normal`]). When `false` they shade with the true
+    /// surface normal, so meshes show their facets.
+    pub smooth_shading: bool,
 }
 
 impl GeometryInstance {
@@ -15,13 +27,63 @@ impl GeometryInstance {
         Self {
             ref_obj: obj,
             transforms: Vec::new(),
+            motion_blur: true,
+            smooth_shading: true,
+        }
+    }
+
+    /// The time transforms are evaluated at for a ray at `time`.
+    fn transform_time(&self, time: f64) -> f64 {
+        if self.motion_blur {
+            time
+        } else {
+            SHUTTER_MIDPOINT
         }
     }
+
+    /// Maps a world-space point into the untransformed geometry's space at `time`.
+    pub fn to_local(&self, point: &vec::Point3, time: f64) -> vec::Point3 {
+        let time = self.transform_time(time);
+        let mut ray = ray::Ray::new(point, &vec::Vec3::new(0.0, 0.0, 0.0), Some(time));
+        self.transforms.iter().rev().for_each(|transform| {
+            ray = transform.apply_inverse(&ray);
+        });
+        ray.origin
+    }
+
+    /// Maps a point on the untransformed geometry to world space at `time`.
+    pub fn to_world(&self, point: &vec::Point3, time: f64) -> vec::Point3 {
+        let time = self.transform_time(time);
+        self.transforms
+            .iter()
+            .fold(*point, |out, transform| transform.apply_point(&out, time))
+    }
+
+    /// Like [`hittable::Hittable::get_pdf`], but for a light whose brightness across its
+    /// `(u, v)` parameters follows `emission`; quads sample by it, other geometry ignores
+    /// it.
+    pub fn get_emission_pdf<'a>(
+        &'a self,
+        origin: &vec::Point3,
+        time: f64,
+        emission: &'a distribution::Distribution2D,
+    ) -> Box<dyn pdf::PDF + Send + Sync + 'a> {
+        let mut pdf = GeometryInstancePDF::new(self, *origin, time);
+        pdf.emission = Some(emission);
+        Box::new(pdf)
+    }
+
+    /// Follows a world-space surface point from where it is at `from` to where it has
+    /// moved by `to`.
+    pub fn transport(&self, point: &vec::Point3, from: f64, to: f64) -> vec::Point3 {
+        self.to_world(&self.to_local(point, from), to)
+    }
 }
 
 impl hittable::Hittable for GeometryInstance {
     fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
-        let mut mut_ray = ray.clone();
+        let time = self.transform_time(ray.time);
+        let mut mut_ray = ray::Ray { time, ..*ray };
         // Apply inverse transforms to the ray here if needed.
         self.transforms.iter().rev().for_each(|transform| {
             mut_ray = transform.apply_inverse(&mut_ray);
@@ -31,10 +93,27 @@ impl hittable::Hittable for GeometryInstance {
 
         let mut hit_point = maybe_hit.point;
         let mut normal = maybe_hit.normal;
+        let mut shading_normal = maybe_hit.shading_normal;
+        let mut tangent = maybe_hit.tangent;
         self.transforms.iter().for_each(|transform| {
-            hit_point = transform.apply_point(&hit_point, ray.time);
-            normal = transform.apply_normal(&normal, ray.time);
+            let moved = transform.apply_point(&hit_point, time);
+            // Directions move with the difference of two points, scaling included.
+            tangent = tangent.map(|tangent| {
+                vec::unit_vector(&(transform.apply_point(&(hit_point + tangent), time) - moved))
+            });
+            hit_point = moved;
+            normal = transform.apply_normal(&normal, time);
+            shading_normal = shading_normal.map(|shading| transform.apply_normal(&shading, time));
         });
+        if let Some(shading) = shading_normal.filter(|_| self.smooth_shading) {
+            // Keep the surface's sense of outside; interpolated normals can lean past the
+            // face plane near silhouettes.
+            normal = if shading.dot(&normal) < 0.0 {
+                -shading
+            } else {
+                shading
+            };
+        }
 
         Some(hittable::Hit {
             ray: ray.clone(),
@@ -43,6 +122,10 @@ impl hittable::Hittable for GeometryInstance {
             normal,
             u: maybe_hit.u,
             v: maybe_hit.v,
+            material_slot: maybe_hit.material_slot,
+            vertex_color: maybe_hit.vertex_color,
+            shading_normal,
+            tangent,
         })
     }
 
@@ -58,6 +141,21 @@ impl hittable::Hittable for GeometryInstance {
         Box::new(GeometryInstancePDF::new(self, *origin, time))
     }
 
+    fn sample_surface(
+        &self,
+        rng: &mut rng::SampleRng,
+        time: f64,
+    ) -> Option<hittable::SurfaceSample> {
+        let time = self.transform_time(time);
+        let mut sample = self.ref_obj.sample_surface(rng, time)?;
+        for transform in &self.transforms {
+            sample.pdf /= transform.area_scale(&sample.normal);
+            sample.point = transform.apply_point(&sample.point, time);
+            sample.normal = transform.apply_normal(&sample.normal, time);
+        }
+        Some(sample)
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -67,6 +165,7 @@ struct GeometryInstancePDF<'a> {
     instance: &'a GeometryInstance,
     origin: vec::Point3,
     time: f64,
+    emission: Option<&'a distribution::Distribution2D>,
 }
 
 impl<'a> GeometryInstancePDF<'a> {
@@ -75,23 +174,30 @@ impl<'a> GeometryInstancePDF<'a> {
             instance,
             origin,
             time,
+            emission: None,
         }
     }
 
+    /// The untransformed geometry's PDF from `local_origin`.
+    fn local_pdf(&self, local_origin: &vec::Point3) -> Box<dyn pdf::PDF + Send + Sync + 'a> {
+        let geometry = self.instance.ref_obj.as_ref();
+        if let (Some(emission), Some(quad)) = (
+            self.emission,
+            geometry.as_any().downcast_ref::<quad::Quad>(),
+        ) {
+            return Box::new(
+                quad::QuadPDF::new(quad, *local_origin, self.time).with_emission(emission),
+            );
+        }
+        geometry.get_pdf(local_origin, self.time)
+    }
+
     fn to_local(&self, point: &vec::Point3) -> vec::Point3 {
-        let mut ray = ray::Ray::new(point, &vec::Vec3::new(0.0, 0.0, 0.0), Some(self.time));
-        self.instance.transforms.iter().rev().for_each(|transform| {
-            ray = transform.apply_inverse(&ray);
-        });
-        ray.origin
+        self.instance.to_local(point, self.time)
     }
 
     fn to_world(&self, point: &vec::Point3) -> vec::Point3 {
-        let mut out = *point;
-        self.instance.transforms.iter().for_each(|transform| {
-            out = transform.apply_point(&out, self.time);
-        });
-        out
+        self.instance.to_world(point, self.time)
     }
 }
 
@@ -102,19 +208,12 @@ impl pdf::PDF for GeometryInstancePDF<'_> {
         let local_point = self.to_local(&world_point);
         let local_direction = local_point - local_origin;
 
-        self.instance
-            .ref_obj
-            .get_pdf(&local_origin, self.time)
-            .value(local_direction)
+        self.local_pdf(&local_origin).value(local_direction)
     }
 
-    fn generate(&self, rng: &mut rand::rngs::ThreadRng) -> vec::Vec3 {
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
         let local_origin = self.to_local(&self.origin);
-        let local_direction = self
-            .instance
-            .ref_obj
-            .get_pdf(&local_origin, self.time)
-            .generate(rng);
+        let local_direction = self.local_pdf(&local_origin).generate(rng);
         let local_point = local_origin + local_direction;
         let world_point = self.to_world(&local_point);
         world_point - self.origin
@@ -0,0 +1,88 @@
+//! Reader for Wavefront OBJ files.
+//!
+//! Reads `v` positions and `f` faces; face corners may be written `v`, `v/vt`, `v//vn` or
+//! `v/vt/vn`, and negative indices count back from the last vertex read. Texture
+//! coordinates, normals, groups and materials are skipped. Faces keep their polygons so
+//! Catmull-Clark subdivision sees the quads the file was modelled with.
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::geometry::primitives::mesh::Mesh;
+use crate::math::vec;
+
+/// Loads an OBJ file from disk.
+pub fn load(path: &Path) -> io::Result<Mesh> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Parses an OBJ file into a mesh.
+pub fn read<R: BufRead>(reader: R) -> io::Result<Mesh> {
+    let mut vertices = Vec::new();
+    let mut polygons = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let mut component = || {
+                    let token = tokens
+                        .next()
+                        .ok_or_else(|| invalid("OBJ vertex has fewer than 3 coordinates"))?;
+                    token
+                        .parse::<f32>()
+                        .map_err(|_| invalid(format!("invalid OBJ number `{}`", token)))
+                };
+                vertices.push(vec::Point3::new(component()?, component()?, component()?));
+            }
+            Some("f") => {
+                let polygon = tokens
+                    .map(|corner| vertex_index(corner, vertices.len()))
+                    .collect::<io::Result<Vec<_>>>()?;
+                if polygon.len() < 3 {
+                    return Err(invalid(format!(
+                        "OBJ face has {} vertices; at least 3 are needed",
+                        polygon.len()
+                    )));
+                }
+                polygons.push(polygon);
+            }
+            _ => {}
+        }
+    }
+
+    // Faces may only refer back to vertices, but check the whole file once it's read.
+    if let Some(&index) = polygons
+        .iter()
+        .flatten()
+        .find(|&&index| index as usize >= vertices.len())
+    {
+        return Err(invalid(format!(
+            "OBJ face references vertex {} but the file has {}",
+            index + 1,
+            vertices.len()
+        )));
+    }
+    Ok(Mesh::from_polygons(vertices, polygons))
+}
+
+/// Resolves the position index of one face corner to zero-based, given the vertices read so
+/// far.
+fn vertex_index(corner: &str, read: usize) -> io::Result<u32> {
+    let position = corner.split('/').next().unwrap_or_default();
+    let index = position
+        .parse::<i64>()
+        .map_err(|_| invalid(format!("invalid OBJ face corner `{}`", corner)))?;
+    let resolved = match index {
+        0 => None,
+        1.. => Some(index - 1),
+        _ => Some(read as i64 + index).filter(|&index| index >= 0),
+    };
+    resolved
+        .and_then(|index| u32::try_from(index).ok())
+        .ok_or_else(|| invalid(format!("OBJ face corner `{}` is out of range", corner)))
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
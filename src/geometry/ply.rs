@@ -0,0 +1,366 @@
+//! Reader for PLY (Stanford polygon) files, ASCII and binary.
+//!
+//! Only what a [`Mesh`] needs is kept: `vertex` positions, optional per-vertex
+//! `red`/`green`/`blue` colors and `nx`/`ny`/`nz` normals, and `face` index lists
+//! (see [`Mesh::from_polygons`]). Other elements and properties are parsed and skipped.
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::geometry::primitives::mesh::Mesh;
+use crate::math::vec;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> io::Result<Self> {
+        Ok(match name {
+            "char" | "int8" => ScalarType::Int8,
+            "uchar" | "uint8" => ScalarType::UInt8,
+            "short" | "int16" => ScalarType::Int16,
+            "ushort" | "uint16" => ScalarType::UInt16,
+            "int" | "int32" => ScalarType::Int32,
+            "uint" | "uint32" => ScalarType::UInt32,
+            "float" | "float32" => ScalarType::Float32,
+            "double" | "float64" => ScalarType::Float64,
+            _ => return Err(invalid(format!("unknown PLY property type `{}`", name))),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+}
+
+enum PropertyType {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+struct Property {
+    name: String,
+    kind: PropertyType,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads scalar values from the body in either encoding.
+enum ValueReader<R: BufRead> {
+    Ascii(std::vec::IntoIter<String>),
+    Binary { reader: R, big_endian: bool },
+}
+
+impl<R: BufRead> ValueReader<R> {
+    fn read(&mut self, kind: ScalarType) -> io::Result<f64> {
+        match self {
+            ValueReader::Ascii(tokens) => {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| invalid("unexpected end of PLY data".to_string()))?;
+                token
+                    .parse::<f64>()
+                    .map_err(|_| invalid(format!("invalid PLY value `{}`", token)))
+            }
+            ValueReader::Binary { reader, big_endian } => {
+                let mut bytes = [0_u8; 8];
+                let bytes = &mut bytes[..kind.size()];
+                reader.read_exact(bytes)?;
+                if !*big_endian {
+                    bytes.reverse();
+                }
+                // `bytes` is now big-endian.
+                let value = match kind {
+                    ScalarType::Int8 => bytes[0] as i8 as f64,
+                    ScalarType::UInt8 => bytes[0] as f64,
+                    ScalarType::Int16 => i16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::UInt16 => u16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::Int32 => i32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+                    ScalarType::UInt32 => u32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+                    ScalarType::Float32 => f32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+                    ScalarType::Float64 => f64::from_be_bytes(bytes.try_into().unwrap()),
+                };
+                Ok(value)
+            }
+        }
+    }
+}
+
+/// Loads a PLY file from disk.
+pub fn load(path: &Path) -> io::Result<Mesh> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Parses a PLY file into a mesh.
+pub fn read<R: BufRead>(mut reader: R) -> io::Result<Mesh> {
+    let (format, elements) = read_header(&mut reader)?;
+    let mut values = match format {
+        Format::Ascii => {
+            let mut body = String::new();
+            reader.read_to_string(&mut body)?;
+            let tokens: Vec<String> = body.split_whitespace().map(str::to_string).collect();
+            ValueReader::Ascii(tokens.into_iter())
+        }
+        Format::BinaryLittleEndian | Format::BinaryBigEndian => ValueReader::Binary {
+            reader,
+            big_endian: format == Format::BinaryBigEndian,
+        },
+    };
+
+    let mut vertices = Vec::new();
+    let mut colors = Vec::new();
+    let mut normals = Vec::new();
+    let mut polygons = Vec::new();
+
+    for element in elements.iter() {
+        match element.name.as_str() {
+            "vertex" => read_vertices(
+                &mut values,
+                element,
+                &mut vertices,
+                &mut colors,
+                &mut normals,
+            )?,
+            "face" => read_faces(&mut values, element, &mut polygons)?,
+            _ => {
+                for _ in 0..element.count {
+                    for property in element.properties.iter() {
+                        read_property(&mut values, &property.kind)?;
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(index) = polygons
+        .iter()
+        .flatten()
+        .find(|&&index| index as usize >= vertices.len())
+    {
+        return Err(invalid(format!(
+            "PLY face references vertex {} but the file has {} vertices",
+            index,
+            vertices.len()
+        )));
+    }
+    Ok(Mesh::from_polygons(vertices, polygons)
+        .with_colors(colors)
+        .with_normals(normals))
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> io::Result<(Format, Vec<Element>)> {
+    let mut line = String::new();
+    let mut next_line = |reader: &mut R| -> io::Result<String> {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("PLY header ended before `end_header`".to_string()));
+        }
+        Ok(line.trim().to_string())
+    };
+
+    if next_line(reader)? != "ply" {
+        return Err(invalid("not a PLY file".to_string()));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        let line = next_line(reader)?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["end_header"] => break,
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(invalid(format!("unknown PLY format `{}`", name))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid PLY element count `{}`", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("PLY property before any element".to_string()))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind: PropertyType::List {
+                        count: ScalarType::parse(count)?,
+                        item: ScalarType::parse(item)?,
+                    },
+                });
+            }
+            ["property", kind, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("PLY property before any element".to_string()))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind: PropertyType::Scalar(ScalarType::parse(kind)?),
+                });
+            }
+            _ => return Err(invalid(format!("unrecognized PLY header line `{}`", line))),
+        }
+    }
+
+    let format = format.ok_or_else(|| invalid("PLY header has no `format` line".to_string()))?;
+    Ok((format, elements))
+}
+
+/// Reads one property value; lists are returned in full.
+fn read_property<R: BufRead>(
+    values: &mut ValueReader<R>,
+    kind: &PropertyType,
+) -> io::Result<Vec<f64>> {
+    match kind {
+        PropertyType::Scalar(scalar) => Ok(vec![values.read(*scalar)?]),
+        PropertyType::List { count, item } => {
+            let count = values.read(*count)? as usize;
+            (0..count).map(|_| values.read(*item)).collect()
+        }
+    }
+}
+
+fn read_vertices<R: BufRead>(
+    values: &mut ValueReader<R>,
+    element: &Element,
+    vertices: &mut Vec<vec::Point3>,
+    colors: &mut Vec<vec::Vec3>,
+    normals: &mut Vec<vec::Vec3>,
+) -> io::Result<()> {
+    let find = |names: &[&str]| {
+        element
+            .properties
+            .iter()
+            .position(|property| names.contains(&property.name.as_str()))
+    };
+    let (Some(x), Some(y), Some(z)) = (find(&["x"]), find(&["y"]), find(&["z"])) else {
+        return Err(invalid("PLY vertices need x, y, and z".to_string()));
+    };
+    let color = match (
+        find(&["red", "r", "diffuse_red"]),
+        find(&["green", "g", "diffuse_green"]),
+        find(&["blue", "b", "diffuse_blue"]),
+    ) {
+        (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+        _ => None,
+    };
+    let normal = match (find(&["nx"]), find(&["ny"]), find(&["nz"])) {
+        (Some(x), Some(y), Some(z)) => Some([x, y, z]),
+        _ => None,
+    };
+    // Integer colors are 0-255; floating-point colors are already 0-1.
+    let color_scale = match color.map(|[r, ..]| &element.properties[r].kind) {
+        Some(PropertyType::Scalar(ScalarType::Float32 | ScalarType::Float64)) => 1.0,
+        _ => 1.0 / 255.0,
+    };
+
+    // Nothing is reserved up front: the count comes from the header, and a file that
+    // claims billions of rows should fail on the first missing one, not allocate for all.
+    for _ in 0..element.count {
+        let row = element
+            .properties
+            .iter()
+            .map(|property| {
+                read_property(values, &property.kind).map(|value| value.first().copied())
+            })
+            .collect::<io::Result<Vec<Option<f64>>>>()?;
+        let scalar = |index: usize| row[index].unwrap_or(0.0) as f32;
+
+        vertices.push(vec::Point3::new(scalar(x), scalar(y), scalar(z)));
+        if let Some([r, g, b]) = color {
+            colors.push(vec::Vec3::new(scalar(r), scalar(g), scalar(b)) * color_scale);
+        }
+        if let Some([x, y, z]) = normal {
+            normals.push(vec::Vec3::new(scalar(x), scalar(y), scalar(z)));
+        }
+    }
+    Ok(())
+}
+
+fn read_faces<R: BufRead>(
+    values: &mut ValueReader<R>,
+    element: &Element,
+    polygons: &mut Vec<Vec<u32>>,
+) -> io::Result<()> {
+    let indices = element
+        .properties
+        .iter()
+        .position(|property| {
+            matches!(property.kind, PropertyType::List { .. })
+                && (property.name == "vertex_indices" || property.name == "vertex_index")
+        })
+        .ok_or_else(|| invalid("PLY faces need a vertex_indices list".to_string()))?;
+
+    for _ in 0..element.count {
+        let mut polygon = Vec::new();
+        for (position, property) in element.properties.iter().enumerate() {
+            let value = read_property(values, &property.kind)?;
+            if position == indices {
+                polygon = value;
+            }
+        }
+        // The mesh triangulates polygons; faces with fewer than three corners are dropped.
+        polygons.push(polygon.into_iter().map(|index| index as u32).collect());
+    }
+    Ok(())
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_counts_fail_without_allocating() {
+        let header = "ply\nformat binary_little_endian 1.0\nelement vertex 4000000000\n\
+                      property float x\nproperty float y\nproperty float z\n\
+                      element face 4000000000\nproperty list uchar int vertex_indices\n\
+                      end_header\n";
+        let mut file = header.as_bytes().to_vec();
+        file.extend(
+            [0.0f32, 1.0, 2.0]
+                .iter()
+                .flat_map(|value| value.to_le_bytes()),
+        );
+        let err = read(file.as_slice())
+            .err()
+            .expect("truncated file should fail");
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
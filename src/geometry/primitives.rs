@@ -1,4 +1,9 @@
 pub mod cube;
+pub mod curves;
+pub mod disk;
+pub mod heightfield;
+pub mod mesh;
 pub mod quad;
+pub mod sdf;
 pub mod sphere;
 pub mod tri;
@@ -2,7 +2,7 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::core::{bbox, ray};
-use crate::math::{pdf, vec};
+use crate::math::{pdf, rng, vec};
 use crate::traits::hittable;
 use crate::traits::hittable::Hittable;
 
@@ -40,7 +40,7 @@ impl pdf::PDF for CubePDF<'_> {
         distance_squared / (cosine * area)
     }
 
-    fn generate(&self, rng: &mut rand::rngs::ThreadRng) -> vec::Vec3 {
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
         let mut areas = [0.0_f32; 6];
         let mut total_area = 0.0_f32;
         for (idx, face) in self.cube.faces.iter().enumerate() {
@@ -67,11 +67,50 @@ impl pdf::PDF for CubePDF<'_> {
     }
 }
 
+/// How a [`Cube`] maps its faces into texture space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CubeUvLayout {
+    /// Every face spans the full `[0, 1]` range, so each face shows the whole texture.
+    #[default]
+    PerFace,
+    /// Faces are packed into a horizontal cross (4x3 cells): the middle row holds
+    /// -X, +Z, +X, -Z from left to right, with +Y above and -Y below the +Z cell.
+    Cross,
+}
+
+impl CubeUvLayout {
+    /// Cell (column, row from the bottom) of each face in the cross layout, indexed like
+    /// [`Cube::FACE_NAMES`].
+    const CROSS_CELLS: [(f32, f32); 6] = [
+        (1.0, 1.0),
+        (3.0, 1.0),
+        (0.0, 1.0),
+        (2.0, 1.0),
+        (1.0, 2.0),
+        (1.0, 0.0),
+    ];
+
+    fn map(&self, face: usize, u: f32, v: f32) -> (f32, f32) {
+        match self {
+            CubeUvLayout::PerFace => (u, v),
+            CubeUvLayout::Cross => {
+                let (col, row) = Self::CROSS_CELLS[face];
+                ((col + u) / 4.0, (row + v) / 3.0)
+            }
+        }
+    }
+}
+
 /// Axis-aligned cube assembled from six quads.
+///
+/// Hits report the face index as their material slot (in [`Cube::FACE_NAMES`] order), so a
+/// [`crate::materials::instance::MaterialInstance`] with slot materials can shade each face
+/// differently.
 #[derive(Clone, Serialize)]
 pub struct Cube {
     pub min: vec::Vec3,
     pub max: vec::Vec3,
+    pub uv_layout: CubeUvLayout,
 
     #[serde(skip)]
     faces: [quad::Quad; 6],
@@ -81,6 +120,9 @@ pub struct Cube {
 }
 
 impl Cube {
+    /// Face order used for material slots and UV layout cells.
+    pub const FACE_NAMES: [&'static str; 6] = ["+Z", "-Z", "-X", "+X", "+Y", "-Y"];
+
     pub fn new(min: vec::Vec3, max: vec::Vec3) -> Self {
         let min_point = vec::Vec3::new(min.x.min(max.x), min.y.min(max.y), min.z.min(max.z));
         let max_point = vec::Vec3::new(min.x.max(max.x), min.y.max(max.y), min.z.max(max.z));
@@ -90,11 +132,17 @@ impl Cube {
         Cube {
             min: min_point,
             max: max_point,
+            uv_layout: CubeUvLayout::default(),
             faces,
             bbox,
         }
     }
 
+    pub fn with_uv_layout(mut self, uv_layout: CubeUvLayout) -> Self {
+        self.uv_layout = uv_layout;
+        self
+    }
+
     fn build_faces(min: &vec::Vec3, max: &vec::Vec3) -> [quad::Quad; 6] {
         let dx = max.x - min.x;
         let dy = max.y - min.y;
@@ -150,10 +198,12 @@ impl<'de> Deserialize<'de> for Cube {
         struct CubeData {
             min: vec::Vec3,
             max: vec::Vec3,
+            #[serde(default)]
+            uv_layout: CubeUvLayout,
         }
 
         let data = CubeData::deserialize(deserializer)?;
-        Ok(Cube::new(data.min, data.max))
+        Ok(Cube::new(data.min, data.max).with_uv_layout(data.uv_layout))
     }
 }
 
@@ -166,9 +216,11 @@ impl hittable::Hittable for Cube {
         let mut closest = t_max;
         let mut hit_record: Option<hittable::Hit> = None;
 
-        for face in self.faces.iter() {
-            if let Some(hit) = face.hit(ray, t_min, closest) {
+        for (index, face) in self.faces.iter().enumerate() {
+            if let Some(mut hit) = face.hit(ray, t_min, closest) {
                 closest = hit.t;
+                (hit.u, hit.v) = self.uv_layout.map(index, hit.u, hit.v);
+                hit.material_slot = index;
                 hit_record = Some(hit);
             }
         }
@@ -184,6 +236,31 @@ impl hittable::Hittable for Cube {
         Box::new(CubePDF::new(self, *origin, time))
     }
 
+    fn sample_surface(
+        &self,
+        rng: &mut rng::SampleRng,
+        time: f64,
+    ) -> Option<hittable::SurfaceSample> {
+        let areas = self
+            .faces
+            .each_ref()
+            .map(|face| face.u.cross(&face.v).length());
+        let total_area: f32 = areas.iter().sum();
+        let mut pick = rng.random::<f32>() * total_area;
+        let face = areas
+            .iter()
+            .position(|area| {
+                pick -= area;
+                pick <= 0.0
+            })
+            .unwrap_or(5);
+        let sample = self.faces[face].sample_surface(rng, time)?;
+        Some(hittable::SurfaceSample {
+            pdf: 1.0 / total_area,
+            ..sample
+        })
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -0,0 +1,360 @@
+//! Thin strands such as hair, fur, and grass, as swept cubic Bezier curves.
+//!
+//! Every strand is flattened into a chain of short capsules at load time, tapering from
+//! its root width to its tip width. A scene can hold tens of thousands of strands, far
+//! too many to go into the scene BVH one by one, so each [`Curves`] keeps its own BVH
+//! over its segments and appears to the scene as a single object.
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{bbox, bvh, ray};
+use crate::math::{interval, pdf, rng, vec};
+use crate::traits::hittable;
+
+/// Most segments kept in one leaf of the curve BVH.
+const LEAF_SEGMENTS: usize = 4;
+
+/// One strand: a cubic Bezier curve from `points[0]` (the root) to `points[3]` (the
+/// tip), `width` across at each end.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Strand {
+    pub points: [vec::Point3; 4],
+    pub width: [f32; 2],
+}
+
+impl Strand {
+    fn point_at(&self, t: f32) -> vec::Point3 {
+        let s = 1.0 - t;
+        let [p0, p1, p2, p3] = self.points;
+        p0 * (s * s * s) + p1 * (3.0 * s * s * t) + p2 * (3.0 * s * t * t) + p3 * (t * t * t)
+    }
+
+    fn radius_at(&self, t: f32) -> f32 {
+        0.5 * (self.width[0] * (1.0 - t) + self.width[1] * t)
+    }
+}
+
+/// Where a set of curves comes from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "source")]
+pub enum CurveSource {
+    /// Strands listed one by one.
+    Strands { strands: Vec<Strand> },
+    /// A patch of `count` grass blades rooted at random over the XZ box from `corner`
+    /// spanning `size.x` by `size.z`, each about `size.y` tall, `width` across at the
+    /// root and bending over by up to `bend` of its height. The same `seed` always gives
+    /// the same patch.
+    Grass {
+        corner: vec::Point3,
+        size: vec::Vec3,
+        count: u32,
+        width: f32,
+        #[serde(default = "default_bend")]
+        bend: f32,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+fn default_bend() -> f32 {
+    0.3
+}
+
+fn default_segments() -> u32 {
+    8
+}
+
+impl CurveSource {
+    fn strands(&self) -> Vec<Strand> {
+        match self {
+            CurveSource::Strands { strands } => strands.clone(),
+            CurveSource::Grass {
+                corner,
+                size,
+                count,
+                width,
+                bend,
+                seed,
+            } => {
+                let mut rng = rng::hashed_rng(*seed);
+                (0..*count)
+                    .map(|_| {
+                        let root = *corner
+                            + vec::Vec3::new(
+                                rng.random::<f32>() * size.x,
+                                0.0,
+                                rng.random::<f32>() * size.z,
+                            );
+                        let height = size.y * rng.random_range(0.7..1.3);
+                        let angle = rng.random_range(0.0..std::f32::consts::TAU);
+                        let lean = vec::Vec3::new(angle.cos(), 0.0, angle.sin())
+                            * (height * bend * rng.random::<f32>());
+                        let up = vec::Vec3::new(0.0, height, 0.0);
+                        // Blades rise straight from the root and droop toward the tip.
+                        Strand {
+                            points: [
+                                root,
+                                root + up * 0.4,
+                                root + up * 0.8 + lean * 0.5,
+                                root + up * 0.9 + lean,
+                            ],
+                            width: [*width, *width * 0.1],
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A straight piece of a strand, swept by a sphere of `radius`.
+#[derive(Clone, Copy)]
+struct Segment {
+    start: vec::Point3,
+    end: vec::Point3,
+    radius: f32,
+    /// Curve parameter at `start` and `end`.
+    span: (f32, f32),
+}
+
+impl bvh::Bounded for Segment {
+    fn bounding_box(&self) -> bbox::BBox {
+        let axis = |a: f32, b: f32| {
+            interval::Interval::new(a.min(b) - self.radius, a.max(b) + self.radius)
+        };
+        bbox::BBox::new(
+            axis(self.start.x, self.end.x),
+            axis(self.start.y, self.end.y),
+            axis(self.start.z, self.end.z),
+        )
+    }
+}
+
+impl Segment {
+    /// Nearest intersection of the capsule around the segment with a ray whose
+    /// direction is unit length, as `(t, normal, fraction along the segment)`.
+    fn intersect(
+        &self,
+        origin: &vec::Point3,
+        direction: &vec::Vec3,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(f32, vec::Vec3, f32)> {
+        let axis = self.end - self.start;
+        let axis_length2 = axis.dot(&axis);
+        let offset = *origin - self.start;
+        let radius2 = self.radius * self.radius;
+        let mut closest: Option<(f32, vec::Vec3, f32)> = None;
+        let mut consider = |t: f32, normal: vec::Vec3, along: f32| {
+            if t > t_min && t < closest.map_or(t_max, |(closest_t, _, _)| closest_t) {
+                closest = Some((t, normal, along));
+            }
+        };
+
+        // The cylinder between the end caps.
+        let axis_direction = axis.dot(direction);
+        let axis_offset = axis.dot(&offset);
+        let a = axis_length2 - axis_direction * axis_direction;
+        if a > 0.0 {
+            let b = axis_length2 * offset.dot(direction) - axis_offset * axis_direction;
+            let c = axis_length2 * offset.dot(&offset)
+                - axis_offset * axis_offset
+                - radius2 * axis_length2;
+            let discriminant = b * b - a * c;
+            if discriminant >= 0.0 {
+                let root = discriminant.sqrt();
+                for t in [(-b - root) / a, (-b + root) / a] {
+                    let y = axis_offset + t * axis_direction;
+                    if y > 0.0 && y < axis_length2 {
+                        let normal =
+                            (offset + *direction * t - axis * (y / axis_length2)) / self.radius;
+                        consider(t, normal, y / axis_length2);
+                    }
+                }
+            }
+        }
+
+        // The spheres capping each end, outside the cylinder.
+        for (center, along) in [(self.start, 0.0), (self.end, 1.0)] {
+            let to_origin = *origin - center;
+            let b = to_origin.dot(direction);
+            let c = to_origin.dot(&to_origin) - radius2;
+            let discriminant = b * b - c;
+            if discriminant < 0.0 {
+                continue;
+            }
+            let root = discriminant.sqrt();
+            for t in [-b - root, -b + root] {
+                let point = to_origin + *direction * t;
+                let y = axis.dot(&(point + center - self.start));
+                let outside = if along == 0.0 {
+                    y <= 0.0
+                } else {
+                    y >= axis_length2
+                };
+                if outside {
+                    consider(t, point / self.radius, along);
+                }
+            }
+        }
+        closest
+    }
+}
+
+/// A set of strands, each a cubic Bezier curve swept by a circle that narrows from root
+/// to tip and cut into `segments` straight pieces for intersection.
+///
+/// Hits report `u` along the strand from root (0) to tip (1) and `v` across it, with
+/// 0.5 along the middle of the strand as the ray sees it. Normals point away from the
+/// strand's axis, so strands shade like thin tubes. Emissive curves are sampled with
+/// uniform directions.
+#[derive(Clone)]
+pub struct Curves {
+    source: CurveSource,
+    segments: u32,
+    pieces: Vec<Segment>,
+    bvh: bvh::FlatBvh,
+}
+
+impl Curves {
+    /// Builds the strands from `source`, cutting each into `segments` pieces (at least
+    /// one).
+    pub fn new(source: CurveSource, segments: u32) -> Self {
+        let segments = segments.max(1);
+        let mut pieces: Vec<Segment> = source
+            .strands()
+            .iter()
+            .flat_map(|strand| {
+                (0..segments).map(move |index| {
+                    let t0 = index as f32 / segments as f32;
+                    let t1 = (index + 1) as f32 / segments as f32;
+                    Segment {
+                        start: strand.point_at(t0),
+                        end: strand.point_at(t1),
+                        radius: strand.radius_at((t0 + t1) * 0.5).max(f32::EPSILON),
+                        span: (t0, t1),
+                    }
+                })
+            })
+            .collect();
+        let bvh = bvh::FlatBvh::build(&mut pieces, LEAF_SEGMENTS);
+        Curves {
+            source,
+            segments,
+            pieces,
+            bvh,
+        }
+    }
+
+    pub fn source(&self) -> &CurveSource {
+        &self.source
+    }
+
+    /// Straight pieces each strand is cut into.
+    pub fn segments(&self) -> u32 {
+        self.segments
+    }
+
+    /// Number of strands.
+    pub fn strand_count(&self) -> usize {
+        self.pieces.len() / self.segments as usize
+    }
+}
+
+impl Serialize for Curves {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct CurvesData<'a> {
+            segments: u32,
+            #[serde(flatten)]
+            source: &'a CurveSource,
+        }
+
+        CurvesData {
+            segments: self.segments,
+            source: &self.source,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Curves {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct CurvesData {
+            #[serde(default = "default_segments")]
+            segments: u32,
+            #[serde(flatten)]
+            source: CurveSource,
+        }
+
+        let data = CurvesData::deserialize(deserializer)?;
+        Ok(Curves::new(data.source, data.segments))
+    }
+}
+
+impl hittable::Hittable for Curves {
+    fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
+        // Segments are intersected along a unit direction; `scale` converts back.
+        let scale = ray.direction.length();
+        if scale == 0.0 {
+            return None;
+        }
+        let direction = ray.direction / scale;
+
+        let mut closest: Option<(f32, vec::Vec3, usize, f32)> = None;
+        self.bvh.hit(ray, t_min, t_max, |piece_index, closest_t| {
+            let (t, normal, along) = self.pieces[piece_index].intersect(
+                &ray.origin,
+                &direction,
+                t_min * scale,
+                closest_t * scale,
+            )?;
+            closest = Some((t / scale, normal, piece_index, along));
+            Some(t / scale)
+        });
+
+        let (t, normal, piece_index, along) = closest?;
+        let piece = &self.pieces[piece_index];
+        let normal = normal.normalize();
+        // Position across the strand: which side of the axis the hit is on, as seen
+        // along the ray.
+        let axis = piece.end - piece.start;
+        let side = axis.cross(&direction);
+        let across = if side.squared_length() > 0.0 {
+            normal.dot(&side.normalize())
+        } else {
+            0.0
+        };
+        Some(hittable::Hit {
+            ray: *ray,
+            t,
+            point: ray.point_at(t),
+            normal,
+            u: piece.span.0 + (piece.span.1 - piece.span.0) * along,
+            v: (0.5 + 0.5 * across).clamp(0.0, 1.0),
+            material_slot: 0,
+            vertex_color: None,
+            shading_normal: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> bbox::BBox {
+        self.bvh.bounding_box()
+    }
+
+    fn get_pdf(&self, _origin: &vec::Point3, _time: f64) -> Box<dyn pdf::PDF + Send + Sync + '_> {
+        Box::new(pdf::uniform::UniformPDF {})
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,230 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{bbox, ray};
+use crate::math::{interval, pdf, rng, vec};
+use crate::traits::hittable;
+use crate::traits::hittable::Hittable;
+
+pub struct DiskPDF<'a> {
+    disk: &'a Disk,
+    origin: vec::Point3,
+    time: f64,
+}
+
+impl<'a> DiskPDF<'a> {
+    pub fn new(disk: &'a Disk, origin: vec::Point3, time: f64) -> Self {
+        DiskPDF { disk, origin, time }
+    }
+}
+
+impl pdf::PDF for DiskPDF<'_> {
+    fn value(&self, direction: vec::Vec3) -> f32 {
+        let ray = ray::Ray::new(&self.origin, &direction, Some(self.time));
+        let Some(hit) = self.disk.hit(&ray, 0.001, f32::MAX) else {
+            return 0.0;
+        };
+        let area = self.disk.area();
+        let direction_len_sq = direction.squared_length();
+        if direction_len_sq <= f32::EPSILON || area <= 0.0 {
+            return 0.0;
+        }
+        let distance_squared = hit.t * hit.t * direction_len_sq;
+        let cosine = (direction.dot(&hit.normal) / direction_len_sq.sqrt()).abs();
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        distance_squared / (cosine * area)
+    }
+
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
+        // Uniform by area: the radius follows the square root between the two radii.
+        let inner_sq = self.disk.inner_radius * self.disk.inner_radius;
+        let outer_sq = self.disk.outer_radius * self.disk.outer_radius;
+        let radius = (inner_sq + rng.random::<f32>() * (outer_sq - inner_sq)).sqrt();
+        let angle = 2.0 * std::f32::consts::PI * rng.random::<f32>();
+        let point = self.disk.center
+            + self.disk.tangent * (radius * angle.cos())
+            + self.disk.bitangent * (radius * angle.sin());
+        point - self.origin
+    }
+}
+
+/// Flat disk, or an annulus when `inner_radius` is positive, facing along `normal`.
+///
+/// Hits report polar coordinates: `u` is the angle around the center in turns and `v`
+/// runs from 0 at the inner edge to 1 at the outer edge.
+#[derive(Serialize)]
+pub struct Disk {
+    pub center: vec::Point3,
+    pub normal: vec::Vec3,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+
+    #[serde(skip)]
+    tangent: vec::Vec3,
+
+    #[serde(skip)]
+    bitangent: vec::Vec3,
+
+    #[serde(skip)]
+    bbox: bbox::BBox,
+}
+
+impl Disk {
+    /// Creates a solid disk of the given radius.
+    pub fn new(center: vec::Point3, normal: vec::Vec3, radius: f32) -> Self {
+        Disk::annulus(center, normal, 0.0, radius)
+    }
+
+    /// Creates a ring between `inner_radius` and `outer_radius`.
+    pub fn annulus(
+        center: vec::Point3,
+        normal: vec::Vec3,
+        inner_radius: f32,
+        outer_radius: f32,
+    ) -> Self {
+        let normal = normal.normalize();
+        // Any axis not parallel to the normal gives a tangent frame.
+        let helper = if normal.x.abs() > 0.9 {
+            vec::Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            vec::Vec3::new(1.0, 0.0, 0.0)
+        };
+        let tangent = helper.cross(&normal).normalize();
+        let bitangent = normal.cross(&tangent);
+
+        // The disk reaches `outer_radius * sin(angle to axis)` along each world axis.
+        let extent = |component: f32| outer_radius * (1.0 - component * component).max(0.0).sqrt();
+        let half = vec::Vec3::new(extent(normal.x), extent(normal.y), extent(normal.z));
+        let bbox = bbox::BBox::new(
+            interval::Interval::new(center.x - half.x, center.x + half.x),
+            interval::Interval::new(center.y - half.y, center.y + half.y),
+            interval::Interval::new(center.z - half.z, center.z + half.z),
+        );
+
+        Disk {
+            center,
+            normal,
+            inner_radius,
+            outer_radius,
+            tangent,
+            bitangent,
+            bbox,
+        }
+    }
+
+    /// Surface area of one side.
+    pub fn area(&self) -> f32 {
+        std::f32::consts::PI
+            * (self.outer_radius * self.outer_radius - self.inner_radius * self.inner_radius)
+                .max(0.0)
+    }
+}
+
+impl Clone for Disk {
+    fn clone(&self) -> Self {
+        Disk::annulus(
+            self.center,
+            self.normal,
+            self.inner_radius,
+            self.outer_radius,
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for Disk {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct DiskData {
+            center: vec::Point3,
+            normal: vec::Vec3,
+            #[serde(default)]
+            inner_radius: f32,
+            outer_radius: f32,
+        }
+
+        let data = DiskData::deserialize(deserializer)?;
+        Ok(Disk::annulus(
+            data.center,
+            data.normal,
+            data.inner_radius,
+            data.outer_radius,
+        ))
+    }
+}
+
+impl hittable::Hittable for Disk {
+    fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
+        let denom = self.normal.dot(&ray.direction);
+        // A zero-length normal normalizes to NaN; such disks are never hit.
+        if denom.is_nan() || denom.abs() < 1e-6 {
+            return None;
+        }
+
+        let t = (self.center - ray.origin).dot(&self.normal) / denom;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = ray.point_at(t);
+        let offset = p - self.center;
+        let distance_sq = offset.squared_length();
+        if distance_sq > self.outer_radius * self.outer_radius
+            || distance_sq < self.inner_radius * self.inner_radius
+        {
+            return None;
+        }
+
+        let angle = offset.dot(&self.bitangent).atan2(offset.dot(&self.tangent));
+        let u = angle / (2.0 * std::f32::consts::PI) + 0.5;
+        let width = self.outer_radius - self.inner_radius;
+        let v = if width > 0.0 {
+            (distance_sq.sqrt() - self.inner_radius) / width
+        } else {
+            0.0
+        };
+
+        Some(hittable::Hit {
+            t,
+            point: p,
+            ray: *ray,
+            normal: self.normal,
+            u,
+            v,
+            material_slot: 0,
+            vertex_color: None,
+            shading_normal: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> bbox::BBox {
+        self.bbox
+    }
+
+    fn get_pdf(&self, origin: &vec::Point3, time: f64) -> Box<dyn pdf::PDF + Send + Sync + '_> {
+        Box::new(DiskPDF::new(self, *origin, time))
+    }
+
+    fn sample_surface(
+        &self,
+        rng: &mut rng::SampleRng,
+        _time: f64,
+    ) -> Option<hittable::SurfaceSample> {
+        let area = self.area();
+        let offset = pdf::PDF::generate(&DiskPDF::new(self, self.center, 0.0), rng);
+        (area > 0.0).then(|| hittable::SurfaceSample {
+            point: self.center + offset,
+            normal: self.normal,
+            pdf: 1.0 / area,
+        })
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,449 @@
+//! Terrain surfaces defined by a regular grid of heights.
+//!
+//! Heights come from a grayscale image, a fractal noise function, or inline samples, and
+//! are stretched over a box in the XZ plane. Rays walk the grid with a 2D DDA, first
+//! over coarse blocks that record their lowest and highest sample so whole stretches of
+//! terrain the ray passes above or below are skipped, then cell by cell inside the blocks
+//! it might hit.
+use serde::{Deserialize, Serialize};
+
+use crate::core::{assets, bbox, ray};
+use crate::math::{interval, noise, pdf, vec};
+use crate::traits::hittable;
+
+use super::tri;
+
+/// Cells along each side of a block of the acceleration grid.
+const BLOCK_CELLS: usize = 16;
+
+/// Where a heightfield's samples come from. Every source gives heights in `[0, 1]`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "source")]
+pub enum HeightSource {
+    /// A grayscale image (color images are converted to luma), one sample per pixel, with
+    /// black at the bottom of the box and white at the top. `path` may be a local file or
+    /// an `http(s)://` URL fetched through [`assets::resolve`].
+    Image { path: String },
+    /// Fractal value noise, `resolution` samples along each side, with `frequency` hills
+    /// across the terrain at the coarsest of `octaves` layers. The same `seed` always
+    /// gives the same terrain.
+    Noise {
+        #[serde(default = "default_resolution")]
+        resolution: u32,
+        #[serde(default = "default_frequency")]
+        frequency: f32,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default)]
+        seed: u64,
+    },
+    /// Row-major samples, `columns` to a row.
+    Samples { columns: u32, heights: Vec<f32> },
+}
+
+fn default_resolution() -> u32 {
+    256
+}
+
+fn default_frequency() -> f32 {
+    4.0
+}
+
+fn default_octaves() -> u32 {
+    6
+}
+
+impl HeightSource {
+    /// Produces the samples as `(columns, rows, heights)`.
+    fn load(&self) -> std::io::Result<(usize, usize, Vec<f32>)> {
+        match self {
+            HeightSource::Image { path } => {
+                let resolved = assets::resolve(path)?;
+                let img = image::open(&resolved).map_err(|err| {
+                    std::io::Error::other(format!("failed to open heightmap {}: {}", path, err))
+                })?;
+                let img = img.to_luma16();
+                let (width, height) = img.dimensions();
+                let heights = img
+                    .into_raw()
+                    .into_iter()
+                    .map(|value| value as f32 / u16::MAX as f32)
+                    .collect();
+                Ok((width as usize, height as usize, heights))
+            }
+            HeightSource::Noise {
+                resolution,
+                frequency,
+                octaves,
+                seed,
+            } => {
+                let resolution = (*resolution).max(2) as usize;
+                let scale = frequency / (resolution - 1) as f32;
+                let heights = (0..resolution * resolution)
+                    .map(|index| {
+                        let x = (index % resolution) as f32 * scale;
+                        let z = (index / resolution) as f32 * scale;
+                        noise::fractal(vec::Point3::new(x, 0.0, z), *octaves, *seed)
+                    })
+                    .collect();
+                Ok((resolution, resolution, heights))
+            }
+            HeightSource::Samples { columns, heights } => {
+                let columns = *columns as usize;
+                if columns == 0 || heights.len() % columns != 0 {
+                    return Err(std::io::Error::other(format!(
+                        "{} height samples don't fill rows of {}",
+                        heights.len(),
+                        columns
+                    )));
+                }
+                Ok((columns, heights.len() / columns, heights.clone()))
+            }
+        }
+    }
+}
+
+/// Lowest and highest sample of one block of the acceleration grid.
+#[derive(Clone, Copy)]
+struct Block {
+    min: f32,
+    max: f32,
+}
+
+/// A grid of heights spanning `size.x` by `size.z` units from `corner`, rising from
+/// `corner.y` (height 0) to `corner.y + size.y` (height 1).
+///
+/// Sample column `i`, row `j` sits at `x = corner.x + i / (columns - 1) * size.x`,
+/// `z = corner.z + j / (rows - 1) * size.z`, so the top row of a heightmap image lies
+/// along `corner.z`. Each cell is split into two triangles. Hits report normals
+/// interpolated from the slope at the surrounding samples, and `(u, v)` across the whole
+/// terrain laid out like the heightmap, so an image texture of the same layout drapes
+/// over it. Emissive heightfields are sampled with uniform directions.
+#[derive(Clone)]
+pub struct Heightfield {
+    pub corner: vec::Point3,
+    pub size: vec::Vec3,
+    source: HeightSource,
+    columns: usize,
+    rows: usize,
+    /// World-space position of every sample, row-major.
+    points: Vec<vec::Point3>,
+    /// Unit surface normal at every sample, row-major.
+    normals: Vec<vec::Vec3>,
+    /// Block grid, row-major, `block_columns` to a row.
+    blocks: Vec<Block>,
+    block_columns: usize,
+    bbox: bbox::BBox,
+}
+
+impl Heightfield {
+    /// Builds the terrain from `source`, failing if its image can't be read or it has
+    /// fewer than two samples along either side.
+    pub fn new(
+        corner: vec::Point3,
+        size: vec::Vec3,
+        source: HeightSource,
+    ) -> std::io::Result<Self> {
+        if size.x == 0.0 || size.z == 0.0 {
+            return Err(std::io::Error::other(
+                "a heightfield needs a nonzero size along X and Z",
+            ));
+        }
+        let (columns, rows, heights) = source.load()?;
+        if columns < 2 || rows < 2 {
+            return Err(std::io::Error::other(format!(
+                "a heightfield needs at least 2x2 samples, got {}x{}",
+                columns, rows
+            )));
+        }
+
+        let points: Vec<vec::Point3> = heights
+            .iter()
+            .enumerate()
+            .map(|(index, height)| {
+                let (i, j) = (index % columns, index / columns);
+                vec::Point3::new(
+                    corner.x + i as f32 / (columns - 1) as f32 * size.x,
+                    corner.y + height.clamp(0.0, 1.0) * size.y,
+                    corner.z + j as f32 / (rows - 1) as f32 * size.z,
+                )
+            })
+            .collect();
+
+        let sample = |i: usize, j: usize| points[j * columns + i];
+        let normals = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                // Central differences, one-sided along the edges.
+                let dx = sample((i + 1).min(columns - 1), j) - sample(i.saturating_sub(1), j);
+                let dz = sample(i, (j + 1).min(rows - 1)) - sample(i, j.saturating_sub(1));
+                let normal = dz.cross(&dx);
+                if normal.squared_length() > 0.0 {
+                    let normal = normal.normalize();
+                    if normal.y < 0.0 { -normal } else { normal }
+                } else {
+                    vec::Vec3::new(0.0, 1.0, 0.0)
+                }
+            })
+            .collect();
+
+        let cells = (columns - 1, rows - 1);
+        let block_columns = cells.0.div_ceil(BLOCK_CELLS);
+        let block_rows = cells.1.div_ceil(BLOCK_CELLS);
+        let blocks = (0..block_rows)
+            .flat_map(|block_j| (0..block_columns).map(move |block_i| (block_i, block_j)))
+            .map(|(block_i, block_j)| {
+                let i_range = block_i * BLOCK_CELLS..=((block_i + 1) * BLOCK_CELLS).min(cells.0);
+                let j_range = block_j * BLOCK_CELLS..=((block_j + 1) * BLOCK_CELLS).min(cells.1);
+                j_range
+                    .flat_map(|j| i_range.clone().map(move |i| (i, j)))
+                    .fold(
+                        Block {
+                            min: f32::INFINITY,
+                            max: f32::NEG_INFINITY,
+                        },
+                        |block, (i, j)| Block {
+                            min: block.min.min(sample(i, j).y),
+                            max: block.max.max(sample(i, j).y),
+                        },
+                    )
+            })
+            .collect();
+
+        let (low, high) = points
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), point| {
+                (low.min(point.y), high.max(point.y))
+            });
+        let bbox = bbox::BBox::new(
+            interval::Interval::new(
+                corner.x.min(corner.x + size.x),
+                corner.x.max(corner.x + size.x),
+            ),
+            interval::Interval::new(low, high),
+            interval::Interval::new(
+                corner.z.min(corner.z + size.z),
+                corner.z.max(corner.z + size.z),
+            ),
+        );
+
+        Ok(Heightfield {
+            corner,
+            size,
+            source,
+            columns,
+            rows,
+            points,
+            normals,
+            blocks,
+            block_columns,
+            bbox,
+        })
+    }
+
+    pub fn source(&self) -> &HeightSource {
+        &self.source
+    }
+
+    /// Samples along the X and Z sides.
+    pub fn resolution(&self) -> (usize, usize) {
+        (self.columns, self.rows)
+    }
+
+    /// Intersects the two triangles of cell `(i, j)`, returning the nearer hit as
+    /// `(t, triangle corners, barycentric weights of the second and third corners)`.
+    fn hit_cell(
+        &self,
+        i: usize,
+        j: usize,
+        ray: &ray::Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(f32, [usize; 3], f32, f32)> {
+        let index = |i: usize, j: usize| j * self.columns + i;
+        let triangles = [
+            [index(i, j), index(i, j + 1), index(i + 1, j)],
+            [index(i + 1, j), index(i, j + 1), index(i + 1, j + 1)],
+        ];
+        let mut closest = None;
+        let mut closest_t = t_max;
+        for corners in triangles {
+            let [a, b, c] = corners.map(|corner| self.points[corner]);
+            if let Some((t, u, v)) = tri::intersect(&a, &b, &c, ray, t_min, closest_t) {
+                closest_t = t;
+                closest = Some((t, corners, u, v));
+            }
+        }
+        closest
+    }
+}
+
+/// Visits the cells of a 2D grid that a ray crosses between `t_start` and `t_end`, in
+/// order. `origin` and `direction` are the ray's XZ components in cell units relative
+/// to the grid's corner; `visit` gets each cell's indices and the span of `t` inside it
+/// and returns `true` to stop.
+fn walk_grid(
+    origin: (f32, f32),
+    direction: (f32, f32),
+    (t_start, t_end): (f32, f32),
+    (columns, rows): (usize, usize),
+    mut visit: impl FnMut(usize, usize, f32, f32) -> bool,
+) {
+    let axis = |origin: f32, direction: f32, cells: usize| {
+        let position = origin + direction * t_start;
+        let cell = (position.floor().max(0.0) as usize).min(cells - 1);
+        if direction > 0.0 {
+            (
+                cell,
+                1_isize,
+                ((cell + 1) as f32 - origin) / direction,
+                1.0 / direction,
+            )
+        } else if direction < 0.0 {
+            (
+                cell,
+                -1,
+                (cell as f32 - origin) / direction,
+                -1.0 / direction,
+            )
+        } else {
+            (cell, 0, f32::INFINITY, f32::INFINITY)
+        }
+    };
+    let (mut i, step_i, mut next_i, delta_i) = axis(origin.0, direction.0, columns);
+    let (mut j, step_j, mut next_j, delta_j) = axis(origin.1, direction.1, rows);
+
+    let mut t = t_start;
+    while t <= t_end {
+        let exit = next_i.min(next_j).min(t_end);
+        if visit(i, j, t, exit) {
+            return;
+        }
+        if next_i < next_j {
+            let Some(cell) = i.checked_add_signed(step_i).filter(|cell| *cell < columns) else {
+                return;
+            };
+            i = cell;
+            t = next_i;
+            next_i += delta_i;
+        } else {
+            let Some(cell) = j.checked_add_signed(step_j).filter(|cell| *cell < rows) else {
+                return;
+            };
+            j = cell;
+            t = next_j;
+            next_j += delta_j;
+        }
+    }
+}
+
+impl Serialize for Heightfield {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct HeightfieldData<'a> {
+            corner: vec::Point3,
+            size: vec::Vec3,
+            #[serde(flatten)]
+            source: &'a HeightSource,
+        }
+
+        HeightfieldData {
+            corner: self.corner,
+            size: self.size,
+            source: &self.source,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Heightfield {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct HeightfieldData {
+            corner: vec::Point3,
+            size: vec::Vec3,
+            #[serde(flatten)]
+            source: HeightSource,
+        }
+
+        let data = HeightfieldData::deserialize(deserializer)?;
+        Heightfield::new(data.corner, data.size, data.source).map_err(serde::de::Error::custom)
+    }
+}
+
+impl hittable::Hittable for Heightfield {
+    fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
+        let (start, end) = self.bbox.clip(ray, t_min, t_max)?;
+        let cells = (self.columns - 1, self.rows - 1);
+        let cell_size = (self.size.x / cells.0 as f32, self.size.z / cells.1 as f32);
+        let origin = (
+            (ray.origin.x - self.corner.x) / cell_size.0,
+            (ray.origin.z - self.corner.z) / cell_size.1,
+        );
+        let direction = (ray.direction.x / cell_size.0, ray.direction.z / cell_size.1);
+        let block_count = (self.block_columns, self.blocks.len() / self.block_columns);
+        let block_scale = BLOCK_CELLS as f32;
+
+        let mut found = None;
+        walk_grid(
+            (origin.0 / block_scale, origin.1 / block_scale),
+            (direction.0 / block_scale, direction.1 / block_scale),
+            (start, end),
+            block_count,
+            |block_i, block_j, block_start, block_end| {
+                let block = self.blocks[block_j * self.block_columns + block_i];
+                let y0 = ray.point_at(block_start).y;
+                let y1 = ray.point_at(block_end).y;
+                if y0.max(y1) < block.min || y0.min(y1) > block.max {
+                    return false;
+                }
+                walk_grid(
+                    origin,
+                    direction,
+                    (block_start, block_end),
+                    cells,
+                    |i, j, _, _| {
+                        found = self.hit_cell(i, j, ray, t_min, t_max);
+                        found.is_some()
+                    },
+                );
+                found.is_some()
+            },
+        );
+
+        let (t, corners, u, v) = found?;
+        let [na, nb, nc] = corners.map(|corner| self.normals[corner]);
+        let normal = (na * (1.0 - u - v) + nb * u + nc * v).normalize();
+        let point = ray.point_at(t);
+        Some(hittable::Hit {
+            ray: *ray,
+            t,
+            point,
+            normal,
+            u: ((point.x - self.corner.x) / self.size.x).clamp(0.0, 1.0),
+            v: 1.0 - ((point.z - self.corner.z) / self.size.z).clamp(0.0, 1.0),
+            material_slot: 0,
+            vertex_color: None,
+            shading_normal: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> bbox::BBox {
+        self.bbox
+    }
+
+    fn get_pdf(&self, _origin: &vec::Point3, _time: f64) -> Box<dyn pdf::PDF + Send + Sync + '_> {
+        Box::new(pdf::uniform::UniformPDF {})
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
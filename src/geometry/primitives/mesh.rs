@@ -0,0 +1,536 @@
+use rand::Rng;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{assets, bbox, bvh, ray};
+use crate::geometry::{displacement, obj, ply, stl, subdivision};
+use crate::math::{interval, pdf, rng, vec};
+use crate::traits::hittable;
+use crate::traits::hittable::Hittable;
+
+use super::tri;
+
+/// Most triangles kept in one leaf of the mesh BVH.
+const LEAF_TRIANGLES: usize = 4;
+
+pub struct MeshPDF<'a> {
+    mesh: &'a Mesh,
+    origin: vec::Point3,
+    time: f64,
+}
+
+impl<'a> MeshPDF<'a> {
+    pub fn new(mesh: &'a Mesh, origin: vec::Point3, time: f64) -> Self {
+        MeshPDF { mesh, origin, time }
+    }
+}
+
+impl pdf::PDF for MeshPDF<'_> {
+    fn value(&self, direction: vec::Vec3) -> f32 {
+        let ray = ray::Ray::new(&self.origin, &direction, Some(self.time));
+        let Some(hit) = self.mesh.hit(&ray, 0.001, f32::MAX) else {
+            return 0.0;
+        };
+        let direction_len_sq = direction.squared_length();
+        if direction_len_sq <= f32::EPSILON || self.mesh.area() <= 0.0 {
+            return 0.0;
+        }
+        let distance_squared = hit.t * hit.t * direction_len_sq;
+        let cosine = (direction.dot(&hit.normal) / direction_len_sq.sqrt()).abs();
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        distance_squared / (cosine * self.mesh.area())
+    }
+
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
+        match self.mesh.sample_point(rng) {
+            Some((point, _)) => point - self.origin,
+            None => vec::Vec3::new(1.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// Indexed triangle mesh, optionally with a color and a normal per vertex.
+///
+/// Hits report the barycentric weights of the second and third corners as `(u, v)`, the
+/// face normal given by the triangle's winding, and the interpolated vertex color and
+/// vertex normal if the mesh has them. In scene files the mesh is either inline
+/// (`vertices`, `triangles`, optional `colors` and `normals`) or loaded from a `path`
+/// (`.ply` or `.stl`, ASCII or binary, or `.obj`) resolved through [`assets::resolve`].
+/// An optional `subdivision` (e.g. `{ scheme = "Loop", levels = 2 }`) refines the loaded
+/// mesh into a smooth surface, starting from the file's own polygons, and an optional
+/// `displacement` then moves its vertices along their normals (see [`displacement`]);
+/// saving keeps both alongside a `path`, while inline meshes are saved already
+/// subdivided and displaced.
+///
+/// Every mesh keeps its own BVH over its triangles, built once when the mesh is created,
+/// so a mesh of any size is a single object to the scene BVH and instances of it share
+/// the tree.
+#[derive(Clone)]
+pub struct Mesh {
+    vertices: Vec<vec::Point3>,
+    triangles: Vec<[u32; 3]>,
+    /// Empty, or one linear RGB color per vertex.
+    colors: Vec<vec::Vec3>,
+    /// Empty, or one unit normal per vertex.
+    normals: Vec<vec::Vec3>,
+    /// Polygons `triangles` were fanned from, kept so Catmull-Clark subdivision refines
+    /// the source faces; empty when every face is a triangle.
+    polygons: Vec<Vec<u32>>,
+
+    /// Asset reference the mesh was loaded from, kept so saving a scene writes the
+    /// reference instead of the geometry.
+    source: Option<String>,
+    /// Subdivision applied to the loaded mesh, saved with `source`.
+    subdivision: Option<subdivision::Subdivision>,
+    /// Displacement applied after `subdivision`, saved with `source`.
+    displacement: Option<displacement::Displacement>,
+    bbox: bbox::BBox,
+    /// Running total of triangle areas, for area-proportional light sampling.
+    cumulative_area: Vec<f32>,
+    /// Triangle indices in BVH order, so every leaf covers a contiguous run.
+    order: Vec<u32>,
+    bvh: bvh::FlatBvh,
+}
+
+impl Mesh {
+    /// Builds a mesh from vertex positions and triangles indexing into them.
+    ///
+    /// Panics if a triangle references a vertex that doesn't exist.
+    pub fn new(vertices: Vec<vec::Point3>, triangles: Vec<[u32; 3]>) -> Self {
+        if let Some(index) = triangles
+            .iter()
+            .flatten()
+            .find(|&&index| index as usize >= vertices.len())
+        {
+            panic!(
+                "mesh triangle references vertex {} but the mesh has {} vertices",
+                index,
+                vertices.len()
+            );
+        }
+
+        let bbox = vertices
+            .iter()
+            .map(|vertex| bbox::BBox::bounding(*vertex, *vertex))
+            .reduce(|acc, bbox| acc.union(&bbox))
+            .map(|bbox| bbox::BBox::new(bbox.x, bbox.y, bbox.z))
+            .unwrap_or_else(|| {
+                bbox::BBox::new(interval::empty(), interval::empty(), interval::empty())
+            });
+
+        let mut mesh = Mesh {
+            vertices,
+            triangles,
+            colors: Vec::new(),
+            normals: Vec::new(),
+            polygons: Vec::new(),
+            source: None,
+            subdivision: None,
+            displacement: None,
+            bbox,
+            cumulative_area: Vec::new(),
+            order: Vec::new(),
+            bvh: bvh::FlatBvh::default(),
+        };
+        let mut total = 0.0;
+        mesh.cumulative_area = (0..mesh.triangles.len())
+            .map(|index| {
+                let [a, b, c] = mesh.corners(index);
+                total += (b - a).cross(&(c - a)).length() / 2.0;
+                total
+            })
+            .collect();
+
+        let mut entries: Vec<(bbox::BBox, u32)> = (0..mesh.triangles.len())
+            .map(|index| {
+                // Padded, so triangles lying in an axis plane still have boxes rays hit.
+                let bounds = mesh
+                    .corners(index)
+                    .map(|corner| bbox::BBox::bounding(corner, corner));
+                let bounds = bounds[0].union(&bounds[1]).union(&bounds[2]);
+                (bbox::BBox::new(bounds.x, bounds.y, bounds.z), index as u32)
+            })
+            .collect();
+        mesh.bvh = bvh::FlatBvh::build(&mut entries, LEAF_TRIANGLES);
+        mesh.order = entries.into_iter().map(|(_, triangle)| triangle).collect();
+        mesh
+    }
+
+    /// Builds a mesh from vertex positions and polygons of three or more corners, which
+    /// are fan-triangulated for rendering and kept for subdivision. Faces with fewer than
+    /// three corners are dropped.
+    ///
+    /// Panics if a polygon references a vertex that doesn't exist.
+    pub fn from_polygons(vertices: Vec<vec::Point3>, polygons: Vec<Vec<u32>>) -> Self {
+        let mut mesh = Mesh::new(vertices, subdivision::triangulate(&polygons));
+        if polygons.iter().any(|polygon| polygon.len() > 3) {
+            mesh.polygons = polygons
+                .into_iter()
+                .filter(|polygon| polygon.len() >= 3)
+                .collect();
+        }
+        mesh
+    }
+
+    /// Attaches one color per vertex; panics if the count doesn't match the vertices.
+    pub fn with_colors(mut self, colors: Vec<vec::Vec3>) -> Self {
+        assert!(
+            colors.is_empty() || colors.len() == self.vertices.len(),
+            "mesh has {} vertices but {} colors",
+            self.vertices.len(),
+            colors.len()
+        );
+        self.colors = colors;
+        self
+    }
+
+    /// Attaches one normal per vertex, normalizing them (zero-length normals stay zero
+    /// and don't contribute); panics if the count doesn't match the vertices.
+    pub fn with_normals(mut self, normals: Vec<vec::Vec3>) -> Self {
+        assert!(
+            normals.is_empty() || normals.len() == self.vertices.len(),
+            "mesh has {} vertices but {} normals",
+            self.vertices.len(),
+            normals.len()
+        );
+        self.normals = normals
+            .iter()
+            .map(|normal| {
+                if normal.squared_length() > f32::EPSILON {
+                    normal.normalize()
+                } else {
+                    vec::Vec3::default()
+                }
+            })
+            .collect();
+        self
+    }
+
+    /// Refines the mesh with `subdivision`, carrying vertex colors and normals along;
+    /// subdivisions stack if applied more than once.
+    pub fn with_subdivision(self, subdivision: subdivision::Subdivision) -> Self {
+        let (vertices, faces, colors, normals) = subdivision::subdivide(
+            &self.vertices,
+            &self.faces(),
+            &self.colors,
+            &self.normals,
+            subdivision,
+        );
+        let mut mesh = Mesh::from_polygons(vertices, faces)
+            .with_colors(colors)
+            .with_normals(normals);
+        mesh.source = self.source;
+        mesh.displacement = self.displacement;
+        mesh.subdivision = match self.subdivision {
+            Some(previous) if previous.scheme == subdivision.scheme => {
+                Some(subdivision::Subdivision {
+                    levels: previous.levels + subdivision.levels,
+                    ..subdivision
+                })
+            }
+            // Mixed schemes can't be described by one setting; keep the newest.
+            _ => Some(subdivision),
+        };
+        mesh
+    }
+
+    /// Displaces the vertices along their normals by the heights `displacement` gives
+    /// them, recomputing vertex normals if the mesh has any. Fails if the displacement
+    /// map can't be read.
+    pub fn with_displacement(
+        self,
+        displacement: displacement::Displacement,
+    ) -> std::io::Result<Self> {
+        let (vertices, normals) = displacement::displace(
+            &self.vertices,
+            &self.triangles,
+            &self.normals,
+            &displacement,
+        )?;
+        let mut mesh = Mesh::new(vertices, self.triangles)
+            .with_colors(self.colors)
+            .with_normals(normals);
+        mesh.polygons = self.polygons;
+        mesh.source = self.source;
+        mesh.subdivision = self.subdivision;
+        mesh.displacement = Some(displacement);
+        Ok(mesh)
+    }
+
+    /// Loads a mesh file from a local path or `http(s)://` URL, picking the format from
+    /// the file extension.
+    pub fn open(reference: &str) -> std::io::Result<Self> {
+        let path = assets::resolve(reference)?;
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let mesh = match extension.as_deref() {
+            Some("ply") => ply::load(&path),
+            Some("stl") => stl::load(&path),
+            Some("obj") => obj::load(&path),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("unsupported mesh format: {}", reference),
+            )),
+        };
+        let mut mesh = mesh.map_err(|err| {
+            std::io::Error::new(
+                err.kind(),
+                format!("failed to load mesh {}: {}", reference, err),
+            )
+        })?;
+        mesh.source = Some(reference.to_string());
+        Ok(mesh)
+    }
+
+    pub fn vertices(&self) -> &[vec::Point3] {
+        &self.vertices
+    }
+
+    pub fn triangles(&self) -> &[[u32; 3]] {
+        &self.triangles
+    }
+
+    /// The mesh's faces: its source polygons if it has any, otherwise its triangles.
+    pub fn faces(&self) -> Vec<Vec<u32>> {
+        if self.polygons.is_empty() {
+            self.triangles
+                .iter()
+                .map(|triangle| triangle.to_vec())
+                .collect()
+        } else {
+            self.polygons.clone()
+        }
+    }
+
+    pub fn colors(&self) -> &[vec::Vec3] {
+        &self.colors
+    }
+
+    pub fn normals(&self) -> &[vec::Vec3] {
+        &self.normals
+    }
+
+    /// Whether the mesh encloses a volume: every edge is shared by an even number of
+    /// triangles, so a ray crossing the surface alternates between outside and inside.
+    /// Triangle winding doesn't matter.
+    pub fn is_closed(&self) -> bool {
+        let mut edges: std::collections::HashMap<(u32, u32), u32> =
+            std::collections::HashMap::new();
+        for [a, b, c] in &self.triangles {
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                *edges.entry((*from.min(to), *from.max(to))).or_default() += 1;
+            }
+        }
+        !self.triangles.is_empty() && edges.values().all(|count| count % 2 == 0)
+    }
+
+    /// Total surface area.
+    pub fn area(&self) -> f32 {
+        self.cumulative_area.last().copied().unwrap_or(0.0)
+    }
+
+    /// A uniformly distributed point on the surface and the index of its triangle.
+    fn sample_point(&self, rng: &mut rng::SampleRng) -> Option<(vec::Point3, usize)> {
+        if self.triangles.is_empty() {
+            return None;
+        }
+        // Pick a triangle proportionally to its area, then a uniform point on it.
+        let pick = rng.random::<f32>() * self.area();
+        let index = self
+            .cumulative_area
+            .partition_point(|&area| area < pick)
+            .min(self.triangles.len() - 1);
+        let [a, b, c] = self.corners(index);
+
+        let r1 = rng.random::<f32>().sqrt();
+        let r2 = rng.random::<f32>();
+        let point = a * (1.0 - r1) + b * (r1 * (1.0 - r2)) + c * (r1 * r2);
+        Some((point, index))
+    }
+
+    fn corners(&self, index: usize) -> [vec::Point3; 3] {
+        self.triangles[index].map(|vertex| self.vertices[vertex as usize])
+    }
+}
+
+impl Serialize for Mesh {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if let Some(source) = &self.source {
+            let mut state = serializer.serialize_struct("Mesh", 3)?;
+            state.serialize_field("path", source)?;
+            if let Some(subdivision) = &self.subdivision {
+                state.serialize_field("subdivision", subdivision)?;
+            } else {
+                state.skip_field("subdivision")?;
+            }
+            if let Some(displacement) = &self.displacement {
+                state.serialize_field("displacement", displacement)?;
+            } else {
+                state.skip_field("displacement")?;
+            }
+            return state.end();
+        }
+        let mut state = serializer.serialize_struct("Mesh", 4)?;
+        state.serialize_field("vertices", &self.vertices)?;
+        state.serialize_field("triangles", &self.triangles)?;
+        if self.colors.is_empty() {
+            state.skip_field("colors")?;
+        } else {
+            state.serialize_field("colors", &self.colors)?;
+        }
+        if self.normals.is_empty() {
+            state.skip_field("normals")?;
+        } else {
+            state.serialize_field("normals", &self.normals)?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Mesh {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct MeshData {
+            path: Option<String>,
+            vertices: Option<Vec<vec::Point3>>,
+            triangles: Option<Vec<[u32; 3]>>,
+            #[serde(default)]
+            colors: Vec<vec::Vec3>,
+            #[serde(default)]
+            normals: Vec<vec::Vec3>,
+            subdivision: Option<subdivision::Subdivision>,
+            displacement: Option<displacement::Displacement>,
+        }
+
+        let data = MeshData::deserialize(deserializer)?;
+        let subdivide = |mesh: Mesh| match data.subdivision {
+            Some(subdivision) => mesh.with_subdivision(subdivision),
+            None => mesh,
+        };
+        let displace = |mesh: Mesh| match data.displacement.clone() {
+            Some(displacement) => mesh.with_displacement(displacement),
+            None => Ok(mesh),
+        };
+        if let Some(path) = data.path {
+            return Mesh::open(&path)
+                .map(subdivide)
+                .and_then(displace)
+                .map_err(serde::de::Error::custom);
+        }
+        let (Some(vertices), Some(triangles)) = (data.vertices, data.triangles) else {
+            return Err(serde::de::Error::custom(
+                "mesh needs either `path` or `vertices` and `triangles`",
+            ));
+        };
+        if let Some(index) = triangles
+            .iter()
+            .flatten()
+            .find(|&&index| index as usize >= vertices.len())
+        {
+            return Err(serde::de::Error::custom(format!(
+                "mesh triangle references missing vertex {}",
+                index
+            )));
+        }
+        if !data.colors.is_empty() && data.colors.len() != vertices.len() {
+            return Err(serde::de::Error::custom(
+                "mesh `colors` must have one entry per vertex",
+            ));
+        }
+        if !data.normals.is_empty() && data.normals.len() != vertices.len() {
+            return Err(serde::de::Error::custom(
+                "mesh `normals` must have one entry per vertex",
+            ));
+        }
+        displace(subdivide(
+            Mesh::new(vertices, triangles)
+                .with_colors(data.colors)
+                .with_normals(data.normals),
+        ))
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+impl hittable::Hittable for Mesh {
+    fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
+        let mut closest: Option<(usize, f32, f32, f32)> = None;
+        self.bvh.hit(ray, t_min, t_max, |item, closest_t| {
+            let index = self.order[item] as usize;
+            let [a, b, c] = self.corners(index);
+            let (t, u, v) = tri::intersect(&a, &b, &c, ray, t_min, closest_t)?;
+            closest = Some((index, t, u, v));
+            Some(t)
+        });
+
+        let (index, t, u, v) = closest?;
+        let [a, b, c] = self.corners(index);
+        let vertex_color = (!self.colors.is_empty()).then(|| {
+            let [ca, cb, cc] = self.triangles[index].map(|vertex| self.colors[vertex as usize]);
+            ca * (1.0 - u - v) + cb * u + cc * v
+        });
+        let shading_normal = (!self.normals.is_empty())
+            .then(|| {
+                let [na, nb, nc] =
+                    self.triangles[index].map(|vertex| self.normals[vertex as usize]);
+                na * (1.0 - u - v) + nb * u + nc * v
+            })
+            // Opposing vertex normals can cancel out; fall back to the face normal.
+            .filter(|normal| normal.squared_length() > f32::EPSILON)
+            .map(|normal| normal.normalize());
+
+        Some(hittable::Hit {
+            ray: *ray,
+            t,
+            point: ray.point_at(t),
+            normal: (b - a).cross(&(c - a)).normalize(),
+            u,
+            v,
+            material_slot: 0,
+            vertex_color,
+            shading_normal,
+            // Barycentric `u` grows toward `b`.
+            tangent: Some((b - a).normalize()),
+        })
+    }
+
+    fn bounding_box(&self) -> bbox::BBox {
+        self.bbox
+    }
+
+    fn get_pdf(&self, origin: &vec::Point3, time: f64) -> Box<dyn pdf::PDF + Send + Sync + '_> {
+        Box::new(MeshPDF::new(self, *origin, time))
+    }
+
+    fn sample_surface(
+        &self,
+        rng: &mut rng::SampleRng,
+        _time: f64,
+    ) -> Option<hittable::SurfaceSample> {
+        let area = self.area();
+        if area <= 0.0 {
+            return None;
+        }
+        let (point, index) = self.sample_point(rng)?;
+        let [a, b, c] = self.corners(index);
+        let normal = (b - a).cross(&(c - a));
+        (normal.squared_length() > 0.0).then(|| hittable::SurfaceSample {
+            point,
+            normal: normal.normalize(),
+            pdf: 1.0 / area,
+        })
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -2,19 +2,34 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::core::{bbox, ray};
-use crate::math::{pdf, vec};
+use crate::math::{distribution, pdf, rng, vec};
 use crate::traits::hittable;
 use crate::traits::hittable::Hittable;
 
+/// Samples directions toward a quad, uniformly over its area or, with
+/// [`QuadPDF::with_emission`], in proportion to a light's brightness across it.
 pub struct QuadPDF<'a> {
     quad: &'a Quad,
     origin: vec::Point3,
     time: f64,
+    emission: Option<&'a distribution::Distribution2D>,
 }
 
 impl<'a> QuadPDF<'a> {
     pub fn new(quad: &'a Quad, origin: vec::Point3, time: f64) -> Self {
-        QuadPDF { quad, origin, time }
+        QuadPDF {
+            quad,
+            origin,
+            time,
+            emission: None,
+        }
+    }
+
+    /// Samples points on the quad by `emission`, a distribution over its `(u, v)`
+    /// parameters such as [`crate::materials::diffuse_light::DiffuseLight::emission_map`].
+    pub fn with_emission(mut self, emission: &'a distribution::Distribution2D) -> Self {
+        self.emission = Some(emission);
+        self
     }
 }
 
@@ -34,22 +49,39 @@ impl pdf::PDF for QuadPDF<'_> {
         if cosine <= 0.0 {
             return 0.0;
         }
-        distance_squared / (cosine * area)
+        let density = match self.emission {
+            Some(emission) => {
+                let (s, t) = self.quad.plane_coords(&hit.point);
+                emission.pdf(s, t)
+            }
+            None => 1.0,
+        };
+        density * distance_squared / (cosine * area)
     }
 
-    fn generate(&self, rng: &mut rand::rngs::ThreadRng) -> vec::Vec3 {
-        let r1: f32 = rng.random::<f32>();
-        let r2: f32 = rng.random::<f32>();
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
+        let (r1, r2) = match self.emission {
+            Some(emission) => {
+                let (s, t, _) = emission.sample(rng);
+                (s, t)
+            }
+            None => (rng.random::<f32>(), rng.random::<f32>()),
+        };
         let point = self.quad.q + self.quad.u * r1 + self.quad.v * r2;
         point - self.origin
     }
 }
+/// A parallelogram from corner `q` spanning `u` and `v`, facing along `u x v`.
 #[derive(Serialize)]
 pub struct Quad {
     pub q: vec::Point3,
     pub u: vec::Vec3,
     pub v: vec::Vec3,
     pub w: vec::Vec3,
+    /// Only rays arriving on the front (the side `u x v` points to) hit the quad, so a
+    /// light shines, or a wall reflects, from one side only.
+    #[serde(skip_serializing_if = "is_false")]
+    pub single_sided: bool,
 
     #[serde(skip)]
     bbox: bbox::BBox,
@@ -61,9 +93,19 @@ pub struct Quad {
     d: f32,
 }
 
+/// Box around the four corners of the quad from `q` spanning `u` and `v`, whichever
+/// way the sides point.
+fn corner_bounds(q: vec::Point3, u: vec::Vec3, v: vec::Vec3) -> bbox::BBox {
+    [q + u, q + v, q + u + v]
+        .iter()
+        .fold(bbox::BBox::bounding(q, q), |bounds, corner| {
+            bounds.union(&bbox::BBox::bounding(*corner, *corner))
+        })
+}
+
 impl Quad {
     pub fn new(q: vec::Point3, u: vec::Vec3, v: vec::Vec3) -> Self {
-        let bbox = bbox::BBox::bounding(q, q + u + v).union(&bbox::BBox::bounding(q + u, q + v));
+        let bbox = corner_bounds(q, u, v);
         let normal = u.cross(&v).normalize();
         let d = normal.dot(&(q as vec::Vec3));
         let w = normal / normal.dot(&normal);
@@ -75,9 +117,33 @@ impl Quad {
             normal,
             d,
             w,
+            single_sided: false,
         }
     }
 
+    /// Makes the quad visible from its front only (see [`Quad::single_sided`]).
+    pub fn with_single_sided(mut self, single_sided: bool) -> Self {
+        self.single_sided = single_sided;
+        self
+    }
+
+    /// Coordinates `(s, t)` of a point in the quad's plane with `point = q + s*u + t*v`;
+    /// both are in `[0, 1]` inside the quad.
+    fn plane_coords(&self, point: &vec::Point3) -> (f32, f32) {
+        let w = *point - self.q;
+
+        let u_dot_u = self.u.dot(&self.u);
+        let u_dot_v = self.u.dot(&self.v);
+        let v_dot_v = self.v.dot(&self.v);
+        let w_dot_u = w.dot(&self.u);
+        let w_dot_v = w.dot(&self.v);
+
+        let denom_quad = u_dot_u * v_dot_v - u_dot_v * u_dot_v;
+        let s = (v_dot_v * w_dot_u - u_dot_v * w_dot_v) / denom_quad;
+        let t = (u_dot_u * w_dot_v - u_dot_v * w_dot_u) / denom_quad;
+        (s, t)
+    }
+
     fn get_uv(&self, point: &vec::Point3) -> (f32, f32) {
         let w = *point - self.q;
         let u_len_sq = self.u.dot(&self.u);
@@ -90,10 +156,14 @@ impl Quad {
 
 impl Clone for Quad {
     fn clone(&self) -> Self {
-        Quad::new(self.q, self.u, self.v)
+        Quad::new(self.q, self.u, self.v).with_single_sided(self.single_sided)
     }
 }
 
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
 impl<'de> Deserialize<'de> for Quad {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -104,11 +174,12 @@ impl<'de> Deserialize<'de> for Quad {
             q: vec::Point3,
             u: vec::Vec3,
             v: vec::Vec3,
+            #[serde(default)]
+            single_sided: bool,
         }
 
         let data = QuadData::deserialize(deserializer)?;
-        let bbox = bbox::BBox::bounding(data.q, data.q + data.u + data.v)
-            .union(&bbox::BBox::bounding(data.q + data.u, data.q + data.v));
+        let bbox = corner_bounds(data.q, data.u, data.v);
         let normal = data.u.cross(&data.v).normalize();
         let d = normal.dot(&data.q);
         let w = normal / normal.dot(&normal);
@@ -121,6 +192,7 @@ impl<'de> Deserialize<'de> for Quad {
             bbox,
             normal,
             d,
+            single_sided: data.single_sided,
         })
     }
 }
@@ -128,7 +200,7 @@ impl<'de> Deserialize<'de> for Quad {
 impl hittable::Hittable for Quad {
     fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
         let denom = self.normal.dot(&ray.direction);
-        if denom.abs() < 1e-6 {
+        if denom.abs() < 1e-6 || (self.single_sided && denom > 0.0) {
             return None;
         }
 
@@ -138,17 +210,7 @@ impl hittable::Hittable for Quad {
         }
 
         let p = ray.point_at(t);
-        let w = p - self.q;
-
-        let u_dot_u = self.u.dot(&self.u);
-        let u_dot_v = self.u.dot(&self.v);
-        let v_dot_v = self.v.dot(&self.v);
-        let w_dot_u = w.dot(&self.u);
-        let w_dot_v = w.dot(&self.v);
-
-        let denom_quad = u_dot_u * v_dot_v - u_dot_v * u_dot_v;
-        let s = (v_dot_v * w_dot_u - u_dot_v * w_dot_v) / denom_quad;
-        let t_param = (u_dot_u * w_dot_v - u_dot_v * w_dot_u) / denom_quad;
+        let (s, t_param) = self.plane_coords(&p);
 
         if s < 0.0 || s > 1.0 || t_param < 0.0 || t_param > 1.0 {
             return None;
@@ -163,6 +225,10 @@ impl hittable::Hittable for Quad {
             normal: self.normal,
             u: u_coord,
             v: v_coord,
+            material_slot: 0,
+            vertex_color: None,
+            shading_normal: None,
+            tangent: Some(vec::unit_vector(&self.u)),
         })
     }
 
@@ -174,6 +240,21 @@ impl hittable::Hittable for Quad {
         Box::new(QuadPDF::new(self, *origin, time))
     }
 
+    fn sample_surface(
+        &self,
+        rng: &mut rng::SampleRng,
+        _time: f64,
+    ) -> Option<hittable::SurfaceSample> {
+        let area = self.u.cross(&self.v).length();
+        let r1: f32 = rng.random::<f32>();
+        let r2: f32 = rng.random::<f32>();
+        (area > 0.0).then(|| hittable::SurfaceSample {
+            point: self.q + self.u * r1 + self.v * r2,
+            normal: self.normal,
+            pdf: 1.0 / area,
+        })
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -0,0 +1,357 @@
+//! Implicit surfaces defined by signed distance fields and rendered by sphere tracing.
+//!
+//! A ray marches forward by the distance to the nearest surface until it comes within
+//! `epsilon` of it, so any shape with a distance function can be rendered, including
+//! fractals and triply periodic surfaces that analytic primitives can't express.
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{bbox, ray};
+use crate::math::{interval, pdf, vec};
+use crate::traits::hittable;
+
+/// A signed distance field: negative inside the surface, positive outside.
+///
+/// Distances may underestimate (marching just takes more steps) but must never
+/// overestimate, or rays step through thin features.
+pub trait DistanceField: Send + Sync {
+    /// Distance from `point` to the surface, in the field's local space.
+    fn distance(&self, point: vec::Point3) -> f32;
+
+    /// Box enclosing the surface in local space; rays are only marched inside it.
+    fn bounding_box(&self) -> bbox::BBox;
+}
+
+/// Built-in distance fields, centered on the origin of their local space.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "shape")]
+pub enum SdfShape {
+    /// The power-`power` Mandelbulb fractal, `scale` units in radius. Higher `iterations`
+    /// resolve finer detail.
+    Mandelbulb {
+        #[serde(default = "default_power")]
+        power: f32,
+        #[serde(default = "default_iterations")]
+        iterations: u32,
+        #[serde(default = "default_scale")]
+        scale: f32,
+    },
+    /// A gyroid sheet `thickness` units thick with cells `cell_size` units wide, clipped
+    /// to a box of `half_extents`.
+    Gyroid {
+        cell_size: f32,
+        thickness: f32,
+        half_extents: vec::Vec3,
+    },
+    /// A box of `half_extents` with its edges rounded off by `radius`.
+    RoundedBox {
+        half_extents: vec::Vec3,
+        radius: f32,
+    },
+}
+
+fn default_power() -> f32 {
+    8.0
+}
+
+fn default_iterations() -> u32 {
+    12
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+impl DistanceField for SdfShape {
+    fn distance(&self, point: vec::Point3) -> f32 {
+        match self {
+            SdfShape::Mandelbulb {
+                power,
+                iterations,
+                scale,
+            } => mandelbulb(point / *scale, *power, *iterations) * scale,
+            SdfShape::Gyroid {
+                cell_size,
+                thickness,
+                half_extents,
+            } => {
+                let frequency = 2.0 * std::f32::consts::PI / cell_size;
+                let p = point * frequency;
+                let gyroid = p.x.sin() * p.y.cos() + p.y.sin() * p.z.cos() + p.z.sin() * p.x.cos();
+                // The gyroid function isn't a true distance; its gradient reaches about
+                // 1.5, so dividing by that keeps the estimate conservative.
+                let sheet = gyroid.abs() / (frequency * 1.5) - thickness * 0.5;
+                sheet.max(box_distance(point, *half_extents))
+            }
+            SdfShape::RoundedBox {
+                half_extents,
+                radius,
+            } => {
+                let inset = *half_extents - vec::Vec3::new(*radius, *radius, *radius);
+                box_distance(point, inset) - radius
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> bbox::BBox {
+        let half = match self {
+            SdfShape::Mandelbulb { scale, .. } => {
+                // The bulb stays within radius 1.2 for common powers.
+                let radius = 1.25 * scale.abs();
+                vec::Vec3::new(radius, radius, radius)
+            }
+            SdfShape::Gyroid { half_extents, .. } => *half_extents,
+            SdfShape::RoundedBox { half_extents, .. } => *half_extents,
+        };
+        bbox::BBox::new(
+            interval::Interval::new(-half.x, half.x),
+            interval::Interval::new(-half.y, half.y),
+            interval::Interval::new(-half.z, half.z),
+        )
+    }
+}
+
+/// Distance estimate for the Mandelbulb of unit scale.
+fn mandelbulb(point: vec::Point3, power: f32, iterations: u32) -> f32 {
+    let mut z = point;
+    let mut derivative = 1.0_f32;
+    let mut radius = z.length();
+    for _ in 0..iterations {
+        if radius > 2.0 {
+            break;
+        }
+        let theta = (z.z / radius).clamp(-1.0, 1.0).acos() * power;
+        let phi = z.y.atan2(z.x) * power;
+        derivative = radius.powf(power - 1.0) * power * derivative + 1.0;
+        let scaled = radius.powf(power);
+        z = vec::Vec3::new(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        ) * scaled
+            + point;
+        radius = z.length();
+    }
+    if radius <= 0.0 {
+        return 0.0;
+    }
+    0.5 * radius.ln() * radius / derivative
+}
+
+/// Exact distance to an origin-centered box of `half_extents`.
+fn box_distance(point: vec::Point3, half_extents: vec::Vec3) -> f32 {
+    let q = vec::Vec3::new(
+        point.x.abs() - half_extents.x,
+        point.y.abs() - half_extents.y,
+        point.z.abs() - half_extents.z,
+    );
+    let outside = vec::Vec3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+    let inside = q.x.max(q.y).max(q.z).min(0.0);
+    outside + inside
+}
+
+/// A distance field placed at `center` and rendered by sphere tracing.
+///
+/// Hits report the surface normal from the field's gradient and spherical UVs around
+/// `center`. Emissive SDFs are sampled with uniform directions rather than toward their
+/// surface.
+#[derive(Clone)]
+pub struct SdfGeometry {
+    pub center: vec::Point3,
+    /// Distance at which a marching ray counts as touching the surface.
+    pub epsilon: f32,
+    /// Most steps a ray takes before it's considered a miss.
+    pub max_steps: u32,
+    /// The built-in shape, if the field is one; custom fields can't be saved to scene files.
+    shape: Option<SdfShape>,
+    field: Arc<dyn DistanceField>,
+    bbox: bbox::BBox,
+}
+
+impl SdfGeometry {
+    /// Places a built-in shape at `center`, marching to 1e-4 units in at most 256 steps.
+    pub fn new(center: vec::Point3, shape: SdfShape) -> Self {
+        let mut sdf = SdfGeometry::custom(center, Arc::new(shape.clone()));
+        sdf.shape = Some(shape);
+        sdf
+    }
+
+    /// Places a user-provided distance field at `center`.
+    pub fn custom(center: vec::Point3, field: Arc<dyn DistanceField>) -> Self {
+        let local = field.bounding_box();
+        let bbox = bbox::BBox::new(
+            interval::Interval::new(local.x.min + center.x, local.x.max + center.x),
+            interval::Interval::new(local.y.min + center.y, local.y.max + center.y),
+            interval::Interval::new(local.z.min + center.z, local.z.max + center.z),
+        );
+        SdfGeometry {
+            center,
+            epsilon: 1e-4,
+            max_steps: 256,
+            shape: None,
+            field,
+            bbox,
+        }
+    }
+
+    pub fn with_epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// The built-in shape, or `None` for a custom field.
+    pub fn shape(&self) -> Option<&SdfShape> {
+        self.shape.as_ref()
+    }
+
+    /// Signed distance from a world-space point to the surface.
+    pub fn distance(&self, point: vec::Point3) -> f32 {
+        self.field.distance(point - self.center)
+    }
+
+    /// Outward unit normal from the central-difference gradient of the field.
+    fn normal(&self, point: vec::Point3) -> vec::Vec3 {
+        let h = self.epsilon.max(1e-5);
+        let axis =
+            |offset: vec::Vec3| self.distance(point + offset) - self.distance(point - offset);
+        let gradient = vec::Vec3::new(
+            axis(vec::Vec3::new(h, 0.0, 0.0)),
+            axis(vec::Vec3::new(0.0, h, 0.0)),
+            axis(vec::Vec3::new(0.0, 0.0, h)),
+        );
+        if gradient.squared_length() > 0.0 {
+            gradient.normalize()
+        } else {
+            vec::Vec3::new(0.0, 1.0, 0.0)
+        }
+    }
+}
+
+impl Serialize for SdfGeometry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct SdfData<'a> {
+            center: vec::Point3,
+            epsilon: f32,
+            max_steps: u32,
+            shape: &'a SdfShape,
+        }
+
+        let Some(shape) = &self.shape else {
+            return Err(serde::ser::Error::custom(
+                "custom distance fields can't be saved",
+            ));
+        };
+        SdfData {
+            center: self.center,
+            epsilon: self.epsilon,
+            max_steps: self.max_steps,
+            shape,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SdfGeometry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct SdfData {
+            center: vec::Point3,
+            #[serde(default = "default_epsilon")]
+            epsilon: f32,
+            #[serde(default = "default_max_steps")]
+            max_steps: u32,
+            shape: SdfShape,
+        }
+
+        let data = SdfData::deserialize(deserializer)?;
+        Ok(SdfGeometry::new(data.center, data.shape)
+            .with_epsilon(data.epsilon)
+            .with_max_steps(data.max_steps))
+    }
+}
+
+fn default_epsilon() -> f32 {
+    1e-4
+}
+
+fn default_max_steps() -> u32 {
+    256
+}
+
+impl hittable::Hittable for SdfGeometry {
+    fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
+        let (start, end) = self.bbox.clip(ray, t_min, t_max)?;
+        let speed = ray.direction.length();
+        if speed <= 0.0 || !speed.is_finite() {
+            return None;
+        }
+
+        // A ray spawned on the surface starts within `epsilon` of it; step off first so
+        // it doesn't hit the same spot again. Rays entering the box from outside may
+        // meet the surface right at its edge, so they don't.
+        let mut t = start;
+        let mut distance = self.distance(ray.point_at(t));
+        for _ in 0..8 {
+            if start > t_min || distance.abs() >= self.epsilon {
+                break;
+            }
+            t += 2.0 * self.epsilon / speed;
+            distance = self.distance(ray.point_at(t));
+        }
+        // March on the side of the surface the ray starts on, so rays leaving a surface
+        // (or travelling inside a refracting solid) find the next crossing.
+        let side = distance.signum();
+        for _ in 0..self.max_steps {
+            if t > end {
+                return None;
+            }
+            let point = ray.point_at(t);
+            let distance = self.distance(point) * side;
+            if distance < self.epsilon {
+                let normal = self.normal(point);
+                let direction = (point - self.center).normalize();
+                let u = direction.z.atan2(direction.x) / (2.0 * std::f32::consts::PI) + 0.5;
+                let v = direction.y.clamp(-1.0, 1.0).acos() / std::f32::consts::PI;
+                return Some(hittable::Hit {
+                    t,
+                    point,
+                    ray: *ray,
+                    normal,
+                    u: if u.is_finite() { u } else { 0.0 },
+                    v: if v.is_finite() { 1.0 - v } else { 0.0 },
+                    material_slot: 0,
+                    vertex_color: None,
+                    shading_normal: None,
+                    tangent: None,
+                });
+            }
+            t += distance / speed;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> bbox::BBox {
+        self.bbox
+    }
+
+    fn get_pdf(&self, _origin: &vec::Point3, _time: f64) -> Box<dyn pdf::PDF + Send + Sync + '_> {
+        Box::new(pdf::uniform::UniformPDF {})
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
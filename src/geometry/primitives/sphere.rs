@@ -2,7 +2,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::core::{bbox, ray};
-use crate::math::{pdf, vec};
+use crate::math::{pdf, rng, vec};
 use crate::traits::hittable;
 use crate::traits::hittable::Hittable;
 
@@ -30,7 +30,7 @@ impl pdf::PDF for SpherePDF<'_> {
         distance_squared / (cosine * area)
     }
 
-    fn generate(&self, rng: &mut rand::rngs::ThreadRng) -> vec::Vec3 {
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
         let unit = vec::unit_vector(&vec::random_in_unit_sphere(rng));
         let point = self.sphere.center + unit * self.sphere.radius;
         point - self.origin
@@ -55,9 +55,12 @@ impl Sphere {
 
     fn get_uv(p_unit: &vec::Vec3) -> (f32, f32) {
         // p_unit is expected to be the unit normal pointing outward from the sphere.
-        let theta = (-p_unit.y).acos();
+        // Clamp before acos: rounding can push |y| just past 1 at the poles and yield NaN.
+        let theta = (-p_unit.y).clamp(-1.0, 1.0).acos();
         let phi = -p_unit.z.atan2(p_unit.x) + std::f32::consts::PI;
+        // Wrap u into [0, 1) so the seam at phi = 2*pi maps back onto the first column.
         let u = phi / (2.0 * std::f32::consts::PI);
+        let u = u - u.floor();
         let v = theta / std::f32::consts::PI;
         (u, v)
     }
@@ -78,6 +81,10 @@ impl hittable::Hittable for Sphere {
                     let point = ray.point_at(temp);
                     let normal = (point - self.center) / self.radius;
                     let (u, v) = Sphere::get_uv(&normal);
+                    // `u` runs around the vertical axis; it has no direction at the poles.
+                    let around = vec::Vec3::new(normal.z, 0.0, -normal.x);
+                    let tangent = (around.squared_length() > 1e-12)
+                        .then(|| vec::unit_vector(&around) * self.radius.signum());
                     return Some(hittable::Hit {
                         ray: ray.clone(),
                         t: temp,
@@ -85,6 +92,10 @@ impl hittable::Hittable for Sphere {
                         normal,
                         u,
                         v,
+                        material_slot: 0,
+                        vertex_color: None,
+                        shading_normal: None,
+                        tangent,
                     });
                 }
             }
@@ -93,7 +104,9 @@ impl hittable::Hittable for Sphere {
     }
 
     fn bounding_box(&self) -> bbox::BBox {
-        let radius_vec = vec::Vec3::new(self.radius, self.radius, self.radius);
+        // A negative radius only turns the normals inward; the sphere is the same size.
+        let radius = self.radius.abs();
+        let radius_vec = vec::Vec3::new(radius, radius, radius);
         bbox::BBox::bounding(self.center - radius_vec, self.center + radius_vec)
     }
 
@@ -105,6 +118,21 @@ impl hittable::Hittable for Sphere {
         })
     }
 
+    fn sample_surface(
+        &self,
+        rng: &mut rng::SampleRng,
+        _time: f64,
+    ) -> Option<hittable::SurfaceSample> {
+        let area = 4.0 * std::f32::consts::PI * self.radius * self.radius;
+        let unit = vec::unit_vector(&vec::random_in_unit_sphere(rng));
+        // Like hits, a negative radius turns the normal inward.
+        (area > 0.0).then(|| hittable::SurfaceSample {
+            point: self.center + unit * self.radius,
+            normal: unit * self.radius.signum(),
+            pdf: 1.0 / area,
+        })
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -1 +1,42 @@
+use crate::core::ray;
+use crate::math::vec;
 
+/// Intersects a ray with the triangle `(a, b, c)` (Möller–Trumbore).
+///
+/// Returns the ray parameter and the barycentric weights of `b` and `c` at the hit; the
+/// weight of `a` is `1 - b - c`. Both faces are hit.
+pub fn intersect(
+    a: &vec::Point3,
+    b: &vec::Point3,
+    c: &vec::Point3,
+    ray: &ray::Ray,
+    t_min: f32,
+    t_max: f32,
+) -> Option<(f32, f32, f32)> {
+    let edge1 = *b - *a;
+    let edge2 = *c - *a;
+    let p = ray.direction.cross(&edge2);
+    let determinant = edge1.dot(&p);
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let inv_determinant = 1.0 / determinant;
+
+    let s = ray.origin - *a;
+    let u = s.dot(&p) * inv_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(&edge1);
+    let v = ray.direction.dot(&q) * inv_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&q) * inv_determinant;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    Some((t, u, v))
+}
@@ -0,0 +1,190 @@
+//! Copies of a geometry strewn over another's surface.
+//!
+//! [`placements`] picks spots on a surface and returns transforms placing a copy at each
+//! one: grass on terrain, pebbles on a floor, moss on a rock. Spots are spread evenly over
+//! the surface's area when its geometry can sample itself
+//! ([`hittable::Hittable::sample_surface`]); other geometry, such as heightfields, is
+//! covered by dropping spots straight down onto it from above its bounding box, which
+//! spreads them evenly as seen from above. An optional density texture thins them out,
+//! and each copy gets its own random size, turn, and lean.
+//!
+//! Scene files describe scatters in `[[scatters]]` entries, which become ordinary
+//! objects when the scene loads.
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core::ray;
+use crate::geometry::transform;
+use crate::math::{mat, rng, vec};
+use crate::traits::{hittable, texturable};
+
+/// Spots tried per copy before giving up on a surface the density texture mostly rejects.
+const ATTEMPTS_PER_COPY: usize = 64;
+
+/// Ray time spots are found at: where the surface is when the shutter opens.
+const SPOT_TIME: f64 = 0.0;
+
+/// How copies are scattered; every field but `count` may be omitted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScatterSettings {
+    /// Copies to place; fewer if the density texture rejects most spots.
+    pub count: usize,
+    /// Seed for the spots and jitter; the same seed scatters the same way. Scene files
+    /// hold seeds up to `i64::MAX`.
+    #[serde(default)]
+    pub seed: u64,
+    /// Smallest and largest uniform scale of a copy.
+    #[serde(default = "default_scale")]
+    pub scale: [f32; 2],
+    /// Largest random turn, in degrees, of a copy about its up axis.
+    #[serde(default = "default_spin")]
+    pub spin: f32,
+    /// Largest random lean, in degrees, of a copy's up axis away from where it would
+    /// otherwise point.
+    #[serde(default)]
+    pub tilt: f32,
+    /// Whether a copy's `+y` axis follows the surface normal; `false` keeps it pointing
+    /// straight up, like trees on a hillside.
+    #[serde(default = "default_align")]
+    pub align: bool,
+}
+
+fn default_scale() -> [f32; 2] {
+    [1.0, 1.0]
+}
+
+fn default_spin() -> f32 {
+    360.0
+}
+
+fn default_align() -> bool {
+    true
+}
+
+impl ScatterSettings {
+    /// `count` copies at their natural size, turned at random about the surface normal.
+    pub fn new(count: usize) -> Self {
+        ScatterSettings {
+            count,
+            seed: 0,
+            scale: default_scale(),
+            spin: default_spin(),
+            tilt: 0.0,
+            align: default_align(),
+        }
+    }
+}
+
+/// Transforms (scale, rotation, then translation) placing each copy scattered over
+/// `surface`, with `density`'s average channel at a spot as the chance a copy lands
+/// there. `None` if `surface` can neither sample its area nor be dropped onto because
+/// it's unbounded.
+pub fn placements(
+    surface: &dyn hittable::Hittable,
+    settings: &ScatterSettings,
+    density: Option<&dyn texturable::Texturable>,
+) -> Option<Vec<Vec<transform::Transform>>> {
+    let mut rng = rng::hashed_rng(settings.seed);
+    let bbox = surface.bounding_box();
+    let by_area = surface.sample_surface(&mut rng, SPOT_TIME).is_some();
+    if !by_area && bbox.is_unbounded() {
+        return None;
+    }
+    let extent = vec::Vec3::new(bbox.x.length(), bbox.y.length(), bbox.z.length()).length();
+    // Far enough off the surface to hit it again from outside, near enough not to hit
+    // anything else.
+    let offset = if extent.is_finite() {
+        (extent * 1e-4).max(1e-5)
+    } else {
+        1e-3
+    };
+
+    let mut placed = Vec::with_capacity(settings.count);
+    for _ in 0..settings.count * ATTEMPTS_PER_COPY {
+        if placed.len() == settings.count {
+            break;
+        }
+        let spot = if by_area {
+            surface
+                .sample_surface(&mut rng, SPOT_TIME)
+                .and_then(|sample| {
+                    let normal = vec::unit_vector(&sample.normal);
+                    let above = sample.point + normal * offset;
+                    surface.hit(
+                        &ray::Ray::new(&above, &-normal, Some(SPOT_TIME)),
+                        0.0,
+                        2.0 * offset,
+                    )
+                })
+        } else {
+            let above = vec::Vec3::new(
+                rng.random_range(bbox.x.min..=bbox.x.max),
+                bbox.y.max + 1.0,
+                rng.random_range(bbox.z.min..=bbox.z.max),
+            );
+            let down = vec::Vec3::new(0.0, -1.0, 0.0);
+            surface.hit(
+                &ray::Ray::new(&above, &down, Some(SPOT_TIME)),
+                0.0,
+                f32::INFINITY,
+            )
+        };
+        let Some(spot) = spot else {
+            continue;
+        };
+        if let Some(density) = density {
+            let color = density.sample(&spot);
+            if rng.random::<f32>() >= (color.x + color.y + color.z) / 3.0 {
+                continue;
+            }
+        }
+
+        // Face the side the spot was found from.
+        let normal = vec::unit_vector(&spot.normal);
+        let normal = if normal.dot(&spot.ray.direction) > 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        let [min_scale, max_scale] = settings.scale;
+        let scale = min_scale + (max_scale - min_scale) * rng.random::<f32>();
+        placed.push(vec![
+            transform::Transform::Scale(vec::Vec3::new(scale, scale, scale)),
+            transform::Transform::Rotate(orientation(&mut rng, settings, &normal)),
+            transform::Transform::Translate(spot.point),
+        ]);
+    }
+    Some(placed)
+}
+
+/// A random rotation for a copy on a surface facing `normal`.
+fn orientation(
+    rng: &mut rng::SampleRng,
+    settings: &ScatterSettings,
+    normal: &vec::Vec3,
+) -> mat::Mat3 {
+    let up_axis = vec::Vec3::new(0.0, 1.0, 0.0);
+    let spin = mat::Mat3::rotation(&up_axis, settings.spin * rng.random::<f32>());
+    let lean_axis =
+        mat::Mat3::rotation(&up_axis, 360.0 * rng.random::<f32>()) * vec::Vec3::new(1.0, 0.0, 0.0);
+    let lean = mat::Mat3::rotation(&lean_axis, settings.tilt * rng.random::<f32>());
+    let up = if settings.align { *normal } else { up_axis };
+    rotation_between(&up_axis, &up) * lean * spin
+}
+
+/// The shortest rotation taking unit vector `from` to unit vector `to`.
+fn rotation_between(from: &vec::Vec3, to: &vec::Vec3) -> mat::Mat3 {
+    let axis = from.cross(to);
+    let cos = from.dot(to).clamp(-1.0, 1.0);
+    if axis.squared_length() < 1e-12 {
+        // Parallel or opposite; any perpendicular axis turns one into the other.
+        let perpendicular = if from.x.abs() < 0.9 {
+            vec::Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            vec::Vec3::new(0.0, 0.0, 1.0)
+        };
+        let axis = from.cross(&perpendicular).normalize();
+        return mat::Mat3::rotation(&axis, if cos > 0.0 { 0.0 } else { 180.0 });
+    }
+    mat::Mat3::rotation(&axis.normalize(), cos.acos().to_degrees())
+}
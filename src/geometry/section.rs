@@ -0,0 +1,164 @@
+//! Section planes for architectural cutaways.
+//!
+//! A [`Section`] wraps another geometry and removes everything on the far side of one or
+//! more planes, in world space, so a building can be sliced open to show its rooms. Where
+//! a plane cuts through a solid, the opening is closed with a cap: a flat face on the
+//! plane that hides the solid's hollow inside and reads as a cut surface. Caps are
+//! reported in [`CAP_SLOT`], which [`crate::materials::instance::MaterialInstance`] shades
+//! with its cap material if it has one, or the object's own material otherwise.
+//!
+//! Wrap a [`crate::geometry::instance::GeometryInstance`] to cut it after its transforms;
+//! the planes then stay put while the object moves through them.
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{bbox, ray};
+use crate::math::{onb, pdf, rng, vec};
+use crate::traits::hittable;
+
+/// Material slot reported by cap hits.
+pub const CAP_SLOT: usize = usize::MAX;
+
+/// A plane through `point` that cuts away the half-space its `normal` points into.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SectionPlane {
+    pub point: vec::Point3,
+    pub normal: vec::Vec3,
+}
+
+impl PartialEq for SectionPlane {
+    fn eq(&self, other: &Self) -> bool {
+        (0..3).all(|axis| {
+            self.point[axis] == other.point[axis] && self.normal[axis] == other.normal[axis]
+        })
+    }
+}
+
+impl SectionPlane {
+    /// Signed distance of `point` in front of the plane, in units of `normal`'s length.
+    fn distance(&self, point: &vec::Point3) -> f32 {
+        (*point - self.point).dot(&self.normal)
+    }
+}
+
+/// A geometry with everything in front of its section planes removed; see the module
+/// docs.
+///
+/// Light sampling is delegated to the wrapped geometry unchanged, like
+/// [`crate::geometry::cutout::Cutout`]: samples of the removed part simply miss.
+#[derive(Clone)]
+pub struct Section {
+    inner: Arc<dyn hittable::Hittable + Send + Sync>,
+    planes: Vec<SectionPlane>,
+}
+
+impl Section {
+    /// Cuts `inner` with `planes`; with no planes it's left whole.
+    pub fn new(
+        inner: Arc<dyn hittable::Hittable + Send + Sync>,
+        planes: Vec<SectionPlane>,
+    ) -> Self {
+        Section { inner, planes }
+    }
+
+    /// The wrapped geometry.
+    pub fn inner(&self) -> &Arc<dyn hittable::Hittable + Send + Sync> {
+        &self.inner
+    }
+
+    pub fn planes(&self) -> &[SectionPlane] {
+        &self.planes
+    }
+
+    /// The span of `ray` behind every plane, and the plane it crosses to enter that span
+    /// (`None` if it starts there). `None` if the ray never gets behind all of them.
+    fn kept_span(&self, ray: &ray::Ray) -> Option<(f32, f32, Option<&SectionPlane>)> {
+        let (mut start, mut end) = (f32::MIN, f32::MAX);
+        let mut entry = None;
+        for plane in &self.planes {
+            let rate = ray.direction.dot(&plane.normal);
+            let distance = plane.distance(&ray.origin);
+            if rate == 0.0 {
+                if distance > 0.0 {
+                    return None;
+                }
+                continue;
+            }
+            let crossing = -distance / rate;
+            if rate > 0.0 {
+                end = end.min(crossing);
+            } else if crossing > start {
+                start = crossing;
+                entry = Some(plane);
+            }
+        }
+        (start <= end).then_some((start, end, entry))
+    }
+
+    /// Whether `point` lies inside the wrapped solid: the surfaces next to it along
+    /// `direction`, ahead and behind, must both be seen from their inside. Open surfaces
+    /// like a lone quad have nothing on one side, so they never get caps.
+    fn is_inside(&self, point: &vec::Point3, direction: &vec::Vec3, time: f64) -> bool {
+        let faces_away = |direction: vec::Vec3| {
+            let probe = ray::Ray::new(point, &direction, Some(time));
+            self.inner
+                .hit(&probe, 0.0, f32::MAX)
+                .is_some_and(|hit| hit.normal.dot(&direction) > 0.0)
+        };
+        faces_away(*direction) && faces_away(-*direction)
+    }
+}
+
+impl hittable::Hittable for Section {
+    fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::Hit> {
+        let (start, end, entry) = self.kept_span(ray)?;
+        let (t_min, t_max) = (t_min.max(start), t_max.min(end));
+        if t_min > t_max {
+            return None;
+        }
+
+        if let Some(plane) = entry.filter(|_| start == t_min) {
+            let point = ray.point_at(start);
+            if self.is_inside(&point, &ray.direction, ray.time) {
+                let normal = vec::unit_vector(&plane.normal);
+                let frame = onb::ONB::build_from_w(&normal);
+                return Some(hittable::Hit {
+                    ray: *ray,
+                    t: start,
+                    point,
+                    normal,
+                    u: point.dot(&frame.u),
+                    v: point.dot(&frame.v),
+                    material_slot: CAP_SLOT,
+                    vertex_color: None,
+                    shading_normal: None,
+                    tangent: Some(frame.u),
+                });
+            }
+        }
+        self.inner.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> bbox::BBox {
+        self.inner.bounding_box()
+    }
+
+    fn get_pdf(&self, origin: &vec::Point3, time: f64) -> Box<dyn pdf::PDF + Send + Sync + '_> {
+        self.inner.get_pdf(origin, time)
+    }
+
+    /// Samples the whole wrapped surface, removed parts included, so sectioned emitters
+    /// are brighter in photon-mapped renders.
+    fn sample_surface(
+        &self,
+        rng: &mut rng::SampleRng,
+        time: f64,
+    ) -> Option<hittable::SurfaceSample> {
+        self.inner.sample_surface(rng, time)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,151 @@
+//! Reader for STL (stereolithography) files, ASCII and binary, as exported by CAD tools.
+//!
+//! STL stores unconnected triangles, each with a facet normal. Identical corner positions
+//! are welded into shared vertices, and the mesh derives its face normals from the
+//! winding; a triangle whose winding disagrees with its stored normal is flipped so the
+//! front face points where the file says.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::geometry::primitives::mesh::Mesh;
+use crate::math::vec;
+
+const HEADER_LEN: usize = 80;
+const FACET_LEN: usize = 50;
+
+/// Loads an STL file from disk.
+pub fn load(path: &Path) -> io::Result<Mesh> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Parses an STL file into a mesh, detecting the encoding from its contents.
+pub fn read<R: BufRead>(mut reader: R) -> io::Result<Mesh> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    let mut builder = Builder::default();
+    if is_binary(&bytes) {
+        read_binary(&bytes, &mut builder)?;
+    } else {
+        let text = std::str::from_utf8(&bytes)
+            .map_err(|_| invalid("STL file is neither binary nor ASCII".to_string()))?;
+        read_ascii(text, &mut builder)?;
+    }
+    Ok(Mesh::new(builder.vertices, builder.triangles))
+}
+
+/// Binary files may also begin with `solid`, so the size implied by the facet count
+/// decides.
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < HEADER_LEN + 4 {
+        return false;
+    }
+    let count = u32::from_le_bytes(bytes[HEADER_LEN..HEADER_LEN + 4].try_into().unwrap());
+    let expected = HEADER_LEN + 4 + count as usize * FACET_LEN;
+    expected == bytes.len() || !bytes.trim_ascii_start().starts_with(b"solid")
+}
+
+fn read_binary(bytes: &[u8], builder: &mut Builder) -> io::Result<()> {
+    let count = u32::from_le_bytes(bytes[HEADER_LEN..HEADER_LEN + 4].try_into().unwrap());
+    let body = &bytes[HEADER_LEN + 4..];
+    if body.len() < count as usize * FACET_LEN {
+        return Err(invalid(format!(
+            "STL header promises {} triangles but the file holds {}",
+            count,
+            body.len() / FACET_LEN
+        )));
+    }
+
+    let read_vec = |facet: &[u8], offset: usize| {
+        let float = |index: usize| {
+            let start = offset + index * 4;
+            f32::from_le_bytes(facet[start..start + 4].try_into().unwrap())
+        };
+        vec::Vec3::new(float(0), float(1), float(2))
+    };
+    // Each facet: normal, three corners, and a 2-byte attribute count that's ignored.
+    for facet in body.chunks_exact(FACET_LEN).take(count as usize) {
+        builder.push(
+            read_vec(facet, 0),
+            [
+                read_vec(facet, 12),
+                read_vec(facet, 24),
+                read_vec(facet, 36),
+            ],
+        );
+    }
+    Ok(())
+}
+
+fn read_ascii(text: &str, builder: &mut Builder) -> io::Result<()> {
+    let mut tokens = text.split_whitespace();
+    let next_vec = |tokens: &mut std::str::SplitWhitespace<'_>| -> io::Result<vec::Vec3> {
+        let mut component = || {
+            let token = tokens
+                .next()
+                .ok_or_else(|| invalid("unexpected end of STL data".to_string()))?;
+            token
+                .parse::<f32>()
+                .map_err(|_| invalid(format!("invalid STL number `{}`", token)))
+        };
+        Ok(vec::Vec3::new(component()?, component()?, component()?))
+    };
+
+    let mut normal = vec::Vec3::default();
+    let mut corners = Vec::with_capacity(3);
+    while let Some(token) = tokens.next() {
+        match token {
+            "normal" => normal = next_vec(&mut tokens)?,
+            "vertex" => corners.push(next_vec(&mut tokens)?),
+            "endfacet" => {
+                let [a, b, c] = corners[..] else {
+                    return Err(invalid(format!(
+                        "STL facet has {} vertices instead of 3",
+                        corners.len()
+                    )));
+                };
+                builder.push(normal, [a, b, c]);
+                corners.clear();
+            }
+            // `solid <name>`, `facet`, `outer loop`, `endloop`, `endsolid <name>`.
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Collects triangles while welding corners at identical positions.
+#[derive(Default)]
+struct Builder {
+    vertices: Vec<vec::Point3>,
+    triangles: Vec<[u32; 3]>,
+    indices: HashMap<[u32; 3], u32>,
+}
+
+impl Builder {
+    fn push(&mut self, normal: vec::Vec3, [a, b, c]: [vec::Point3; 3]) {
+        let winding = (b - a).cross(&(c - a));
+        let corners = if winding.dot(&normal) < 0.0 {
+            [a, c, b]
+        } else {
+            [a, b, c]
+        };
+        let triangle = corners.map(|corner| self.index(corner));
+        self.triangles.push(triangle);
+    }
+
+    fn index(&mut self, point: vec::Point3) -> u32 {
+        // Adding 0.0 folds -0.0 into 0.0 so both weld together.
+        let key = [point.x + 0.0, point.y + 0.0, point.z + 0.0].map(f32::to_bits);
+        *self.indices.entry(key).or_insert_with(|| {
+            self.vertices.push(point);
+            (self.vertices.len() - 1) as u32
+        })
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
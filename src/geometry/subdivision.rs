@@ -0,0 +1,395 @@
+//! Subdivision surfaces for polygon meshes.
+//!
+//! Each level splits every face and moves the vertices toward the limit surface, so a
+//! coarse control mesh renders as a smooth surface without exporting dense geometry.
+//! Open boundaries follow the boundary curve rules of each scheme and stay in place as
+//! creases; vertices on non-manifold edges keep their positions.
+//!
+//! Every new vertex is a weighted average of vertices from the previous level. The
+//! weights depend only on connectivity, so positions and per-vertex colors and normals are
+//! refined with the same stencils.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::math::vec;
+
+/// Which refinement rules to apply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubdivisionScheme {
+    /// Loop subdivision: each triangle splits into four. Suited to triangle meshes such
+    /// as scans.
+    Loop,
+    /// Catmull-Clark subdivision: each face splits into quads around a face point, which
+    /// are split into triangles for rendering. Suited to quad-dominant models, whose
+    /// quads it keeps from the source file.
+    CatmullClark,
+}
+
+/// A subdivision scheme and how many times to apply it; every level multiplies the face
+/// count by four (Loop, on triangles) or by each face's corner count (Catmull-Clark,
+/// which turns every face into quads).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subdivision {
+    pub scheme: SubdivisionScheme,
+    pub levels: u32,
+}
+
+/// New vertices as weighted sums of the previous level's vertices.
+type Stencils = Vec<Vec<(u32, f32)>>;
+
+/// Subdivides a polygon mesh, returning its new vertices, faces, per-vertex colors (empty
+/// if `colors` is), and per-vertex normals (empty if `normals` is; averaged, not
+/// renormalized). Winding is preserved.
+///
+/// Catmull-Clark refines `faces` as given and returns quads; Loop fan-triangulates them
+/// first and returns triangles.
+pub fn subdivide(
+    vertices: &[vec::Point3],
+    faces: &[Vec<u32>],
+    colors: &[vec::Vec3],
+    normals: &[vec::Vec3],
+    subdivision: Subdivision,
+) -> (
+    Vec<vec::Point3>,
+    Vec<Vec<u32>>,
+    Vec<vec::Vec3>,
+    Vec<vec::Vec3>,
+) {
+    let mut vertices = vertices.to_vec();
+    let mut colors = colors.to_vec();
+    let mut normals = normals.to_vec();
+    let mut faces = match subdivision.scheme {
+        SubdivisionScheme::Loop if subdivision.levels > 0 => triangulate(faces)
+            .into_iter()
+            .map(|triangle| triangle.to_vec())
+            .collect(),
+        _ => faces.to_vec(),
+    };
+
+    for _ in 0..subdivision.levels {
+        let (stencils, next_faces) = match subdivision.scheme {
+            SubdivisionScheme::Loop => loop_level(vertices.len(), &faces),
+            SubdivisionScheme::CatmullClark => catmull_clark_level(vertices.len(), &faces),
+        };
+        vertices = apply(&stencils, &vertices);
+        if !colors.is_empty() {
+            colors = apply(&stencils, &colors);
+        }
+        if !normals.is_empty() {
+            normals = apply(&stencils, &normals);
+        }
+        faces = next_faces;
+    }
+    (vertices, faces, colors, normals)
+}
+
+/// Fan-triangulates polygons around their first corner; faces with fewer than three
+/// corners are dropped.
+pub fn triangulate(faces: &[Vec<u32>]) -> Vec<[u32; 3]> {
+    faces
+        .iter()
+        .flat_map(|face| {
+            (1..face.len().saturating_sub(1)).map(move |i| [face[0], face[i], face[i + 1]])
+        })
+        .collect()
+}
+
+fn apply(stencils: &Stencils, values: &[vec::Vec3]) -> Vec<vec::Vec3> {
+    stencils
+        .iter()
+        .map(|stencil| {
+            stencil
+                .iter()
+                .fold(vec::Vec3::default(), |sum, (index, weight)| {
+                    sum + values[*index as usize] * *weight
+                })
+        })
+        .collect()
+}
+
+/// Connectivity of a polygon mesh: the faces on each side of every edge and the edges
+/// around every vertex.
+struct Topology {
+    /// Faces using each undirected edge, keyed by `(low, high)` vertex index.
+    edge_faces: HashMap<(u32, u32), Vec<usize>>,
+    /// Neighbors of each vertex along edges, in no particular order.
+    neighbors: Vec<Vec<u32>>,
+}
+
+impl Topology {
+    fn new(vertex_count: usize, faces: &[Vec<u32>]) -> Self {
+        let mut edge_faces: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+        let mut neighbors = vec![Vec::new(); vertex_count];
+        for (face_index, face) in faces.iter().enumerate() {
+            for (a, b) in face_edges(face) {
+                let faces_on_edge = edge_faces.entry(edge_key(a, b)).or_default();
+                if faces_on_edge.is_empty() {
+                    neighbors[a as usize].push(b);
+                    neighbors[b as usize].push(a);
+                }
+                faces_on_edge.push(face_index);
+            }
+        }
+        Topology {
+            edge_faces,
+            neighbors,
+        }
+    }
+
+    fn faces_on(&self, a: u32, b: u32) -> &[usize] {
+        &self.edge_faces[&edge_key(a, b)]
+    }
+
+    /// Neighbors of `vertex` across boundary edges, if it's a regular boundary vertex
+    /// (exactly two of them); `None` for interior vertices.
+    fn boundary_neighbors(&self, vertex: u32) -> Option<Vec<u32>> {
+        let boundary: Vec<u32> = self.neighbors[vertex as usize]
+            .iter()
+            .copied()
+            .filter(|neighbor| self.faces_on(vertex, *neighbor).len() == 1)
+            .collect();
+        (!boundary.is_empty()).then_some(boundary)
+    }
+
+    /// Whether any edge at `vertex` is shared by more than two faces.
+    fn non_manifold(&self, vertex: u32) -> bool {
+        self.neighbors[vertex as usize]
+            .iter()
+            .any(|neighbor| self.faces_on(vertex, *neighbor).len() > 2)
+    }
+}
+
+fn edge_key(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+fn face_edges(face: &[u32]) -> impl Iterator<Item = (u32, u32)> + '_ {
+    (0..face.len()).map(move |i| (face[i], face[(i + 1) % face.len()]))
+}
+
+/// Vertex-point stencil shared by both schemes for boundary and non-manifold vertices,
+/// or `None` for interior vertices.
+fn boundary_vertex(topology: &Topology, vertex: u32) -> Option<Vec<(u32, f32)>> {
+    if topology.non_manifold(vertex) {
+        return Some(vec![(vertex, 1.0)]);
+    }
+    let boundary = topology.boundary_neighbors(vertex)?;
+    Some(match boundary.as_slice() {
+        [a, b] => vec![(vertex, 0.75), (*a, 0.125), (*b, 0.125)],
+        // A corner where boundaries meet isn't a smooth curve; keep it sharp.
+        _ => vec![(vertex, 1.0)],
+    })
+}
+
+/// One level of Loop subdivision. Every face must be a triangle.
+fn loop_level(vertex_count: usize, faces: &[Vec<u32>]) -> (Stencils, Vec<Vec<u32>>) {
+    let topology = Topology::new(vertex_count, faces);
+
+    let mut stencils: Stencils = (0..vertex_count as u32)
+        .map(|vertex| {
+            if let Some(stencil) = boundary_vertex(&topology, vertex) {
+                return stencil;
+            }
+            let neighbors = &topology.neighbors[vertex as usize];
+            let n = neighbors.len();
+            if n == 0 {
+                return vec![(vertex, 1.0)];
+            }
+            let beta = if n == 3 {
+                3.0 / 16.0
+            } else {
+                3.0 / (8.0 * n as f32)
+            };
+            std::iter::once((vertex, 1.0 - n as f32 * beta))
+                .chain(neighbors.iter().map(|neighbor| (*neighbor, beta)))
+                .collect()
+        })
+        .collect();
+
+    let mut edge_points: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut edge_point = |a: u32, b: u32, stencils: &mut Stencils| {
+        *edge_points.entry(edge_key(a, b)).or_insert_with(|| {
+            let faces_on_edge = topology.faces_on(a, b);
+            let stencil = if let [left, right] = faces_on_edge {
+                let opposite = |face: usize| {
+                    faces[face]
+                        .iter()
+                        .copied()
+                        .find(|vertex| *vertex != a && *vertex != b)
+                        .unwrap_or(a)
+                };
+                vec![
+                    (a, 0.375),
+                    (b, 0.375),
+                    (opposite(*left), 0.125),
+                    (opposite(*right), 0.125),
+                ]
+            } else {
+                vec![(a, 0.5), (b, 0.5)]
+            };
+            stencils.push(stencil);
+            (stencils.len() - 1) as u32
+        })
+    };
+
+    let mut next_faces = Vec::with_capacity(faces.len() * 4);
+    for face in faces {
+        let [a, b, c] = [face[0], face[1], face[2]];
+        let ab = edge_point(a, b, &mut stencils);
+        let bc = edge_point(b, c, &mut stencils);
+        let ca = edge_point(c, a, &mut stencils);
+        next_faces.extend([
+            vec![a, ab, ca],
+            vec![ab, b, bc],
+            vec![ca, bc, c],
+            vec![ab, bc, ca],
+        ]);
+    }
+    (stencils, next_faces)
+}
+
+/// One level of Catmull-Clark subdivision, for faces of any size.
+fn catmull_clark_level(vertex_count: usize, faces: &[Vec<u32>]) -> (Stencils, Vec<Vec<u32>>) {
+    let topology = Topology::new(vertex_count, faces);
+    let face_stencil = |face: usize| -> Vec<(u32, f32)> {
+        let weight = 1.0 / faces[face].len() as f32;
+        faces[face].iter().map(|vertex| (*vertex, weight)).collect()
+    };
+
+    // Faces around each vertex, for the vertex rule.
+    let mut vertex_faces = vec![Vec::new(); vertex_count];
+    for (face_index, face) in faces.iter().enumerate() {
+        for vertex in face {
+            vertex_faces[*vertex as usize].push(face_index);
+        }
+    }
+
+    // Interior vertex: (F + 2R + (n - 3)P) / n, with F the average face point and R the
+    // average edge midpoint around it.
+    let mut stencils: Stencils = (0..vertex_count as u32)
+        .map(|vertex| {
+            if let Some(stencil) = boundary_vertex(&topology, vertex) {
+                return stencil;
+            }
+            let neighbors = &topology.neighbors[vertex as usize];
+            let around = &vertex_faces[vertex as usize];
+            let n = neighbors.len() as f32;
+            if neighbors.is_empty() || around.is_empty() {
+                return vec![(vertex, 1.0)];
+            }
+            let mut stencil = vec![(vertex, (n - 3.0) / n + 1.0 / n)];
+            for face in around {
+                for (corner, weight) in face_stencil(*face) {
+                    stencil.push((corner, weight / (around.len() as f32 * n)));
+                }
+            }
+            for neighbor in neighbors {
+                stencil.push((*neighbor, 1.0 / (n * n)));
+            }
+            stencil
+        })
+        .collect();
+
+    let face_points: Vec<u32> = (0..faces.len())
+        .map(|face| {
+            stencils.push(face_stencil(face));
+            (stencils.len() - 1) as u32
+        })
+        .collect();
+
+    let mut edge_points: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut edge_point = |a: u32, b: u32, stencils: &mut Stencils| {
+        *edge_points.entry(edge_key(a, b)).or_insert_with(|| {
+            let stencil = if let [left, right] = topology.faces_on(a, b) {
+                let mut stencil = vec![(a, 0.25), (b, 0.25)];
+                for face in [*left, *right] {
+                    for (corner, weight) in face_stencil(face) {
+                        stencil.push((corner, weight * 0.25));
+                    }
+                }
+                stencil
+            } else {
+                vec![(a, 0.5), (b, 0.5)]
+            };
+            stencils.push(stencil);
+            (stencils.len() - 1) as u32
+        })
+    };
+
+    let mut next_faces = Vec::new();
+    for (face_index, face) in faces.iter().enumerate() {
+        let edges: Vec<u32> = face_edges(face)
+            .map(|(a, b)| edge_point(a, b, &mut stencils))
+            .collect();
+        for i in 0..face.len() {
+            let previous = edges[(i + face.len() - 1) % face.len()];
+            next_faces.push(vec![face[i], edges[i], face_points[face_index], previous]);
+        }
+    }
+    (stencils, next_faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{obj, primitives::mesh::Mesh};
+
+    /// A unit cube centered on the origin, as six outward-wound quads.
+    const CUBE: &str = "\
+v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1\nv -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1
+f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8\n";
+
+    fn cube() -> Mesh {
+        obj::read(CUBE.as_bytes()).unwrap()
+    }
+
+    fn levels(scheme: SubdivisionScheme, levels: u32) -> Subdivision {
+        Subdivision { scheme, levels }
+    }
+
+    /// Largest over smallest distance of a vertex from the origin; 1 for a sphere.
+    fn radius_ratio(mesh: &Mesh) -> f32 {
+        let radii = mesh.vertices().iter().map(|vertex| vertex.length());
+        radii.clone().fold(0.0, f32::max) / radii.fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn loop_quadruples_triangles_per_level() {
+        let cube = cube();
+        assert_eq!(cube.triangles().len(), 12);
+        for level in 1..=3 {
+            let refined = cube
+                .clone()
+                .with_subdivision(levels(SubdivisionScheme::Loop, level));
+            assert_eq!(refined.triangles().len(), 12 * 4usize.pow(level));
+            assert!(refined.is_closed());
+        }
+    }
+
+    #[test]
+    fn catmull_clark_refines_source_quads() {
+        let refined = cube().with_subdivision(levels(SubdivisionScheme::CatmullClark, 1));
+        // Refining the quads, not their triangles, gives 24 quads and 26 vertices.
+        assert_eq!(refined.faces().len(), 24);
+        assert!(refined.faces().iter().all(|face| face.len() == 4));
+        assert_eq!(refined.vertices().len(), 26);
+        assert_eq!(refined.triangles().len(), 48);
+    }
+
+    #[test]
+    fn subdivided_cube_approaches_a_sphere() {
+        // The cube's corners all sit on one sphere, so start from the first refinement,
+        // whose face points sit much closer in than its corners.
+        let mut previous = f32::MAX;
+        for level in 1..=3 {
+            let refined = cube().with_subdivision(levels(SubdivisionScheme::CatmullClark, level));
+            assert_eq!(refined.faces().len(), 6 * 4usize.pow(level));
+            let ratio = radius_ratio(&refined);
+            assert!(ratio < previous, "level {level}: {ratio} !< {previous}");
+            previous = ratio;
+        }
+        // The limit surface isn't exactly round; it stays within a few percent.
+        assert!(previous < 1.05, "{previous}");
+    }
+}
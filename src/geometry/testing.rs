@@ -0,0 +1,524 @@
+//! Intersection checks for primitives against an analytic ground truth.
+//!
+//! A primitive, or a faster path through one such as a SIMD packet tracer, is checked by
+//! firing rays at it and comparing every answer with a [`Reference`]: the analytic shapes
+//! here ([`SphereReference`], [`QuadReference`], [`BoxReference`]), worked out in `f64`
+//! independently of the primitives' own code, or the scalar implementation itself
+//! ([`ScalarReference`]). [`ray_grid`] sweeps a dense grid of parallel rays across the
+//! shape from every axis and diagonal direction, and [`random_rays`] draws seeded random
+//! rays: from outside, from inside, aimed at points on the surface, and with directions
+//! of any length.
+//!
+//! Some rays have no answer floating point can settle: they graze a silhouette or an
+//! edge, run nearly parallel to a face, or cross the surface right at an end of the ray
+//! interval. References report how close each ray comes to such a case, and rays within
+//! the tolerance are skipped instead of counted as disagreements.
+use rand::Rng;
+
+use crate::core::{bbox, ray};
+use crate::math::{onb, rng, vec};
+use crate::traits::hittable;
+
+/// Start of the ray interval every check traces, as scene rays use.
+pub const T_MIN: f32 = 0.001;
+
+/// End of the ray interval every check traces.
+pub const T_MAX: f32 = f32::MAX;
+
+/// Planar crossings where the ray direction's component along the unit plane normal is
+/// below this are too close to parallel to call; quads count such rays as misses.
+const GRAZING: f64 = 1e-5;
+
+/// Mismatches listed when [`assert_agrees`] fails.
+const REPORTED_MISMATCHES: usize = 5;
+
+/// Largest fraction of rays [`assert_agrees`] lets the references skip before it fails
+/// for lack of coverage.
+const MAX_SKIPPED: f32 = 0.1;
+
+/// The closest intersection of a ray with a surface.
+#[derive(Clone, Copy, Debug)]
+pub struct Expected {
+    pub t: f32,
+    /// Unit geometric normal, oriented the way the primitive orients it (outward for
+    /// closed shapes, along `u x v` for quads) regardless of the side the ray comes from.
+    pub normal: vec::Vec3,
+}
+
+/// A reference's answer for one ray.
+#[derive(Clone, Copy, Debug)]
+pub struct Truth {
+    pub hit: Option<Expected>,
+    /// How far the ray is, in world units, from a case floating point can't settle: a
+    /// silhouette, an edge, a face it nearly runs along, or a crossing at an end of the
+    /// ray interval.
+    pub margin: f32,
+}
+
+/// Ground truth a primitive is checked against.
+pub trait Reference {
+    fn intersect(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Truth;
+}
+
+/// How closely a primitive must agree with its reference.
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance {
+    /// Rays closer than this to an unsettled case (see [`Truth::margin`]) are skipped.
+    pub margin: f32,
+    /// Largest allowed distance of the actual hit point off the expected surface,
+    /// relative to one plus the size of the coordinates there.
+    pub distance: f32,
+    /// Largest allowed `1 - cos` of the angle between the expected and actual normals.
+    pub normal: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            margin: 1e-4,
+            distance: 1e-4,
+            normal: 1e-4,
+        }
+    }
+}
+
+/// One ray a primitive and its reference disagree on.
+#[derive(Clone, Copy, Debug)]
+pub struct Mismatch {
+    pub ray: ray::Ray,
+    pub expected: Option<Expected>,
+    pub actual: Option<Expected>,
+    /// What's wrong: `missed`, `spurious hit`, `hit point off`, `normal off`, or
+    /// `outside bounding box` (a hit the scene BVH would cull).
+    pub reason: &'static str,
+}
+
+/// Outcome of [`check`].
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    /// Rays traced.
+    pub rays: usize,
+    /// Rays the reference couldn't settle, which weren't compared.
+    pub skipped: usize,
+    /// Rays that hit the primitive, of those compared.
+    pub hits: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Traces `rays` against `hittable` over [`T_MIN`]..[`T_MAX`] and compares every hit, or
+/// miss, with `reference`.
+pub fn check(
+    hittable: &dyn hittable::Hittable,
+    reference: &dyn Reference,
+    rays: &[ray::Ray],
+    tolerance: &Tolerance,
+) -> Report {
+    let mut report = Report {
+        rays: rays.len(),
+        ..Report::default()
+    };
+    for ray in rays {
+        let truth = reference.intersect(ray, T_MIN, T_MAX);
+        if truth.margin < tolerance.margin {
+            report.skipped += 1;
+            continue;
+        }
+        let actual = hittable.hit(ray, T_MIN, T_MAX).map(|hit| Expected {
+            t: hit.t,
+            normal: vec::unit_vector(&hit.normal),
+        });
+        let reason = match (truth.hit, actual) {
+            (None, None) => None,
+            (Some(_), None) => Some("missed"),
+            (None, Some(_)) => Some("spurious hit"),
+            (Some(expected), Some(actual)) => {
+                report.hits += 1;
+                let point = ray.point_at(expected.t);
+                let size = point.x.abs().max(point.y.abs()).max(point.z.abs());
+                let slack = tolerance.distance * (1.0 + size);
+                // Measured off the surface, along the normal: at grazing angles a hit
+                // can slide a long way along the ray while staying on the surface, and
+                // sliding along the surface turns the normal instead.
+                let offset = (actual.t - expected.t) * ray.direction.dot(&expected.normal);
+                let bounds = hittable.bounding_box();
+                let actual_point = ray.point_at(actual.t);
+                if offset.abs() > slack {
+                    Some("hit point off")
+                } else if 1.0 - actual.normal.dot(&expected.normal) > tolerance.normal {
+                    Some("normal off")
+                } else if !(0..3).all(|axis| {
+                    let range = bounds.axis(axis);
+                    actual_point[axis] >= range.min - slack
+                        && actual_point[axis] <= range.max + slack
+                }) {
+                    Some("outside bounding box")
+                } else {
+                    None
+                }
+            }
+        };
+        if let Some(reason) = reason {
+            report.mismatches.push(Mismatch {
+                ray: *ray,
+                expected: truth.hit,
+                actual,
+                reason,
+            });
+        }
+    }
+    report
+}
+
+/// Like [`check`], panicking with the first few mismatches if there are any, or if the
+/// reference skipped more than a tenth of the rays.
+pub fn assert_agrees(
+    hittable: &dyn hittable::Hittable,
+    reference: &dyn Reference,
+    rays: &[ray::Ray],
+    tolerance: &Tolerance,
+) -> Report {
+    let report = check(hittable, reference, rays, tolerance);
+    if !report.passed() {
+        let listed: Vec<String> = report
+            .mismatches
+            .iter()
+            .take(REPORTED_MISMATCHES)
+            .map(|mismatch| format!("{:?}", mismatch))
+            .collect();
+        panic!(
+            "{} of {} rays disagree with the reference ({} skipped), e.g.:\n{}",
+            report.mismatches.len(),
+            report.rays,
+            report.skipped,
+            listed.join("\n")
+        );
+    }
+    if report.skipped as f32 > MAX_SKIPPED * report.rays as f32 {
+        panic!(
+            "the reference skipped {} of {} rays as too close to call",
+            report.skipped, report.rays
+        );
+    }
+    report
+}
+
+/// Parallel rays sweeping `resolution` by `resolution` grids across the bounds of
+/// `hittable`, from each of the 6 axis directions and the 8 diagonals.
+pub fn ray_grid(hittable: &dyn hittable::Hittable, resolution: u32) -> Vec<ray::Ray> {
+    let (center, radius) = bounding_sphere(&hittable.bounding_box());
+    let resolution = resolution.max(1);
+    let mut directions = Vec::with_capacity(14);
+    for axis in 0..3 {
+        for sign in [1.0, -1.0] {
+            let mut direction = vec::Vec3::new(0.0, 0.0, 0.0);
+            direction[axis] = sign;
+            directions.push(direction);
+        }
+    }
+    for index in 0..8 {
+        let sign = |bit: u32| if index & (1 << bit) == 0 { 1.0 } else { -1.0 };
+        directions.push(vec::unit_vector(&vec::Vec3::new(sign(0), sign(1), sign(2))));
+    }
+
+    let mut rays = Vec::with_capacity(directions.len() * (resolution * resolution) as usize);
+    for direction in directions {
+        // Start well outside the bounds, on a square that covers them from this side.
+        let frame = onb::ONB::build_from_w(&direction);
+        let start = center - direction * (2.0 * radius);
+        for i in 0..resolution {
+            for j in 0..resolution {
+                let a = ((i as f32 + 0.5) / resolution as f32 * 2.0 - 1.0) * radius;
+                let b = ((j as f32 + 0.5) / resolution as f32 * 2.0 - 1.0) * radius;
+                let origin = start + frame.u * a + frame.v * b;
+                rays.push(ray::Ray::new(&origin, &direction, None));
+            }
+        }
+    }
+    rays
+}
+
+/// `count` random rays at `hittable`: two fifths from outside its bounds toward points
+/// inside them, two fifths from anywhere around it toward points on its surface (when
+/// it can draw them), and the rest from inside its bounds in any direction. Directions
+/// are scaled to random lengths, since hits are reported in units of the direction.
+pub fn random_rays(
+    rng: &mut rng::SampleRng,
+    hittable: &dyn hittable::Hittable,
+    count: usize,
+) -> Vec<ray::Ray> {
+    let bounds = hittable.bounding_box();
+    let (center, radius) = bounding_sphere(&bounds);
+    let inside = |rng: &mut rng::SampleRng| {
+        vec::Vec3::new(
+            rng.random_range(bounds.x.min..=bounds.x.max),
+            rng.random_range(bounds.y.min..=bounds.y.max),
+            rng.random_range(bounds.z.min..=bounds.z.max),
+        )
+    };
+    let around = |rng: &mut rng::SampleRng, distance: f32| {
+        center + vec::unit_vector(&vec::random_in_unit_sphere(rng)) * distance
+    };
+
+    (0..count)
+        .map(|_| {
+            let pick = rng.random::<f32>();
+            let (origin, target) = if pick < 0.4 {
+                (around(rng, 2.0 * radius), inside(rng))
+            } else if pick < 0.8 {
+                let distance = rng.random_range(0.5..3.0) * radius;
+                let origin = around(rng, distance);
+                match hittable.sample_surface(rng, 0.0) {
+                    Some(sample) => (origin, sample.point),
+                    None => (origin, inside(rng)),
+                }
+            } else {
+                let origin = inside(rng);
+                (origin, origin + vec::random_in_unit_sphere(rng))
+            };
+            let direction =
+                vec::unit_vector(&(target - origin)) * 10.0_f32.powf(rng.random_range(-1.0..1.0));
+            ray::Ray::new(&origin, &direction, None)
+        })
+        .collect()
+}
+
+/// Center and radius of a sphere around `bounds`, padded so rays at the edge of a grid
+/// still clear them.
+fn bounding_sphere(bounds: &bbox::BBox) -> (vec::Point3, f32) {
+    let min = vec::Vec3::new(bounds.x.min, bounds.y.min, bounds.z.min);
+    let max = vec::Vec3::new(bounds.x.max, bounds.y.max, bounds.z.max);
+    (
+        (min + max) * 0.5,
+        0.55 * (max - min).length() + f32::EPSILON,
+    )
+}
+
+/// A sphere, matching [`crate::geometry::primitives::sphere::Sphere`]: a negative
+/// radius turns the normals inward.
+#[derive(Clone, Copy, Debug)]
+pub struct SphereReference {
+    pub center: vec::Point3,
+    pub radius: f32,
+}
+
+impl Reference for SphereReference {
+    fn intersect(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Truth {
+        let (origin, direction) = (wide(&ray.origin), wide(&ray.direction));
+        let center = wide(&self.center);
+        let radius = self.radius as f64;
+        let oc = sub(origin, center);
+        let a = dot(direction, direction);
+        let b = dot(oc, direction);
+        let c = dot(oc, oc) - radius * radius;
+        // Distance from the ray's line to the center, against the radius.
+        let line_distance = (dot(oc, oc) - b * b / a).max(0.0).sqrt();
+        let mut margin = (line_distance - radius.abs()).abs();
+
+        let discriminant = b * b - a * c;
+        let mut hit = None;
+        if discriminant > 0.0 {
+            let root = discriminant.sqrt();
+            for t in [(-b - root) / a, (-b + root) / a] {
+                margin = margin.min(range_margin(t, a.sqrt(), t_min, t_max));
+                if hit.is_none() && t > t_min as f64 && t < t_max as f64 {
+                    let point = add(origin, scale(direction, t));
+                    hit = Some(Expected {
+                        t: t as f32,
+                        normal: narrow(scale(sub(point, center), 1.0 / radius)),
+                    });
+                }
+            }
+        }
+        Truth {
+            hit,
+            margin: margin as f32,
+        }
+    }
+}
+
+/// A two-sided parallelogram from `q` spanning `u` and `v`, matching
+/// [`crate::geometry::primitives::quad::Quad`] without `single_sided`.
+#[derive(Clone, Copy, Debug)]
+pub struct QuadReference {
+    pub q: vec::Point3,
+    pub u: vec::Vec3,
+    pub v: vec::Vec3,
+}
+
+impl Reference for QuadReference {
+    fn intersect(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Truth {
+        let (hit, margin) = parallelogram(
+            ray,
+            wide(&self.q),
+            wide(&self.u),
+            wide(&self.v),
+            t_min,
+            t_max,
+        );
+        Truth {
+            hit: hit.map(|(t, normal)| Expected {
+                t: t as f32,
+                normal: narrow(normal),
+            }),
+            margin: margin as f32,
+        }
+    }
+}
+
+/// A solid axis-aligned box, matching [`crate::geometry::primitives::cube::Cube`].
+#[derive(Clone, Copy, Debug)]
+pub struct BoxReference {
+    pub min: vec::Point3,
+    pub max: vec::Point3,
+}
+
+impl Reference for BoxReference {
+    fn intersect(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Truth {
+        let (min, max) = (wide(&self.min), wide(&self.max));
+        let mut closest: Option<(f64, [f64; 3])> = None;
+        let mut margin = f64::INFINITY;
+        for axis in 0..3 {
+            let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+            for (corner, outward) in [(min, -1.0), (max, 1.0)] {
+                // Order the face's sides so `u x v` points out of the box.
+                let mut q = min;
+                q[axis] = corner[axis];
+                let mut u = [0.0; 3];
+                u[a] = max[a] - min[a];
+                let mut v = [0.0; 3];
+                v[b] = max[b] - min[b];
+                let (u, v) = if outward > 0.0 { (u, v) } else { (v, u) };
+                let (hit, face_margin) = parallelogram(ray, q, u, v, t_min, t_max);
+                margin = margin.min(face_margin);
+                if let Some((t, normal)) =
+                    hit.filter(|(t, _)| closest.is_none_or(|(closest_t, _)| *t < closest_t))
+                {
+                    closest = Some((t, normal));
+                }
+            }
+        }
+        Truth {
+            hit: closest.map(|(t, normal)| Expected {
+                t: t as f32,
+                normal: narrow(normal),
+            }),
+            margin: margin as f32,
+        }
+    }
+}
+
+/// The scalar implementation of a primitive, as the reference for a faster path through
+/// the same shape. Every ray is compared except ones crossing the surface at an end of
+/// the ray interval, so the two must agree at edges and silhouettes too.
+pub struct ScalarReference<'a>(pub &'a dyn hittable::Hittable);
+
+impl Reference for ScalarReference<'_> {
+    fn intersect(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Truth {
+        // Widen the interval a little so crossings near its ends are seen and skipped.
+        let length = ray.direction.length() as f64;
+        let slack = 1e-3 / length.max(f64::MIN_POSITIVE);
+        let hit = self
+            .0
+            .hit(ray, (t_min as f64 - slack) as f32, t_max)
+            .map(|hit| Expected {
+                t: hit.t,
+                normal: vec::unit_vector(&hit.normal),
+            });
+        let margin = hit.map_or(f64::INFINITY, |hit| {
+            range_margin(hit.t as f64, length, t_min, t_max)
+        });
+        Truth {
+            hit: hit.filter(|hit| hit.t > t_min && hit.t < t_max),
+            margin: margin as f32,
+        }
+    }
+}
+
+/// The crossing of a ray with the two-sided parallelogram from `q` spanning `u` and
+/// `v`, as `(t, unit normal along u x v)`, and the ray's margin (see
+/// [`Truth::margin`]).
+fn parallelogram(
+    ray: &ray::Ray,
+    q: [f64; 3],
+    u: [f64; 3],
+    v: [f64; 3],
+    t_min: f32,
+    t_max: f32,
+) -> (Option<(f64, [f64; 3])>, f64) {
+    let (origin, direction) = (wide(&ray.origin), wide(&ray.direction));
+    let normal = cross(u, v);
+    let area = dot(normal, normal).sqrt();
+    let unit = scale(normal, 1.0 / area);
+    let length = dot(direction, direction).sqrt();
+    let height = dot(unit, sub(origin, q));
+    let along = dot(unit, direction);
+    if along == 0.0 {
+        // Parallel to the plane: only a ray in the plane itself is in doubt.
+        return (None, height.abs());
+    }
+
+    let t = -height / along;
+    let pq = sub(add(origin, scale(direction, t)), q);
+    let alpha = dot(cross(pq, v), normal) / (area * area);
+    let beta = dot(cross(u, pq), normal) / (area * area);
+    // Distances to the edges: the parallelogram is `area / |v|` across between its
+    // `v` sides and `area / |u|` across between its `u` sides.
+    let across_v = area / dot(v, v).sqrt();
+    let across_u = area / dot(u, u).sqrt();
+    let edge_margin = (alpha.abs().min((1.0 - alpha).abs()) * across_v)
+        .min(beta.abs().min((1.0 - beta).abs()) * across_u);
+    let inside = (0.0..=1.0).contains(&alpha) && (0.0..=1.0).contains(&beta);
+    let margin = if !inside {
+        edge_margin
+    } else if along.abs() < GRAZING {
+        0.0
+    } else {
+        edge_margin.min(range_margin(t, length, t_min, t_max))
+    };
+    let hit = (inside && t >= t_min as f64 && t <= t_max as f64).then_some((t, unit));
+    (hit, margin)
+}
+
+/// World distance along a ray with a direction `length` long between a crossing at `t`
+/// and the nearer end of the ray interval.
+fn range_margin(t: f64, length: f64, t_min: f32, t_max: f32) -> f64 {
+    (t - t_min as f64).abs().min((t_max as f64 - t).abs()) * length
+}
+
+fn wide(v: &vec::Vec3) -> [f64; 3] {
+    [v.x as f64, v.y as f64, v.z as f64]
+}
+
+fn narrow(v: [f64; 3]) -> vec::Vec3 {
+    vec::Vec3::new(v[0] as f32, v[1] as f32, v[2] as f32)
+}
+
+fn add(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f64; 3], s: f64) -> [f64; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
@@ -5,28 +5,249 @@ use crate::math::{mat, vec};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub enum Transform {
-    Rotate(mat::Mat3),
+    /// Rotation about the origin. Scene files give either the 3x3 matrix, row by row, or
+    /// Euler angles in degrees such as `{ y = 15 }` (see [`mat::Mat3::from_euler`]).
+    Rotate(#[serde(deserialize_with = "deserialize_rotation")] mat::Mat3),
+    /// Rotation about `pivot` instead of the origin, e.g. a box spun about its own
+    /// center; `rotation` is given like `Rotate`'s.
+    RotateAround {
+        pivot: vec::Vec3,
+        #[serde(deserialize_with = "deserialize_rotation")]
+        rotation: mat::Mat3,
+    },
     Translate(vec::Vec3),
     Scale(vec::Vec3),
-    Move {
-        start: vec::Vec3,
-        end: vec::Vec3,
-        time_start: f64,
-        time_end: f64,
-    },
+    /// Translation that changes over time, for motion blur (see [`Motion`]).
+    Move(Motion),
+}
+
+/// How a [`Motion`] moves between keyframes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Straight lines between keyframes at constant speed.
+    #[default]
+    Linear,
+    /// A smooth Catmull-Rom curve through the keyframes, with tangents from the
+    /// neighboring keyframes scaled to their spacing in time.
+    Cubic,
+}
+
+impl Interpolation {
+    pub fn is_default(&self) -> bool {
+        *self == Interpolation::default()
+    }
+}
+
+/// Where a moving object is offset to at a moment in time.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f64,
+    pub offset: vec::Vec3,
+}
+
+/// A path of offsets through time. Before the first keyframe the offset holds at the
+/// first, and after the last at the last.
+///
+/// Scene files list `keyframes` (with an optional `interpolation`), or give a single
+/// segment as `start`, `end`, `time_start`, and `time_end`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Motion {
+    keyframes: Vec<Keyframe>,
+    #[serde(skip_serializing_if = "Interpolation::is_default")]
+    interpolation: Interpolation,
+}
+
+impl Motion {
+    /// A path through `keyframes`, which are sorted by time; an empty list stays at
+    /// the origin.
+    pub fn new(mut keyframes: Vec<Keyframe>, interpolation: Interpolation) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        if keyframes.is_empty() {
+            keyframes.push(Keyframe {
+                time: 0.0,
+                offset: vec::Vec3::default(),
+            });
+        }
+        Motion {
+            keyframes,
+            interpolation,
+        }
+    }
+
+    /// Moves linearly from `start` at `time_start` to `end` at `time_end`.
+    pub fn between(start: vec::Vec3, end: vec::Vec3, time_start: f64, time_end: f64) -> Self {
+        Motion::new(
+            vec![
+                Keyframe {
+                    time: time_start,
+                    offset: start,
+                },
+                Keyframe {
+                    time: time_end,
+                    offset: end,
+                },
+            ],
+            Interpolation::Linear,
+        )
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Offset at `time`.
+    pub fn offset(&self, time: f64) -> vec::Vec3 {
+        let keys = &self.keyframes;
+        let next = keys.partition_point(|key| key.time <= time);
+        if next == 0 {
+            return keys[0].offset;
+        }
+        if next == keys.len() {
+            return keys[next - 1].offset;
+        }
+        let segment = next - 1;
+        let duration = (keys[next].time - keys[segment].time).max(f64::EPSILON);
+        let s = ((time - keys[segment].time) / duration).clamp(0.0, 1.0) as f32;
+        let [a, b, c, d] = self.coefficients(segment);
+        ((a * s + b) * s + c) * s + d
+    }
+
+    /// Cubic coefficients of the segment from keyframe `segment` to the next, for a
+    /// parameter running from 0 to 1 across it (highest power first).
+    fn coefficients(&self, segment: usize) -> [vec::Vec3; 4] {
+        let (start, end) = (self.keyframes[segment], self.keyframes[segment + 1]);
+        let delta = end.offset - start.offset;
+        if self.interpolation == Interpolation::Linear {
+            let zero = vec::Vec3::default();
+            return [zero, zero, delta, start.offset];
+        }
+        // Hermite form, with tangents in units of the segment's own duration.
+        let duration = (end.time - start.time) as f32;
+        let m0 = self.tangent(segment) * duration;
+        let m1 = self.tangent(segment + 1) * duration;
+        [
+            m0 + m1 - delta * 2.0,
+            delta * 3.0 - m0 * 2.0 - m1,
+            m0,
+            start.offset,
+        ]
+    }
+
+    /// Velocity of the curve at keyframe `index`: the slope between its neighbors, or
+    /// to its one neighbor at either end.
+    fn tangent(&self, index: usize) -> vec::Vec3 {
+        let keys = &self.keyframes;
+        let before = keys[index.saturating_sub(1)];
+        let after = keys[(index + 1).min(keys.len() - 1)];
+        let span = (after.time - before.time) as f32;
+        if span <= 0.0 {
+            return vec::Vec3::default();
+        }
+        (after.offset - before.offset) / span
+    }
+
+    /// Every offset the path reaches a furthest point along some axis at: the keyframes
+    /// and, for cubic paths, where a segment turns around between them.
+    fn extremes(&self) -> Vec<vec::Vec3> {
+        let mut extremes: Vec<vec::Vec3> = self.keyframes.iter().map(|key| key.offset).collect();
+        if self.interpolation == Interpolation::Cubic {
+            for segment in 0..self.keyframes.len() - 1 {
+                let [a, b, c, _] = self.coefficients(segment);
+                let (start, end) = (
+                    self.keyframes[segment].time,
+                    self.keyframes[segment + 1].time,
+                );
+                for axis in 0..3 {
+                    // Roots of the derivative 3a s^2 + 2b s + c.
+                    for s in quadratic_roots(3.0 * a[axis], 2.0 * b[axis], c[axis]) {
+                        if s > 0.0 && s < 1.0 {
+                            extremes.push(self.offset(start + (end - start) * s as f64));
+                        }
+                    }
+                }
+            }
+        }
+        extremes
+    }
+
+    fn retimed(&self, map: impl Fn(f64) -> f64) -> Motion {
+        Motion::new(
+            self.keyframes
+                .iter()
+                .map(|key| Keyframe {
+                    time: map(key.time),
+                    offset: key.offset,
+                })
+                .collect(),
+            self.interpolation,
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for Motion {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum MotionData {
+            Keyframes {
+                keyframes: Vec<Keyframe>,
+                #[serde(default)]
+                interpolation: Interpolation,
+            },
+            Segment {
+                start: vec::Vec3,
+                end: vec::Vec3,
+                time_start: f64,
+                time_end: f64,
+            },
+        }
+
+        Ok(match MotionData::deserialize(deserializer)? {
+            MotionData::Keyframes {
+                keyframes,
+                interpolation,
+            } => Motion::new(keyframes, interpolation),
+            MotionData::Segment {
+                start,
+                end,
+                time_start,
+                time_end,
+            } => Motion::between(start, end, time_start, time_end),
+        })
+    }
+}
+
+/// Real roots of `a x^2 + b x + c`, falling back to the linear root when `a` is zero.
+fn quadratic_roots(a: f32, b: f32, c: f32) -> Vec<f32> {
+    if a.abs() <= f32::EPSILON {
+        return if b.abs() <= f32::EPSILON {
+            Vec::new()
+        } else {
+            vec![-c / b]
+        };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+    let root = discriminant.sqrt();
+    vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
 }
 
 impl Transform {
     pub fn apply_point(&self, point: &vec::Vec3, time: f64) -> vec::Vec3 {
         match self {
             Transform::Rotate(mat) => mat * *point,
+            Transform::RotateAround { pivot, rotation } => *pivot + rotation * (*point - *pivot),
             Transform::Translate(offset) => *point + *offset,
-            Transform::Move {
-                start,
-                end,
-                time_start,
-                time_end,
-            } => *point + Self::move_offset(start, end, *time_start, *time_end, time),
+            Transform::Move(motion) => *point + motion.offset(time),
             Transform::Scale(factors) => vec::Vec3 {
                 x: point.x * factors.x,
                 y: point.y * factors.y,
@@ -37,9 +258,11 @@ impl Transform {
 
     pub fn apply_normal(&self, normal: &vec::Vec3, _time: f64) -> vec::Vec3 {
         match self {
-            Transform::Rotate(mat) => vec::unit_vector(&(mat * *normal)),
+            Transform::Rotate(mat) | Transform::RotateAround { rotation: mat, .. } => {
+                vec::unit_vector(&(mat * *normal))
+            }
             Transform::Translate(_) => *normal,
-            Transform::Move { .. } => *normal,
+            Transform::Move(_) => *normal,
             Transform::Scale(factors) => vec::unit_vector(&vec::Vec3 {
                 x: normal.x / factors.x,
                 y: normal.y / factors.y,
@@ -48,6 +271,22 @@ impl Transform {
         }
     }
 
+    /// How much the transform stretches the area of a surface element whose unit normal
+    /// is `normal` before the transform; only `Scale` changes areas.
+    pub fn area_scale(&self, normal: &vec::Vec3) -> f32 {
+        match self {
+            Transform::Scale(factors) => {
+                let inverse_normal = vec::Vec3::new(
+                    normal.x / factors.x,
+                    normal.y / factors.y,
+                    normal.z / factors.z,
+                );
+                (factors.x * factors.y * factors.z).abs() * inverse_normal.length()
+            }
+            _ => 1.0,
+        }
+    }
+
     pub fn apply_inverse(&self, ray: &ray::Ray) -> ray::Ray {
         match self {
             Transform::Rotate(mat) => {
@@ -59,6 +298,14 @@ impl Transform {
                     time: ray.time,
                 }
             }
+            Transform::RotateAround { pivot, rotation } => {
+                let transposed = rotation.transpose();
+                ray::Ray {
+                    origin: *pivot + transposed * (ray.origin - *pivot),
+                    direction: transposed * ray.direction,
+                    time: ray.time,
+                }
+            }
             Transform::Translate(offset) => ray::Ray {
                 origin: ray.origin - *offset,
                 direction: ray.direction,
@@ -77,19 +324,11 @@ impl Transform {
                 },
                 time: ray.time,
             },
-            Transform::Move {
-                start,
-                end,
-                time_start,
-                time_end,
-            } => {
-                let offset = Self::move_offset(start, end, *time_start, *time_end, ray.time);
-                ray::Ray {
-                    origin: ray.origin - offset,
-                    direction: ray.direction,
-                    time: ray.time,
-                }
-            }
+            Transform::Move(motion) => ray::Ray {
+                origin: ray.origin - motion.offset(ray.time),
+                direction: ray.direction,
+                time: ray.time,
+            },
         }
     }
 
@@ -117,64 +356,94 @@ impl Transform {
                     vec::Vec3::new(x0.max(x1), y0.max(y1), z0.max(z1)),
                 )
             }
-            Transform::Rotate(mat) => {
-                let corners = [
-                    vec::Vec3::new(bbox.x.min, bbox.y.min, bbox.z.min),
-                    vec::Vec3::new(bbox.x.min, bbox.y.min, bbox.z.max),
-                    vec::Vec3::new(bbox.x.min, bbox.y.max, bbox.z.min),
-                    vec::Vec3::new(bbox.x.min, bbox.y.max, bbox.z.max),
-                    vec::Vec3::new(bbox.x.max, bbox.y.min, bbox.z.min),
-                    vec::Vec3::new(bbox.x.max, bbox.y.min, bbox.z.max),
-                    vec::Vec3::new(bbox.x.max, bbox.y.max, bbox.z.min),
-                    vec::Vec3::new(bbox.x.max, bbox.y.max, bbox.z.max),
-                ];
-                let rotated = corners.map(|corner| mat * corner);
-                let mut min = rotated[0];
-                let mut max = rotated[0];
-                for point in rotated.iter().skip(1) {
-                    min =
-                        vec::Vec3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z));
-                    max =
-                        vec::Vec3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z));
-                }
-                bbox::BBox::bounding(min, max)
-            }
-            Transform::Move {
-                start,
-                end,
-                time_start: _,
-                time_end: _,
-            } => {
-                let moved_min = bbox::BBox::bounding(
-                    vec::Vec3::new(
-                        bbox.x.min + start.x,
-                        bbox.y.min + start.y,
-                        bbox.z.min + start.z,
-                    ),
-                    vec::Vec3::new(
-                        bbox.x.max + start.x,
-                        bbox.y.max + start.y,
-                        bbox.z.max + start.z,
-                    ),
-                );
-                let moved_max = bbox::BBox::bounding(
-                    vec::Vec3::new(bbox.x.min + end.x, bbox.y.min + end.y, bbox.z.min + end.z),
-                    vec::Vec3::new(bbox.x.max + end.x, bbox.y.max + end.y, bbox.z.max + end.z),
-                );
-                moved_min.union(&moved_max)
-            }
+            Transform::Rotate(mat) => rotate_bbox(bbox, mat, &vec::Vec3::default()),
+            Transform::RotateAround { pivot, rotation } => rotate_bbox(bbox, rotation, pivot),
+            Transform::Move(motion) => motion
+                .extremes()
+                .iter()
+                .map(|offset| Transform::Translate(*offset).apply_bbox(bbox))
+                .reduce(|bounds, next| bounds.union(&next))
+                .unwrap_or(*bbox),
         }
     }
 
-    fn move_offset(
-        start: &vec::Vec3,
-        end: &vec::Vec3,
-        time_start: f64,
-        time_end: f64,
-        time: f64,
-    ) -> vec::Vec3 {
-        let duration = (time_end - time_start).max(f64::EPSILON);
-        let lerp_t = ((time - time_start) / duration).clamp(0.0, 1.0) as f32;
-        *start + (*end - *start) * lerp_t
+    /// Returns this transform with its keyframe times passed through `map`; only `Move`
+    /// has any.
+    pub fn retimed(&self, map: impl Fn(f64) -> f64) -> Transform {
+        match self {
+            Transform::Move(motion) => Transform::Move(motion.retimed(map)),
+            other => other.clone(),
+        }
+    }
+}
+
+/// Box around `bbox` rotated by `mat` about `pivot`.
+fn rotate_bbox(bbox: &bbox::BBox, mat: &mat::Mat3, pivot: &vec::Vec3) -> bbox::BBox {
+    let corners = [
+        vec::Vec3::new(bbox.x.min, bbox.y.min, bbox.z.min),
+        vec::Vec3::new(bbox.x.min, bbox.y.min, bbox.z.max),
+        vec::Vec3::new(bbox.x.min, bbox.y.max, bbox.z.min),
+        vec::Vec3::new(bbox.x.min, bbox.y.max, bbox.z.max),
+        vec::Vec3::new(bbox.x.max, bbox.y.min, bbox.z.min),
+        vec::Vec3::new(bbox.x.max, bbox.y.min, bbox.z.max),
+        vec::Vec3::new(bbox.x.max, bbox.y.max, bbox.z.min),
+        vec::Vec3::new(bbox.x.max, bbox.y.max, bbox.z.max),
+    ];
+    let rotated = corners.map(|corner| *pivot + mat * (corner - *pivot));
+    let mut min = rotated[0];
+    let mut max = rotated[0];
+    for point in rotated.iter().skip(1) {
+        min = vec::Vec3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z));
+        max = vec::Vec3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z));
+    }
+    bbox::BBox::bounding(min, max)
+}
+
+/// Reads a rotation as either a matrix or Euler angles in degrees; missing angles are 0.
+fn deserialize_rotation<'de, D>(deserializer: D) -> Result<mat::Mat3, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Euler {
+        #[serde(default)]
+        x: f32,
+        #[serde(default)]
+        y: f32,
+        #[serde(default)]
+        z: f32,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RotationData {
+        Matrix(mat::Mat3),
+        Euler(Euler),
+    }
+
+    Ok(match RotationData::deserialize(deserializer)? {
+        RotationData::Matrix(matrix) => matrix,
+        RotationData::Euler(Euler { x, y, z }) => mat::Mat3::from_euler(&vec::Vec3::new(x, y, z)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotations_read_as_euler_angles_or_matrices() {
+        // The Cornell box's tall block, written both ways.
+        let euler: Transform = toml::from_str("Rotate = { y = 15 }").unwrap();
+        let matrix: Transform = toml::from_str(
+            "Rotate = [[0.9659258, 0.0, 0.2588190], [0.0, 1.0, 0.0], [-0.2588190, 0.0, 0.9659258]]",
+        )
+        .unwrap();
+        let point = vec::Vec3::new(1.0, 2.0, 3.0);
+        let difference = euler.apply_point(&point, 0.0) - matrix.apply_point(&point, 0.0);
+        assert!(difference.length() < 1e-5);
+
+        assert!(toml::from_str::<Transform>("Rotate = { w = 15 }").is_err());
     }
 }
@@ -0,0 +1,19 @@
+//! Integrator organizations that can drive a render.
+pub mod photon;
+pub mod sppm;
+pub mod wavefront;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Integrator {
+    /// Traces each camera path to completion before starting the next one.
+    #[default]
+    Megakernel,
+    /// Advances every path of a chunk one bounce at a time through separate
+    /// generate, intersect, and shade stages (see [`wavefront`]).
+    Wavefront,
+    /// Stochastic progressive photon mapping (see [`sppm`]): `samples` becomes the number
+    /// of photon passes. Handles caustics seen through glass that path tracing can't.
+    Sppm,
+}
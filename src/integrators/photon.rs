@@ -0,0 +1,340 @@
+//! Photon emission and tracing for photon-mapping integrators.
+//!
+//! Photons leave the scene's emitters carrying a share of its emitted power and bounce
+//! through the scene the way light does, reporting every surface that scatters them
+//! diffusely; an integrator decides what to do with those reports (see [`super::sppm`]).
+//!
+//! Emitters are the scene's lights whose geometry can sample points on its surface
+//! ([`hittable::Hittable::sample_surface`]) and unbounded emitters such as the sky, whose
+//! photons start on a disk just outside the scene's bounds facing into it. Lights on
+//! other geometry (SDFs, heightfields, curves) emit no photons.
+use rand::Rng;
+
+use crate::core::{bbox, object, path, ray, scene};
+use crate::math::{onb, pdf, rng, vec};
+use crate::traits::hittable::Hittable;
+use crate::traits::renderable::Renderable;
+use crate::traits::{hittable, renderable, scatterable};
+
+/// Photons drawn from each emitter to estimate its power.
+const POWER_SAMPLES: u64 = 64;
+/// Bounces a photon makes before Russian roulette may end it.
+const ROULETTE_DEPTH: u32 = 3;
+/// Lowest survival probability under Russian roulette.
+const MIN_SURVIVAL: f32 = 0.05;
+/// How far above an emitter's surface its emission is probed, relative to the size of
+/// the coordinates there.
+const PROBE_OFFSET: f32 = 1e-3;
+
+enum Emitter<'a> {
+    /// A light with a sampleable surface, emitting from both sides.
+    Surface(&'a object::RenderObject),
+    /// An unbounded emitter lighting the scene from every direction.
+    Sky(&'a dyn renderable::Renderable),
+}
+
+/// A photon leaving an emitter.
+struct Emission {
+    ray: ray::Ray,
+    /// Power carried, before dividing by the number of photons shot.
+    power: vec::Vec3,
+}
+
+/// The scene's emitters, chosen for each photon in proportion to their power.
+pub struct PhotonSource<'a> {
+    scene: &'a scene::Scene,
+    emitters: Vec<Emitter<'a>>,
+    /// Selection probability of each emitter.
+    probabilities: Vec<f32>,
+    /// Sphere around every bounded object, which sky photons start outside of.
+    center: vec::Point3,
+    radius: f32,
+    depth: u32,
+    ray_range: ray::RayRange,
+    algorithm: rng::RngAlgorithm,
+}
+
+impl<'a> PhotonSource<'a> {
+    /// Gathers the emitters of `scene`; photons bounce at most `depth` times. Emitter
+    /// powers are estimated with `algorithm`, the generator the render samples with.
+    pub fn new(scene: &'a scene::Scene, depth: u32, algorithm: rng::RngAlgorithm) -> Self {
+        let bounds = scene
+            .renderables
+            .objects
+            .iter()
+            .map(|object| object.bounding_box())
+            .filter(|bounds| !bounds.is_unbounded())
+            .reduce(|bounds, next| bounds.union(&next));
+        let (center, radius) = bounds.map_or((vec::Point3::default(), 0.0), |bounds| {
+            let min = vec::Point3::new(bounds.x.min, bounds.y.min, bounds.z.min);
+            let max = vec::Point3::new(bounds.x.max, bounds.y.max, bounds.z.max);
+            ((min + max) * 0.5, (max - min).length() * 0.5)
+        });
+
+        // Sky-only scenes shoot photons from the sky alone.
+        let lights = if scene.sky_only {
+            &[][..]
+        } else {
+            &scene.lights[..]
+        };
+        let mut emitters: Vec<Emitter<'a>> = lights
+            .iter()
+            .filter_map(|light| light.as_any().downcast_ref::<object::RenderObject>())
+            .map(Emitter::Surface)
+            .collect();
+        if radius > 0.0 {
+            emitters.extend(
+                scene
+                    .unbounded
+                    .iter()
+                    .map(|index| Emitter::Sky(scene.renderables.objects[*index].as_ref())),
+            );
+        }
+
+        let mut source = PhotonSource {
+            scene,
+            emitters,
+            probabilities: Vec::new(),
+            center,
+            radius,
+            depth,
+            ray_range: ray::RayRange::default(),
+            algorithm,
+        };
+        let powers: Vec<f32> = (0..source.emitters.len())
+            .map(|index| source.estimate_power(index))
+            .collect();
+        let total: f32 = powers.iter().sum();
+        source.probabilities = if total > 0.0 {
+            powers.iter().map(|power| power / total).collect()
+        } else {
+            vec![0.0; powers.len()]
+        };
+        source
+    }
+
+    /// Traces photons with the hit distances in `ray_range` instead of the defaults.
+    pub fn with_ray_range(mut self, ray_range: ray::RayRange) -> Self {
+        self.ray_range = ray_range;
+        self
+    }
+
+    /// Whether any emitter gives off light photons can carry.
+    pub fn is_empty(&self) -> bool {
+        self.probabilities
+            .iter()
+            .all(|probability| *probability <= 0.0)
+    }
+
+    /// Traces one photon, calling `deposit` with the hit, the direction the photon
+    /// arrived along, and its power at every diffuse surface it reaches. Powers are for
+    /// a single photon; divide by the number shot.
+    pub fn trace(
+        &self,
+        rng: &mut rng::SampleRng,
+        mut deposit: impl FnMut(&hittable::HitRecord<'_>, &vec::Vec3, vec::Vec3),
+    ) {
+        let Some(index) = self.choose(rng) else {
+            return;
+        };
+        let Some(emission) = self.emit(rng, index) else {
+            return;
+        };
+        let mut photon = emission.ray;
+        let mut power = emission.power / self.probabilities[index];
+        let mut state = path::PathState::new(self.depth);
+
+        while let Some(hit_record) = self.ray_range.hit(self.scene, &photon) {
+            if state.out_of_bounces(hit_record.renderable.max_bounces(&hit_record)) {
+                return;
+            }
+            let Some(scatter_record) = hit_record.renderable.scatter(rng, &hit_record, &mut state)
+            else {
+                return;
+            };
+            let next = match scatter(rng, &hit_record, &scatter_record) {
+                Scattered::Specular(next) => {
+                    state.record_bounce(path::BounceKind::Specular);
+                    next
+                }
+                Scattered::Diffuse(next, weight) => {
+                    deposit(&hit_record, &photon.direction, power);
+                    state.record_bounce(path::BounceKind::Diffuse);
+                    power = power * weight;
+                    next
+                }
+                Scattered::Absorbed => return,
+            };
+
+            power = power * scatter_record.attenuation;
+            if state.bounces > ROULETTE_DEPTH {
+                let attenuation = scatter_record.attenuation;
+                let survival = attenuation
+                    .x
+                    .max(attenuation.y)
+                    .max(attenuation.z)
+                    .clamp(MIN_SURVIVAL, 1.0);
+                if rng.random::<f32>() >= survival {
+                    return;
+                }
+                power = power / survival;
+            }
+            photon = next;
+        }
+    }
+
+    fn choose(&self, rng: &mut rng::SampleRng) -> Option<usize> {
+        let mut pick = rng.random::<f32>();
+        let mut last = None;
+        for (index, probability) in self.probabilities.iter().enumerate() {
+            if *probability <= 0.0 {
+                continue;
+            }
+            last = Some(index);
+            if pick < *probability {
+                return last;
+            }
+            pick -= probability;
+        }
+        last
+    }
+
+    /// Average brightness of the photons `emitters[index]` would emit if it were the
+    /// only emitter, drawn with a fixed generator so renders stay reproducible.
+    fn estimate_power(&self, index: usize) -> f32 {
+        let mut rng = self.algorithm.hashed(index as u64);
+        let total: f32 = (0..POWER_SAMPLES)
+            .filter_map(|_| self.emit(&mut rng, index))
+            .map(|emission| {
+                let power = emission.power;
+                (power.x + power.y + power.z) / 3.0
+            })
+            .filter(|power| power.is_finite())
+            .sum();
+        total / POWER_SAMPLES as f32
+    }
+
+    fn emit(&self, rng: &mut rng::SampleRng, index: usize) -> Option<Emission> {
+        let time = rng.random::<f64>();
+        match self.emitters[index] {
+            Emitter::Surface(light) => {
+                let sample = light.geometry_instance.sample_surface(rng, time)?;
+                let side: f32 = if rng.random::<bool>() { 1.0 } else { -1.0 };
+                let normal = sample.normal * side;
+
+                // Ask the light what it emits on this side by looking at it from just above.
+                let point = sample.point;
+                let offset =
+                    PROBE_OFFSET * (1.0 + point.x.abs().max(point.y.abs()).max(point.z.abs()));
+                let probe = ray::Ray::new(&(point + normal * offset), &-normal, Some(time));
+                let emitted = light.emit(&light.hit(&probe, 0.0, 2.0 * offset)?);
+
+                // Cosine-weighted directions on the chosen side cancel the cosine in the
+                // emitted power, leaving pi per side.
+                let direction = pdf::PDF::generate(&pdf::cosine::CosinePDF::new(&normal), rng);
+                Some(Emission {
+                    ray: ray::Ray::new(&point, &direction, Some(time)),
+                    power: emitted * (2.0 * std::f32::consts::PI / sample.pdf),
+                })
+            }
+            Emitter::Sky(sky) => {
+                let direction = vec::unit_vector(&vec::random_in_unit_sphere(rng));
+                let looking = ray::Ray::new(&self.center, &-direction, Some(time));
+                let emitted = sky.emit(&sky.hit(&looking, 0.001, f32::MAX)?);
+
+                let frame = onb::ONB::build_from_w(&direction);
+                let disk = vec::random_in_unit_disk(rng) * self.radius;
+                let origin = self.center - direction * self.radius + frame.local(&disk);
+                let area = std::f32::consts::PI * self.radius * self.radius;
+                Some(Emission {
+                    ray: ray::Ray::new(&origin, &direction, Some(time)),
+                    power: emitted * (4.0 * std::f32::consts::PI * area),
+                })
+            }
+        }
+    }
+}
+
+enum Scattered {
+    Specular(ray::Ray),
+    /// A ray drawn from the material's PDF, and the sample's weight beyond the
+    /// attenuation (1 for materials their PDF samples exactly).
+    Diffuse(ray::Ray, f32),
+    Absorbed,
+}
+
+/// Continues a photon from a hit. Diffuse bounces sample the material's own PDF, so the
+/// attenuation is the weight, as in the path tracer, up to the PDF's `scattering`.
+fn scatter(
+    rng: &mut rng::SampleRng,
+    hit_record: &hittable::HitRecord<'_>,
+    scatter_record: &scatterable::ScatterRecord,
+) -> Scattered {
+    if let Some(specular) = scatter_record.scattered_ray {
+        return Scattered::Specular(specular);
+    }
+    let Some(scatter_pdf) = scatter_record.scatter_pdf.as_ref() else {
+        return Scattered::Absorbed;
+    };
+    let direction = scatter_pdf.generate(rng);
+    let density = scatter_pdf.value(direction);
+    if density <= 0.0 {
+        return Scattered::Absorbed;
+    }
+    Scattered::Diffuse(
+        ray::Ray::new(
+            &hit_record.hit.point,
+            &direction,
+            Some(hit_record.hit.ray.time),
+        ),
+        scatter_pdf.scattering(direction) / density,
+    )
+}
+
+/// A uniform grid over points with a search radius each, finding the points whose
+/// radius reaches a query position.
+pub struct PointGrid {
+    cell_size: f32,
+    cells: std::collections::HashMap<[i32; 3], Vec<u32>>,
+}
+
+impl PointGrid {
+    /// Indexes `points` as `(index, position, radius)`.
+    pub fn new(points: &[(u32, vec::Point3, f32)]) -> Self {
+        let cell_size = points
+            .iter()
+            .map(|(_, _, radius)| *radius)
+            .fold(0.0_f32, f32::max)
+            .max(f32::EPSILON);
+        let mut grid = PointGrid {
+            cell_size,
+            cells: std::collections::HashMap::new(),
+        };
+        for (index, position, radius) in points {
+            let reach = vec::Vec3::new(*radius, *radius, *radius);
+            let bounds = bbox::BBox::bounding(*position - reach, *position + reach);
+            let low = grid.cell(&vec::Point3::new(bounds.x.min, bounds.y.min, bounds.z.min));
+            let high = grid.cell(&vec::Point3::new(bounds.x.max, bounds.y.max, bounds.z.max));
+            for x in low[0]..=high[0] {
+                for y in low[1]..=high[1] {
+                    for z in low[2]..=high[2] {
+                        grid.cells.entry([x, y, z]).or_default().push(*index);
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    /// Indices of the points that may reach `position`; callers check the distance.
+    pub fn candidates(&self, position: &vec::Point3) -> &[u32] {
+        self.cells
+            .get(&self.cell(position))
+            .map_or(&[], |indices| indices.as_slice())
+    }
+
+    fn cell(&self, position: &vec::Point3) -> [i32; 3] {
+        let cell = |value: f32| (value / self.cell_size).floor() as i32;
+        [cell(position.x), cell(position.y), cell(position.z)]
+    }
+}
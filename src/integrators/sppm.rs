@@ -0,0 +1,260 @@
+//! Stochastic progressive photon mapping (SPPM).
+//!
+//! Each iteration follows one camera path per pixel through mirrors and glass to the
+//! first surface that scatters diffusely, its visible point, then shoots a batch of
+//! photons ([`super::photon`]) and gathers the ones landing within each visible point's
+//! search radius. Radii shrink after every iteration while the flux already gathered is
+//! kept, so the estimate converges as iterations accumulate.
+//!
+//! Light that reaches the camera along specular-diffuse-specular paths, like a caustic
+//! under a glass object seen through another piece of glass, is carried to the diffuse
+//! surface by photons instead of having to be found from the camera side, where the path
+//! tracer almost never finds it.
+//!
+//! `render.samples` is the number of iterations, and every iteration shoots as many
+//! photons as the chunk has pixels. Photons are shot per chunk, so frames should be
+//! rendered as one chunk (as [`crate::raytrace_film`] does for this integrator).
+//! Participating media are gathered as if their scattering points were surfaces, which
+//! is only approximate; the path tracer is the reference for smoke and fog.
+//!
+//! An interrupted render (see [`render::Render::interrupt`]) finishes the iteration in
+//! flight and returns the estimate from the iterations done so far.
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::core::{path, ray, render, volume};
+use crate::integrators::photon;
+use crate::math::{pdf, rng, vec};
+use crate::{ChunkBounds, ChunkOutput, write_pixel};
+
+/// Fraction of each iteration's photons kept when the search radius shrinks; lower
+/// values shrink radii faster.
+const ALPHA: f32 = 2.0 / 3.0;
+/// Initial search radius, in pixel footprints at the visible point's distance.
+const INITIAL_RADIUS_PIXELS: f32 = 4.0;
+
+/// The first diffuse vertex of a camera path.
+struct VisiblePoint {
+    point: vec::Point3,
+    normal: vec::Vec3,
+    /// Path throughput times the material's attenuation.
+    weight: vec::Vec3,
+    scatter_pdf: Box<dyn pdf::PDF + Send + Sync>,
+    /// Whether the point is inside a participating medium, whose phase function has no
+    /// cosine to remove.
+    in_medium: bool,
+}
+
+impl VisiblePoint {
+    /// Radiance carried toward the camera per unit of irradiance arriving from
+    /// `incoming`, including the throughput of the path back to the camera.
+    fn response(&self, incoming: &vec::Vec3) -> vec::Vec3 {
+        let scattering = self.scatter_pdf.scattering(*incoming);
+        if scattering <= 0.0 {
+            return vec::Vec3::default();
+        }
+        if self.in_medium {
+            return self.weight * scattering;
+        }
+        // Materials report f * cos as attenuation times their PDF's `scattering`;
+        // photons arrive as irradiance, so the cosine comes off again.
+        let cosine = self.normal.dot(&vec::unit_vector(incoming)).abs();
+        if cosine <= f32::EPSILON {
+            return vec::Vec3::default();
+        }
+        self.weight * (scattering / cosine)
+    }
+}
+
+/// Running estimate for one pixel.
+#[derive(Clone, Copy, Default)]
+struct PixelEstimate {
+    /// Emission seen directly or through specular bounces, summed over iterations.
+    direct: vec::Vec3,
+    /// Photon flux gathered so far, scaled to the current radius.
+    flux: vec::Vec3,
+    /// Photons the flux counts for after shrinking.
+    photons: f32,
+    /// Current search radius; zero until the pixel first finds a visible point.
+    radius: f32,
+}
+
+pub(crate) fn render_chunk(render: &render::Render, bounds: ChunkBounds) -> ChunkOutput {
+    let (width, height) = (render.image_width(), render.image_height());
+    let pixels =
+        render
+            .pixel_order
+            .pixels(bounds.x_start, bounds.x_end, bounds.y_start, bounds.y_end);
+    let iterations = render.samples.max(1);
+    let photons_per_iteration = pixels.len().max(1);
+    let pixel_angle =
+        2.0 * (render.camera.vertical_fov.to_radians() * 0.5).tan() / height.max(1) as f32;
+    let source = photon::PhotonSource::new(&render.scene, render.depth, render.rng)
+        .with_ray_range(render.ray_ranges.indirect);
+    let mut estimates = vec![PixelEstimate::default(); pixels.len()];
+
+    let mut completed = 0;
+    for iteration in 0..iterations {
+        if render.interrupted() {
+            break;
+        }
+        completed += 1;
+        let visible: Vec<Option<VisiblePoint>> = pixels
+            .par_iter()
+            .zip(estimates.par_iter_mut())
+            .map(|(&(x, y), estimate)| {
+                let mut rng = render
+                    .rng
+                    .pixel_rng_at(render.frame_seed(), x, y, iteration);
+                let u = (x as f32 + rng.random::<f32>()) / width as f32;
+                let v = (y as f32 + rng.random::<f32>()) / height as f32;
+                camera_path(render, &mut rng, u, v, pixel_angle, estimate)
+            })
+            .collect();
+        if source.is_empty() {
+            continue;
+        }
+
+        let entries: Vec<(u32, vec::Point3, f32)> = visible
+            .iter()
+            .zip(&estimates)
+            .enumerate()
+            .filter_map(|(index, (point, estimate))| {
+                point
+                    .as_ref()
+                    .map(|point| (index as u32, point.point, estimate.radius))
+            })
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+        let grid = photon::PointGrid::new(&entries);
+
+        let flux: Vec<[AtomicU32; 3]> = (0..pixels.len())
+            .map(|_| std::array::from_fn(|_| AtomicU32::new(0)))
+            .collect();
+        let counts: Vec<AtomicU32> = (0..pixels.len()).map(|_| AtomicU32::new(0)).collect();
+        (0..photons_per_iteration)
+            .into_par_iter()
+            .for_each(|index| {
+                let bits = ((iteration as u64) << 32) | index as u64;
+                let mut rng = render
+                    .rng
+                    .hashed(render.frame_seed() ^ bits.rotate_left(17));
+                source.trace(&mut rng, |hit_record, direction, power| {
+                    let point = hit_record.hit.point;
+                    for &pixel in grid.candidates(&point) {
+                        let pixel = pixel as usize;
+                        let Some(visible_point) = visible[pixel].as_ref() else {
+                            continue;
+                        };
+                        let radius = estimates[pixel].radius;
+                        if (visible_point.point - point).squared_length() > radius * radius {
+                            continue;
+                        }
+                        let gathered = power * visible_point.response(&-*direction);
+                        for (channel, value) in
+                            flux[pixel].iter().zip([gathered.x, gathered.y, gathered.z])
+                        {
+                            add_f32(channel, value);
+                        }
+                        counts[pixel].fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+            });
+
+        for ((estimate, flux), count) in estimates.iter_mut().zip(&flux).zip(&counts) {
+            let count = count.load(Ordering::Relaxed) as f32;
+            if count == 0.0 {
+                continue;
+            }
+            let [x, y, z] = flux
+                .each_ref()
+                .map(|channel| f32::from_bits(channel.load(Ordering::Relaxed)));
+            // Keep a fraction of the new photons and shrink the radius so the density
+            // they represent stays the same.
+            let photons = estimate.photons + ALPHA * count;
+            let shrink = photons / (estimate.photons + count);
+            estimate.flux = (estimate.flux + vec::Vec3::new(x, y, z)) * shrink;
+            estimate.photons = photons;
+            estimate.radius *= shrink.sqrt();
+        }
+    }
+
+    let completed = completed.max(1);
+    let photons_shot = completed as f32 * photons_per_iteration as f32;
+    let mut data = vec![vec::Vec3::default(); bounds.width() as usize * bounds.height() as usize];
+    for (&(x, y), estimate) in pixels.iter().zip(&estimates) {
+        let area = std::f32::consts::PI * estimate.radius * estimate.radius;
+        let mut color = estimate.direct / completed as f32;
+        if area > 0.0 {
+            color = color + estimate.flux / (photons_shot * area);
+        }
+        write_pixel(&mut data, &bounds, x, y, color);
+    }
+
+    ChunkOutput { bounds, data }
+}
+
+/// Traces a camera path through specular bounces, adding the emission it sees to
+/// `estimate`, and returns its first diffuse vertex.
+fn camera_path(
+    render: &render::Render,
+    rng: &mut rng::SampleRng,
+    u: f32,
+    v: f32,
+    pixel_angle: f32,
+    estimate: &mut PixelEstimate,
+) -> Option<VisiblePoint> {
+    let (mut ray, mut throughput) = render.camera.get_weighted_ray(rng, u, v);
+    let mut state = path::PathState::new(render.depth)
+        .with_ray_ranges(render.camera.clip(&ray, &render.ray_ranges));
+    let mut distance = 0.0;
+
+    while let Some(hit_record) = state.ray_range().hit(&render.scene, &ray) {
+        if state.last_bounce == path::BounceKind::Camera
+            && !hit_record.renderable.camera_visible(&hit_record)
+        {
+            distance += hit_record.hit.t * ray.direction.length();
+            ray = ray::Ray::new(&hit_record.hit.point, &ray.direction, Some(ray.time));
+            continue;
+        }
+        estimate.direct = estimate.direct + throughput * render.scene.emitted(&hit_record);
+        if state.out_of_bounces(hit_record.renderable.max_bounces(&hit_record)) {
+            return None;
+        }
+        let scatter_record = hit_record
+            .renderable
+            .scatter(rng, &hit_record, &mut state)?;
+        if let Some(specular_ray) = scatter_record.scattered_ray {
+            distance += hit_record.hit.t * ray.direction.length();
+            state.record_bounce(path::BounceKind::Specular);
+            throughput = throughput * scatter_record.attenuation;
+            ray = specular_ray;
+            continue;
+        }
+
+        let scatter_pdf = scatter_record.scatter_pdf?;
+        distance += hit_record.hit.t * ray.direction.length();
+        if estimate.radius == 0.0 {
+            estimate.radius = (INITIAL_RADIUS_PIXELS * pixel_angle * distance).max(f32::EPSILON);
+        }
+        return Some(VisiblePoint {
+            point: hit_record.hit.point,
+            normal: hit_record.hit.normal,
+            weight: throughput * scatter_record.attenuation,
+            scatter_pdf,
+            in_medium: hit_record.renderable.as_any().is::<volume::RenderVolume>(),
+        });
+    }
+    None
+}
+
+/// Adds to an `f32` stored as bits in an atomic.
+fn add_f32(atomic: &AtomicU32, value: f32) {
+    let _ = atomic.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+        Some((f32::from_bits(bits) + value).to_bits())
+    });
+}
@@ -0,0 +1,144 @@
+//! Wavefront path tracing.
+//!
+//! Instead of following one camera path to completion, each sample pass generates a path
+//! per pixel and then repeats two stages over the queue of live paths until it drains:
+//! intersect (all rays at once through [`crate::core::scene::Scene::hit_batch`]) and shade
+//! (hits sorted by material type so consecutive work runs the same material code).
+//!
+//! An interrupted render (see [`render::Render::interrupt`]) finishes the pass in flight
+//! and averages the passes done so far, so every pixel keeps the same, smaller number of
+//! samples, spread over its strata a diagonal at a time.
+use std::any::TypeId;
+
+use rand::Rng;
+
+use crate::core::{object, path, ray, render};
+use crate::math::{rng, vec};
+use crate::samplers::monte_carlo;
+use crate::traits::hittable;
+use crate::{ChunkBounds, ChunkOutput, shade_hit, write_pixel};
+
+/// A live path waiting in the queue.
+struct PathItem {
+    /// Index into the chunk's pixel list.
+    pixel: usize,
+    ray: ray::Ray,
+    throughput: vec::Vec3,
+    state: path::PathState,
+}
+
+pub(crate) fn render_chunk(render: &render::Render, bounds: ChunkBounds) -> ChunkOutput {
+    let height = render.image_height();
+    let (spp_sqrt, _) = monte_carlo::square_spp(render.samples.max(1));
+    let recip_spp_sqrt = 1.0 / spp_sqrt as f32;
+    let pixels =
+        render
+            .pixel_order
+            .pixels(bounds.x_start, bounds.x_end, bounds.y_start, bounds.y_end);
+    let mut radiance = vec![vec::Vec3::default(); pixels.len()];
+    // Each path draws from its pixel's generator, so the image matches the seed no matter
+    // how paths are ordered in the queue.
+    let mut rngs: Vec<rng::SampleRng> = pixels
+        .iter()
+        .map(|&(x, y)| render.rng.pixel_rng(render.frame_seed(), x, y))
+        .collect();
+
+    let mut passes = 0;
+    for pass in 0..spp_sqrt * spp_sqrt {
+        if render.interrupted() {
+            break;
+        }
+        passes += 1;
+        // Strata are visited a diagonal at a time, so the passes finished before an
+        // interrupt cover every row and column of strata rather than the first rows.
+        let (i, j) = (
+            pass % spp_sqrt,
+            (pass / spp_sqrt + pass % spp_sqrt) % spp_sqrt,
+        );
+        // Generate: one stratified camera path per pixel for this stratum.
+        let mut queue: Vec<PathItem> = pixels
+            .iter()
+            .enumerate()
+            .map(|(pixel, &(x, y))| {
+                let rng = &mut rngs[pixel];
+                let u = (x as f32 + (i as f32 + rng.random::<f32>()) * recip_spp_sqrt)
+                    / render.image_width() as f32;
+                let v =
+                    (y as f32 + (j as f32 + rng.random::<f32>()) * recip_spp_sqrt) / height as f32;
+                let (ray, weight) = render.camera.get_weighted_ray(rng, u, v);
+                PathItem {
+                    pixel,
+                    ray,
+                    throughput: weight,
+                    state: path::PathState::new(render.depth)
+                        .with_ray_ranges(render.camera.clip(&ray, &render.ray_ranges)),
+                }
+            })
+            .collect();
+
+        while !queue.is_empty() {
+            // Intersect: the whole queue as one packet, unless clip planes give the
+            // camera rays ranges of their own.
+            let rays: Vec<ray::Ray> = queue.iter().map(|item| item.ray).collect();
+            let range = queue[0].state.ray_range();
+            let hits = if queue.iter().all(|item| item.state.ray_range() == range) {
+                range.hit_batch(&render.scene, &rays)
+            } else {
+                queue
+                    .iter()
+                    .map(|item| item.state.ray_range().hit(&render.scene, &item.ray))
+                    .collect()
+            };
+
+            // Shade: misses terminate; hits run grouped by material.
+            let mut order: Vec<(TypeId, usize)> = hits
+                .iter()
+                .enumerate()
+                .filter_map(|(k, hit)| hit.as_ref().map(|record| (material_key(record), k)))
+                .collect();
+            order.sort_unstable();
+
+            let mut alive = Vec::with_capacity(order.len());
+            for (_, k) in order {
+                let item = &mut queue[k];
+                let hit_record = hits[k].as_ref().unwrap();
+                if let Some(next_ray) = shade_hit(
+                    &mut rngs[item.pixel],
+                    &render.scene,
+                    hit_record,
+                    &mut item.state,
+                    &mut item.throughput,
+                    &mut radiance[item.pixel],
+                ) {
+                    item.ray = next_ray;
+                    alive.push(k);
+                }
+            }
+
+            let mut slots: Vec<Option<PathItem>> = queue.into_iter().map(Some).collect();
+            queue = alive.into_iter().filter_map(|k| slots[k].take()).collect();
+        }
+    }
+
+    // All `spp_sqrt * spp_sqrt` passes unless the render was interrupted.
+    let recip_spp = 1.0 / passes.max(1) as f32;
+    let mut data = vec![vec::Vec3::default(); bounds.width() as usize * bounds.height() as usize];
+    for (&(x, y), col) in pixels.iter().zip(radiance.iter()) {
+        write_pixel(&mut data, &bounds, x, y, *col * recip_spp);
+    }
+
+    ChunkOutput { bounds, data }
+}
+
+/// Groups hits by the concrete material type that will shade them.
+fn material_key(hit_record: &hittable::HitRecord<'_>) -> TypeId {
+    let renderable = hit_record.renderable.as_any();
+    match renderable.downcast_ref::<object::RenderObject>() {
+        Some(render_object) => (*render_object
+            .material_instance
+            .material_for(hit_record)
+            .as_any())
+        .type_id(),
+        None => (*renderable).type_id(),
+    }
+}
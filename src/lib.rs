@@ -4,25 +4,43 @@
 //! primitives, materials, and rendering functionality.
 pub mod core;
 pub mod geometry;
+pub mod integrators;
 pub mod materials;
 pub mod math;
 pub mod samplers;
 pub mod stats;
+pub mod testing;
 pub mod textures;
 pub mod traits;
 
 use rayon::prelude::*;
+use std::path::Path;
 use std::time;
 
+use crate::core::disk_film;
+use crate::core::events;
+use crate::core::film;
+use crate::core::overlay;
+use crate::core::path;
+use crate::core::preset;
 use crate::core::ray;
 use crate::core::render;
+use crate::core::resolution;
 use crate::core::scene;
+use crate::core::scene_file;
 use crate::math::pdf;
+use crate::math::rng;
 use crate::math::vec;
 use crate::samplers::monte_carlo::MonteCarloSampler;
 use crate::samplers::sampleable::Sampleable;
-use crate::traits::renderable::Renderable;
+use crate::traits::hittable;
 
+/// Rows per chunk in [`raytrace_film`], kept small so few full-precision chunk buffers
+/// are alive at once.
+const FILM_CHUNK_ROWS: u32 = 16;
+
+/// Pixel bounds of a chunk of the image, `[x_start, x_end) x [y_start, y_end)` with row
+/// 0 at the bottom.
 #[derive(Clone, Copy)]
 pub(crate) struct ChunkBounds {
     pub x_start: u32,
@@ -43,17 +61,16 @@ impl ChunkBounds {
 
 pub(crate) struct ChunkOutput {
     pub bounds: ChunkBounds,
-    pub data: Vec<u8>,
-}
-
-pub(crate) fn image_height(render: &render::Render) -> u32 {
-    (render.width as f32 / render.camera.aspect_ratio) as u32
+    /// Linear pixel colors, row-major from `(x_start, y_start)`.
+    pub data: Vec<vec::Vec3>,
 }
 
 /// Renders the given scene to an RGB buffer using stochastic sampling.
 ///
+/// Sampling is seeded per pixel from [`core::render::Render::frame_seed`], so the same
+/// render state always produces the same image.
+///
 /// # Arguments
-/// * `rng` - Random number generator used for jittered sampling.
 /// * `width`/`height` - Output dimensions in pixels.
 /// * `camera` - Camera used to generate view rays.
 /// * `scene` - Collection of renderable objects to trace against.
@@ -62,191 +79,458 @@ pub(crate) fn image_height(render: &render::Render) -> u32 {
 ///
 /// # Returns
 /// A flat RGB buffer in row-major order with gamma correction applied.
-pub fn raytrace(rng: &mut rand::rngs::ThreadRng, render: &render::Render) -> Vec<u8> {
-    let height = image_height(render);
+pub fn raytrace(render: &render::Render) -> Vec<u8> {
+    let height = render.image_height();
     let render_start = time::Instant::now();
+    let rays_before = render.scene.rays_traced();
 
     let full_frame = ChunkBounds {
         x_start: 0,
-        x_end: render.width,
+        x_end: render.image_width(),
         y_start: 0,
         y_end: height,
     };
-    let chunk = raytrace_chunk(rng, render, full_frame);
-    let image_data = assemble_chunks(&[chunk], render.width, height);
+    let chunk = raytrace_chunk(render, full_frame);
+    let mut image_data = assemble_chunks(&[chunk], render);
 
     let wall_time = render_start.elapsed();
+    stamp_stats(render, &mut image_data, wall_time, rays_before);
 
-    println!("Wall time: {}", format_duration(wall_time));
+    if render.events.is_none() {
+        println!("Wall time: {}", format_duration(wall_time));
+    }
 
     image_data
 }
 
 pub fn raytrace_concurrent(render: &render::Render) -> Vec<u8> {
-    let height = image_height(render);
     let render_start = time::Instant::now();
+    let rays_before = render.scene.rays_traced();
+
+    let mut image_data = render.encode(&raytrace_film(render));
+
+    let wall_time = render_start.elapsed();
+    stamp_stats(render, &mut image_data, wall_time, rays_before);
+
+    if render.events.is_none() {
+        println!("Wall time: {}", format_duration(wall_time));
+    }
+
+    image_data
+}
 
-    let num_threads = num_cpus::get();
-    let chunk_height = (height + num_threads as u32 - 1) / num_threads as u32;
+/// Renders the scene in parallel row chunks and returns the linear film instead of
+/// 8-bit output, for callers that post-process frames (e.g. [`core::temporal`]) before
+/// encoding them with [`render::Render::encode`].
+///
+/// Each chunk is splatted into the film as soon as it's finished, so only the chunks in
+/// flight hold full-precision pixels beside a half-precision film. Photon mapping renders
+/// the frame as one chunk, since every chunk shoots its own photons.
+pub fn raytrace_film(render: &render::Render) -> film::Film {
+    let (width, height) = (render.image_width(), render.image_height());
+    if render.integrator == integrators::Integrator::Sppm {
+        let full_frame = ChunkBounds {
+            x_start: 0,
+            x_end: width,
+            y_start: 0,
+            y_end: height,
+        };
+        return film_from_chunks(&[raytrace_chunk(render, full_frame)], render);
+    }
 
-    let chunks: Vec<ChunkBounds> = (0..num_threads)
-        .map(|i| {
-            let y_start = i as u32 * chunk_height;
-            let y_end = ((i as u32 + 1) * chunk_height).min(height);
-            ChunkBounds {
+    let film = film::Film::with_precision(width, height, render.film_precision);
+    (0..height.div_ceil(FILM_CHUNK_ROWS))
+        .into_par_iter()
+        .for_each(|chunk| {
+            let y_start = chunk * FILM_CHUNK_ROWS;
+            let bounds = ChunkBounds {
                 x_start: 0,
-                x_end: render.width,
+                x_end: width,
                 y_start,
-                y_end,
-            }
-        })
-        .collect();
+                y_end: (y_start + FILM_CHUNK_ROWS).min(height),
+            };
+            splat_chunk(&film, &raytrace_chunk(render, bounds));
+        });
+    film
+}
 
-    let chunk_outputs: Vec<ChunkOutput> = chunks
-        .into_par_iter()
-        .map(|chunk_bounds| {
-            let mut local_rng = rand::rng();
-            raytrace_chunk(&mut local_rng, render, chunk_bounds)
+/// Renders the scene in square tiles of `tile_size` pixels straight into a disk-backed
+/// film, writing each tile as soon as it's finished so only the tiles in flight are held
+/// in memory. Pixels match [`raytrace_film`]. Once the render is interrupted no new tiles
+/// are started, and tiles that never ran are left as they were in the film.
+pub fn raytrace_to_disk(render: &render::Render, film: &disk_film::DiskFilm, tile_size: u32) {
+    let (width, height) = (render.image_width(), render.image_height());
+    let tile_size = tile_size.max(1);
+    let tiles: Vec<ChunkBounds> = (0..height)
+        .step_by(tile_size as usize)
+        .flat_map(|y_start| {
+            (0..width)
+                .step_by(tile_size as usize)
+                .map(move |x_start| ChunkBounds {
+                    x_start,
+                    x_end: (x_start + tile_size).min(width),
+                    y_start,
+                    y_end: (y_start + tile_size).min(height),
+                })
         })
         .collect();
 
-    let image_data = assemble_chunks(&chunk_outputs, render.width, height);
+    tiles.into_par_iter().for_each(|bounds| {
+        if render.interrupted() {
+            return;
+        }
+        let chunk = raytrace_chunk(render, bounds);
+        film.write_tile(bounds.x_start, bounds.y_start, bounds.width(), &chunk.data);
+    });
+}
 
-    let wall_time = render_start.elapsed();
+/// Renders the scene once per light group, returning each group's name and linear film
+/// in the order of [`scene::Scene::light_groups`], followed by `"default"` for emitters
+/// without a group (including the sky).
+///
+/// Every pixel draws the same samples as [`raytrace_film`], so the films add up to the
+/// regular render and can be rebalanced in compositing. Paths are always traced one at a
+/// time, whatever `render.integrator` says.
+pub fn raytrace_light_groups(render: &render::Render) -> Vec<(String, film::Film)> {
+    let (width, height) = (render.image_width(), render.image_height());
+    let group_count = render.scene.light_groups.len();
+    let films: Vec<film::Film> = (0..=group_count)
+        .map(|_| film::Film::with_precision(width, height, render.film_precision))
+        .collect();
+    let sampler = MonteCarloSampler::new(
+        render.samples,
+        render.depth,
+        &render.camera,
+        &render.scene,
+        trace_ray,
+    )
+    .with_ray_ranges(render.ray_ranges);
+
+    (0..height).into_par_iter().for_each(|y| {
+        for x in 0..width {
+            let mut rng = render.rng.pixel_rng(render.frame_seed(), x, y);
+            let groups =
+                sampler.sample_pixel_light_groups(&mut rng, x, y, width, height, group_count);
+            for (film, radiance) in films.iter().zip(groups) {
+                film.splat(x, y, radiance);
+            }
+        }
+    });
+
+    let names = render
+        .scene
+        .light_groups
+        .iter()
+        .cloned()
+        .chain(std::iter::once("default".to_string()));
+    names.zip(films).collect()
+}
+
+/// Loads a scene file (or bundle) and renders a fast preview whose longer edge is
+/// `max_edge_px` pixels, for asset browsers and other headless integrations.
+///
+/// The scene's camera keeps its viewing direction and field of view but is re-aimed and
+/// pulled back to frame the scene's content ([`scene::Scene::content_bounds`]), and the
+/// render uses the `draft` preset (including the scene's overrides for it).
+pub fn render_thumbnail(
+    scene_path: &Path,
+    max_edge_px: u32,
+) -> Result<image::RgbImage, scene_file::SceneFileError> {
+    let mut rng = rand::rng();
+    let mut render = scene_file::load_render(&mut rng, scene_path)?;
+
+    if let Some(bounds) = render.scene.content_bounds() {
+        render.camera = render.camera.framing(&bounds);
+    }
+    render.apply_preset(preset::Preset::Draft);
+    let max_edge_px = max_edge_px.clamp(1, resolution::MAX_EDGE);
+    let aspect_ratio = render.camera.aspect_ratio;
+    let (width, height) = if aspect_ratio >= 1.0 {
+        (
+            max_edge_px,
+            (max_edge_px as f32 / aspect_ratio).round() as u32,
+        )
+    } else {
+        (
+            (max_edge_px as f32 * aspect_ratio).round() as u32,
+            max_edge_px,
+        )
+    };
+    render.set_resolution(resolution::Resolution::new(width.max(1), height.max(1))?);
+    render.scale = 1.0;
 
-    println!("Wall time: {}", format_duration(wall_time));
+    let render_start = time::Instant::now();
+    let rays_before = render.scene.rays_traced();
+    let mut data = render.encode(&raytrace_film(&render));
+    stamp_stats(&render, &mut data, render_start.elapsed(), rays_before);
+    Ok(
+        image::RgbImage::from_raw(render.image_width(), render.image_height(), data)
+            .expect("film size matches the render's image size"),
+    )
+}
 
-    image_data
+/// Renders one chunk with the render's integrator and reports it to the render's event
+/// sink.
+pub(crate) fn raytrace_chunk(render: &render::Render, bounds: ChunkBounds) -> ChunkOutput {
+    let chunk = match render.integrator {
+        integrators::Integrator::Megakernel => trace_chunk(render, bounds),
+        integrators::Integrator::Wavefront => integrators::wavefront::render_chunk(render, bounds),
+        integrators::Integrator::Sppm => integrators::sppm::render_chunk(render, bounds),
+    };
+    render.emit(events::RenderEvent::TileDone {
+        x_start: bounds.x_start,
+        x_end: bounds.x_end,
+        y_start: bounds.y_start,
+        y_end: bounds.y_end,
+        samples: render.samples,
+    });
+    chunk
 }
 
-pub(crate) fn raytrace_chunk(
-    rng: &mut rand::rngs::ThreadRng,
-    render: &render::Render,
-    bounds: ChunkBounds,
-) -> ChunkOutput {
-    let height = image_height(render);
+/// Path traces one chunk, a camera path at a time.
+fn trace_chunk(render: &render::Render, bounds: ChunkBounds) -> ChunkOutput {
+    let height = render.image_height();
     let sampler = MonteCarloSampler::new(
         render.samples,
         render.depth,
         &render.camera,
         &render.scene,
         trace_ray,
-    );
-    let row_width = bounds.width() as usize * 3;
-    let mut data = Vec::with_capacity(row_width * bounds.height() as usize);
-
-    for y in bounds.y_start..bounds.y_end {
-        for x in bounds.x_start..bounds.x_end {
-            let mut col = sampler.sample_pixel(rng, x, y, render.width, height);
-            col = col.sqrt(); // Gamma correction
-
-            data.push((col.x * 255.99) as u8);
-            data.push((col.y * 255.99) as u8);
-            data.push((col.z * 255.99) as u8);
+    )
+    .with_pixel_order(render.pixel_order)
+    .with_ray_ranges(render.ray_ranges);
+    let mut data = vec![vec::Vec3::default(); bounds.width() as usize * bounds.height() as usize];
+
+    let pixels =
+        sampler
+            .pixel_order()
+            .pixels(bounds.x_start, bounds.x_end, bounds.y_start, bounds.y_end);
+    for (x, y) in pixels {
+        if render.interrupted() {
+            break;
         }
+        let mut rng = render.rng.pixel_rng(render.frame_seed(), x, y);
+        let col = sampler.sample_pixel(&mut rng, x, y, render.image_width(), height);
+        write_pixel(&mut data, &bounds, x, y, col);
     }
 
     ChunkOutput { bounds, data }
 }
 
-fn trace_ray(
-    rng: &mut rand::rngs::ThreadRng,
+/// Stores a linear pixel color in a chunk buffer.
+pub(crate) fn write_pixel(
+    data: &mut [vec::Vec3],
+    bounds: &ChunkBounds,
+    x: u32,
+    y: u32,
+    col: vec::Vec3,
+) {
+    let offset =
+        (y - bounds.y_start) as usize * bounds.width() as usize + (x - bounds.x_start) as usize;
+    data[offset] = col;
+}
+
+pub(crate) fn trace_ray(
+    rng: &mut rng::SampleRng,
     scene: &scene::Scene,
     ray: &ray::Ray,
-    max_depth: u32,
+    state: &mut path::PathState,
 ) -> vec::Vec3 {
     let mut current_ray = *ray;
     let mut throughput = vec::Vec3::new(1.0, 1.0, 1.0);
     let mut radiance = vec::Vec3::new(0.0, 0.0, 0.0);
-    let mut remaining_depth = max_depth;
 
-    loop {
-        let Some(hit_record) = scene.hit(&current_ray, 0.001, f32::MAX) else {
-            // no hit, no color contribution
-            break;
-        };
-
-        let emitted = hit_record.renderable.emit(&hit_record);
-        let scatter_record = if remaining_depth > 0 {
-            hit_record
-                .renderable
-                .scatter(rng, &hit_record, remaining_depth)
-        } else {
-            None
-        };
-
-        radiance = radiance + throughput * emitted;
+    // no hit, no color contribution
+    while let Some(hit_record) = state.ray_range().hit(scene, &current_ray) {
+        match shade_hit(
+            rng,
+            scene,
+            &hit_record,
+            state,
+            &mut throughput,
+            &mut radiance,
+        ) {
+            Some(next_ray) => current_ray = next_ray,
+            None => break,
+        }
+    }
 
-        let Some(scatter_record) = scatter_record else {
-            break;
-        };
+    radiance
+}
 
-        remaining_depth = remaining_depth.saturating_sub(1);
+/// Shades a single path vertex: adds the emission at the hit to `radiance`, scatters the
+/// path, and folds the scatter weight into `throughput`.
+///
+/// Returns the continuation ray, or `None` once the path terminates.
+pub(crate) fn shade_hit(
+    rng: &mut rng::SampleRng,
+    scene: &scene::Scene,
+    hit_record: &hittable::HitRecord<'_>,
+    state: &mut path::PathState,
+    throughput: &mut vec::Vec3,
+    radiance: &mut vec::Vec3,
+) -> Option<ray::Ray> {
+    if state.last_bounce == path::BounceKind::Camera
+        && !hit_record.renderable.camera_visible(hit_record)
+    {
+        // Camera rays pass through lights hidden from view.
+        return Some(ray::Ray::new(
+            &hit_record.hit.point,
+            &hit_record.hit.ray.direction,
+            Some(hit_record.hit.ray.time),
+        ));
+    }
+    let emitted = scene.emitted(hit_record);
+    let depth_limited = state.out_of_bounces(hit_record.renderable.max_bounces(hit_record));
+    let scatter_record = if depth_limited {
+        None
+    } else {
+        hit_record.renderable.scatter(rng, hit_record, state)
+    };
 
-        if let Some(specular_ray) = scatter_record.scattered_ray {
-            throughput = throughput * scatter_record.attenuation;
-            current_ray = specular_ray;
-            continue;
+    let incoming = *throughput;
+    let log = |event: path::VertexEvent, scattered: Option<vec::Vec3>| {
+        let object = scene
+            .renderables
+            .objects
+            .iter()
+            .position(|object| std::ptr::addr_eq(object.as_ref(), hit_record.renderable));
+        move || path::PathVertex {
+            object,
+            t: hit_record.hit.t,
+            point: hit_record.hit.point,
+            normal: hit_record.hit.normal,
+            emitted,
+            throughput: incoming,
+            event,
+            scattered,
         }
+    };
 
-        let Some(scatter_pdf) = scatter_record.scatter_pdf.as_ref() else {
-            break;
-        };
+    let contribution = *throughput * emitted;
+    *radiance = *radiance + contribution;
+    state.record_emission(hit_record.renderable.light_group(), contribution);
 
-        let mut mixed_pdf: Option<pdf::MixturePDF<'_>> = None;
-        let sample_pdf: &dyn pdf::PDF = if scatter_record.use_light_pdf {
-            if let Some(pdf) = scene.light_pdf(&hit_record, scatter_pdf.as_ref()) {
-                mixed_pdf = Some(pdf);
-                mixed_pdf.as_ref().unwrap()
+    let Some(scatter_record) = scatter_record else {
+        if state.log.is_some() {
+            let event = if depth_limited {
+                path::VertexEvent::DepthLimit
             } else {
-                scatter_pdf.as_ref()
-            }
-        } else {
-            scatter_pdf.as_ref()
-        };
+                path::VertexEvent::Absorbed
+            };
+            state.log_vertex(log(event, None));
+        }
+        return None;
+    };
 
-        let scatter_direction = sample_pdf.generate(rng);
-        let scattered_ray = ray::Ray::new(
-            &hit_record.hit.point,
-            &scatter_direction,
-            Some(hit_record.hit.ray.time),
-        );
+    if let Some(specular_ray) = scatter_record.scattered_ray {
+        state.record_bounce(path::BounceKind::Specular);
+        if state.log.is_some() {
+            let event = path::VertexEvent::Specular {
+                attenuation: scatter_record.attenuation,
+            };
+            state.log_vertex(log(event, Some(specular_ray.direction)));
+        }
+        *throughput = *throughput * scatter_record.attenuation;
+        return Some(specular_ray);
+    }
 
-        let pdf_value = sample_pdf.value(scattered_ray.direction);
-        if pdf_value <= 0.0 {
-            break;
+    let Some(scatter_pdf) = scatter_record.scatter_pdf.as_ref() else {
+        if state.log.is_some() {
+            state.log_vertex(log(path::VertexEvent::Absorbed, None));
         }
+        return None;
+    };
+    state.record_bounce(path::BounceKind::Diffuse);
 
-        if scatter_record.use_light_pdf && mixed_pdf.is_some() {
-            let scattering_pdf = scatter_pdf.value(scattered_ray.direction);
-            throughput = throughput * scatter_record.attenuation * scattering_pdf / pdf_value;
+    let mut mixed_pdf: Option<pdf::MixturePDF<'_>> = None;
+    let sample_pdf: &dyn pdf::PDF = if scatter_record.use_light_pdf {
+        if let Some(pdf) = scene.light_pdf(hit_record, scatter_pdf.as_ref()) {
+            mixed_pdf = Some(pdf);
+            mixed_pdf.as_ref().unwrap()
         } else {
-            throughput = throughput * scatter_record.attenuation;
+            scatter_pdf.as_ref()
         }
-        current_ray = scattered_ray;
+    } else {
+        scatter_pdf.as_ref()
+    };
+
+    let scatter_direction = sample_pdf.generate(rng);
+    let scattered_ray = ray::Ray::new(
+        &hit_record.hit.point,
+        &scatter_direction,
+        Some(hit_record.hit.ray.time),
+    );
+
+    let pdf_value = sample_pdf.value(scattered_ray.direction);
+    if state.log.is_some() {
+        let event = if pdf_value <= 0.0 {
+            path::VertexEvent::ZeroPdf
+        } else {
+            path::VertexEvent::Diffuse {
+                attenuation: scatter_record.attenuation,
+                scatter_pdf: scatter_pdf.value(scattered_ray.direction),
+                sample_pdf: pdf_value,
+                light_sampling: mixed_pdf.is_some(),
+            }
+        };
+        let scattered = (pdf_value > 0.0).then_some(scattered_ray.direction);
+        state.log_vertex(log(event, scattered));
+    }
+    if pdf_value <= 0.0 {
+        return None;
     }
 
-    radiance
+    let scattering = scatter_pdf.scattering(scattered_ray.direction);
+    if scatter_record.use_light_pdf && mixed_pdf.is_some() {
+        *throughput = *throughput * scatter_record.attenuation * scattering / pdf_value;
+    } else {
+        // Exactly 1 for materials whose PDF matches their scattering.
+        *throughput = *throughput * scatter_record.attenuation * (scattering / pdf_value);
+    }
+    Some(scattered_ray)
 }
 
-pub(crate) fn assemble_chunks(chunks: &[ChunkOutput], width: u32, height: u32) -> Vec<u8> {
-    let frame_row_stride = width as usize * 3;
-    let mut image = vec![0_u8; frame_row_stride * height as usize];
+pub(crate) fn assemble_chunks(chunks: &[ChunkOutput], render: &render::Render) -> Vec<u8> {
+    render.encode(&film_from_chunks(chunks, render))
+}
 
+pub(crate) fn film_from_chunks(chunks: &[ChunkOutput], render: &render::Render) -> film::Film {
+    let film = film::Film::with_precision(
+        render.image_width(),
+        render.image_height(),
+        render.film_precision,
+    );
     for chunk in chunks {
-        let chunk_row_stride = chunk.bounds.width() as usize * 3;
-        for (row_idx, y) in (chunk.bounds.y_start..chunk.bounds.y_end).enumerate() {
-            let dest_row = (height - 1 - y) as usize;
-            let dest_offset = dest_row * frame_row_stride + chunk.bounds.x_start as usize * 3;
-            let src_offset = row_idx * chunk_row_stride;
-            let src_end = src_offset + chunk_row_stride;
-
-            image[dest_offset..dest_offset + chunk_row_stride]
-                .copy_from_slice(&chunk.data[src_offset..src_end]);
+        splat_chunk(&film, chunk);
+    }
+    film
+}
+
+/// Writes a finished chunk's pixels into the film.
+fn splat_chunk(film: &film::Film, chunk: &ChunkOutput) {
+    let mut colors = chunk.data.iter();
+    for y in chunk.bounds.y_start..chunk.bounds.y_end {
+        for x in chunk.bounds.x_start..chunk.bounds.x_end {
+            film.splat(x, y, *colors.next().unwrap());
         }
     }
+}
 
-    image
+/// Stamps the statistics of a full-frame render that started when the scene had traced
+/// `rays_before` rays, if the render asks for them.
+fn stamp_stats(
+    render: &render::Render,
+    data: &mut [u8],
+    elapsed: time::Duration,
+    rays_before: u64,
+) {
+    let stats = overlay::RenderStats {
+        samples: render.samples as f32,
+        elapsed,
+        rays: render.scene.rays_traced() - rays_before,
+    };
+    render.stamp_stats(data, &stats);
 }
 
 fn format_duration(dur: time::Duration) -> String {
@@ -1,6 +1,20 @@
 //! Material implementations controlling how rays scatter or attenuate light.
+pub mod anisotropic;
+pub mod blinn_phong;
+pub mod bounce_limit;
+pub mod conductor;
 pub mod dielectric;
 pub mod diffuse_light;
 pub mod instance;
 pub mod lambertian;
+pub mod layered;
+pub mod lobes;
+pub mod measured;
 pub mod metallic;
+pub mod normal_map;
+pub mod opacity;
+pub mod plastic;
+pub mod principled;
+pub mod toon;
+pub mod two_sided;
+pub mod velvet;
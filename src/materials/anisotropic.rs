@@ -0,0 +1,110 @@
+//! Brushed metal with an anisotropic GGX reflection.
+//!
+//! Brushing, machining, or hair fibers leave a surface smoother along one direction than
+//! across it, which stretches highlights into streaks perpendicular to the grain. The
+//! grain follows either a fixed direction or the surface's `u` texture direction
+//! ([`hittable::Hit::tangent`]), so spun-metal disks and brushed panels come out of
+//! ordinary spheres and quads.
+use serde::{Deserialize, Serialize};
+
+use crate::core::{path, ray};
+use crate::math::pdf::ggx;
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// Metal with separate roughness along and across its grain.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Anisotropic {
+    pub albedo: vec::Vec3,
+    /// Roughness along the grain, with `alpha = roughness²` as for [`ggx::GgxPDF`].
+    pub roughness_u: f32,
+    /// Roughness across the grain.
+    pub roughness_v: f32,
+    /// World-space direction of the grain, projected onto the surface at each hit. When
+    /// omitted the grain runs along the surface's `u` direction, or an arbitrary one on
+    /// geometry that doesn't report it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tangent: Option<vec::Vec3>,
+}
+
+impl Anisotropic {
+    /// Creates a brushed metal whose grain follows the surface's `u` direction;
+    /// roughnesses are clamped to `[0, 1]`.
+    pub fn new(albedo: &vec::Vec3, roughness_u: f32, roughness_v: f32) -> Self {
+        Anisotropic {
+            albedo: *albedo,
+            roughness_u: roughness_u.clamp(0.0, 1.0),
+            roughness_v: roughness_v.clamp(0.0, 1.0),
+            tangent: None,
+        }
+    }
+
+    /// Runs the grain along `tangent`, in world space.
+    pub fn with_tangent(mut self, tangent: vec::Vec3) -> Self {
+        self.tangent = Some(tangent);
+        self
+    }
+}
+
+impl Scatterable for Anisotropic {
+    /// Samples the stretched GGX lobe, or reflects like a mirror when it's too smooth in
+    /// both directions to sample.
+    fn scatter(
+        &self,
+        _rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        if state.depth == 0 {
+            return None;
+        }
+
+        let hit = hit_record.hit;
+        let toward_viewer = -vec::unit_vector(&hit.ray.direction);
+        let normal = if hit.normal.dot(&toward_viewer) < 0.0 {
+            -hit.normal
+        } else {
+            hit.normal
+        };
+        let (alpha_u, alpha_v) = (
+            self.roughness_u * self.roughness_u,
+            self.roughness_v * self.roughness_v,
+        );
+        if alpha_u.max(alpha_v) < ggx::MIN_ALPHA {
+            let reflected = vec::reflect(&-toward_viewer, &normal);
+            return Some(ScatterRecord {
+                attenuation: self.albedo,
+                scatter_pdf: None,
+                scattered_ray: Some(ray::Ray::new(&hit.point, &reflected, Some(hit.ray.time))),
+                use_light_pdf: false,
+            });
+        }
+
+        // A zero tangent falls back to an arbitrary grain in `anisotropic`.
+        let tangent = self
+            .tangent
+            .or(hit.tangent)
+            .unwrap_or(vec::Vec3::new(0.0, 0.0, 0.0));
+        Some(ScatterRecord {
+            attenuation: self.albedo,
+            scatter_pdf: Some(Box::new(ggx::GgxPDF::anisotropic(
+                &normal,
+                &tangent,
+                &toward_viewer,
+                alpha_u,
+                alpha_v,
+            ))),
+            scattered_ray: None,
+            use_light_pdf: true,
+        })
+    }
+
+    fn emit(&self, _hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        vec::Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,91 @@
+//! The Blinn-Phong shading model of legacy and real-time renderers.
+//!
+//! A diffuse color plus a specular highlight whose tightness is set by a Phong
+//! `exponent` (shininess) instead of a roughness: 1 is a broad sheen, 100 a plastic
+//! highlight, and thousands a near mirror. Handy for matching scenes and images from
+//! older renderers, and for teaching how the ad hoc model compares with microfacet ones.
+//! Scatters pick the diffuse or the specular lobe as [`lobes`] describes, sampling the
+//! highlight with [`crate::math::pdf::blinn_phong::BlinnPhongPDF`].
+use serde::{Deserialize, Serialize};
+
+use crate::core::path;
+use crate::materials::lobes;
+use crate::math::pdf::{blinn_phong::BlinnPhongPDF, cosine};
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// Blinn-Phong material parameters. The diffuse and specular colors should sum to at
+/// most 1 per channel for the surface not to reflect more light than it receives.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BlinnPhong {
+    pub diffuse: vec::Vec3,
+    #[serde(default = "default_specular")]
+    pub specular: vec::Vec3,
+    /// Phong exponent; higher is shinier.
+    #[serde(default = "default_exponent")]
+    pub exponent: f32,
+}
+
+fn default_specular() -> vec::Vec3 {
+    vec::Vec3::new(0.04, 0.04, 0.04)
+}
+
+fn default_exponent() -> f32 {
+    32.0
+}
+
+impl BlinnPhong {
+    /// A `diffuse` surface with a faint white highlight of exponent 32.
+    pub fn new(diffuse: &vec::Vec3) -> Self {
+        BlinnPhong {
+            diffuse: *diffuse,
+            specular: default_specular(),
+            exponent: default_exponent(),
+        }
+    }
+
+    /// A highlight of color `specular` and tightness `exponent`.
+    pub fn with_specular(mut self, specular: &vec::Vec3, exponent: f32) -> Self {
+        self.specular = *specular;
+        self.exponent = exponent;
+        self
+    }
+}
+
+impl Scatterable for BlinnPhong {
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        if state.depth == 0 {
+            return None;
+        }
+
+        let hit = hit_record.hit;
+        let toward_viewer = -vec::unit_vector(&hit.ray.direction);
+        let normal = if toward_viewer.dot(&hit.normal) > 0.0 {
+            hit.normal
+        } else {
+            -hit.normal
+        };
+
+        lobes::scatter_either(
+            rng,
+            self.diffuse,
+            || Box::new(cosine::CosinePDF::new(&normal)),
+            self.specular,
+            || Box::new(BlinnPhongPDF::new(&normal, &toward_viewer, self.exponent)),
+        )
+    }
+
+    fn emit(&self, _hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        vec::Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
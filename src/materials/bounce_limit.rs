@@ -0,0 +1,78 @@
+//! Per-material bounce budgets.
+//!
+//! Every path shares one bounce budget, the render's `depth`. A [`BounceLimit`] lets a
+//! material stop paths sooner: it scatters only while the path has bounced fewer than
+//! `max_bounces` times, so diffuse interreflection can be cut off after a few bounces
+//! while glass and mirrors keep going up to the full depth. Integrators check the limit
+//! with [`path::PathState::out_of_bounces`] wherever they check the shared budget.
+//!
+//! The wrapper is transparent to everything else: [`Scatterable::as_any`] exposes the
+//! wrapped material, so code looking for a particular material type still finds it.
+use std::sync::Arc;
+
+use crate::core::path;
+use crate::materials::normal_map;
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// A material that scatters only the first `max_bounces` bounces of a path.
+#[derive(Clone)]
+pub struct BounceLimit {
+    material: Arc<dyn Scatterable + Send + Sync>,
+    max_bounces: u32,
+}
+
+impl BounceLimit {
+    pub fn new(material: Arc<dyn Scatterable + Send + Sync>, max_bounces: u32) -> Self {
+        BounceLimit {
+            material,
+            max_bounces,
+        }
+    }
+
+    /// The limited material.
+    pub fn material(&self) -> &Arc<dyn Scatterable + Send + Sync> {
+        &self.material
+    }
+}
+
+impl Scatterable for BounceLimit {
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        self.material.scatter(rng, hit_record, state)
+    }
+
+    fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        self.material.emit(hit_record)
+    }
+
+    /// The tighter of this limit and any on the wrapped material.
+    fn max_bounces(&self) -> Option<u32> {
+        Some(
+            self.material
+                .max_bounces()
+                .map_or(self.max_bounces, |inner| inner.min(self.max_bounces)),
+        )
+    }
+
+    fn camera_visible(&self, hit_record: &hittable::HitRecord) -> bool {
+        self.material.camera_visible(hit_record)
+    }
+
+    fn normal_map(&self) -> Option<&normal_map::NormalMap> {
+        self.material.normal_map()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self.material.as_any()
+    }
+
+    fn material_name(&self) -> &'static str {
+        self.material.material_name()
+    }
+}
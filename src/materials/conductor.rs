@@ -0,0 +1,153 @@
+//! Metal described by its complex index of refraction.
+//!
+//! A [`Conductor`] reflects with the full Fresnel equations for a conductor, per RGB
+//! channel, from the real part `eta` and the extinction coefficient `k` of the metal's
+//! refractive index. Color comes from physics instead of a hand-picked tint: gold and
+//! copper are saturated head on and whiten toward grazing angles, as real metals do.
+//! Measured values for common metals are built in as [`Metal`] presets.
+use serde::{Deserialize, Serialize};
+
+use crate::core::{path, ray};
+use crate::math::pdf::ggx;
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// The metal a [`Conductor`] is made of: a measured preset, selected by name in scene
+/// files (`metal = "Gold"`), or `{ Custom = { eta = [...], k = [...] } }`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Metal {
+    Gold,
+    Copper,
+    Silver,
+    Aluminum,
+    /// Complex refractive index `eta + ik` at the red, green, and blue wavelengths.
+    Custom {
+        eta: vec::Vec3,
+        k: vec::Vec3,
+    },
+}
+
+impl Metal {
+    /// Real and imaginary parts of the refractive index at the red, green, and blue
+    /// wavelengths (roughly 650, 550, and 450 nm).
+    pub fn eta_k(&self) -> (vec::Vec3, vec::Vec3) {
+        match *self {
+            Metal::Gold => (
+                vec::Vec3::new(0.143, 0.374, 1.442),
+                vec::Vec3::new(3.983, 2.385, 1.603),
+            ),
+            Metal::Copper => (
+                vec::Vec3::new(0.200, 0.924, 1.102),
+                vec::Vec3::new(3.912, 2.452, 2.142),
+            ),
+            Metal::Silver => (
+                vec::Vec3::new(0.155, 0.117, 0.138),
+                vec::Vec3::new(4.828, 3.122, 2.147),
+            ),
+            Metal::Aluminum => (
+                vec::Vec3::new(1.657, 0.880, 0.521),
+                vec::Vec3::new(9.224, 6.269, 4.837),
+            ),
+            Metal::Custom { eta, k } => (eta, k),
+        }
+    }
+}
+
+/// A metal reflecting with conductor Fresnel, spread by a GGX lobe when rough.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Conductor {
+    pub metal: Metal,
+    /// Microfacet roughness, with `alpha = roughness²` as for [`ggx::GgxPDF`]; 0 is a
+    /// perfect mirror.
+    #[serde(default)]
+    pub roughness: f32,
+}
+
+impl Conductor {
+    /// Creates a conductor of `metal`; roughness is clamped to `[0, 1]`.
+    pub fn new(metal: Metal, roughness: f32) -> Self {
+        Conductor {
+            metal,
+            roughness: roughness.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Fraction of light reflected per channel at `cos_theta` from the normal.
+    pub fn reflectance(&self, cos_theta: f32) -> vec::Vec3 {
+        let (eta, k) = self.metal.eta_k();
+        vec::Vec3::new(
+            fresnel(cos_theta, eta.x, k.x),
+            fresnel(cos_theta, eta.y, k.y),
+            fresnel(cos_theta, eta.z, k.z),
+        )
+    }
+}
+
+impl Scatterable for Conductor {
+    /// Reflects like a mirror, or samples a GGX lobe when rough, tinted by the Fresnel
+    /// reflectance at the viewing angle.
+    fn scatter(
+        &self,
+        _rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        if state.depth == 0 {
+            return None;
+        }
+
+        let hit = hit_record.hit;
+        let toward_viewer = -vec::unit_vector(&hit.ray.direction);
+        let normal = if hit.normal.dot(&toward_viewer) < 0.0 {
+            -hit.normal
+        } else {
+            hit.normal
+        };
+        let attenuation = self.reflectance(toward_viewer.dot(&normal));
+
+        let alpha = self.roughness * self.roughness;
+        if alpha < ggx::MIN_ALPHA {
+            let reflected = vec::reflect(&-toward_viewer, &normal);
+            return Some(ScatterRecord {
+                attenuation,
+                scatter_pdf: None,
+                scattered_ray: Some(ray::Ray::new(&hit.point, &reflected, Some(hit.ray.time))),
+                use_light_pdf: false,
+            });
+        }
+        Some(ScatterRecord {
+            attenuation,
+            scatter_pdf: Some(Box::new(ggx::GgxPDF::new(&normal, &toward_viewer, alpha))),
+            scattered_ray: None,
+            use_light_pdf: true,
+        })
+    }
+
+    fn emit(&self, _hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        vec::Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Unpolarized Fresnel reflectance of a conductor with refractive index `eta + ik`, seen
+/// from air at `cos_theta` from the normal.
+fn fresnel(cos_theta: f32, eta: f32, k: f32) -> f32 {
+    let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let a = (0.5 * (a2b2 + t0)).max(0.0).sqrt();
+
+    let t1 = a2b2 + cos2;
+    let t2 = 2.0 * cos2.sqrt() * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    (0.5 * (rs + rp)).clamp(0.0, 1.0)
+}
@@ -1,31 +1,170 @@
-use rand::rngs;
-
-use crate::math::vec;
+use crate::core::{color, path, photometry, ray};
+use crate::math::{distribution, rng, vec};
+use crate::textures::uv;
 use crate::traits::scatterable::{ScatterRecord, Scatterable};
 use crate::traits::{hittable, texturable};
 
+/// Largest emission map built for an image-textured light, per side; bigger images are
+/// sampled down to this many cells.
+const EMISSION_MAP_RESOLUTION: u32 = 256;
+
+/// Share of the average brightness added to every emission map cell, so dark texels
+/// next to bright ones (which bilinear filtering bleeds into) are still sampled.
+const EMISSION_MAP_FLOOR: f32 = 0.01;
+
 pub struct DiffuseLight {
     pub texture: Box<dyn texturable::Texturable + Send + Sync>,
+    /// Brightness of an image texture over `(u, v)`, built once from `texture`; `None`
+    /// for textures that don't vary with `(u, v)` alone.
+    emission_map: Option<distribution::Distribution2D>,
+    /// Output the light was fitted to by [`DiffuseLight::with_power`].
+    power: Option<photometry::LightPower>,
+    /// Factor applied to the texture's emission.
+    scale: f32,
+    /// Multiplier on the texture's emission, set by [`DiffuseLight::with_intensity`].
+    intensity: f32,
+    /// Whether only the side the normal points to emits.
+    single_sided: bool,
+    /// Whether camera rays see the light; hidden lights still light the scene.
+    camera_visible: bool,
 }
 
 impl DiffuseLight {
     pub fn new(texture: Box<dyn texturable::Texturable + Send + Sync>) -> Self {
-        DiffuseLight { texture }
+        let emission_map = texture
+            .as_any()
+            .downcast_ref::<uv::UvTexture>()
+            .map(|image| build_emission_map(texture.as_ref(), image.dimensions()));
+        DiffuseLight {
+            texture,
+            emission_map,
+            power: None,
+            scale: 1.0,
+            intensity: 1.0,
+            single_sided: false,
+            camera_visible: true,
+        }
+    }
+
+    /// Multiplies the texture's emission by `intensity`, so a light can be brighter than
+    /// its texture's colors go. A light fitted to a power keeps that power regardless.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Makes the light emit only from the side its normal points to, like a panel or a
+    /// spotlight's face; its back is black.
+    pub fn with_single_sided(mut self, single_sided: bool) -> Self {
+        self.single_sided = single_sided;
+        self
+    }
+
+    /// Hides the light from camera rays, which pass through it to whatever is behind,
+    /// while it still lights the scene through bounces and light sampling.
+    pub fn with_camera_visible(mut self, camera_visible: bool) -> Self {
+        self.camera_visible = camera_visible;
+        self
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn single_sided(&self) -> bool {
+        self.single_sided
+    }
+
+    pub fn camera_visible(&self) -> bool {
+        self.camera_visible
+    }
+
+    /// Radiance emitted at `hit`, before any power fit.
+    fn radiance(&self, hit: &hittable::Hit) -> vec::Vec3 {
+        if self.single_sided && hit.ray.direction.dot(&hit.normal) >= 0.0 {
+            return vec::Vec3::new(0.0, 0.0, 0.0);
+        }
+        self.texture.sample(hit) * self.intensity
+    }
+
+    /// Scales the emission so the light gives off `power` in total when covering
+    /// `geometry` (see [`photometry::emitted_flux`]), keeping the texture's colors and
+    /// relative brightness. Radiance is then in nits, for geometry in meters.
+    ///
+    /// `None` if the geometry can't draw points on its surface or the texture is black.
+    pub fn with_power(
+        mut self,
+        power: photometry::LightPower,
+        geometry: &dyn hittable::Hittable,
+    ) -> Option<Self> {
+        let flux = photometry::emitted_flux(geometry, |hit| self.radiance(hit))?;
+        if flux <= 0.0 || !flux.is_finite() {
+            return None;
+        }
+        self.scale = power.lumens() / flux;
+        self.power = Some(power);
+        Some(self)
+    }
+
+    /// Output the light was fitted to, if it was given one.
+    pub fn power(&self) -> Option<photometry::LightPower> {
+        self.power
+    }
+
+    /// Distribution of emitted brightness over the light's `(u, v)` coordinates, used by
+    /// [`crate::geometry::primitives::quad::QuadPDF`] to aim light samples at the bright
+    /// parts of an image-textured quad light (a window photo, say).
+    pub fn emission_map(&self) -> Option<&distribution::Distribution2D> {
+        self.emission_map.as_ref()
     }
 }
 
+/// Tabulates the luminance of `texture` at the centers of a grid over `(u, v)`.
+fn build_emission_map(
+    texture: &dyn texturable::Texturable,
+    (width, height): (u32, u32),
+) -> distribution::Distribution2D {
+    let width = width.clamp(1, EMISSION_MAP_RESOLUTION) as usize;
+    let height = height.clamp(1, EMISSION_MAP_RESOLUTION) as usize;
+    let origin = vec::Point3::default();
+    let mut values: Vec<f32> = (0..width * height)
+        .map(|cell| {
+            let hit = hittable::Hit {
+                ray: ray::Ray::new(&origin, &origin, None),
+                t: 0.0,
+                point: origin,
+                normal: origin,
+                u: ((cell % width) as f32 + 0.5) / width as f32,
+                v: ((cell / width) as f32 + 0.5) / height as f32,
+                material_slot: 0,
+                vertex_color: None,
+                shading_normal: None,
+                tangent: None,
+            };
+            color::luminance(texture.sample(&hit))
+        })
+        .collect();
+    let floor = EMISSION_MAP_FLOOR * values.iter().sum::<f32>() / values.len() as f32;
+    values.iter_mut().for_each(|value| *value += floor);
+    distribution::Distribution2D::new(&values, width, height)
+}
+
 impl Scatterable for DiffuseLight {
     fn scatter(
         &self,
-        _rng: &mut rngs::ThreadRng,
+        _rng: &mut rng::SampleRng,
         _hit_record: &hittable::HitRecord,
-        _depth: u32,
+        _state: &mut path::PathState,
     ) -> Option<ScatterRecord> {
         None
     }
 
     fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3 {
-        self.texture.sample(&hit_record.hit)
+        self.radiance(&hit_record.hit) * self.scale
+    }
+
+    fn camera_visible(&self, _hit_record: &hittable::HitRecord) -> bool {
+        self.camera_visible
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
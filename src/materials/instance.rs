@@ -1,11 +1,22 @@
 use std::sync::Arc;
 
-use crate::math::vec;
+use crate::core::path;
+use crate::geometry::section;
+use crate::math::{rng, vec};
+use crate::traits::hittable::HitRecord;
 use crate::traits::scatterable::{ScatterRecord, Scatterable};
 
 pub struct MaterialInstance {
     pub ref_mat: Arc<dyn Scatterable + Send + Sync>,
     pub albedo: Option<vec::Vec3>,
+    /// Per-slot materials indexed by `Hit::material_slot`; slots past the end fall back to
+    /// `ref_mat`.
+    pub slots: Vec<Arc<dyn Scatterable + Send + Sync>>,
+    /// Index into the scene's light groups that this object's emission is credited to.
+    pub light_group: Option<usize>,
+    /// Material for the caps a [`section::Section`] puts where it cuts through the
+    /// object; `ref_mat` if `None`.
+    pub cap: Option<Arc<dyn Scatterable + Send + Sync>>,
 }
 
 impl MaterialInstance {
@@ -13,6 +24,9 @@ impl MaterialInstance {
         Self {
             ref_mat: mat,
             albedo: None,
+            slots: Vec::new(),
+            light_group: None,
+            cap: None,
         }
     }
 
@@ -20,23 +34,51 @@ impl MaterialInstance {
         self.albedo = Some(albedo);
         self
     }
+
+    pub fn with_slots(mut self, slots: Vec<Arc<dyn Scatterable + Send + Sync>>) -> Self {
+        self.slots = slots;
+        self
+    }
+
+    pub fn with_light_group(mut self, light_group: usize) -> Self {
+        self.light_group = Some(light_group);
+        self
+    }
+
+    pub fn with_cap(mut self, cap: Arc<dyn Scatterable + Send + Sync>) -> Self {
+        self.cap = Some(cap);
+        self
+    }
+
+    /// Resolves the material bound to the slot that was hit.
+    pub fn material_for(&self, hit_record: &HitRecord) -> &Arc<dyn Scatterable + Send + Sync> {
+        if hit_record.hit.material_slot == section::CAP_SLOT {
+            return self.cap.as_ref().unwrap_or(&self.ref_mat);
+        }
+        self.slots
+            .get(hit_record.hit.material_slot)
+            .unwrap_or(&self.ref_mat)
+    }
 }
 
 impl Scatterable for MaterialInstance {
     fn scatter(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
-        hit_record: &crate::traits::hittable::HitRecord,
-        depth: u32,
+        rng: &mut rng::SampleRng,
+        hit_record: &HitRecord,
+        state: &mut path::PathState,
     ) -> Option<ScatterRecord> {
-        let mut scatter_record = self.ref_mat.scatter(rng, hit_record, depth)?;
+        let mut scatter_record = self
+            .material_for(hit_record)
+            .scatter(rng, hit_record, state)?;
         let tint = self.albedo.unwrap_or(vec::Vec3::new(1.0, 1.0, 1.0));
         scatter_record.attenuation = scatter_record.attenuation * tint;
         Some(scatter_record)
     }
 
-    fn emit(&self, hit_record: &crate::traits::hittable::HitRecord) -> vec::Vec3 {
-        self.ref_mat.emit(hit_record) * self.albedo.unwrap_or(vec::Vec3::new(1.0, 1.0, 1.0))
+    fn emit(&self, hit_record: &HitRecord) -> vec::Vec3 {
+        self.material_for(hit_record).emit(hit_record)
+            * self.albedo.unwrap_or(vec::Vec3::new(1.0, 1.0, 1.0))
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
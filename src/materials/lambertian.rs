@@ -1,5 +1,6 @@
 //! Lambertian diffuse material that scatters light uniformly.
-use crate::math::{pdf::cosine, vec};
+use crate::core::path;
+use crate::math::{pdf::cosine, rng, vec};
 use crate::traits::scatterable::{ScatterRecord, Scatterable};
 use crate::traits::{hittable, texturable};
 
@@ -19,11 +20,11 @@ impl Scatterable for Lambertian {
     /// Provides a diffuse scatter record using cosine-weighted hemisphere sampling.
     fn scatter(
         &self,
-        _rng: &mut rand::rngs::ThreadRng,
+        _rng: &mut rng::SampleRng,
         hit_record: &hittable::HitRecord,
-        depth: u32,
+        state: &mut path::PathState,
     ) -> Option<ScatterRecord> {
-        if depth == 0 {
+        if state.depth == 0 {
             return None;
         }
 
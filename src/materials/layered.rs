@@ -0,0 +1,132 @@
+//! Materials built in layers: a coat over a base.
+//!
+//! A [`Layered`] material puts one material on top of another, like a clear lacquer over
+//! car paint or varnish over wood. The coat is a thin film with a refractive index,
+//! usually a smooth white [`crate::materials::metallic::Metallic`] for the glossy
+//! reflection off its top. At each hit the Fresnel reflectance of the coat's surface, by
+//! Schlick's approximation, is the chance the light reflects off the coat; otherwise it
+//! passes into the film and scatters off the base, filtered by the coat's `tint` once on
+//! the way in and once on the way out. Grazing views therefore see mostly coat and head-on
+//! views mostly base, the way lacquered surfaces look.
+//!
+//! Either layer may itself be layered, so stacks of coats compose. Light reflected back
+//! down inside the film is taken to reach the base eventually, so the stack neither gains
+//! nor loses energy at the interface. Hits from behind the surface see only the base.
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::core::path;
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// A `coat` material over a `base` one, split by the coat's Fresnel reflectance.
+#[derive(Clone)]
+pub struct Layered {
+    coat: Arc<dyn Scatterable + Send + Sync>,
+    base: Arc<dyn Scatterable + Send + Sync>,
+    ior: f32,
+    tint: Option<vec::Vec3>,
+}
+
+impl Layered {
+    /// `coat` over `base`, the coat's film having refractive index `ior` (1.5 for most
+    /// lacquers and varnishes).
+    pub fn new(
+        coat: Arc<dyn Scatterable + Send + Sync>,
+        base: Arc<dyn Scatterable + Send + Sync>,
+        ior: f32,
+    ) -> Self {
+        Layered {
+            coat,
+            base,
+            ior,
+            tint: None,
+        }
+    }
+
+    /// Filters light by `tint` each time it crosses the coat to or from the base, as an
+    /// amber varnish does.
+    pub fn with_tint(mut self, tint: vec::Vec3) -> Self {
+        self.tint = Some(tint);
+        self
+    }
+
+    /// The material on top.
+    pub fn coat(&self) -> &Arc<dyn Scatterable + Send + Sync> {
+        &self.coat
+    }
+
+    /// The material under the coat.
+    pub fn base(&self) -> &Arc<dyn Scatterable + Send + Sync> {
+        &self.base
+    }
+
+    pub fn ior(&self) -> f32 {
+        self.ior
+    }
+
+    pub fn tint(&self) -> Option<vec::Vec3> {
+        self.tint
+    }
+
+    /// Share of light arriving at `hit` that reflects off the coat, or zero from behind.
+    pub fn coat_reflectance(&self, hit: &hittable::Hit) -> f32 {
+        let cos_theta = -vec::unit_vector(&hit.ray.direction).dot(&vec::unit_vector(&hit.normal));
+        if cos_theta <= 0.0 {
+            return 0.0;
+        }
+        let r0 = ((1.0 - self.ior) / (1.0 + self.ior)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos_theta.min(1.0)).powi(5)
+    }
+
+    /// Filter light picks up crossing the coat once; white from behind, where there's
+    /// no coat to cross.
+    fn film(&self, hit: &hittable::Hit) -> vec::Vec3 {
+        let white = vec::Vec3::new(1.0, 1.0, 1.0);
+        match self.tint {
+            Some(tint) if hit.ray.direction.dot(&hit.normal) < 0.0 => tint,
+            _ => white,
+        }
+    }
+}
+
+impl Scatterable for Layered {
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        let hit = hit_record.hit;
+        if rng.random::<f32>() < self.coat_reflectance(&hit) {
+            return self.coat.scatter(rng, hit_record, state);
+        }
+        let film = self.film(&hit);
+        self.base.scatter(rng, hit_record, state).map(|mut record| {
+            record.attenuation = record.attenuation * film * film;
+            record
+        })
+    }
+
+    /// The coat's emission plus the base's, seen through the coat.
+    fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        let hit = hit_record.hit;
+        self.coat.emit(hit_record)
+            + self.base.emit(hit_record) * self.film(&hit) * (1.0 - self.coat_reflectance(&hit))
+    }
+
+    /// The looser of the two layers' limits, or none if either layer is unlimited.
+    fn max_bounces(&self) -> Option<u32> {
+        Some(self.coat.max_bounces()?.max(self.base.max_bounces()?))
+    }
+
+    fn camera_visible(&self, hit_record: &hittable::HitRecord) -> bool {
+        self.base.camera_visible(hit_record)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,43 @@
+//! Materials made of two reflection lobes, such as a diffuse base under a highlight.
+//!
+//! Each scatter samples just one of the lobes, picked with chance in proportion to the
+//! brightness of its color, and divides its color by that chance. On average the surface
+//! then reflects the sum of both lobes, while most samples go to the one that contributes
+//! most.
+use rand::Rng;
+
+use crate::math::{pdf, rng, vec};
+use crate::traits::scatterable::ScatterRecord;
+
+/// Scatters by the `base` lobe or the `layer` over it, each given as its color and the
+/// PDF that samples it. `None` if both colors are black.
+pub fn scatter_either(
+    rng: &mut rng::SampleRng,
+    base: vec::Vec3,
+    base_pdf: impl FnOnce() -> Box<dyn pdf::PDF + Send + Sync>,
+    layer: vec::Vec3,
+    layer_pdf: impl FnOnce() -> Box<dyn pdf::PDF + Send + Sync>,
+) -> Option<ScatterRecord> {
+    let base_weight = brightness(&base);
+    let layer_weight = brightness(&layer);
+    if base_weight + layer_weight <= 0.0 {
+        return None;
+    }
+    let layer_chance = layer_weight / (base_weight + layer_weight);
+    let (attenuation, scatter_pdf) = if rng.random::<f32>() < layer_chance {
+        (layer / layer_chance, layer_pdf())
+    } else {
+        (base / (1.0 - layer_chance), base_pdf())
+    };
+    Some(ScatterRecord {
+        attenuation,
+        scatter_pdf: Some(scatter_pdf),
+        scattered_ray: None,
+        use_light_pdf: true,
+    })
+}
+
+/// Average channel of `color`, clamped at zero.
+fn brightness(color: &vec::Vec3) -> f32 {
+    ((color.x + color.y + color.z) / 3.0).max(0.0)
+}
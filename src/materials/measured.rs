@@ -0,0 +1,247 @@
+//! Measured BRDFs from the MERL database.
+//!
+//! The MERL BRDF database (Matusik et al., 2003) holds 100 real materials (paints,
+//! plastics, metals, fabrics) measured with a gonioreflectometer, each a `.binary` table
+//! of isotropic reflectance over the half and difference angles of Rusinkiewicz's
+//! parameterization. A [`Measured`] material looks its reflectance up in such a table,
+//! so validation renders can compare analytic materials against the real thing.
+//!
+//! The table's color changes with direction, so each scatter draws a cosine-weighted
+//! direction itself and weights it by the looked-up BRDF, instead of handing the
+//! integrator a PDF; lights are therefore found only by directions that happen to reach
+//! them. Diffuse and glossy materials converge like Lambertian ones, while near-mirror
+//! metals need many samples.
+use std::fs;
+use std::io;
+use std::sync::Arc;
+
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{assets, path, ray};
+use crate::math::pdf::{PDF, cosine};
+use crate::math::{onb, rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+const THETA_HALF_CELLS: usize = 90;
+const THETA_DIFF_CELLS: usize = 90;
+/// Cells of the difference azimuth over `[0, pi)`; isotropy and reciprocity fold the
+/// rest of the circle onto it.
+const PHI_DIFF_CELLS: usize = 180;
+const CELLS: usize = THETA_HALF_CELLS * THETA_DIFF_CELLS * PHI_DIFF_CELLS;
+
+/// Factors the stored red, green, and blue values are multiplied by.
+const CHANNEL_SCALES: [f32; 3] = [1.0 / 1500.0, 1.15 / 1500.0, 1.66 / 1500.0];
+
+/// A material reflecting as a measured MERL BRDF table.
+#[derive(Clone)]
+pub struct Measured {
+    source: Option<String>,
+    /// Reflectance per cell, each channel's block after the other's, already scaled.
+    table: Arc<Vec<f32>>,
+}
+
+impl Measured {
+    /// Loads a MERL `.binary` table from a local path or `http(s)://` URL.
+    pub fn open(reference: &str) -> io::Result<Self> {
+        let path = assets::resolve(reference)?;
+        let bytes = fs::read(&path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("failed to read MERL BRDF {}: {}", reference, err),
+            )
+        })?;
+        let mut measured = Measured::from_merl_bytes(&bytes).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("failed to read MERL BRDF {}: {}", reference, err),
+            )
+        })?;
+        measured.source = Some(reference.to_string());
+        Ok(measured)
+    }
+
+    /// Parses a table in MERL's format: three little-endian `i32` dimensions (90, 90,
+    /// 180) followed by each channel's `f64` values.
+    pub fn from_merl_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |reason: String| io::Error::new(io::ErrorKind::InvalidData, reason);
+        if bytes.len() < 12 {
+            return Err(invalid(String::from("truncated header")));
+        }
+        let dims: Vec<i32> = bytes[..12]
+            .chunks_exact(4)
+            .map(|dim| i32::from_le_bytes([dim[0], dim[1], dim[2], dim[3]]))
+            .collect();
+        if dims
+            != [
+                THETA_HALF_CELLS as i32,
+                THETA_DIFF_CELLS as i32,
+                PHI_DIFF_CELLS as i32,
+            ]
+        {
+            return Err(invalid(format!(
+                "dimensions {:?}, expected [90, 90, 180]",
+                dims
+            )));
+        }
+        let values = &bytes[12..];
+        if values.len() != 3 * CELLS * 8 {
+            return Err(invalid(format!(
+                "{} bytes of values, expected {}",
+                values.len(),
+                3 * CELLS * 8
+            )));
+        }
+        let table = values
+            .chunks_exact(8)
+            .enumerate()
+            .map(|(index, value)| {
+                let value = f64::from_le_bytes(value.try_into().expect("8-byte chunk"));
+                // Unmeasured cells hold negative values.
+                (value.max(0.0) as f32) * CHANNEL_SCALES[index / CELLS]
+            })
+            .collect();
+        Ok(Measured {
+            source: None,
+            table: Arc::new(table),
+        })
+    }
+
+    /// Where the table was loaded from, if it came from a file.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// The BRDF for light arriving from `incoming` and leaving toward `outgoing`, unit
+    /// vectors in a frame whose `z` is the surface normal; black below the surface.
+    pub fn evaluate(&self, incoming: &vec::Vec3, outgoing: &vec::Vec3) -> vec::Vec3 {
+        if incoming.z <= 0.0 || outgoing.z <= 0.0 {
+            return vec::Vec3::new(0.0, 0.0, 0.0);
+        }
+        let index = cell(incoming, outgoing);
+        vec::Vec3::new(
+            self.table[index],
+            self.table[index + CELLS],
+            self.table[index + 2 * CELLS],
+        )
+    }
+}
+
+/// Index of the table cell holding the pair `incoming`, `outgoing` in the local frame.
+fn cell(incoming: &vec::Vec3, outgoing: &vec::Vec3) -> usize {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    let half = vec::unit_vector(&(*incoming + *outgoing));
+    let theta_half = half.z.clamp(-1.0, 1.0).acos();
+    let phi_half = half.y.atan2(half.x);
+    // The incoming direction seen from the half vector: undo its azimuth, then its tilt.
+    let difference = rotate_y(&rotate_z(incoming, -phi_half), -theta_half);
+    let theta_diff = difference.z.clamp(-1.0, 1.0).acos();
+    let mut phi_diff = difference.y.atan2(difference.x);
+    if phi_diff < 0.0 {
+        phi_diff += PI;
+    }
+
+    // Half angles are spaced by their square root, densest near the highlight.
+    let theta_half_index =
+        ((theta_half / FRAC_PI_2).max(0.0).sqrt() * THETA_HALF_CELLS as f32) as usize;
+    let theta_diff_index = (theta_diff / FRAC_PI_2 * THETA_DIFF_CELLS as f32) as usize;
+    let phi_diff_index = (phi_diff / PI * PHI_DIFF_CELLS as f32) as usize;
+    phi_diff_index.min(PHI_DIFF_CELLS - 1)
+        + theta_diff_index.min(THETA_DIFF_CELLS - 1) * PHI_DIFF_CELLS
+        + theta_half_index.min(THETA_HALF_CELLS - 1) * PHI_DIFF_CELLS * THETA_DIFF_CELLS
+}
+
+fn rotate_z(v: &vec::Vec3, angle: f32) -> vec::Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    vec::Vec3::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos, v.z)
+}
+
+fn rotate_y(v: &vec::Vec3, angle: f32) -> vec::Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    vec::Vec3::new(v.x * cos + v.z * sin, v.y, -v.x * sin + v.z * cos)
+}
+
+/// `v` in `frame`'s coordinates.
+fn to_local(frame: &onb::ONB, v: &vec::Vec3) -> vec::Vec3 {
+    vec::Vec3::new(v.dot(&frame.u), v.dot(&frame.v), v.dot(&frame.w))
+}
+
+impl Scatterable for Measured {
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        if state.depth == 0 {
+            return None;
+        }
+
+        let hit = hit_record.hit;
+        let toward_viewer = -vec::unit_vector(&hit.ray.direction);
+        let normal = vec::unit_vector(&if toward_viewer.dot(&hit.normal) > 0.0 {
+            hit.normal
+        } else {
+            -hit.normal
+        });
+        let frame = onb::ONB::build_from_w(&normal);
+        let sampler = cosine::CosinePDF::new(&normal);
+        let direction = vec::unit_vector(&sampler.generate(rng));
+        let pdf = sampler.value(direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let brdf = self.evaluate(
+            &to_local(&frame, &direction),
+            &to_local(&frame, &toward_viewer),
+        );
+        let cos_incoming = direction.dot(&normal);
+        Some(ScatterRecord {
+            attenuation: brdf * (cos_incoming / pdf),
+            scatter_pdf: None,
+            scattered_ray: Some(ray::Ray::new(&hit.point, &direction, Some(hit.ray.time))),
+            use_light_pdf: false,
+        })
+    }
+
+    fn emit(&self, _hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        vec::Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl Serialize for Measured {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let Some(source) = &self.source else {
+            return Err(serde::ser::Error::custom(
+                "measured BRDFs can only be saved with the `path` they were loaded from",
+            ));
+        };
+        let mut state = serializer.serialize_struct("Measured", 1)?;
+        state.serialize_field("path", source)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Measured {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct MeasuredData {
+            path: String,
+        }
+
+        let data = MeasuredData::deserialize(deserializer)?;
+        Measured::open(&data.path).map_err(serde::de::Error::custom)
+    }
+}
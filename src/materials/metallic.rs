@@ -1,16 +1,54 @@
 //! Reflective metallic material with optional roughness for blurred reflections.
+//!
+//! The reflection is tinted by `albedo`, and by a texture too if one is given, so checker,
+//! noise, or image textures can pattern a metal the way they do diffuse surfaces.
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
-use crate::core::ray;
-use crate::math::vec;
-use crate::traits::hittable;
+use crate::core::{path, ray};
+use crate::math::pdf::ggx;
+use crate::math::{rng, vec};
 use crate::traits::scatterable::{ScatterRecord, Scatterable};
+use crate::traits::{hittable, texturable};
+
+/// How a rough metal spreads its reflection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetalModel {
+    /// Mirror direction jittered by a random point in a sphere of radius `roughness`.
+    /// Cheap, but treated as specular, so it never samples lights and loses energy to
+    /// jitters that dip below the surface.
+    #[default]
+    Fuzz,
+    /// GGX microfacet reflection with `alpha = roughness²`, importance sampled and
+    /// mixed with light sampling like diffuse surfaces, so rough metals converge to a
+    /// physically based highlight. Roughness 0 is still a perfect mirror.
+    Ggx,
+}
+
+impl MetalModel {
+    pub fn is_default(&self) -> bool {
+        *self == MetalModel::default()
+    }
+}
 
 /// Mirror-like surface with an albedo tint and surface roughness.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Metallic {
+    /// Tint of the reflection; white if omitted, for metals colored by their texture.
+    #[serde(default = "default_albedo")]
     pub albedo: vec::Vec3,
     pub roughness: f32,
+    #[serde(default, skip_serializing_if = "MetalModel::is_default")]
+    pub model: MetalModel,
+    /// Texture the albedo is multiplied by at each hit. Scene files describe it beside
+    /// the material's data rather than in it.
+    #[serde(skip)]
+    pub texture: Option<Arc<dyn texturable::Texturable + Send + Sync>>,
+}
+
+fn default_albedo() -> vec::Vec3 {
+    vec::Vec3::new(1.0, 1.0, 1.0)
 }
 
 impl Metallic {
@@ -19,32 +57,75 @@ impl Metallic {
         Metallic {
             albedo: *albedo,
             roughness: if roughness < 1.0 { roughness } else { 1.0 },
+            model: MetalModel::default(),
+            texture: None,
         }
     }
+
+    /// Patterns the reflection with `texture`, multiplied by the albedo.
+    pub fn with_texture(mut self, texture: Arc<dyn texturable::Texturable + Send + Sync>) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    /// The tint of the reflection at `hit`.
+    fn albedo_at(&self, hit: &hittable::Hit) -> vec::Vec3 {
+        match &self.texture {
+            Some(texture) => self.albedo * texture.sample(hit),
+            None => self.albedo,
+        }
+    }
+
+    /// Spreads the reflection with `model` instead of the default fuzz.
+    pub fn with_model(mut self, model: MetalModel) -> Self {
+        self.model = model;
+        self
+    }
 }
 
 impl Scatterable for Metallic {
-    /// Samples a specular reflection with optional fuzziness.
+    /// Samples a specular reflection with optional fuzziness, or a GGX lobe.
     fn scatter(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut rng::SampleRng,
         hit_record: &hittable::HitRecord,
-        depth: u32,
+        state: &mut path::PathState,
     ) -> Option<ScatterRecord> {
-        if depth == 0 {
+        if state.depth == 0 {
             return None;
         }
 
         let hit = hit_record.hit;
+        let alpha = self.roughness * self.roughness;
+        if self.model == MetalModel::Ggx && alpha >= ggx::MIN_ALPHA {
+            let toward_viewer = -vec::unit_vector(&hit.ray.direction);
+            // Reflect off whichever side the ray arrived from.
+            let normal = if hit.normal.dot(&toward_viewer) < 0.0 {
+                -hit.normal
+            } else {
+                hit.normal
+            };
+            return Some(ScatterRecord {
+                attenuation: self.albedo_at(&hit),
+                scatter_pdf: Some(Box::new(ggx::GgxPDF::new(&normal, &toward_viewer, alpha))),
+                scattered_ray: None,
+                use_light_pdf: true,
+            });
+        }
+
         let reflected = vec::reflect(&vec::unit_vector(&hit.ray.direction), &hit.normal);
+        let fuzz = match self.model {
+            MetalModel::Fuzz => self.roughness,
+            MetalModel::Ggx => 0.0,
+        };
         let scattered_ray = ray::Ray::new(
             &hit.point,
-            &(reflected + vec::random_in_unit_sphere(rng) * self.roughness),
+            &(reflected + vec::random_in_unit_sphere(rng) * fuzz),
             Some(hit.ray.time),
         );
 
         Some(ScatterRecord {
-            attenuation: self.albedo,
+            attenuation: self.albedo_at(&hit),
             scatter_pdf: None,
             scattered_ray: Some(scattered_ray),
             use_light_pdf: false,
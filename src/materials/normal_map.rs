@@ -0,0 +1,134 @@
+//! Tangent-space normal maps.
+//!
+//! A [`NormalMap`] adds fine surface detail (bricks, scratches, weave) without more
+//! geometry: it reads a normal from a texture in the surface's tangent frame and bends
+//! the hit normal to it before the wrapped material scatters. Textures store normals the
+//! usual way, each channel mapping `[0, 1]` to `[-1, 1]`: red along the direction `u`
+//! increases ([`hittable::Hit::tangent`]), green along `v` (the OpenGL convention), and
+//! blue out of the surface, so an untouched normal is `(0.5, 0.5, 1)`.
+//!
+//! Geometry that reports no tangent gets an arbitrary frame around the normal, which only
+//! suits maps without a preferred direction. Only scattering sees the bent normal;
+//! emission and shadow rays use the surface's own.
+//!
+//! Like [`crate::materials::bounce_limit::BounceLimit`], the wrapper is transparent to
+//! code looking for a particular material type.
+use std::sync::Arc;
+
+use crate::core::path;
+use crate::math::{onb, rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+use crate::traits::texturable;
+
+/// A material whose shading normals are bent by a tangent-space normal map.
+pub struct NormalMap {
+    material: Arc<dyn Scatterable + Send + Sync>,
+    texture: Box<dyn texturable::Texturable + Send + Sync>,
+    strength: f32,
+}
+
+impl NormalMap {
+    pub fn new(
+        material: Arc<dyn Scatterable + Send + Sync>,
+        texture: Box<dyn texturable::Texturable + Send + Sync>,
+    ) -> Self {
+        NormalMap {
+            material,
+            texture,
+            strength: 1.0,
+        }
+    }
+
+    /// Scales how far the map tilts normals: 0 leaves them flat, 1 is as authored, and
+    /// larger values exaggerate the relief.
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    /// The mapped material.
+    pub fn material(&self) -> &Arc<dyn Scatterable + Send + Sync> {
+        &self.material
+    }
+
+    /// The normal map texture.
+    pub fn texture(&self) -> &dyn texturable::Texturable {
+        self.texture.as_ref()
+    }
+
+    pub fn strength(&self) -> f32 {
+        self.strength
+    }
+
+    /// The world-space normal the map gives `hit`, on the same side as `hit.normal`.
+    pub fn mapped_normal(&self, hit: &hittable::Hit) -> vec::Vec3 {
+        let normal = vec::unit_vector(&hit.normal);
+        let tangent: Option<vec::Vec3> = hit
+            .tangent
+            .map(|tangent| tangent - normal * tangent.dot(&normal))
+            .filter(|tangent| tangent.squared_length() > 1e-12);
+        let (tangent, bitangent) = match tangent {
+            Some(tangent) => {
+                let tangent = tangent.normalize();
+                (tangent, normal.cross(&tangent))
+            }
+            None => {
+                let frame = onb::ONB::build_from_w(&normal);
+                (frame.u, frame.v)
+            }
+        };
+
+        let texel = self.texture.sample(hit) * 2.0_f32 - vec::Vec3::new(1.0, 1.0, 1.0);
+        let mapped = tangent * (texel.x * self.strength)
+            + bitangent * (texel.y * self.strength)
+            + normal * texel.z;
+        // Texels pointing into the surface (or nowhere) leave the normal as it was.
+        if mapped.dot(&normal) <= 1e-6 {
+            return normal;
+        }
+        mapped.normalize()
+    }
+}
+
+impl Scatterable for NormalMap {
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        let mut hit = hit_record.hit;
+        hit.normal = self.mapped_normal(&hit);
+        let mapped = hittable::HitRecord::new(
+            hit,
+            hit_record.renderable.get_pdf(&hit.point, hit.ray.time),
+            hit_record.renderable,
+        );
+        self.material.scatter(rng, &mapped, state)
+    }
+
+    fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        self.material.emit(hit_record)
+    }
+
+    fn max_bounces(&self) -> Option<u32> {
+        self.material.max_bounces()
+    }
+
+    fn camera_visible(&self, hit_record: &hittable::HitRecord) -> bool {
+        self.material.camera_visible(hit_record)
+    }
+
+    fn normal_map(&self) -> Option<&NormalMap> {
+        Some(self)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self.material.as_any()
+    }
+
+    fn material_name(&self) -> &'static str {
+        self.material.material_name()
+    }
+}
@@ -0,0 +1,114 @@
+//! Partly transparent surfaces, for foliage cards, fences, and lace.
+//!
+//! An [`Opacity`] wrapper gives a material an opacity between 0 and 1, a scalar times an
+//! optional mask texture's average channel (usually an image's alpha read with
+//! `alpha = true`, see [`crate::textures::uv::UvTexture`]). At each hit the wrapped
+//! material shades with that probability; otherwise the ray carries straight on through
+//! the surface untouched. Averaged over samples, a half-opaque texel shows half the
+//! material and half of what's behind it, and a leaf card shows only the leaf.
+//!
+//! Paths reaching a light through the surface pass the same share, so cut-out leaves cast
+//! leaf-shaped shadows. Passing through counts as a bounce against the path's budget.
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::core::{path, ray};
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+use crate::traits::texturable;
+
+/// A material that is only there where, and as much as, its opacity says.
+pub struct Opacity {
+    material: Arc<dyn Scatterable + Send + Sync>,
+    opacity: f32,
+    mask: Option<Box<dyn texturable::Texturable + Send + Sync>>,
+}
+
+impl Opacity {
+    /// `material` at `opacity`, from 0 (invisible) to 1 (solid).
+    pub fn new(material: Arc<dyn Scatterable + Send + Sync>, opacity: f32) -> Self {
+        Opacity {
+            material,
+            opacity,
+            mask: None,
+        }
+    }
+
+    /// Multiplies the opacity by `mask`'s average channel at each hit.
+    pub fn with_mask(mut self, mask: Box<dyn texturable::Texturable + Send + Sync>) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// The wrapped material.
+    pub fn material(&self) -> &Arc<dyn Scatterable + Send + Sync> {
+        &self.material
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    /// The mask texture, if there is one.
+    pub fn mask(&self) -> Option<&dyn texturable::Texturable> {
+        self.mask
+            .as_deref()
+            .map(|mask| mask as &dyn texturable::Texturable)
+    }
+
+    /// Chance, in `[0, 1]`, that the surface is there at `hit`.
+    pub fn opacity_at(&self, hit: &hittable::Hit) -> f32 {
+        let mask = self.mask.as_ref().map_or(1.0, |mask| {
+            let color = mask.sample(hit);
+            (color.x + color.y + color.z) / 3.0
+        });
+        (self.opacity * mask).clamp(0.0, 1.0)
+    }
+}
+
+impl Scatterable for Opacity {
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        let hit = hit_record.hit;
+        let opacity = self.opacity_at(&hit);
+        if opacity < 1.0 && rng.random::<f32>() >= opacity {
+            if state.depth == 0 {
+                return None;
+            }
+            return Some(ScatterRecord {
+                attenuation: vec::Vec3::new(1.0, 1.0, 1.0),
+                scatter_pdf: None,
+                scattered_ray: Some(ray::Ray::new(
+                    &hit.point,
+                    &hit.ray.direction,
+                    Some(hit.ray.time),
+                )),
+                use_light_pdf: false,
+            });
+        }
+        self.material.scatter(rng, hit_record, state)
+    }
+
+    /// The wrapped material's emission, scaled by how often the surface is there.
+    fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        self.material.emit(hit_record) * self.opacity_at(&hit_record.hit)
+    }
+
+    fn max_bounces(&self) -> Option<u32> {
+        self.material.max_bounces()
+    }
+
+    fn camera_visible(&self, hit_record: &hittable::HitRecord) -> bool {
+        self.material.camera_visible(hit_record)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,116 @@
+//! Plastic: a diffuse body under a clear, glossy surface.
+//!
+//! Light hitting plastic either reflects off its smooth surface, white and mirror-like,
+//! or enters and scatters off the pigment inside, taking its color. Which one happens is
+//! set by the surface's Fresnel reflectance, by Schlick's approximation from its `ior`:
+//! about 4% head on, rising to all of it at grazing angles, so plastic looks matte and
+//! colored face on and glossy toward its edges. A [`Plastic`] picks the reflection with
+//! that probability and the diffuse body otherwise; `roughness` above 0 spreads the
+//! reflection over a GGX lobe (see [`crate::math::pdf::ggx`]) for satin and frosted
+//! finishes.
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{path, ray};
+use crate::math::pdf::{cosine, ggx};
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// Plastic material parameters.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Plastic {
+    pub color: vec::Vec3,
+    /// Refractive index of the surface; 1.5 for most plastics.
+    #[serde(default = "default_ior")]
+    pub ior: f32,
+    /// Blur of the reflection, from 0 (a mirror) to 1.
+    #[serde(default)]
+    pub roughness: f32,
+}
+
+fn default_ior() -> f32 {
+    1.5
+}
+
+impl Plastic {
+    /// Smooth `color` plastic of refractive index 1.5.
+    pub fn new(color: &vec::Vec3) -> Self {
+        Plastic {
+            color: *color,
+            ior: default_ior(),
+            roughness: 0.0,
+        }
+    }
+
+    pub fn with_ior(mut self, ior: f32) -> Self {
+        self.ior = ior;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    /// Share of light reflected off the surface at `cos_theta` from the normal.
+    pub fn reflectance(&self, cos_theta: f32) -> f32 {
+        let r0 = ((1.0 - self.ior) / (1.0 + self.ior)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+    }
+}
+
+impl Scatterable for Plastic {
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        if state.depth == 0 {
+            return None;
+        }
+
+        let hit = hit_record.hit;
+        let toward_viewer = -vec::unit_vector(&hit.ray.direction);
+        let normal = vec::unit_vector(&if toward_viewer.dot(&hit.normal) > 0.0 {
+            hit.normal
+        } else {
+            -hit.normal
+        });
+
+        let white = vec::Vec3::new(1.0, 1.0, 1.0);
+        if rng.random::<f32>() < self.reflectance(toward_viewer.dot(&normal)) {
+            let alpha = self.roughness * self.roughness;
+            if alpha >= ggx::MIN_ALPHA {
+                return Some(ScatterRecord {
+                    attenuation: white,
+                    scatter_pdf: Some(Box::new(ggx::GgxPDF::new(&normal, &toward_viewer, alpha))),
+                    scattered_ray: None,
+                    use_light_pdf: true,
+                });
+            }
+            let reflected = vec::reflect(&-toward_viewer, &normal);
+            return Some(ScatterRecord {
+                attenuation: white,
+                scatter_pdf: None,
+                scattered_ray: Some(ray::Ray::new(&hit.point, &reflected, Some(hit.ray.time))),
+                use_light_pdf: false,
+            });
+        }
+        Some(ScatterRecord {
+            attenuation: self.color,
+            scatter_pdf: Some(Box::new(cosine::CosinePDF::new(&normal))),
+            scattered_ray: None,
+            use_light_pdf: true,
+        })
+    }
+
+    fn emit(&self, _hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        vec::Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
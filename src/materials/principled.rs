@@ -0,0 +1,251 @@
+//! All-in-one physically based material after Disney's principled BSDF.
+//!
+//! A handful of intuitive parameters blend a diffuse base, a dielectric specular
+//! highlight, metal, glass-like transmission, sheen, and a clear coat, so materials
+//! authored for other PBR renderers (glTF, Blender, Substance) map onto one material.
+//! Each scatter picks one layer at random in proportion to how much light it reflects at
+//! the viewing angle, so every layer is sampled with its own PDF and no weights are lost:
+//! the clear coat first, then metal, then transmission, then the dielectric highlight,
+//! with whatever is left scattering diffusely.
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{path, ray};
+use crate::math::pdf::{cosine, ggx};
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// Reflectance at normal incidence of a dielectric with `specular = 1`; the default 0.5
+/// gives 4%, typical of plastics and paint.
+const SPECULAR_SCALE: f32 = 0.08;
+
+/// Reflectance at normal incidence of the clear coat, a varnish of index 1.5.
+const CLEARCOAT_REFLECTANCE: f32 = 0.04;
+
+/// Principled material parameters, each in `[0, 1]` except `ior`. Scene files may omit
+/// everything but `base_color`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Principled {
+    /// Diffuse color of dielectrics and the reflection color of metals.
+    pub base_color: vec::Vec3,
+    /// Blend from dielectric (0) to metal (1).
+    #[serde(default)]
+    pub metallic: f32,
+    /// Microfacet roughness of the specular and metallic reflections, `alpha = roughness²`.
+    #[serde(default = "default_roughness")]
+    pub roughness: f32,
+    /// Strength of the dielectric highlight; 0.5 is 4% reflectance head on.
+    #[serde(default = "default_specular")]
+    pub specular: f32,
+    /// White retroreflection at grazing angles, for cloth.
+    #[serde(default)]
+    pub sheen: f32,
+    /// Strength of a clear varnish layer over the rest.
+    #[serde(default)]
+    pub clearcoat: f32,
+    #[serde(default = "default_clearcoat_roughness")]
+    pub clearcoat_roughness: f32,
+    /// Blend from opaque (0) to smooth glass tinted by `base_color` (1), for dielectrics.
+    #[serde(default)]
+    pub transmission: f32,
+    /// Refractive index of transmitted light.
+    #[serde(default = "default_ior")]
+    pub ior: f32,
+}
+
+fn default_roughness() -> f32 {
+    0.5
+}
+
+fn default_specular() -> f32 {
+    0.5
+}
+
+fn default_clearcoat_roughness() -> f32 {
+    0.03
+}
+
+fn default_ior() -> f32 {
+    1.5
+}
+
+impl Principled {
+    /// A rough dielectric of `base_color`, with every other parameter at its default.
+    pub fn new(base_color: &vec::Vec3) -> Self {
+        Principled {
+            base_color: *base_color,
+            metallic: 0.0,
+            roughness: default_roughness(),
+            specular: default_specular(),
+            sheen: 0.0,
+            clearcoat: 0.0,
+            clearcoat_roughness: default_clearcoat_roughness(),
+            transmission: 0.0,
+            ior: default_ior(),
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: f32) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: f32) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: f32) -> Self {
+        self.sheen = sheen;
+        self
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: f32, roughness: f32) -> Self {
+        self.clearcoat = clearcoat;
+        self.clearcoat_roughness = roughness;
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: f32, ior: f32) -> Self {
+        self.transmission = transmission;
+        self.ior = ior;
+        self
+    }
+
+    /// Refracts or reflects like [`crate::materials::dielectric::Dielectric`], tinting
+    /// light by `base_color` as it enters.
+    fn transmit(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit: &hittable::Hit,
+        front_face: bool,
+        normal: &vec::Vec3,
+        state: &mut path::PathState,
+    ) -> ScatterRecord {
+        let unit_direction = vec::unit_vector(&hit.ray.direction);
+        let refraction_ratio = state.refraction_ratio(self.ior, front_face);
+        let cos_theta = (-unit_direction.dot(normal)).min(1.0);
+        let r0 = ((1.0 - refraction_ratio) / (1.0 + refraction_ratio)).powi(2);
+
+        let refracted = vec::refract(&unit_direction, normal, refraction_ratio)
+            .filter(|_| rng.random::<f32>() >= schlick(r0, cos_theta));
+        let (direction, attenuation) = match refracted {
+            Some(direction) => {
+                if front_face {
+                    state.eta_stack.push(self.ior);
+                    (direction, self.base_color)
+                } else {
+                    state.eta_stack.pop();
+                    (direction, vec::Vec3::new(1.0, 1.0, 1.0))
+                }
+            }
+            None => (
+                vec::reflect(&unit_direction, normal),
+                vec::Vec3::new(1.0, 1.0, 1.0),
+            ),
+        };
+        specular(hit, &direction, attenuation)
+    }
+}
+
+impl Scatterable for Principled {
+    /// Picks a layer and samples it; see the module docs.
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        if state.depth == 0 {
+            return None;
+        }
+
+        let hit = hit_record.hit;
+        let toward_viewer = -vec::unit_vector(&hit.ray.direction);
+        let front_face = toward_viewer.dot(&hit.normal) > 0.0;
+        let normal = if front_face { hit.normal } else { -hit.normal };
+        let cos_view = toward_viewer.dot(&normal).clamp(0.0, 1.0);
+        let white = vec::Vec3::new(1.0, 1.0, 1.0);
+
+        // Light inside a transmissive object can only be on its way out.
+        if !front_face && self.transmission > 0.0 {
+            return Some(self.transmit(rng, &hit, front_face, &normal, state));
+        }
+        if rng.random::<f32>() < self.clearcoat * schlick(CLEARCOAT_REFLECTANCE, cos_view) {
+            return Some(glossy(
+                &hit,
+                &normal,
+                &toward_viewer,
+                self.clearcoat_roughness,
+                white,
+            ));
+        }
+        if rng.random::<f32>() < self.metallic {
+            let tint = self.base_color + (white - self.base_color) * (1.0 - cos_view).powi(5);
+            return Some(glossy(&hit, &normal, &toward_viewer, self.roughness, tint));
+        }
+        if rng.random::<f32>() < self.transmission {
+            return Some(self.transmit(rng, &hit, front_face, &normal, state));
+        }
+        if rng.random::<f32>() < schlick(SPECULAR_SCALE * self.specular, cos_view) {
+            return Some(glossy(&hit, &normal, &toward_viewer, self.roughness, white));
+        }
+
+        let sheen = self.sheen * (1.0 - cos_view).powi(5);
+        Some(ScatterRecord {
+            attenuation: self.base_color + white * sheen,
+            scatter_pdf: Some(Box::new(cosine::CosinePDF::new(&normal))),
+            scattered_ray: None,
+            use_light_pdf: true,
+        })
+    }
+
+    fn emit(&self, _hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        vec::Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Schlick's approximation of Fresnel reflectance from its value `r0` head on.
+fn schlick(r0: f32, cosine: f32) -> f32 {
+    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+}
+
+/// A GGX reflection lobe of `roughness`, or a mirror bounce when it's too smooth to
+/// sample.
+fn glossy(
+    hit: &hittable::Hit,
+    normal: &vec::Vec3,
+    toward_viewer: &vec::Vec3,
+    roughness: f32,
+    attenuation: vec::Vec3,
+) -> ScatterRecord {
+    let alpha = roughness * roughness;
+    if alpha < ggx::MIN_ALPHA {
+        return specular(hit, &vec::reflect(&-*toward_viewer, normal), attenuation);
+    }
+    ScatterRecord {
+        attenuation,
+        scatter_pdf: Some(Box::new(ggx::GgxPDF::new(normal, toward_viewer, alpha))),
+        scattered_ray: None,
+        use_light_pdf: true,
+    }
+}
+
+fn specular(hit: &hittable::Hit, direction: &vec::Vec3, attenuation: vec::Vec3) -> ScatterRecord {
+    ScatterRecord {
+        attenuation,
+        scatter_pdf: None,
+        scattered_ray: Some(ray::Ray::new(&hit.point, direction, Some(hit.ray.time))),
+        use_light_pdf: false,
+    }
+}
@@ -0,0 +1,133 @@
+//! Cel shading, for cartoon and illustration looks.
+//!
+//! A [`Toon`] surface ignores the scene's lights and shows flat bands of its `color`
+//! instead: the Lambertian shading `n . l` against a fixed key light `light_direction`,
+//! quantized into `bands` steps from `shadow` brightness up to full. With an `outline`
+//! threshold, the surface turns `outline_color` where it faces the viewer at less than
+//! that cosine, inking silhouettes round curved shapes. The shade is emitted rather than
+//! reflected, so toon surfaces neither receive shadows nor light their surroundings, and
+//! photoreal materials around them still shade with the scene's lights.
+use serde::{Deserialize, Serialize};
+
+use crate::core::path;
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// Toon material parameters.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Toon {
+    pub color: vec::Vec3,
+    /// Direction toward the key light the bands are shaded from, in world space.
+    #[serde(default = "default_light_direction")]
+    pub light_direction: vec::Vec3,
+    /// Number of shading steps; 1 is flat color.
+    #[serde(default = "default_bands")]
+    pub bands: u32,
+    /// Brightness of the darkest band, from 0 (black) to 1.
+    #[serde(default = "default_shadow")]
+    pub shadow: f32,
+    /// Cosine between the normal and the view below which the surface draws as outline;
+    /// no outline if omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline: Option<f32>,
+    #[serde(default = "default_outline_color")]
+    pub outline_color: vec::Vec3,
+}
+
+fn default_light_direction() -> vec::Vec3 {
+    vec::Vec3::new(1.0, 1.0, 1.0)
+}
+
+fn default_bands() -> u32 {
+    3
+}
+
+fn default_shadow() -> f32 {
+    0.3
+}
+
+fn default_outline_color() -> vec::Vec3 {
+    vec::Vec3::new(0.0, 0.0, 0.0)
+}
+
+impl Toon {
+    /// `color` in three bands, lit from above and to the side, without outlines.
+    pub fn new(color: &vec::Vec3) -> Self {
+        Toon {
+            color: *color,
+            light_direction: default_light_direction(),
+            bands: default_bands(),
+            shadow: default_shadow(),
+            outline: None,
+            outline_color: default_outline_color(),
+        }
+    }
+
+    /// Shades toward a key light in `direction`.
+    pub fn with_light_direction(mut self, direction: &vec::Vec3) -> Self {
+        self.light_direction = *direction;
+        self
+    }
+
+    /// Quantizes shading into `bands` steps, the darkest at brightness `shadow`.
+    pub fn with_bands(mut self, bands: u32, shadow: f32) -> Self {
+        self.bands = bands;
+        self.shadow = shadow;
+        self
+    }
+
+    /// Inks the surface `color` where the normal and view meet at a cosine below
+    /// `threshold`, e.g. 0.3.
+    pub fn with_outline(mut self, threshold: f32, color: &vec::Vec3) -> Self {
+        self.outline = Some(threshold);
+        self.outline_color = *color;
+        self
+    }
+
+    /// Brightness of the band shading `normal`, a unit vector.
+    fn band(&self, normal: &vec::Vec3) -> f32 {
+        let bands = self.bands.max(1);
+        if bands == 1 || self.light_direction.squared_length() <= 0.0 {
+            return 1.0;
+        }
+        let lit = normal
+            .dot(&vec::unit_vector(&self.light_direction))
+            .clamp(0.0, 1.0);
+        let step = ((lit * bands as f32) as u32).min(bands - 1);
+        let shadow = self.shadow.clamp(0.0, 1.0);
+        shadow + (1.0 - shadow) * step as f32 / (bands - 1) as f32
+    }
+}
+
+impl Scatterable for Toon {
+    fn scatter(
+        &self,
+        _rng: &mut rng::SampleRng,
+        _hit_record: &hittable::HitRecord,
+        _state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        None
+    }
+
+    fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        let hit = hit_record.hit;
+        let toward_viewer = -vec::unit_vector(&hit.ray.direction);
+        let mut normal = vec::unit_vector(&hit.normal);
+        let facing = toward_viewer.dot(&normal);
+        if facing < 0.0 {
+            normal = -normal;
+        }
+        if self
+            .outline
+            .is_some_and(|threshold| facing.abs() < threshold)
+        {
+            return self.outline_color;
+        }
+        self.color * self.band(&normal)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,99 @@
+//! Different materials on the two faces of a surface.
+//!
+//! A [`TwoSided`] material shades hits on the side the geometry's normal points to with
+//! its `front` material and hits from behind with its `back` one, deciding by the sign of
+//! the ray direction dotted with the normal. Thin geometry such as leaves, paper, playing
+//! cards, or an open mesh's inside can then look different from either side.
+//!
+//! The back material sees the surface flipped: its hits carry the reversed normal, so it
+//! shades the back face as if it were a front face and scatters light back out on the
+//! side the ray came from.
+use std::sync::Arc;
+
+use crate::core::path;
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// A material that is `front` on the side the normal points to and `back` on the other.
+#[derive(Clone)]
+pub struct TwoSided {
+    front: Arc<dyn Scatterable + Send + Sync>,
+    back: Arc<dyn Scatterable + Send + Sync>,
+}
+
+impl TwoSided {
+    pub fn new(
+        front: Arc<dyn Scatterable + Send + Sync>,
+        back: Arc<dyn Scatterable + Send + Sync>,
+    ) -> Self {
+        TwoSided { front, back }
+    }
+
+    /// The material on the side the normal points to.
+    pub fn front(&self) -> &Arc<dyn Scatterable + Send + Sync> {
+        &self.front
+    }
+
+    /// The material on the side facing away from the normal.
+    pub fn back(&self) -> &Arc<dyn Scatterable + Send + Sync> {
+        &self.back
+    }
+}
+
+/// Whether `hit`'s ray arrived on the side its normal points to.
+fn is_front(hit: &hittable::Hit) -> bool {
+    hit.ray.direction.dot(&hit.normal) < 0.0
+}
+
+/// `hit_record` with the surface turned around, for the back material.
+fn flipped<'a>(hit_record: &hittable::HitRecord<'a>) -> hittable::HitRecord<'a> {
+    let mut hit = hit_record.hit;
+    hit.normal = -hit.normal;
+    hit.shading_normal = hit.shading_normal.map(|normal| -normal);
+    hittable::HitRecord::new(
+        hit,
+        hit_record.renderable.get_pdf(&hit.point, hit.ray.time),
+        hit_record.renderable,
+    )
+}
+
+impl Scatterable for TwoSided {
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        if is_front(&hit_record.hit) {
+            self.front.scatter(rng, hit_record, state)
+        } else {
+            self.back.scatter(rng, &flipped(hit_record), state)
+        }
+    }
+
+    fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        if is_front(&hit_record.hit) {
+            self.front.emit(hit_record)
+        } else {
+            self.back.emit(&flipped(hit_record))
+        }
+    }
+
+    fn camera_visible(&self, hit_record: &hittable::HitRecord) -> bool {
+        if is_front(&hit_record.hit) {
+            self.front.camera_visible(hit_record)
+        } else {
+            self.back.camera_visible(&flipped(hit_record))
+        }
+    }
+
+    /// The looser of the two sides' limits, or none if either side is unlimited.
+    fn max_bounces(&self) -> Option<u32> {
+        Some(self.front.max_bounces()?.max(self.back.max_bounces()?))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,100 @@
+//! Cloth-like velvet: a diffuse base with a sheen at grazing angles.
+//!
+//! Velvet, felt, and peach skin look darker head on than at their edges, where fibers
+//! catch the light and throw it back. A [`Velvet`] surface reflects its `color`
+//! diffusely and adds a sheen of color `sheen`, whose `roughness` spreads it from a
+//! thin rim of light around silhouettes to a soft glow over the whole grazing side (see
+//! [`crate::math::pdf::sheen::SheenPDF`]). The two are sampled as [`lobes`] describes.
+//!
+//! Light the sheen reflects never reaches the fabric beneath it, so the base reflects its
+//! color only of the rest: with `E` the sheen's directional albedo for the view, the
+//! surface reflects `sheen E + color (1 - E)` of uniform light, after Estevez and Kulla.
+//! That is never more than the brighter of `color` and `sheen`, so the surface doesn't
+//! create energy as long as both stay within `[0, 1]` per channel.
+use serde::{Deserialize, Serialize};
+
+use crate::core::path;
+use crate::materials::lobes;
+use crate::math::pdf::{cosine, sheen::SheenPDF};
+use crate::math::{rng, vec};
+use crate::traits::hittable;
+use crate::traits::scatterable::{ScatterRecord, Scatterable};
+
+/// Fabric with a diffuse `color` under a grazing `sheen`. Both should be at most 1 per
+/// channel for the surface not to reflect more light than it receives.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Velvet {
+    /// Color of the fabric seen head on.
+    pub color: vec::Vec3,
+    /// Tint of the sheen; white for a neutral sheen, `color` or lighter for a
+    /// saturated one.
+    #[serde(default = "default_sheen")]
+    pub sheen: vec::Vec3,
+    /// Spread of the sheen, from 0 (a thin rim) to 1 (a broad glow).
+    #[serde(default = "default_roughness")]
+    pub roughness: f32,
+}
+
+fn default_sheen() -> vec::Vec3 {
+    vec::Vec3::new(1.0, 1.0, 1.0)
+}
+
+fn default_roughness() -> f32 {
+    0.5
+}
+
+impl Velvet {
+    /// Fabric of `color` with a white sheen of roughness 0.5.
+    pub fn new(color: &vec::Vec3) -> Self {
+        Velvet {
+            color: *color,
+            sheen: default_sheen(),
+            roughness: default_roughness(),
+        }
+    }
+
+    /// Tints the sheen `sheen` and spreads it by `roughness`.
+    pub fn with_sheen(mut self, sheen: &vec::Vec3, roughness: f32) -> Self {
+        self.sheen = *sheen;
+        self.roughness = roughness;
+        self
+    }
+}
+
+impl Scatterable for Velvet {
+    fn scatter(
+        &self,
+        rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        if state.depth == 0 {
+            return None;
+        }
+
+        // Fibers stand up on whichever side the ray arrives from.
+        let hit = hit_record.hit;
+        let toward_viewer = -vec::unit_vector(&hit.ray.direction);
+        let normal = if toward_viewer.dot(&hit.normal) > 0.0 {
+            hit.normal
+        } else {
+            -hit.normal
+        };
+        let sheen = SheenPDF::new(&normal, &toward_viewer, self.roughness);
+        lobes::scatter_either(
+            rng,
+            self.color * (1.0 - sheen.albedo()),
+            || Box::new(cosine::CosinePDF::new(&normal)),
+            self.sheen,
+            || Box::new(sheen),
+        )
+    }
+
+    fn emit(&self, _hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        vec::Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -1,6 +1,9 @@
+pub mod distribution;
 pub mod interval;
 pub mod mat;
+pub mod noise;
 pub mod onb;
 pub mod pdf;
 pub mod perlin;
+pub mod rng;
 pub mod vec;
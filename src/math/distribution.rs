@@ -0,0 +1,220 @@
+//! Piecewise-constant distributions for importance sampling tabulated functions.
+//!
+//! A table of non-negative values over `[0, 1)` (or `[0, 1)^2`) becomes a density
+//! proportional to it, with each entry covering an equal-width cell. Samples land in a
+//! cell with probability proportional to its value and uniformly within it.
+use rand::Rng;
+
+use crate::math::rng;
+
+/// A density over `[0, 1)` proportional to a table of values.
+#[derive(Clone, Debug)]
+pub struct Distribution1D {
+    values: Vec<f32>,
+    /// Running sums of `values`, normalized to end at 1.
+    cdf: Vec<f32>,
+    /// Mean of `values`.
+    integral: f32,
+}
+
+impl Distribution1D {
+    /// Builds the distribution of `values`; negative and non-finite entries count as
+    /// zero, and an all-zero (or empty) table gives the uniform distribution.
+    pub fn new(values: &[f32]) -> Self {
+        let mut values: Vec<f32> = values
+            .iter()
+            .map(|value| {
+                if value.is_finite() {
+                    value.max(0.0)
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        if values.is_empty() {
+            values.push(1.0);
+        }
+        let mut total: f32 = values.iter().sum();
+        if total <= 0.0 {
+            values.fill(1.0);
+            total = values.len() as f32;
+        }
+        let mut sum = 0.0;
+        let cdf = values
+            .iter()
+            .map(|value| {
+                sum += value;
+                sum / total
+            })
+            .collect();
+        Distribution1D {
+            integral: total / values.len() as f32,
+            values,
+            cdf,
+        }
+    }
+
+    /// Maps a uniform `r` in `[0, 1)` to `(x, pdf, cell)`.
+    pub fn sample(&self, r: f32) -> (f32, f32, usize) {
+        let cell = self
+            .cdf
+            .partition_point(|bound| *bound <= r)
+            .min(self.values.len() - 1);
+        let start = if cell == 0 { 0.0 } else { self.cdf[cell - 1] };
+        let width = self.cdf[cell] - start;
+        let offset = if width > 0.0 {
+            (r - start) / width
+        } else {
+            0.5
+        };
+        let x = (cell as f32 + offset.clamp(0.0, 1.0)) / self.values.len() as f32;
+        (x.min(1.0 - f32::EPSILON), self.pdf_of(cell), cell)
+    }
+
+    /// Density at `x`.
+    pub fn pdf(&self, x: f32) -> f32 {
+        self.pdf_of(self.cell(x))
+    }
+
+    /// Mean of the table, the integral of the piecewise-constant function it describes.
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    fn cell(&self, x: f32) -> usize {
+        ((x * self.values.len() as f32) as usize).min(self.values.len() - 1)
+    }
+
+    fn pdf_of(&self, cell: usize) -> f32 {
+        self.values[cell] / self.integral
+    }
+}
+
+/// A density over `[0, 1)^2` proportional to a row-major table of values, rows running
+/// along `v` and columns along `u`.
+#[derive(Clone, Debug)]
+pub struct Distribution2D {
+    /// Distribution of `u` within each row.
+    rows: Vec<Distribution1D>,
+    /// Distribution of `v` across rows, by row total.
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// Builds the distribution of `values`, `width` by `height` entries in row-major
+    /// order.
+    pub fn new(values: &[f32], width: usize, height: usize) -> Self {
+        let width = width.max(1);
+        let row_values = |row: usize| {
+            let start = (row * width).min(values.len());
+            &values[start..(start + width).min(values.len())]
+        };
+        let rows: Vec<Distribution1D> = (0..height.max(1))
+            .map(|row| Distribution1D::new(row_values(row)))
+            .collect();
+        // Rows are weighted by their own totals; an all-zero row is uniform within itself
+        // but never chosen.
+        let totals: Vec<f32> = (0..rows.len())
+            .map(|row| {
+                row_values(row)
+                    .iter()
+                    .filter(|value| value.is_finite())
+                    .map(|value| value.max(0.0))
+                    .sum()
+            })
+            .collect();
+        Distribution2D {
+            marginal: Distribution1D::new(&totals),
+            rows,
+        }
+    }
+
+    /// Draws `(u, v, pdf)`.
+    pub fn sample(&self, rng: &mut rng::SampleRng) -> (f32, f32, f32) {
+        let (v, v_pdf, row) = self.marginal.sample(rng.random::<f32>());
+        let (u, u_pdf, _) = self.rows[row].sample(rng.random::<f32>());
+        (u, v, u_pdf * v_pdf)
+    }
+
+    /// Density at `(u, v)`.
+    pub fn pdf(&self, u: f32, v: f32) -> f32 {
+        let row = self.marginal.cell(v);
+        self.marginal.pdf_of(row) * self.rows[row].pdf(u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    const STEPS: usize = 4096;
+
+    /// Midpoint-rule integral of `pdf` over `[0, 1)`.
+    fn integrate(pdf: impl Fn(f32) -> f32) -> f32 {
+        (0..STEPS)
+            .map(|step| pdf((step as f32 + 0.5) / STEPS as f32))
+            .sum::<f32>()
+            / STEPS as f32
+    }
+
+    #[test]
+    fn samples_carry_the_density_at_their_point() {
+        let table = Distribution1D::new(&[0.0, 1.0, 3.0, 0.5, 2.0]);
+        let mut rng = rng::pixel_rng(1, 0, 0);
+        for _ in 0..1000 {
+            let (x, pdf, cell) = table.sample(rng.random());
+            assert!((0.0..1.0).contains(&x));
+            assert_ne!(cell, 0, "sampled a zero-valued cell");
+            assert!(
+                (pdf - table.pdf(x)).abs() < 1e-5,
+                "{} != {}",
+                pdf,
+                table.pdf(x)
+            );
+        }
+
+        let image = Distribution2D::new(&[0.0, 1.0, 4.0, 2.0, 0.0, 0.0, 1.0, 8.0, 0.5], 3, 3);
+        for _ in 0..1000 {
+            let (u, v, pdf) = image.sample(&mut rng);
+            assert!(pdf > 0.0);
+            assert!(
+                (pdf - image.pdf(u, v)).abs() < 1e-4,
+                "{} != {}",
+                pdf,
+                image.pdf(u, v)
+            );
+        }
+    }
+
+    #[test]
+    fn densities_integrate_to_one() {
+        let table = Distribution1D::new(&[0.0, 1.0, 3.0, 0.5, 2.0]);
+        assert!((integrate(|x| table.pdf(x)) - 1.0).abs() < 1e-3);
+        assert!((table.integral() - 1.3).abs() < 1e-6);
+
+        let image = Distribution2D::new(&[0.0, 1.0, 4.0, 2.0, 0.0, 0.0, 1.0, 8.0, 0.5], 3, 3);
+        let total = integrate(|v| integrate(|u| image.pdf(u, v)));
+        assert!((total - 1.0).abs() < 1e-3, "{}", total);
+    }
+
+    #[test]
+    fn zero_tables_are_uniform() {
+        for values in [&[0.0, 0.0, 0.0][..], &[-1.0, f32::NAN, 0.0], &[]] {
+            let table = Distribution1D::new(values);
+            assert_eq!(table.pdf(0.1), 1.0);
+            assert_eq!(table.pdf(0.9), 1.0);
+            let (x, pdf, _) = table.sample(0.4);
+            assert!((x - 0.4).abs() < 1e-6);
+            assert_eq!(pdf, 1.0);
+        }
+
+        let image = Distribution2D::new(&[0.0; 6], 3, 2);
+        assert_eq!(image.pdf(0.2, 0.7), 1.0);
+        // An all-zero row in a lit image is never chosen.
+        let image = Distribution2D::new(&[0.0, 0.0, 1.0, 1.0], 2, 2);
+        assert_eq!(image.pdf(0.5, 0.25), 0.0);
+        assert_eq!(image.pdf(0.5, 0.75), 2.0);
+    }
+}
@@ -13,6 +13,28 @@ impl Mat3 {
         Mat3 { rows }
     }
 
+    /// Rotation by `degrees` about the unit vector `axis`, counter-clockwise when looking
+    /// down the axis toward the origin (right-handed).
+    pub fn rotation(axis: &vec::Vec3, degrees: f32) -> Mat3 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        Mat3::new([
+            vec::Vec3::new(t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y),
+            vec::Vec3::new(t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x),
+            vec::Vec3::new(t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos),
+        ])
+    }
+
+    /// Rotation by Euler angles in degrees: about X first, then Y, then Z, each about the
+    /// fixed world axes as in [`Mat3::rotation`].
+    pub fn from_euler(degrees: &vec::Vec3) -> Mat3 {
+        let x = Mat3::rotation(&vec::Vec3::new(1.0, 0.0, 0.0), degrees.x);
+        let y = Mat3::rotation(&vec::Vec3::new(0.0, 1.0, 0.0), degrees.y);
+        let z = Mat3::rotation(&vec::Vec3::new(0.0, 0.0, 1.0), degrees.z);
+        z * y * x
+    }
+
     pub fn transpose(&self) -> Mat3 {
         let mut cols = [vec::Vec3::new(0.0, 0.0, 0.0); 3];
         for i in 0..3 {
@@ -128,3 +150,35 @@ impl ops::Mul<&Mat3> for &Mat3 {
         Mat3 { rows: result_rows }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: vec::Vec3, expected: vec::Vec3) {
+        assert!(
+            (actual - expected).length() < 1e-5,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn euler_angles_rotate_about_x_then_y_then_z() {
+        let (x, y, z) = (
+            vec::Vec3::new(1.0, 0.0, 0.0),
+            vec::Vec3::new(0.0, 1.0, 0.0),
+            vec::Vec3::new(0.0, 0.0, 1.0),
+        );
+        // Each angle alone turns counter-clockwise about its own axis.
+        assert_close(Mat3::from_euler(&vec::Vec3::new(90.0, 0.0, 0.0)) * y, z);
+        assert_close(Mat3::from_euler(&vec::Vec3::new(0.0, 90.0, 0.0)) * z, x);
+        assert_close(Mat3::from_euler(&vec::Vec3::new(0.0, 0.0, 90.0)) * x, y);
+
+        // X turns Y onto Z, which Y then turns onto X; turning about Y first would leave Y
+        // where it is for X to turn onto Z.
+        let both = Mat3::from_euler(&vec::Vec3::new(90.0, 90.0, 0.0));
+        assert_close(both * y, x);
+    }
+}
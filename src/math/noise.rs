@@ -0,0 +1,47 @@
+//! Seeded fractal value noise.
+//!
+//! Unlike [`crate::math::perlin::PerlinGenerator`], which shuffles its tables from the
+//! caller's random generator, these functions hash the lattice from a `seed`, so the same
+//! seed rebuilds the same terrain or displacement in every run and on every machine.
+use rand::Rng;
+
+use crate::math::{rng, vec};
+
+/// Sum of `octaves` layers of [`value`] noise, each at twice the frequency and half the
+/// amplitude of the last, normalized to `[0, 1]`.
+pub fn fractal(point: vec::Point3, octaves: u32, seed: u64) -> f32 {
+    let mut sum = 0.0;
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    for octave in 0..octaves.max(1) {
+        sum += amplitude * value(point * frequency, seed ^ octave as u64);
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / total
+}
+
+/// Random values in `[0, 1]` at the points of the integer lattice, smoothly
+/// interpolated between them.
+pub fn value(point: vec::Point3, seed: u64) -> f32 {
+    let lattice = |i: i64, j: i64, k: i64| {
+        let bits = ((i as u64) << 42) ^ ((j as u64 & 0x1f_ffff) << 21) ^ (k as u64 & 0x1f_ffff);
+        rng::hashed_rng(bits ^ seed.rotate_left(17)).random::<f32>()
+    };
+    let (i, j, k) = (
+        point.x.floor() as i64,
+        point.y.floor() as i64,
+        point.z.floor() as i64,
+    );
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (fx, fy, fz) = (
+        smooth(point.x - point.x.floor()),
+        smooth(point.y - point.y.floor()),
+        smooth(point.z - point.z.floor()),
+    );
+    let row = |j: i64, k: i64| lattice(i, j, k) * (1.0 - fx) + lattice(i + 1, j, k) * fx;
+    let plane = |k: i64| row(j, k) * (1.0 - fy) + row(j + 1, k) * fy;
+    plane(k) * (1.0 - fz) + plane(k + 1) * fz
+}
@@ -1,15 +1,26 @@
+pub mod blinn_phong;
 pub mod cosine;
+pub mod ggx;
 pub mod phase;
+pub mod sheen;
 pub mod uniform;
 
 use rand::Rng;
 
-use crate::math::vec;
+use crate::math::{rng, vec};
 
 /// Probability Density Function trait
 pub trait PDF {
     fn value(&self, direction: vec::Vec3) -> f32;
-    fn generate(&self, rng: &mut rand::rngs::ThreadRng) -> vec::Vec3;
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3;
+
+    /// How much a material scatters toward `direction`: its BRDF times the cosine
+    /// term, over the scatter record's attenuation. The same as [`PDF::value`] for PDFs
+    /// that sample their material exactly, like a Lambertian surface's cosine lobe;
+    /// others (microfacet lobes) weight each sample by `scattering / value`.
+    fn scattering(&self, direction: vec::Vec3) -> f32 {
+        self.value(direction)
+    }
 }
 
 /// Borrowed PDF wrapper for building mixtures without taking ownership.
@@ -22,9 +33,13 @@ impl PDF for PDFRef<'_> {
         self.pdf.value(direction)
     }
 
-    fn generate(&self, rng: &mut rand::rngs::ThreadRng) -> vec::Vec3 {
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
         self.pdf.generate(rng)
     }
+
+    fn scattering(&self, direction: vec::Vec3) -> f32 {
+        self.pdf.scattering(direction)
+    }
 }
 
 /// Single PDF with an associated weight for mixture
@@ -74,7 +89,7 @@ impl PDF for MixturePDF<'_> {
             .sum()
     }
 
-    fn generate(&self, rng: &mut rand::rngs::ThreadRng) -> vec::Vec3 {
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
         let r: f32 = rng.random::<f32>();
         let mut cumulative_weight = 0.0;
         for mix in &self.mixes {
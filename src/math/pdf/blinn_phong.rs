@@ -0,0 +1,84 @@
+//! Blinn-Phong specular reflection.
+//!
+//! Half vectors between the viewer and the light are drawn with density proportional to
+//! `cos^exponent` of their angle from the normal and reflected about, so higher exponents
+//! give tighter highlights. The lobe reflects with the normalized Blinn-Phong BRDF,
+//! `(exponent + 2) / (8 pi) cos^exponent`, without Fresnel or shadowing terms, as legacy
+//! renderers do; each sample's weight `scattering / value` is then
+//! `(exponent + 2) / (exponent + 1)` times two cosines, close to 1 seen head on and
+//! falling off toward grazing views, where the highlight dims as in those renderers.
+use std::f32::consts::PI;
+
+use crate::math::{onb, pdf, rng, vec};
+
+pub struct BlinnPhongPDF {
+    onb: onb::ONB,
+    /// Unit direction toward the viewer.
+    outgoing: vec::Vec3,
+    exponent: f32,
+}
+
+impl BlinnPhongPDF {
+    /// Highlight about `normal` for light leaving toward `toward_viewer`, its half vectors
+    /// spread by `cos^exponent`.
+    pub fn new(normal: &vec::Vec3, toward_viewer: &vec::Vec3, exponent: f32) -> Self {
+        BlinnPhongPDF {
+            onb: onb::ONB::build_from_w(normal),
+            outgoing: vec::unit_vector(toward_viewer),
+            exponent: exponent.max(0.0),
+        }
+    }
+
+    /// The half vector between the outgoing direction and unit `incoming`, or `None` if
+    /// either is below the surface.
+    fn half(&self, incoming: &vec::Vec3) -> Option<vec::Vec3> {
+        if incoming.dot(&self.onb.w) <= 0.0 || self.outgoing.dot(&self.onb.w) <= 0.0 {
+            return None;
+        }
+        let half = *incoming + self.outgoing;
+        (half.squared_length() > 0.0).then(|| vec::unit_vector(&half))
+    }
+
+    /// `cos^exponent` of `half`'s angle from the normal.
+    fn lobe(&self, half: &vec::Vec3) -> f32 {
+        half.dot(&self.onb.w).max(0.0).powf(self.exponent)
+    }
+}
+
+impl pdf::PDF for BlinnPhongPDF {
+    fn value(&self, direction: vec::Vec3) -> f32 {
+        let incoming = vec::unit_vector(&direction);
+        let Some(half) = self.half(&incoming) else {
+            return 0.0;
+        };
+        // Half vectors have density `(n + 1) / (2 pi) cos^n`, and reflecting about them
+        // maps solid angle by `1 / (4 o . h)`.
+        let cos_outgoing = self.outgoing.dot(&half);
+        if cos_outgoing <= 0.0 {
+            return 0.0;
+        }
+        (self.exponent + 1.0) / (2.0 * PI) * self.lobe(&half) / (4.0 * cos_outgoing)
+    }
+
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
+        let r1: f32 = rand::Rng::random::<f32>(rng);
+        let r2: f32 = rand::Rng::random::<f32>(rng);
+        let cos_theta = r1.powf(1.0 / (self.exponent + 1.0));
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * r2;
+        let half = self.onb.local(&vec::Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+        half * (2.0 * self.outgoing.dot(&half)) - self.outgoing
+    }
+
+    fn scattering(&self, direction: vec::Vec3) -> f32 {
+        let incoming = vec::unit_vector(&direction);
+        let Some(half) = self.half(&incoming) else {
+            return 0.0;
+        };
+        (self.exponent + 2.0) / (8.0 * PI) * self.lobe(&half) * incoming.dot(&self.onb.w)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::math::{onb, pdf, vec};
+use crate::math::{onb, pdf, rng, vec};
 
 pub struct CosinePDF {
     onb: onb::ONB,
@@ -21,12 +21,12 @@ impl pdf::PDF for CosinePDF {
         }
     }
 
-    fn generate(&self, rng: &mut rand::rngs::ThreadRng) -> vec::Vec3 {
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
         self.onb.local(&random_cosine_direction(rng))
     }
 }
 
-fn random_cosine_direction(rng: &mut rand::rngs::ThreadRng) -> vec::Vec3 {
+fn random_cosine_direction(rng: &mut rng::SampleRng) -> vec::Vec3 {
     let r1: f32 = rand::Rng::random::<f32>(rng);
     let r2: f32 = rand::Rng::random::<f32>(rng);
     let z = (1.0 - r2).sqrt();
@@ -0,0 +1,157 @@
+//! GGX (Trowbridge-Reitz) microfacet reflection.
+//!
+//! A rough surface is modeled as tiny mirrors whose normals follow the GGX distribution
+//! `D`, with the Smith function `G` accounting for facets hidden or shadowed by others.
+//! Directions are drawn from the distribution of normals visible from the viewer
+//! (Heitz 2018), which keeps the sample weight `G2 / G1` close to 1 at every roughness.
+//! The lobe may be stretched along a tangent, with separate widths along and across it,
+//! for brushed metals whose highlights streak perpendicular to the brushing.
+use crate::math::{onb, pdf, rng, vec};
+
+/// Smallest `alpha`; sharper lobes overflow `f32` and should be treated as mirrors.
+pub const MIN_ALPHA: f32 = 1e-3;
+
+pub struct GgxPDF {
+    onb: onb::ONB,
+    /// Direction toward the viewer in the local frame, with `z` along the normal and `x`
+    /// along the tangent.
+    outgoing: vec::Vec3,
+    alpha_x: f32,
+    alpha_y: f32,
+}
+
+impl GgxPDF {
+    /// Reflection lobe about `normal` for light leaving toward `toward_viewer`, of width
+    /// `alpha` (the square of perceptual roughness).
+    pub fn new(normal: &vec::Vec3, toward_viewer: &vec::Vec3, alpha: f32) -> Self {
+        GgxPDF::with_frame(onb::ONB::build_from_w(normal), toward_viewer, alpha, alpha)
+    }
+
+    /// An anisotropic lobe of width `alpha_x` along `tangent` and `alpha_y` across it.
+    /// `tangent` needn't be perpendicular to `normal`, only not parallel to it; an
+    /// isotropic lobe is used if it is.
+    pub fn anisotropic(
+        normal: &vec::Vec3,
+        tangent: &vec::Vec3,
+        toward_viewer: &vec::Vec3,
+        alpha_x: f32,
+        alpha_y: f32,
+    ) -> Self {
+        let w = vec::unit_vector(normal);
+        let along = *tangent - w * tangent.dot(&w);
+        let onb = if along.squared_length() > 1e-12 {
+            let u = vec::unit_vector(&along);
+            onb::ONB {
+                u,
+                v: w.cross(&u),
+                w,
+            }
+        } else {
+            onb::ONB::build_from_w(&w)
+        };
+        GgxPDF::with_frame(onb, toward_viewer, alpha_x, alpha_y)
+    }
+
+    fn with_frame(onb: onb::ONB, toward_viewer: &vec::Vec3, alpha_x: f32, alpha_y: f32) -> Self {
+        let outgoing = to_local(&onb, toward_viewer);
+        GgxPDF {
+            onb,
+            // Grazing views can land just below the surface through rounding.
+            outgoing: vec::unit_vector(&vec::Vec3::new(
+                outgoing.x,
+                outgoing.y,
+                outgoing.z.max(1e-4),
+            )),
+            alpha_x: alpha_x.max(MIN_ALPHA),
+            alpha_y: alpha_y.max(MIN_ALPHA),
+        }
+    }
+
+    /// Density of microfacet normals `half`, in the local frame.
+    fn distribution(&self, half: &vec::Vec3) -> f32 {
+        let (x, y) = (half.x / self.alpha_x, half.y / self.alpha_y);
+        let denominator = x * x + y * y + half.z * half.z;
+        1.0 / (std::f32::consts::PI * self.alpha_x * self.alpha_y * denominator * denominator)
+    }
+
+    /// Smith's `Lambda` for a local direction, from which `G1 = 1 / (1 + Lambda)`.
+    fn lambda(&self, direction: &vec::Vec3) -> f32 {
+        let (x, y) = (self.alpha_x * direction.x, self.alpha_y * direction.y);
+        let stretched_tan2 = (x * x + y * y) / (direction.z * direction.z);
+        0.5 * ((1.0 + stretched_tan2).sqrt() - 1.0)
+    }
+
+    /// The half vector between the outgoing direction and a local `incoming` one, or
+    /// `None` if `incoming` is below the surface.
+    fn half(&self, incoming: &vec::Vec3) -> Option<vec::Vec3> {
+        if incoming.z <= 0.0 {
+            return None;
+        }
+        let half = *incoming + self.outgoing;
+        (half.squared_length() > 0.0).then(|| vec::unit_vector(&half))
+    }
+}
+
+impl pdf::PDF for GgxPDF {
+    fn value(&self, direction: vec::Vec3) -> f32 {
+        let incoming = to_local(&self.onb, &vec::unit_vector(&direction));
+        let Some(half) = self.half(&incoming) else {
+            return 0.0;
+        };
+        // Visible normals are `G1 * D * (o . h) / o.z`, and reflecting about them maps
+        // solid angle by `1 / (4 o . h)`.
+        let g1 = 1.0 / (1.0 + self.lambda(&self.outgoing));
+        g1 * self.distribution(&half) / (4.0 * self.outgoing.z)
+    }
+
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
+        let r1: f32 = rand::Rng::random::<f32>(rng);
+        let r2: f32 = rand::Rng::random::<f32>(rng);
+        let o = self.outgoing;
+
+        // Stretch the view so the lobe becomes a hemisphere, sample the projected
+        // hemisphere it sees, and unstretch the normal.
+        let view = vec::unit_vector(&vec::Vec3::new(self.alpha_x * o.x, self.alpha_y * o.y, o.z));
+        let length2 = view.x * view.x + view.y * view.y;
+        let t1 = if length2 > 0.0 {
+            vec::Vec3::new(-view.y, view.x, 0.0) / length2.sqrt()
+        } else {
+            vec::Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = view.cross(&t1);
+        let radius = r1.sqrt();
+        let phi = 2.0 * std::f32::consts::PI * r2;
+        let p1 = radius * phi.cos();
+        let s = 0.5 * (1.0 + view.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * radius * phi.sin();
+        let p3 = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        let normal = t1 * p1 + t2 * p2 + view * p3;
+        let half = vec::unit_vector(&vec::Vec3::new(
+            self.alpha_x * normal.x,
+            self.alpha_y * normal.y,
+            normal.z.max(0.0),
+        ));
+
+        let incoming = half * (2.0 * o.dot(&half)) - o;
+        self.onb.local(&incoming)
+    }
+
+    fn scattering(&self, direction: vec::Vec3) -> f32 {
+        let incoming = to_local(&self.onb, &vec::unit_vector(&direction));
+        let Some(half) = self.half(&incoming) else {
+            return 0.0;
+        };
+        // `D G / (4 o.z i.z)` times the cosine `i.z`, leaving the color to the
+        // attenuation.
+        let g2 = 1.0 / (1.0 + self.lambda(&self.outgoing) + self.lambda(&incoming));
+        self.distribution(&half) * g2 / (4.0 * self.outgoing.z)
+    }
+}
+
+fn to_local(onb: &onb::ONB, direction: &vec::Vec3) -> vec::Vec3 {
+    vec::Vec3::new(
+        direction.dot(&onb.u),
+        direction.dot(&onb.v),
+        direction.dot(&onb.w),
+    )
+}
@@ -1,6 +1,4 @@
-use rand::rngs;
-
-use crate::math::{pdf, vec};
+use crate::math::{pdf, rng, vec};
 
 pub struct ConstantPhaseFunction {}
 
@@ -9,7 +7,7 @@ impl pdf::PDF for ConstantPhaseFunction {
         1.0 / (4.0 * std::f32::consts::PI)
     }
 
-    fn generate(&self, rng: &mut rngs::ThreadRng) -> vec::Vec3 {
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
         vec::random_in_unit_sphere(rng)
     }
 }
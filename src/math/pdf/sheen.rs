@@ -0,0 +1,145 @@
+//! The sheen of cloth and velvet.
+//!
+//! Fibers standing up from a fabric catch light at grazing angles and scatter it back
+//! toward the light and the viewer, brightening silhouettes and edges rather than
+//! highlights. The lobe reflects with the "Charlie" sheen BRDF (Estevez and Kulla, 2017):
+//! half vectors facing away from the normal weighted by `sin^(1 / roughness)`, over
+//! Ashikhmin's velvet visibility term `4 (l + v - l v)` in the two cosines. Directions are
+//! drawn uniformly over the hemisphere and weighted by `scattering / value`: the lobe
+//! leans toward the horizon, where a cosine-weighted draw would rarely land and give
+//! grazing samples huge weights, while the BRDF times the cosine stays bounded there.
+//!
+//! The lobe's directional albedo, the share of uniform light it reflects toward the
+//! viewer, has no closed form; [`SheenPDF::albedo`] interpolates a table of it integrated
+//! once, so materials can leave the light the sheen doesn't reflect to the layer beneath.
+use std::f32::consts::PI;
+use std::sync::OnceLock;
+
+use crate::math::{onb, pdf, rng, vec};
+
+/// Smallest roughness the lobe is evaluated at, keeping `1 / roughness` finite.
+const MIN_ROUGHNESS: f32 = 0.07;
+
+/// View cosines and roughnesses the albedo table is integrated at, evenly spaced over
+/// `[0, 1]` and `[MIN_ROUGHNESS, 1]`.
+const ALBEDO_COSINES: usize = 33;
+const ALBEDO_ROUGHNESSES: usize = 17;
+
+/// Steps in each of the incoming cosine and azimuth when integrating one table entry.
+const ALBEDO_STEPS: usize = 64;
+
+pub struct SheenPDF {
+    onb: onb::ONB,
+    /// Unit direction toward the viewer.
+    outgoing: vec::Vec3,
+    roughness: f32,
+}
+
+impl SheenPDF {
+    /// Sheen about `normal` for light leaving toward `toward_viewer`, from 0 (fibers
+    /// combed flat, a thin rim of light) to 1 (a soft, broad glow).
+    pub fn new(normal: &vec::Vec3, toward_viewer: &vec::Vec3, roughness: f32) -> Self {
+        SheenPDF {
+            onb: onb::ONB::build_from_w(normal),
+            outgoing: vec::unit_vector(toward_viewer),
+            roughness: roughness.clamp(MIN_ROUGHNESS, 1.0),
+        }
+    }
+
+    /// Share of light arriving uniformly from every direction that the lobe reflects
+    /// toward the viewer, interpolated from a table built on first use.
+    pub fn albedo(&self) -> f32 {
+        let table = ALBEDO.get_or_init(albedo_table);
+        let cosine = self.outgoing.dot(&self.onb.w).clamp(0.0, 1.0) * (ALBEDO_COSINES - 1) as f32;
+        let roughness = (self.roughness - MIN_ROUGHNESS) / (1.0 - MIN_ROUGHNESS)
+            * (ALBEDO_ROUGHNESSES - 1) as f32;
+        let (i, j) = (
+            (cosine as usize).min(ALBEDO_COSINES - 2),
+            (roughness as usize).min(ALBEDO_ROUGHNESSES - 2),
+        );
+        let (s, t) = (cosine - i as f32, roughness - j as f32);
+        let at = |i: usize, j: usize| table[j * ALBEDO_COSINES + i];
+        (1.0 - t) * ((1.0 - s) * at(i, j) + s * at(i + 1, j))
+            + t * ((1.0 - s) * at(i, j + 1) + s * at(i + 1, j + 1))
+    }
+}
+
+static ALBEDO: OnceLock<Vec<f32>> = OnceLock::new();
+
+/// Directional albedo by roughness (rows) and view cosine (columns), integrated with
+/// the midpoint rule over the hemisphere of incoming directions.
+fn albedo_table() -> Vec<f32> {
+    let normal = vec::Vec3::new(0.0, 0.0, 1.0);
+    let mut table = Vec::with_capacity(ALBEDO_COSINES * ALBEDO_ROUGHNESSES);
+    for j in 0..ALBEDO_ROUGHNESSES {
+        let roughness =
+            MIN_ROUGHNESS + (1.0 - MIN_ROUGHNESS) * j as f32 / (ALBEDO_ROUGHNESSES - 1) as f32;
+        for i in 0..ALBEDO_COSINES {
+            // The albedo tends to a finite limit at grazing, where the lobe itself is 0.
+            let cos_outgoing = (i as f32 / (ALBEDO_COSINES - 1) as f32).max(1e-4);
+            let outgoing = vec::Vec3::new(
+                (1.0 - cos_outgoing * cos_outgoing).sqrt(),
+                0.0,
+                cos_outgoing,
+            );
+            let lobe = SheenPDF::new(&normal, &outgoing, roughness);
+            let mut sum = 0.0;
+            for k in 0..ALBEDO_STEPS {
+                let cos_theta = (k as f32 + 0.5) / ALBEDO_STEPS as f32;
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                // The lobe is symmetric about the plane of the view, so half the azimuths
+                // cover it.
+                for l in 0..ALBEDO_STEPS {
+                    let phi = PI * (l as f32 + 0.5) / ALBEDO_STEPS as f32;
+                    sum += pdf::PDF::scattering(
+                        &lobe,
+                        vec::Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta),
+                    );
+                }
+            }
+            // Each step covers `1 / STEPS` of the cosine and `2 pi / STEPS` of azimuth,
+            // counting the mirrored half.
+            table.push(sum * 2.0 * PI / (ALBEDO_STEPS * ALBEDO_STEPS) as f32);
+        }
+    }
+    table
+}
+
+impl pdf::PDF for SheenPDF {
+    fn value(&self, direction: vec::Vec3) -> f32 {
+        if direction.dot(&self.onb.w) <= 0.0 {
+            return 0.0;
+        }
+        1.0 / (2.0 * PI)
+    }
+
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
+        let cos_theta: f32 = rand::Rng::random::<f32>(rng);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rand::Rng::random::<f32>(rng);
+        self.onb.local(&vec::Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+
+    fn scattering(&self, direction: vec::Vec3) -> f32 {
+        let incoming = vec::unit_vector(&direction);
+        let cos_incoming = incoming.dot(&self.onb.w);
+        let cos_outgoing = self.outgoing.dot(&self.onb.w);
+        if cos_incoming <= 0.0 || cos_outgoing <= 0.0 {
+            return 0.0;
+        }
+        let half = incoming + self.outgoing;
+        if half.squared_length() <= 0.0 {
+            return 0.0;
+        }
+        let cos_half = vec::unit_vector(&half).dot(&self.onb.w);
+        let sin_half = (1.0 - cos_half * cos_half).max(0.0).sqrt();
+        let inverse = 1.0 / self.roughness;
+        let distribution = (2.0 + inverse) * sin_half.powf(inverse) / (2.0 * PI);
+        let visibility = 4.0 * (cos_incoming + cos_outgoing - cos_incoming * cos_outgoing);
+        distribution / visibility * cos_incoming
+    }
+}
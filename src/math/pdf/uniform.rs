@@ -1,4 +1,4 @@
-use crate::math::{pdf, vec};
+use crate::math::{pdf, rng, vec};
 
 pub struct UniformPDF {}
 
@@ -7,7 +7,7 @@ impl pdf::PDF for UniformPDF {
         1.0 / (4.0 * std::f32::consts::PI)
     }
 
-    fn generate(&self, rng: &mut rand::rngs::ThreadRng) -> vec::Vec3 {
+    fn generate(&self, rng: &mut rng::SampleRng) -> vec::Vec3 {
         let z: f32 = 1.0 - 2.0 * rand::Rng::random::<f32>(rng);
         let r = (1.0 - z * z).sqrt();
         let phi = 2.0 * std::f32::consts::PI * rand::Rng::random::<f32>(rng);
@@ -0,0 +1,157 @@
+//! Seedable random number generation for rendering.
+//!
+//! Every pixel draws its samples from its own generator, seeded from the render seed and
+//! the pixel coordinates, so an image depends only on the scene and its seed and not on
+//! how the frame was split across threads.
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Generator algorithm used for sampling, chosen per render.
+///
+/// Both are small, fast, and statistically solid for rendering; they produce different
+/// (equally valid) noise for the same seed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RngAlgorithm {
+    /// xoshiro256++, 256 bits of state. The default.
+    #[default]
+    Xoshiro,
+    /// PCG32 (XSH RR), 64 bits of state and 32-bit outputs, the cheapest to seed and step.
+    Pcg32,
+}
+
+impl RngAlgorithm {
+    pub fn is_default(&self) -> bool {
+        *self == RngAlgorithm::default()
+    }
+
+    /// Creates this algorithm's generator for the pixel at `(x, y)` of a render seeded
+    /// with `seed`.
+    pub fn pixel_rng(self, seed: u64, x: u32, y: u32) -> SampleRng {
+        let pixel = ((y as u64) << 32) | x as u64;
+        self.seeded(mix(seed ^ mix(pixel)))
+    }
+
+    /// Creates this algorithm's generator for samples `first_sample..` of the pixel at
+    /// `(x, y)`, for renderers that add samples to a pixel over several passes. Sample 0
+    /// gives the same generator as [`RngAlgorithm::pixel_rng`].
+    pub fn pixel_rng_at(self, seed: u64, x: u32, y: u32, first_sample: u32) -> SampleRng {
+        self.pixel_rng(seed ^ mix(first_sample as u64), x, y)
+    }
+
+    /// Creates this algorithm's generator seeded from arbitrary bits, e.g. a hash of a
+    /// photon's pass and index.
+    pub fn hashed(self, bits: u64) -> SampleRng {
+        self.seeded(mix(bits))
+    }
+
+    fn seeded(self, seed: u64) -> SampleRng {
+        match self {
+            RngAlgorithm::Xoshiro => SampleRng::Xoshiro(rand::rngs::SmallRng::seed_from_u64(seed)),
+            RngAlgorithm::Pcg32 => SampleRng::Pcg32(Pcg32::new(seed)),
+        }
+    }
+}
+
+/// Random number generator threaded through sampling, scattering, and PDF generation.
+#[derive(Clone, Debug)]
+pub enum SampleRng {
+    Xoshiro(rand::rngs::SmallRng),
+    Pcg32(Pcg32),
+}
+
+impl RngCore for SampleRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            SampleRng::Xoshiro(rng) => rng.next_u32(),
+            SampleRng::Pcg32(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            SampleRng::Xoshiro(rng) => rng.next_u64(),
+            SampleRng::Pcg32(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            SampleRng::Xoshiro(rng) => rng.fill_bytes(dest),
+            SampleRng::Pcg32(rng) => rng.fill_bytes(dest),
+        }
+    }
+}
+
+/// The PCG32 generator (O'Neill's XSH RR 64/32 variant) on its default stream.
+#[derive(Clone, Debug)]
+pub struct Pcg32 {
+    state: u64,
+}
+
+impl Pcg32 {
+    const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+    const INCREMENT: u64 = 1_442_695_040_888_963_407;
+
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Pcg32 { state: 0 };
+        rng.step();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.step();
+        rng
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(Self::INCREMENT);
+    }
+}
+
+impl RngCore for Pcg32 {
+    fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.step();
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        xorshifted.rotate_right((state >> 59) as u32)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32() as u64;
+        let high = self.next_u32() as u64;
+        (high << 32) | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+/// Creates the default algorithm's generator for the pixel at `(x, y)` of a render
+/// seeded with `seed`; see [`RngAlgorithm::pixel_rng`].
+pub fn pixel_rng(seed: u64, x: u32, y: u32) -> SampleRng {
+    RngAlgorithm::default().pixel_rng(seed, x, y)
+}
+
+/// Creates the default algorithm's generator for samples `first_sample..` of the pixel
+/// at `(x, y)`; see [`RngAlgorithm::pixel_rng_at`].
+pub fn pixel_rng_at(seed: u64, x: u32, y: u32, first_sample: u32) -> SampleRng {
+    RngAlgorithm::default().pixel_rng_at(seed, x, y, first_sample)
+}
+
+/// Creates the default algorithm's generator seeded from arbitrary bits, e.g. a hash of a
+/// ray; see [`RngAlgorithm::hashed`].
+pub fn hashed_rng(bits: u64) -> SampleRng {
+    RngAlgorithm::default().hashed(bits)
+}
+
+/// SplitMix64 finalizer: spreads nearby inputs across the whole 64-bit range.
+pub fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
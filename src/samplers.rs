@@ -1,2 +1,3 @@
+pub mod monte_carlo;
 pub mod sampleable;
-pub mod monte_carlo;
\ No newline at end of file
+pub mod traversal;
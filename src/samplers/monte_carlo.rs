@@ -1,11 +1,12 @@
 use rand::Rng;
 
-use crate::core::{camera, ray, scene};
-use crate::math::vec;
+use crate::core::{camera, path, ray, scene};
+use crate::math::{rng, vec};
 use crate::samplers::sampleable::Sampleable;
+use crate::samplers::traversal;
 
 pub type TraceRay =
-    fn(&mut rand::rngs::ThreadRng, &scene::Scene, &ray::Ray, u32) -> vec::Vec3;
+    fn(&mut rng::SampleRng, &scene::Scene, &ray::Ray, &mut path::PathState) -> vec::Vec3;
 
 pub struct MonteCarloSampler<'a> {
     trace: TraceRay,
@@ -14,6 +15,8 @@ pub struct MonteCarloSampler<'a> {
     max_depth: u32,
     camera: &'a camera::Camera,
     scene: &'a scene::Scene,
+    pixel_order: traversal::PixelOrder,
+    ray_ranges: ray::RayRanges,
 }
 
 impl<'a> MonteCarloSampler<'a> {
@@ -32,40 +35,140 @@ impl<'a> MonteCarloSampler<'a> {
             max_depth,
             camera,
             scene,
+            pixel_order: traversal::PixelOrder::default(),
+            ray_ranges: ray::RayRanges::default(),
         }
     }
-}
 
-impl Sampleable for MonteCarloSampler<'_> {
-    fn sample_pixel(
+    pub fn with_pixel_order(mut self, pixel_order: traversal::PixelOrder) -> Self {
+        self.pixel_order = pixel_order;
+        self
+    }
+
+    pub fn with_ray_ranges(mut self, ray_ranges: ray::RayRanges) -> Self {
+        self.ray_ranges = ray_ranges;
+        self
+    }
+
+    /// Samples a pixel like [`Sampleable::sample_pixel`], but returns the radiance split
+    /// into `light_groups` groups plus a final slot for ungrouped emitters. Draws the same
+    /// random numbers, so the slots sum to the pixel's regular color.
+    pub fn sample_pixel_light_groups(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut rng::SampleRng,
         x: u32,
         y: u32,
         width: u32,
         height: u32,
-    ) -> vec::Vec3 {
-        let recip_spp_sqrt = 1.0 / self.spp_sqrt as f32;
+        light_groups: usize,
+    ) -> Vec<vec::Vec3> {
+        self.accumulate(rng, x, y, width, height, Some(light_groups))
+            .1
+    }
+
+    /// Traces every sample of a pixel like [`Sampleable::sample_pixel`], but returns each
+    /// sample's path with its vertices logged (see [`path::PathState::with_log`]). Draws
+    /// the same random numbers, so these are exactly the paths that went into the pixel's
+    /// regular color.
+    pub fn sample_pixel_paths(
+        &self,
+        rng: &mut rng::SampleRng,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Vec<path::TracedPath> {
+        let mut samples = Vec::with_capacity(self.spp as usize);
+        self.for_each_sample(rng, x, y, width, height, |rng, ray, weight| {
+            let mut state = path::PathState::new(self.max_depth)
+                .with_ray_ranges(self.camera.clip(ray, &self.ray_ranges))
+                .with_log();
+            let radiance = (self.trace)(rng, self.scene, ray, &mut state) * weight;
+            samples.push(path::TracedPath {
+                ray: *ray,
+                radiance,
+                vertices: state.log.unwrap_or_default(),
+            });
+        });
+        samples
+    }
+
+    /// Traces every sample of a pixel, returning the averaged radiance and, when
+    /// `light_groups` is given, its split into that many groups plus the ungrouped slot.
+    fn accumulate(
+        &self,
+        rng: &mut rng::SampleRng,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        light_groups: Option<usize>,
+    ) -> (vec::Vec3, Vec<vec::Vec3>) {
         let recip_spp = 1.0 / self.spp as f32;
         let mut col = vec::Vec3::new(0.0, 0.0, 0.0);
+        let mut groups = vec![vec::Vec3::default(); light_groups.map_or(0, |count| count + 1)];
 
+        self.for_each_sample(rng, x, y, width, height, |rng, ray, weight| {
+            let mut state = path::PathState::new(self.max_depth)
+                .with_ray_ranges(self.camera.clip(ray, &self.ray_ranges));
+            if let Some(count) = light_groups {
+                state = state.with_light_groups(count);
+            }
+            col = col + (self.trace)(rng, self.scene, ray, &mut state) * weight;
+            for (group, radiance) in groups.iter_mut().zip(state.light_groups) {
+                *group = *group + radiance * weight;
+            }
+        });
+
+        let groups = groups.into_iter().map(|group| group * recip_spp).collect();
+        (col * recip_spp, groups)
+    }
+
+    /// Generates the stratified camera rays of a pixel in order and hands each to
+    /// `sample` to trace.
+    fn for_each_sample(
+        &self,
+        rng: &mut rng::SampleRng,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        mut sample: impl FnMut(&mut rng::SampleRng, &ray::Ray, vec::Vec3),
+    ) {
+        let recip_spp_sqrt = 1.0 / self.spp_sqrt as f32;
         for i in 0..self.spp_sqrt {
             for j in 0..self.spp_sqrt {
                 let u =
                     (x as f32 + (i as f32 + rng.random::<f32>()) * recip_spp_sqrt) / width as f32;
-                let v = (y as f32 + (j as f32 + rng.random::<f32>()) * recip_spp_sqrt)
-                    / height as f32;
+                let v =
+                    (y as f32 + (j as f32 + rng.random::<f32>()) * recip_spp_sqrt) / height as f32;
 
-                let r = self.camera.get_ray(rng, u, v);
-                col = col + (self.trace)(rng, self.scene, &r, self.max_depth);
+                let (r, weight) = self.camera.get_weighted_ray(rng, u, v);
+                sample(rng, &r, weight);
             }
         }
+    }
+}
+
+impl Sampleable for MonteCarloSampler<'_> {
+    fn sample_pixel(
+        &self,
+        rng: &mut rng::SampleRng,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> vec::Vec3 {
+        self.accumulate(rng, x, y, width, height, None).0
+    }
 
-        col * recip_spp
+    fn pixel_order(&self) -> traversal::PixelOrder {
+        self.pixel_order
     }
 }
 
-fn square_spp(spp: u32) -> (u32, u32) {
+/// Snaps a sample count to a perfect square, returning `(sqrt, sqrt * sqrt)`.
+pub(crate) fn square_spp(spp: u32) -> (u32, u32) {
     let sqrt = (spp as f32).sqrt() as u32;
     (sqrt, sqrt * sqrt)
 }
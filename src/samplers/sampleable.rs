@@ -1,12 +1,18 @@
-use crate::math::vec;
+use crate::math::{rng, vec};
+use crate::samplers::traversal;
 
 pub trait Sampleable {
     fn sample_pixel(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut rng::SampleRng,
         x: u32,
         y: u32,
         width: u32,
         height: u32,
     ) -> vec::Vec3;
+
+    /// Order in which pixels of a chunk should be handed to [`Sampleable::sample_pixel`].
+    fn pixel_order(&self) -> traversal::PixelOrder {
+        traversal::PixelOrder::Scanline
+    }
 }
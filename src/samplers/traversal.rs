@@ -0,0 +1,42 @@
+//! Orders in which a sampler visits the pixels of a chunk.
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PixelOrder {
+    /// Row by row, left to right.
+    #[default]
+    Scanline,
+    /// Z-order curve, so consecutive pixels stay spatially close in both axes and their rays
+    /// tend to traverse the same BVH nodes.
+    Morton,
+}
+
+impl PixelOrder {
+    /// Returns the `(x, y)` pixels in `[x_start, x_end) x [y_start, y_end)` in visiting order.
+    pub fn pixels(&self, x_start: u32, x_end: u32, y_start: u32, y_end: u32) -> Vec<(u32, u32)> {
+        let mut pixels: Vec<(u32, u32)> = (y_start..y_end)
+            .flat_map(|y| (x_start..x_end).map(move |x| (x, y)))
+            .collect();
+
+        if *self == PixelOrder::Morton {
+            pixels.sort_by_key(|&(x, y)| morton_code(x - x_start, y - y_start));
+        }
+
+        pixels
+    }
+}
+
+/// Interleaves the bits of `x` and `y` (x in the even bits).
+fn morton_code(x: u32, y: u32) -> u64 {
+    spread_bits(x) | (spread_bits(y) << 1)
+}
+
+fn spread_bits(value: u32) -> u64 {
+    let mut v = value as u64;
+    v = (v | (v << 16)) & 0x0000_ffff_0000_ffff;
+    v = (v | (v << 8)) & 0x00ff_00ff_00ff_00ff;
+    v = (v | (v << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+    v = (v | (v << 1)) & 0x5555_5555_5555_5555;
+    v
+}
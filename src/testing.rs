@@ -0,0 +1,289 @@
+//! Golden-image regression testing.
+//!
+//! Renders are seeded per pixel, so a scene rendered with the same settings always
+//! produces the same image. [`render_builtin`] renders one of the bundled scenes at a
+//! tiny resolution and sample count, and [`assert_matches_golden`] compares the result
+//! against a reference image checked into `tests/golden/`. The comparison is perceptual
+//! (CIE76 ΔE over a lightly blurred image) rather than byte-exact, so last-bit float
+//! differences between platforms pass while changes to integrators or materials that
+//! visibly alter output fail.
+//!
+//! Set `RUSTRAY_UPDATE_GOLDEN=1` to write the current renders as the new references
+//! after an intentional change.
+use std::path::{Path, PathBuf};
+
+use crate::core::{color, render, scene_file};
+use crate::math::vec;
+
+/// Environment variable that makes [`assert_matches_golden`] overwrite references.
+pub const UPDATE_ENV: &str = "RUSTRAY_UPDATE_GOLDEN";
+
+/// Scenes shipped in `scenes/` that golden tests can render.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuiltinScene {
+    CornellBox,
+    BouncingSpheres,
+    NextWeek,
+}
+
+impl BuiltinScene {
+    pub const ALL: [BuiltinScene; 3] = [
+        BuiltinScene::CornellBox,
+        BuiltinScene::BouncingSpheres,
+        BuiltinScene::NextWeek,
+    ];
+
+    /// Name used for the scene file and its golden image.
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinScene::CornellBox => "cornell_box",
+            BuiltinScene::BouncingSpheres => "bouncing_spheres",
+            BuiltinScene::NextWeek => "next_week_scene",
+        }
+    }
+
+    pub fn path(self) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("scenes")
+            .join(format!("{}.toml", self.name()))
+    }
+}
+
+/// Output settings for a golden render; everything else comes from the scene file.
+#[derive(Clone, Copy, Debug)]
+pub struct GoldenSettings {
+    pub width: u32,
+    pub samples: u32,
+    pub depth: u32,
+    pub seed: u64,
+}
+
+impl Default for GoldenSettings {
+    fn default() -> Self {
+        GoldenSettings {
+            width: 48,
+            samples: 16,
+            depth: 8,
+            seed: 0,
+        }
+    }
+}
+
+/// How far a render may drift from its reference before the assertion fails.
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance {
+    /// Largest allowed mean ΔE over the whole image.
+    pub mean_delta_e: f32,
+    /// ΔE above which a single pixel counts as different.
+    pub pixel_delta_e: f32,
+    /// Largest allowed fraction of differing pixels.
+    pub max_differing: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            mean_delta_e: 1.0,
+            pixel_delta_e: 10.0,
+            max_differing: 0.01,
+        }
+    }
+}
+
+/// Result of comparing two images with [`perceptual_diff`].
+#[derive(Clone, Copy, Debug)]
+pub struct PerceptualDiff {
+    pub mean_delta_e: f32,
+    pub max_delta_e: f32,
+    /// Fraction of pixels whose ΔE exceeds the tolerance's `pixel_delta_e`.
+    pub differing: f32,
+}
+
+impl PerceptualDiff {
+    pub fn within(&self, tolerance: &Tolerance) -> bool {
+        self.mean_delta_e <= tolerance.mean_delta_e && self.differing <= tolerance.max_differing
+    }
+}
+
+/// Renders a built-in scene with the given settings.
+pub fn render_builtin(scene: BuiltinScene, settings: GoldenSettings) -> image::RgbImage {
+    render_scene(&scene.path(), settings)
+}
+
+/// Loads a scene file and renders it with the given settings, overriding the file's
+/// resolution, sampling, and seed.
+pub fn render_scene(path: &Path, settings: GoldenSettings) -> image::RgbImage {
+    let mut rng = rand::rng();
+    let mut render = scene_file::load_render(&mut rng, path)
+        .unwrap_or_else(|err| panic!("failed to load {}: {}", path.display(), err));
+    render
+        .set_width(settings.width)
+        .unwrap_or_else(|err| panic!("invalid golden width: {}", err));
+    render.samples = settings.samples;
+    render.depth = settings.depth;
+    render.seed = settings.seed;
+    render.dither = false;
+    render.scale = 1.0;
+    encode_image(&render)
+}
+
+fn encode_image(render: &render::Render) -> image::RgbImage {
+    let data = render.encode(&crate::raytrace_film(render));
+    image::RgbImage::from_raw(render.image_width(), render.image_height(), data)
+        .expect("film size matches the render's image size")
+}
+
+/// Compares two equally sized images perceptually.
+///
+/// Both images get a 3×3 box blur first so that per-pixel sampling noise which moves
+/// around without changing the picture doesn't dominate, then each pixel's color
+/// difference is measured as CIE76 ΔE in L*a*b* (about 2.3 is a just-noticeable
+/// difference).
+pub fn perceptual_diff(
+    expected: &image::RgbImage,
+    actual: &image::RgbImage,
+    tolerance: &Tolerance,
+) -> PerceptualDiff {
+    assert_eq!(
+        expected.dimensions(),
+        actual.dimensions(),
+        "images differ in size"
+    );
+    let expected = blurred_lab(expected);
+    let actual = blurred_lab(actual);
+
+    let mut total = 0.0;
+    let mut max = 0.0_f32;
+    let mut differing = 0;
+    for (a, b) in expected.iter().zip(actual.iter()) {
+        let delta_e =
+            ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt();
+        total += delta_e;
+        max = max.max(delta_e);
+        if delta_e > tolerance.pixel_delta_e {
+            differing += 1;
+        }
+    }
+
+    let count = expected.len().max(1) as f32;
+    PerceptualDiff {
+        mean_delta_e: total / count,
+        max_delta_e: max,
+        differing: differing as f32 / count,
+    }
+}
+
+/// Asserts that `image` matches the golden image `tests/golden/<name>.png` under the
+/// default [`Tolerance`].
+pub fn assert_matches_golden(name: &str, image: &image::RgbImage) {
+    assert_matches_golden_with(name, image, &Tolerance::default());
+}
+
+/// Like [`assert_matches_golden`] with an explicit tolerance.
+///
+/// On failure the render is written next to the reference as `<name>.actual.png` for
+/// inspection. With `RUSTRAY_UPDATE_GOLDEN=1` set, the reference is overwritten instead.
+pub fn assert_matches_golden_with(name: &str, image: &image::RgbImage, tolerance: &Tolerance) {
+    let dir = golden_dir();
+    let golden_path = dir.join(format!("{}.png", name));
+
+    if std::env::var_os(UPDATE_ENV).is_some_and(|value| value != "0") {
+        std::fs::create_dir_all(&dir).expect("failed to create the golden image directory");
+        image
+            .save(&golden_path)
+            .unwrap_or_else(|err| panic!("failed to write {}: {}", golden_path.display(), err));
+        return;
+    }
+
+    let expected = match image::open(&golden_path) {
+        Ok(expected) => expected.to_rgb8(),
+        Err(err) => panic!(
+            "missing golden image {} ({}); rerun with {}=1 to create it",
+            golden_path.display(),
+            err,
+            UPDATE_ENV
+        ),
+    };
+    if expected.dimensions() != image.dimensions() {
+        panic!(
+            "golden image {} is {:?} but the render is {:?}",
+            golden_path.display(),
+            expected.dimensions(),
+            image.dimensions()
+        );
+    }
+
+    let diff = perceptual_diff(&expected, image, tolerance);
+    if !diff.within(tolerance) {
+        let actual_path = dir.join(format!("{}.actual.png", name));
+        let saved = image.save(&actual_path).is_ok();
+        panic!(
+            "render differs from {}: mean ΔE {:.2} (max {:.2}), {:.2}% of pixels above ΔE {}{}",
+            golden_path.display(),
+            diff.mean_delta_e,
+            diff.max_delta_e,
+            diff.differing * 100.0,
+            tolerance.pixel_delta_e,
+            if saved {
+                format!("; actual render saved to {}", actual_path.display())
+            } else {
+                String::new()
+            }
+        );
+    }
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+}
+
+/// Converts an image to L*a*b* after a 3×3 box blur.
+fn blurred_lab(image: &image::RgbImage) -> Vec<[f32; 3]> {
+    let (width, height) = image.dimensions();
+    let mut lab = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0_f32; 3];
+            let mut count = 0.0;
+            for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                    let pixel = image.get_pixel(nx, ny);
+                    for (channel, value) in sum.iter_mut().zip(pixel.0) {
+                        *channel += srgb_to_linear(value);
+                    }
+                    count += 1.0;
+                }
+            }
+            lab.push(linear_to_lab(sum.map(|channel| channel / count)));
+        }
+    }
+    lab
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Linear sRGB to CIE L*a*b* under a D65 white point.
+fn linear_to_lab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = color::luminance(vec::Vec3::new(r, g, b));
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f32| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
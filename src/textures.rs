@@ -1,4 +1,6 @@
 pub mod checker;
 pub mod color;
 pub mod noise;
+pub mod streaming;
 pub mod uv;
+pub mod vertex_color;
@@ -0,0 +1,299 @@
+//! Memory-mapped image textures loaded a tile at a time.
+//!
+//! A streamed texture is decoded once into a tiled file in the asset cache (see
+//! [`assets::cache_dir`]), keyed by the source image's contents, and that file is mapped
+//! into memory; tiles are paged in only when rays sample them, and later loads skip
+//! decoding entirely. Tiles from every streamed texture share one memory budget
+//! (`$RUSTRAY_TEXTURE_BUDGET_MB`, or [`set_budget`]). Past it, the least recently
+//! sampled tiles are handed back to the operating system, so texture memory stays
+//! bounded however many large textures a scene uses.
+//!
+//! Recency is tracked with the clock (second-chance) approximation of LRU, so sampling
+//! a tile that's already resident only sets a flag.
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use memmap2::Mmap;
+
+use crate::core::assets;
+
+/// Environment variable that sets the streamed texture budget in MiB.
+pub const BUDGET_ENV: &str = "RUSTRAY_TEXTURE_BUDGET_MB";
+
+/// Budget in MiB when neither [`BUDGET_ENV`] nor [`set_budget`] gives one.
+const DEFAULT_BUDGET_MB: usize = 512;
+
+/// Width and height of a tile in texels.
+pub const TILE_SIZE: u32 = 64;
+
+/// Bytes per RGB8 tile, a whole number of 4 KiB pages so tiles can be released one at
+/// a time.
+const TILE_BYTES: usize = (TILE_SIZE * TILE_SIZE * 3) as usize;
+
+/// Bytes before the first tile: the magic, width, and height, padded to a page.
+const HEADER_BYTES: usize = 4096;
+
+const MAGIC: &[u8; 8] = b"RTTILES1";
+
+/// Budget in bytes; zero until set or first read from the environment.
+static BUDGET: AtomicUsize = AtomicUsize::new(0);
+
+static RESIDENCY: Mutex<Residency> = Mutex::new(Residency {
+    tiles: VecDeque::new(),
+    bytes: 0,
+});
+
+/// Sets how many bytes of streamed texture tiles may be resident at once, across all
+/// streamed textures.
+pub fn set_budget(bytes: usize) {
+    BUDGET.store(bytes.max(TILE_BYTES), Ordering::Relaxed);
+}
+
+/// Bytes of streamed texture tiles allowed to be resident at once.
+pub fn budget() -> usize {
+    match BUDGET.load(Ordering::Relaxed) {
+        0 => {
+            let megabytes = std::env::var(BUDGET_ENV)
+                .ok()
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(DEFAULT_BUDGET_MB);
+            let bytes = (megabytes << 20).max(TILE_BYTES);
+            BUDGET.store(bytes, Ordering::Relaxed);
+            bytes
+        }
+        bytes => bytes,
+    }
+}
+
+/// Bytes of streamed texture tiles currently counted against the budget.
+pub fn resident_bytes() -> usize {
+    RESIDENCY.lock().expect("texture residency lock").bytes
+}
+
+/// An RGB8 image stored as tiles in a memory-mapped file.
+pub struct TiledImage {
+    width: u32,
+    height: u32,
+    tiles_x: u32,
+    map: Mmap,
+    /// Per tile, whether it's counted against the budget.
+    resident: Vec<AtomicBool>,
+    /// Per tile, whether it's been sampled since the clock hand last passed it.
+    referenced: Vec<AtomicBool>,
+}
+
+impl TiledImage {
+    /// Maps the tiled copy of the image at `source`, decoding it into the cache first
+    /// if this image hasn't been streamed before.
+    pub fn open(source: &Path) -> io::Result<Arc<Self>> {
+        let bytes = fs::read(source)?;
+        let tiled = assets::cache_dir()
+            .join("textures")
+            .join(format!("{:016x}.tiles", assets::content_hash(&bytes)));
+        if !tiled.is_file() {
+            let image = image::load_from_memory(&bytes)
+                .map_err(|err| {
+                    io::Error::other(format!(
+                        "failed to open texture {}: {}",
+                        source.display(),
+                        err
+                    ))
+                })?
+                .to_rgb8();
+            let (width, height) = image.dimensions();
+            write_tiles(&tiled, image.as_raw(), width, height)?;
+        }
+        TiledImage::map(&tiled)
+    }
+
+    fn map(path: &Path) -> io::Result<Arc<Self>> {
+        let file = fs::File::open(path)?;
+        // Safety: cache files are written under a temporary name and renamed into
+        // place whole, and nothing writes to them afterwards.
+        let map = unsafe { Mmap::map(&file)? };
+        let invalid = || io::Error::other(format!("{} is not a tiled texture", path.display()));
+        if map.len() < HEADER_BYTES || &map[..MAGIC.len()] != MAGIC {
+            return Err(invalid());
+        }
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes(map[offset..offset + 4].try_into().expect("4 bytes"))
+        };
+        let (width, height) = (read_u32(MAGIC.len()), read_u32(MAGIC.len() + 4));
+        let (tiles_x, tiles_y) = (width.div_ceil(TILE_SIZE), height.div_ceil(TILE_SIZE));
+        let tiles = tiles_x as usize * tiles_y as usize;
+        if width == 0 || height == 0 || map.len() != HEADER_BYTES + tiles * TILE_BYTES {
+            return Err(invalid());
+        }
+        Ok(Arc::new(TiledImage {
+            width,
+            height,
+            tiles_x,
+            map,
+            resident: (0..tiles).map(|_| AtomicBool::new(false)).collect(),
+            referenced: (0..tiles).map(|_| AtomicBool::new(false)).collect(),
+        }))
+    }
+
+    /// Image size in texels.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The texel in column `x` of row `y`, counting rows from the top.
+    pub fn texel(self: &Arc<Self>, x: u32, y: u32) -> [u8; 3] {
+        let tile = (y / TILE_SIZE) as usize * self.tiles_x as usize + (x / TILE_SIZE) as usize;
+        if !self.referenced[tile].load(Ordering::Relaxed) {
+            self.referenced[tile].store(true, Ordering::Relaxed);
+        }
+        if !self.resident[tile].load(Ordering::Relaxed)
+            && !self.resident[tile].swap(true, Ordering::Relaxed)
+        {
+            admit(Arc::downgrade(self), tile);
+        }
+        let within = ((y % TILE_SIZE) * TILE_SIZE + x % TILE_SIZE) as usize * 3;
+        let offset = HEADER_BYTES + tile * TILE_BYTES + within;
+        [self.map[offset], self.map[offset + 1], self.map[offset + 2]]
+    }
+
+    /// Hands a tile's pages back to the operating system; it's read from the file again
+    /// if sampled later.
+    fn release(&self, tile: usize) {
+        self.resident[tile].store(false, Ordering::Relaxed);
+        release_pages(&self.map, HEADER_BYTES + tile * TILE_BYTES, TILE_BYTES);
+    }
+}
+
+#[cfg(unix)]
+fn release_pages(map: &Mmap, offset: usize, len: usize) {
+    // Safety: the mapping is a read-only view of a file, so dropped pages are read back
+    // from the file unchanged on the next access.
+    let _ = unsafe { map.unchecked_advise_range(memmap2::UncheckedAdvice::DontNeed, offset, len) };
+}
+
+/// Without `madvise`, released tiles stay mapped until the operating system reclaims
+/// them on its own.
+#[cfg(not(unix))]
+fn release_pages(_map: &Mmap, _offset: usize, _len: usize) {}
+
+/// Resident tiles of every streamed texture, in the order the clock hand visits them.
+struct Residency {
+    tiles: VecDeque<(Weak<TiledImage>, usize)>,
+    bytes: usize,
+}
+
+/// Counts a newly resident tile against the budget, releasing tiles that haven't been
+/// sampled recently until the budget holds.
+fn admit(image: Weak<TiledImage>, tile: usize) {
+    let budget = budget();
+    let mut residency = RESIDENCY.lock().expect("texture residency lock");
+    residency.tiles.push_back((image, tile));
+    residency.bytes += TILE_BYTES;
+
+    // Two sweeps clear every referenced flag, so the hand always finds a tile to
+    // release by then.
+    let mut visits = 2 * residency.tiles.len();
+    while residency.bytes > budget && visits > 0 {
+        visits -= 1;
+        let Some((image, tile)) = residency.tiles.pop_front() else {
+            break;
+        };
+        let Some(live) = image.upgrade() else {
+            residency.bytes -= TILE_BYTES;
+            continue;
+        };
+        if live.referenced[tile].swap(false, Ordering::Relaxed) {
+            residency.tiles.push_back((image, tile));
+            continue;
+        }
+        live.release(tile);
+        residency.bytes -= TILE_BYTES;
+    }
+}
+
+/// Writes `pixels`, tightly packed RGB8 rows from the top, to `path` as tiles.
+fn write_tiles(path: &Path, pixels: &[u8], width: u32, height: u32) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut header = vec![0_u8; HEADER_BYTES];
+    header[..MAGIC.len()].copy_from_slice(MAGIC);
+    header[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&width.to_le_bytes());
+    header[MAGIC.len() + 4..MAGIC.len() + 8].copy_from_slice(&height.to_le_bytes());
+
+    // Write under a name of its own and rename, so a half-written file never looks
+    // cached and textures converted in parallel don't collide.
+    static CONVERSIONS: AtomicUsize = AtomicUsize::new(0);
+    let partial = path.with_extension(format!(
+        "partial-{}-{}",
+        std::process::id(),
+        CONVERSIONS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = io::BufWriter::new(fs::File::create(&partial)?);
+    file.write_all(&header)?;
+    let row_bytes = TILE_SIZE as usize * 3;
+    let mut tile = vec![0_u8; TILE_BYTES];
+    for tile_y in 0..height.div_ceil(TILE_SIZE) {
+        for tile_x in 0..width.div_ceil(TILE_SIZE) {
+            tile.fill(0);
+            let (x_start, y_start) = (tile_x * TILE_SIZE, tile_y * TILE_SIZE);
+            let columns = (width - x_start).min(TILE_SIZE) as usize;
+            for row in 0..(height - y_start).min(TILE_SIZE) as usize {
+                let source = ((y_start as usize + row) * width as usize + x_start as usize) * 3;
+                tile[row * row_bytes..row * row_bytes + columns * 3]
+                    .copy_from_slice(&pixels[source..source + columns * 3]);
+            }
+            file.write_all(&tile)?;
+        }
+    }
+    file.into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()?;
+    fs::rename(&partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resident(image: &TiledImage) -> Vec<bool> {
+        image
+            .resident
+            .iter()
+            .map(|tile| tile.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    #[test]
+    fn the_clock_releases_tiles_not_sampled_since_its_last_pass() {
+        // A row of four tiles, each filled with its own index.
+        let width = 4 * TILE_SIZE;
+        let pixels: Vec<u8> = (0..TILE_SIZE * width)
+            .flat_map(|texel| [(texel % width / TILE_SIZE) as u8; 3])
+            .collect();
+        let path = std::env::temp_dir().join(format!("rustray-clock-{}.tiles", std::process::id()));
+        write_tiles(&path, &pixels, width, TILE_SIZE).unwrap();
+        let image = TiledImage::map(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        set_budget(3 * TILE_BYTES);
+        let sample = |tile: u32| image.texel(tile * TILE_SIZE, 0);
+
+        for tile in 0..3 {
+            assert_eq!(sample(tile), [tile as u8; 3]);
+        }
+        assert_eq!(resident(&image), [true, true, true, false]);
+
+        // Every tile was sampled once, so the hand clears them all and the oldest goes.
+        sample(3);
+        assert_eq!(resident(&image), [false, true, true, true]);
+
+        // Tile 1 gets a second chance; tile 2, next in line, goes instead.
+        sample(1);
+        assert_eq!(sample(0), [0; 3]);
+        assert_eq!(resident(&image), [true, true, false, true]);
+        assert_eq!(sample(2), [2; 3], "released tiles read back from the file");
+    }
+}
@@ -1,46 +1,435 @@
 extern crate image;
 
+use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize};
 
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::SystemTime;
+
+use crate::core::assets;
 use crate::math::{interval, vec};
+use crate::textures::streaming;
 use crate::traits::hittable;
 use crate::traits::texturable;
 
-#[derive(Clone, Serialize, Deserialize)]
+/// Image texture addressed by the hit's `(u, v)` coordinates.
+///
+/// Sampling is bilinear, wraps horizontally so the `u = 0`/`u = 1` seam of an
+/// equirectangular map blends across the edge, and fades toward the average
+/// color of the first/last row at the poles so they don't pinch into streaks.
+///
+/// In scene files the image is either embedded (`data`, `width`, `height`) or
+/// referenced by `path`, which may be a local file or an `http(s)://` URL
+/// fetched through [`assets::resolve`]. Referenced images can set `stream = true` to
+/// be loaded a tile at a time within a shared memory budget ([`streaming`]) instead of
+/// decoded into memory whole, or `alpha = true` to read the image's alpha channel as a
+/// gray texture instead of its colors, e.g. as an
+/// [`crate::materials::opacity::Opacity`] mask.
+///
+/// Images loaded by `path` are decoded once and shared: every texture opening the same
+/// file (unchanged since) reads the same pixels while any of them is alive.
+#[derive(Clone)]
 pub struct UvTexture {
-    data: Vec<u8>,
+    data: Texels,
+    width: u32,
+    height: u32,
+
+    /// Asset reference the image was loaded from, kept so saving a scene writes
+    /// the reference instead of embedding the pixels.
+    source: Option<String>,
+    /// Whether the texels are the source image's alpha channel rather than its colors.
+    alpha: bool,
+    poles: [vec::Vec3; 2],
+}
+
+#[derive(Clone)]
+enum Texels {
+    /// Tightly packed RGB8 rows from the top.
+    Memory(Arc<Vec<u8>>),
+    Streamed(Arc<streaming::TiledImage>),
+}
+
+/// Identifies a decoded image: the file, when it was last modified, and whether its
+/// alpha channel was read.
+type CacheKey = (PathBuf, Option<SystemTime>, bool);
+
+/// Decoded pixels of one image, held only while some texture uses them.
+#[derive(Default)]
+struct CacheSlot {
+    pixels: Weak<Vec<u8>>,
     width: u32,
     height: u32,
 }
 
+/// Images decoded into memory, by file. Each image has its own slot, so scenes loading
+/// in parallel decode different images at once but each image only once.
+static CACHE: Mutex<BTreeMap<CacheKey, Arc<Mutex<CacheSlot>>>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    /// The last texture on this thread that failed to load while deserializing.
+    static LOAD_ERROR: RefCell<Option<(String, io::Error)>> = const { RefCell::new(None) };
+}
+
+/// Takes the reference and error of the last texture that failed to load while
+/// deserializing on this thread, for reporting it as more than a parse error.
+pub fn take_load_error() -> Option<(String, io::Error)> {
+    LOAD_ERROR.with(|error| error.borrow_mut().take())
+}
+
+/// Decoded pixels of the image at `path`, shared with any texture that already has them.
+fn cached_pixels(path: &Path, alpha: bool) -> io::Result<(Arc<Vec<u8>>, u32, u32)> {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let file = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let slot = CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .entry((file, modified, alpha))
+        .or_default()
+        .clone();
+    let mut slot = slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(pixels) = slot.pixels.upgrade() {
+        return Ok((pixels, slot.width, slot.height));
+    }
+
+    let img = image::open(path).map_err(io::Error::other)?;
+    let (pixels, (width, height)) = if alpha {
+        let img = img.to_rgba8();
+        let pixels = img.pixels().flat_map(|pixel| [pixel.0[3]; 3]).collect();
+        (pixels, img.dimensions())
+    } else {
+        let img = img.to_rgb8();
+        let dimensions = img.dimensions();
+        (img.into_raw(), dimensions)
+    };
+    let pixels = Arc::new(pixels);
+    *slot = CacheSlot {
+        pixels: Arc::downgrade(&pixels),
+        width,
+        height,
+    };
+    Ok((pixels, width, height))
+}
+
 impl UvTexture {
+    /// Loads an image like [`UvTexture::open`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image can't be loaded.
     pub fn new(path: &str) -> Self {
-        let img = image::open(path).expect("Failed to open UV texture image");
-        let img = img.to_rgb8();
-        let (width, height) = img.dimensions();
-        let data = img.into_raw();
-        UvTexture {
+        UvTexture::open(path).expect("Failed to open UV texture image")
+    }
+
+    /// Loads an image from a local path or `http(s)://` URL.
+    pub fn open(reference: &str) -> io::Result<Self> {
+        UvTexture::open_cached(reference, false)
+    }
+
+    /// Loads the alpha channel of an image from a local path or `http(s)://` URL as a
+    /// gray texture; images without one are opaque white.
+    pub fn open_alpha(reference: &str) -> io::Result<Self> {
+        UvTexture::open_cached(reference, true)
+    }
+
+    fn open_cached(reference: &str, alpha: bool) -> io::Result<Self> {
+        let path = assets::resolve(reference)?;
+        let (pixels, width, height) = cached_pixels(&path, alpha).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("failed to open texture {}: {}", reference, err),
+            )
+        })?;
+        let mut texture = UvTexture::with_texels(Texels::Memory(pixels), width, height);
+        texture.source = Some(reference.to_string());
+        texture.alpha = alpha;
+        Ok(texture)
+    }
+
+    /// Loads an image from a local path or `http(s)://` URL for streaming: it's decoded
+    /// into the asset cache once, then mapped and read a tile at a time (see
+    /// [`streaming`]).
+    pub fn open_streamed(reference: &str) -> io::Result<Self> {
+        let path = assets::resolve(reference)?;
+        let image = streaming::TiledImage::open(&path).map_err(|err| {
+            io::Error::other(format!("failed to stream texture {}: {}", reference, err))
+        })?;
+        let (width, height) = image.dimensions();
+        let mut texture = UvTexture::with_texels(Texels::Streamed(image), width, height);
+        texture.source = Some(reference.to_string());
+        Ok(texture)
+    }
+
+    /// Builds a texture from tightly packed RGB8 pixel data in row-major order.
+    pub fn from_rgb8(data: Vec<u8>, width: u32, height: u32) -> Self {
+        UvTexture::with_texels(Texels::Memory(Arc::new(data)), width, height)
+    }
+
+    fn with_texels(data: Texels, width: u32, height: u32) -> Self {
+        let mut texture = UvTexture {
             data,
             width,
             height,
+            source: None,
+            alpha: false,
+            poles: [vec::Vec3::default(); 2],
+        };
+        texture.poles = [
+            texture.row_average(0),
+            texture.row_average(height.saturating_sub(1)),
+        ];
+        texture
+    }
+
+    /// Whether the image is streamed rather than held in memory.
+    pub fn is_streamed(&self) -> bool {
+        matches!(self.data, Texels::Streamed(_))
+    }
+
+    /// Image size in pixels.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn texel(&self, i: i64, j: i64) -> vec::Vec3 {
+        // Wrap horizontally (longitude) and clamp vertically (latitude).
+        let i = i.rem_euclid(self.width as i64) as u32;
+        let j = j.clamp(0, self.height as i64 - 1) as u32;
+        let [r, g, b] = self.rgb8(i, j);
+        vec::Vec3::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+    }
+
+    fn rgb8(&self, i: u32, j: u32) -> [u8; 3] {
+        match &self.data {
+            Texels::Memory(data) => {
+                let pixel_index = ((j * self.width + i) * 3) as usize;
+                [
+                    data[pixel_index],
+                    data[pixel_index + 1],
+                    data[pixel_index + 2],
+                ]
+            }
+            Texels::Streamed(image) => image.texel(i, j),
+        }
+    }
+
+    fn row_average(&self, j: u32) -> vec::Vec3 {
+        if self.width == 0 || self.height == 0 {
+            return vec::Vec3::default();
+        }
+        let sum = (0..self.width as i64)
+            .fold(vec::Vec3::default(), |acc, i| acc + self.texel(i, j as i64));
+        sum / self.width as f32
+    }
+}
+
+impl Serialize for UvTexture {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if let Some(source) = &self.source {
+            let streamed = self.is_streamed();
+            let mut state = serializer
+                .serialize_struct("UvTexture", 1 + streamed as usize + self.alpha as usize)?;
+            state.serialize_field("path", source)?;
+            if streamed {
+                state.serialize_field("stream", &true)?;
+            }
+            if self.alpha {
+                state.serialize_field("alpha", &true)?;
+            }
+            return state.end();
+        }
+        let data: Vec<u8> = match &self.data {
+            Texels::Memory(data) => data.to_vec(),
+            Texels::Streamed(_) => (0..self.height)
+                .flat_map(|j| (0..self.width).flat_map(move |i| self.rgb8(i, j)))
+                .collect(),
+        };
+        let mut state = serializer.serialize_struct("UvTexture", 3)?;
+        state.serialize_field("data", &data)?;
+        state.serialize_field("width", &self.width)?;
+        state.serialize_field("height", &self.height)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for UvTexture {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct UvTextureData {
+            path: Option<String>,
+            #[serde(default)]
+            stream: bool,
+            #[serde(default)]
+            alpha: bool,
+            data: Option<Vec<u8>>,
+            width: Option<u32>,
+            height: Option<u32>,
+        }
+
+        let data = UvTextureData::deserialize(deserializer)?;
+        if let Some(path) = data.path {
+            let texture = if data.alpha && data.stream {
+                return Err(serde::de::Error::custom(
+                    "uv texture can't both `stream` and read `alpha`",
+                ));
+            } else if data.alpha {
+                UvTexture::open_alpha(&path)
+            } else if data.stream {
+                UvTexture::open_streamed(&path)
+            } else {
+                UvTexture::open(&path)
+            };
+            return texture.map_err(|err| {
+                let message = err.to_string();
+                LOAD_ERROR.with(|error| *error.borrow_mut() = Some((path, err)));
+                serde::de::Error::custom(message)
+            });
+        }
+        match (data.data, data.width, data.height) {
+            (Some(_), Some(0), _) | (Some(_), _, Some(0)) => Err(serde::de::Error::custom(
+                "uv texture `width` and `height` must be positive",
+            )),
+            (Some(pixels), Some(width), Some(height)) => {
+                let expected = width as u64 * height as u64 * 3;
+                if pixels.len() as u64 != expected {
+                    return Err(serde::de::Error::custom(format!(
+                        "uv texture `data` holds {} bytes, but a {}x{} RGB image needs {}",
+                        pixels.len(),
+                        width,
+                        height,
+                        expected
+                    )));
+                }
+                Ok(UvTexture::from_rgb8(pixels, width, height))
+            }
+            _ => Err(serde::de::Error::custom(
+                "uv texture needs either `path` or `data`, `width`, and `height`",
+            )),
         }
     }
 }
 
 impl texturable::Texturable for UvTexture {
     fn sample(&self, hit: &hittable::Hit) -> vec::Vec3 {
-        let u = interval::Interval::new(0.0, 1.0).clamp(hit.u);
+        let u = hit.u - hit.u.floor();
         let v = interval::Interval::new(0.0, 1.0).clamp(hit.v);
-        let i = ((u * self.width as f32) as u32).min(self.width - 1);
-        let j = (((1.0 - v) * self.height as f32) as u32).min(self.height - 1);
-        let pixel_index = ((j * self.width + i) * 3) as usize;
-        let r = self.data[pixel_index] as f32 / 255.0;
-        let g = self.data[pixel_index + 1] as f32 / 255.0;
-        let b = self.data[pixel_index + 2] as f32 / 255.0;
-        vec::Vec3::new(r, g, b)
+
+        // Continuous texel coordinates, with texel centers at half-integers.
+        let x = u * self.width as f32 - 0.5;
+        let y = (1.0 - v) * self.height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let (i, j) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(i, j) * (1.0 - fx) + self.texel(i + 1, j) * fx;
+        let bottom = self.texel(i, j + 1) * (1.0 - fx) + self.texel(i + 1, j + 1) * fx;
+        let color = top * (1.0 - fy) + bottom * fy;
+
+        // Within the first texel row of either pole every column converges on one point,
+        // so fade to the row average instead of letting the texels pinch.
+        let from_north = y + 0.5;
+        let from_south = self.height as f32 - 0.5 - y;
+        if from_north < 1.0 {
+            self.poles[0] * (1.0 - from_north) + color * from_north
+        } else if from_south < 1.0 {
+            self.poles[1] * (1.0 - from_south) + color * from_south
+        } else {
+            color
+        }
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::scene_file::{self, SceneFileError};
+
+    fn pixels(texture: &UvTexture) -> &Arc<Vec<u8>> {
+        match &texture.data {
+            Texels::Memory(pixels) => pixels,
+            Texels::Streamed(_) => panic!("expected decoded pixels"),
+        }
+    }
+
+    #[test]
+    fn missing_images_fail_the_scene_load() {
+        assert!(UvTexture::open("assets/missing.png").is_err());
+
+        let mut scene = std::fs::read_to_string("scenes/cornell_box.toml").unwrap();
+        scene.push_str(
+            r#"
+[[materials]]
+id = 1000
+sampleable = "Lambertian"
+
+[materials.data.texture]
+texturable = "Uv"
+
+[materials.data.texture.data]
+path = "assets/missing.png"
+"#,
+        );
+        let path = std::env::temp_dir().join(format!(
+            "rustray-missing-texture-{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(&path, scene).unwrap();
+        let result = scene_file::read_scene_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(SceneFileError::TextureLoad { path, .. }) => {
+                assert_eq!(path, "assets/missing.png")
+            }
+            Err(err) => panic!("expected a texture load error, got {}", err),
+            Ok(_) => panic!("scene with a missing texture loaded"),
+        }
+    }
+
+    #[test]
+    fn textures_of_one_image_share_its_pixels() {
+        let first: UvTexture = toml::from_str(r#"path = "assets/earth.jpg""#).unwrap();
+        let second: UvTexture = toml::from_str(r#"path = "assets/earth.jpg""#).unwrap();
+        assert!(Arc::ptr_eq(pixels(&first), pixels(&second)));
+
+        // Reading the alpha channel decodes the image differently, so it isn't shared.
+        let alpha = UvTexture::open_alpha("assets/earth.jpg").unwrap();
+        assert!(!Arc::ptr_eq(pixels(&first), pixels(&alpha)));
+    }
+
+    #[test]
+    fn inline_pixels_must_match_their_size() {
+        let texture: UvTexture =
+            toml::from_str("data = [255, 0, 0, 0, 255, 0]\nwidth = 2\nheight = 1")
+                .expect("a 2x1 image loads");
+        assert_eq!(pixels(&texture).len(), 6);
+
+        for (data, width, height) in [("[]", 0, 0), ("[255, 0, 0]", 1, 0), ("[255, 0, 0]", 2, 1)] {
+            let scene = format!("data = {}\nwidth = {}\nheight = {}", data, width, height);
+            assert!(
+                toml::from_str::<UvTexture>(&scene).is_err(),
+                "{}x{} texture with {} loaded",
+                width,
+                height,
+                data
+            );
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use crate::math::vec;
+use crate::traits::{hittable, texturable};
+
+/// Samples the per-vertex color interpolated at the hit (e.g. from a PLY scan), or
+/// `fallback` on geometry without vertex colors.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VertexColorTexture {
+    #[serde(default = "default_fallback")]
+    pub fallback: vec::Vec3,
+}
+
+impl VertexColorTexture {
+    pub fn new(fallback: vec::Vec3) -> Self {
+        VertexColorTexture { fallback }
+    }
+}
+
+fn default_fallback() -> vec::Vec3 {
+    vec::Vec3::new(0.5, 0.5, 0.5)
+}
+
+impl texturable::Texturable for VertexColorTexture {
+    fn sample(&self, hit: &hittable::Hit) -> vec::Vec3 {
+        hit.vertex_color.unwrap_or(self.fallback)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -1,8 +1,8 @@
 //! Abstractions for geometry that can be intersected by rays.
 use std::any::Any;
 
-use crate::core::{bbox, ray};
-use crate::math::{pdf, vec};
+use crate::core::{bbox, introspect, ray};
+use crate::math::{pdf, rng, vec};
 use crate::traits::renderable;
 
 /// Information about a ray-object intersection.
@@ -20,6 +20,31 @@ pub struct Hit {
     pub u: f32,
     /// Texture coordinates at the hit point.
     pub v: f32,
+    /// Material slot of the surface that was hit; 0 unless the geometry has several faces
+    /// that can carry their own material (see [`crate::materials::instance::MaterialInstance`]).
+    pub material_slot: usize,
+    /// Color interpolated from per-vertex colors, for geometry that carries them (e.g. PLY
+    /// scans); sampled by [`crate::textures::vertex_color::VertexColorTexture`].
+    pub vertex_color: Option<vec::Vec3>,
+    /// Normal interpolated from per-vertex normals, for geometry that carries them (see
+    /// [`crate::geometry::primitives::mesh::Mesh`]). `normal` stays the true surface
+    /// normal; [`crate::geometry::instance::GeometryInstance`] shades with this one instead
+    /// when smooth shading is on.
+    pub shading_normal: Option<vec::Vec3>,
+    /// Unit direction in which `u` increases across the surface, for geometry that knows
+    /// it (spheres and quads); orients anisotropic materials such as
+    /// [`crate::materials::anisotropic::Anisotropic`].
+    pub tangent: Option<vec::Vec3>,
+}
+
+/// A point drawn uniformly from a geometry's surface by [`Hittable::sample_surface`].
+#[derive(Clone, Copy, Debug)]
+pub struct SurfaceSample {
+    pub point: vec::Point3,
+    /// Unit surface normal at `point`.
+    pub normal: vec::Vec3,
+    /// Density of the sample per unit area (one over the surface area).
+    pub pdf: f32,
 }
 
 /// Trait for objects that can be intersected by rays.
@@ -35,8 +60,20 @@ pub trait Hittable: Any + Send + Sync {
     /// Returns a probability density function for sampling directions toward the object.
     fn get_pdf(&self, origin: &vec::Point3, time: f64) -> Box<dyn pdf::PDF + Send + Sync + '_>;
 
+    /// Draws a point uniformly from the surface at `time`, for integrators that start
+    /// paths on emitters (see [`crate::integrators::photon`]). `None` for geometry that
+    /// doesn't support it, which then can't emit photons.
+    fn sample_surface(&self, _rng: &mut rng::SampleRng, _time: f64) -> Option<SurfaceSample> {
+        None
+    }
+
     /// Allows downcasting to concrete types.
     fn as_any(&self) -> &dyn Any;
+
+    /// Short type name of the geometry (e.g. `Sphere`), for display in tools.
+    fn geometry_name(&self) -> &'static str {
+        introspect::short_type_name(std::any::type_name::<Self>())
+    }
 }
 
 /// A record of a hit, associating the hit information with the renderable object.
@@ -1,8 +1,8 @@
 //! Glue trait combining geometry (hittable) and material scattering.
 use std::any::Any;
 
-use crate::core::{bbox, ray};
-use crate::math::{pdf, vec};
+use crate::core::{bbox, path, ray};
+use crate::math::{pdf, rng, vec};
 use crate::traits::{hittable, scatterable};
 
 /// Trait for objects that can be rendered in the scene.
@@ -19,6 +19,14 @@ pub trait Renderable: Any + Send + Sync {
     /// An Option containing a [`hittable::HitRecord`] HitRecord if the ray hits the object, otherwise None.
     fn hit(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> Option<hittable::HitRecord<'_>>;
 
+    /// Returns whether the ray hits the renderable object within the given t range.
+    ///
+    /// Occlusion queries (shadow rays) only need a yes/no answer, so implementations should
+    /// skip building the [`hittable::HitRecord`] and its PDF where they can.
+    fn occludes(&self, ray: &ray::Ray, t_min: f32, t_max: f32) -> bool {
+        self.hit(ray, t_min, t_max).is_some()
+    }
+
     /// Returns the bounding box of the renderable object.
     fn bounding_box(&self) -> bbox::BBox;
 
@@ -28,13 +36,30 @@ pub trait Renderable: Any + Send + Sync {
     /// Produces a scatter record for the hit point.
     fn scatter(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut rng::SampleRng,
         hit_record: &hittable::HitRecord,
-        depth: u32,
+        state: &mut path::PathState,
     ) -> Option<scatterable::ScatterRecord>;
 
     /// Returns emitted radiance at the hit point.
     fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3;
 
+    /// Bounce limit of the material at `hit_record`, if it sets one (see
+    /// [`scatterable::Scatterable::max_bounces`]).
+    fn max_bounces(&self, _hit_record: &hittable::HitRecord) -> Option<u32> {
+        None
+    }
+
+    /// Whether a camera ray sees the material at `hit_record` (see
+    /// [`scatterable::Scatterable::camera_visible`]).
+    fn camera_visible(&self, _hit_record: &hittable::HitRecord) -> bool {
+        true
+    }
+
+    /// Index of the scene light group this renderable's emission is credited to, if any.
+    fn light_group(&self) -> Option<usize> {
+        None
+    }
+
     fn as_any(&self) -> &dyn Any;
 }
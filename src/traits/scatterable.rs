@@ -1,7 +1,8 @@
 use std::any::Any;
 
-use crate::core::ray;
-use crate::math::{pdf, vec};
+use crate::core::{introspect, path, ray};
+use crate::materials::normal_map;
+use crate::math::{pdf, rng, vec};
 use crate::traits::hittable;
 
 pub struct ScatterRecord {
@@ -18,12 +19,37 @@ pub struct ScatterRecord {
 pub trait Scatterable: Any + Send + Sync {
     fn scatter(
         &self,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut rng::SampleRng,
         hit_record: &hittable::HitRecord,
-        depth: u32,
+        state: &mut path::PathState,
     ) -> Option<ScatterRecord>;
 
     fn emit(&self, hit_record: &hittable::HitRecord) -> vec::Vec3;
 
+    /// Most bounces a path may have made and still scatter off this material, or `None`
+    /// to leave it to the path's shared budget alone (see
+    /// [`crate::materials::bounce_limit::BounceLimit`]).
+    fn max_bounces(&self) -> Option<u32> {
+        None
+    }
+
+    /// Whether a camera ray arriving at `hit_record` sees the surface; `false` lets it
+    /// pass straight through, as for lights hidden from view (see
+    /// [`crate::materials::diffuse_light::DiffuseLight::with_camera_visible`]).
+    fn camera_visible(&self, _hit_record: &hittable::HitRecord) -> bool {
+        true
+    }
+
+    /// The normal map bending this material's shading normals, if it has one, so scene
+    /// files can save it (see [`crate::materials::normal_map::NormalMap`]).
+    fn normal_map(&self) -> Option<&normal_map::NormalMap> {
+        None
+    }
+
     fn as_any(&self) -> &dyn Any;
+
+    /// Short type name of the material (e.g. `Lambertian`), for display in tools.
+    fn material_name(&self) -> &'static str {
+        introspect::short_type_name(std::any::type_name::<Self>())
+    }
 }
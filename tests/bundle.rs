@@ -0,0 +1,83 @@
+//! Scene bundles resolve relative asset references against the unpacked bundle.
+//!
+//! Geometries and materials load in parallel on worker threads, so this checks that the
+//! bundle directory reaches them rather than only the thread that started the load.
+use std::path::PathBuf;
+
+use rustray::core::scene_file;
+use rustray::testing::BuiltinScene;
+
+const TRIANGLE_PLY: &str = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0
+1 0 0
+0 1 0
+3 0 1 2
+";
+
+fn append(builder: &mut tar::Builder<Vec<u8>>, name: &str, contents: &[u8]) {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, name, contents)
+        .expect("entry is written");
+}
+
+/// Packs the Cornell box plus a mesh loaded from `triangle.ply` next to it.
+fn write_bundle() -> PathBuf {
+    let scene = std::fs::read_to_string(BuiltinScene::CornellBox.path()).expect("scene reads");
+    let mut scene: toml::Table = toml::from_str(&scene).expect("scene parses");
+    let mesh: toml::Table = toml::from_str(
+        r#"
+        id = 1000
+        hittable = "Mesh"
+        data = { path = "triangle.ply" }
+        "#,
+    )
+    .expect("mesh parses");
+    scene["geometries"]
+        .as_array_mut()
+        .expect("geometries is an array")
+        .push(toml::Value::Table(mesh));
+
+    let mut builder = tar::Builder::new(Vec::new());
+    append(
+        &mut builder,
+        "scene.toml",
+        toml::to_string(&scene)
+            .expect("scene serializes")
+            .as_bytes(),
+    );
+    append(&mut builder, "triangle.ply", TRIANGLE_PLY.as_bytes());
+    let bytes = builder.into_inner().expect("bundle is written");
+
+    let path = std::env::temp_dir().join(format!(
+        "rustray-bundle-test-{}.rustray",
+        std::process::id()
+    ));
+    std::fs::write(&path, bytes).expect("bundle saves");
+    path
+}
+
+#[test]
+fn bundles_load_relative_meshes() {
+    let path = write_bundle();
+    let loaded = scene_file::read_scene_file(&path);
+    std::fs::remove_file(&path).ok();
+    let file = loaded.unwrap_or_else(|err| panic!("bundle fails to load: {}", err));
+    assert!(
+        file.geometries
+            .iter()
+            .any(|geometry| matches!(geometry.geometry, scene_file::GeometryTemplate::Mesh(_))),
+        "the bundled mesh is missing"
+    );
+}
@@ -0,0 +1,138 @@
+//! White-furnace energy checks for the built-in materials.
+//!
+//! Materials that conserve energy must reflect exactly their albedo from a uniformly lit
+//! environment; see `rustray::core::furnace`.
+use std::sync::Arc;
+
+use rustray::core::furnace::{self, FurnaceReport, FurnaceSettings};
+use rustray::materials::{anisotropic, blinn_phong, dielectric, lambertian, metallic, velvet};
+use rustray::math::vec;
+use rustray::textures::color;
+use rustray::traits::scatterable::Scatterable;
+
+/// Largest allowed difference from the expected albedo, per channel.
+const TOLERANCE: f32 = 0.01;
+
+fn measure(material: Arc<dyn Scatterable + Send + Sync>) -> FurnaceReport {
+    let settings = FurnaceSettings {
+        samples: 1 << 14,
+        ..FurnaceSettings::default()
+    };
+    furnace::test(material, &settings)
+}
+
+fn assert_conserves_energy(material: Arc<dyn Scatterable + Send + Sync>) {
+    let report = measure(material);
+    assert!(report.expected.is_some(), "no expected albedo");
+    assert!(report.passed(TOLERANCE), "{:?}", report);
+}
+
+/// GGX reflects only light that leaves after a single bounce off the microfacets, so
+/// rough surfaces fall short of their tint by the share that would have bounced again.
+/// `(roughness, share reflected)`, integrated numerically from the GGX `D` and the
+/// height-correlated Smith `G`.
+const GGX_REFLECTED: [(f32, f32); 2] = [(0.5, 0.882), (1.0, 0.409)];
+
+/// Asserts `material` reflects `share` of `tint`.
+fn assert_reflects(material: Arc<dyn Scatterable + Send + Sync>, tint: vec::Vec3, share: f32) {
+    let report = FurnaceReport {
+        expected: Some(tint * share),
+        ..measure(material)
+    };
+    assert!(report.passed(TOLERANCE), "{:?}", report);
+}
+
+#[test]
+fn lambertian_reflects_its_albedo() {
+    let texture = color::ColorTexture::new(vec::Vec3::new(0.8, 0.5, 0.2));
+    assert_conserves_energy(Arc::new(lambertian::Lambertian::new(Box::new(texture))));
+}
+
+#[test]
+fn mirror_reflects_its_tint() {
+    let albedo = vec::Vec3::new(0.9, 0.6, 0.3);
+    assert_conserves_energy(Arc::new(metallic::Metallic::new(&albedo, 0.0)));
+}
+
+#[test]
+fn glass_reflects_everything() {
+    assert_conserves_energy(Arc::new(dielectric::Dielectric::new(1.5)));
+}
+
+#[test]
+fn ggx_metal_loses_only_multiple_scattering() {
+    let albedo = vec::Vec3::new(0.9, 0.6, 0.3);
+    let ggx = |roughness| {
+        Arc::new(metallic::Metallic::new(&albedo, roughness).with_model(metallic::MetalModel::Ggx))
+    };
+    // Smooth enough that almost nothing bounces twice.
+    assert_conserves_energy(ggx(0.1));
+    for (roughness, share) in GGX_REFLECTED {
+        assert_reflects(ggx(roughness), albedo, share);
+    }
+}
+
+#[test]
+fn evenly_brushed_metal_matches_ggx() {
+    let albedo = vec::Vec3::new(0.9, 0.6, 0.3);
+    assert_reflects(
+        Arc::new(anisotropic::Anisotropic::new(&albedo, 0.1, 0.05)),
+        albedo,
+        1.0,
+    );
+    for (roughness, share) in GGX_REFLECTED {
+        assert_reflects(
+            Arc::new(anisotropic::Anisotropic::new(&albedo, roughness, roughness)),
+            albedo,
+            share,
+        );
+    }
+}
+
+#[test]
+fn velvet_with_a_matching_sheen_reflects_its_color() {
+    // However the light splits between the sheen and the base, both reflect this color.
+    let color = vec::Vec3::new(0.8, 0.5, 0.2);
+    for roughness in [0.1, 0.5, 1.0] {
+        assert_conserves_energy(Arc::new(
+            velvet::Velvet::new(&color).with_sheen(&color, roughness),
+        ));
+    }
+}
+
+#[test]
+fn white_sheen_brightens_velvet_without_creating_energy() {
+    let gray = vec::Vec3::new(0.8, 0.8, 0.8);
+    let report = measure(Arc::new(velvet::Velvet::new(&gray)));
+    let allowed = TOLERANCE + 4.0 * report.standard_error;
+    assert!(report.measured.x > gray.x + allowed, "{:?}", report);
+    assert!(report.measured.x < 1.0 + allowed, "{:?}", report);
+}
+
+#[test]
+fn blinn_phong_reflects_at_most_its_two_colors() {
+    let diffuse = vec::Vec3::new(0.6, 0.4, 0.2);
+    let specular = vec::Vec3::new(0.3, 0.3, 0.3);
+    // Without a highlight it's a Lambertian surface.
+    assert_reflects(
+        Arc::new(blinn_phong::BlinnPhong::new(&diffuse).with_specular(&vec::Vec3::default(), 32.0)),
+        diffuse,
+        1.0,
+    );
+    // The highlight dims toward grazing views, so it adds only part of its color.
+    for exponent in [1.0, 32.0, 1000.0] {
+        let report = measure(Arc::new(
+            blinn_phong::BlinnPhong::new(&diffuse).with_specular(&specular, exponent),
+        ));
+        let allowed = TOLERANCE + 4.0 * report.standard_error;
+        for channel in 0..3 {
+            let measured = report.measured[channel];
+            assert!(measured > diffuse[channel] + allowed, "{:?}", report);
+            assert!(
+                measured < diffuse[channel] + specular[channel] + allowed,
+                "{:?}",
+                report
+            );
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Golden-image regression tests for the built-in scenes.
+//!
+//! Run with `RUSTRAY_UPDATE_GOLDEN=1` to regenerate the references after an intentional
+//! change to rendering output.
+use rustray::testing::{self, BuiltinScene, GoldenSettings};
+
+#[test]
+fn cornell_box_matches_golden() {
+    let image = testing::render_builtin(BuiltinScene::CornellBox, GoldenSettings::default());
+    testing::assert_matches_golden("cornell_box", &image);
+}
+
+#[test]
+fn bouncing_spheres_matches_golden() {
+    let image = testing::render_builtin(BuiltinScene::BouncingSpheres, GoldenSettings::default());
+    testing::assert_matches_golden("bouncing_spheres", &image);
+}
+
+#[test]
+fn next_week_scene_matches_golden() {
+    let image = testing::render_builtin(BuiltinScene::NextWeek, GoldenSettings::default());
+    testing::assert_matches_golden("next_week_scene", &image);
+}
@@ -0,0 +1,49 @@
+//! Interrupted renders stop sampling with every integrator.
+//!
+//! A render interrupted before it starts takes no samples, so its film comes back black,
+//! while the same render left to run lights the Cornell box.
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use rustray::core::{film, render, scene_file};
+use rustray::integrators::Integrator;
+use rustray::testing::BuiltinScene;
+
+fn cornell_box(integrator: Integrator, interrupted: bool) -> render::Render {
+    let mut render = scene_file::load_render(&mut rand::rng(), &BuiltinScene::CornellBox.path())
+        .expect("cornell box loads");
+    render.set_width(16).expect("valid width");
+    render.samples = 4;
+    render.depth = 4;
+    render.integrator = integrator;
+    render.interrupt = Some(Arc::new(AtomicBool::new(interrupted)));
+    render
+}
+
+fn is_black(film: &film::Film) -> bool {
+    (0..film.height).all(|y| {
+        (0..film.width).all(|x| {
+            let pixel = film.pixel(x, y);
+            pixel.x == 0.0 && pixel.y == 0.0 && pixel.z == 0.0
+        })
+    })
+}
+
+#[test]
+fn interrupted_renders_take_no_samples() {
+    for integrator in [
+        Integrator::Megakernel,
+        Integrator::Wavefront,
+        Integrator::Sppm,
+    ] {
+        let film = rustray::raytrace_film(&cornell_box(integrator, true));
+        assert!(
+            is_black(&film),
+            "{:?} sampled after the interrupt",
+            integrator
+        );
+
+        let film = rustray::raytrace_film(&cornell_box(integrator, false));
+        assert!(!is_black(&film), "{:?} rendered nothing", integrator);
+    }
+}
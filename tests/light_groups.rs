@@ -0,0 +1,66 @@
+//! Light group films add up to the regular render, with or without groups in the scene.
+use rustray::core::{render, scene_file};
+use rustray::math::vec;
+use rustray::testing::BuiltinScene;
+
+fn cornell_box(grouped: bool) -> render::Render {
+    let mut file =
+        scene_file::read_scene_file(&BuiltinScene::CornellBox.path()).expect("cornell box reads");
+    if grouped {
+        // Every other object in a group, so both grouped and default emitters remain.
+        for object in file.objects.iter_mut().step_by(2) {
+            object.light_group = Some(String::from("key"));
+        }
+    }
+    let mut render = file
+        .into_render(&mut rand::rng())
+        .expect("cornell box builds");
+    render.set_width(16).expect("valid width");
+    render.samples = 4;
+    render.depth = 4;
+    render
+}
+
+fn assert_groups_sum_to_beauty(render: &render::Render) {
+    let beauty = rustray::raytrace_film(render);
+    let groups = rustray::raytrace_light_groups(render);
+    assert_eq!(
+        groups.last().map(|(name, _)| name.as_str()),
+        Some("default")
+    );
+
+    let mut total = 0.0;
+    for y in 0..beauty.height {
+        for x in 0..beauty.width {
+            let expected = beauty.pixel(x, y);
+            let sum = groups.iter().fold(vec::Vec3::default(), |sum, (_, film)| {
+                sum + film.pixel(x, y)
+            });
+            let difference = (sum - expected).length();
+            assert!(
+                difference <= 1e-3 * (1.0 + expected.length()),
+                "pixel ({}, {}): groups sum to {:?}, beauty is {:?}",
+                x,
+                y,
+                sum,
+                expected
+            );
+            total += expected.x + expected.y + expected.z;
+        }
+    }
+    assert!(total > 0.0, "the Cornell box renders black");
+}
+
+#[test]
+fn default_group_holds_everything_without_groups() {
+    let render = cornell_box(false);
+    assert!(render.scene.light_groups.is_empty());
+    assert_groups_sum_to_beauty(&render);
+}
+
+#[test]
+fn light_groups_sum_to_the_render() {
+    let render = cornell_box(true);
+    assert_eq!(render.scene.light_groups, ["key"]);
+    assert_groups_sum_to_beauty(&render);
+}
@@ -0,0 +1,148 @@
+//! Materials and textures registered from outside the crate load from scene files, save
+//! back under their tags, and stop loading once the registry is cleared.
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use rustray::core::{path, plugins, scene_file};
+use rustray::math::pdf::cosine;
+use rustray::math::{rng, vec};
+use rustray::testing::BuiltinScene;
+use rustray::traits::scatterable::{ScatterRecord, Scatterable};
+use rustray::traits::{hittable, texturable};
+
+/// A diffuse material defined the way a downstream crate would.
+#[derive(Serialize, Deserialize)]
+struct Suede {
+    color: vec::Vec3,
+    nap: f32,
+}
+
+impl Scatterable for Suede {
+    fn scatter(
+        &self,
+        _rng: &mut rng::SampleRng,
+        hit_record: &hittable::HitRecord,
+        _state: &mut path::PathState,
+    ) -> Option<ScatterRecord> {
+        Some(ScatterRecord {
+            attenuation: self.color * (1.0 - self.nap),
+            scatter_pdf: Some(Box::new(cosine::CosinePDF::new(&hit_record.hit.normal))),
+            scattered_ray: None,
+            use_light_pdf: true,
+        })
+    }
+
+    fn emit(&self, _hit_record: &hittable::HitRecord) -> vec::Vec3 {
+        vec::Vec3::default()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Black and white bands across `u`.
+#[derive(Serialize, Deserialize)]
+struct Stripes {
+    count: u32,
+}
+
+impl texturable::Texturable for Stripes {
+    fn sample(&self, hit: &hittable::Hit) -> vec::Vec3 {
+        let band = (hit.u * self.count as f32) as u32 % 2;
+        vec::Vec3::new(1.0, 1.0, 1.0) * band as f32
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+const PLUGIN_MATERIALS: &str = r#"
+[[materials]]
+id = 4
+sampleable = "Suede"
+data = { color = [0.4, 0.05, 0.1], nap = 0.25 }
+
+[[materials]]
+id = 5
+sampleable = "Lambertian"
+data = { texture = { texturable = "Stripes", data = { count = 8 } } }
+"#;
+
+/// Writes the Cornell box with its first two objects switched to the plugin materials.
+fn write_scene(path: &Path) {
+    let scene = std::fs::read_to_string(BuiltinScene::CornellBox.path()).expect("scene reads");
+    let mut scene: toml::Table = toml::from_str(&scene).expect("scene parses");
+    let extra: toml::Table = toml::from_str(PLUGIN_MATERIALS).expect("materials parse");
+    let materials = scene["materials"].as_array_mut().expect("materials");
+    materials.extend(extra["materials"].as_array().expect("materials").clone());
+    let objects = scene["objects"].as_array_mut().expect("objects");
+    for (object, material) in objects.iter_mut().zip([4, 5]) {
+        object["material"] = toml::Value::Integer(material);
+    }
+    std::fs::write(path, toml::to_string(&scene).expect("scene serializes")).expect("saves");
+}
+
+fn temp_scene(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "rustray-plugins-{}-{}.toml",
+        name,
+        std::process::id()
+    ))
+}
+
+/// The first saved material entry `matches` accepts.
+fn material(saved: &toml::Table, matches: impl Fn(&toml::Table) -> bool) -> &toml::Table {
+    saved["materials"]
+        .as_array()
+        .expect("materials")
+        .iter()
+        .filter_map(toml::Value::as_table)
+        .find(|entry| matches(entry))
+        .expect("the plugin material is saved")
+}
+
+#[test]
+fn plugins_load_save_and_clear() {
+    plugins::register_material::<Suede>("Suede");
+    plugins::register_texture::<Stripes>("Stripes");
+
+    let original = temp_scene("original");
+    write_scene(&original);
+    let render = scene_file::load_render(&mut rand::rng(), &original);
+    std::fs::remove_file(&original).ok();
+    let render = render.unwrap_or_else(|err| panic!("plugin scene fails to load: {}", err));
+
+    let saved = scene_file::SceneFile::from_render(&render).expect("scene saves");
+    let saved = toml::to_string(&saved).expect("scene serializes");
+    let table: toml::Table = toml::from_str(&saved).expect("saved scene parses");
+    let suede = material(&table, |entry| {
+        entry["sampleable"].as_str() == Some("Suede")
+    });
+    assert_eq!(suede["data"]["nap"].as_float(), Some(0.25));
+    let striped = material(&table, |entry| {
+        entry
+            .get("data")
+            .and_then(|data| data.get("texture"))
+            .is_some_and(|texture| texture["texturable"].as_str() == Some("Stripes"))
+    });
+    assert_eq!(
+        striped["data"]["texture"]["data"]["count"].as_integer(),
+        Some(8)
+    );
+
+    let resaved = temp_scene("resaved");
+    std::fs::write(&resaved, &saved).expect("saved scene writes");
+    let reloaded = scene_file::load_render(&mut rand::rng(), &resaved);
+    assert!(reloaded.is_ok(), "saved plugin scene fails to reload");
+
+    plugins::clear();
+    let unregistered = scene_file::load_render(&mut rand::rng(), &resaved);
+    std::fs::remove_file(&resaved).ok();
+    assert!(
+        unregistered.is_err(),
+        "plugin tags still load after the registry is cleared"
+    );
+}
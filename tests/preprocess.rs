@@ -0,0 +1,46 @@
+//! Preprocessing hooks run on every scene as it loads, in the order they were
+//! registered, and an error from any of them aborts the load.
+use std::sync::{Arc, Mutex};
+
+use rustray::core::preprocess;
+use rustray::core::scene_file::{self, SceneFileError};
+use rustray::testing::BuiltinScene;
+
+fn load() -> Result<rustray::core::render::Render, SceneFileError> {
+    scene_file::load_render(&mut rand::rng(), &BuiltinScene::CornellBox.path())
+}
+
+#[test]
+fn hooks_run_in_order_and_errors_abort() {
+    let objects = load().expect("scene loads").scene.renderables.objects.len();
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let file_log = log.clone();
+    preprocess::on_scene_file(move |file| {
+        file_log.lock().unwrap().push("scene file");
+        file.objects.pop();
+        Ok(())
+    });
+    let scene_log = log.clone();
+    preprocess::on_scene(move |scene| {
+        scene_log.lock().unwrap().push("scene");
+        // The scene is built from the file the first hook edited.
+        assert_eq!(scene.renderables.objects.len(), objects - 1);
+        Ok(())
+    });
+
+    let render = load().expect("hooked scene loads");
+    assert_eq!(*log.lock().unwrap(), ["scene file", "scene"]);
+    assert_eq!(render.scene.renderables.objects.len(), objects - 1);
+
+    log.lock().unwrap().clear();
+    preprocess::on_scene_file(|_| Err(SceneFileError::Preprocess("rejected".to_string())));
+    let err = load().err().expect("a failing hook aborts the load");
+    assert!(matches!(err, SceneFileError::Preprocess(ref reason) if reason == "rejected"));
+    // The first hook still ran; the scene was never built.
+    assert_eq!(*log.lock().unwrap(), ["scene file"]);
+
+    preprocess::clear();
+    let render = load().expect("scene loads without hooks");
+    assert_eq!(render.scene.renderables.objects.len(), objects);
+}
@@ -0,0 +1,139 @@
+//! Ray intersection tests for the analytic primitives.
+//!
+//! Each primitive is swept with a grid of rays and then fuzzed with random rays at
+//! randomly placed and sized instances, comparing every hit with the analytic reference
+//! in `rustray::geometry::testing`.
+use rand::Rng;
+
+use rustray::geometry::primitives::{cube, quad, sphere};
+use rustray::geometry::testing::{self, BoxReference, QuadReference, SphereReference, Tolerance};
+use rustray::math::{rng, vec};
+
+/// Random shapes fuzzed per primitive.
+const SHAPES: u64 = 32;
+
+/// Random rays fired at each shape.
+const RAYS_PER_SHAPE: usize = 512;
+
+/// Grid resolution of the sweeps, per direction.
+const GRID: u32 = 24;
+
+fn random_point(rng: &mut rng::SampleRng, extent: f32) -> vec::Vec3 {
+    vec::Vec3::new(
+        rng.random_range(-extent..extent),
+        rng.random_range(-extent..extent),
+        rng.random_range(-extent..extent),
+    )
+}
+
+fn random_size(rng: &mut rng::SampleRng) -> f32 {
+    rng.random_range(0.2..4.0)
+}
+
+#[test]
+fn sphere_matches_reference_on_grid() {
+    let center = vec::Vec3::new(0.5, -1.0, 2.0);
+    let sphere = sphere::Sphere::new(&center, 1.5);
+    let reference = SphereReference {
+        center,
+        radius: 1.5,
+    };
+    let report = testing::assert_agrees(
+        &sphere,
+        &reference,
+        &testing::ray_grid(&sphere, GRID),
+        &Tolerance::default(),
+    );
+    assert!(report.hits > 0);
+}
+
+#[test]
+fn quad_matches_reference_on_grid() {
+    let (q, u, v) = (
+        vec::Vec3::new(-1.0, 0.0, -1.0),
+        vec::Vec3::new(2.0, 0.0, 0.0),
+        vec::Vec3::new(0.5, 0.5, 2.0),
+    );
+    let quad = quad::Quad::new(q, u, v);
+    let report = testing::assert_agrees(
+        &quad,
+        &QuadReference { q, u, v },
+        &testing::ray_grid(&quad, GRID),
+        &Tolerance::default(),
+    );
+    assert!(report.hits > 0);
+}
+
+#[test]
+fn cube_matches_reference_on_grid() {
+    let (min, max) = (
+        vec::Vec3::new(-1.0, -0.5, 0.0),
+        vec::Vec3::new(1.0, 2.0, 0.75),
+    );
+    let cube = cube::Cube::new(min, max);
+    let report = testing::assert_agrees(
+        &cube,
+        &BoxReference { min, max },
+        &testing::ray_grid(&cube, GRID),
+        &Tolerance::default(),
+    );
+    assert!(report.hits > 0);
+}
+
+#[test]
+fn random_spheres_match_reference() {
+    let mut rng = rng::hashed_rng(0x5748_4552);
+    for _ in 0..SHAPES {
+        let center = random_point(&mut rng, 10.0);
+        // Negative radii turn the sphere inside out.
+        let radius = random_size(&mut rng) * if rng.random_bool(0.2) { -1.0 } else { 1.0 };
+        let sphere = sphere::Sphere::new(&center, radius);
+        let rays = testing::random_rays(&mut rng, &sphere, RAYS_PER_SHAPE);
+        testing::assert_agrees(
+            &sphere,
+            &SphereReference { center, radius },
+            &rays,
+            &Tolerance::default(),
+        );
+    }
+}
+
+#[test]
+fn random_quads_match_reference() {
+    let mut rng = rng::hashed_rng(0x5155_4144);
+    for _ in 0..SHAPES {
+        let q = random_point(&mut rng, 10.0);
+        let u = vec::unit_vector(&random_point(&mut rng, 1.0)) * random_size(&mut rng);
+        let v = vec::unit_vector(&random_point(&mut rng, 1.0)) * random_size(&mut rng);
+        let quad = quad::Quad::new(q, u, v);
+        let rays = testing::random_rays(&mut rng, &quad, RAYS_PER_SHAPE);
+        testing::assert_agrees(
+            &quad,
+            &QuadReference { q, u, v },
+            &rays,
+            &Tolerance::default(),
+        );
+    }
+}
+
+#[test]
+fn random_cubes_match_reference() {
+    let mut rng = rng::hashed_rng(0x4355_4245);
+    for _ in 0..SHAPES {
+        let min = random_point(&mut rng, 10.0);
+        let max = min
+            + vec::Vec3::new(
+                random_size(&mut rng),
+                random_size(&mut rng),
+                random_size(&mut rng),
+            );
+        let cube = cube::Cube::new(min, max);
+        let rays = testing::random_rays(&mut rng, &cube, RAYS_PER_SHAPE);
+        testing::assert_agrees(
+            &cube,
+            &BoxReference { min, max },
+            &rays,
+            &Tolerance::default(),
+        );
+    }
+}
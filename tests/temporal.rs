@@ -0,0 +1,96 @@
+//! Temporal reuse averages a still shot's noise across frames and starts over where a
+//! surface comes into view.
+use rustray::core::{aov, film, render, scene_file, shutter, temporal};
+use rustray::testing::BuiltinScene;
+
+/// Frame `frame` of the Cornell box, sampled with its own noise; without its tall box if
+/// `moved`.
+fn frame(frame: u32, moved: bool) -> render::Render {
+    let mut file =
+        scene_file::read_scene_file(&BuiltinScene::CornellBox.path()).expect("cornell box reads");
+    if moved {
+        file.objects.pop();
+    }
+    file.shutter = shutter::Shutter::frame(frame as f64, 24.0, 180.0);
+    file.frame_noise = shutter::FrameNoise::PerFrame;
+    let mut render = file
+        .into_render(&mut rand::rng())
+        .expect("cornell box builds");
+    render.set_width(24).expect("valid width");
+    render.samples = 4;
+    render.depth = 4;
+    render
+}
+
+/// Mean squared difference from `reference`, per channel.
+fn error(film: &film::Film, reference: &film::Film) -> f32 {
+    let mut total = 0.0;
+    for y in 0..film.height {
+        for x in 0..film.width {
+            let difference = film.pixel(x, y) - reference.pixel(x, y);
+            total += difference.squared_length();
+        }
+    }
+    total / (3 * film.width * film.height) as f32
+}
+
+#[test]
+fn still_frames_converge() {
+    let mut reference = frame(1000, false);
+    reference.samples = 256;
+    let reference = rustray::raytrace_film(&reference);
+
+    let mut reuse = temporal::TemporalReuse::new();
+    let first = frame(0, false);
+    let first_error = error(
+        &reuse.accumulate(&first, rustray::raytrace_film(&first)),
+        &reference,
+    );
+    let mut errors = vec![first_error];
+    for index in 1..4 {
+        let render = frame(index, false);
+        let film = reuse.accumulate(&render, rustray::raytrace_film(&render));
+        errors.push(error(&film, &reference));
+    }
+    assert!(
+        errors[1] < errors[0],
+        "the second frame isn't less noisy: {:?}",
+        errors
+    );
+    // Four frames average four times the samples; leave room for noise in the estimate.
+    assert!(errors[3] < errors[0] * 0.5, "{:?}", errors);
+}
+
+#[test]
+fn disoccluded_pixels_start_over() {
+    let before = frame(0, false);
+    let after = frame(1, true);
+    let mut reuse = temporal::TemporalReuse::new();
+    reuse.accumulate(&before, rustray::raytrace_film(&before));
+    // Renders are deterministic, so this is the film accumulated below.
+    let current = rustray::raytrace_film(&after);
+    let blended = reuse.accumulate(&after, rustray::raytrace_film(&after));
+
+    let (depth_before, depth_after) = (aov::render_depth(&before), aov::render_depth(&after));
+    let (mut disoccluded, mut reused) = (0, 0);
+    for y in 0..current.height {
+        for x in 0..current.width {
+            let (was, is) = (depth_before.pixel(x, y)[0], depth_after.pixel(x, y)[0]);
+            if (is - was).abs() > 0.1 * was {
+                // The box was here; the wall behind it is new to this frame.
+                let difference = blended.pixel(x, y) - current.pixel(x, y);
+                assert!(
+                    difference.length() < 1e-6,
+                    "pixel ({}, {}) kept history",
+                    x,
+                    y
+                );
+                disoccluded += 1;
+            } else if (blended.pixel(x, y) - current.pixel(x, y)).length() > 1e-6 {
+                reused += 1;
+            }
+        }
+    }
+    assert!(disoccluded > 0, "removing the box uncovers nothing");
+    assert!(reused > 0, "no pixel kept its history");
+}